@@ -0,0 +1,398 @@
+//! Security audit of bundled dependencies
+//!
+//! Checks the `node_modules` packages that are actually part of the module
+//! graph against the [OSV](https://osv.dev) vulnerability database. Results
+//! are cached per package version so repeated audits, and audits without
+//! network access, don't need to query the API again.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+
+/// Public OSV API
+pub const DEFAULT_API: &str = "https://api.osv.dev";
+
+/// Maximum queries per OSV batch request
+const BATCH_SIZE: usize = 1000;
+
+/// Request timeout for OSV requests
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Advisory severity, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "moderate" | "medium" => Ok(Self::Moderate),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            _ => anyhow::bail!("Unknown severity '{}' (expected low, moderate, high, or critical)", s),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Low => "low",
+            Self::Moderate => "moderate",
+            Self::High => "high",
+            Self::Critical => "critical",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A known vulnerability affecting a package version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    /// OSV id (e.g. `GHSA-xxxx-xxxx-xxxx`)
+    pub id: String,
+    
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    
+    #[serde(default)]
+    pub summary: String,
+    
+    /// `None` when the database doesn't rate the advisory
+    #[serde(default)]
+    pub severity: Option<Severity>,
+    
+    /// First version that fixes the vulnerability, if any
+    #[serde(default)]
+    pub fixed: Option<String>,
+    
+    pub url: String,
+}
+
+impl Advisory {
+    /// Build from an OSV vulnerability record for the given npm package
+    pub fn from_osv(vuln: &Value, package: &str) -> Self {
+        let id = vuln["id"].as_str().unwrap_or_default().to_string();
+        let strings = |value: &Value| -> Vec<String> {
+            value.as_array()
+                .map(|items| items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+                .unwrap_or_default()
+        };
+        
+        let severity = vuln["database_specific"]["severity"]
+            .as_str()
+            .and_then(|s| s.parse().ok());
+        
+        let fixed = vuln["affected"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|affected| affected["package"]["name"].as_str() == Some(package))
+            .flat_map(|affected| affected["ranges"].as_array().into_iter().flatten())
+            .flat_map(|range| range["events"].as_array().into_iter().flatten())
+            .find_map(|event| event["fixed"].as_str().map(str::to_string));
+        
+        Self {
+            url: format!("https://osv.dev/vulnerability/{}", id),
+            id,
+            aliases: strings(&vuln["aliases"]),
+            summary: vuln["summary"].as_str().unwrap_or_default().to_string(),
+            severity,
+            fixed,
+        }
+    }
+    
+    /// Whether the advisory meets a severity threshold
+    ///
+    /// Unrated advisories always do.
+    pub fn is_at_least(&self, threshold: Severity) -> bool {
+        self.severity.is_none_or(|severity| severity >= threshold)
+    }
+}
+
+/// A package name and version to audit
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PackageVersion {
+    pub name: String,
+    pub version: String,
+}
+
+/// Audit results for one package
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageAudit {
+    pub name: String,
+    pub version: String,
+    pub advisories: Vec<Advisory>,
+}
+
+/// Result of auditing a set of packages
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    /// Number of packages checked
+    pub checked: usize,
+    
+    /// Packages with at least one advisory
+    pub vulnerable: Vec<PackageAudit>,
+    
+    /// Packages that could not be checked (offline without a cached result)
+    pub unchecked: Vec<PackageVersion>,
+}
+
+impl AuditReport {
+    /// Advisories at or above a severity threshold
+    pub fn count_at_least(&self, threshold: Severity) -> usize {
+        self.vulnerable
+            .iter()
+            .flat_map(|package| &package.advisories)
+            .filter(|advisory| advisory.is_at_least(threshold))
+            .count()
+    }
+}
+
+/// Cached advisories for one package version
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Seconds since the Unix epoch
+    fetched_at: u64,
+    advisories: Vec<Advisory>,
+}
+
+/// On-disk cache of OSV results, one file per package version
+pub struct AdvisoryCache {
+    dir: PathBuf,
+}
+
+impl AdvisoryCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+    
+    fn path(&self, package: &PackageVersion) -> PathBuf {
+        let name = package.name.replace('/', "+");
+        self.dir.join(format!("{}@{}.json", name, package.version))
+    }
+    
+    /// Cached advisories no older than `max_age` (any age when `None`)
+    pub fn get(&self, package: &PackageVersion, max_age: Option<Duration>) -> Option<Vec<Advisory>> {
+        let content = fs::read_to_string(self.path(package)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+        
+        if let Some(max_age) = max_age {
+            if now().saturating_sub(entry.fetched_at) > max_age.as_secs() {
+                return None;
+            }
+        }
+        Some(entry.advisories)
+    }
+    
+    pub fn put(&self, package: &PackageVersion, advisories: &[Advisory]) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create audit cache: {}", self.dir.display()))?;
+        let entry = CacheEntry { fetched_at: now(), advisories: advisories.to_vec() };
+        fs::write(self.path(package), serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Client for the OSV API
+pub struct OsvClient {
+    api: String,
+    agent: ureq::Agent,
+}
+
+impl OsvClient {
+    pub fn new(api: &str) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+        }
+    }
+    
+    /// Query advisories for npm packages
+    ///
+    /// Returns advisories in the same order as `packages`.
+    pub fn query(&self, packages: &[PackageVersion]) -> Result<Vec<Vec<Advisory>>> {
+        let mut ids: Vec<Vec<String>> = Vec::with_capacity(packages.len());
+        
+        for batch in packages.chunks(BATCH_SIZE) {
+            let queries: Vec<Value> = batch
+                .iter()
+                .map(|p| json!({ "package": { "name": p.name, "ecosystem": "npm" }, "version": p.version }))
+                .collect();
+            
+            let body = self.agent
+                .post(&format!("{}/v1/querybatch", self.api))
+                .set("Content-Type", "application/json")
+                .send_string(&json!({ "queries": queries }).to_string())
+                .context("OSV query failed")?
+                .into_string()?;
+            let response: Value = serde_json::from_str(&body).context("Invalid OSV response")?;
+            
+            let results = response["results"].as_array().context("Invalid OSV response: missing results")?;
+            anyhow::ensure!(results.len() == batch.len(), "Invalid OSV response: expected {} results", batch.len());
+            
+            for result in results {
+                ids.push(
+                    result["vulns"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|vuln| vuln["id"].as_str().map(str::to_string))
+                        .collect(),
+                );
+            }
+        }
+        
+        // The batch endpoint only returns ids; fetch each record once
+        let unique: BTreeSet<&String> = ids.iter().flatten().collect();
+        let mut records = BTreeMap::new();
+        for id in unique {
+            debug!("Fetching advisory {}", id);
+            let body = self.agent
+                .get(&format!("{}/v1/vulns/{}", self.api, id))
+                .call()
+                .with_context(|| format!("Failed to fetch advisory {}", id))?
+                .into_string()?;
+            let record: Value = serde_json::from_str(&body)
+                .with_context(|| format!("Invalid advisory {}", id))?;
+            records.insert(id.clone(), record);
+        }
+        
+        Ok(packages
+            .iter()
+            .zip(ids)
+            .map(|(package, ids)| {
+                ids.iter()
+                    .filter_map(|id| records.get(id))
+                    .map(|record| Advisory::from_osv(record, &package.name))
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+/// Options for an audit run
+pub struct AuditOptions<'a> {
+    /// OSV API base URL
+    pub api: &'a str,
+    
+    /// Directory for cached results
+    pub cache_dir: &'a Path,
+    
+    /// Reuse cached results younger than this
+    pub max_age: Duration,
+    
+    /// Only use cached results, never the network
+    pub offline: bool,
+}
+
+/// Audit packages, using cached results where possible
+pub fn audit(packages: &[PackageVersion], options: &AuditOptions) -> Result<AuditReport> {
+    let cache = AdvisoryCache::new(options.cache_dir);
+    let mut results: BTreeMap<PackageVersion, Vec<Advisory>> = BTreeMap::new();
+    let mut missing = Vec::new();
+    
+    for package in packages {
+        let max_age = if options.offline { None } else { Some(options.max_age) };
+        match cache.get(package, max_age) {
+            Some(advisories) => {
+                results.insert(package.clone(), advisories);
+            }
+            None => missing.push(package.clone()),
+        }
+    }
+    
+    let mut report = AuditReport::default();
+    if options.offline {
+        report.unchecked = missing;
+    } else if !missing.is_empty() {
+        let fetched = OsvClient::new(options.api).query(&missing)?;
+        for (package, advisories) in missing.into_iter().zip(fetched) {
+            cache.put(&package, &advisories)?;
+            results.insert(package, advisories);
+        }
+    }
+    
+    report.checked = results.len();
+    report.vulnerable = results
+        .into_iter()
+        .filter(|(_, advisories)| !advisories.is_empty())
+        .map(|(package, advisories)| PackageAudit {
+            name: package.name,
+            version: package.version,
+            advisories,
+        })
+        .collect();
+    
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_advisory_from_osv() {
+        let vuln = json!({
+            "id": "GHSA-29mw-wpgm-hmr9",
+            "aliases": ["CVE-2020-28500"],
+            "summary": "Regular Expression Denial of Service (ReDoS) in lodash",
+            "database_specific": { "severity": "MODERATE" },
+            "affected": [{
+                "package": { "ecosystem": "npm", "name": "lodash" },
+                "ranges": [{ "type": "SEMVER", "events": [{ "introduced": "4.0.0" }, { "fixed": "4.17.21" }] }]
+            }]
+        });
+        
+        let advisory = Advisory::from_osv(&vuln, "lodash");
+        assert_eq!(advisory.aliases, ["CVE-2020-28500"]);
+        assert_eq!(advisory.severity, Some(Severity::Moderate));
+        assert_eq!(advisory.fixed.as_deref(), Some("4.17.21"));
+        assert!(advisory.is_at_least(Severity::Low));
+        assert!(!advisory.is_at_least(Severity::High));
+    }
+    
+    #[test]
+    fn test_offline_audit_uses_cache() {
+        let dir = std::env::temp_dir().join(format!("component-audit-test-{}", std::process::id()));
+        let cached = PackageVersion { name: "@scope/pkg".to_string(), version: "1.0.0".to_string() };
+        let uncached = PackageVersion { name: "other".to_string(), version: "2.0.0".to_string() };
+        
+        let advisory = Advisory::from_osv(&json!({ "id": "GHSA-test" }), "@scope/pkg");
+        AdvisoryCache::new(&dir).put(&cached, &[advisory]).unwrap();
+        
+        let options = AuditOptions {
+            api: "http://127.0.0.1:9",
+            cache_dir: &dir,
+            max_age: Duration::ZERO,
+            offline: true,
+        };
+        let report = audit(&[cached, uncached.clone()], &options).unwrap();
+        fs::remove_dir_all(&dir).ok();
+        
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.vulnerable[0].advisories[0].id, "GHSA-test");
+        assert_eq!(report.unchecked, [uncached]);
+        assert_eq!(report.count_at_least(Severity::Critical), 1);
+    }
+}
@@ -0,0 +1,313 @@
+//! Transform result caching
+//!
+//! Transformed modules and finished chunks are kept in memory and optionally
+//! read from / written to persistent backends: a local directory or a remote
+//! cache shared between CI machines and teammates.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use tracing::warn;
+
+use super::ModuleType;
+use crate::utils::{hash_content, hash_parts};
+
+/// Persistent storage for cache entries
+///
+/// Keys are `<namespace>/<hash>` and safe to use as relative paths.
+pub trait CacheBackend: Send + Sync {
+    /// Description used in log messages
+    fn name(&self) -> String;
+    
+    /// Fetch an entry; `Ok(None)` when absent
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    
+    /// Store an entry
+    fn put(&self, key: &str, value: &str) -> Result<()>;
+}
+
+/// A backend that is skipped after its first failure
+struct Backend {
+    inner: Arc<dyn CacheBackend>,
+    failed: AtomicBool,
+}
+
+impl Backend {
+    fn new(inner: Arc<dyn CacheBackend>) -> Self {
+        Self { inner, failed: AtomicBool::new(false) }
+    }
+    
+    /// Run an operation unless the backend already failed; errors are
+    /// logged and never fail the build
+    fn call<T>(&self, op: impl FnOnce(&dyn CacheBackend) -> Result<T>) -> Option<T> {
+        if self.failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        match op(self.inner.as_ref()) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    warn!("Cache backend {} failed, skipping it for this build: {:#}", self.inner.name(), e);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Cache of transformed module code keyed by source and transform inputs
+///
+/// Shared between bundler instances (e.g. across warm builds) via `Arc`.
+#[derive(Default)]
+pub struct TransformCache {
+    /// Cached transform output by key
+    entries: DashMap<String, String>,
+    
+    /// Number of lookups served from the cache
+    hits: AtomicU64,
+    
+    /// Number of lookups that missed
+    misses: AtomicU64,
+    
+    /// Chunk lookups served from the cache
+    chunk_hits: AtomicU64,
+    
+    /// Chunk lookups that missed
+    chunk_misses: AtomicU64,
+    
+    /// Backends consulted, in order, on a memory miss
+    read_from: Vec<Backend>,
+    
+    /// Backends new entries are written to
+    write_to: Vec<Backend>,
+}
+
+impl fmt::Debug for TransformCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |backends: &[Backend]| backends.iter().map(|b| b.inner.name()).collect::<Vec<_>>();
+        f.debug_struct("TransformCache")
+            .field("entries", &self.entries.len())
+            .field("stats", &self.stats())
+            .field("chunk_stats", &self.chunk_stats())
+            .field("read_from", &names(&self.read_from))
+            .field("write_to", &names(&self.write_to))
+            .finish()
+    }
+}
+
+/// Cache hit/miss counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Fraction of lookups served from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl TransformCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Also look up entries in `backend` on a memory miss
+    pub fn read_from(mut self, backend: Arc<dyn CacheBackend>) -> Self {
+        self.read_from.push(Backend::new(backend));
+        self
+    }
+    
+    /// Also write new entries to `backend`
+    pub fn write_to(mut self, backend: Arc<dyn CacheBackend>) -> Self {
+        self.write_to.push(Backend::new(backend));
+        self
+    }
+    
+    /// Namespace for cache keys
+    ///
+    /// Entries produced by a different tool version or transform-affecting
+    /// configuration never collide, even in a shared remote cache.
+    pub fn namespace(fingerprint: &str) -> String {
+        let config_hash = hash_content(fingerprint.as_bytes());
+        format!("v{}-{}", env!("CARGO_PKG_VERSION"), config_hash)
+    }
+    
+    /// Compute the cache key for a module transform
+    ///
+    /// `fingerprint` covers configuration that affects transform output.
+    pub fn key(source: &str, path: &Path, module_type: &ModuleType, fingerprint: &str) -> String {
+        let hash = hash_parts(&[
+            path.to_string_lossy().as_bytes(),
+            format!("{:?}", module_type).as_bytes(),
+            source.as_bytes(),
+        ]);
+        format!("{}/{}", Self::namespace(fingerprint), hash)
+    }
+    
+    /// Compute the cache key for a chunk's finished code
+    ///
+    /// `parts` are every input of the chunk: the code around its modules and
+    /// each module's path, imports, and transformed code.
+    pub fn chunk_key(parts: &[&[u8]], fingerprint: &str) -> String {
+        format!("{}/chunk-{}", Self::namespace(fingerprint), hash_parts(parts))
+    }
+    
+    /// Look up a cached transform result
+    pub fn get(&self, key: &str) -> Option<String> {
+        let found = self.lookup(key);
+        let counter = if found.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+    
+    /// Look up a chunk's cached code; counted in `chunk_stats`
+    pub fn get_chunk(&self, key: &str) -> Option<String> {
+        let found = self.lookup(key);
+        let counter = if found.is_some() { &self.chunk_hits } else { &self.chunk_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+    
+    /// Find an entry in memory, then in the read backends
+    fn lookup(&self, key: &str) -> Option<String> {
+        self.entries.get(key).map(|entry| entry.clone()).or_else(|| {
+            let value = self.read_from.iter().find_map(|backend| backend.call(|b| b.get(key)).flatten())?;
+            self.entries.insert(key.to_string(), value.clone());
+            Some(value)
+        })
+    }
+    
+    /// Store a transform result or chunk
+    pub fn insert(&self, key: String, code: String) {
+        for backend in &self.write_to {
+            backend.call(|b| b.put(&key, &code));
+        }
+        self.entries.insert(key, code);
+    }
+    
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// Current hit/miss counters
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+    
+    /// Hit/miss counters of chunk lookups
+    pub fn chunk_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.chunk_hits.load(Ordering::Relaxed),
+            misses: self.chunk_misses.load(Ordering::Relaxed),
+        }
+    }
+    
+    /// Reset the hit/miss counters
+    pub fn reset_stats(&self) {
+        for counter in [&self.hits, &self.misses, &self.chunk_hits, &self.chunk_misses] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Cache entries stored as files under a directory
+pub struct DiskBackend {
+    dir: PathBuf,
+}
+
+impl DiskBackend {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl CacheBackend for DiskBackend {
+    fn name(&self) -> String {
+        self.dir.display().to_string()
+    }
+    
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        match fs::read_to_string(self.dir.join(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read cache entry {}", key)),
+        }
+    }
+    
+    fn put(&self, key: &str, value: &str) -> Result<()> {
+        let path = self.dir.join(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create cache directory {}", parent.display()))?;
+        }
+        
+        // Concurrent builds never see a partial entry
+        crate::utils::write_atomic(&path, value.as_bytes()).with_context(|| format!("Failed to write cache entry {}", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_cache_hits_and_misses() {
+        let cache = TransformCache::new();
+        let key = TransformCache::key("let a = 1;", Path::new("/a.ts"), &ModuleType::TypeScript, "");
+        
+        assert!(cache.get(&key).is_none());
+        cache.insert(key.clone(), "let a = 1;".to_string());
+        assert_eq!(cache.get(&key).as_deref(), Some("let a = 1;"));
+        
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+        assert_eq!(cache.stats().hit_rate(), 0.5);
+    }
+    
+    #[test]
+    fn test_cache_key_depends_on_inputs() {
+        let path = Path::new("/a.js");
+        let base = TransformCache::key("x", path, &ModuleType::JavaScript, "");
+        
+        assert_ne!(base, TransformCache::key("y", path, &ModuleType::JavaScript, ""));
+        assert_ne!(base, TransformCache::key("x", path, &ModuleType::Jsx, ""));
+        assert_ne!(base, TransformCache::key("x", path, &ModuleType::JavaScript, "jsx"));
+    }
+    
+    #[test]
+    fn test_cache_reads_and_writes_backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = TransformCache::key("x", Path::new("/a.js"), &ModuleType::JavaScript, "");
+        
+        let writer = TransformCache::new().write_to(Arc::new(DiskBackend::new(dir.path())));
+        writer.insert(key.clone(), "cached".to_string());
+        
+        let reader = TransformCache::new().read_from(Arc::new(DiskBackend::new(dir.path())));
+        assert_eq!(reader.get(&key).as_deref(), Some("cached"));
+        assert_eq!(reader.len(), 1);
+        assert_eq!(reader.stats(), CacheStats { hits: 1, misses: 0 });
+    }
+}
@@ -22,26 +22,44 @@ pub struct Chunk {
     /// Type of chunk
     pub chunk_type: ChunkType,
     
-    /// Module IDs included in this chunk
+    /// Module IDs included in this chunk, in execution order
     pub module_ids: Vec<ModuleId>,
+    
+    /// Module the chunk exists for: the entry point it runs, or the
+    /// dynamically imported module of an async chunk; shared chunks have none
+    pub entry: Option<ModuleId>,
+    
+    /// Modules run as soon as the chunk loads, in order: an entry chunk's
+    /// entry module, or each script and stylesheet of a page
+    pub entry_module_ids: Vec<ModuleId>,
+    
+    /// Names of the async chunks an entry chunk can load, directly or
+    /// through other async chunks
+    pub async_chunks: Vec<String>,
 }
 
 impl Chunk {
     /// Create a new entry chunk
-    pub fn entry(name: String, module_ids: Vec<ModuleId>) -> Self {
+    pub fn entry(name: String, entry: ModuleId, module_ids: Vec<ModuleId>) -> Self {
         Self {
             name,
             chunk_type: ChunkType::Entry,
             module_ids,
+            entry: Some(entry),
+            entry_module_ids: vec![entry],
+            async_chunks: Vec::new(),
         }
     }
     
     /// Create a new async chunk
-    pub fn async_chunk(name: String, module_ids: Vec<ModuleId>) -> Self {
+    pub fn async_chunk(name: String, root: ModuleId, module_ids: Vec<ModuleId>) -> Self {
         Self {
             name,
             chunk_type: ChunkType::Async,
             module_ids,
+            entry: Some(root),
+            entry_module_ids: Vec::new(),
+            async_chunks: Vec::new(),
         }
     }
     
@@ -51,6 +69,9 @@ impl Chunk {
             name,
             chunk_type: ChunkType::Shared,
             module_ids,
+            entry: None,
+            entry_module_ids: Vec::new(),
+            async_chunks: Vec::new(),
         }
     }
     
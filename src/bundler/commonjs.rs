@@ -0,0 +1,570 @@
+//! ES modules to CommonJS, for bundles
+//!
+//! Bundles run each module in a function wrapper, which can't hold `import`
+//! and `export` statements. Those statements are rewritten by span: imports
+//! become `require()` calls, and a module's own exports become getters on
+//! `exports`, defined before its code runs so they stay live. Imported
+//! bindings stay live too: each reference reads the required module again,
+//! and writes to exported bindings are reported to `__component_changed__`
+//! for the library runtime's ES module builds. Everything else is copied as
+//! written.
+//!
+//! The rewritten code calls `__component_define__`, `__component_default__`,
+//! `__component_export_star__` and `__component_changed__`, which the
+//! browser and library runtimes provide.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::Result;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser;
+use oxc_span::{GetSpan, SourceType, Span};
+use oxc_syntax::scope::ScopeFlags;
+
+/// A module rewritten to CommonJS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonJsModule {
+    pub code: String,
+    
+    /// Names the module exports, including `default`, apart from those of
+    /// its `export * from` statements
+    pub names: Vec<String>,
+    
+    /// Specifiers of `export * from` statements
+    pub star: Vec<String>,
+    
+    /// Whether the module had any imports or exports, rather than being
+    /// CommonJS already
+    pub esm: bool,
+}
+
+/// Rewrite a module's imports and exports to CommonJS
+///
+/// Modules without any are returned unchanged.
+pub fn convert(source: &str, path: &Path) -> Result<CommonJsModule> {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, source, SourceType::mjs().with_jsx(true)).parse();
+    if let Some(error) = parsed.errors.first() {
+        let offset = error.labels.as_ref().and_then(|labels| labels.first()).map_or(0, |label| label.offset());
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        anyhow::bail!("Failed to parse {}:{}: {}", path.display(), line, error.message);
+    }
+    
+    let mut converter = Converter::default();
+    for statement in &parsed.program.body {
+        converter.statement(statement, source);
+    }
+    if converter.esm {
+        converter.visit_program(&parsed.program);
+    }
+    Ok(converter.finish(source))
+}
+
+#[derive(Default)]
+struct Converter {
+    edits: Vec<(Range<usize>, String)>,
+    
+    /// Exported names and the expressions their getters return
+    getters: Vec<(String, String)>,
+    names: Vec<String>,
+    star: Vec<String>,
+    
+    /// Modules required so far, which name their variables
+    requires: usize,
+    esm: bool,
+    
+    /// Imported bindings and the expressions reading them
+    imports: HashMap<String, String>,
+    
+    /// Module-level bindings the module exports
+    exported: HashSet<String>,
+    
+    /// Names declared in each enclosing scope that shadow an imported or
+    /// exported binding
+    shadowed: Vec<Vec<String>>,
+}
+
+impl Converter {
+    fn statement(&mut self, statement: &Statement, source: &str) {
+        match statement {
+            Statement::ImportDeclaration(import) => {
+                self.esm = true;
+                let code = if import.import_kind.is_type() {
+                    String::new()
+                } else {
+                    self.import(import)
+                };
+                self.rewrite(import.span.start as usize..import.span.end as usize, code, source);
+            }
+            Statement::ExportNamedDeclaration(export) => {
+                self.esm = true;
+                let span = export.span.start as usize..export.span.end as usize;
+                if export.export_kind.is_type() {
+                    return self.rewrite(span, String::new(), source);
+                }
+                if let Some(declaration) = &export.declaration {
+                    let names: Vec<String> = match declaration {
+                        Declaration::VariableDeclaration(variables) => variables.declarations
+                            .iter()
+                            .flat_map(|declarator| declarator.id.get_binding_identifiers())
+                            .map(|id| id.name.to_string())
+                            .collect(),
+                        _ => declaration.id().map(|id| id.name.to_string()).into_iter().collect(),
+                    };
+                    for name in names {
+                        self.exported.insert(name.clone());
+                        self.export(name.clone(), name);
+                    }
+                    return self.edits.push((span.start..declaration.span().start as usize, String::new()));
+                }
+                
+                let specifiers = export.specifiers.iter().filter(|specifier| specifier.export_kind.is_value());
+                let code = match &export.source {
+                    Some(from) => {
+                        let module = self.module_variable();
+                        for specifier in specifiers {
+                            let value = import_value(&module, &specifier.local.name());
+                            self.export(specifier.exported.name().to_string(), value);
+                        }
+                        format!("var {} = require({});", module, quote(&from.value))
+                    }
+                    None => {
+                        for specifier in specifiers {
+                            let local = specifier.local.name().to_string();
+                            self.exported.insert(local.clone());
+                            self.export(specifier.exported.name().to_string(), local);
+                        }
+                        String::new()
+                    }
+                };
+                self.rewrite(span, code, source);
+            }
+            Statement::ExportDefaultDeclaration(export) => {
+                self.esm = true;
+                let id = match &export.declaration {
+                    ExportDefaultDeclarationKind::FunctionDeclaration(function) => function.id.as_ref(),
+                    ExportDefaultDeclarationKind::ClassDeclaration(class) => class.id.as_ref(),
+                    ExportDefaultDeclarationKind::TSInterfaceDeclaration(_) => {
+                        return self.rewrite(export.span.start as usize..export.span.end as usize, String::new(), source);
+                    }
+                    _ => None,
+                };
+                let prefix = export.span.start as usize..export.declaration.span().start as usize;
+                match id {
+                    // Named declarations stay declarations, so functions are hoisted
+                    Some(id) => {
+                        self.exported.insert(id.name.to_string());
+                        self.export("default".to_string(), id.name.to_string());
+                        self.edits.push((prefix, String::new()));
+                    }
+                    None => {
+                        self.names.push("default".to_string());
+                        self.edits.push((prefix, "exports.default = ".to_string()));
+                    }
+                }
+            }
+            Statement::ExportAllDeclaration(export) => {
+                self.esm = true;
+                let span = export.span.start as usize..export.span.end as usize;
+                if export.export_kind.is_type() {
+                    return self.rewrite(span, String::new(), source);
+                }
+                let module = self.module_variable();
+                match &export.exported {
+                    Some(name) => self.export(name.name().to_string(), module.clone()),
+                    None => self.star.push(export.source.value.to_string()),
+                }
+                let mut code = format!("var {} = require({});", module, quote(&export.source.value));
+                if export.exported.is_none() {
+                    code.push_str(&format!(" __component_export_star__(exports, {});", module));
+                }
+                self.rewrite(span, code, source);
+            }
+            _ => {}
+        }
+    }
+    
+    /// `require()` call for an import, whose bindings are read through the
+    /// required module wherever they're referenced
+    fn import(&mut self, import: &ImportDeclaration) -> String {
+        let from = quote(&import.source.value);
+        let Some(specifiers) = &import.specifiers else {
+            return format!("require({});", from);
+        };
+        let module = self.module_variable();
+        for specifier in specifiers {
+            let (local, value) = match specifier {
+                ImportDeclarationSpecifier::ImportDefaultSpecifier(default) => {
+                    (&default.local.name, format!("__component_default__({})", module))
+                }
+                ImportDeclarationSpecifier::ImportNamespaceSpecifier(namespace) => (&namespace.local.name, module.clone()),
+                ImportDeclarationSpecifier::ImportSpecifier(named) if named.import_kind.is_type() => continue,
+                ImportDeclarationSpecifier::ImportSpecifier(named) => (&named.local.name, import_value(&module, &named.imported.name())),
+            };
+            self.imports.insert(local.to_string(), value);
+        }
+        format!("var {} = require({});", module, from)
+    }
+    
+    /// Name the variable of the next required module
+    fn module_variable(&mut self) -> String {
+        let name = format!("__component_import_{}", self.requires);
+        self.requires += 1;
+        name
+    }
+    
+    fn export(&mut self, name: String, value: String) {
+        self.names.push(name.clone());
+        self.getters.push((name, value));
+    }
+    
+    /// Replace a statement, keeping its line breaks
+    fn rewrite(&mut self, range: Range<usize>, mut code: String, source: &str) {
+        code.push_str(&"\n".repeat(source[range.clone()].matches('\n').count()));
+        self.edits.push((range, code));
+    }
+    
+    /// Whether `name` refers to the module-level binding where it's
+    /// referenced
+    fn is_module_binding(&self, name: &str) -> bool {
+        !self.shadowed.iter().any(|scope| scope.iter().any(|shadowed| shadowed == name))
+    }
+    
+    /// The expression reading an imported binding at `name`'s reference
+    fn import_reference(&self, name: &str) -> Option<&String> {
+        self.imports.get(name).filter(|_| self.is_module_binding(name))
+    }
+    
+    /// Run `visit` in a scope declaring `names`
+    fn scoped(&mut self, names: Vec<String>, visit: impl FnOnce(&mut Self)) {
+        let names: Vec<String> = names
+            .into_iter()
+            .filter(|name| self.imports.contains_key(name) || self.exported.contains(name))
+            .collect();
+        self.shadowed.push(names);
+        visit(self);
+        self.shadowed.pop();
+    }
+    
+    /// Report an assignment to `__component_changed__` when it writes to an
+    /// exported binding
+    fn report_write(&mut self, span: Span, target: impl FnOnce(&mut References)) {
+        let mut references = References::default();
+        target(&mut references);
+        if references.0.iter().any(|name| self.exported.contains(name) && self.is_module_binding(name)) {
+            let (start, end) = (span.start as usize, span.end as usize);
+            self.edits.push((start..start, "__component_changed__(".to_string()));
+            self.edits.push((end..end, ")".to_string()));
+        }
+    }
+    
+    fn finish(mut self, source: &str) -> CommonJsModule {
+        if !self.esm {
+            return CommonJsModule { code: source.to_string(), names: Vec::new(), star: Vec::new(), esm: false };
+        }
+        
+        // Exports of imported bindings read the required module
+        for (_, value) in &mut self.getters {
+            if let Some(import) = self.imports.get(value.as_str()) {
+                *value = import.clone();
+            }
+        }
+        
+        // Getters come first, on the first line, so lines stay where they were
+        let getters: Vec<String> = self.getters
+            .iter()
+            .map(|(name, value)| format!("{}: function() {{ return {}; }}", quote(name), value))
+            .collect();
+        let mut code = match getters.is_empty() {
+            true => "__component_define__(exports, {}); ".to_string(),
+            false => format!("__component_define__(exports, {{ {} }}); ", getters.join(", ")),
+        };
+        
+        self.edits.sort_by_key(|(range, _)| (range.start, range.end));
+        let mut cursor = 0;
+        for (range, text) in &self.edits {
+            code.push_str(&source[cursor..range.start]);
+            code.push_str(text);
+            cursor = range.end;
+        }
+        code.push_str(&source[cursor..]);
+        
+        CommonJsModule { code, names: self.names, star: self.star, esm: true }
+    }
+}
+
+/// Imported bindings are rewritten where they're referenced, and writes to
+/// exported ones reported, outside scopes that shadow them
+impl<'a> Visit<'a> for Converter {
+    fn visit_import_declaration(&mut self, _it: &ImportDeclaration<'a>) {}
+    
+    fn visit_export_named_declaration(&mut self, it: &ExportNamedDeclaration<'a>) {
+        if let Some(declaration) = &it.declaration {
+            self.visit_declaration(declaration);
+        }
+    }
+    
+    fn visit_export_all_declaration(&mut self, _it: &ExportAllDeclaration<'a>) {}
+    
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        if let Some(value) = self.import_reference(&it.name).cloned() {
+            self.edits.push((it.span.start as usize..it.span.end as usize, value));
+        }
+    }
+    
+    fn visit_object_property(&mut self, it: &ObjectProperty<'a>) {
+        match &it.value {
+            Expression::Identifier(id) if it.shorthand => {
+                if let Some(value) = self.import_reference(&id.name).cloned() {
+                    self.edits.push((id.span.start as usize..id.span.end as usize, format!("{}: {}", id.name, value)));
+                }
+            }
+            _ => walk::walk_object_property(self, it),
+        }
+    }
+    
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        // Imported functions are called without the module as `this`
+        match &it.callee {
+            Expression::Identifier(id) if self.import_reference(&id.name).is_some() => {
+                let value = format!("(0, {})", self.imports[id.name.as_str()]);
+                self.edits.push((id.span.start as usize..id.span.end as usize, value));
+                self.visit_arguments(&it.arguments);
+            }
+            _ => walk::walk_call_expression(self, it),
+        }
+    }
+    
+    fn visit_tagged_template_expression(&mut self, it: &TaggedTemplateExpression<'a>) {
+        match &it.tag {
+            Expression::Identifier(id) if self.import_reference(&id.name).is_some() => {
+                let value = format!("(0, {})", self.imports[id.name.as_str()]);
+                self.edits.push((id.span.start as usize..id.span.end as usize, value));
+                self.visit_template_literal(&it.quasi);
+            }
+            _ => walk::walk_tagged_template_expression(self, it),
+        }
+    }
+    
+    fn visit_assignment_expression(&mut self, it: &AssignmentExpression<'a>) {
+        self.report_write(it.span, |references| references.visit_assignment_target(&it.left));
+        walk::walk_assignment_expression(self, it);
+    }
+    
+    fn visit_update_expression(&mut self, it: &UpdateExpression<'a>) {
+        self.report_write(it.span, |references| references.visit_simple_assignment_target(&it.argument));
+        walk::walk_update_expression(self, it);
+    }
+    
+    fn visit_function(&mut self, it: &Function<'a>, flags: ScopeFlags) {
+        let mut names: Vec<String> = it.id.iter().map(|id| id.name.to_string()).collect();
+        names.extend(parameter_names(&it.params));
+        if let Some(body) = &it.body {
+            names.extend(body_names(&body.statements));
+        }
+        self.scoped(names, |converter| walk::walk_function(converter, it, flags));
+    }
+    
+    fn visit_arrow_function_expression(&mut self, it: &ArrowFunctionExpression<'a>) {
+        let mut names = parameter_names(&it.params);
+        names.extend(body_names(&it.body.statements));
+        self.scoped(names, |converter| walk::walk_arrow_function_expression(converter, it));
+    }
+    
+    fn visit_class(&mut self, it: &Class<'a>) {
+        let names = it.id.iter().map(|id| id.name.to_string()).collect();
+        self.scoped(names, |converter| walk::walk_class(converter, it));
+    }
+    
+    fn visit_static_block(&mut self, it: &StaticBlock<'a>) {
+        self.scoped(body_names(&it.body), |converter| walk::walk_static_block(converter, it));
+    }
+    
+    fn visit_block_statement(&mut self, it: &BlockStatement<'a>) {
+        self.scoped(lexical_names(&it.body), |converter| walk::walk_block_statement(converter, it));
+    }
+    
+    fn visit_switch_statement(&mut self, it: &SwitchStatement<'a>) {
+        let names = it.cases.iter().flat_map(|case| lexical_names(&case.consequent)).collect();
+        self.scoped(names, |converter| walk::walk_switch_statement(converter, it));
+    }
+    
+    fn visit_catch_clause(&mut self, it: &CatchClause<'a>) {
+        let names = it.param.iter().flat_map(|param| pattern_names(&param.pattern)).collect();
+        self.scoped(names, |converter| walk::walk_catch_clause(converter, it));
+    }
+    
+    fn visit_for_statement(&mut self, it: &ForStatement<'a>) {
+        let names = match &it.init {
+            Some(ForStatementInit::VariableDeclaration(declaration)) => declaration_names(declaration),
+            _ => Vec::new(),
+        };
+        self.scoped(names, |converter| walk::walk_for_statement(converter, it));
+    }
+    
+    fn visit_for_in_statement(&mut self, it: &ForInStatement<'a>) {
+        let names = match &it.left {
+            ForStatementLeft::VariableDeclaration(declaration) => declaration_names(declaration),
+            _ => Vec::new(),
+        };
+        self.scoped(names, |converter| walk::walk_for_in_statement(converter, it));
+    }
+    
+    fn visit_for_of_statement(&mut self, it: &ForOfStatement<'a>) {
+        let names = match &it.left {
+            ForStatementLeft::VariableDeclaration(declaration) => declaration_names(declaration),
+            _ => Vec::new(),
+        };
+        self.scoped(names, |converter| walk::walk_for_of_statement(converter, it));
+    }
+    
+    fn visit_ts_type(&mut self, _it: &TSType<'a>) {}
+}
+
+/// Names referenced in an assignment target
+#[derive(Default)]
+struct References(Vec<String>);
+
+impl<'a> Visit<'a> for References {
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        self.0.push(it.name.to_string());
+    }
+}
+
+/// `var` declarations of a function body, outside nested functions
+#[derive(Default)]
+struct VarNames(Vec<String>);
+
+impl<'a> Visit<'a> for VarNames {
+    fn visit_variable_declaration(&mut self, it: &VariableDeclaration<'a>) {
+        if it.kind == VariableDeclarationKind::Var {
+            self.0.extend(declaration_names(it));
+        }
+        walk::walk_variable_declaration(self, it);
+    }
+    
+    fn visit_function(&mut self, _it: &Function<'a>, _flags: ScopeFlags) {}
+    
+    fn visit_arrow_function_expression(&mut self, _it: &ArrowFunctionExpression<'a>) {}
+    
+    fn visit_class(&mut self, _it: &Class<'a>) {}
+}
+
+fn pattern_names(pattern: &BindingPattern) -> Vec<String> {
+    pattern.get_binding_identifiers().iter().map(|id| id.name.to_string()).collect()
+}
+
+fn declaration_names(declaration: &VariableDeclaration) -> Vec<String> {
+    declaration.declarations.iter().flat_map(|declarator| pattern_names(&declarator.id)).collect()
+}
+
+fn parameter_names(params: &FormalParameters) -> Vec<String> {
+    let mut names: Vec<String> = params.items.iter().flat_map(|param| pattern_names(&param.pattern)).collect();
+    if let Some(rest) = &params.rest {
+        names.extend(pattern_names(&rest.rest.argument));
+    }
+    names
+}
+
+/// Names a block declares with `let`, `const`, `class` and `function`
+fn lexical_names(statements: &[Statement]) -> Vec<String> {
+    statements
+        .iter()
+        .flat_map(|statement| match statement {
+            Statement::VariableDeclaration(declaration) if declaration.kind != VariableDeclarationKind::Var => {
+                declaration_names(declaration)
+            }
+            Statement::FunctionDeclaration(function) => function.id.iter().map(|id| id.name.to_string()).collect(),
+            Statement::ClassDeclaration(class) => class.id.iter().map(|id| id.name.to_string()).collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Names a function body declares, with its `var`s
+fn body_names(statements: &[Statement]) -> Vec<String> {
+    let mut vars = VarNames::default();
+    for statement in statements {
+        vars.visit_statement(statement);
+    }
+    let mut names = lexical_names(statements);
+    names.extend(vars.0);
+    names
+}
+
+/// Whether an export name can be written without quotes
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// The value of an imported name, with CommonJS modules' `module.exports`
+/// as their default export
+fn import_value(module: &str, name: &str) -> String {
+    if name == "default" {
+        format!("__component_default__({})", module)
+    } else {
+        format!("{}[{}]", module, quote(name))
+    }
+}
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn cjs(source: &str) -> CommonJsModule {
+        convert(source, Path::new("module.js")).unwrap()
+    }
+    
+    #[test]
+    fn test_imports() {
+        let module = cjs("import a, { b as c, default as d } from './a.js';\nimport * as ns from './ns.js';\nimport './side.js';\nconsole.log(a, c, d, ns);\n");
+        assert_eq!(
+            module.code,
+            "__component_define__(exports, {}); var __component_import_0 = require(\"./a.js\");\nvar __component_import_1 = require(\"./ns.js\");\nrequire(\"./side.js\");\nconsole.log(__component_default__(__component_import_0), __component_import_0[\"b\"], __component_default__(__component_import_0), __component_import_1);\n"
+        );
+        assert!(module.names.is_empty());
+        
+        // Bindings are read at each use, except where they're shadowed
+        let module = cjs("import { a, b } from './a.js';\nfunction f(a) { let x = { a, b }; return b(a); }\n{ const b = 1; b; }\ntry {} catch (a) { a; }\nb`t`;\nexport { b };\n");
+        assert_eq!(
+            module.code,
+            "__component_define__(exports, { \"b\": function() { return __component_import_0[\"b\"]; } }); var __component_import_0 = require(\"./a.js\");\nfunction f(a) { let x = { a, b: __component_import_0[\"b\"] }; return (0, __component_import_0[\"b\"])(a); }\n{ const b = 1; b; }\ntry {} catch (a) { a; }\n(0, __component_import_0[\"b\"])`t`;\n\n"
+        );
+    }
+    
+    #[test]
+    fn test_exports() {
+        let module = cjs("export const a = 1, { b, c: [d] } = obj;\nexport function f() {}\nexport default class Widget {}\nconst e = 2;\nexport { e as \"e-e\", f as g };\nexport { x } from './x.js';\nexport * from './all.js';\nexport * as ns from './ns.js';\n");
+        assert_eq!(module.names, vec!["a", "b", "d", "f", "default", "e-e", "g", "x", "ns"]);
+        assert_eq!(module.star, vec!["./all.js"]);
+        assert!(module.code.starts_with("__component_define__(exports, { \"a\": function() { return a; }, \"b\": function() { return b; }, "));
+        assert!(module.code.contains("\"x\": function() { return __component_import_0[\"x\"]; }"));
+        assert!(module.code.contains("\"ns\": function() { return __component_import_2; } }); const a = 1, { b, c: [d] } = obj;\nfunction f() {}\nclass Widget {}\nconst e = 2;\n\n"));
+        assert!(module.code.ends_with("var __component_import_1 = require(\"./all.js\"); __component_export_star__(exports, __component_import_1);\nvar __component_import_2 = require(\"./ns.js\");\n"));
+        
+        // Writes to exported bindings are reported
+        let live = cjs("export let count = 0;\nexport function inc() { count++; count += 1; let other = 0; other++; }\nfunction reset(count) { count = 0; }\n");
+        assert!(live.code.ends_with("let count = 0;\nfunction inc() { __component_changed__(count++); __component_changed__(count += 1); let other = 0; other++; }\nfunction reset(count) { count = 0; }\n"), "{}", live.code);
+        
+        let anonymous = cjs("export default function() {}\n");
+        assert_eq!(anonymous.code, "__component_define__(exports, {}); exports.default = function() {}\n");
+        assert_eq!(anonymous.names, vec!["default"]);
+        
+        // CommonJS modules are left alone
+        let commonjs = cjs("module.exports = require('./a');\n");
+        assert_eq!(commonjs.code, "module.exports = require('./a');\n");
+        assert!(!commonjs.esm);
+        assert!(convert("export const = 1;", Path::new("bad.js")).is_err());
+    }
+}
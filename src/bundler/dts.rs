@@ -0,0 +1,391 @@
+//! Declaration bundling for library builds
+//!
+//! With `library.dts`, tsc emits declarations for the project into the state
+//! directory, and the root entry's declaration file is flattened with every
+//! declaration file it reaches through relative imports into one file. Each
+//! module's declarations lose their `export` keyword and the file ends with
+//! an `export { ... }` list of what the entry exports, so internals stay
+//! private. Imports of packages are kept.
+//!
+//! Modules are inlined side by side rather than renamed, so flattening fails
+//! when two of them declare the same name or a name is imported under
+//! another one, and names `library.types` as the way out.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `import { a, b as c } from './x'`, `import a from './x'`, or both
+static IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^import\s+(?:type\s+)?(?:(\w+)\s*,?\s*)?(?:\{([^}]*)\})?\s*from\s+['"](\.[^'"]*)['"];?$"#).unwrap()
+});
+
+/// `export * from './x'` and `export { a, b as c } from './x'`
+static REEXPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^export\s+(?:type\s+)?(?:(\*)|\{([^}]*)\})\s*from\s+['"](\.[^'"]*)['"];?$"#).unwrap()
+});
+
+/// `import * as x from './x'` and `export * as x from './x'`
+static NAMESPACE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^(?:import|export)\s+(?:type\s+)?\*\s+as\s+\w+\s+from\s+['"](\.[^'"]*)['"]"#).unwrap()
+});
+
+/// `export { a, b as c };`
+static EXPORT_LIST_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^export\s+(?:type\s+)?\{([^}]*)\};?$").unwrap()
+});
+
+/// `export default name;`
+static EXPORT_DEFAULT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^export\s+default\s+([\w$]+);?$").unwrap()
+});
+
+/// A declaration, with the name it declares
+static DECLARATION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(export\s+)?(default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:const\s+enum|function|class|interface|type|const|let|var|enum|namespace|module)\s+([\w$]+)",
+    )
+    .unwrap()
+});
+
+/// Run tsc to emit the project's declarations into `out_dir` and return the
+/// declaration file of `entry`
+///
+/// The project's `tsconfig.json` is used when there is one; emit settings
+/// are overridden so only declarations are written.
+pub fn emit_declarations(root: &Path, entry: &Path, out_dir: &Path) -> Result<PathBuf> {
+    if out_dir.exists() {
+        fs::remove_dir_all(out_dir).with_context(|| format!("Failed to clear {}", out_dir.display()))?;
+    }
+    
+    let tsc = find_tsc(root);
+    let mut command = Command::new(&tsc);
+    let tsconfig = root.join("tsconfig.json");
+    if tsconfig.exists() {
+        command.arg("-p").arg(&tsconfig);
+    } else {
+        command.arg(entry).arg("--skipLibCheck");
+    }
+    command
+        .args(["--declaration", "--emitDeclarationOnly", "--noEmit", "false", "--declarationMap", "false"])
+        .arg("--outDir")
+        .arg(out_dir)
+        .current_dir(root);
+    
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}; library.dts needs TypeScript installed", tsc.display()))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "tsc failed to emit declarations:\n{}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    
+    // tsc mirrors the sources below their common directory
+    let relative = entry.strip_prefix(root).unwrap_or(entry).with_extension("d.ts");
+    let components: Vec<_> = relative.components().collect();
+    (0..components.len())
+        .map(|skip| out_dir.join(components[skip..].iter().collect::<PathBuf>()))
+        .find(|candidate| candidate.is_file())
+        .with_context(|| format!("tsc emitted no declarations for {}", entry.display()))
+}
+
+/// tsc of the nearest `node_modules`, or the one on PATH
+fn find_tsc(root: &Path) -> PathBuf {
+    let name = if cfg!(windows) { "tsc.cmd" } else { "tsc" };
+    root.ancestors()
+        .map(|dir| dir.join("node_modules").join(".bin").join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// A parsed declaration file
+#[derive(Debug, Default)]
+struct DeclarationFile {
+    /// Top-level statements kept in the output, without `export`
+    statements: Vec<String>,
+    
+    /// Package imports and triple-slash directives, kept once at the top
+    header: Vec<String>,
+    
+    /// Names the file declares
+    declared: Vec<String>,
+    
+    /// Relative imports by local name, with the module and imported name
+    imports: HashMap<String, (PathBuf, String)>,
+    
+    /// Exported names, with the module re-exported from or `None` for the
+    /// file itself, and the local or imported name
+    exports: Vec<(String, Option<PathBuf>, String)>,
+    
+    /// Modules whose exports are all re-exported
+    star_exports: Vec<PathBuf>,
+    
+    /// Relative modules the file depends on, in order
+    dependencies: Vec<PathBuf>,
+}
+
+/// Flatten the declaration file `entry` and the ones it reaches through
+/// relative imports into a single file
+pub fn bundle_declarations(entry: &Path) -> Result<String> {
+    let mut files: BTreeMap<PathBuf, DeclarationFile> = BTreeMap::new();
+    let mut order = Vec::new();
+    load(entry, &mut files, &mut order, &mut HashSet::new())?;
+    
+    let mut owners: HashMap<&str, &Path> = HashMap::new();
+    for path in &order {
+        for name in &files[path].declared {
+            if let Some(other) = owners.insert(name, path) {
+                anyhow::bail!(
+                    "Can't bundle declarations: '{}' is declared in both {} and {} (set library.types instead)",
+                    name,
+                    other.display(),
+                    path.display()
+                );
+            }
+        }
+    }
+    
+    let mut out = String::new();
+    let mut seen = HashSet::new();
+    for line in order.iter().flat_map(|path| &files[path].header) {
+        if seen.insert(line.as_str()) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    for path in &order {
+        // An imported name has to be the name the other module declares
+        for (local, (module, name)) in &files[path].imports {
+            let resolved = resolve(&files, module, name)?;
+            if &resolved != local {
+                anyhow::bail!(
+                    "Can't bundle declarations: {} imports '{}' as '{}' (set library.types instead)",
+                    path.display(),
+                    resolved,
+                    local
+                );
+            }
+        }
+        for statement in &files[path].statements {
+            out.push_str(statement);
+            out.push('\n');
+        }
+    }
+    
+    let mut exported = Vec::new();
+    for name in export_names(&files, entry) {
+        let local = resolve(&files, entry, &name)?;
+        exported.push(if local == name { name } else { format!("{} as {}", local, name) });
+    }
+    out.push_str(&format!("export {{ {} }};\n", exported.join(", ")));
+    Ok(out)
+}
+
+/// Parse `path` and the files it depends on, dependencies first
+fn load(
+    path: &Path,
+    files: &mut BTreeMap<PathBuf, DeclarationFile>,
+    order: &mut Vec<PathBuf>,
+    visiting: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    if files.contains_key(path) || !visiting.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+    let source = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let file = parse(&source, path)?;
+    for dependency in &file.dependencies {
+        load(dependency, files, order, visiting)?;
+    }
+    files.insert(path.to_path_buf(), file);
+    order.push(path.to_path_buf());
+    Ok(())
+}
+
+/// Split a declaration file into top-level statements and classify them
+fn parse(source: &str, path: &Path) -> Result<DeclarationFile> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut file = DeclarationFile::default();
+    
+    for statement in split_statements(source) {
+        if let Some(captures) = NAMESPACE_REGEX.captures(&statement) {
+            anyhow::bail!(
+                "Can't bundle declarations: {} imports '{}' as a namespace (set library.types instead)",
+                path.display(),
+                &captures[1]
+            );
+        }
+        if let Some(captures) = IMPORT_REGEX.captures(&statement) {
+            let module = resolve_specifier(dir, &captures[3])?;
+            if let Some(default) = captures.get(1) {
+                file.imports.insert(default.as_str().to_string(), (module.clone(), "default".to_string()));
+            }
+            for (name, local) in captures.get(2).map_or(Vec::new(), |list| parse_names(list.as_str())) {
+                file.imports.insert(local, (module.clone(), name));
+            }
+            file.dependencies.push(module);
+        } else if let Some(captures) = REEXPORT_REGEX.captures(&statement) {
+            let module = resolve_specifier(dir, &captures[3])?;
+            if captures.get(1).is_some() {
+                file.star_exports.push(module.clone());
+            }
+            for (name, exported) in captures.get(2).map_or(Vec::new(), |list| parse_names(list.as_str())) {
+                file.exports.push((exported, Some(module.clone()), name));
+            }
+            file.dependencies.push(module);
+        } else if let Some(captures) = EXPORT_LIST_REGEX.captures(&statement) {
+            for (local, exported) in parse_names(&captures[1]) {
+                file.exports.push((exported, None, local));
+            }
+        } else if let Some(captures) = EXPORT_DEFAULT_REGEX.captures(&statement) {
+            file.exports.push(("default".to_string(), None, captures[1].to_string()));
+        } else if statement.starts_with("import ") || statement.starts_with("///") {
+            file.header.push(statement);
+        } else if let Some(captures) = DECLARATION_REGEX.captures(&statement) {
+            let name = captures[3].to_string();
+            let prefix = captures.get(1).map_or(0, |m| m.len()) + captures.get(2).map_or(0, |m| m.len());
+            let mut body = statement[prefix..].to_string();
+            // Values need `declare` once their `export` is gone
+            if !body.starts_with("declare ") && !body.starts_with("interface ") && !body.starts_with("type ") {
+                body = format!("declare {}", body);
+            }
+            match (captures.get(1).is_some(), captures.get(2).is_some()) {
+                (true, true) => file.exports.push(("default".to_string(), None, name.clone())),
+                (true, false) => file.exports.push((name.clone(), None, name.clone())),
+                _ => {}
+            }
+            file.declared.push(name);
+            file.statements.push(body);
+        } else {
+            file.statements.push(statement);
+        }
+    }
+    Ok(file)
+}
+
+/// Top-level statements: a statement starts on each line that isn't
+/// indented and doesn't close a bracket
+fn split_statements(source: &str) -> Vec<String> {
+    let mut statements: Vec<String> = Vec::new();
+    for line in source.lines() {
+        let continues = line.starts_with(|c: char| c.is_whitespace() || matches!(c, '}' | ')' | ']'));
+        match statements.last_mut() {
+            Some(statement) if continues => {
+                statement.push('\n');
+                statement.push_str(line);
+            }
+            _ if line.trim().is_empty() => {}
+            _ => statements.push(line.to_string()),
+        }
+    }
+    statements.retain(|statement| !matches!(statement.trim(), "export {};" | "export {}"));
+    statements
+}
+
+/// `a, type b as c` as `[(a, a), (b, c)]`
+fn parse_names(list: &str) -> Vec<(String, String)> {
+    list.split(',')
+        .map(|item| item.trim().trim_start_matches("type ").trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.split_once(" as ") {
+            Some((name, alias)) => (name.trim().to_string(), alias.trim().to_string()),
+            None => (item.to_string(), item.to_string()),
+        })
+        .collect()
+}
+
+/// The declaration file a relative specifier refers to
+fn resolve_specifier(dir: &Path, specifier: &str) -> Result<PathBuf> {
+    let base = specifier
+        .strip_suffix(".js")
+        .or_else(|| specifier.strip_suffix(".mjs"))
+        .or_else(|| specifier.strip_suffix(".cjs"))
+        .unwrap_or(specifier);
+    let base = dir.join(base);
+    let candidates = [
+        PathBuf::from(format!("{}.d.ts", base.display())),
+        base.join("index.d.ts"),
+    ];
+    candidates
+        .into_iter()
+        .find(|path| path.is_file())
+        .with_context(|| format!("No declarations for '{}' in {}", specifier, dir.display()))
+}
+
+/// Names a file exports, including through `export *`
+fn export_names(files: &BTreeMap<PathBuf, DeclarationFile>, path: &Path) -> Vec<String> {
+    let Some(file) = files.get(path) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = file.exports.iter().map(|(name, _, _)| name.clone()).collect();
+    for module in &file.star_exports {
+        for name in export_names(files, module) {
+            if name != "default" && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// The declared name an export of a file refers to
+fn resolve(files: &BTreeMap<PathBuf, DeclarationFile>, path: &Path, name: &str) -> Result<String> {
+    let file = files.get(path).with_context(|| format!("{} wasn't loaded", path.display()))?;
+    if let Some((_, module, local)) = file.exports.iter().find(|(exported, _, _)| exported == name) {
+        return match (module, file.imports.get(local)) {
+            (Some(module), _) => resolve(files, module, local),
+            (None, Some((module, imported))) => resolve(files, module, imported),
+            (None, None) => Ok(local.clone()),
+        };
+    }
+    for module in &file.star_exports {
+        if export_names(files, module).iter().any(|exported| exported == name) {
+            return resolve(files, module, name);
+        }
+    }
+    anyhow::bail!("{} doesn't export '{}'", path.display(), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bundle_declarations() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, source: &str| fs::write(dir.path().join(name), source).unwrap();
+        write(
+            "index.d.ts",
+            "import type { Options } from './options.js';\nexport * from './math';\n\
+             export { format as formatValue } from './format.js';\n\
+             export declare function create(options: Options): void;\nexport type { Options };\n",
+        );
+        write(
+            "options.d.ts",
+            "/// <reference types=\"node\" />\nimport { EventEmitter } from 'events';\n\
+             export interface Options {\n    emitter?: EventEmitter;\n}\n",
+        );
+        write("math.d.ts", "export declare const PI = 3.14;\ndeclare function helper(): void;\nexport default helper;\n");
+        write("format.d.ts", "export declare function format(value: number): string;\nexport {};\n");
+        
+        assert_eq!(
+            bundle_declarations(&dir.path().join("index.d.ts")).unwrap(),
+            "/// <reference types=\"node\" />\nimport { EventEmitter } from 'events';\n\
+             interface Options {\n    emitter?: EventEmitter;\n}\n\
+             declare const PI = 3.14;\ndeclare function helper(): void;\n\
+             declare function format(value: number): string;\n\
+             declare function create(options: Options): void;\n\
+             export { format as formatValue, create, Options, PI };\n"
+        );
+        
+        write("format.d.ts", "export declare function format(value: number): string;\nexport declare const PI = 3;\n");
+        let error = bundle_declarations(&dir.path().join("index.d.ts")).unwrap_err();
+        assert!(error.to_string().contains("'PI' is declared in both"), "{}", error);
+    }
+}
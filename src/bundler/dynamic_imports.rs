@@ -0,0 +1,177 @@
+//! Dynamic imports of computed paths
+//!
+//! `import(`./pages/${name}.js`)` can load any file its template matches.
+//! As in Vite, each substitution matches within one directory level, and
+//! the call becomes a lookup in a table of `import()`s of the matching
+//! files, each of which then starts an async chunk. The template must start
+//! with `./` or `../` and end with a file extension. Other computed imports
+//! are left as written, with a warning, since what they load isn't bundled.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use globset::GlobBuilder;
+
+use crate::resolver::scan_computed_imports;
+use crate::utils::{normalize_path, path_to_module_id};
+use crate::vfs::FileSystem;
+
+/// Replace the template `import()`s of a script with lookups of the files
+/// they can match, returning the warnings for those that can't be expanded
+///
+/// `name` is the script's path as warnings show it.
+pub fn expand<'a>(source: &'a str, path: &Path, name: &str, fs: &dyn FileSystem) -> (Cow<'a, str>, Vec<String>) {
+    let imports = scan_computed_imports(source);
+    if imports.is_empty() {
+        return (Cow::Borrowed(source), Vec::new());
+    }
+    
+    let mut output = String::with_capacity(source.len());
+    let mut cursor = 0;
+    let mut warnings = Vec::new();
+    for import in imports {
+        let before = &source[..import.span.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        let location = format!("{}:{}:{}", name, line, column);
+        
+        let Some(template) = import.template.map(|range| &source[range]) else {
+            warnings.push(format!(
+                "{}: import() of a computed path isn't bundled; write it as a template literal naming a directory and an extension, as in `./pages/${{name}}.js`, to bundle the files it can load",
+                location
+            ));
+            continue;
+        };
+        match matching_files(&import.parts, path, fs) {
+            Ok(files) => {
+                output.push_str(&source[cursor..import.span.start]);
+                output.push_str(&lookup(&files, template));
+                cursor = import.span.end;
+            }
+            Err(reason) => warnings.push(format!("{}: import() of {} isn't bundled: {}", location, template, reason)),
+        }
+    }
+    
+    if cursor == 0 {
+        return (Cow::Borrowed(source), warnings);
+    }
+    output.push_str(&source[cursor..]);
+    (Cow::Owned(output), warnings)
+}
+
+/// Files a template can name, as written relative to the importer, with
+/// the text around its substitutions in `parts`
+fn matching_files(parts: &[String], importer: &Path, fs: &dyn FileSystem) -> Result<Vec<String>, &'static str> {
+    let pattern = parts.join("*");
+    if !pattern.starts_with("./") && !pattern.starts_with("../") {
+        return Err("only paths starting with ./ or ../ can be matched");
+    }
+    let last = parts.last().map(String::as_str).unwrap_or_default();
+    if !last.rsplit_once('.').is_some_and(|(_, extension)| !extension.is_empty() && !extension.contains('/')) {
+        return Err("the path must end with a file extension, as in `./pages/${name}.js`");
+    }
+    
+    // Files are searched from the deepest directory the template names
+    let prefix = &parts[0][..parts[0].rfind('/').map_or(0, |index| index + 1)];
+    let mut escaped: Vec<String> = parts.iter().map(|part| escape_glob(part)).collect();
+    escaped[0] = escape_glob(&parts[0][prefix.len()..]);
+    let rest = escaped.join("*");
+    let glob = GlobBuilder::new(&rest)
+        .literal_separator(true)
+        .build()
+        .map_err(|_| "the path isn't a valid pattern")?
+        .compile_matcher();
+    
+    let importer_dir = importer.parent().unwrap_or(importer);
+    let base = normalize_path(&importer_dir.join(prefix));
+    let mut candidates = Vec::new();
+    collect(&base, &base, rest.matches('/').count(), fs, &mut candidates);
+    let mut files: Vec<String> = candidates
+        .into_iter()
+        .filter(|file| base.join(file) != importer && glob.is_match(file))
+        .map(|file| format!("{}{}", prefix, file))
+        .collect();
+    files.sort();
+    
+    if files.is_empty() {
+        return Err("no files match it");
+    }
+    Ok(files)
+}
+
+/// Files under `dir` at most `depth` directories down, relative to `base`
+fn collect(dir: &Path, base: &Path, depth: usize, fs: &dyn FileSystem, files: &mut Vec<String>) {
+    for entry in fs.read_dir(dir).unwrap_or_default() {
+        if fs.is_dir(&entry) {
+            if depth > 0 {
+                collect(&entry, base, depth - 1, fs, files);
+            }
+        } else if let Ok(relative) = entry.strip_prefix(base) {
+            files.push(path_to_module_id(relative));
+        }
+    }
+}
+
+/// Text matched literally by a glob
+fn escape_glob(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '*' | '?' | '[' | ']' | '{' | '}' => format!("[{}]", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// An expression importing the file `template` names, from a table of
+/// imports of the files it can name, kept on one line
+fn lookup(files: &[String], template: &str) -> String {
+    let table: Vec<String> = files
+        .iter()
+        .map(|file| format!("{0}: function () {{ return import({0}); }}", serde_json::json!(file)))
+        .collect();
+    format!(
+        "(function (path) {{ var modules = {{ {} }}; return Object.prototype.hasOwnProperty.call(modules, path) ? modules[path]() : Promise.reject(new Error(\"Unknown dynamic import: \" + path)); }})({})",
+        table.join(", "),
+        template
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_expand_template_imports() {
+        let fs = MemoryFs::new();
+        fs.write("/app/src/main.js", "");
+        fs.write("/app/src/pages/home.js", "");
+        fs.write("/app/src/pages/about.js", "");
+        fs.write("/app/src/pages/styles.css", "");
+        fs.write("/app/src/pages/nested/deep.js", "");
+        fs.write("/app/src/locales/en/messages.json", "");
+        
+        let source = "const page = await import(`./pages/${name}.js`);\nconst messages = import(`./locales/${locale}/messages.json`);";
+        let (expanded, warnings) = expand(source, Path::new("/app/src/main.js"), "src/main.js", &fs);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let lines: Vec<&str> = expanded.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"const page = await (function (path) { var modules = { "./pages/about.js": function () { return import("./pages/about.js"); }, "./pages/home.js": function () { return import("./pages/home.js"); } };"#));
+        assert!(lines[0].ends_with("})(`./pages/${name}.js`);"));
+        assert!(lines[1].contains(r#""./locales/en/messages.json": function () { return import("./locales/en/messages.json"); }"#));
+    }
+    
+    #[test]
+    fn test_warn_about_unexpandable_imports() {
+        let fs = MemoryFs::new();
+        fs.write("/app/src/pages/home.js", "");
+        let source = "import(path);\nimport(`${base}/a.js`);\nimport(`./pages/${name}`);\nimport(`./missing/${name}.js`);";
+        let (expanded, warnings) = expand(source, Path::new("/app/src/main.js"), "src/main.js", &fs);
+        assert_eq!(expanded, source);
+        assert_eq!(warnings.len(), 4);
+        assert!(warnings[0].starts_with("src/main.js:1:1: import() of a computed path isn't bundled"));
+        assert_eq!(warnings[1], "src/main.js:2:1: import() of `${base}/a.js` isn't bundled: only paths starting with ./ or ../ can be matched");
+        assert!(warnings[2].ends_with("the path must end with a file extension, as in `./pages/${name}.js`"));
+        assert!(warnings[3].ends_with("no files match it"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Entrypoint globs
+//!
+//! An `[entrypoints]` value containing glob characters (`"src/pages/*.ts"`)
+//! adds an entry per matching file, named after the file's path below the
+//! pattern's first glob component without its extension (`about`,
+//! `blog/post`). Matching runs on every build, so new files become entries
+//! without editing the config.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use globset::{Glob, GlobBuilder, GlobMatcher};
+
+use crate::config::Config;
+use crate::utils::path_to_module_id;
+use crate::vfs::FileSystem;
+
+/// Directories never searched for entries
+const SKIPPED_DIRS: &[&str] = &["node_modules"];
+
+/// Whether an entrypoint value is a glob pattern
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Compile an entrypoint glob, relative to the project root
+pub fn compile(pattern: &str) -> Result<GlobMatcher> {
+    let pattern = pattern.trim_start_matches("./");
+    let glob: Glob = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid entrypoint glob '{}': {}", pattern, e))?;
+    Ok(glob.compile_matcher())
+}
+
+/// Every entrypoint with globs expanded, sorted by name
+///
+/// Entries named in the config win over glob matches of the same name;
+/// two glob matches with the same name are an error.
+pub fn expand_entrypoints(config: &Config, fs: &dyn FileSystem) -> Result<Vec<(String, PathBuf)>> {
+    let mut entries: BTreeMap<String, PathBuf> = config.entrypoints
+        .iter()
+        .filter(|(_, path)| !is_glob(path))
+        .map(|(name, path)| (name.clone(), config.root.join(path)))
+        .collect();
+    
+    let mut patterns: Vec<&String> = config.entrypoints.values().filter(|path| is_glob(path)).collect();
+    patterns.sort();
+    
+    let mut matched = BTreeMap::new();
+    for pattern in patterns {
+        let glob = compile(pattern)?;
+        let base = config.root.join(literal_base(pattern));
+        let mut files = Vec::new();
+        collect_files(&base, fs, &mut files);
+        
+        for file in files {
+            let Ok(relative) = file.strip_prefix(&config.root) else {
+                continue;
+            };
+            if !glob.is_match(relative) {
+                continue;
+            }
+            let name = entry_name(file.strip_prefix(&base).unwrap_or(relative));
+            if entries.contains_key(&name) {
+                continue;
+            }
+            if let Some(other) = matched.insert(name.clone(), file.clone()) {
+                anyhow::bail!(
+                    "Entrypoint globs match {} and {}, which both name entry '{}'",
+                    other.display(),
+                    file.display(),
+                    name
+                );
+            }
+        }
+    }
+    
+    entries.extend(matched);
+    Ok(entries.into_iter().collect())
+}
+
+/// Whether a path relative to the project root matches one of the entrypoint globs
+pub fn matches_entry_glob(config: &Config, relative: &Path) -> bool {
+    config.entrypoints
+        .values()
+        .filter(|pattern| is_glob(pattern))
+        .filter_map(|pattern| compile(pattern).ok())
+        .any(|glob| glob.is_match(relative))
+}
+
+/// Leading directories of a pattern that contain no glob characters
+fn literal_base(pattern: &str) -> PathBuf {
+    pattern
+        .trim_start_matches("./")
+        .split('/')
+        .take_while(|part| !is_glob(part))
+        .collect()
+}
+
+fn collect_files(dir: &Path, fs: &dyn FileSystem, files: &mut Vec<PathBuf>) {
+    for entry in fs.read_dir(dir).unwrap_or_default() {
+        let name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.starts_with('.') || SKIPPED_DIRS.contains(&name) {
+            continue;
+        }
+        if fs.is_dir(&entry) {
+            collect_files(&entry, fs, files);
+        } else {
+            files.push(entry);
+        }
+    }
+}
+
+/// Entry name for a matched file's path below the pattern's base
+fn entry_name(relative: &Path) -> String {
+    path_to_module_id(&relative.with_extension(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_expand_entrypoints() {
+        let fs = MemoryFs::new();
+        fs.write("/app/src/main.ts", "");
+        fs.write("/app/src/pages/about.ts", "");
+        fs.write("/app/src/pages/main.ts", "");
+        fs.write("/app/src/pages/blog/post.ts", "");
+        fs.write("/app/src/pages/styles.css", "");
+        
+        let mut config = Config::parse("[project]\nname = \"app\"\n", "/app").unwrap();
+        config.entrypoints.insert("main".to_string(), "src/main.ts".to_string());
+        config.entrypoints.insert("pages".to_string(), "src/pages/**/*.ts".to_string());
+        
+        let entries = expand_entrypoints(&config, &fs).unwrap();
+        let names: Vec<(&str, &Path)> = entries.iter().map(|(name, path)| (name.as_str(), path.as_path())).collect();
+        assert_eq!(names, [
+            ("about", Path::new("/app/src/pages/about.ts")),
+            ("blog/post", Path::new("/app/src/pages/blog/post.ts")),
+            ("main", Path::new("/app/src/main.ts")),
+        ]);
+        
+        assert!(matches_entry_glob(&config, Path::new("src/pages/contact.ts")));
+        assert!(!matches_entry_glob(&config, Path::new("src/pages/contact.css")));
+        assert_eq!(literal_base("./src/pages/*.ts"), Path::new("src/pages"));
+    }
+    
+    #[test]
+    fn test_conditional_entrypoints() {
+        let toml = r#"
+[project]
+name = "app"
+
+[entrypoints]
+main = "src/main.ts"
+sw = "src/sw-noop.ts"
+
+[entrypoints.development]
+instrument = "src/instrument.ts"
+
+[entrypoints.production]
+sw = "src/sw.ts"
+"#;
+        let mut config = Config::parse(toml, "/app").unwrap();
+        assert_eq!(config.entrypoints.len(), 2);
+        
+        config.entrypoints.apply("production");
+        assert_eq!(config.entrypoints.get("sw").map(String::as_str), Some("src/sw.ts"));
+        assert!(!config.entrypoints.contains_key("instrument"));
+        
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["entrypoints"]["development"]["instrument"], "src/instrument.ts");
+    }
+}
+
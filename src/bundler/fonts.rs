@@ -0,0 +1,653 @@
+//! Web fonts
+//!
+//! With `fonts.subset`, the TrueType and OpenType fonts stylesheets load in
+//! `@font-face` rules are copied with only the glyphs of characters in
+//! `fonts.unicode_ranges`, as WOFF2. Glyph ids and every other table are
+//! kept, so a glyph goes only when all characters mapping to it are outside
+//! the ranges; ligatures, alternates and the parts of accented letters
+//! stay. Fonts with CFF outlines keep their glyphs and only drop the
+//! characters, and WOFF and WOFF2 files are copied as they are.
+//!
+//! With `fonts.preload`, pages get a `<link rel="preload" as="font">` for
+//! the fonts the CSS loaded with them uses: the first source of each
+//! `@font-face` rule whose family the CSS names outside the rule.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `@font-face` rules
+static FONT_FACE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)@font-face\s*\{[^}]*\}").unwrap());
+
+/// `font-family` of a `@font-face` rule
+static FAMILY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)font-family\s*:\s*(?:"([^"]*)"|'([^']*)'|([^;}]*))"#).unwrap()
+});
+
+/// `url()` of a font source, with the `format()` after it
+static SOURCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"url\(\s*(?:"([^"]*)"|'([^']*)'|([^)\s"']*))\s*\)(\s*format\(\s*(?:"[^"]*"|'[^']*')\s*\))?"#).unwrap()
+});
+
+/// Extensions of the font files that can be subset
+const SUBSET_EXTENSIONS: &[&str] = &["ttf", "otf"];
+
+/// A `@font-face` rule of a stylesheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFace {
+    /// Family the rule names, unquoted
+    pub family: Option<String>,
+    
+    /// The rule's sources with a file URL, in the order browsers try them
+    pub sources: Vec<FontSource>,
+}
+
+/// A `url()` in a `@font-face` rule's `src`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontSource {
+    /// Byte range of the `url()`, and of the `format()` after it if any
+    pub range: Range<usize>,
+    
+    /// URL the source names, relative to the stylesheet
+    pub specifier: String,
+    
+    /// Byte range of the `format()` after the `url()`, if any
+    pub format: Option<Range<usize>>,
+}
+
+/// The `@font-face` rules of a stylesheet, with the sources that name
+/// files relative to it
+pub fn font_faces(css: &str) -> Vec<FontFace> {
+    FONT_FACE_REGEX
+        .find_iter(css)
+        .map(|rule| {
+            let family = FAMILY_REGEX.captures(rule.as_str()).and_then(|caps| {
+                let family = caps.get(1).or(caps.get(2)).or(caps.get(3))?.as_str().trim();
+                (!family.is_empty()).then(|| family.to_string())
+            });
+            let sources = SOURCE_REGEX
+                .captures_iter(rule.as_str())
+                .filter_map(|caps| {
+                    let specifier = caps.get(1).or(caps.get(2)).or(caps.get(3))?.as_str().trim();
+                    let is_file = !specifier.is_empty()
+                        && !specifier.starts_with(['/', '#'])
+                        && !specifier.starts_with("data:")
+                        && !specifier.contains("://");
+                    let whole = caps.get(0)?;
+                    is_file.then(|| FontSource {
+                        range: rule.start() + whole.start()..rule.start() + whole.end(),
+                        specifier: specifier.to_string(),
+                        format: caps.get(4).map(|format| rule.start() + format.start()..rule.start() + format.end()),
+                    })
+                })
+                .collect();
+            FontFace { family, sources }
+        })
+        .collect()
+}
+
+/// Whether a stylesheet uses `family` outside its `@font-face` rules
+pub fn uses_family(css: &str, family: &str) -> bool {
+    let family = family.to_ascii_lowercase();
+    FONT_FACE_REGEX
+        .split(css)
+        .any(|rest| rest.to_ascii_lowercase().contains(&family))
+}
+
+/// File a font source names, and the query or fragment after it
+pub fn source_path<'a>(stylesheet: &Path, specifier: &'a str) -> (PathBuf, &'a str) {
+    let split = specifier.find(['?', '#']).unwrap_or(specifier.len());
+    let (url, suffix) = specifier.split_at(split);
+    let dir = stylesheet.parent().unwrap_or(stylesheet);
+    (crate::utils::normalize_path(&dir.join(url)), suffix)
+}
+
+/// Point a stylesheet's font sources at the copies of the fonts in
+/// `assets`, under `public_url`
+///
+/// `code` is the stylesheet's CSS, or the module injecting it. Fonts
+/// converted to WOFF2 get `format("woff2")` in place of the format they
+/// had.
+pub fn rewrite_sources(code: &str, stylesheet: &Path, assets: &HashMap<PathBuf, String>, public_url: &str) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut cursor = 0;
+    for source in font_faces(code).iter().flat_map(|face| &face.sources) {
+        let (path, suffix) = source_path(stylesheet, &source.specifier);
+        let Some(file_name) = assets.get(&path) else {
+            continue;
+        };
+        output.push_str(&code[cursor..source.range.start]);
+        output.push_str(&format!("url(\"{}/{}{}\")", public_url, file_name, suffix));
+        match (&source.format, format_of(file_name)) {
+            (Some(_), Some(format)) => output.push_str(&format!(" format(\"{}\")", format)),
+            (Some(format), None) => output.push_str(&code[format.clone()]),
+            (None, _) => {}
+        }
+        cursor = source.range.end;
+    }
+    output.push_str(&code[cursor..]);
+    output
+}
+
+/// `format()` of a font file, by its extension
+fn format_of(file_name: &str) -> Option<&'static str> {
+    match file_name.rsplit_once('.')?.1.to_ascii_lowercase().as_str() {
+        "woff2" => Some("woff2"),
+        "woff" => Some("woff"),
+        "ttf" => Some("truetype"),
+        "otf" => Some("opentype"),
+        _ => None,
+    }
+}
+
+/// Content type a preload of a font file announces
+pub fn mime_type(file_name: &str) -> Option<&'static str> {
+    match format_of(file_name)? {
+        "woff2" => Some("font/woff2"),
+        "woff" => Some("font/woff"),
+        "truetype" => Some("font/ttf"),
+        _ => Some("font/otf"),
+    }
+}
+
+/// Whether a font file can be subset, by its extension
+pub fn can_subset(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SUBSET_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Parse `fonts.unicode_ranges`, written as in CSS `unicode-range`:
+/// `U+0000-00FF`, `U+0131`, or `U+4??`
+pub fn parse_unicode_ranges(ranges: &[String]) -> Result<Vec<RangeInclusive<u32>>> {
+    ranges.iter().map(|range| parse_unicode_range(range)).collect()
+}
+
+fn parse_unicode_range(range: &str) -> Result<RangeInclusive<u32>> {
+    let invalid = || anyhow!("fonts.unicode_ranges: '{}' isn't a unicode range, as in U+0000-00FF", range);
+    let digits = range.trim().strip_prefix("U+").or_else(|| range.trim().strip_prefix("u+")).ok_or_else(invalid)?;
+    let hex = |digits: &str| {
+        let valid = (1..=6).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_hexdigit());
+        valid.then(|| u32::from_str_radix(digits, 16).ok()).flatten().ok_or_else(invalid)
+    };
+    let (start, end) = match digits.split_once('-') {
+        Some((start, end)) => (hex(start)?, hex(end)?),
+        None if digits.contains('?') => (hex(&digits.replace('?', "0"))?, hex(&digits.replace('?', "F"))?),
+        None => (hex(digits)?, hex(digits)?),
+    };
+    if start > end || end > 0x10FFFF {
+        return Err(invalid());
+    }
+    Ok(start..=end)
+}
+
+/// A font without the glyphs of characters outside `ranges`, as a
+/// TrueType or OpenType file
+pub fn subset(font: &[u8], ranges: &[RangeInclusive<u32>]) -> Result<Vec<u8>> {
+    let (flavor, tables) = read_tables(font)?;
+    let face = ttf_parser::Face::parse(font, 0).map_err(|e| anyhow!("Invalid font: {}", e))?;
+    let cmap = face.tables().cmap.context("The font has no cmap table")?;
+    
+    // Characters the font maps, and the glyphs of those kept
+    let mut characters = BTreeMap::new();
+    for table in cmap.subtables.into_iter().filter(|table| table.is_unicode()) {
+        table.codepoints(|codepoint| {
+            if let Some(glyph) = table.glyph_index(codepoint) {
+                characters.entry(codepoint).or_insert(glyph.0);
+            }
+        });
+    }
+    characters.retain(|codepoint, _| ranges.iter().any(|range| range.contains(codepoint)));
+    let mapped: BTreeSet<u16> = {
+        let mut all = BTreeSet::new();
+        for table in cmap.subtables.into_iter().filter(|table| table.is_unicode()) {
+            table.codepoints(|codepoint| all.extend(table.glyph_index(codepoint).map(|glyph| glyph.0)));
+        }
+        all
+    };
+    
+    let mut tables: BTreeMap<[u8; 4], Vec<u8>> = tables.into_iter().map(|(tag, data)| (tag, data.to_vec())).collect();
+    tables.remove(b"DSIG");
+    tables.insert(*b"cmap", write_cmap(&characters)?);
+    
+    // Glyphs go when only characters outside the ranges map to them
+    if let (Some(glyf), Some(loca), Some(head)) = (tables.get(b"glyf"), tables.get(b"loca"), tables.get(b"head")) {
+        let glyph_count = usize::from(face.number_of_glyphs());
+        let long_offsets = read_u16(head, 50)? == 1;
+        let offsets = (0..=glyph_count)
+            .map(|index| match long_offsets {
+                true => read_u32(loca, index * 4).map(|offset| offset as usize),
+                false => read_u16(loca, index * 2).map(|offset| usize::from(offset) * 2),
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        let glyph = |index: usize| glyf.get(offsets[index]..offsets[index + 1]).context("Invalid loca table");
+        
+        let mut kept: BTreeSet<u16> = (0..glyph_count as u16).filter(|glyph| !mapped.contains(glyph)).collect();
+        kept.extend(characters.values());
+        let mut pending: Vec<u16> = kept.iter().copied().collect();
+        while let Some(index) = pending.pop() {
+            for component in components(glyph(usize::from(index))?)? {
+                if usize::from(component) < glyph_count && kept.insert(component) {
+                    pending.push(component);
+                }
+            }
+        }
+        
+        let mut new_glyf = Vec::new();
+        let mut new_loca = Vec::with_capacity((glyph_count + 1) * 4);
+        for index in 0..glyph_count {
+            new_loca.extend((new_glyf.len() as u32).to_be_bytes());
+            if kept.contains(&(index as u16)) {
+                new_glyf.extend(glyph(index)?);
+                new_glyf.resize(new_glyf.len().next_multiple_of(4), 0);
+            }
+        }
+        new_loca.extend((new_glyf.len() as u32).to_be_bytes());
+        
+        let mut head = head.clone();
+        head[50..52].copy_from_slice(&1u16.to_be_bytes());
+        tables.insert(*b"head", head);
+        tables.insert(*b"glyf", new_glyf);
+        tables.insert(*b"loca", new_loca);
+    }
+    
+    Ok(write_sfnt(flavor, &tables))
+}
+
+/// Glyphs a composite glyph is made of; none for a simple glyph
+fn components(glyph: &[u8]) -> Result<Vec<u16>> {
+    const ARGS_ARE_WORDS: u16 = 0x0001;
+    const HAS_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const HAS_X_Y_SCALE: u16 = 0x0040;
+    const HAS_TWO_BY_TWO: u16 = 0x0080;
+    
+    if glyph.is_empty() || (read_u16(glyph, 0)? as i16) >= 0 {
+        return Ok(Vec::new());
+    }
+    let mut components = Vec::new();
+    let mut offset = 10;
+    loop {
+        let flags = read_u16(glyph, offset)?;
+        components.push(read_u16(glyph, offset + 2)?);
+        offset += 4 + if flags & ARGS_ARE_WORDS != 0 { 4 } else { 2 };
+        offset += match flags {
+            _ if flags & HAS_SCALE != 0 => 2,
+            _ if flags & HAS_X_Y_SCALE != 0 => 4,
+            _ if flags & HAS_TWO_BY_TWO != 0 => 8,
+            _ => 0,
+        };
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(components);
+        }
+    }
+}
+
+/// A cmap table mapping `characters` to their glyphs: a format 4 subtable
+/// for the Basic Multilingual Plane, and a format 12 one for all
+/// characters when some are beyond it
+fn write_cmap(characters: &BTreeMap<u32, u16>) -> Result<Vec<u8>> {
+    // Format 4 segments of consecutive characters with a constant glyph delta
+    let mut segments: Vec<(u16, u16, u16)> = Vec::new();
+    for (&codepoint, &glyph) in characters.range(..0xFFFF) {
+        let codepoint = codepoint as u16;
+        let delta = glyph.wrapping_sub(codepoint);
+        match segments.last_mut() {
+            Some((_, end, last)) if *end + 1 == codepoint && *last == delta => *end = codepoint,
+            _ => segments.push((codepoint, codepoint, delta)),
+        }
+    }
+    segments.push((0xFFFF, 0xFFFF, 1));
+    
+    let seg_count = segments.len() as u16;
+    let length = 16 + segments.len() * 8;
+    if length > usize::from(u16::MAX) {
+        bail!("The subset keeps too many characters for a cmap table");
+    }
+    let search_range = 2 * (1u16 << (15 - seg_count.leading_zeros()));
+    let mut format4 = Vec::with_capacity(length);
+    for value in [4, length as u16, 0, seg_count * 2, search_range, search_range.trailing_zeros() as u16 - 1, seg_count * 2 - search_range] {
+        format4.extend(value.to_be_bytes());
+    }
+    format4.extend(segments.iter().flat_map(|&(_, end, _)| end.to_be_bytes()));
+    format4.extend(0u16.to_be_bytes());
+    format4.extend(segments.iter().flat_map(|&(start, _, _)| start.to_be_bytes()));
+    format4.extend(segments.iter().flat_map(|&(_, _, delta)| delta.to_be_bytes()));
+    format4.extend(segments.iter().flat_map(|_| 0u16.to_be_bytes()));
+    
+    let mut subtables = vec![(1u16, format4)];
+    if characters.keys().any(|&codepoint| codepoint > 0xFFFF) {
+        // Groups of consecutive characters with consecutive glyphs
+        let mut groups: Vec<(u32, u32, u32)> = Vec::new();
+        for (&codepoint, &glyph) in characters {
+            match groups.last_mut() {
+                Some((start, end, first)) if *end + 1 == codepoint && *first + (codepoint - *start) == u32::from(glyph) => *end = codepoint,
+                _ => groups.push((codepoint, codepoint, u32::from(glyph))),
+            }
+        }
+        let mut format12 = Vec::with_capacity(16 + groups.len() * 12);
+        format12.extend(12u16.to_be_bytes());
+        format12.extend(0u16.to_be_bytes());
+        format12.extend((16 + groups.len() as u32 * 12).to_be_bytes());
+        format12.extend(0u32.to_be_bytes());
+        format12.extend((groups.len() as u32).to_be_bytes());
+        for (start, end, glyph) in groups {
+            format12.extend(start.to_be_bytes());
+            format12.extend(end.to_be_bytes());
+            format12.extend(glyph.to_be_bytes());
+        }
+        subtables.push((10, format12));
+    }
+    
+    let mut cmap = Vec::new();
+    cmap.extend(0u16.to_be_bytes());
+    cmap.extend((subtables.len() as u16).to_be_bytes());
+    let mut offset = 4 + subtables.len() as u32 * 8;
+    for (encoding, subtable) in &subtables {
+        cmap.extend(3u16.to_be_bytes());
+        cmap.extend(encoding.to_be_bytes());
+        cmap.extend(offset.to_be_bytes());
+        offset += subtable.len() as u32;
+    }
+    for (_, subtable) in subtables {
+        cmap.extend(subtable);
+    }
+    Ok(cmap)
+}
+
+/// A font table's tag and data
+type Table<'a> = ([u8; 4], &'a [u8]);
+
+/// The flavor of a TrueType or OpenType font, and its tables by tag
+fn read_tables(font: &[u8]) -> Result<(u32, Vec<Table<'_>>)> {
+    let flavor = read_u32(font, 0)?;
+    if !matches!(&flavor.to_be_bytes(), b"\0\x01\0\0" | b"true" | b"OTTO") {
+        bail!("Only TrueType and OpenType fonts can be subset");
+    }
+    let count = usize::from(read_u16(font, 4)?);
+    let tables = (0..count)
+        .map(|index| {
+            let record = 12 + index * 16;
+            let tag = font.get(record..record + 4).context("Truncated font")?;
+            let offset = read_u32(font, record + 8)? as usize;
+            let length = read_u32(font, record + 12)? as usize;
+            let data = font.get(offset..offset + length).context("Truncated font")?;
+            Ok((tag.try_into()?, data))
+        })
+        .collect::<Result<_>>()?;
+    Ok((flavor, tables))
+}
+
+/// A TrueType or OpenType font of `tables`, with their checksums
+fn write_sfnt(flavor: u32, tables: &BTreeMap<[u8; 4], Vec<u8>>) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let search_range = 16 * (1u16 << (15 - count.leading_zeros()));
+    let mut font = Vec::new();
+    font.extend(flavor.to_be_bytes());
+    for value in [count, search_range, search_range.trailing_zeros() as u16 - 4, count * 16 - search_range] {
+        font.extend(value.to_be_bytes());
+    }
+    
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in tables {
+        font.extend(tag);
+        font.extend(checksum(data).to_be_bytes());
+        font.extend((offset as u32).to_be_bytes());
+        font.extend((data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+    let mut head = None;
+    for (tag, data) in tables {
+        if tag == b"head" {
+            head = Some(font.len());
+        }
+        font.extend(data);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+    
+    // The head table's checksum adjustment makes the whole font's 0xB1B0AFBA
+    if let Some(head) = head.filter(|head| head + 12 <= font.len()) {
+        font[head + 8..head + 12].fill(0);
+        let adjustment = 0xB1B0AFBAu32.wrapping_sub(checksum(&font));
+        font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+/// Tags WOFF2 table directories name by their index
+const WOFF2_KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm", b"glyf", b"loca", b"prep",
+    b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern", b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE",
+    b"GDEF", b"GPOS", b"GSUB", b"EBSC", b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt",
+    b"avar", b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty", b"just", b"lcar",
+    b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat", b"Gloc", b"Feat", b"Sill",
+];
+
+/// A TrueType or OpenType font as WOFF2
+///
+/// Tables are stored as they are, without WOFF2's transforms of glyph
+/// data, and compressed together with Brotli.
+pub fn to_woff2(font: &[u8]) -> Result<Vec<u8>> {
+    let (flavor, mut tables) = read_tables(font)?;
+    
+    // loca must follow glyf
+    tables.sort_by_key(|&(tag, _)| (if &tag == b"loca" { *b"glyf" } else { tag }, &tag == b"loca"));
+    
+    let mut directory = Vec::new();
+    let mut stream = Vec::new();
+    for (tag, data) in &tables {
+        // Glyph data untransformed is transform 3; other tables' is 0
+        let transform = if tag == b"glyf" || tag == b"loca" { 3 << 6 } else { 0 };
+        match WOFF2_KNOWN_TAGS.iter().position(|known| *known == tag) {
+            Some(index) => directory.push(index as u8 | transform),
+            None => {
+                directory.push(63 | transform);
+                directory.extend(tag);
+            }
+        }
+        write_base128(&mut directory, data.len() as u32);
+        stream.extend(*data);
+    }
+    
+    let mut compressed = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        writer.write_all(&stream)?;
+    }
+    
+    let sfnt_size: usize = 12 + tables.len() * 16 + tables.iter().map(|(_, data)| data.len().next_multiple_of(4)).sum::<usize>();
+    let length = (48 + directory.len() + compressed.len()).next_multiple_of(4);
+    let mut woff2 = Vec::with_capacity(length);
+    woff2.extend(b"wOF2");
+    woff2.extend(flavor.to_be_bytes());
+    woff2.extend((length as u32).to_be_bytes());
+    woff2.extend((tables.len() as u16).to_be_bytes());
+    woff2.extend(0u16.to_be_bytes());
+    woff2.extend((sfnt_size as u32).to_be_bytes());
+    woff2.extend((compressed.len() as u32).to_be_bytes());
+    woff2.extend(1u16.to_be_bytes());
+    woff2.extend(0u16.to_be_bytes());
+    woff2.extend([0; 20]);
+    woff2.extend(directory);
+    woff2.extend(compressed);
+    woff2.resize(length, 0);
+    Ok(woff2)
+}
+
+/// Write a WOFF2 UIntBase128: seven bits a byte, most significant first
+fn write_base128(output: &mut Vec<u8>, value: u32) {
+    let bytes = (1..5).take_while(|&i| value >> (7 * i) != 0).count() + 1;
+    for i in (0..bytes).rev() {
+        let more = if i > 0 { 0x80 } else { 0 };
+        output.push(((value >> (7 * i)) & 0x7F) as u8 | more);
+    }
+}
+
+/// OpenType checksum: the sum of big-endian u32 words, zero-padded
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data.get(offset..offset + 2).context("Truncated font")?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset + 4).context("Truncated font")?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// A TrueType font with glyphs for A, B, Á (A and an accent) and an
+/// unmapped ligature
+#[cfg(test)]
+pub(crate) fn test_font() -> Vec<u8> {
+    let mut tables = BTreeMap::new();
+    let mut head = vec![0; 54];
+    head[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    head[12..16].copy_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+    head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+    tables.insert(*b"head", head);
+    let mut hhea = vec![0; 36];
+    hhea[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    hhea[34..36].copy_from_slice(&6u16.to_be_bytes());
+    tables.insert(*b"hhea", hhea);
+    let mut maxp = vec![0; 6];
+    maxp[0..4].copy_from_slice(&0x0000_5000u32.to_be_bytes());
+    maxp[4..6].copy_from_slice(&6u16.to_be_bytes());
+    tables.insert(*b"maxp", maxp);
+    tables.insert(*b"hmtx", vec![0; 24]);
+    
+    // .notdef, A, B, accent, Á, ligature
+    let simple = |marker: u8| {
+        let mut glyph = vec![0, 1, 0, 0, 0, 0, 0, 10, 0, 10, 0, 0, 0, 0, 1, 0, 0];
+        glyph.push(marker);
+        glyph.resize(20, 0);
+        glyph
+    };
+    let composite = {
+        let mut glyph = vec![0xFF, 0xFF, 0, 0, 0, 0, 0, 10, 0, 10];
+        glyph.extend([0, 0x20, 0, 1, 0, 0]);
+        glyph.extend([0, 0x00, 0, 3, 0, 0]);
+        glyph
+    };
+    let glyphs = vec![simple(0), simple(1), simple(2), simple(3), composite, simple(5)];
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    for glyph in &glyphs {
+        loca.extend((glyf.len() as u16 / 2).to_be_bytes());
+        glyf.extend(glyph);
+    }
+    loca.extend((glyf.len() as u16 / 2).to_be_bytes());
+    tables.insert(*b"glyf", glyf);
+    tables.insert(*b"loca", loca);
+    
+    let characters = BTreeMap::from([(0x41, 1), (0x42, 2), (0xB4, 3), (0xC1, 4)]);
+    tables.insert(*b"cmap", write_cmap(&characters).unwrap());
+    write_sfnt(0x0001_0000, &tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    
+    fn glyph_len(font: &[u8], glyph: u16) -> u32 {
+        let (_, tables) = read_tables(font).unwrap();
+        let loca = tables.iter().find(|(tag, _)| tag == b"loca").unwrap().1;
+        let index = usize::from(glyph) * 4;
+        read_u32(loca, index + 4).unwrap() - read_u32(loca, index).unwrap()
+    }
+    
+    #[test]
+    fn test_subset_font() {
+        let font = test_font();
+        assert!(ttf_parser::Face::parse(&font, 0).is_ok());
+        
+        // Only A, and Á with the accent it's made of
+        let ranges = parse_unicode_ranges(&["U+0041".to_string(), "U+00C0-00FF".to_string()]).unwrap();
+        let subset = subset(&font, &ranges).unwrap();
+        let face = ttf_parser::Face::parse(&subset, 0).unwrap();
+        assert_eq!(face.glyph_index('A').map(|g| g.0), Some(1));
+        assert_eq!(face.glyph_index('Á').map(|g| g.0), Some(4));
+        assert_eq!(face.glyph_index('B'), None);
+        assert_eq!(face.glyph_index('´'), None);
+        assert_eq!(face.number_of_glyphs(), 6);
+        assert!(glyph_len(&subset, 1) > 0);
+        assert_eq!(glyph_len(&subset, 2), 0);
+        assert!(glyph_len(&subset, 3) > 0, "components of kept glyphs stay");
+        assert!(glyph_len(&subset, 5) > 0, "unmapped glyphs stay");
+        assert_eq!(checksum(&subset), 0xB1B0AFBA);
+        
+        // WOFF2 holds the tables, compressed
+        let woff2 = to_woff2(&subset).unwrap();
+        assert_eq!(&woff2[0..4], b"wOF2");
+        assert_eq!(read_u32(&woff2, 8).unwrap() as usize, woff2.len());
+        let count = usize::from(read_u16(&woff2, 12).unwrap());
+        let compressed_len = read_u32(&woff2, 20).unwrap() as usize;
+        let (_, tables) = read_tables(&subset).unwrap();
+        assert_eq!(count, tables.len());
+        let directory_len = 2 * count;
+        assert_eq!(woff2[48], 0, "cmap is known tag 0");
+        let compressed = &woff2[48 + directory_len..48 + directory_len + compressed_len];
+        let mut stream = Vec::new();
+        brotli::Decompressor::new(compressed, 4096).read_to_end(&mut stream).unwrap();
+        let glyf = tables.iter().find(|(tag, _)| tag == b"glyf").unwrap().1;
+        assert_eq!(stream.len(), tables.iter().map(|(_, data)| data.len()).sum::<usize>());
+        assert!(stream.windows(glyf.len()).any(|window| window == glyf));
+        
+        assert!(subset_error(b"wOF2....").contains("Only TrueType and OpenType fonts"));
+    }
+    
+    fn subset_error(font: &[u8]) -> String {
+        subset(font, &[0..=0x7F]).unwrap_err().to_string()
+    }
+    
+    #[test]
+    fn test_parse_unicode_ranges() {
+        let ranges = ["U+0000-00FF", "u+0131", "U+4??", "U+1F600-1F64F"].map(String::from);
+        assert_eq!(parse_unicode_ranges(&ranges).unwrap(), vec![0..=0xFF, 0x131..=0x131, 0x400..=0x4FF, 0x1F600..=0x1F64F]);
+        for invalid in ["0041", "U+00FF-0000", "U+110000", "U+", "U+00G1"] {
+            let error = parse_unicode_ranges(&[invalid.to_string()]).unwrap_err().to_string();
+            assert!(error.contains("isn't a unicode range"), "{}", error);
+        }
+    }
+    
+    #[test]
+    fn test_font_faces() {
+        let css = r#"@font-face {
+  font-family: "Inter";
+  src: url("./fonts/Inter.woff2") format("woff2"), url(fonts/Inter.ttf?v=2) format('truetype'), local(Inter);
+}
+@font-face { font-family: Icons; src: url(data:font/woff2;base64,AAAA), url(https://cdn.example.com/icons.woff2); }
+body { font-family: Inter, sans-serif; background: url(./bg.png); }
+"#;
+        let faces = font_faces(css);
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[0].family.as_deref(), Some("Inter"));
+        assert_eq!(faces[0].sources.len(), 2);
+        assert_eq!(&css[faces[0].sources[0].range.clone()], r#"url("./fonts/Inter.woff2") format("woff2")"#);
+        assert_eq!(faces[0].sources[1].specifier, "fonts/Inter.ttf?v=2");
+        assert_eq!(faces[1].family.as_deref(), Some("Icons"));
+        assert!(faces[1].sources.is_empty());
+        assert!(uses_family(css, "Inter"));
+        assert!(!uses_family(css, "Icons"));
+        
+        let assets = HashMap::from([
+            (PathBuf::from("/app/src/fonts/Inter.woff2"), "assets/Inter.1a2b.woff2".to_string()),
+            (PathBuf::from("/app/src/fonts/Inter.ttf"), "assets/Inter.3c4d.woff2".to_string()),
+        ]);
+        let rewritten = rewrite_sources(css, Path::new("/app/src/styles.css"), &assets, "/app");
+        assert!(rewritten.contains(r#"src: url("/app/assets/Inter.1a2b.woff2") format("woff2"), url("/app/assets/Inter.3c4d.woff2?v=2") format("woff2"), local(Inter);"#));
+        assert!(rewritten.contains("background: url(./bg.png);"));
+    }
+}
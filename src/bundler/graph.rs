@@ -1,20 +1,33 @@
 //! Module graph data structures
 
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::path_key;
+use crate::vfs::FileStamp;
 
 /// Unique identifier for a module
 pub type ModuleId = usize;
 
 /// Types of modules the bundler can handle
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ModuleType {
     JavaScript,
     TypeScript,
     Jsx,
     Tsx,
     Css,
+    /// A stylesheet imported with `type: 'css'`, whose default export is a
+    /// constructable `CSSStyleSheet` instead of styles added to the page
+    StyleSheet,
     Json,
+    Html,
+    Graphql,
     Unknown,
 }
 
@@ -28,6 +41,8 @@ impl ModuleType {
             "tsx" => ModuleType::Tsx,
             "css" | "scss" | "sass" | "less" => ModuleType::Css,
             "json" => ModuleType::Json,
+            "html" | "htm" => ModuleType::Html,
+            "graphql" | "gql" => ModuleType::Graphql,
             _ => ModuleType::Unknown,
         }
     }
@@ -44,6 +59,136 @@ impl ModuleType {
     }
 }
 
+/// How a module refers to a dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportKind {
+    /// `import ... from` or `export ... from`
+    Static,
+    /// `import()`
+    Dynamic,
+    /// `require()`
+    Require,
+    /// A stylesheet's `@import`, `@use` or `@forward`
+    Css,
+    /// A page's `<script src>` or `<link rel="stylesheet">`
+    Html,
+    /// A GraphQL document's `#import`
+    Graphql,
+    /// `new URL('./file', import.meta.url)`, naming an asset rather than a
+    /// module
+    Url,
+}
+
+/// An import in a module's source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    /// Specifier as written
+    pub specifier: String,
+    
+    pub kind: ImportKind,
+    
+    /// Byte range of the specifier in the source, or of the whole tag in
+    /// pages
+    pub span: Range<usize>,
+    
+    /// Bindings a static import takes by name, `default` for a default
+    /// import
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+    
+    /// Import attributes, as in `with { type: 'json' }`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Dependency {
+    /// The `type` import attribute, if given
+    pub fn import_type(&self) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == "type").map(|(_, value)| value.as_str())
+    }
+    
+    /// Zero-based line and UTF-16 column of the start of the span, as
+    /// editors count them
+    pub fn position(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.span.start.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        (before.matches('\n').count(), before[line_start..].encode_utf16().count())
+    }
+}
+
+/// Each distinct specifier among `dependencies`, in order of first
+/// appearance, and whether it is only imported with `import()`
+///
+/// A module imported both ways is bundled with its importer, so only
+/// dynamic-only imports start async chunks. Asset URLs name no module and
+/// are left out.
+pub fn distinct_specifiers(dependencies: &[Dependency]) -> Vec<(String, bool)> {
+    let mut specifiers: Vec<(String, bool)> = Vec::new();
+    for dependency in dependencies.iter().filter(|dependency| dependency.kind != ImportKind::Url) {
+        let dynamic = dependency.kind == ImportKind::Dynamic;
+        match specifiers.iter_mut().find(|(specifier, _)| *specifier == dependency.specifier) {
+            Some((_, only_dynamic)) => *only_dynamic &= dynamic,
+            None => specifiers.push((dependency.specifier.clone(), dynamic)),
+        }
+    }
+    specifiers
+}
+
+/// What a module exports, as found in its source
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleExports {
+    /// Names the module exports from its own bindings, including `default`
+    pub names: Vec<String>,
+    
+    /// Names exported from other modules, as in `export { a as b } from './x'`
+    pub reexports: Vec<ReExport>,
+    
+    /// Specifiers of `export * from` statements, which pass on every name
+    /// but `default`
+    pub star: Vec<String>,
+    
+    /// Whether the module sets `module.exports` or `exports`, so its exports
+    /// are only known at runtime
+    pub commonjs: bool,
+}
+
+impl ModuleExports {
+    /// Whether nothing is known of what the module exports: it uses
+    /// CommonJS, or has no ES exports at all
+    pub fn is_opaque(&self) -> bool {
+        self.commonjs || (self.names.is_empty() && self.reexports.is_empty() && self.star.is_empty())
+    }
+}
+
+/// A name a module exports from another module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReExport {
+    /// Name as exported
+    pub name: String,
+    
+    /// Name in the other module, or `*` for its namespace
+    pub imported: String,
+    
+    pub specifier: String,
+}
+
+/// Output of a single transform stage, recorded with `--inspect-transforms`
+#[derive(Debug, Clone, Serialize)]
+pub struct TransformStage {
+    /// Stage name (`core:<type>` or `plugin:<name>`)
+    pub name: String,
+    
+    /// Code after this stage ran
+    pub code: String,
+    
+    /// Whether this stage changed the code
+    pub changed: bool,
+    
+    /// Time spent in this stage
+    pub duration_ms: f64,
+}
+
 /// A module in the dependency graph
 #[derive(Debug, Clone)]
 pub struct Module {
@@ -59,16 +204,48 @@ pub struct Module {
     /// Whether this is an entry point
     pub is_entry: bool,
     
-    /// Import specifiers found in this module
-    pub dependencies: Vec<String>,
+    /// Imports found in this module, in source order
+    pub dependencies: Vec<Dependency>,
+    
+    /// Exports found in this module
+    pub exports: ModuleExports,
     
     /// Transformed code (after TypeScript/JSX compilation)
     pub transformed: Option<String>,
+    
+    /// Time spent in the last transform of this module
+    pub transform_time: Option<Duration>,
+    
+    /// Number of times this module has been invalidated by a file change
+    pub invalidations: u32,
+    
+    /// Per-stage transform output (only recorded when inspecting transforms)
+    pub transform_stages: Vec<TransformStage>,
+    
+    /// Transform stages that produced this module's code, e.g. `core:jsx` or `plugin:virtual`
+    pub provenance: Vec<String>,
+    
+    /// Files outside the graph the last transform read; changing one
+    /// transforms the module again
+    pub watch_files: Vec<PathBuf>,
+    
+    /// Data plugins attached to the module by key, e.g. a CSS module's
+    /// class names, with [`PluginContext::set_meta`](crate::plugins::PluginContext::set_meta)
+    pub meta: serde_json::Map<String, serde_json::Value>,
+    
+    /// Size and modification time of the file when it was read; a change
+    /// event for a file with the same stamp is ignored
+    pub stamp: Option<FileStamp>,
 }
 
 impl Module {
+    /// Distinct import specifiers, in order of first appearance
+    pub fn specifiers(&self) -> Vec<String> {
+        distinct_specifiers(&self.dependencies).into_iter().map(|(specifier, _)| specifier).collect()
+    }
+    
     /// Detect module type from path
-    pub fn detect_type(path: &PathBuf) -> ModuleType {
+    pub fn detect_type(path: &Path) -> ModuleType {
         path.extension()
             .and_then(|ext| ext.to_str())
             .map(ModuleType::from_extension)
@@ -82,11 +259,17 @@ pub struct ModuleGraph {
     /// All modules indexed by their ID
     modules: HashMap<ModuleId, Module>,
     
-    /// Map from path to module ID
+    /// Map from path, compared case-insensitively on Windows, to module ID
     path_to_id: HashMap<PathBuf, ModuleId>,
     
-    /// Dependency edges: module ID -> set of dependency IDs
-    edges: HashMap<ModuleId, HashSet<ModuleId>>,
+    /// Dependency edges: module ID -> dependency IDs, in import order
+    edges: HashMap<ModuleId, Vec<ModuleId>>,
+    
+    /// Dynamic `import()` edges: module ID -> set of imported IDs
+    dynamic_edges: HashMap<ModuleId, HashSet<ModuleId>>,
+    
+    /// Module each specifier of a module resolved to
+    resolved: HashMap<ModuleId, HashMap<String, ModuleId>>,
     
     /// Next available module ID
     next_id: ModuleId,
@@ -100,7 +283,7 @@ impl ModuleGraph {
     
     /// Add a module to the graph
     pub fn add_module(&mut self, module: Module) -> ModuleId {
-        let path = module.path.clone();
+        let path = path_key(&module.path);
         
         // Check if already exists
         if let Some(&id) = self.path_to_id.get(&path) {
@@ -112,21 +295,59 @@ impl ModuleGraph {
         
         self.path_to_id.insert(path, id);
         self.modules.insert(id, module);
-        self.edges.insert(id, HashSet::new());
+        self.edges.insert(id, Vec::new());
         
         id
     }
     
     /// Add a dependency edge between modules
     pub fn add_dependency(&mut self, from: ModuleId, to: ModuleId) {
-        if let Some(deps) = self.edges.get_mut(&from) {
-            deps.insert(to);
+        if let Some(deps) = self.edges.get_mut(&from).filter(|deps| !deps.contains(&to)) {
+            deps.push(to);
         }
     }
     
+    /// Add a dynamic `import()` edge between modules
+    pub fn add_dynamic_dependency(&mut self, from: ModuleId, to: ModuleId) {
+        self.dynamic_edges.entry(from).or_default().insert(to);
+    }
+    
+    /// Record the module a specifier of `from` resolved to
+    pub fn set_resolved(&mut self, from: ModuleId, specifier: &str, to: ModuleId) {
+        self.resolved.entry(from).or_default().insert(specifier.to_string(), to);
+    }
+    
+    /// The module a specifier of `from` resolved to
+    pub fn get_resolved(&self, from: ModuleId, specifier: &str) -> Option<ModuleId> {
+        self.resolved.get(&from)?.get(specifier).copied()
+    }
+    
+    /// Remove all outgoing dependency edges of a module
+    pub fn clear_dependencies(&mut self, id: ModuleId) {
+        if let Some(deps) = self.edges.get_mut(&id) {
+            deps.clear();
+        }
+        self.dynamic_edges.remove(&id);
+        self.resolved.remove(&id);
+    }
+    
+    /// Get modules that directly depend on the given module, statically or
+    /// through a dynamic import
+    pub fn get_importers(&self, id: ModuleId) -> Vec<ModuleId> {
+        let static_importers = self.edges.iter().filter(|(_, deps)| deps.contains(&id));
+        let dynamic_importers = self.dynamic_edges.iter().filter(|(_, deps)| deps.contains(&id));
+        let mut importers: Vec<ModuleId> = static_importers
+            .map(|(&from, _)| from)
+            .chain(dynamic_importers.map(|(&from, _)| from))
+            .collect();
+        importers.sort_unstable();
+        importers.dedup();
+        importers
+    }
+    
     /// Get module ID from path
-    pub fn get_module_id(&self, path: &PathBuf) -> Option<ModuleId> {
-        self.path_to_id.get(path).copied()
+    pub fn get_module_id(&self, path: &Path) -> Option<ModuleId> {
+        self.path_to_id.get(&path_key(path)).copied()
     }
     
     /// Get a module by ID
@@ -144,7 +365,9 @@ impl ModuleGraph {
         self.modules.keys().copied().collect()
     }
     
-    /// Get all modules reachable from a given module (BFS)
+    /// Get all modules statically reachable from a given module (BFS)
+    ///
+    /// Dynamic imports are not followed; their targets start async chunks.
     pub fn get_reachable_modules(&self, start: ModuleId) -> Vec<ModuleId> {
         let mut visited = HashSet::new();
         let mut result = Vec::new();
@@ -168,14 +391,124 @@ impl ModuleGraph {
         result
     }
     
+    /// Get all modules statically reachable from `start` in the order ES
+    /// modules evaluate them: each after its imports, in import order
+    ///
+    /// An import that leads back into a cycle is skipped, so the module it
+    /// imports runs after the importer, as it would in the browser.
+    pub fn get_execution_order(&self, start: ModuleId) -> Vec<ModuleId> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::from([start]);
+        let mut stack = vec![(start, 0)];
+        
+        while let Some((id, next)) = stack.last_mut() {
+            let dep = self.edges.get(id).and_then(|deps| deps.get(*next)).copied();
+            *next += 1;
+            match dep {
+                Some(dep) => {
+                    if visited.insert(dep) {
+                        stack.push((dep, 0));
+                    }
+                }
+                None => {
+                    order.push(*id);
+                    stack.pop();
+                }
+            }
+        }
+        
+        order
+    }
+    
+    /// Get the given modules and every module importing them, directly or
+    /// transitively, in breadth-first order
+    pub fn get_affected_modules(&self, changed: &[ModuleId]) -> Vec<ModuleId> {
+        let mut visited: HashSet<ModuleId> = changed.iter().copied().collect();
+        let mut queue: VecDeque<ModuleId> = changed.iter().copied().collect();
+        let mut result = Vec::new();
+        
+        while let Some(id) = queue.pop_front() {
+            result.push(id);
+            for importer in self.get_importers(id) {
+                if visited.insert(importer) {
+                    queue.push_back(importer);
+                }
+            }
+        }
+        
+        result
+    }
+    
     /// Get direct dependencies of a module
     pub fn get_dependencies(&self, id: ModuleId) -> Vec<ModuleId> {
         self.edges
             .get(&id)
-            .map(|deps| deps.iter().copied().collect())
+            .map(|deps| deps.to_vec())
             .unwrap_or_default()
     }
     
+    /// Get modules dynamically imported by a module
+    pub fn get_dynamic_dependencies(&self, id: ModuleId) -> Vec<ModuleId> {
+        let mut deps: Vec<ModuleId> = self.dynamic_edges
+            .get(&id)
+            .map(|deps| deps.iter().copied().collect())
+            .unwrap_or_default();
+        deps.sort_unstable();
+        deps
+    }
+    
+    /// Whether a module exports `name`, following re-exports; `None` when
+    /// that can't be known, as for CommonJS, JSON or unresolved modules
+    pub fn provides_export(&self, id: ModuleId, name: &str) -> Option<bool> {
+        self.find_export(id, name, &mut HashSet::new())
+    }
+    
+    fn find_export(&self, id: ModuleId, name: &str, visited: &mut HashSet<ModuleId>) -> Option<bool> {
+        if !visited.insert(id) {
+            return Some(false);
+        }
+        let module = self.get_module(id)?;
+        let exports = &module.exports;
+        if !module.module_type.is_js_like() || exports.is_opaque() {
+            return None;
+        }
+        if exports.names.iter().chain(exports.reexports.iter().map(|r| &r.name)).any(|n| n == name) {
+            return Some(true);
+        }
+        if name == "default" {
+            return Some(false);
+        }
+        
+        let mut known = true;
+        for specifier in &exports.star {
+            match self.get_resolved(id, specifier).and_then(|target| self.find_export(target, name, visited)) {
+                Some(true) => return Some(true),
+                Some(false) => {}
+                None => known = false,
+            }
+        }
+        known.then_some(false)
+    }
+    
+    /// Every name a module exports, following `export *`, sorted
+    ///
+    /// Names behind modules whose exports are unknown are left out.
+    pub fn export_names(&self, id: ModuleId) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut queue = vec![(id, true)];
+        while let Some((id, with_default)) = queue.pop() {
+            let Some(module) = self.get_module(id).filter(|_| visited.insert(id)) else {
+                continue;
+            };
+            let exports = &module.exports;
+            let own = exports.names.iter().chain(exports.reexports.iter().map(|r| &r.name));
+            names.extend(own.filter(|name| with_default || *name != "default").cloned());
+            queue.extend(exports.star.iter().filter_map(|specifier| self.get_resolved(id, specifier)).map(|target| (target, false)));
+        }
+        names.into_iter().collect()
+    }
+    
     /// Get entry point modules
     pub fn get_entry_modules(&self) -> Vec<ModuleId> {
         self.modules
@@ -208,6 +541,8 @@ mod tests {
         assert_eq!(ModuleType::from_extension("tsx"), ModuleType::Tsx);
         assert_eq!(ModuleType::from_extension("css"), ModuleType::Css);
         assert_eq!(ModuleType::from_extension("json"), ModuleType::Json);
+        assert_eq!(ModuleType::from_extension("html"), ModuleType::Html);
+        assert_eq!(ModuleType::from_extension("gql"), ModuleType::Graphql);
         assert_eq!(ModuleType::from_extension("xyz"), ModuleType::Unknown);
     }
     
@@ -222,6 +557,14 @@ mod tests {
             is_entry: true,
             dependencies: vec![],
             transformed: None,
+            transform_time: None,
+            invalidations: 0,
+            transform_stages: vec![],
+            provenance: vec![],
+            watch_files: vec![],
+            exports: Default::default(),
+            meta: Default::default(),
+            stamp: None,
         };
         
         let id = graph.add_module(module);
@@ -229,4 +572,74 @@ mod tests {
         assert!(graph.get_module(id).is_some());
         assert_eq!(graph.get_module_id(&PathBuf::from("/test/main.js")), Some(id));
     }
+    
+    #[test]
+    fn test_importers_and_clear_dependencies() {
+        let mut graph = ModuleGraph::new();
+        let make = |path: &str| Module {
+            path: PathBuf::from(path),
+            source: String::new(),
+            module_type: ModuleType::JavaScript,
+            is_entry: false,
+            dependencies: vec![],
+            transformed: None,
+            transform_time: None,
+            invalidations: 0,
+            transform_stages: vec![],
+            provenance: vec![],
+            watch_files: vec![],
+            exports: Default::default(),
+            meta: Default::default(),
+            stamp: None,
+        };
+        
+        let main = graph.add_module(make("/test/main.js"));
+        let util = graph.add_module(make("/test/util.js"));
+        graph.add_dependency(main, util);
+        
+        assert_eq!(graph.get_importers(util), vec![main]);
+        
+        let test = graph.add_module(make("/test/util.test.js"));
+        let lazy = graph.add_module(make("/test/lazy.js"));
+        graph.add_dependency(test, util);
+        graph.add_dynamic_dependency(lazy, main);
+        assert_eq!(graph.get_affected_modules(&[util]), vec![util, main, test, lazy]);
+        
+        graph.clear_dependencies(main);
+        assert_eq!(graph.get_importers(util), vec![test]);
+        assert!(graph.get_dependencies(main).is_empty());
+    }
+    
+    #[test]
+    fn test_execution_order() {
+        let mut graph = ModuleGraph::new();
+        let mut add = |path: &str| graph.add_module(Module {
+            path: PathBuf::from(path),
+            source: String::new(),
+            module_type: ModuleType::JavaScript,
+            is_entry: false,
+            dependencies: vec![],
+            transformed: None,
+            transform_time: None,
+            invalidations: 0,
+            transform_stages: vec![],
+            provenance: vec![],
+            watch_files: vec![],
+            exports: Default::default(),
+            meta: Default::default(),
+            stamp: None,
+        });
+        let [main, polyfill, app, store, lazy] = ["main", "polyfill", "app", "store", "lazy"].map(|name| add(&format!("/test/{}.js", name)));
+        
+        graph.add_dependency(main, polyfill);
+        graph.add_dependency(main, app);
+        graph.add_dependency(app, store);
+        graph.add_dependency(store, app);
+        graph.add_dependency(store, polyfill);
+        graph.add_dynamic_dependency(app, lazy);
+        
+        // The cycle's import back into `app` is skipped, so `store` runs first
+        assert_eq!(graph.get_execution_order(main), vec![polyfill, store, app, main]);
+        assert_eq!(graph.get_execution_order(store), vec![app, polyfill, store]);
+    }
 }
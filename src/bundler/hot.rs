@@ -0,0 +1,197 @@
+//! Hot module replacement boundaries
+//!
+//! A module calling `import.meta.hot.accept()` accepts updates of itself,
+//! and one calling `import.meta.hot.accept('./dep', ...)` accepts updates of
+//! the modules it names. An update travels from the changed module up
+//! through its importers, stopping at each module that accepts it. When
+//! any chain of importers ends, at an entry or a page, without one, the
+//! page has to reload instead. Stylesheets accept their own updates, since
+//! the dev server injects them again in place.
+
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::{Module, ModuleGraph, ModuleId, ModuleType};
+
+/// `import.meta.hot.accept(` and its first argument when it's a string or
+/// array of specifiers; anything else accepts the module itself
+static ACCEPT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"import\.meta\.hot\??\.accept\(\s*(?:("[^"\n]*"|'[^'\n]*'|\[[^\]]*\])|[^"'\[])"#).unwrap()
+});
+
+/// Quoted specifiers in an `accept()` argument, with the quote captured
+static SPECIFIER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(["'])([^"'\n]*)["']"#).unwrap());
+
+/// The updates a module accepts, from its `import.meta.hot.accept()` calls
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HotAccepts {
+    /// Whether it accepts updates of itself
+    pub itself: bool,
+    
+    /// Specifiers of the imports whose updates it accepts
+    pub imports: Vec<String>,
+}
+
+impl HotAccepts {
+    /// Find the `accept()` calls in a script's source
+    pub fn scan(source: &str) -> Self {
+        let mut accepts = Self::default();
+        for caps in ACCEPT_REGEX.captures_iter(source) {
+            match caps.get(1) {
+                Some(argument) => accepts.imports.extend(
+                    SPECIFIER_REGEX.captures_iter(argument.as_str()).map(|caps| caps[2].to_string()),
+                ),
+                None => accepts.itself = true,
+            }
+        }
+        accepts
+    }
+    
+    /// Updates a module in the graph accepts
+    fn of(module: &Module) -> Self {
+        match module.module_type {
+            ModuleType::Css => Self { itself: true, imports: Vec::new() },
+            ref module_type if module_type.is_js_like() => Self::scan(&module.source),
+            _ => Self::default(),
+        }
+    }
+}
+
+/// Replace the specifiers `accept()` calls name with what `rewrite` gives,
+/// leaving those it returns `None` for as written
+pub fn rewrite_accepted(code: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    ACCEPT_REGEX
+        .replace_all(code, |caps: &Captures| {
+            let (Some(call), Some(argument)) = (caps.get(0), caps.get(1)) else {
+                return caps[0].to_string();
+            };
+            let argument_range = argument.start() - call.start()..argument.end() - call.start();
+            let argument = SPECIFIER_REGEX.replace_all(argument.as_str(), |caps: &Captures| match rewrite(&caps[2]) {
+                Some(url) => format!("{0}{1}{0}", &caps[1], url),
+                None => caps[0].to_string(),
+            });
+            format!("{}{}{}", &caps[0][..argument_range.start], argument, &caps[0][argument_range.end..])
+        })
+        .into_owned()
+}
+
+/// How an update of a module reaches the modules accepting it
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HotUpdate {
+    /// Each accepting module, with the module whose update it accepts:
+    /// itself, or one it imports
+    pub boundaries: Vec<(ModuleId, ModuleId)>,
+    
+    /// The changed module and every module the update passed through,
+    /// which browsers must import afresh
+    pub stale: Vec<ModuleId>,
+}
+
+/// Find the modules accepting an update of `changed`, or `None` when the
+/// page must reload
+pub fn hot_update(graph: &ModuleGraph, changed: ModuleId) -> Option<HotUpdate> {
+    let mut update = HotUpdate::default();
+    let mut visited = HashSet::new();
+    let mut queue = vec![changed];
+    
+    while let Some(id) = queue.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let module = graph.get_module(id)?;
+        if module.module_type == ModuleType::Html {
+            return None;
+        }
+        update.stale.push(id);
+        if HotAccepts::of(module).itself {
+            update.boundaries.push((id, id));
+            continue;
+        }
+        
+        let importers = graph.get_importers(id);
+        if importers.is_empty() {
+            return None;
+        }
+        for importer in importers {
+            let accepts = graph.get_module(importer).map(HotAccepts::of).unwrap_or_default();
+            if accepts.imports.iter().any(|specifier| graph.get_resolved(importer, specifier) == Some(id)) {
+                update.boundaries.push((importer, id));
+            } else {
+                queue.push(importer);
+            }
+        }
+    }
+    
+    Some(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    fn module(path: &str, source: &str) -> Module {
+        Module {
+            path: PathBuf::from(path),
+            source: source.to_string(),
+            module_type: Module::detect_type(path.as_ref()),
+            is_entry: false,
+            dependencies: Vec::new(),
+            exports: Default::default(),
+            transformed: None,
+            transform_time: None,
+            invalidations: 0,
+            transform_stages: Vec::new(),
+            provenance: Vec::new(),
+            watch_files: Vec::new(),
+            meta: Default::default(),
+            stamp: None,
+        }
+    }
+    
+    #[test]
+    fn test_scan_accepts() {
+        assert_eq!(HotAccepts::scan("import.meta.hot.accept();"), HotAccepts { itself: true, imports: vec![] });
+        assert!(HotAccepts::scan("import.meta.hot?.accept((mod) => render(mod.default));").itself);
+        let accepts = HotAccepts::scan("if (import.meta.hot) {\n  import.meta.hot.accept('./store', cb);\n  import.meta.hot.accept([\"./a\", './b'], cb);\n}");
+        assert_eq!(accepts, HotAccepts { itself: false, imports: vec!["./store".into(), "./a".into(), "./b".into()] });
+        assert_eq!(HotAccepts::scan("import.meta.hot.dispose(() => {});"), HotAccepts::default());
+        
+        let code = "import.meta.hot.accept('./store', cb);\nimport.meta.hot.accept([\"./a\", './missing'], cb);\nimport.meta.hot.accept();";
+        let rewritten = rewrite_accepted(code, |specifier| (specifier != "./missing").then(|| format!("/src/{}.js", &specifier[2..])));
+        assert_eq!(rewritten, "import.meta.hot.accept('/src/store.js', cb);\nimport.meta.hot.accept([\"/src/a.js\", './missing'], cb);\nimport.meta.hot.accept();");
+    }
+    
+    #[test]
+    fn test_hot_update_boundaries() {
+        let mut graph = ModuleGraph::new();
+        let main = graph.add_module(module("/app/main.js", "import './App.jsx';\nimport './store.js';\nimport './base.css';"));
+        let app = graph.add_module(module("/app/App.jsx", "import './Button.jsx';\nimport.meta.hot.accept();"));
+        let button = graph.add_module(module("/app/Button.jsx", "import './icon.js';"));
+        let icon = graph.add_module(module("/app/icon.js", ""));
+        let store = graph.add_module(module("/app/store.js", ""));
+        let css = graph.add_module(module("/app/base.css", "body {}"));
+        let consumer = graph.add_module(module("/app/consumer.js", "import './store.js';\nimport.meta.hot.accept('./store.js', update);"));
+        for (from, to) in [(main, app), (main, store), (main, css), (app, button), (button, icon), (consumer, store)] {
+            graph.add_dependency(from, to);
+        }
+        graph.set_resolved(consumer, "./store.js", store);
+        graph.get_module_mut(main).unwrap().is_entry = true;
+        
+        // Up to the self-accepting component, through what it imports
+        let update = hot_update(&graph, icon).unwrap();
+        assert_eq!(update.boundaries, [(app, app)]);
+        assert_eq!(update.stale, [icon, button, app]);
+        
+        assert_eq!(hot_update(&graph, css).unwrap().boundaries, [(css, css)]);
+        
+        // The store reaches the entry through main, which accepts nothing
+        assert_eq!(hot_update(&graph, store), None);
+        graph.clear_dependencies(main);
+        graph.add_dependency(main, app);
+        assert_eq!(hot_update(&graph, store).unwrap().boundaries, [(consumer, store)]);
+        assert_eq!(hot_update(&graph, main), None);
+    }
+}
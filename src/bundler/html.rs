@@ -0,0 +1,502 @@
+//! HTML entrypoints
+//!
+//! An entrypoint ending in `.html` is a page. Its local `<script src>` and
+//! `<link rel="stylesheet">` tags become the page's dependencies, and the
+//! page is emitted with those tags replaced by a single bundle script.
+//!
+//! Inline `<script>` and `<style>` blocks are bundled too, as virtual
+//! modules named after the page (`index.html?inline-0.js`), so they are
+//! transformed like any other module. Inline blocks and event handlers left
+//! in the emitted page are listed in `csp.json` with their
+//! Content-Security-Policy hashes.
+//!
+//! Icons, images and social preview images the page references are copied
+//! to the output with a content hash, unless their tag has a
+//! `data-component-ignore` attribute.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+static SCRIPT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<script\b([^>]*)>\s*</script\s*>"#).unwrap()
+});
+
+static INLINE_SCRIPT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<script\b([^>]*)>(.*?)</script\s*>"#).unwrap()
+});
+
+static INLINE_STYLE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<style\b([^>]*)>(.*?)</style\s*>"#).unwrap()
+});
+
+static LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<link\b([^>]*)>"#).unwrap()
+});
+
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<([a-zA-Z][\w-]*)(\s[^>]*)>"#).unwrap()
+});
+
+static ASSET_TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<(link|img|meta)\b([^>]*)>"#).unwrap()
+});
+
+/// Attribute that keeps a tag's URL, or an inline block, as written
+pub const IGNORE_ATTRIBUTE: &str = "data-component-ignore";
+
+/// Prefix of the specifiers pages use for their inline blocks
+const INLINE_SPECIFIER_PREFIX: &str = "component:inline-";
+
+/// Script `type`s bundled when inline; others (JSON-LD, import maps,
+/// templates) are data and stay in the page
+const SCRIPT_TYPES: &[&str] = &["", "module", "text/javascript", "application/javascript"];
+
+/// `rel` values of links to icons
+const ICON_RELS: &[&str] = &["icon", "apple-touch-icon", "apple-touch-icon-precomposed", "mask-icon"];
+
+/// `<meta property>` and `<meta name>` values holding preview images
+const IMAGE_METAS: &[&str] = &["og:image", "og:image:url", "og:image:secure_url", "twitter:image"];
+
+static HANDLER_ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\son[a-z]+\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+
+static URL_ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(\s(src|href|poster)\s*=\s*)(?:"(/[^"]*)"|'(/[^']*)')"#).unwrap()
+});
+
+/// A tag in an HTML page that references a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlReference {
+    /// Referenced URL, as written in the page
+    pub specifier: String,
+    
+    /// Byte range of the whole tag
+    pub range: Range<usize>,
+}
+
+/// Find local scripts and stylesheets in document order
+///
+/// Remote URLs (`https://`, protocol-relative, `data:`) are left alone.
+pub fn extract_references(html: &str) -> Vec<HtmlReference> {
+    let mut references = Vec::new();
+    
+    for cap in SCRIPT_REGEX.captures_iter(html) {
+        if let Some(src) = attribute(&cap[1], "src") {
+            references.push((src, cap.get(0).unwrap().range()));
+        }
+    }
+    for block in extract_inline_blocks(html) {
+        references.push((inline_specifier(&block), block.range));
+    }
+    for cap in LINK_REGEX.captures_iter(html) {
+        let is_stylesheet = attribute(&cap[1], "rel")
+            .is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("stylesheet")));
+        if let (true, Some(href)) = (is_stylesheet, attribute(&cap[1], "href")) {
+            references.push((href, cap.get(0).unwrap().range()));
+        }
+    }
+    
+    let mut references: Vec<HtmlReference> = references
+        .into_iter()
+        .filter(|(specifier, _)| is_local(specifier))
+        .map(|(specifier, range)| HtmlReference { specifier, range })
+        .collect();
+    references.sort_by_key(|r| r.range.start);
+    references
+}
+
+/// An inline `<script>` or `<style>` block bundled as a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineBlock {
+    /// Position among the page's bundled blocks
+    pub index: usize,
+    
+    /// `js` for scripts, `css` for styles
+    pub extension: &'static str,
+    
+    /// Contents of the block
+    pub code: String,
+    
+    /// Byte range of the whole element
+    pub range: Range<usize>,
+}
+
+/// Find the inline scripts and styles to bundle, in document order
+///
+/// Scripts with a `src`, data scripts, styles for a `media` query, and
+/// blocks with `data-component-ignore` stay in the page.
+pub fn extract_inline_blocks(html: &str) -> Vec<InlineBlock> {
+    let mut blocks = Vec::new();
+    
+    for cap in INLINE_SCRIPT_REGEX.captures_iter(html) {
+        let kind = attribute(&cap[1], "type").unwrap_or_default().to_ascii_lowercase();
+        if attribute(&cap[1], "src").is_none() && SCRIPT_TYPES.contains(&kind.as_str()) {
+            blocks.push(("js", cap));
+        }
+    }
+    for cap in INLINE_STYLE_REGEX.captures_iter(html) {
+        let kind = attribute(&cap[1], "type").unwrap_or_default().to_ascii_lowercase();
+        if attribute(&cap[1], "media").is_none() && (kind.is_empty() || kind == "text/css") {
+            blocks.push(("css", cap));
+        }
+    }
+    
+    blocks.retain(|(_, cap)| !cap[2].trim().is_empty() && !has_attribute(&cap[1], IGNORE_ATTRIBUTE));
+    blocks.sort_by_key(|(_, cap)| cap.get(0).unwrap().start());
+    blocks
+        .into_iter()
+        .enumerate()
+        .map(|(index, (extension, cap))| InlineBlock {
+            index,
+            extension,
+            code: cap[2].to_string(),
+            range: cap.get(0).unwrap().range(),
+        })
+        .collect()
+}
+
+/// Specifier a page uses for one of its inline blocks
+fn inline_specifier(block: &InlineBlock) -> String {
+    format!("{}{}.{}", INLINE_SPECIFIER_PREFIX, block.index, block.extension)
+}
+
+/// Path of the virtual module for an inline block specifier of `page`
+pub fn inline_module_path(specifier: &str, page: &Path) -> Option<PathBuf> {
+    let block = specifier.strip_prefix(INLINE_SPECIFIER_PREFIX)?;
+    Some(PathBuf::from(format!("{}?inline-{}", page.display(), block)))
+}
+
+/// Page and block index of an inline block's virtual module
+pub fn inline_module_page(path: &Path) -> Option<(PathBuf, usize)> {
+    let path = path.to_str()?;
+    let (page, block) = path.rsplit_once("?inline-")?;
+    let index = block.split_once('.')?.0.parse().ok()?;
+    Some((PathBuf::from(page), index))
+}
+
+/// Content-Security-Policy hashes of the inline blocks in a page
+#[derive(Debug, Default, Serialize)]
+pub struct CspHashes {
+    #[serde(rename = "script-src", skip_serializing_if = "Vec::is_empty")]
+    pub script_src: Vec<String>,
+    
+    #[serde(rename = "style-src", skip_serializing_if = "Vec::is_empty")]
+    pub style_src: Vec<String>,
+}
+
+impl CspHashes {
+    /// Hash every non-empty inline script and style left in an emitted page,
+    /// and its event handler attributes
+    ///
+    /// Data scripts are left out, as CSP doesn't check them. Handlers only
+    /// match their hashes under `'unsafe-hashes'`, which is added with them.
+    pub fn of_page(html: &str) -> Self {
+        let hash = |source: &str| format!("'sha256-{}'", base64::engine::general_purpose::STANDARD.encode(Sha256::digest(source.as_bytes())));
+        let mut script_src: Vec<String> = INLINE_SCRIPT_REGEX
+            .captures_iter(html)
+            .filter(|cap| !cap[2].trim().is_empty())
+            .filter(|cap| {
+                let kind = attribute(&cap[1], "type").unwrap_or_default().to_ascii_lowercase();
+                attribute(&cap[1], "src").is_none() && SCRIPT_TYPES.contains(&kind.as_str())
+            })
+            .map(|cap| hash(&cap[2]))
+            .collect();
+        let mut handlers: Vec<String> = TAG_REGEX
+            .captures_iter(html)
+            .flat_map(|tag| {
+                HANDLER_ATTRIBUTE_REGEX
+                    .captures_iter(tag.get(2).unwrap().as_str())
+                    .filter_map(|cap| cap.get(1).or_else(|| cap.get(2)).or_else(|| cap.get(3)))
+                    .map(|value| hash(&unescape_attribute(value.as_str())))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        handlers.sort_unstable();
+        handlers.dedup();
+        if !handlers.is_empty() {
+            script_src.push("'unsafe-hashes'".to_string());
+            script_src.extend(handlers);
+        }
+        Self {
+            script_src,
+            style_src: INLINE_STYLE_REGEX
+                .captures_iter(html)
+                .filter(|cap| !cap[2].trim().is_empty())
+                .map(|cap| hash(&cap[2]))
+                .collect(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.script_src.is_empty() && self.style_src.is_empty()
+    }
+}
+
+/// Find local icons, images, and preview images in document order
+///
+/// Returns the range of each URL in the page, so it can be replaced by the
+/// URL of the copied file.
+pub fn extract_assets(html: &str) -> Vec<HtmlReference> {
+    let mut assets = Vec::new();
+    
+    for cap in ASSET_TAG_REGEX.captures_iter(html) {
+        let attributes = cap.get(2).unwrap();
+        if has_attribute(attributes.as_str(), IGNORE_ATTRIBUTE) {
+            continue;
+        }
+        let is_one_of = |name: &str, values: &[&str]| {
+            attribute(attributes.as_str(), name).is_some_and(|value| {
+                value.split_whitespace().any(|word| values.iter().any(|v| word.eq_ignore_ascii_case(v)))
+            })
+        };
+        let url_attribute = match cap[1].to_ascii_lowercase().as_str() {
+            "link" if is_one_of("rel", ICON_RELS) => "href",
+            "img" => "src",
+            "meta" if is_one_of("property", IMAGE_METAS) || is_one_of("name", IMAGE_METAS) => "content",
+            _ => continue,
+        };
+        let Some(range) = attribute_range(attributes.as_str(), url_attribute) else {
+            continue;
+        };
+        let specifier = &attributes.as_str()[range.clone()];
+        if is_local(specifier) {
+            let start = attributes.start() + range.start;
+            assets.push(HtmlReference {
+                specifier: specifier.to_string(),
+                range: start..start + specifier.len(),
+            });
+        }
+    }
+    
+    assets
+}
+
+/// Replace ranges of a page, such as asset URLs, with new text
+pub fn replace_ranges(html: &str, replacements: &[(Range<usize>, String)]) -> String {
+    let mut page = String::with_capacity(html.len());
+    let mut offset = 0;
+    for (range, text) in replacements {
+        page.push_str(&html[offset..range.start]);
+        page.push_str(text);
+        offset = range.end;
+    }
+    page.push_str(&html[offset..]);
+    page
+}
+
+/// Value of a quoted attribute in a tag's attribute list
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    attribute_range(attributes, name).map(|range| attributes[range].to_string())
+}
+
+/// Byte range of an attribute's value in a tag's attribute list
+fn attribute_range(attributes: &str, name: &str) -> Option<Range<usize>> {
+    let pattern = format!(r#"(?i)(?:^|\s){}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#, regex::escape(name));
+    let cap = Regex::new(&pattern).ok()?.captures(attributes)?;
+    cap.get(1).or_else(|| cap.get(2)).or_else(|| cap.get(3)).map(|m| m.range())
+}
+
+/// Whether a tag's attribute list has an attribute, with or without a value
+fn has_attribute(attributes: &str, name: &str) -> bool {
+    let pattern = format!(r#"(?i)(?:^|\s){}(?:[\s=/]|$)"#, regex::escape(name));
+    Regex::new(&pattern).is_ok_and(|regex| regex.is_match(attributes))
+}
+
+fn is_local(url: &str) -> bool {
+    !url.is_empty() && !url.contains("://") && !url.starts_with("//") && !url.starts_with("data:")
+}
+
+/// Remove the referencing tags and insert markup at the end of `<head>` and `<body>`
+///
+/// Without those elements, head markup goes first and body markup last.
+pub fn rewrite(html: &str, references: &[HtmlReference], head: &str, body: &str) -> String {
+    let mut page = String::with_capacity(html.len());
+    let mut offset = 0;
+    for reference in references {
+        page.push_str(&html[offset..reference.range.start]);
+        offset = reference.range.end;
+    }
+    page.push_str(&html[offset..]);
+    
+    if !head.is_empty() {
+        match find_tag(&page, "</head") {
+            Some(at) => page.insert_str(at, &format!("{}\n", head)),
+            None => page.insert_str(0, &format!("{}\n", head)),
+        }
+    }
+    if !body.is_empty() {
+        match find_tag(&page, "</body") {
+            Some(at) => page.insert_str(at, &format!("{}\n", body)),
+            None => page.push_str(&format!("\n{}\n", body)),
+        }
+    }
+    page
+}
+
+fn find_tag(html: &str, tag: &str) -> Option<usize> {
+    html.to_ascii_lowercase().rfind(tag)
+}
+
+/// Prefix root-relative asset URLs with `prefix`, such as `/subapp` or a CDN URL
+///
+/// Covers `src` and `poster` attributes and `<link href>`; page links
+/// (`<a href>`) are left alone, as are URLs already under the prefix.
+pub fn rebase(html: &str, prefix: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        return html.to_string();
+    }
+    let nested = format!("{}/", prefix);
+    
+    TAG_REGEX.replace_all(html, |tag: &regex::Captures| {
+        let is_link = tag[1].eq_ignore_ascii_case("link");
+        let attributes = URL_ATTRIBUTE_REGEX.replace_all(&tag[2], |attr: &regex::Captures| {
+            let (url, quote) = match attr.get(3) {
+                Some(url) => (url.as_str(), '"'),
+                None => (&attr[4], '\''),
+            };
+            let is_asset = is_link || !attr[2].eq_ignore_ascii_case("href");
+            if !is_asset || url.starts_with("//") || url == prefix || url.starts_with(&nested) {
+                return attr[0].to_string();
+            }
+            format!("{}{quote}{}{}{quote}", &attr[1], prefix, url)
+        });
+        format!("<{}{}>", &tag[1], attributes)
+    }).into_owned()
+}
+
+/// Inline critical CSS and start fetching the full stylesheet early
+///
+/// The stylesheet itself is linked at the end of the body, see
+/// [`stylesheet_tag`], so it applies without an inline `onload` handler a
+/// Content-Security-Policy would block.
+pub fn critical_css_tags(critical: &str, stylesheet_url: &str) -> String {
+    format!(
+        "<style>{}</style>\n<link rel=\"preload\" href=\"{}\" as=\"style\">",
+        critical.replace("</style", "<\\/style"),
+        escape_attribute(stylesheet_url),
+    )
+}
+
+/// Stylesheet link for the end of a page's body, which doesn't hold up
+/// rendering of the content before it
+pub fn stylesheet_tag(url: &str) -> String {
+    format!("<link rel=\"stylesheet\" href=\"{}\">", escape_attribute(url))
+}
+
+/// Preload links for the fonts at `urls`
+pub fn font_preload_tags(urls: &[String]) -> String {
+    urls.iter()
+        .map(|url| {
+            let mime = super::fonts::mime_type(url).unwrap_or("font/woff2");
+            format!("<link rel=\"preload\" href=\"{}\" as=\"font\" type=\"{}\" crossorigin>", escape_attribute(url), mime)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Script tag for a page's bundle
+pub fn script_tag(url: &str) -> String {
+    format!("<script defer src=\"{}\"></script>", escape_attribute(url))
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+/// Decode the character references escaped attribute values commonly hold
+fn unescape_attribute(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Escape text for HTML content or a quoted attribute value
+#[cfg(feature = "native")]
+pub fn escape(value: &str) -> String {
+    escape_attribute(value).replace('<', "&lt;").replace('>', "&gt;").replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_extract_and_rewrite() {
+        let html = r#"<html><head>
+<link rel="stylesheet" href="./style.css">
+<link rel="icon" href="/favicon.ico">
+<script src="https://cdn.example.com/lib.js"></script>
+</head><body>
+<script type="module" src="/src/main.js"></script>
+</body></html>"#;
+        
+        let references = extract_references(html);
+        let specifiers: Vec<&str> = references.iter().map(|r| r.specifier.as_str()).collect();
+        assert_eq!(specifiers, ["./style.css", "/src/main.js"]);
+        
+        let page = rewrite(html, &references, "<style>a{}</style>", &script_tag("/main.js"));
+        assert!(!page.contains("style.css") && !page.contains("/src/main.js"));
+        assert!(page.contains("cdn.example.com") && page.contains("favicon.ico"));
+        assert!(page.contains("<style>a{}</style>\n</head>"));
+        assert!(page.contains("<script defer src=\"/main.js\"></script>\n</body>"));
+        
+        let path = inline_module_path("component:inline-2.css", Path::new("/app/index.html")).unwrap();
+        assert_eq!(path, PathBuf::from("/app/index.html?inline-2.css"));
+        assert_eq!(inline_module_page(&path), Some((PathBuf::from("/app/index.html"), 2)));
+        assert_eq!(inline_module_path("./style.css", Path::new("/app/index.html")), None);
+    }
+    
+    #[test]
+    fn test_extract_assets() {
+        let html = r#"<link rel="shortcut icon" href="/favicon.ico">
+<link rel="stylesheet" href="./style.css">
+<meta property="og:image" content="./preview.png"><meta name="description" content="./not-an-image">
+<img alt="logo" src='./logo.svg#mark'> <img src="https://example.com/a.png">
+<img data-component-ignore src="/kept.png">"#;
+        
+        let assets = extract_assets(html);
+        let specifiers: Vec<&str> = assets.iter().map(|a| a.specifier.as_str()).collect();
+        assert_eq!(specifiers, ["/favicon.ico", "./preview.png", "./logo.svg#mark"]);
+        assert_eq!(&html[assets[1].range.clone()], "./preview.png");
+        
+        let replacements: Vec<_> = assets.iter().map(|a| (a.range.clone(), "/assets/x.png".to_string())).collect();
+        let page = replace_ranges(html, &replacements);
+        assert!(page.contains(r#"<meta property="og:image" content="/assets/x.png">"#));
+        assert!(page.contains("<img alt=\"logo\" src='/assets/x.png'>"));
+        assert!(page.contains(r#"src="/kept.png""#));
+    }
+    
+    #[test]
+    fn test_rebase() {
+        let html = r#"<link rel="icon" href="/favicon.ico">
+<img src='/logo.png' alt="/not-a-url"> <a href="/about">About</a>
+<script src="/subapp/main.js"></script><script src="//cdn.example.com/x.js"></script>
+<video poster="/poster.jpg" src="./local.mp4"></video>"#;
+        
+        let page = rebase(html, "/subapp/");
+        assert!(page.contains(r#"<link rel="icon" href="/subapp/favicon.ico">"#));
+        assert!(page.contains("<img src='/subapp/logo.png' alt=\"/not-a-url\">"));
+        assert!(page.contains(r#"<a href="/about">"#));
+        assert!(page.contains(r#"src="/subapp/main.js""#) && page.contains(r#"src="//cdn.example.com/x.js""#));
+        assert!(page.contains(r#"poster="/subapp/poster.jpg" src="./local.mp4""#));
+        assert_eq!(rebase(html, "/"), html);
+        
+        assert_eq!(crate::config::normalize_public_url("subapp"), "/subapp/");
+        let output = crate::config::OutputConfig {
+            public_url: "https://cdn.example.com/app/".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(output.base_path(), "/app/");
+    }
+}
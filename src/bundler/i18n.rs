@@ -0,0 +1,258 @@
+//! Internationalization
+//!
+//! Calls to the configured message functions (`t('key')`) are found in each
+//! chunk's modules, and only those keys are emitted into one message file
+//! per locale. Entry chunks get a small runtime, `window.__component_i18n__`,
+//! that loads a locale's messages on demand; the default locale can be
+//! inlined so the first render needs no extra request.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::config::I18nConfig;
+use crate::vfs::FileSystem;
+
+/// Placeholder for the locale in `I18nConfig::messages`
+const LOCALE_PLACEHOLDER: &str = "{locale}";
+
+/// Find the string keys passed to message functions
+///
+/// Only literal keys are recognized; `t(key)` or template literals with
+/// substitutions can't be resolved at build time and are skipped.
+pub fn extract_keys(source: &str, functions: &[String], keys: &mut BTreeSet<String>) {
+    for function in functions {
+        let mut offset = 0;
+        while let Some(found) = source[offset..].find(function.as_str()) {
+            let start = offset + found;
+            offset = start + function.len();
+            
+            // Require an identifier boundary so `t` doesn't match `set(`
+            let preceding = source[..start].chars().next_back();
+            if preceding.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '.') {
+                continue;
+            }
+            
+            let rest = source[offset..].trim_start();
+            let Some(rest) = rest.strip_prefix('(') else {
+                continue;
+            };
+            if let Some(key) = string_literal(rest.trim_start()) {
+                keys.insert(key);
+            }
+        }
+    }
+}
+
+/// Parse a leading string literal
+fn string_literal(source: &str) -> Option<String> {
+    let quote = source.chars().next().filter(|c| matches!(c, '\'' | '"' | '`'))?;
+    let mut value = String::new();
+    let mut chars = source[1..].chars();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            '$' if quote == '`' && chars.as_str().starts_with('{') => return None,
+            c if c == quote => return Some(value),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Messages for every configured locale
+#[derive(Debug)]
+pub struct Catalogs {
+    config: I18nConfig,
+    messages: BTreeMap<String, BTreeMap<String, String>>,
+    warnings: Mutex<Vec<String>>,
+}
+
+impl Catalogs {
+    /// Load each locale's catalog; nested objects are flattened to dotted keys
+    pub fn load(config: &I18nConfig, root: &Path, fs: &dyn FileSystem) -> Result<Self> {
+        let mut messages = BTreeMap::new();
+        let mut warnings = Vec::new();
+        
+        for locale in &config.locales {
+            let path = root.join(config.messages.replace(LOCALE_PLACEHOLDER, locale));
+            let catalog = if fs.is_file(&path) {
+                let content = fs.read_to_string(&path)?;
+                let json: Value = serde_json::from_str(&content)
+                    .with_context(|| format!("Invalid message catalog: {}", path.display()))?;
+                let mut catalog = BTreeMap::new();
+                flatten("", &json, &mut catalog);
+                catalog
+            } else {
+                warnings.push(format!("Message catalog for locale '{}' not found: {}", locale, path.display()));
+                BTreeMap::new()
+            };
+            messages.insert(locale.clone(), catalog);
+        }
+        
+        Ok(Self { config: config.clone(), messages, warnings: Mutex::new(warnings) })
+    }
+    
+    /// Messages for `keys` in `locale`, falling back to the default locale
+    pub fn select(&self, locale: &str, keys: &BTreeSet<String>) -> BTreeMap<String, String> {
+        let default_locale = self.config.default_locale();
+        let catalog = &self.messages[locale];
+        let fallback = &self.messages[default_locale];
+        
+        let mut selected = BTreeMap::new();
+        let mut fell_back = Vec::new();
+        let mut missing = Vec::new();
+        for key in keys {
+            if let Some(message) = catalog.get(key) {
+                selected.insert(key.clone(), message.clone());
+            } else if let Some(message) = fallback.get(key) {
+                selected.insert(key.clone(), message.clone());
+                fell_back.push(key.as_str());
+            } else {
+                missing.push(key.as_str());
+            }
+        }
+        
+        let mut warnings = self.warnings.lock();
+        if !fell_back.is_empty() {
+            warnings.push(format!("Locale '{}' is missing {}, using '{}'", locale, fell_back.join(", "), default_locale));
+        }
+        if !missing.is_empty() {
+            warnings.push(format!("No locale has messages for {}", missing.join(", ")));
+        }
+        selected
+    }
+    
+    /// Runtime for an entry chunk
+    ///
+    /// `files` maps each locale to its message file, loaded from the runtime
+    /// public path.
+    pub fn runtime(&self, files: &BTreeMap<String, String>, inline: Option<&BTreeMap<String, String>>) -> Result<String> {
+        let default_locale = serde_json::to_string(self.config.default_locale())?;
+        let files = serde_json::to_string(files)?;
+        let messages = match inline {
+            Some(messages) => serde_json::to_string(messages)?,
+            None => "null".to_string(),
+        };
+        
+        Ok(I18N_RUNTIME
+            .replace("__DEFAULT_LOCALE__", &default_locale)
+            .replace("__FILES__", &files)
+            .replace("__MESSAGES__", &messages))
+    }
+    
+    /// Missing catalogs and messages found so far
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock())
+    }
+    
+    /// Configured locales
+    pub fn locales(&self) -> &[String] {
+        &self.config.locales
+    }
+    
+    /// Whether the default locale is inlined into entry chunks
+    pub fn inline_default(&self) -> bool {
+        self.config.inline_default
+    }
+    
+    /// Message functions to extract keys from
+    pub fn functions(&self) -> &[String] {
+        &self.config.functions
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&key, value, out);
+            }
+        }
+        Value::String(message) => {
+            out.insert(prefix.to_string(), message.clone());
+        }
+        other => {
+            out.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+const I18N_RUNTIME: &str = r#"
+// Component i18n runtime
+(function() {
+  var files = __FILES__;
+  var inlined = __MESSAGES__;
+  var i18n = {
+    locale: __DEFAULT_LOCALE__,
+    messages: inlined || {},
+    locales: Object.keys(files),
+    load: function(locale) {
+      if (!files[locale]) {
+        return Promise.reject(new Error("Unknown locale: " + locale));
+      }
+      return fetch(window.__component_asset_url__(files[locale]))
+        .then(function(res) { return res.json(); })
+        .then(function(messages) {
+          i18n.locale = locale;
+          i18n.messages = messages;
+          return messages;
+        });
+    },
+    t: function(key, params) {
+      var message = Object.prototype.hasOwnProperty.call(i18n.messages, key) ? i18n.messages[key] : key;
+      return params ? message.replace(/\{(\w+)\}/g, function(m, name) {
+        return name in params ? params[name] : m;
+      }) : message;
+    }
+  };
+  i18n.ready = inlined ? Promise.resolve(inlined) : i18n.load(i18n.locale);
+  window.__component_i18n__ = i18n;
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_extract_keys() {
+        let source = r#"
+            t('home.title');
+            i18n.t("ignored.member");
+            console.log(t( "greeting" ), format(`x`), t(`count.${n}`), t(dynamic));
+            reset('not.a.key');
+            t('it\'s');
+        "#;
+        let mut keys = BTreeSet::new();
+        extract_keys(source, &["t".to_string()], &mut keys);
+        
+        let keys: Vec<_> = keys.into_iter().collect();
+        assert_eq!(keys, ["greeting", "home.title", "it's"]);
+    }
+    
+    #[test]
+    fn test_select_with_fallback() {
+        let fs = MemoryFs::new();
+        fs.write("/p/locales/en.json", r#"{"home": {"title": "Home"}, "bye": "Bye"}"#);
+        fs.write("/p/locales/fr.json", r#"{"home": {"title": "Accueil"}}"#);
+        
+        let config = I18nConfig {
+            locales: vec!["en".to_string(), "fr".to_string()],
+            ..I18nConfig::default()
+        };
+        let catalogs = Catalogs::load(&config, Path::new("/p"), &fs).unwrap();
+        let keys: BTreeSet<String> = ["home.title".to_string(), "bye".to_string()].into();
+        
+        let fr = catalogs.select("fr", &keys);
+        assert_eq!(fr["home.title"], "Accueil");
+        assert_eq!(fr["bye"], "Bye");
+        assert_eq!(catalogs.select("en", &keys).len(), 2);
+    }
+}
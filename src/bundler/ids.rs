@@ -0,0 +1,119 @@
+//! Module ids in bundles
+//!
+//! Bundles register and require modules by id. `output.ids` picks how ids
+//! are made:
+//!
+//! - `named` - the path relative to the project root, as in `src/main.js`,
+//!   for readable output
+//! - `hashed` - a short hash of that path, stable across builds and machines
+//! - `numeric` - the module's index in path order; smallest, but adding a
+//!   module renumbers the ones after it
+//!
+//! Unset, non-minified builds use named ids and minified builds hashed ones.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{hash_content, path_to_module_id, relative_path};
+
+/// Length of hashed ids, grown when two modules would share one
+const HASHED_ID_LENGTH: usize = 6;
+
+/// Module id strategies selectable with `output.ids`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleIds {
+    /// Path relative to the project root
+    Named,
+    
+    /// Short hash of the relative path
+    Hashed,
+    
+    /// Index in path order
+    Numeric,
+}
+
+/// Ids of a build's modules, by canonical path
+#[derive(Debug, Clone)]
+pub struct ModuleIdMap {
+    strategy: ModuleIds,
+    ids: HashMap<PathBuf, String>,
+}
+
+impl ModuleIdMap {
+    /// Assign ids to every module of a build
+    pub fn new<'a>(strategy: ModuleIds, root: &Path, paths: impl IntoIterator<Item = &'a Path>) -> Self {
+        let mut named: Vec<(String, PathBuf)> = paths
+            .into_iter()
+            .map(|path| {
+                let relative = relative_path(root, path).unwrap_or_else(|| path.display().to_string());
+                (path_to_module_id(Path::new(&relative)), path.to_path_buf())
+            })
+            .collect();
+        named.sort();
+        named.dedup();
+        
+        let mut taken = HashSet::new();
+        let ids = named
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, path))| {
+                let id = match strategy {
+                    ModuleIds::Named => name,
+                    ModuleIds::Numeric => index.to_string(),
+                    ModuleIds::Hashed => {
+                        let hash = hash_content(name.as_bytes());
+                        (HASHED_ID_LENGTH..=hash.len())
+                            .map(|length| hash[..length].to_string())
+                            .find(|id| !taken.contains(id))
+                            .unwrap_or(name)
+                    }
+                };
+                taken.insert(id.clone());
+                (path, id)
+            })
+            .collect();
+        Self { strategy, ids }
+    }
+    
+    /// The id of a module, as used for object keys; modules outside the
+    /// build fall back to their path
+    pub fn key(&self, path: &Path) -> String {
+        self.ids.get(path).cloned().unwrap_or_else(|| path_to_module_id(path))
+    }
+    
+    /// The id of a module as a JavaScript literal
+    pub fn literal(&self, path: &Path) -> String {
+        let key = self.key(path);
+        match self.strategy {
+            ModuleIds::Numeric if self.ids.contains_key(path) => key,
+            _ => serde_json::to_string(&key).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_module_ids() {
+        let root = Path::new("/app");
+        let paths = [Path::new("/app/src/main.js"), Path::new("/app/src/a.js")];
+        
+        let named = ModuleIdMap::new(ModuleIds::Named, root, paths);
+        assert_eq!(named.literal(Path::new("/app/src/main.js")), "\"src/main.js\"");
+        
+        let numeric = ModuleIdMap::new(ModuleIds::Numeric, root, paths);
+        assert_eq!(numeric.literal(Path::new("/app/src/a.js")), "0");
+        assert_eq!(numeric.key(Path::new("/app/src/main.js")), "1");
+        
+        let hashed = ModuleIdMap::new(ModuleIds::Hashed, root, paths);
+        let id = hashed.key(Path::new("/app/src/main.js"));
+        assert_eq!(id.len(), HASHED_ID_LENGTH);
+        assert_eq!(id, ModuleIdMap::new(ModuleIds::Hashed, root, [Path::new("/app/src/main.js")]).key(paths[0]));
+        assert_ne!(id, hashed.key(Path::new("/app/src/a.js")));
+    }
+}
@@ -0,0 +1,362 @@
+//! Responsive images
+//!
+//! `import hero from './hero.jpg?srcset=480,800,1200'` imports an image as
+//! an object describing copies of it resized to each width, for `<img>` and
+//! `<picture>`:
+//!
+//! - `src`, the widest copy, and `srcset`, every copy as `url 480w, ...`
+//! - `width` and `height` of the widest copy, and its content `type`
+//! - `sources`, a `{ type, srcset }` for each other format asked for with
+//!   `&format=avif,webp`, in that order, to list before the `<img>` in a
+//!   `<picture>`
+//!
+//! Copies are never wider than the image itself. The generated module names
+//! each one as `new URL('./hero.jpg?width=480&format=webp', import.meta.url)`,
+//! which builds emit as a resized asset and the dev server resizes when it's
+//! requested.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use image::codecs::avif::AvifEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use serde_json::json;
+
+/// AVIF encoder speed, from 1 (smallest files) to 10 (fastest); copies are
+/// encoded on every build without a cache, so favor speed
+const AVIF_SPEED: u8 = 8;
+
+/// AVIF encoder quality, from 1 to 100
+const AVIF_QUALITY: u8 = 70;
+
+/// Formats images can be resized to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Webp,
+    Avif,
+}
+
+impl ImageFormat {
+    /// Format of an image file, by its extension
+    fn for_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        match extension.as_str() {
+            "jpg" | "jpeg" => Ok(Self::Jpeg),
+            "png" => Ok(Self::Png),
+            "webp" => Ok(Self::Webp),
+            _ => bail!("Only JPEG, PNG and WebP images can be resized: {}", path.display()),
+        }
+    }
+    
+    /// Format named in a `format=` parameter
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "jpg" | "jpeg" => Ok(Self::Jpeg),
+            "png" => Ok(Self::Png),
+            "webp" => Ok(Self::Webp),
+            "avif" => Ok(Self::Avif),
+            _ => bail!("Unknown image format '{}'; use avif, webp, jpeg or png", name),
+        }
+    }
+    
+    /// Name used in queries and as the file extension of copies
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::Avif => "avif",
+        }
+    }
+    
+    /// Content type
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+            Self::Avif => "image/avif",
+        }
+    }
+    
+    fn encoding(self) -> image::ImageFormat {
+        match self {
+            Self::Jpeg => image::ImageFormat::Jpeg,
+            Self::Png => image::ImageFormat::Png,
+            Self::Webp => image::ImageFormat::WebP,
+            Self::Avif => image::ImageFormat::Avif,
+        }
+    }
+}
+
+/// What a `?srcset=` import asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Srcset {
+    /// Widths of the copies, ascending
+    pub widths: Vec<u32>,
+    /// Formats to offer besides the image's own, as `sources`
+    pub formats: Vec<ImageFormat>,
+}
+
+impl Srcset {
+    /// Parse a query such as `srcset=480,800&format=webp`; `None` when it
+    /// has no `srcset`
+    pub fn parse(query: &str) -> Option<Result<Self>> {
+        let value = param(query, "srcset")?;
+        Some(Self::parse_params(&value, param(query, "format").as_deref()))
+    }
+    
+    fn parse_params(widths: &str, formats: Option<&str>) -> Result<Self> {
+        let mut widths = widths
+            .split(',')
+            .map(|width| match width.trim().parse::<u32>() {
+                Ok(width) if width > 0 => Ok(width),
+                _ => bail!("Invalid srcset width '{}'; list widths in pixels, as in ?srcset=480,800", width),
+            })
+            .collect::<Result<Vec<u32>>>()?;
+        widths.sort_unstable();
+        widths.dedup();
+        
+        let formats = formats
+            .into_iter()
+            .flat_map(|formats| formats.split(','))
+            .map(|name| ImageFormat::parse(name.trim()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { widths, formats })
+    }
+}
+
+/// One resized copy, named by `?width=` and an optional `&format=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Variant {
+    pub width: u32,
+    pub format: Option<ImageFormat>,
+}
+
+impl Variant {
+    /// Parse a query such as `width=480&format=webp`; `None` when it isn't
+    /// one
+    pub fn parse(query: &str) -> Option<Self> {
+        let width = param(query, "width")?.parse().ok().filter(|&width| width > 0)?;
+        let format = match param(query, "format") {
+            Some(name) => Some(ImageFormat::parse(&name).ok()?),
+            None => None,
+        };
+        Some(Self { width, format })
+    }
+    
+    /// The query naming this copy
+    pub fn query(&self) -> String {
+        match self.format {
+            Some(format) => format!("width={}&format={}", self.width, format.name()),
+            None => format!("width={}", self.width),
+        }
+    }
+    
+    /// Format of this copy of `image`
+    pub fn format_of(&self, image: &Path) -> Result<ImageFormat> {
+        self.format.map_or_else(|| ImageFormat::for_path(image), Ok)
+    }
+}
+
+/// Value of a query parameter
+fn param(query: &str, name: &str) -> Option<String> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Path a file appears at with a query, as `?srcset=` modules and resized
+/// copies are known to the graph and to `assets`
+pub fn with_query(path: &Path, query: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push("?");
+    path.push(query);
+    PathBuf::from(path)
+}
+
+/// Image and query of a path made by [`with_query`]
+fn split_query(path: &Path) -> Option<(PathBuf, &str)> {
+    let (image, query) = path.to_str()?.rsplit_once('?')?;
+    Some((PathBuf::from(image), query))
+}
+
+/// Image and query of a `?srcset=` module's path
+pub fn srcset_module(path: &Path) -> Option<(PathBuf, &str)> {
+    split_query(path).filter(|(_, query)| param(query, "srcset").is_some())
+}
+
+/// Image and copy a resized copy's path names
+pub fn variant(path: &Path) -> Option<(PathBuf, Variant)> {
+    let (image, query) = split_query(path)?;
+    Some((image, Variant::parse(query)?))
+}
+
+/// Source of the module a `?srcset=` import of `image` loads
+pub fn render_srcset_module(contents: &[u8], image: &Path, srcset: &Srcset) -> Result<String> {
+    let format = ImageFormat::for_path(image)?;
+    let (width, height) = ImageReader::new(Cursor::new(contents))
+        .with_guessed_format()?
+        .into_dimensions()
+        .with_context(|| format!("Failed to read image {}", image.display()))?;
+    
+    let mut widths: Vec<u32> = srcset.widths.iter().map(|&w| w.min(width)).collect();
+    widths.dedup();
+    let widest = widths.last().copied().unwrap_or(width);
+    let file_name = image.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let urls = |format: Option<ImageFormat>| -> String {
+        let urls: Vec<String> = widths
+            .iter()
+            .map(|&width| {
+                let specifier = format!("./{}?{}", file_name, Variant { width, format }.query());
+                format!("  new URL({}, import.meta.url).href", json!(specifier))
+            })
+            .collect();
+        format!("[\n{}\n]", urls.join(",\n"))
+    };
+    let sources: Vec<String> = srcset.formats
+        .iter()
+        .filter(|&&other| other != format)
+        .map(|&other| format!("    {{ type: {}, srcset: srcset({}) }}", json!(other.mime()), urls(Some(other)).replace('\n', "\n    ")))
+        .collect();
+    
+    Ok(format!(
+        r#"var widths = {};
+
+function srcset(urls) {{
+  return urls.map(function (url, index) {{ return url + " " + widths[index] + "w"; }}).join(", ");
+}}
+
+var images = {};
+
+export default {{
+  src: images[images.length - 1],
+  srcset: srcset(images),
+  width: {},
+  height: {},
+  type: {},
+  sources: [{}]
+}};
+"#,
+        json!(widths),
+        urls(None),
+        widest,
+        scaled_height(width, height, widest),
+        json!(format.mime()),
+        if sources.is_empty() { String::new() } else { format!("\n{}\n  ", sources.join(",\n")) }
+    ))
+}
+
+/// Height of an image `width` by `height` scaled to `to` pixels wide
+fn scaled_height(width: u32, height: u32, to: u32) -> u32 {
+    ((u64::from(height) * u64::from(to) + u64::from(width) / 2) / u64::from(width.max(1))).max(1) as u32
+}
+
+/// Resize an image to a copy's width, in the copy's format
+pub fn resize(contents: &[u8], image: &Path, variant: &Variant) -> Result<Vec<u8>> {
+    let format = variant.format_of(image)?;
+    let decoded = image::load_from_memory(contents)
+        .with_context(|| format!("Failed to read image {}", image.display()))?;
+    let width = variant.width.min(decoded.width());
+    let height = scaled_height(decoded.width(), decoded.height(), width);
+    let resized = if width == decoded.width() {
+        decoded
+    } else {
+        decoded.resize_exact(width, height, FilterType::Lanczos3)
+    };
+    
+    // JPEG has no alpha channel, and the AVIF encoder takes 8-bit pixels
+    let resized = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(resized.to_rgb8()),
+        ImageFormat::Avif => DynamicImage::ImageRgba8(resized.to_rgba8()),
+        _ => resized,
+    };
+    let mut output = Cursor::new(Vec::new());
+    let encoded = match format {
+        ImageFormat::Avif => resized.write_with_encoder(AvifEncoder::new_with_speed_quality(&mut output, AVIF_SPEED, AVIF_QUALITY)),
+        _ => resized.write_to(&mut output, format.encoding()),
+    };
+    encoded.with_context(|| format!("Failed to encode {} as {}", image.display(), format.name()))?;
+    Ok(output.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut output = Cursor::new(Vec::new());
+        DynamicImage::new_rgba8(width, height).write_to(&mut output, image::ImageFormat::Png).unwrap();
+        output.into_inner()
+    }
+    
+    #[test]
+    fn test_parse_queries() {
+        let srcset = Srcset::parse("srcset=800,480,800&format=webp").unwrap().unwrap();
+        assert_eq!(srcset, Srcset { widths: vec![480, 800], formats: vec![ImageFormat::Webp] });
+        assert!(Srcset::parse("width=480").is_none());
+        assert!(Srcset::parse("srcset=wide").unwrap().is_err());
+        let srcset = Srcset::parse("srcset=480&format=avif,webp").unwrap().unwrap();
+        assert_eq!(srcset.formats, vec![ImageFormat::Avif, ImageFormat::Webp]);
+        let error = Srcset::parse("srcset=480&format=gif").unwrap().unwrap_err().to_string();
+        assert_eq!(error, "Unknown image format 'gif'; use avif, webp, jpeg or png");
+        
+        assert_eq!(Variant::parse("width=480&format=webp"), Some(Variant { width: 480, format: Some(ImageFormat::Webp) }));
+        assert_eq!(Variant::parse("width=480").unwrap().query(), "width=480");
+        assert_eq!(Variant::parse("width=0"), None);
+        
+        let module = with_query(Path::new("/app/src/hero.png"), "srcset=480");
+        assert_eq!(srcset_module(&module), Some((PathBuf::from("/app/src/hero.png"), "srcset=480")));
+        let copy = with_query(Path::new("/app/src/hero.png"), "width=480&format=webp");
+        assert_eq!(variant(&copy).unwrap().1.format, Some(ImageFormat::Webp));
+        assert!(srcset_module(&copy).is_none());
+    }
+    
+    #[test]
+    fn test_srcset_module() {
+        let image = png(1000, 500);
+        let srcset = Srcset::parse("srcset=480,1200&format=webp,png").unwrap().unwrap();
+        let module = render_srcset_module(&image, Path::new("/app/src/hero.png"), &srcset).unwrap();
+        assert!(module.starts_with("var widths = [480,1000];\n"), "{}", module);
+        assert!(module.contains("new URL(\"./hero.png?width=480\", import.meta.url).href"));
+        assert!(module.contains("new URL(\"./hero.png?width=1000&format=webp\", import.meta.url).href"));
+        assert!(module.contains("  width: 1000,\n  height: 500,\n  type: \"image/png\",\n"));
+        assert_eq!(module.matches("{ type: ").count(), 1);
+        
+        let resized = resize(&image, Path::new("hero.png"), &Variant { width: 480, format: Some(ImageFormat::Webp) }).unwrap();
+        let decoded = image::load_from_memory(&resized).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (480, 240));
+        let jpeg = resize(&image, Path::new("hero.png"), &Variant { width: 2000, format: Some(ImageFormat::Jpeg) }).unwrap();
+        assert_eq!(image::load_from_memory(&jpeg).unwrap().width(), 1000);
+    }
+    
+    /// Width and height in an AVIF file's `ispe` (image spatial extents) box
+    fn avif_dimensions(avif: &[u8]) -> (u32, u32) {
+        let at = avif.windows(4).position(|w| w == b"ispe").unwrap();
+        let field = |offset: usize| u32::from_be_bytes(avif[at + offset..at + offset + 4].try_into().unwrap());
+        // Box type, then version and flags, then the extents
+        (field(8), field(12))
+    }
+    
+    #[test]
+    fn test_avif_copies() {
+        let image = png(200, 100);
+        let srcset = Srcset::parse("srcset=80,200&format=avif").unwrap().unwrap();
+        let module = render_srcset_module(&image, Path::new("/app/src/hero.png"), &srcset).unwrap();
+        assert!(module.contains("{ type: \"image/avif\", srcset: srcset("), "{}", module);
+        assert!(module.contains("new URL(\"./hero.png?width=80&format=avif\", import.meta.url).href"));
+        
+        // The image crate only decodes AVIF through the native dav1d library,
+        // so check the container the copy is written in
+        let variant = Variant::parse("width=80&format=avif").unwrap();
+        let avif = resize(&image, Path::new("hero.png"), &variant).unwrap();
+        assert_eq!(&avif[4..12], b"ftypavif");
+        assert_eq!(avif_dimensions(&avif), (80, 40));
+        let full = resize(&image, Path::new("hero.png"), &Variant { width: 1000, format: Some(ImageFormat::Avif) }).unwrap();
+        assert_eq!(avif_dimensions(&full), (200, 100));
+    }
+}
@@ -0,0 +1,393 @@
+//! Library mode
+//!
+//! A library build is published as a package rather than served: its output
+//! files keep their unhashed names, and each entry is emitted as an ES module
+//! (`<entry>.mjs`) and a CommonJS module (`<entry>.cjs`). With
+//! `library.package_json` the package's `exports`, `main`, `module` and
+//! `types` fields are kept pointing at the emitted entries, so a package
+//! can't be published with stale ones, and with `library.check_parity` both
+//! formats are loaded with Node to check they export the same names.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::EmittedFile;
+use crate::config::LibraryConfig;
+
+/// package.json fields a library build maintains
+pub const PACKAGE_FIELDS: &[&str] = &["exports", "main", "module", "types"];
+
+/// Entries exported as the package root when `library.main` isn't set and
+/// there are several
+const ROOT_ENTRIES: &[&str] = &["index", "main"];
+
+/// Module registry library bundles run their modules in, with the helpers
+/// of modules rewritten to CommonJS
+pub(super) const RUNTIME: &str = r#"// Component Runtime
+var __component_modules__ = {};
+var __component_cache__ = {};
+
+function __component_require__(moduleId) {
+  if (__component_cache__[moduleId]) {
+    return __component_cache__[moduleId].exports;
+  }
+  
+  var module = { exports: {} };
+  __component_cache__[moduleId] = module;
+  __component_modules__[moduleId](module, module.exports, __component_require__);
+  return module.exports;
+}
+
+// Dynamically imported modules are bundled too
+function __component_import__(moduleId) {
+  return Promise.resolve().then(function() {
+    return __component_require__(moduleId);
+  });
+}
+
+function __component_define__(exports, getters) {
+  Object.defineProperty(exports, '__esModule', { value: true });
+  Object.keys(getters).forEach(function(name) {
+    Object.defineProperty(exports, name, { enumerable: true, get: getters[name] });
+  });
+}
+
+function __component_default__(exports) {
+  return exports && exports.__esModule ? exports.default : exports;
+}
+
+function __component_export_star__(exports, from) {
+  Object.keys(from).forEach(function(name) {
+    if (name !== 'default' && name !== '__esModule' && !Object.prototype.hasOwnProperty.call(exports, name)) {
+      Object.defineProperty(exports, name, { enumerable: true, get: function() { return from[name]; } });
+    }
+  });
+}
+
+// The ES module build copies its entry's exports into its own bindings,
+// and copies them again whenever a module writes to an exported binding
+var __component_listeners__ = [];
+
+function __component_changed__(value) {
+  __component_listeners__.forEach(function(listener) { listener(); });
+  return value;
+}
+
+function __component_listen__(listener) {
+  __component_listeners__.push(listener);
+  listener();
+}
+"#;
+
+/// Loads each entry's builds and prints what doesn't match as a JSON array
+const PARITY_SCRIPT: &str = r#"
+import { createRequire } from 'node:module';
+import { pathToFileURL } from 'node:url';
+const dir = process.argv[1];
+const require = createRequire(dir + '/');
+const problems = [];
+for (const [name, esmFile, cjsFile] of JSON.parse(process.argv[2])) {
+  let esm, cjs;
+  try { esm = await import(pathToFileURL(dir + '/' + esmFile).href); } catch (e) { problems.push(`${name}: the ES module build fails to load: ${e.message}`); }
+  try { cjs = require(dir + '/' + cjsFile); } catch (e) { problems.push(`${name}: require() fails: ${e.message}`); }
+  if (!esm || !cjs) continue;
+  const named = Object.keys(esm).filter((key) => key !== 'default');
+  const required = Object(cjs) === cjs ? Object.keys(cjs).filter((key) => key !== 'default' && key !== '__esModule') : [];
+  for (const key of named.filter((key) => !required.includes(key))) problems.push(`${name}: '${key}' is exported by the ES module build but not by require()`);
+  for (const key of required.filter((key) => !named.includes(key))) problems.push(`${name}: '${key}' is exported by require() but not by the ES module build`);
+  const cjsDefault = cjs && cjs.__esModule ? cjs.default : cjs;
+  if (!('default' in esm) && cjs && cjs.__esModule && 'default' in cjs) problems.push(`${name}: require() has a default export the ES module build lacks`);
+  if ('default' in esm && typeof esm.default !== typeof cjsDefault) problems.push(`${name}: the default export is ${typeof esm.default} in the ES module build but ${typeof cjsDefault} through require()`);
+}
+process.stdout.write(JSON.stringify(problems));
+"#;
+
+/// package.json fields pointing at a library build's entries
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct PackageExports {
+    fields: Map<String, Value>,
+}
+
+impl PackageExports {
+    /// Fields for the entry scripts among `files`, emitted to `outdir`
+    /// (relative to the package root)
+    ///
+    /// The root entry is `.` in the exports map and the target of `main`
+    /// (its CommonJS build), `module` (its ES module build) and `types`;
+    /// every other entry is exported by its name. Each export maps the
+    /// `import` and `require` conditions to the two builds.
+    pub fn new(config: &LibraryConfig, files: &[EmittedFile], outdir: &str) -> Result<Self> {
+        let outdir = outdir.trim_start_matches("./").trim_end_matches('/');
+        let path = |file_name: &str| package_path(&format!("{}/{}", outdir, file_name));
+        let entries: BTreeMap<&str, (String, String)> = entry_scripts(files)
+            .into_iter()
+            .map(|(entry, (esm, cjs))| (entry, (path(esm), path(cjs))))
+            .collect();
+        
+        let main = root_entry(config, files)?;
+        let main_path = main.and_then(|name| entries.get(name));
+        let types = match (&config.types, main) {
+            (Some(types), _) => Some(package_path(types)),
+            (None, Some(main)) if config.dts => Some(package_path(&format!("{}/{}.d.ts", outdir, main))),
+            _ => None,
+        };
+        
+        let mut exports = Map::new();
+        if let Some((esm, cjs)) = main_path {
+            let target = match &types {
+                Some(types) => json!({ "types": types, "import": esm, "require": cjs }),
+                None => json!({ "import": esm, "require": cjs }),
+            };
+            exports.insert(".".to_string(), target);
+        }
+        for (name, (esm, cjs)) in entries.iter().filter(|(name, _)| Some(**name) != main) {
+            exports.insert(format!("./{}", name), json!({ "import": esm, "require": cjs }));
+        }
+        
+        let mut fields = Map::new();
+        fields.insert("exports".to_string(), Value::Object(exports));
+        if let Some((esm, cjs)) = main_path {
+            fields.insert("main".to_string(), json!(cjs));
+            fields.insert("module".to_string(), json!(esm));
+        }
+        if let Some(types) = types {
+            fields.insert("types".to_string(), json!(types));
+        }
+        Ok(Self { fields })
+    }
+    
+    /// The maintained fields, in package.json order
+    pub fn fields(&self) -> &Map<String, Value> {
+        &self.fields
+    }
+    
+    /// Maintained fields `package` has out of date, including ones it sets
+    /// that the build no longer points anywhere
+    pub fn stale_fields(&self, package: &Value) -> Vec<&'static str> {
+        PACKAGE_FIELDS
+            .iter()
+            .copied()
+            .filter(|field| package.get(field) != self.fields.get(*field))
+            .collect()
+    }
+    
+    /// Stale fields of the package.json at `path`; every field is stale when
+    /// it doesn't exist
+    pub fn check(&self, path: &Path) -> Result<Vec<&'static str>> {
+        match read_package(path)? {
+            Some((package, _)) => Ok(self.stale_fields(&package)),
+            None => Ok(PACKAGE_FIELDS.iter().copied().filter(|field| self.fields.contains_key(*field)).collect()),
+        }
+    }
+    
+    /// Bring the package.json at `path` up to date and return the fields
+    /// that changed
+    ///
+    /// Other fields keep their order and the file its indentation. A missing
+    /// package.json is created with the project's name and version.
+    pub fn write(&self, path: &Path, name: &str, version: &str) -> Result<Vec<&'static str>> {
+        let (mut package, indent) = read_package(path)?
+            .unwrap_or_else(|| (json!({ "name": name, "version": version }), "  ".to_string()));
+        let stale = self.stale_fields(&package);
+        if stale.is_empty() {
+            return Ok(stale);
+        }
+        
+        let object = package
+            .as_object_mut()
+            .with_context(|| format!("{} is not a JSON object", path.display()))?;
+        for field in &stale {
+            match self.fields.get(*field) {
+                Some(value) => {
+                    object.insert(field.to_string(), value.clone());
+                }
+                None => {
+                    object.shift_remove(*field);
+                }
+            }
+        }
+        
+        let mut json = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        package.serialize(&mut serde_json::Serializer::with_formatter(&mut json, formatter))?;
+        json.push(b'\n');
+        fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(stale)
+    }
+}
+
+/// The entry exported as the package root, among the entry scripts in `files`
+pub(super) fn root_entry<'a>(config: &'a LibraryConfig, files: &'a [EmittedFile]) -> Result<Option<&'a str>> {
+    let entries = entry_scripts(files);
+    Ok(match config.main.as_deref() {
+        Some(name) if !entries.contains_key(name) => {
+            anyhow::bail!("library.main '{}' is not a script entry", name);
+        }
+        Some(name) => Some(name),
+        None if entries.len() == 1 => entries.keys().next().copied(),
+        None => ROOT_ENTRIES.iter().copied().find(|name| entries.contains_key(name)),
+    })
+}
+
+/// File names of each entry's ES module and CommonJS builds, by entry name;
+/// they may be in `js/` with `output.split_by_type`
+fn entry_scripts(files: &[EmittedFile]) -> BTreeMap<&str, (&str, &str)> {
+    let script = |entry: &str, extension: &str| {
+        let name = format!("{}.{}", entry, extension);
+        files
+            .iter()
+            .find(|file| file.entry.as_deref() == Some(entry) && (file.file_name == name || file.file_name == format!("js/{}", name)))
+            .map(|file| file.file_name.as_str())
+    };
+    files
+        .iter()
+        .filter_map(|file| {
+            let entry = file.entry.as_deref()?;
+            Some((entry, (script(entry, "mjs")?, script(entry, "cjs")?)))
+        })
+        .collect()
+}
+
+/// Load each entry's ES module and CommonJS builds among `files` with Node,
+/// and list where they differ: a build failing to load, a name only one of
+/// them exports, or a default export of another type or missing from one
+pub fn check_parity(files: &[EmittedFile]) -> Result<Vec<String>> {
+    let entries: Vec<(&str, &str, &str)> = entry_scripts(files).into_iter().map(|(name, (esm, cjs))| (name, esm, cjs)).collect();
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    // Library bundles hold all their modules, so they load from anywhere
+    let dir = std::env::temp_dir().join(format!("component-parity-{}", std::process::id()));
+    let check = || -> Result<Vec<String>> {
+        for file in files.iter().filter(|file| entries.iter().any(|(_, esm, cjs)| file.file_name == *esm || file.file_name == *cjs)) {
+            let path = dir.join(&file.file_name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &file.contents).with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        let output = Command::new("node")
+            .args(["--input-type=module", "-e", PARITY_SCRIPT])
+            .arg(&dir)
+            .arg(serde_json::to_string(&entries)?)
+            .output()
+            .context("Failed to run node; library.check_parity needs Node.js on PATH")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to check library exports: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        serde_json::from_slice(&output.stdout).context("Failed to read the library export check's output")
+    };
+    let problems = check();
+    let _ = fs::remove_dir_all(&dir);
+    problems
+}
+
+/// A package.json with the indentation of its first nested line, or `None`
+/// when it doesn't exist
+fn read_package(path: &Path) -> Result<Option<(Value, String)>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let package = serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let indent = contents
+        .lines()
+        .nth(1)
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or_else(|| "  ".to_string());
+    Ok(Some((package, indent)))
+}
+
+/// A path relative to the package root as package.json writes it, as in `./dist/index.js`
+fn package_path(path: &str) -> String {
+    format!("./{}", path.trim_start_matches("./").replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn script(name: &str, extension: &str, contents: &str) -> EmittedFile {
+        EmittedFile {
+            file_name: format!("{}.{}", name, extension),
+            contents: contents.as_bytes().to_vec(),
+            origin: String::new(),
+            entry: Some(name.to_string()),
+        }
+    }
+    
+    fn entry(name: &str) -> [EmittedFile; 2] {
+        [script(name, "mjs", ""), script(name, "cjs", "")]
+    }
+    
+    #[test]
+    fn test_package_exports() {
+        let config = LibraryConfig { types: Some("types/index.d.ts".to_string()), ..Default::default() };
+        let files = [entry("index"), entry("utils")].concat();
+        let exports = PackageExports::new(&config, &files, "dist").unwrap();
+        assert_eq!(
+            Value::Object(exports.fields().clone()),
+            json!({
+                "exports": {
+                    ".": { "types": "./types/index.d.ts", "import": "./dist/index.mjs", "require": "./dist/index.cjs" },
+                    "./utils": { "import": "./dist/utils.mjs", "require": "./dist/utils.cjs" }
+                },
+                "main": "./dist/index.cjs",
+                "module": "./dist/index.mjs",
+                "types": "./types/index.d.ts"
+            })
+        );
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.json");
+        fs::write(&path, "{\n\t\"name\": \"lib\",\n\t\"main\": \"old.js\",\n\t\"license\": \"MIT\"\n}\n").unwrap();
+        assert_eq!(exports.check(&path).unwrap(), vec!["exports", "main", "module", "types"]);
+        assert_eq!(exports.write(&path, "lib", "1.0.0").unwrap().len(), 4);
+        assert!(exports.check(&path).unwrap().is_empty());
+        
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("{\n\t\"name\": \"lib\",\n\t\"main\": \"./dist/index.cjs\",\n\t\"license\": \"MIT\",\n\t\"exports\""));
+        
+        let unknown = LibraryConfig { main: Some("app".to_string()), ..Default::default() };
+        assert!(PackageExports::new(&unknown, &entry("index"), "dist").is_err());
+    }
+    
+    #[test]
+    fn test_check_parity() {
+        if Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        
+        let matching = [
+            script("index", "mjs", "export const a = 1;\nexport default function() {}\n"),
+            script("index", "cjs", "exports.__esModule = true;\nexports.a = 1;\nexports.default = function() {};\n"),
+        ];
+        assert!(check_parity(&matching).unwrap().is_empty());
+        
+        let broken = [
+            script("index", "mjs", "export const a = 1;\nexport default 'x';\n"),
+            script("index", "cjs", "exports.b = 2;\n"),
+            script("utils", "mjs", "export const a = 1;\n"),
+            script("utils", "cjs", "throw new Error('no');\n"),
+        ];
+        assert_eq!(
+            check_parity(&broken).unwrap(),
+            vec![
+                "index: 'a' is exported by the ES module build but not by require()",
+                "index: 'b' is exported by require() but not by the ES module build",
+                "index: the default export is string in the ES module build but object through require()",
+                "utils: require() fails: no",
+            ]
+        );
+    }
+}
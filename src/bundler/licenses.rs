@@ -0,0 +1,283 @@
+//! Third-party license reporting
+//!
+//! Collects name, version, license, and author for every `node_modules`
+//! package that contributes a module to the graph, along with the package's
+//! LICENSE file if it ships one.
+
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::vfs::FileSystem;
+
+/// File names checked for license text, in order
+const LICENSE_FILES: &[&str] = &[
+    "LICENSE", "LICENSE.md", "LICENSE.txt", "LICENCE", "LICENCE.md", "LICENCE.txt",
+    "license", "license.md", "license.txt", "LICENSE-MIT", "COPYING",
+];
+
+/// License used for packages that do not declare one
+pub const UNKNOWN_LICENSE: &str = "UNKNOWN";
+
+/// License information for a single package
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageLicense {
+    pub name: String,
+    pub version: String,
+    
+    /// SPDX expression from package.json, or `UNKNOWN`
+    pub license: String,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    
+    /// Package directory relative to the project root
+    pub path: String,
+    
+    /// Name of the bundled license file, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_file: Option<String>,
+    
+    #[serde(skip)]
+    pub license_text: Option<String>,
+}
+
+/// Licenses of all bundled packages, sorted by name and version
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LicenseReport {
+    pub packages: Vec<PackageLicense>,
+}
+
+impl LicenseReport {
+    /// Collect licenses for the packages containing the given module paths
+    pub fn collect<'a>(
+        modules: impl IntoIterator<Item = &'a Path>,
+        root: &Path,
+        fs: &dyn FileSystem,
+    ) -> Result<Self> {
+        let dirs: BTreeSet<PathBuf> = modules.into_iter().filter_map(package_dir).collect();
+        
+        let mut packages = Vec::new();
+        for dir in dirs {
+            if let Some(package) = read_package(&dir, root, fs)? {
+                packages.push(package);
+            }
+        }
+        packages.sort_by(|a, b| (&a.name, &a.version, &a.path).cmp(&(&b.name, &b.version, &b.path)));
+        packages.dedup_by(|a, b| a.name == b.name && a.version == b.version);
+        
+        Ok(Self { packages })
+    }
+    
+    /// Packages whose license is not permitted by the denylist
+    pub fn denied(&self, deny: &[String]) -> Vec<&PackageLicense> {
+        self.packages
+            .iter()
+            .filter(|package| is_denied(&package.license, deny))
+            .collect()
+    }
+    
+    /// Fail if any package is denied
+    pub fn check(&self, deny: &[String]) -> Result<()> {
+        let denied = self.denied(deny);
+        if denied.is_empty() {
+            return Ok(());
+        }
+        
+        let list = denied
+            .iter()
+            .map(|p| format!("  {}@{} ({})", p.name, p.version, p.license))
+            .collect::<Vec<_>>()
+            .join("\n");
+        anyhow::bail!("{} package(s) use a denied license:\n{}", denied.len(), list)
+    }
+    
+    /// Plain-text report including the full license texts
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for package in &self.packages {
+            out.push_str(&format!("{}@{}\n", package.name, package.version));
+            out.push_str(&format!("License: {}\n", package.license));
+            if let Some(author) = &package.author {
+                out.push_str(&format!("Author: {}\n", author));
+            }
+            if let Some(text) = &package.license_text {
+                out.push('\n');
+                out.push_str(text.trim_end());
+                out.push('\n');
+            }
+            out.push_str(&format!("\n{}\n\n", "-".repeat(72)));
+        }
+        out
+    }
+}
+
+/// Directory of the innermost `node_modules` package containing `path`
+fn package_dir(path: &Path) -> Option<PathBuf> {
+    let components: Vec<Component> = path.components().collect();
+    let index = components
+        .iter()
+        .rposition(|c| c.as_os_str() == "node_modules")?;
+    
+    let name = components.get(index + 1)?;
+    let len = if name.as_os_str().to_string_lossy().starts_with('@') {
+        index + 3
+    } else {
+        index + 2
+    };
+    
+    // The path must be inside the package, not the package dir itself
+    if components.len() <= len {
+        return None;
+    }
+    Some(components[..len].iter().collect())
+}
+
+/// Read license information from a package directory
+fn read_package(dir: &Path, root: &Path, fs: &dyn FileSystem) -> Result<Option<PackageLicense>> {
+    let Ok(manifest) = fs.read_to_string(&dir.join("package.json")) else {
+        return Ok(None);
+    };
+    let json: Value = serde_json::from_str(&manifest)?;
+    
+    let name = json["name"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| dir.file_name().unwrap_or_default().to_string_lossy().into_owned());
+    
+    let license_file = LICENSE_FILES
+        .iter()
+        .find(|file| fs.is_file(&dir.join(file)))
+        .map(|file| file.to_string());
+    let license_text = license_file
+        .as_ref()
+        .and_then(|file| fs.read_to_string(&dir.join(file)).ok());
+    
+    Ok(Some(PackageLicense {
+        name,
+        version: json["version"].as_str().unwrap_or("0.0.0").to_string(),
+        license: declared_license(&json).unwrap_or_else(|| UNKNOWN_LICENSE.to_string()),
+        author: person(&json["author"]),
+        path: crate::utils::path_to_module_id(dir.strip_prefix(root).unwrap_or(dir)),
+        license_file,
+        license_text,
+    }))
+}
+
+/// `license` as a string or `{ type }`, or the legacy `licenses` array
+fn declared_license(json: &Value) -> Option<String> {
+    match &json["license"] {
+        Value::String(license) => return Some(license.clone()),
+        Value::Object(license) => return license.get("type")?.as_str().map(str::to_string),
+        _ => {}
+    }
+    
+    let types: Vec<&str> = json["licenses"]
+        .as_array()?
+        .iter()
+        .filter_map(|l| l["type"].as_str().or_else(|| l.as_str()))
+        .collect();
+    (!types.is_empty()).then(|| types.join(" OR "))
+}
+
+/// A package.json person as a string or `{ name, email }`
+fn person(value: &Value) -> Option<String> {
+    match value {
+        Value::String(person) => Some(person.clone()),
+        Value::Object(person) => {
+            let name = person.get("name")?.as_str()?;
+            match person.get("email").and_then(Value::as_str) {
+                Some(email) => Some(format!("{} <{}>", name, email)),
+                None => Some(name.to_string()),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether an SPDX expression is denied
+///
+/// An `OR` expression is allowed if any alternative is; an `AND` expression
+/// is denied if any term is. Deny entries match case-insensitively, and a
+/// trailing `*` matches any suffix (`GPL-*`).
+pub fn is_denied(expression: &str, deny: &[String]) -> bool {
+    if deny.is_empty() {
+        return false;
+    }
+    
+    let expression = expression.replace(['(', ')'], " ");
+    expression
+        .split(" OR ")
+        .all(|alternative| {
+            alternative
+                .split(" AND ")
+                .any(|term| {
+                    let id = term.split(" WITH ").next().unwrap_or(term).trim();
+                    deny.iter().any(|pattern| matches_pattern(id, pattern))
+                })
+        })
+}
+
+fn matches_pattern(id: &str, pattern: &str) -> bool {
+    let id = id.to_ascii_lowercase();
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => id.starts_with(prefix),
+        None => id == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    fn deny(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_is_denied() {
+        let deny = deny(&["GPL-*", "UNKNOWN"]);
+        assert!(is_denied("GPL-3.0-only", &deny));
+        assert!(is_denied("gpl-2.0", &deny));
+        assert!(is_denied("MIT AND GPL-2.0", &deny));
+        assert!(is_denied("UNKNOWN", &deny));
+        assert!(!is_denied("(MIT OR GPL-3.0)", &deny));
+        assert!(!is_denied("LGPL-2.1", &deny));
+        assert!(!is_denied("GPL-3.0", &[]));
+    }
+    
+    #[test]
+    fn test_collect_packages() {
+        let fs = MemoryFs::new();
+        fs.write("/p/node_modules/a/package.json", r#"{"name":"a","version":"1.0.0","license":"MIT","author":{"name":"Ann","email":"ann@example.com"}}"#);
+        fs.write("/p/node_modules/a/LICENSE", "MIT License");
+        fs.write("/p/node_modules/a/index.js", "");
+        fs.write("/p/node_modules/a/lib/util.js", "");
+        fs.write("/p/node_modules/@s/b/package.json", r#"{"name":"@s/b","version":"2.0.0","licenses":[{"type":"GPL-2.0"}]}"#);
+        fs.write("/p/node_modules/@s/b/index.js", "");
+        
+        let modules = [
+            Path::new("/p/src/main.js"),
+            Path::new("/p/node_modules/a/index.js"),
+            Path::new("/p/node_modules/a/lib/util.js"),
+            Path::new("/p/node_modules/@s/b/index.js"),
+        ];
+        let report = LicenseReport::collect(modules, Path::new("/p"), &fs).unwrap();
+        
+        assert_eq!(report.packages.len(), 2);
+        assert_eq!(report.packages[0].name, "@s/b");
+        assert_eq!(report.packages[0].license, "GPL-2.0");
+        assert_eq!(report.packages[0].path, "node_modules/@s/b");
+        assert_eq!(report.packages[1].author.as_deref(), Some("Ann <ann@example.com>"));
+        assert_eq!(report.packages[1].license_file.as_deref(), Some("LICENSE"));
+        assert!(report.to_text().contains("MIT License"));
+        
+        assert!(report.check(&deny(&["GPL-*"])).is_err());
+        assert!(report.check(&deny(&["AGPL-*"])).is_ok());
+    }
+}
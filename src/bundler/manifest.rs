@@ -0,0 +1,173 @@
+//! Asset manifests
+//!
+//! Server-rendered apps look up the hashed files of their entries in a
+//! manifest, and `output.manifest_format` picks the one their framework
+//! reads:
+//!
+//! - `json` - `manifest.json`, the URL of every output file
+//! - `entrypoints` - `entrypoints.json`, the scripts and stylesheets of each
+//!   entry (Symfony Encore and similar helpers)
+//! - `rails` - `manifest.json` in the Shakapacker format: URLs of every
+//!   output file by unhashed name, plus each entry's assets
+//! - `laravel` - `mix-manifest.json`, mapping `/main.js`, and every other
+//!   output file, to the hashed file for Laravel's `mix()` helper
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::EmittedFile;
+
+/// The content hash `output.hash` adds before the extension
+static HASH_SUFFIX_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\.[0-9a-f]{8}(\.[^./]+)$").unwrap()
+});
+
+/// Manifest formats selectable with `output.manifest_format`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFormat {
+    /// URL of every output file
+    #[default]
+    Json,
+    
+    /// Scripts and stylesheets of each entry
+    Entrypoints,
+    
+    /// Shakapacker's `manifest.json`
+    Rails,
+    
+    /// Laravel Mix's `mix-manifest.json`
+    Laravel,
+}
+
+impl ManifestFormat {
+    /// Name of the manifest file in the output directory
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ManifestFormat::Json | ManifestFormat::Rails => "manifest.json",
+            ManifestFormat::Entrypoints => "entrypoints.json",
+            ManifestFormat::Laravel => "mix-manifest.json",
+        }
+    }
+    
+    /// Render the manifest of a build's files
+    ///
+    /// `urls` maps each output file to its URL under `output.public_url`.
+    pub fn render(self, files: &[EmittedFile], urls: &HashMap<String, String>) -> Result<String> {
+        let url = |file: &EmittedFile| urls.get(&file.file_name).cloned().unwrap_or_default();
+        let assets: Vec<&EmittedFile> = files.iter().filter(|file| is_asset(&file.file_name)).collect();
+        
+        let manifest = match self {
+            ManifestFormat::Json => json!(urls.iter().collect::<BTreeMap<_, _>>()),
+            ManifestFormat::Entrypoints => json!({ "entrypoints": entrypoints(&assets, &url) }),
+            ManifestFormat::Rails => {
+                let mut manifest: BTreeMap<String, Value> = files
+                    .iter()
+                    .map(|file| (unhashed_name(&file.file_name), json!(url(file))))
+                    .collect();
+                let entrypoints: BTreeMap<String, Value> = entrypoints(&assets, &url)
+                    .into_iter()
+                    .map(|(name, assets)| (name, json!({ "assets": assets })))
+                    .collect();
+                manifest.insert("entrypoints".to_string(), json!(entrypoints));
+                json!(manifest)
+            }
+            ManifestFormat::Laravel => json!(files
+                .iter()
+                .map(|file| (format!("/{}", unhashed_name(&file.file_name)), format!("/{}", file.file_name)))
+                .collect::<BTreeMap<_, _>>()),
+        };
+        Ok(serde_json::to_string_pretty(&manifest)?)
+    }
+}
+
+/// Scripts and stylesheets each entry loads up front, by entry name
+fn entrypoints(assets: &[&EmittedFile], url: &dyn Fn(&EmittedFile) -> String) -> BTreeMap<String, Value> {
+    let mut entrypoints: BTreeMap<String, BTreeMap<&str, Vec<String>>> = BTreeMap::new();
+    for file in assets {
+        let (Some(entry), Some(kind)) = (&file.entry, file.file_name.rsplit('.').next()) else {
+            continue;
+        };
+        entrypoints
+            .entry(entry.clone())
+            .or_default()
+            .entry(kind)
+            .or_default()
+            .push(url(file));
+    }
+    entrypoints.into_iter().map(|(name, assets)| (name, json!(assets))).collect()
+}
+
+/// Whether a page would load the file with a script or stylesheet tag
+fn is_asset(file_name: &str) -> bool {
+    file_name.ends_with(".js") || file_name.ends_with(".css")
+}
+
+/// A file's name without the content hash, as in `main.js` for `main.1f2e3d4c.js`
+pub(super) fn unhashed_name(file_name: &str) -> String {
+    HASH_SUFFIX_REGEX.replace(file_name, "$1").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn file(file_name: &str, entry: Option<&str>) -> EmittedFile {
+        EmittedFile {
+            file_name: file_name.to_string(),
+            contents: Vec::new(),
+            origin: String::new(),
+            entry: entry.map(str::to_string),
+        }
+    }
+    
+    #[test]
+    fn test_manifest_formats() {
+        let files = vec![
+            file("lazy.0a1b2c3d.js", None),
+            file("app.1f2e3d4c.css", Some("app")),
+            file("app.html", None),
+            file("app.5a6b7c8d.js", Some("app")),
+            file("assets/hero-480w.9e8d7c6b.jpg", None),
+        ];
+        let urls: HashMap<String, String> = files
+            .iter()
+            .map(|f| (f.file_name.clone(), format!("/build/{}", f.file_name)))
+            .collect();
+        let render = |format: ManifestFormat| -> Value {
+            serde_json::from_str(&format.render(&files, &urls).unwrap()).unwrap()
+        };
+        
+        assert_eq!(render(ManifestFormat::Json)["app.html"], "/build/app.html");
+        assert_eq!(
+            render(ManifestFormat::Entrypoints),
+            json!({ "entrypoints": { "app": { "js": ["/build/app.5a6b7c8d.js"], "css": ["/build/app.1f2e3d4c.css"] } } })
+        );
+        assert_eq!(
+            render(ManifestFormat::Rails),
+            json!({
+                "app.css": "/build/app.1f2e3d4c.css",
+                "app.html": "/build/app.html",
+                "app.js": "/build/app.5a6b7c8d.js",
+                "assets/hero-480w.jpg": "/build/assets/hero-480w.9e8d7c6b.jpg",
+                "lazy.js": "/build/lazy.0a1b2c3d.js",
+                "entrypoints": { "app": { "assets": { "js": ["/build/app.5a6b7c8d.js"], "css": ["/build/app.1f2e3d4c.css"] } } }
+            })
+        );
+        assert_eq!(
+            render(ManifestFormat::Laravel),
+            json!({
+                "/app.css": "/app.1f2e3d4c.css",
+                "/app.html": "/app.html",
+                "/app.js": "/app.5a6b7c8d.js",
+                "/assets/hero-480w.jpg": "/assets/hero-480w.9e8d7c6b.jpg",
+                "/lazy.js": "/lazy.0a1b2c3d.js"
+            })
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! HTML and JSON minification
+//!
+//! Pages lose comments and the whitespace browsers ignore. Whitespace
+//! between inline elements renders as a space, so runs of it collapse to one
+//! space instead, and is only dropped next to block-level and head elements.
+//! `<pre>`, `<textarea>`, `<script>` and `<style>` contents are kept as is.
+
+/// Elements whose contents are copied verbatim
+const RAW_TEXT_ELEMENTS: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// Elements whitespace around which never renders
+const BLOCK_ELEMENTS: &[&str] = &[
+    "!doctype", "html", "head", "body", "title", "meta", "link", "script", "style", "base", "noscript",
+    "template", "div", "p", "ul", "ol", "li", "dl", "dt", "dd", "section", "article", "aside", "header",
+    "footer", "main", "nav", "form", "fieldset", "table", "thead", "tbody", "tfoot", "tr", "td", "th",
+    "caption", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "br", "blockquote", "figure", "figcaption",
+    "details", "summary", "option", "select", "pre",
+];
+
+/// Minify an HTML page
+///
+/// Comments are removed except conditional comments, whitespace is
+/// collapsed, and attribute values are double-quoted.
+pub fn minify_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut previous_tag = String::from("!doctype");
+    
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").map_or(comment.len(), |end| end + 3);
+            if comment.starts_with("[if") || comment.starts_with("<![endif]") {
+                out.push_str(&rest[..end + 4]);
+            }
+            rest = &comment[end..];
+            continue;
+        }
+        
+        if let Some(name) = tag_name(rest) {
+            let end = tag_end(rest);
+            out.push_str(&normalize_tag(&rest[..end]));
+            rest = &rest[end..];
+            
+            // Raw text runs to the closing tag
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let close = find_ignore_case(rest, &format!("</{}", name)).unwrap_or(rest.len());
+                out.push_str(&rest[..close]);
+                rest = &rest[close..];
+            }
+            previous_tag = name.trim_start_matches('/').to_string();
+            continue;
+        }
+        
+        // Text up to the next tag; a lone `<` is text too
+        let first = rest.chars().next().map_or(1, char::len_utf8);
+        let end = rest[first..].find('<').map_or(rest.len(), |i| i + first);
+        let next_tag = tag_name(&rest[end..]).unwrap_or_default();
+        let mut text = collapse_whitespace(&rest[..end]);
+        if BLOCK_ELEMENTS.contains(&previous_tag.as_str()) {
+            text = text.trim_start().to_string();
+        }
+        if end == rest.len() || BLOCK_ELEMENTS.contains(&next_tag.trim_start_matches('/')) {
+            text = text.trim_end().to_string();
+        }
+        out.push_str(&text);
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// Minify JSON, keeping the order of keys; `None` if it isn't valid JSON
+pub fn minify_json(json: &[u8]) -> Option<Vec<u8>> {
+    serde_json::from_slice::<serde::de::IgnoredAny>(json).ok()?;
+    
+    let mut out = Vec::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in json {
+        if in_string {
+            out.push(byte);
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if !byte.is_ascii_whitespace() {
+            in_string = byte == b'"';
+            out.push(byte);
+        }
+    }
+    Some(out)
+}
+
+/// Lowercase name of the tag `html` starts with, as in `div` or `/div`
+fn tag_name(html: &str) -> Option<String> {
+    let rest = html.strip_prefix('<')?;
+    let (prefix, name) = match rest.chars().next() {
+        Some(c @ ('/' | '!')) => (c.to_string(), &rest[1..]),
+        _ => (String::new(), rest),
+    };
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let end = name.find(|c: char| c.is_whitespace() || c == '>' || c == '/').unwrap_or(name.len());
+    Some(format!("{}{}", prefix, name[..end].to_ascii_lowercase()))
+}
+
+/// Length of the tag at the start of `html`, up to and including its `>`
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Collapse whitespace between attributes and double-quote their values
+fn normalize_tag(tag: &str) -> String {
+    let inner = tag.trim_start_matches('<').trim_end_matches('>');
+    // `/` ends an unquoted value in `<a href=/x/>`, not the tag
+    let self_closing = inner.ends_with('/')
+        && (!inner.contains(char::is_whitespace) || [" /", "\"/", "'/"].iter().any(|end| inner.ends_with(end)));
+    let inner = if self_closing { &inner[..inner.len() - 1] } else { inner };
+    let mut chars = inner.char_indices().peekable();
+    let mut out = String::from("<");
+    
+    // Tag name
+    let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+    out.push_str(&inner[..name_end]);
+    while chars.peek().is_some_and(|&(i, _)| i < name_end) {
+        chars.next();
+    }
+    
+    loop {
+        while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+        let Some(&(start, _)) = chars.peek() else {
+            break;
+        };
+        while chars.peek().is_some_and(|&(_, c)| !c.is_whitespace() && c != '=') {
+            chars.next();
+        }
+        let name_end = chars.peek().map_or(inner.len(), |&(i, _)| i);
+        out.push(' ');
+        out.push_str(&inner[start..name_end]);
+        
+        // Whitespace is allowed around `=`
+        let mut lookahead = chars.clone();
+        while lookahead.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+            lookahead.next();
+        }
+        if lookahead.peek().map(|&(_, c)| c) != Some('=') {
+            continue;
+        }
+        chars = lookahead;
+        chars.next();
+        while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+        
+        let value = match chars.peek().map(|&(_, c)| c) {
+            Some(quote @ ('"' | '\'')) => {
+                let (open, _) = chars.next().unwrap_or_default();
+                let mut close = inner.len();
+                for (i, c) in chars.by_ref() {
+                    if c == quote {
+                        close = i;
+                        break;
+                    }
+                }
+                &inner[open + 1..close]
+            }
+            Some(_) => {
+                let (open, _) = *chars.peek().unwrap_or(&(inner.len(), ' '));
+                while chars.peek().is_some_and(|(_, c)| !c.is_whitespace()) {
+                    chars.next();
+                }
+                &inner[open..chars.peek().map_or(inner.len(), |&(i, _)| i)]
+            }
+            None => "",
+        };
+        if value.contains('"') {
+            out.push_str(&format!("='{}'", value));
+        } else {
+            out.push_str(&format!("=\"{}\"", value));
+        }
+    }
+    
+    if self_closing {
+        out.push('/');
+    }
+    out.push('>');
+    out
+}
+
+/// Replace each run of whitespace with one space
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.to_ascii_lowercase().find(&needle.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_minify_html() {
+        let html = "<!DOCTYPE html>\n<html>\n  <head>\n    <!-- comment -->\n    <meta  charset=utf-8 >\n    \
+            <title> App </title>\n    <script>\n  if (a  <  b) {}\n    </script>\n  </head>\n  <body class='x'>\n    \
+            <!--[if IE]><p>Old</p><![endif]-->\n    <p>Hello,\n      <b>you</b>   <i>there</i></p>\n    \
+            <pre>  keep\n  this </pre>\n    <input disabled value = 'say \"hi\"' />\n  </body>\n</html>\n";
+        
+        assert_eq!(
+            minify_html(html),
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>App</title><script>\n  if (a  <  b) {}\n    </script>\
+             </head><body class=\"x\"><!--[if IE]><p>Old</p><![endif]--><p>Hello, <b>you</b> <i>there</i></p>\
+             <pre>  keep\n  this </pre><input disabled value='say \"hi\"'/></body></html>"
+        );
+    }
+    
+    #[test]
+    fn test_minify_json() {
+        let json = b"{\n  \"b\": [1, 2],\n  \"a\": \"x  \\\" y\"\n}\n";
+        assert_eq!(minify_json(json).unwrap(), b"{\"b\":[1,2],\"a\":\"x  \\\" y\"}".to_vec());
+        assert_eq!(minify_json(b"{ invalid"), None);
+    }
+}
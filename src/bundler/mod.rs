@@ -2,51 +2,274 @@
 //!
 //! Handles the module graph, dependency resolution, and bundle generation.
 
+mod cache;
 mod graph;
 mod chunk;
+mod commonjs;
+mod dts;
+mod dynamic_imports;
+mod entries;
+pub(crate) mod fonts;
+mod hot;
+pub(crate) mod html;
+mod i18n;
+mod ids;
+pub(crate) mod images;
+mod library;
+mod licenses;
+mod manifest;
+mod minify;
+mod provenance;
+mod public_path;
+mod reporter;
+#[cfg(feature = "native")]
+mod remote;
+mod routes;
+mod side_effects;
+mod snapshot;
+mod sourcemap;
+mod stats;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use parking_lot::RwLock;
-use sha2::{Sha256, Digest};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
 use tracing::{debug, info};
+use web_time::Instant;
 
-use crate::cli::BuildOptions;
-use crate::config::Config;
-use crate::resolver::Resolver;
-use crate::transform::Transformer;
+use crate::config::{Config, MinifyConfig, PluginConfig};
+use crate::plugins::{CodegenOutput, Plugin, PluginContext, PluginManager, PluginRegistry, PluginTiming, CODEGEN_PREFIX};
+use crate::resolver::{lockfile_hash, Resolver, ASSET_URL_REGEX, DYNAMIC_IMPORT_REGEX, REQUIRE_REGEX};
+use crate::transform::{css, Transformer};
+#[cfg(feature = "native")]
+use crate::transform::worker::WorkerPool;
+use crate::utils::path_to_module_id;
+use crate::vfs::{FileStamp, FileSystem, RealFs};
 
-pub use graph::{ModuleGraph, Module, ModuleId, ModuleType};
+pub use cache::{CacheBackend, CacheStats, DiskBackend, TransformCache};
+pub use graph::{
+    distinct_specifiers, Dependency, ImportKind, ModuleExports, ModuleGraph, Module, ModuleId, ModuleType, ReExport, TransformStage,
+};
 pub use chunk::{Chunk, ChunkType};
+pub use entries::{compile as compile_entry_glob, expand_entrypoints, is_glob as is_entry_glob};
+pub use hot::{hot_update, rewrite_accepted, HotAccepts, HotUpdate};
+pub use i18n::{extract_keys, Catalogs};
+pub use ids::{ModuleIdMap, ModuleIds};
+pub use library::{PackageExports, PACKAGE_FIELDS};
+pub use licenses::{is_denied, LicenseReport, PackageLicense, UNKNOWN_LICENSE};
+pub use manifest::ManifestFormat;
+pub use minify::{minify_html, minify_json};
+pub use provenance::{load_signing_key, FileDigest, Provenance, ProvenanceSignature, PROVENANCE_FILE};
+pub use public_path::ASSET_URL_MODULE;
+#[cfg(feature = "native")]
+pub use remote::{backend_from_location, HttpBackend, S3Backend, S3Credentials};
+pub use reporter::{JsonReporter, Reporter, ReporterKind, TracingReporter};
+#[cfg(feature = "native")]
+pub use reporter::ConsoleReporter;
+pub use routes::{discover_routes, render_routes_module, Route, ROUTES_MODULE};
+pub use side_effects::SideEffects;
+pub use snapshot::{GraphSnapshot, ModuleSnapshot};
+pub use sourcemap::SourceMap;
+pub use stats::{BuildStats, ChunkDelta, ChunkStats, StatsDiff, STATS_FILE};
+
+/// Options for a single build
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    pub outdir: Option<PathBuf>,
+    pub minify: bool,
+    pub sourcemap: bool,
+    pub target: String,
+    /// Record the output of every transform stage per module
+    pub inspect_transforms: bool,
+}
 
 /// Result of a build operation
-#[derive(Debug)]
+///
+/// Serializes to the report the JSON reporter prints when a build finishes,
+/// so tools embedding the bundler and those reading its output see the same
+/// data.
+#[derive(Debug, Serialize)]
 pub struct BuildResult {
-    /// Generated bundles
+    /// Generated bundles, pages and assets, in the order they were emitted
     pub bundles: Vec<BundleInfo>,
     
     /// Asset manifest
     pub manifest: HashMap<String, String>,
+    
+    /// Time spent in each build phase
+    pub timings: BuildTimings,
+    
+    /// Warnings reported during the build, in order
+    pub warnings: Vec<String>,
+    
+    /// package.json fields pointing at the entries, with `library.package_json`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_exports: Option<PackageExports>,
+}
+
+impl BuildResult {
+    /// Written files that aren't script chunks: pages, stylesheets, and
+    /// copied or generated assets
+    pub fn assets(&self) -> impl Iterator<Item = &BundleInfo> {
+        self.bundles.iter().filter(|bundle| bundle.kind != OutputKind::Chunk)
+    }
+}
+
+/// Duration of each build phase, serialized in milliseconds
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildTimings {
+    /// Module graph construction
+    #[serde(rename = "graph_ms", serialize_with = "serialize_ms")]
+    pub graph: Duration,
+    
+    /// Module transforms
+    #[serde(rename = "transform_ms", serialize_with = "serialize_ms")]
+    pub transform: Duration,
+    
+    /// Chunk generation
+    #[serde(rename = "chunks_ms", serialize_with = "serialize_ms")]
+    pub chunks: Duration,
+    
+    /// Writing bundles to disk
+    #[serde(rename = "write_ms", serialize_with = "serialize_ms")]
+    pub write: Duration,
+    
+    /// Manifest generation
+    #[serde(rename = "manifest_ms", serialize_with = "serialize_ms")]
+    pub manifest: Duration,
+    
+    /// Whole build
+    #[serde(rename = "total_ms", serialize_with = "serialize_ms")]
+    pub total: Duration,
+}
+
+fn serialize_ms<S: serde::Serializer>(duration: &Duration, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+/// A file produced by a build, before it is written to disk
+#[derive(Debug, Clone)]
+pub struct EmittedFile {
+    /// Path relative to the output directory
+    pub file_name: String,
+    
+    /// File contents
+    pub contents: Vec<u8>,
+    
+    /// What produced the file, for error messages (e.g. `entry 'main'`)
+    pub origin: String,
+    
+    /// Entry whose page loads the file up front, for manifests
+    pub entry: Option<String>,
 }
 
 /// Information about a generated bundle
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BundleInfo {
     /// Output file path
     pub output_path: PathBuf,
     
+    /// Path relative to the output directory
+    pub file_name: String,
+    
+    /// What the file is
+    pub kind: OutputKind,
+    
     /// Bundle size in bytes
     pub size: usize,
     
+    /// Size in bytes after gzip compression, as most servers send it
+    pub gzip_size: usize,
+    
+    /// Modules bundled into a chunk, relative to the project root; empty
+    /// for other files
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<String>,
+    
     /// Source map path (if generated)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sourcemap_path: Option<PathBuf>,
+    
+    /// Whether the file already had these contents and was left alone
+    pub unchanged: bool,
+}
+
+/// Kinds of files a build writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputKind {
+    /// An entry or async chunk of bundled modules
+    Chunk,
+    /// An HTML entry's page
+    Page,
+    /// Extracted CSS
+    Stylesheet,
+    /// Any other file: copied assets, locale messages, metadata
+    Asset,
+}
+
+/// A single module run through resolution and every transform
+#[derive(Debug, Serialize)]
+pub struct ModuleInspection {
+    pub path: PathBuf,
+    pub module_type: ModuleType,
+    pub dependencies: Vec<ResolvedDependency>,
+    
+    /// Output of the core transform and each plugin that ran
+    pub stages: Vec<TransformStage>,
+    
+    /// Final code
+    pub code: String,
+    
+    /// Source map returned by the last plugin that produced one
+    pub map: Option<String>,
+}
+
+/// An import specifier and what it resolved to
+#[derive(Debug, Serialize)]
+pub struct ResolvedDependency {
+    pub specifier: String,
+    pub resolved: Option<PathBuf>,
+    
+    /// Why resolution failed, when it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
+/// Directory under the root that generated modules appear to live in
+const CODEGEN_DIR: &str = "__component_codegen__";
+
+/// Output subdirectory of page assets, and with `output.split_by_type` of
+/// everything that isn't a script or stylesheet
+const ASSETS_DIR: &str = "assets";
+
+/// Delay before the first retry of a failed chunk load, doubled for each
+/// further attempt
+const CHUNK_RETRY_DELAY_MS: u64 = 500;
+
+/// Reload of the page after a chunk failed to load for good and no
+/// `component:chunk-error` listener prevented it, at most every 10 seconds so
+/// a broken deploy can't loop
+const CHUNK_RELOAD_RUNTIME: &str = r#" && (function() {
+      try {
+        var key = 'component:chunk-reload';
+        if (Date.now() - (Number(sessionStorage.getItem(key)) || 0) > 10000) {
+          sessionStorage.setItem(key, String(Date.now()));
+          location.reload();
+        }
+      } catch (e) {}
+    })()"#;
+
+/// Changed files at which watchers rebuild the whole graph instead of
+/// invalidating file by file, as after `git checkout` or `npm install`
+pub const BULK_CHANGE_THRESHOLD: usize = 20;
+
 /// The main bundler
 pub struct Bundler {
     /// Project configuration
@@ -55,256 +278,2392 @@ pub struct Bundler {
     /// Build options
     options: BuildOptions,
     
+    /// Filesystem sources are read from
+    fs: Arc<dyn FileSystem>,
+    
     /// Module resolver
-    resolver: Resolver,
+    resolver: Arc<Resolver>,
     
     /// Code transformer
     transformer: Transformer,
     
+    /// Plugins run after the core transform; replaced when `[[plugins]]` is reloaded
+    plugins: RwLock<Arc<PluginManager>>,
+    
+    /// Plugins `[[plugins]]` entries can name
+    registry: PluginRegistry,
+    
+    /// Output of the plugin codegen stage, run before the first graph build
+    codegen: RwLock<Option<Arc<CodegenOutput>>>,
+    
     /// Module graph
     graph: Arc<RwLock<ModuleGraph>>,
+    
+    /// Transform cache (shareable across builds)
+    cache: Arc<TransformCache>,
+    
+    /// Fingerprint of the config and lockfiles affecting transform output
+    transform_fingerprint: String,
+    
+    /// Worker processes core transforms run in, with `build.isolate_transforms`
+    #[cfg(feature = "native")]
+    workers: Option<WorkerPool>,
+    
+    /// Which modules have side effects, for tree shaking
+    side_effects: SideEffects,
+    
+    /// Stylesheets imported with `type: 'css'`, which compile to
+    /// constructable stylesheets
+    stylesheets: RwLock<HashSet<PathBuf>>,
+    
+    /// Receives build progress events
+    reporter: Arc<dyn Reporter>,
+    
+    /// Warnings reported since the current build started
+    warnings: Mutex<Vec<String>>,
 }
 
 impl Bundler {
     /// Create a new bundler instance
     pub fn new(config: Config, options: BuildOptions) -> Result<Self> {
         let config = Arc::new(config);
-        let resolver = Resolver::new(config.clone())?;
+        let resolver = Arc::new(Resolver::new(config.clone())?);
         let transformer = Transformer::new(config.clone())?;
+        let registry = PluginRegistry::default();
+        let graph = Arc::new(RwLock::new(ModuleGraph::new()));
+        let plugins = PluginManager::from_config(config.root.clone(), &config.plugins, &registry)?
+            .with_timeout(config.build.plugin_timeout())
+            .with_context(PluginContext::for_build(config.clone(), graph.clone()));
+        plugins.context().set_resolver(resolver.clone());
+        let transform_fingerprint = transform_fingerprint(&config, &RealFs)?;
+        let side_effects = SideEffects::new(&config.optimization.side_effects)?;
+        #[cfg(feature = "native")]
+        let workers = if config.build.isolate_transforms {
+            let workers = WorkerPool::new(&config)?;
+            if workers.is_none() {
+                tracing::warn!("build.isolate_transforms needs a component executable to run workers; transforming in-process");
+            }
+            workers
+        } else {
+            None
+        };
         
         Ok(Self {
             config,
             options,
+            fs: RealFs::shared(),
             resolver,
             transformer,
-            graph: Arc::new(RwLock::new(ModuleGraph::new())),
+            plugins: RwLock::new(Arc::new(plugins)),
+            registry,
+            codegen: RwLock::new(None),
+            graph,
+            cache: Arc::new(TransformCache::new()),
+            transform_fingerprint,
+            #[cfg(feature = "native")]
+            workers,
+            side_effects,
+            stylesheets: RwLock::new(HashSet::new()),
+            reporter: Arc::new(TracingReporter),
+            warnings: Mutex::new(Vec::new()),
         })
     }
     
+    /// Use a shared transform cache (e.g. to reuse results across builds)
+    pub fn with_cache(mut self, cache: Arc<TransformCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+    
+    /// Report build progress to the given reporter
+    pub fn with_reporter(mut self, reporter: Arc<dyn Reporter>) -> Self {
+        self.reporter = reporter;
+        self
+    }
+    
+    /// Create `[[plugins]]` entries from the given registry instead of the built-ins
+    pub fn with_plugin_registry(mut self, registry: PluginRegistry) -> Result<Self> {
+        let plugins = self.plugins.read().rebuild(&self.config.plugins, &registry)?;
+        self.plugins = RwLock::new(Arc::new(plugins));
+        self.registry = registry;
+        Ok(self)
+    }
+    
+    /// Run these plugins in addition to the `[[plugins]]` entries
+    ///
+    /// They run after the configured plugins unless their
+    /// [`order`](Plugin::order) says otherwise, and stay registered when the
+    /// plugin list is reloaded.
+    pub fn with_plugins(self, plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        self.add_plugins(plugins);
+        self
+    }
+    
+    /// Register plugins on a bundler that may be shared
+    pub(crate) fn add_plugins(&self, plugins: Vec<Arc<dyn Plugin>>) {
+        let mut current = self.plugins.write();
+        *current = Arc::new(current.extended(plugins));
+    }
+    
+    /// The context plugins' build, resolve and load hooks receive
+    pub fn plugin_context(&self) -> Arc<PluginContext> {
+        self.plugins.read().context()
+    }
+    
+    /// Read sources from the given filesystem instead of the real disk
+    ///
+    /// Output is still written to disk by `build()`; use `build_in_memory()`
+    /// for a build that never touches the real filesystem.
+    pub fn with_fs(mut self, fs: Arc<dyn FileSystem>) -> Result<Self> {
+        self.resolver = Arc::new(Resolver::with_fs(self.config.clone(), fs.clone())?);
+        self.plugins.read().context().set_resolver(self.resolver.clone());
+        self.transform_fingerprint = transform_fingerprint(&self.config, fs.as_ref())?;
+        self.fs = fs;
+        Ok(self)
+    }
+    
+    /// The transform cache used by this bundler
+    pub fn cache(&self) -> Arc<TransformCache> {
+        self.cache.clone()
+    }
+    
     /// Build the project
     pub async fn build(&self) -> Result<BuildResult> {
+        self.reporter.build_started(self.entrypoints().map_or(0, |entries| entries.len()));
+        match self.build_project().await {
+            Ok(result) => {
+                self.reporter.build_finished(&result);
+                Ok(result)
+            }
+            Err(e) => {
+                self.reporter.build_failed(&e);
+                Err(e)
+            }
+        }
+    }
+    
+    async fn build_project(&self) -> Result<BuildResult> {
         let start = Instant::now();
+        let mut timings = BuildTimings::default();
+        self.plugins.read().reset_timings();
+        self.warnings.lock().clear();
         
         // 1. Build the module graph from entrypoints
         info!("Building module graph...");
+        let phase = Instant::now();
         self.build_module_graph().await?;
+        self.check_missing_files()?;
+        self.check_imports()?;
+        self.check_import_attributes()?;
+        timings.graph = phase.elapsed();
+        
+        let licenses = self.check_licenses()?;
         
         // 2. Transform all modules
         info!("Transforming modules...");
+        let phase = Instant::now();
         self.transform_modules().await?;
+        timings.transform = phase.elapsed();
         
         // 3. Generate chunks
         info!("Generating chunks...");
+        let phase = Instant::now();
         let chunks = self.generate_chunks()?;
+        timings.chunks = phase.elapsed();
+        
+        // 4. Render output bundles
+        let phase = Instant::now();
+        let files = self.render_bundles(&chunks)?;
+        self.check_library_parity(&files)?;
+        timings.write = phase.elapsed();
+        
+        // 5. Generate manifest and provenance
+        let phase = Instant::now();
+        let manifest = self.generate_manifest(&files);
+        let manifest_file: Vec<EmittedFile> = self.render_manifest(&files, &manifest)?.into_iter().collect();
+        let mut metadata: Vec<EmittedFile> = render_licenses(licenses.as_ref())?;
+        metadata.extend(self.render_stats(&chunks, &files)?);
+        metadata.extend(self.render_declarations(&files)?);
+        metadata.extend(self.render_provenance(files.iter().chain(&manifest_file).chain(&metadata))?);
+        check_collisions(files.iter().chain(&manifest_file).chain(&metadata))?;
+        let package_exports = self.package_exports(&files)?;
+        timings.manifest = phase.elapsed();
         
-        // 4. Write output bundles
+        // 6. Write everything once no two outputs share a file
         info!("Writing bundles...");
-        let bundles = self.write_bundles(&chunks)?;
+        let phase = Instant::now();
+        let bundles = self.write_bundles(&files, &self.chunk_modules(&chunks, &files))?;
+        for file in &files {
+            self.reporter.chunk_emitted(&file.file_name, file.contents.len());
+        }
+        self.write_files(&metadata)?;
+        // The manifest goes last: once it's updated, every file it lists is
+        self.write_files(&manifest_file)?;
+        timings.write += phase.elapsed();
+        
+        timings.total = start.elapsed();
+        debug!("Build completed in {:?}", timings.total);
         
-        // 5. Generate manifest
-        let manifest = self.generate_manifest(&bundles)?;
+        let warnings = std::mem::take(&mut *self.warnings.lock());
+        Ok(BuildResult { bundles, manifest, timings, warnings, package_exports })
+    }
+    
+    /// Build the project without writing to disk
+    ///
+    /// Returns every emitted file keyed by its path relative to the output
+    /// directory.
+    pub async fn build_in_memory(&self) -> Result<BTreeMap<String, Vec<u8>>> {
+        self.build_module_graph().await?;
+        self.check_missing_files()?;
+        self.check_imports()?;
+        self.check_import_attributes()?;
+        let licenses = self.check_licenses()?;
+        self.transform_modules().await?;
         
-        debug!("Build completed in {:?}", start.elapsed());
+        let chunks = self.generate_chunks()?;
+        let mut files = self.render_bundles(&chunks)?;
+        self.check_library_parity(&files)?;
+        let manifest = self.generate_manifest(&files);
+        files.extend(self.render_manifest(&files, &manifest)?);
+        files.extend(render_licenses(licenses.as_ref())?);
+        files.extend(self.render_stats(&chunks, &files)?);
+        files.extend(self.render_provenance(&files)?);
+        check_collisions(&files)?;
         
-        Ok(BuildResult { bundles, manifest })
+        Ok(files.into_iter().map(|file| (file.file_name, file.contents)).collect())
     }
     
-    /// Build the module graph by traversing from entrypoints
-    async fn build_module_graph(&self) -> Result<()> {
-        let entrypoints = self.config.all_entrypoints();
+    /// Build and transform the module graph without emitting bundles
+    ///
+    /// Used by the dev server, which serves modules from the live graph.
+    pub async fn build_graph(&self) -> Result<()> {
+        self.build_module_graph().await?;
+        self.transform_modules().await
+    }
+    
+    /// Drop the module graph and generated modules, then build it again
+    ///
+    /// Unchanged modules still come from the transform cache.
+    pub async fn rebuild_graph(&self) -> Result<()> {
+        *self.graph.write() = ModuleGraph::new();
+        *self.codegen.write() = None;
+        self.build_graph().await
+    }
+    
+    /// Snapshot the module graph for [`restore_graph`](Self::restore_graph);
+    /// `None` while a module has not been transformed
+    pub fn snapshot(&self) -> Result<Option<GraphSnapshot>> {
+        let fingerprint = snapshot::fingerprint(&self.config, self.fs.as_ref())?;
+        Ok(GraphSnapshot::capture(&self.graph.read(), fingerprint, self.fs.as_ref()))
+    }
+    
+    /// Build the module graph from a snapshot instead of from scratch
+    ///
+    /// Modules whose source or watch files changed since the snapshot are
+    /// read, resolved and transformed again. Returns how many were, or `None`
+    /// without touching the graph when the snapshot can't be used: it was
+    /// taken with another version, config or lockfile, the entrypoints
+    /// changed, or one of its modules no longer exists.
+    pub async fn restore_graph(&self, snapshot: GraphSnapshot) -> Result<Option<usize>> {
+        // Snapshots don't keep per-stage output
+        if self.options.inspect_transforms {
+            return Ok(None);
+        }
+        if snapshot.fingerprint != snapshot::fingerprint(&self.config, self.fs.as_ref())? {
+            return Ok(None);
+        }
+        if self.codegen.read().is_none() {
+            self.run_codegen().await?;
+        }
         
-        for (name, path) in entrypoints {
-            debug!("Processing entrypoint: {} -> {}", name, path.display());
-            self.process_module(&path, true).await?;
+        let entries: BTreeSet<PathBuf> = self.entrypoints()?
+            .into_iter()
+            .map(|(_, path)| self.fs.canonicalize(&path).unwrap_or(path))
+            .collect();
+        let saved: BTreeSet<PathBuf> = snapshot.modules.iter().filter(|m| m.is_entry).map(|m| m.path.clone()).collect();
+        if entries != saved {
+            return Ok(None);
+        }
+        
+        // A deleted module may change how its importers resolve
+        let mut sources = Vec::with_capacity(snapshot.modules.len());
+        for module in &snapshot.modules {
+            let stamp = self.file_stamp(&module.path);
+            match self.read_module(&module.path) {
+                Ok(source) => sources.push((source, stamp)),
+                Err(_) => return Ok(None),
+            }
+        }
+        
+        let mut stale = Vec::new();
+        let context = self.plugins.read().context();
+        {
+            let mut graph = self.graph.write();
+            *graph = ModuleGraph::new();
+            
+            let mut edges = Vec::with_capacity(snapshot.modules.len());
+            let mut ids = Vec::with_capacity(snapshot.modules.len());
+            for (saved, (source, stamp)) in snapshot.modules.into_iter().zip(sources) {
+                let changed = crate::utils::hash_content(source.as_bytes()) != saved.source_hash
+                    || saved.watch_files
+                        .iter()
+                        .any(|(file, hash)| snapshot::file_hash(self.fs.as_ref(), file).as_ref() != Some(hash));
+                if changed {
+                    stale.push(saved.path.clone());
+                }
+                
+                edges.push((saved.imports, saved.dynamic_imports, saved.resolved));
+                if saved.module_type == ModuleType::StyleSheet {
+                    self.stylesheets.write().insert(saved.path.clone());
+                }
+                context.restore_meta(&saved.path.display().to_string(), saved.meta.clone());
+                ids.push(graph.add_module(Module {
+                    exports: self.resolver.extract_exports(&source, &saved.module_type),
+                    path: saved.path,
+                    source,
+                    module_type: saved.module_type,
+                    is_entry: saved.is_entry,
+                    dependencies: saved.dependencies,
+                    transformed: (!changed).then_some(saved.transformed),
+                    transform_time: None,
+                    invalidations: 0,
+                    transform_stages: Vec::new(),
+                    provenance: saved.provenance,
+                    watch_files: saved.watch_files.into_iter().map(|(file, _)| file).collect(),
+                    meta: saved.meta,
+                    stamp,
+                }));
+            }
+            
+            for (&id, (imports, dynamic_imports, resolved)) in ids.iter().zip(edges) {
+                for dep in imports.into_iter().filter_map(|i| ids.get(i)) {
+                    graph.add_dependency(id, *dep);
+                }
+                for dep in dynamic_imports.into_iter().filter_map(|i| ids.get(i)) {
+                    graph.add_dynamic_dependency(id, *dep);
+                }
+                for (specifier, dep) in resolved.into_iter().filter_map(|(s, i)| Some((s, *ids.get(i)?))) {
+                    graph.set_resolved(id, &specifier, dep);
+                }
+            }
+        }
+        
+        for path in &stale {
+            self.invalidate_module(path).await?;
+        }
+        Ok(Some(stale.len()))
+    }
+    
+    /// Whether a batch of changed paths is large enough for [`rebuild_graph`](Self::rebuild_graph)
+    ///
+    /// The output directory and hidden directories such as the build cache
+    /// don't count, so a build's own writes never trigger one.
+    pub fn is_bulk_change(&self, paths: &[PathBuf]) -> bool {
+        let root = self.canonical_root();
+        let output_dir = self.output_dir();
+        let output_dir = self.fs.canonicalize(&output_dir).unwrap_or(output_dir);
+        
+        let counted = paths.iter().filter(|path| {
+            let hidden = path.strip_prefix(&root).is_ok_and(|relative| {
+                relative.components().any(|c| c.as_os_str().to_str().is_some_and(|name| name.starts_with('.')))
+            });
+            !hidden && !path.starts_with(&output_dir)
+        });
+        counted.count() >= BULK_CHANGE_THRESHOLD
+    }
+    
+    /// Resolve an import specifier as the bundler would
+    pub fn resolve(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
+        self.resolve_dependency(specifier, from)
+    }
+    
+    /// Chunks for the current module graph
+    pub fn chunks(&self) -> Result<Vec<Chunk>> {
+        self.generate_chunks()
+    }
+    
+    /// Licenses of the `node_modules` packages in the current module graph
+    pub fn licenses(&self) -> Result<LicenseReport> {
+        let graph = self.graph.read();
+        let modules: Vec<&Module> = graph.all_module_ids()
+            .into_iter()
+            .filter_map(|id| graph.get_module(id))
+            .collect();
+        LicenseReport::collect(modules.iter().map(|m| m.path.as_path()), &self.canonical_root(), self.fs.as_ref())
+    }
+    
+    /// Report a warning, and keep it for the build's result
+    fn warn(&self, message: &str) {
+        self.warnings.lock().push(message.to_string());
+        self.reporter.warning(message);
+    }
+    
+    /// Run one file through resolution and every transform, without building the graph
+    ///
+    /// Skips the transform cache so the output reflects the current
+    /// transformer and plugins.
+    pub async fn inspect(&self, path: &Path) -> Result<ModuleInspection> {
+        if self.codegen.read().is_none() {
+            self.run_codegen().await?;
+        }
+        
+        let path = self.canonical_module_path(path)
+            .with_context(|| format!("Failed to resolve module path: {}", path.display()))?;
+        let source = self.read_module(&path)?;
+        let module_type = self.module_type(&path);
+        
+        let dependencies = self.resolver.extract_dependencies(&source, &path, &module_type)?;
+        let dependencies = distinct_specifiers(&dependencies)
+            .into_iter()
+            .map(|(specifier, _)| {
+                let (resolved, error) = match self.resolve_dependency(&specifier, &path) {
+                    Ok(resolved) => (resolved, None),
+                    Err(e) => (None, Some(format!("{:#}", e))),
+                };
+                ResolvedDependency { specifier, resolved, error }
+            })
+            .collect();
+        
+        let started = Instant::now();
+        let transformed = self.transformer.transform(&source, &path, &module_type)?;
+        let mut stages = vec![TransformStage {
+            name: format!("core:{:?}", module_type).to_lowercase(),
+            changed: transformed != source,
+            code: transformed.clone(),
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        }];
+        
+        let plugins = self.plugins.read().clone();
+        let output = plugins
+            .transform_traced(&transformed, &path.display().to_string(), Some(&mut stages))
+            .await?;
+        
+        Ok(ModuleInspection {
+            path,
+            module_type,
+            dependencies,
+            stages,
+            code: output.code,
+            map: output.map,
+        })
+    }
+    
+    /// Transformed code of a script module, for the dev server
+    ///
+    /// Modules in the graph are served as last transformed; others, such as
+    /// a file no entry imports yet, are transformed when requested. With
+    /// source maps enabled the code ends in an inline map, so devtools show
+    /// the original TypeScript or JSX. Returns `None` for other files.
+    pub async fn served_module(&self, path: &Path) -> Result<Option<String>> {
+        let canonical = self.canonical_module_path(path)?;
+        let module_type = self.module_type(&canonical);
+        if !module_type.is_js_like() {
+            return Ok(None);
+        }
+        let (source, code) = self.transformed_file(&canonical, module_type).await?;
+        if !self.options.sourcemap {
+            return Ok(Some(code));
+        }
+        
+        let relative = canonical.strip_prefix(self.canonical_root()).unwrap_or(&canonical);
+        let url = format!("{}{}", self.config.output.base_path(), path_to_module_id(relative));
+        let map = SourceMap::for_module(&source, &code, &url, &self.config.sourcemap);
+        Ok(Some(format!("{}\n{}\n", code.trim_end(), map.to_inline_comment())))
+    }
+    
+    /// Transformed code of a stylesheet, JSON or GraphQL file a script
+    /// imports, for the dev server
+    ///
+    /// The code is a CommonJS module, as bundles hold it. Returns `None` for
+    /// other files.
+    pub async fn served_import(&self, path: &Path) -> Result<Option<String>> {
+        let canonical = self.canonical_module_path(path)?;
+        let module_type = Module::detect_type(&canonical);
+        if !matches!(module_type, ModuleType::Css | ModuleType::Json | ModuleType::Graphql) {
+            return Ok(None);
+        }
+        let (_, code) = self.transformed_file(&canonical, module_type).await?;
+        Ok(Some(code))
+    }
+    
+    /// Source and transformed code of a file, from the graph when it holds
+    /// the file as `module_type`, and otherwise transformed now through the
+    /// transform cache and plugins
+    async fn transformed_file(&self, path: &Path, module_type: ModuleType) -> Result<(String, String)> {
+        {
+            let graph = self.graph.read();
+            let module = graph.get_module_id(path).and_then(|id| graph.get_module(id));
+            if let Some(module) = module.filter(|m| m.module_type == module_type) {
+                if let Some(code) = &module.transformed {
+                    return Ok((module.source.clone(), code.clone()));
+                }
+            }
+        }
+        
+        let source = self.read_module(path)?;
+        let root = self.canonical_root();
+        let key_path = path.strip_prefix(&root).unwrap_or(path);
+        let cache_key = TransformCache::key(&source, key_path, &module_type, &self.transform_fingerprint);
+        let transformed = match self.cache.get(&cache_key) {
+            Some(cached) => cached,
+            None => {
+                let transformed = self.core_transform(&source, path, &module_type)?;
+                self.cache.insert(cache_key, transformed.clone());
+                transformed
+            }
+        };
+        let plugins = self.plugins.read().clone();
+        let output = plugins.transform_traced(&transformed, &path.display().to_string(), None).await?;
+        Ok((source, output.code))
+    }
+    
+    /// Resolve a bare specifier to an installed package's file, as the
+    /// dev server does for `/@deps/`
+    pub fn resolve_package(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
+        self.resolver.resolve_bare(specifier, from)
+    }
+    
+    /// Shared handle to the module graph
+    pub fn graph(&self) -> Arc<RwLock<ModuleGraph>> {
+        self.graph.clone()
+    }
+    
+    /// Re-read and re-transform a changed module already in the graph
+    ///
+    /// Newly imported modules are added to the graph, as are new files
+    /// matching an entrypoint glob. Returns `None` when the path is not part
+    /// of the graph.
+    /// Modules whose transform read the path through
+    /// [`add_watch_file`](crate::plugins::PluginContext::add_watch_file) are
+    /// transformed again.
+    pub async fn invalidate(&self, path: &Path) -> Result<Option<ModuleId>> {
+        let mut invalidated = self.invalidate_module(path).await?;
+        
+        // Adding or removing a page changes the generated routes module
+        if invalidated.is_none() {
+            if let Some(dir) = self.routes_dir().filter(|dir| path.starts_with(dir)) {
+                invalidated = self.invalidate_module(&routes::routes_module_path(&dir)).await?;
+            }
+        }
+        
+        // A new file matching an entrypoint glob is a new entry
+        let relative = path.strip_prefix(self.canonical_root()).or_else(|_| path.strip_prefix(&self.config.root));
+        let is_new_entry = relative.is_ok_and(|relative| entries::matches_entry_glob(&self.config, relative))
+            && !self.contains(path);
+        if invalidated.is_none() && is_new_entry && self.fs.is_file(path) {
+            let id = self.process_module(path, true).await?;
+            self.transform_modules().await?;
+            invalidated = Some(id);
+        }
+        
+        let inline = self.invalidate_inline_blocks(path).await?;
+        let watchers = self.invalidate_watchers(path).await?;
+        let generated = self.invalidate_codegen(path).await?;
+        Ok(invalidated.or(inline).or(watchers).or(generated))
+    }
+    
+    /// Refresh the inline scripts and styles of a changed page
+    async fn invalidate_inline_blocks(&self, path: &Path) -> Result<Option<ModuleId>> {
+        let Ok(page) = self.fs.canonicalize(path) else {
+            return Ok(None);
+        };
+        let blocks: Vec<PathBuf> = {
+            let graph = self.graph.read();
+            graph.all_module_ids()
+                .into_iter()
+                .filter_map(|id| graph.get_module(id))
+                .filter(|module| html::inline_module_page(&module.path).is_some_and(|(p, _)| p == page))
+                .map(|module| module.path.clone())
+                .collect()
+        };
+        
+        let mut invalidated = None;
+        for block in blocks {
+            invalidated = invalidated.or(self.invalidate_module(&block).await?);
+        }
+        Ok(invalidated)
+    }
+    
+    /// Paths of the modules at `paths` and every module importing them
+    ///
+    /// Paths outside the graph are skipped.
+    pub fn affected_files(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let graph = self.graph.read();
+        let changed: Vec<ModuleId> = paths
+            .iter()
+            .filter_map(|path| graph.get_module_id(&self.canonical_module_path(path).ok()?))
+            .collect();
+        graph.get_affected_modules(&changed)
+            .into_iter()
+            .filter_map(|id| graph.get_module(id).map(|m| m.path.clone()))
+            .collect()
+    }
+    
+    /// Entrypoints by name, with globs expanded against the current files
+    pub fn entrypoints(&self) -> Result<Vec<(String, PathBuf)>> {
+        entries::expand_entrypoints(&self.config, self.fs.as_ref())
+    }
+    
+    /// Files outside the graph that plugins read while transforming or
+    /// generating modules, and modules outside the project root, sorted
+    pub fn watch_files(&self) -> Vec<PathBuf> {
+        let graph = self.graph.read();
+        let root = self.canonical_root();
+        let mut files: BTreeSet<PathBuf> = graph.all_module_ids()
+            .into_iter()
+            .filter_map(|id| graph.get_module(id))
+            .flat_map(|m| {
+                // Modules outside the root, like workspace package sources
+                let outside = (!m.path.starts_with(&root)).then(|| m.path.clone());
+                m.watch_files.iter().cloned().chain(outside)
+            })
+            .collect();
+        if let Some(codegen) = self.codegen.read().as_ref() {
+            files.extend(codegen.watch_files.iter().cloned());
+        }
+        files.into_iter().collect()
+    }
+    
+    /// Whether a module's last transform, or the codegen stage, read this file
+    pub fn is_watch_file(&self, path: &Path) -> bool {
+        let path = self.fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        !self.watchers_of(&path).is_empty() || self.is_codegen_input(&path)
+    }
+    
+    fn is_codegen_input(&self, path: &Path) -> bool {
+        self.codegen.read().as_ref().is_some_and(|codegen| codegen.watch_files.iter().any(|f| f == path))
+    }
+    
+    /// Run the plugin codegen stage and keep its output for resolution
+    async fn run_codegen(&self) -> Result<Arc<CodegenOutput>> {
+        let plugins = self.plugins.read().clone();
+        let mut output = plugins.run_codegen().await?;
+        for file in &mut output.watch_files {
+            if let Ok(canonical) = self.fs.canonicalize(file) {
+                *file = canonical;
+            }
+        }
+        
+        let output = Arc::new(output);
+        *self.codegen.write() = Some(output.clone());
+        Ok(output)
+    }
+    
+    /// Generate modules again when one of their inputs changed; `None` otherwise
+    async fn invalidate_codegen(&self, path: &Path) -> Result<Option<ModuleId>> {
+        let path = self.fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if !self.is_codegen_input(&path) {
+            return Ok(None);
+        }
+        
+        let output = self.run_codegen().await?;
+        let dir = self.codegen_dir();
+        let mut invalidated = None;
+        for module in &output.modules {
+            if let Some(id) = self.invalidate_module(&dir.join(&module.id)).await? {
+                invalidated.get_or_insert(id);
+            }
+        }
+        Ok(invalidated)
+    }
+    
+    fn watchers_of(&self, path: &Path) -> Vec<ModuleId> {
+        let graph = self.graph.read();
+        graph.all_module_ids()
+            .into_iter()
+            .filter(|&id| graph.get_module(id).is_some_and(|m| m.watch_files.iter().any(|f| f == path)))
+            .collect()
+    }
+    
+    /// Transform the modules watching a file again; `None` when none do
+    async fn invalidate_watchers(&self, path: &Path) -> Result<Option<ModuleId>> {
+        let path = self.fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let watchers = self.watchers_of(&path);
+        if watchers.is_empty() {
+            return Ok(None);
+        }
+        
+        {
+            let mut graph = self.graph.write();
+            for &id in &watchers {
+                if let Some(module) = graph.get_module_mut(id) {
+                    module.transformed = None;
+                    module.invalidations += 1;
+                }
+            }
+        }
+        
+        self.transform_modules().await?;
+        Ok(watchers.first().copied())
+    }
+    
+    /// Re-read a module in the graph
+    ///
+    /// `None` when it is not part of the graph, or its file is unchanged:
+    /// it has the size and modification time it was last read with, or
+    /// the same contents.
+    async fn invalidate_module(&self, path: &Path) -> Result<Option<ModuleId>> {
+        let Ok(canonical_path) = self.canonical_module_path(path) else {
+            return Ok(None);
+        };
+        
+        let Some(module_id) = self.graph.read().get_module_id(&canonical_path) else {
+            return Ok(None);
+        };
+        
+        let stamp = self.file_stamp(&canonical_path);
+        let is_fresh = |module: &Module| module.transformed.is_some() && module.stamp.is_some() && module.stamp == stamp;
+        if self.graph.read().get_module(module_id).is_some_and(is_fresh) {
+            return Ok(None);
+        }
+        
+        let source = self.read_module(&canonical_path)?;
+        {
+            let mut graph = self.graph.write();
+            if let Some(module) = graph.get_module_mut(module_id).filter(|m| m.transformed.is_some() && m.source == source) {
+                module.stamp = stamp;
+                return Ok(None);
+            }
+        }
+        
+        let module_type = self.module_type(&canonical_path);
+        let dependencies = self.resolver.extract_dependencies(&source, &canonical_path, &module_type)?;
+        let specifiers = distinct_specifiers(&dependencies);
+        let side_effect_imports = self.side_effect_imports(&source, &module_type);
+        let exports = self.resolver.extract_exports(&source, &module_type);
+        let stylesheet_imports = stylesheet_imports(&dependencies);
+        let required = required_specifiers(&dependencies);
+        
+        {
+            let mut graph = self.graph.write();
+            if let Some(module) = graph.get_module_mut(module_id) {
+                module.source = source;
+                module.stamp = stamp;
+                module.dependencies = dependencies;
+                module.exports = exports;
+                module.transformed = None;
+                module.invalidations += 1;
+            }
+            graph.clear_dependencies(module_id);
+        }
+        
+        for (dep, dynamic) in specifiers {
+            let kind = if required.contains(&dep) { ImportKind::Require } else { ImportKind::Static };
+            if let Some(resolved_path) = self.resolve_dependency_as(&dep, &canonical_path, kind)? {
+                if side_effect_imports.contains(&dep) && !self.has_side_effects(&resolved_path) {
+                    continue;
+                }
+                if stylesheet_imports.contains(&dep) {
+                    self.add_stylesheet(&resolved_path);
+                }
+                let dep_id = self.process_module(&resolved_path, false).await?;
+                self.add_edge(module_id, &dep, dep_id, dynamic);
+            }
+        }
+        
+        self.transform_modules().await?;
+        
+        Ok(Some(module_id))
+    }
+    
+    /// Time each plugin spent in its hooks during the last build, longest
+    /// first
+    pub fn plugin_timings(&self) -> Vec<PluginTiming> {
+        self.plugins.read().timings()
+    }
+    
+    /// Apply a new `[[plugins]]` list to the live graph
+    ///
+    /// Only plugins whose entry changed are re-instantiated, and only the
+    /// modules whose provenance names them are transformed again. A newly added plugin
+    /// may transform anything, so adding one re-transforms every module.
+    /// Returns the names of the reloaded plugins.
+    pub async fn reload_plugins(&self, configs: &[PluginConfig]) -> Result<Vec<String>> {
+        let current = self.plugins.read().clone();
+        let (next, changed) = current.reconfigure(configs, &self.registry)?;
+        if changed.is_empty() {
+            return Ok(changed);
+        }
+        
+        let stages: Option<Vec<String>> = changed
+            .iter()
+            .all(|name| current.has_plugin(name))
+            .then(|| changed.iter().map(|name| format!("plugin:{}", name)).collect());
+        *self.plugins.write() = Arc::new(next);
+        
+        {
+            let mut graph = self.graph.write();
+            for id in graph.all_module_ids() {
+                if let Some(module) = graph.get_module_mut(id) {
+                    if stages.as_ref().is_none_or(|stages| module.provenance.iter().any(|s| stages.contains(s))) {
+                        module.transformed = None;
+                        module.invalidations += 1;
+                    }
+                }
+            }
+        }
+        
+        self.transform_modules().await?;
+        Ok(changed)
+    }
+    
+    /// Fail on relative and absolute imports of files that don't exist,
+    /// which the graph otherwise leaves out without a word
+    ///
+    /// Only the `import` statements and `import()` calls of scripts outside
+    /// packages are checked: `require()` of a missing file may sit in a
+    /// `try`, and missing `new URL()` assets are already warned about.
+    fn check_missing_files(&self) -> Result<()> {
+        let root = self.canonical_root();
+        let mut unresolved = Vec::new();
+        {
+            let graph = self.graph.read();
+            let mut ids = graph.all_module_ids();
+            ids.sort_unstable();
+            for id in ids {
+                let Some(module) = graph.get_module(id).filter(|m| m.module_type.is_js_like() && !m.path.components().any(|c| c.as_os_str() == "node_modules")) else {
+                    continue;
+                };
+                for dependency in &module.dependencies {
+                    let relative = dependency.specifier.starts_with('.') || dependency.specifier.starts_with('/');
+                    if !matches!(dependency.kind, ImportKind::Static | ImportKind::Dynamic) || !relative || graph.get_resolved(id, &dependency.specifier).is_some() {
+                        continue;
+                    }
+                    let (line, column) = dependency.position(&module.source);
+                    let location = format!("{}:{}:{}", path_to_module_id(module.path.strip_prefix(&root).unwrap_or(&module.path)), line + 1, column + 1);
+                    unresolved.push((location, module.path.clone(), dependency.specifier.clone(), dependency.kind));
+                }
+            }
+        }
+        
+        // Imports of modules without side effects resolved but have no edge
+        let mut missing = Vec::new();
+        for (location, from, specifier, kind) in unresolved {
+            if self.resolve_dependency_as(&specifier, &from, kind)?.is_none() {
+                missing.push(format!("  {}: '{}' does not exist", location, specifier));
+            }
+        }
+        if !missing.is_empty() {
+            anyhow::bail!("Some imports point at files that don't exist:\n{}", missing.join("\n"));
+        }
+        Ok(())
+    }
+    
+    /// Fail on named imports and re-exports of names their modules don't
+    /// export, which would otherwise only fail in the browser
+    ///
+    /// Modules whose exports are only known at runtime, such as CommonJS and
+    /// JSON modules, are not checked, nor are the imports of packages.
+    fn check_imports(&self) -> Result<()> {
+        let root = self.canonical_root();
+        let graph = self.graph.read();
+        let relative = |module: &Module| path_to_module_id(module.path.strip_prefix(&root).unwrap_or(&module.path));
+        let mut ids = graph.all_module_ids();
+        ids.sort_unstable();
+        
+        let mut missing = Vec::new();
+        for id in ids {
+            let Some(module) = graph.get_module(id).filter(|m| !m.path.components().any(|c| c.as_os_str() == "node_modules")) else {
+                continue;
+            };
+            let imports = module.dependencies.iter().flat_map(|d| d.names.iter().map(move |name| (&d.specifier, name)));
+            let reexports = module.exports.reexports.iter().filter(|r| r.imported != "*").map(|r| (&r.specifier, &r.imported));
+            for (specifier, name) in imports.chain(reexports) {
+                let Some(target) = graph.get_resolved(id, specifier).filter(|&t| graph.provides_export(t, name) == Some(false)) else {
+                    continue;
+                };
+                let location = match module.dependencies.iter().find(|d| d.specifier == *specifier) {
+                    Some(dependency) => {
+                        let (line, column) = dependency.position(&module.source);
+                        format!("{}:{}:{}", relative(module), line + 1, column + 1)
+                    }
+                    None => relative(module),
+                };
+                let target = graph.get_module(target).map(relative).unwrap_or_default();
+                missing.push(format!("  {}: '{}' is not exported by {}", location, name, target));
+            }
+        }
+        if !missing.is_empty() {
+            anyhow::bail!("Some imports name exports their modules don't have:\n{}", missing.join("\n"));
+        }
+        Ok(())
+    }
+    
+    /// Fail on imports whose `type` attribute doesn't match the module they
+    /// import, which browsers would refuse to load
+    ///
+    /// A stylesheet is either added to the page or imported with
+    /// `type: 'css'` as a constructable stylesheet, not both.
+    fn check_import_attributes(&self) -> Result<()> {
+        let root = self.canonical_root();
+        let graph = self.graph.read();
+        let relative = |module: &Module| path_to_module_id(module.path.strip_prefix(&root).unwrap_or(&module.path));
+        let mut ids = graph.all_module_ids();
+        ids.sort_unstable();
+        
+        let mut mismatched = Vec::new();
+        for id in ids {
+            let Some(module) = graph.get_module(id) else {
+                continue;
+            };
+            for dependency in &module.dependencies {
+                let Some(target) = graph.get_resolved(id, &dependency.specifier).and_then(|target| graph.get_module(target)) else {
+                    continue;
+                };
+                let problem = match (dependency.import_type(), &target.module_type) {
+                    (Some("json"), ModuleType::Json) | (Some("css"), ModuleType::StyleSheet) => continue,
+                    (None, ModuleType::StyleSheet) => "is imported with type 'css' elsewhere, so it isn't added to the page".to_string(),
+                    (None, _) => continue,
+                    (Some("css"), ModuleType::Css) => "is added to the page elsewhere, so it can't also be imported with type 'css'".to_string(),
+                    (Some(kind @ ("json" | "css")), _) => format!("is imported with type '{}' but isn't {}", kind, if kind == "json" { "JSON" } else { "a stylesheet" }),
+                    (Some(kind), _) => format!("is imported with unsupported type '{}'", kind),
+                };
+                let (line, column) = dependency.position(&module.source);
+                mismatched.push(format!("  {}:{}:{}: '{}' {}", relative(module), line + 1, column + 1, dependency.specifier, problem));
+            }
+        }
+        if !mismatched.is_empty() {
+            anyhow::bail!("Some imports have attributes their modules don't match:\n{}", mismatched.join("\n"));
+        }
+        Ok(())
+    }
+    
+    /// Collect the license report if enabled, failing on denied licenses
+    ///
+    /// Runs right after the graph is built so a denied dependency fails the
+    /// build before anything is written.
+    fn check_licenses(&self) -> Result<Option<LicenseReport>> {
+        let config = &self.config.licenses;
+        if !config.report && config.deny.is_empty() {
+            return Ok(None);
+        }
+        
+        let report = self.licenses()?;
+        report.check(&config.deny)?;
+        Ok(config.report.then_some(report))
+    }
+    
+    /// Build the module graph by traversing from entrypoints
+    async fn build_module_graph(&self) -> Result<()> {
+        if self.codegen.read().is_none() {
+            self.run_codegen().await?;
+        }
+        
+        let entrypoints = self.entrypoints()?;
+        
+        for (name, path) in entrypoints {
+            debug!("Processing entrypoint: {} -> {}", name, path.display());
+            self.process_module(&path, true).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Process a single module and its dependencies
+    /// 
+    /// Uses Box::pin for async recursion to avoid infinite type size issues
+    async fn process_module(&self, path: &Path, is_entry: bool) -> Result<ModuleId> {
+        let canonical_path = self.canonical_module_path(path)
+            .with_context(|| format!("Failed to resolve module path: {}", path.display()))?;
+        
+        // Check if already processed
+        {
+            let graph = self.graph.read();
+            if let Some(id) = graph.get_module_id(&canonical_path) {
+                return Ok(id);
+            }
+        }
+        
+        // Read module source
+        let stamp = self.file_stamp(&canonical_path);
+        let source = self.read_module(&canonical_path)?;
+        
+        // Determine module type from extension
+        let module_type = self.module_type(&canonical_path);
+        
+        // Parse and extract dependencies
+        let dependencies = self.resolver.extract_dependencies(&source, &canonical_path, &module_type)?;
+        let specifiers = distinct_specifiers(&dependencies);
+        let side_effect_imports = self.side_effect_imports(&source, &module_type);
+        let exports = self.resolver.extract_exports(&source, &module_type);
+        let stylesheet_imports = stylesheet_imports(&dependencies);
+        let required = required_specifiers(&dependencies);
+        
+        // Create module
+        let module = Module {
+            path: canonical_path.clone(),
+            source,
+            module_type,
+            is_entry,
+            dependencies,
+            exports,
+            transformed: None,
+            transform_time: None,
+            invalidations: 0,
+            transform_stages: Vec::new(),
+            provenance: Vec::new(),
+            watch_files: Vec::new(),
+            meta: self.plugins.read().context().get_meta(&canonical_path.display().to_string()),
+            stamp,
+        };
+        
+        // Add to graph
+        let module_id = {
+            let mut graph = self.graph.write();
+            graph.add_module(module)
+        };
+        
+        // Process dependencies recursively (Box::pin needed for async recursion)
+        for (dep, dynamic) in specifiers {
+            let kind = if required.contains(&dep) { ImportKind::Require } else { ImportKind::Static };
+            let resolved = self.resolve_dependency_as(&dep, &canonical_path, kind)?;
+            if let Some(resolved_path) = resolved {
+                // Importing a module without side effects for them does nothing
+                if side_effect_imports.contains(&dep) && !self.has_side_effects(&resolved_path) {
+                    debug!("Dropping import of side-effect-free module {}", resolved_path.display());
+                    continue;
+                }
+                if stylesheet_imports.contains(&dep) {
+                    self.add_stylesheet(&resolved_path);
+                }
+                let dep_id = Box::pin(self.process_module(&resolved_path, false)).await?;
+                self.add_edge(module_id, &dep, dep_id, dynamic);
+            }
+        }
+        
+        Ok(module_id)
+    }
+    
+    /// A module's type: by extension, except for stylesheets imported with
+    /// `type: 'css'` and the modules `?srcset=` imports generate
+    fn module_type(&self, path: &Path) -> ModuleType {
+        if images::srcset_module(path).is_some() {
+            return ModuleType::JavaScript;
+        }
+        match Module::detect_type(path) {
+            ModuleType::Css if self.stylesheets.read().contains(path) => ModuleType::StyleSheet,
+            module_type => module_type,
+        }
+    }
+    
+    /// Compile a stylesheet to a constructable stylesheet, as an import with
+    /// `type: 'css'` asks, unless it was already processed
+    fn add_stylesheet(&self, path: &Path) {
+        if let Ok(path) = self.canonical_module_path(path) {
+            self.stylesheets.write().insert(path);
+        }
+    }
+    
+    /// Specifiers a module imports only for their side effects, when tree
+    /// shaking may drop them
+    fn side_effect_imports(&self, source: &str, module_type: &ModuleType) -> Vec<String> {
+        if !self.config.features.tree_shaking {
+            return Vec::new();
+        }
+        self.resolver.extract_side_effect_imports(source, module_type)
+    }
+    
+    /// Whether importing a module may have effects beyond its exports
+    fn has_side_effects(&self, path: &Path) -> bool {
+        let path = self.fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.side_effects.has_side_effects(&path, &self.canonical_root(), self.fs.as_ref())
+    }
+    
+    /// Record a static or dynamic import edge
+    fn add_edge(&self, from: ModuleId, specifier: &str, to: ModuleId, dynamic: bool) {
+        let mut graph = self.graph.write();
+        graph.set_resolved(from, specifier, to);
+        if dynamic {
+            graph.add_dynamic_dependency(from, to);
+        } else {
+            graph.add_dependency(from, to);
+        }
+    }
+    
+    /// Pages directory when file-based routing is enabled
+    fn routes_dir(&self) -> Option<PathBuf> {
+        let dir = self.config.routes.dir.as_ref()?;
+        Some(self.canonical_root().join(dir))
+    }
+    
+    /// Resolve an import specifier, including generated modules
+    fn resolve_dependency(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
+        self.resolve_dependency_as(specifier, from, ImportKind::Static)
+    }
+    
+    /// Resolve a specifier imported in a given way, including generated
+    /// modules
+    fn resolve_dependency_as(&self, specifier: &str, from: &Path, kind: ImportKind) -> Result<Option<PathBuf>> {
+        if let Some(path) = html::inline_module_path(specifier, from) {
+            return Ok(Some(path));
+        }
+        
+        if specifier == ROUTES_MODULE {
+            if let Some(dir) = self.routes_dir() {
+                return Ok(Some(routes::routes_module_path(&dir)));
+            }
+        }
+        
+        if specifier == ASSET_URL_MODULE {
+            return Ok(Some(public_path::asset_url_module_path(&self.canonical_root())));
+        }
+        
+        // `./hero.jpg?srcset=480,800` is a module generated for the image
+        if let Some((image, query)) = specifier.split_once('?').filter(|(_, query)| images::Srcset::parse(query).is_some()) {
+            let resolved = self.resolver.resolve_as(image, from, kind)?;
+            return Ok(resolved.map(|image| images::with_query(&image, query)));
+        }
+        
+        // Generated modules import each other with relative paths
+        let dir = self.codegen_dir();
+        let generated_id = match specifier.strip_prefix(CODEGEN_PREFIX) {
+            Some(id) => Some(id.to_string()),
+            None if specifier.starts_with('.') && from.starts_with(&dir) => {
+                let path = from.parent().unwrap_or(&dir).join(specifier);
+                Some(codegen_id(path.strip_prefix(&dir).unwrap_or(&path)))
+            }
+            None => None,
+        };
+        if let Some(id) = generated_id {
+            return match self.find_generated(&id) {
+                Some(found) => Ok(Some(dir.join(found))),
+                None => anyhow::bail!("No plugin generated a module named '{}'", id),
+            };
+        }
+        
+        self.resolver.resolve_as(specifier, from, kind)
+    }
+    
+    /// Directory generated modules appear to live in; it never exists on disk
+    fn codegen_dir(&self) -> PathBuf {
+        self.canonical_root().join(CODEGEN_DIR)
+    }
+    
+    /// Id of the generated module an import refers to, with or without its extension
+    fn find_generated(&self, id: &str) -> Option<String> {
+        let codegen = self.codegen.read();
+        let modules = &codegen.as_ref()?.modules;
+        modules
+            .iter()
+            .find(|m| m.id == id)
+            .or_else(|| modules.iter().find(|m| Path::new(&m.id).with_extension("") == Path::new(id)))
+            .map(|m| m.id.clone())
+    }
+    
+    /// Source of a generated module
+    fn generated_source(&self, path: &Path) -> Option<String> {
+        let id = codegen_id(path.strip_prefix(self.codegen_dir()).ok()?);
+        let codegen = self.codegen.read();
+        codegen.as_ref()?.modules.iter().find(|m| m.id == id).map(|m| m.code.clone())
+    }
+    
+    /// Whether a path is the generated routes module
+    fn is_routes_module(&self, path: &Path) -> bool {
+        self.routes_dir().is_some_and(|dir| path == routes::routes_module_path(&dir))
+    }
+    
+    /// Whether a path is the asset URL helper module
+    fn is_asset_url_module(&self, path: &Path) -> bool {
+        path == public_path::asset_url_module_path(&self.canonical_root())
+    }
+    
+    /// Canonical path of a module; generated modules are already canonical
+    fn canonical_module_path(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let is_virtual = self.is_routes_module(path)
+            || self.is_asset_url_module(path)
+            || path.starts_with(self.codegen_dir())
+            || html::inline_module_page(path).is_some();
+        if is_virtual {
+            return Ok(path.to_path_buf());
+        }
+        if let Some((image, query)) = images::srcset_module(path) {
+            return Ok(images::with_query(&self.fs.canonicalize(&image)?, query));
+        }
+        self.fs.canonicalize(path)
+    }
+    
+    /// Whether a file is a module in the graph
+    fn contains(&self, path: &Path) -> bool {
+        self.canonical_module_path(path).is_ok_and(|path| self.graph.read().get_module_id(&path).is_some())
+    }
+    
+    /// Stamp of a module's file; `None` for virtual modules
+    fn file_stamp(&self, path: &Path) -> Option<FileStamp> {
+        self.fs.metadata(path).ok()?.stamp()
+    }
+    
+    /// Read a module's source, generating it for virtual modules
+    fn read_module(&self, path: &Path) -> Result<String> {
+        if self.is_routes_module(path) {
+            let dir = path.parent().unwrap_or(path);
+            return Ok(render_routes_module(&discover_routes(dir, self.fs.as_ref())?));
+        }
+        if self.is_asset_url_module(path) {
+            return Ok(public_path::render_asset_url_module(&self.config.output.public_url));
+        }
+        if path.starts_with(self.codegen_dir()) {
+            return self.generated_source(path)
+                .with_context(|| format!("Generated module no longer exists: {}", path.display()));
+        }
+        if let Some((page, index)) = html::inline_module_page(path) {
+            let source = self.fs.read_to_string(&page)
+                .with_context(|| format!("Failed to read page: {}", page.display()))?;
+            return html::extract_inline_blocks(&source)
+                .into_iter()
+                .nth(index)
+                .map(|block| block.code)
+                .with_context(|| format!("Inline block no longer exists: {}", path.display()));
+        }
+        if let Some((image, query)) = images::srcset_module(path) {
+            let srcset = images::Srcset::parse(query).context("Not a srcset query")??;
+            let contents = self.fs.read(&image)
+                .with_context(|| format!("Failed to read image: {}", image.display()))?;
+            return images::render_srcset_module(&contents, &image, &srcset);
+        }
+        let source = self.fs.read_to_string(path)
+            .with_context(|| format!("Failed to read module: {}", path.display()))?;
+        if !Module::detect_type(path).is_js_like() {
+            return Ok(source);
+        }
+        
+        // Template `import()`s load whichever files they match
+        let (expanded, warnings) = dynamic_imports::expand(&source, path, &self.display_path(path), self.fs.as_ref());
+        for warning in warnings {
+            self.warn(&warning);
+        }
+        Ok(expanded.into_owned())
+    }
+    
+    /// Transform all modules in the graph that have not been transformed yet
+    ///
+    /// Core transforms run on up to `build.jobs` threads; plugins then run
+    /// module by module.
+    async fn transform_modules(&self) -> Result<()> {
+        let modules: Vec<(ModuleId, String, PathBuf, ModuleType)> = {
+            let graph = self.graph.read();
+            graph.all_module_ids()
+                .into_iter()
+                .filter_map(|id| graph.get_module(id).filter(|m| m.transformed.is_none()).map(|m| (id, m)))
+                .map(|(id, m)| (id, m.source.clone(), m.path.clone(), m.module_type.clone()))
+                .collect()
+        };
+        
+        // Cache keys use root-relative paths so remote entries are shared
+        // between checkouts in different directories
+        let root = self.canonical_root();
+        let plugins = self.plugins.read().clone();
+        
+        let core = crate::utils::parallel_map(modules, self.config.build.jobs(), |(id, source, path, module_type)| {
+            let started = Instant::now();
+            let key_path = path.strip_prefix(&root).unwrap_or(&path);
+            let cache_key = TransformCache::key(&source, key_path, &module_type, &self.transform_fingerprint);
+            let cached = self.cache.get(&cache_key);
+            let was_cached = cached.is_some();
+            let transformed = match cached {
+                Some(cached) => cached,
+                None => {
+                    let transformed = self.core_transform(&source, &path, &module_type)?;
+                    self.cache.insert(cache_key, transformed.clone());
+                    transformed
+                }
+            };
+            anyhow::Ok((id, source, path, module_type, transformed, was_cached, started.elapsed()))
+        });
+        
+        for result in core {
+            let (id, source, path, module_type, transformed, was_cached, core_elapsed) = result?;
+            let started = Instant::now();
+            let key_path = path.strip_prefix(&root).unwrap_or(&path);
+            
+            let mut stages = Vec::new();
+            if self.options.inspect_transforms {
+                stages.push(TransformStage {
+                    name: format!("core:{:?}", module_type).to_lowercase(),
+                    changed: transformed != source,
+                    code: transformed.clone(),
+                    duration_ms: core_elapsed.as_secs_f64() * 1000.0,
+                });
+            }
+            
+            let mut provenance = Vec::new();
+            if transformed != source {
+                provenance.push(format!("core:{:?}", module_type).to_lowercase());
+            }
+            
+            let id_str = path.display().to_string();
+            let trace = self.options.inspect_transforms.then_some(&mut stages);
+            let output = plugins
+                .transform_traced(&transformed, &id_str, trace)
+                .await?;
+            let elapsed = core_elapsed + started.elapsed();
+            self.reporter.module_transformed(key_path, elapsed, was_cached);
+            
+            provenance.extend(output.plugins.iter().map(|name| format!("plugin:{}", name)));
+            let watch_files = output.watch_files
+                .into_iter()
+                .map(|file| self.fs.canonicalize(&file).unwrap_or(file))
+                .collect();
+            
+            {
+                let mut graph = self.graph.write();
+                if let Some(module) = graph.get_module_mut(id) {
+                    module.transformed = Some(output.code);
+                    module.transform_time = Some(elapsed);
+                    module.transform_stages = stages;
+                    module.provenance = provenance;
+                    module.watch_files = watch_files;
+                    module.meta = plugins.context().get_meta(&id_str);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Run the core transforms on a module, in a worker process with
+    /// `build.isolate_transforms`
+    fn core_transform(&self, source: &str, path: &Path, module_type: &ModuleType) -> Result<String> {
+        #[cfg(feature = "native")]
+        if let Some(workers) = &self.workers {
+            return workers.transform(source, path, module_type);
+        }
+        self.transformer.transform(source, path, module_type)
+    }
+    
+    /// Generate chunks from the module graph
+    ///
+    /// Each entrypoint gets a chunk with everything it imports statically.
+    /// Every module an entry imports dynamically starts an async chunk holding
+    /// what it needs beyond that entry's chunk; entries needing the same
+    /// modules for a dynamic import share its chunk.
+    fn generate_chunks(&self) -> Result<Vec<Chunk>> {
+        let graph = self.graph.read();
+        let mut chunks = Vec::new();
+        
+        for (name, path) in self.entrypoints()? {
+            let canonical_path = self.fs.canonicalize(&path)?;
+            
+            if let Some(entry_id) = graph.get_module_id(&canonical_path) {
+                // Get all modules statically reachable from this entry
+                let mut module_ids = graph.get_execution_order(entry_id);
+                
+                // A package has nowhere to load chunks from, so a library
+                // entry bundles what it imports dynamically too
+                if self.config.library.enabled {
+                    let mut bundled: HashSet<ModuleId> = module_ids.iter().copied().collect();
+                    let mut roots: VecDeque<ModuleId> = module_ids.iter().flat_map(|&id| graph.get_dynamic_dependencies(id)).collect();
+                    while let Some(root) = roots.pop_front() {
+                        for id in graph.get_execution_order(root) {
+                            if bundled.insert(id) {
+                                roots.extend(graph.get_dynamic_dependencies(id));
+                                module_ids.push(id);
+                            }
+                        }
+                    }
+                }
+                let mut chunk = Chunk::entry(name, entry_id, module_ids);
+                
+                // Pages run their scripts in document order
+                if let Some(page) = graph.get_module(entry_id).filter(|m| m.module_type == ModuleType::Html) {
+                    chunk.entry_module_ids.clear();
+                    for dep in page.specifiers() {
+                        if let Some(path) = self.resolve_dependency(&dep, &page.path)? {
+                            chunk.entry_module_ids.extend(graph.get_module_id(&self.canonical_module_path(&path)?));
+                        }
+                    }
+                }
+                chunks.push(chunk);
+            }
+        }
+        
+        let mut names: HashSet<String> = chunks.iter().map(|c| c.name.clone()).collect();
+        let mut async_chunks: Vec<Chunk> = Vec::new();
+        
+        for chunk in &mut chunks {
+            // Only what this entry has loaded can be left out: another
+            // entry's modules aren't on its page
+            let bundled: HashSet<ModuleId> = chunk.module_ids.iter().copied().collect();
+            let mut roots: VecDeque<ModuleId> = chunk.module_ids
+                .iter()
+                .flat_map(|&id| graph.get_dynamic_dependencies(id))
+                .collect();
+            let mut seen = HashSet::new();
+            
+            while let Some(root) = roots.pop_front() {
+                if bundled.contains(&root) || !seen.insert(root) {
+                    continue;
+                }
+                
+                let module_ids: Vec<ModuleId> = graph.get_execution_order(root)
+                    .into_iter()
+                    .filter(|id| !bundled.contains(id))
+                    .collect();
+                roots.extend(module_ids.iter().flat_map(|&id| graph.get_dynamic_dependencies(id)));
+                
+                let existing = async_chunks.iter().find(|c| c.entry == Some(root) && c.module_ids == module_ids);
+                let name = match existing {
+                    Some(existing) => existing.name.clone(),
+                    None => {
+                        let stem = graph.get_module(root)
+                            .and_then(|m| m.path.file_stem())
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        let name = unique_chunk_name(&stem, &mut names);
+                        async_chunks.push(Chunk::async_chunk(name.clone(), root, module_ids));
+                        name
+                    }
+                };
+                chunk.async_chunks.push(name);
+            }
+        }
+        
+        chunks.extend(async_chunks);
+        Ok(chunks)
+    }
+    
+    /// Project root as it appears in module paths
+    fn canonical_root(&self) -> PathBuf {
+        self.fs.canonicalize(&self.config.root).unwrap_or_else(|_| self.config.root.clone())
+    }
+    
+    /// A module path relative to the project root, for messages
+    fn display_path(&self, path: &Path) -> String {
+        let root = self.canonical_root();
+        path.strip_prefix(&root).unwrap_or(path).display().to_string()
+    }
+    
+    /// Output directory for this build
+    fn output_dir(&self) -> PathBuf {
+        self.options.outdir.clone()
+            .unwrap_or_else(|| self.config.output_dir())
+    }
+    
+    /// Rolled-up declarations of a library's root entry, with `library.dts`
+    fn render_declarations(&self, files: &[EmittedFile]) -> Result<Option<EmittedFile>> {
+        let library = &self.config.library;
+        if !library.enabled || !library.dts {
+            return Ok(None);
+        }
+        let entry = library::root_entry(library, files)?
+            .context("library.dts needs a root entry; set library.main")?;
+        let path = self.entrypoints()?
+            .into_iter()
+            .find_map(|(name, path)| (name == entry).then_some(path))
+            .with_context(|| format!("Entry '{}' not found", entry))?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        if !matches!(extension, "ts" | "tsx" | "mts" | "cts") {
+            anyhow::bail!("library.dts needs a TypeScript root entry, not {}", path.display());
+        }
+        
+        let state = crate::config::ProjectState::new(&self.config.root);
+        state.ensure()?;
+        let declarations = dts::emit_declarations(&self.config.root, &path, &state.path("declarations"))?;
+        Ok(Some(EmittedFile {
+            file_name: format!("{}.d.ts", entry),
+            contents: dts::bundle_declarations(&declarations)?.into_bytes(),
+            origin: "declarations".to_string(),
+            entry: None,
+        }))
+    }
+    
+    /// package.json fields for the emitted entries of a library build
+    fn package_exports(&self, files: &[EmittedFile]) -> Result<Option<PackageExports>> {
+        let library = &self.config.library;
+        if !library.enabled || !library.package_json {
+            return Ok(None);
+        }
+        let outdir = crate::utils::relative_path(&self.config.root, &self.output_dir())
+            .unwrap_or_else(|| self.config.output.dir.clone());
+        PackageExports::new(library, files, &outdir).map(Some)
+    }
+    
+    /// Write emitted files to the output directory
+    ///
+    /// Each file is replaced atomically, so a server or deploy script reading
+    /// the output during a rebuild never gets half of one. Files whose
+    /// contents are unchanged aren't rewritten, keeping their mtimes for
+    /// tools like rsync; each path comes with whether that was the case.
+    fn write_files(&self, files: &[EmittedFile]) -> Result<Vec<(PathBuf, bool)>> {
+        let output_dir = self.output_dir();
+        
+        fs::create_dir_all(&output_dir)
+            .context("Failed to create output directory")?;
+        
+        crate::utils::parallel_map(files.iter().collect(), self.config.build.jobs(), |file| {
+            let output_path = output_dir.join(&file.file_name);
+            if let Some(parent) = output_path.parent().filter(|parent| *parent != output_dir) {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let unchanged = fs::metadata(&output_path).is_ok_and(|m| m.len() == file.contents.len() as u64)
+                && fs::read(&output_path).is_ok_and(|existing| existing == file.contents);
+            if !unchanged {
+                crate::utils::write_atomic(&output_path, &file.contents)
+                    .with_context(|| format!("Failed to write {}", output_path.display()))?;
+            }
+            Ok((output_path, unchanged))
+        })
+        .into_iter()
+        .collect()
+    }
+    
+    /// Write bundles to disk
+    ///
+    /// `chunk_modules` lists the modules of each chunk's file.
+    fn write_bundles(&self, files: &[EmittedFile], chunk_modules: &HashMap<&str, Vec<String>>) -> Result<Vec<BundleInfo>> {
+        let paths = self.write_files(files)?;
+        
+        Ok(files
+            .iter()
+            .zip(paths)
+            .map(|(file, (output_path, unchanged))| {
+                let modules = chunk_modules.get(file.file_name.as_str());
+                let kind = match file.file_name.rsplit('.').next() {
+                    _ if modules.is_some() => OutputKind::Chunk,
+                    Some("html") => OutputKind::Page,
+                    Some("css") => OutputKind::Stylesheet,
+                    _ => OutputKind::Asset,
+                };
+                BundleInfo {
+                    output_path,
+                    file_name: file.file_name.clone(),
+                    kind,
+                    size: file.contents.len(),
+                    gzip_size: gzip_size(&file.contents),
+                    modules: modules.cloned().unwrap_or_default(),
+                    sourcemap_path: None, // TODO: Generate sourcemaps
+                    unchanged,
+                }
+            })
+            .collect())
+    }
+    
+    /// Modules of each chunk, relative to the root, by the chunk's file
+    fn chunk_modules<'a>(&self, chunks: &'a [Chunk], files: &'a [EmittedFile]) -> HashMap<&'a str, Vec<String>> {
+        let root = self.canonical_root();
+        let graph = self.graph.read();
+        self.chunk_files(chunks, files)
+            .into_iter()
+            .map(|(chunk, file)| {
+                let modules = chunk.module_ids
+                    .iter()
+                    .filter_map(|&id| graph.get_module(id))
+                    .map(|m| path_to_module_id(m.path.strip_prefix(&root).unwrap_or(&m.path)))
+                    .collect();
+                (file.file_name.as_str(), modules)
+            })
+            .collect()
+    }
+    
+    /// Each chunk with the files it was rendered to: a script, or a library
+    /// entry's ES module and CommonJS builds
+    fn chunk_files<'a>(&self, chunks: &'a [Chunk], files: &'a [EmittedFile]) -> Vec<(&'a Chunk, &'a EmittedFile)> {
+        chunks
+            .iter()
+            .flat_map(|chunk| {
+                ["js", "mjs", "cjs"].into_iter().filter_map(move |extension| {
+                    let unhashed = self.typed_file_name(format!("{}.{}", chunk.name, extension));
+                    let file = files.iter().find(|f| manifest::unhashed_name(&f.file_name) == unhashed)?;
+                    Some((chunk, file))
+                })
+            })
+            .collect()
+    }
+    
+    /// Render chunks into bundle files
+    fn render_bundles(&self, chunks: &[Chunk]) -> Result<Vec<EmittedFile>> {
+        let catalogs = self.config.i18n.is_enabled()
+            .then(|| Catalogs::load(&self.config.i18n, &self.config.root, self.fs.as_ref()))
+            .transpose()?;
+        
+        let graph = self.graph.read();
+        let mut bundles = Vec::new();
+        
+        // Async chunks first, so entries know the file each one landed in
+        let (async_chunks, entry_chunks): (Vec<&Chunk>, Vec<&Chunk>) = chunks
+            .iter()
+            .partition(|chunk| chunk.chunk_type == ChunkType::Async);
+        let async_by_name: HashMap<&str, &Chunk> = async_chunks.iter().map(|c| (c.name.as_str(), *c)).collect();
+        let mut chunk_files = HashMap::new();
+        let ids = self.module_ids(&graph);
+        
+        // Assets scripts, stylesheets and pages reference, by file, copied once
+        let mut assets = HashMap::new();
+        self.copy_script_assets(&graph, &mut assets, &mut bundles);
+        self.copy_font_assets(&graph, &mut assets, &mut bundles);
+        let (page_styles, extracted_css) = self.page_styles(&entry_chunks, &async_by_name, &graph, &assets);
+        
+        for chunk in &async_chunks {
+            let key = self.chunk_cache_key(&[], chunk, &graph, &extracted_css, &ids, &assets);
+            let code = self.cached_chunk(key, || self.finish_bundle(self.render_modules(chunk, &graph, &extracted_css, &ids, &assets)?))?;
+            let file_name = self.typed_file_name(format!("{}{}.js", chunk.name, self.hash_suffix(&code)));
+            
+            let root = chunk.entry.and_then(|id| graph.get_module(id));
+            if let Some(root) = root {
+                chunk_files.insert(chunk.name.as_str(), (ids.key(&root.path), file_name.clone()));
+            }
+            let origin = match root {
+                Some(root) => format!("chunk for {}", self.display_path(&root.path)),
+                None => format!("chunk '{}'", chunk.name),
+            };
+            bundles.push(EmittedFile { file_name, contents: code.into_bytes(), origin, entry: None });
+        }
+        
+        for chunk in entry_chunks {
+            let entry_module = chunk.entry.and_then(|id| graph.get_module(id));
+            let is_page = entry_module.is_some_and(|m| m.module_type == ModuleType::Html);
+            if self.config.library.enabled && !is_page {
+                bundles.extend(self.render_library_entry(chunk, &graph, &extracted_css, &ids, &assets)?);
+                continue;
+            }
+            
+            // Runtime header, then the async chunk map and messages
+            let mut prelude = self.generate_runtime_header();
+            
+            // Every module the entry imports dynamically and doesn't bundle
+            // itself maps to the async chunk this entry loads it from
+            let chunk_map: BTreeMap<&String, &String> = chunk.async_chunks
+                .iter()
+                .filter_map(|name| chunk_files.get(name.as_str()))
+                .map(|(key, file_name)| (key, file_name))
+                .collect();
+            if !chunk_map.is_empty() {
+                prelude.push_str(&format!(
+                    "\nwindow.__component_chunks__ = Object.assign(window.__component_chunks__ || {{}}, {});\n",
+                    serde_json::to_string(&chunk_map)?
+                ));
+            }
+            
+            if let Some(catalogs) = &catalogs {
+                // Async chunks use the entry's messages
+                let module_ids = chunk.async_chunks
+                    .iter()
+                    .filter_map(|name| async_by_name.get(name.as_str()))
+                    .fold(chunk.module_ids.clone(), |mut ids, c| {
+                        ids.extend(&c.module_ids);
+                        ids
+                    });
+                let (messages, runtime) = self.render_messages(catalogs, &chunk.name, &module_ids, &graph)?;
+                bundles.extend(messages);
+                prelude.push_str(&runtime);
+            }
+            
+            // Run the entry modules; an entry module's exports are published
+            // under the chunk's name
+            let mut epilogue = String::new();
+            if !chunk.entry_module_ids.is_empty() {
+                epilogue.push_str("\n// Execute entry point\n");
+            }
+            for &module_id in &chunk.entry_module_ids {
+                let Some(module) = graph.get_module(module_id) else {
+                    continue;
+                };
+                let id = ids.literal(&module.path);
+                if chunk.entry == Some(module_id) {
+                    epilogue.push_str(&format!("__component_export__({}, {});\n", serde_json::to_string(&chunk.name)?, id));
+                } else {
+                    epilogue.push_str(&format!("__component_require__({});\n", id));
+                }
+            }
+            
+            // Concatenate all transformed module code, unless the chunk is unchanged
+            let key = self.chunk_cache_key(&[prelude.as_bytes(), epilogue.as_bytes()], chunk, &graph, &extracted_css, &ids, &assets);
+            let final_code = self.cached_chunk(key, || {
+                self.finish_bundle(prelude + &self.render_modules(chunk, &graph, &extracted_css, &ids, &assets)? + &epilogue)
+            })?;
+            
+            // Generate hash for filename
+            let hash = self.hash_suffix(&final_code);
+            let file_name = self.typed_file_name(format!("{}{}.js", chunk.name, hash));
+            
+            if let Some(page) = entry_module.filter(|_| is_page) {
+                let fonts = self.font_preloads(chunk, &graph, &assets);
+                bundles.extend(self.render_page(&chunk.name, page, &file_name, page_styles.get(&chunk.name), &fonts, &mut assets));
+            }
+            
+            let origin = match entry_module {
+                Some(entry) => format!("entry '{}' ({})", chunk.name, self.display_path(&entry.path)),
+                None => format!("entry '{}'", chunk.name),
+            };
+            bundles.push(EmittedFile {
+                file_name,
+                contents: final_code.into_bytes(),
+                origin,
+                entry: Some(chunk.name.clone()),
+            });
+        }
+        
+        for warning in catalogs.iter().flat_map(|c| c.take_warnings()) {
+            self.warn(&warning);
+        }
+        
+        // Inline blocks and handlers left in pages need their hashes in a CSP
+        let csp: BTreeMap<&str, html::CspHashes> = bundles
+            .iter()
+            .filter(|file| file.file_name.ends_with(".html"))
+            .map(|file| (file.file_name.as_str(), html::CspHashes::of_page(&String::from_utf8_lossy(&file.contents))))
+            .filter(|(_, hashes)| !hashes.is_empty())
+            .collect();
+        if !csp.is_empty() {
+            let contents = serde_json::to_string_pretty(&csp)?.into_bytes();
+            bundles.push(EmittedFile {
+                file_name: "csp.json".to_string(),
+                contents,
+                origin: "inline blocks of pages".to_string(),
+                entry: None,
+            });
+        }
+        
+        Ok(bundles)
+    }
+    
+    /// Render a library entry as an ES module and a CommonJS module
+    ///
+    /// Both run the same modules in the library runtime; the ES module
+    /// re-exports the entry module's exports by name, and exports a
+    /// CommonJS entry's `module.exports` as its default.
+    fn render_library_entry(
+        &self,
+        chunk: &Chunk,
+        graph: &ModuleGraph,
+        extracted_css: &HashSet<ModuleId>,
+        ids: &ModuleIdMap,
+        assets: &HashMap<PathBuf, String>,
+    ) -> Result<Vec<EmittedFile>> {
+        let Some((entry_id, entry)) = chunk.entry.and_then(|id| graph.get_module(id).map(|m| (id, m))) else {
+            return Ok(Vec::new());
+        };
+        let id = ids.literal(&entry.path);
+        
+        let mut esm = format!("\n// Run the entry module\nvar __component_entry__ = __component_require__({});\n", id);
+        match self.library_export_names(entry_id, graph)? {
+            Some(names) if !names.is_empty() => {
+                // Exports are copied again when their bindings change, so
+                // they stay live
+                let mut variables = Vec::new();
+                let mut copies = Vec::new();
+                let mut specifiers = Vec::new();
+                for (index, name) in names.iter().enumerate() {
+                    let variable = format!("__component_export_{}__", index);
+                    copies.push(format!("  {} = __component_entry__[{}];\n", variable, serde_json::to_string(name)?));
+                    let exported = if commonjs::is_identifier(name) { name.clone() } else { serde_json::to_string(name)? };
+                    specifiers.push(format!("{} as {}", variable, exported));
+                    variables.push(variable);
+                }
+                esm.push_str(&format!("var {};\n", variables.join(", ")));
+                esm.push_str(&format!("__component_listen__(function() {{\n{}}});\n", copies.concat()));
+                esm.push_str(&format!("export {{ {} }};\n", specifiers.join(", ")));
+            }
+            Some(_) => {}
+            None => esm.push_str("export default __component_entry__;\n"),
+        }
+        let cjs = format!("\n// Run the entry module\nmodule.exports = __component_require__({});\n", id);
+        
+        let mut files = Vec::new();
+        for (extension, prelude, epilogue) in [("mjs", "", esm), ("cjs", "\"use strict\";\n", cjs)] {
+            let key = self.chunk_cache_key(&[extension.as_bytes(), epilogue.as_bytes()], chunk, graph, extracted_css, ids, assets);
+            let code = self.cached_chunk(key, || {
+                let modules = self.render_modules(chunk, graph, extracted_css, ids, assets)?;
+                self.finish_bundle(format!("{}{}{}{}", prelude, library::RUNTIME, modules, epilogue))
+            })?;
+            files.push(EmittedFile {
+                file_name: self.typed_file_name(format!("{}.{}", chunk.name, extension)),
+                contents: code.into_bytes(),
+                origin: format!("entry '{}' ({})", chunk.name, self.display_path(&entry.path)),
+                entry: Some(chunk.name.clone()),
+            });
+        }
+        Ok(files)
+    }
+    
+    /// Names a library entry exports, following `export *`; `None` when it
+    /// isn't an ES module
+    ///
+    /// Names behind CommonJS modules are left out.
+    fn library_export_names(&self, entry: ModuleId, graph: &ModuleGraph) -> Result<Option<Vec<String>>> {
+        let mut names = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut queue = vec![(entry, true)];
+        while let Some((id, with_default)) = queue.pop() {
+            let Some(module) = graph.get_module(id).filter(|_| visited.insert(id)) else {
+                continue;
+            };
+            let converted = commonjs::convert(module.transformed.as_ref().unwrap_or(&module.source), &module.path)?;
+            if !converted.esm {
+                if id == entry {
+                    return Ok(None);
+                }
+                continue;
+            }
+            names.extend(converted.names.into_iter().filter(|name| with_default || name != "default"));
+            for specifier in &converted.star {
+                if let Some(path) = self.resolve_dependency(specifier, &module.path)? {
+                    queue.extend(graph.get_module_id(&self.canonical_module_path(&path)?).map(|target| (target, false)));
+                }
+            }
+        }
+        Ok(Some(names.into_iter().collect()))
+    }
+    
+    /// Fail a library build whose ES module and CommonJS entries export
+    /// different things, with `library.check_parity`
+    fn check_library_parity(&self, files: &[EmittedFile]) -> Result<()> {
+        let library = &self.config.library;
+        if !library.enabled || !library.check_parity {
+            return Ok(());
+        }
+        let problems = library::check_parity(files)?;
+        if !problems.is_empty() {
+            anyhow::bail!("The ES module and CommonJS builds don't match:\n  {}", problems.join("\n  "));
+        }
+        Ok(())
+    }
+    
+    /// Split CSS out of HTML entries when critical CSS is enabled
+    ///
+    /// Returns each page's styles by chunk name, and the CSS modules that are
+    /// served from stylesheets instead of injecting themselves.
+    fn page_styles(
+        &self,
+        entry_chunks: &[&Chunk],
+        async_chunks: &HashMap<&str, &Chunk>,
+        graph: &ModuleGraph,
+        assets: &HashMap<PathBuf, String>,
+    ) -> (HashMap<String, PageStyles>, HashSet<ModuleId>) {
+        let mut pages = HashMap::new();
+        let mut extracted = HashSet::new();
+        if !self.config.output.critical_css {
+            return (pages, extracted);
+        }
+        
+        for chunk in entry_chunks {
+            let is_page = chunk.entry
+                .and_then(|id| graph.get_module(id))
+                .is_some_and(|m| m.module_type == ModuleType::Html);
+            if !is_page {
+                continue;
+            }
+            
+            // Critical: CSS loaded with the page; full: also CSS of every async chunk it can load
+            let critical = css_modules(&chunk.module_ids, graph);
+            let mut full = critical.clone();
+            for async_chunk in chunk.async_chunks.iter().filter_map(|name| async_chunks.get(name.as_str())) {
+                full.extend(css_modules(&async_chunk.module_ids, graph));
+            }
+            full.sort_unstable();
+            full.dedup();
+            
+            if full.is_empty() {
+                continue;
+            }
+            extracted.extend(&full);
+            let public_url = self.config.output.public_url.trim_end_matches('/');
+            pages.insert(chunk.name.clone(), PageStyles {
+                critical: concat_css(&critical, graph, assets, public_url),
+                full: concat_css(&full, graph, assets, public_url),
+            });
         }
         
-        Ok(())
+        (pages, extracted)
     }
     
-    /// Process a single module and its dependencies
-    /// 
-    /// Uses Box::pin for async recursion to avoid infinite type size issues
-    async fn process_module(&self, path: &PathBuf, is_entry: bool) -> Result<ModuleId> {
-        let canonical_path = fs::canonicalize(path)
-            .with_context(|| format!("Failed to resolve module path: {}", path.display()))?;
+    /// Render an HTML entry's page, plus its stylesheet when CSS is extracted
+    ///
+    /// `fonts` are the URLs of the fonts the page preloads.
+    fn render_page(
+        &self,
+        name: &str,
+        page: &Module,
+        script: &str,
+        styles: Option<&PageStyles>,
+        fonts: &[String],
+        assets: &mut HashMap<PathBuf, String>,
+    ) -> Vec<EmittedFile> {
+        let public_url = self.config.output.public_url.trim_end_matches('/');
+        let mut files = Vec::new();
         
-        // Check if already processed
-        {
-            let graph = self.graph.read();
-            if let Some(id) = graph.get_module_id(&canonical_path) {
-                return Ok(id);
+        let mut head = html::font_preload_tags(fonts);
+        let mut body = String::new();
+        if let Some(styles) = styles {
+            let file_name = self.typed_file_name(format!("{}{}.css", name, self.hash_suffix(&styles.full)));
+            let url = format!("{}/{}", public_url, file_name);
+            if !head.is_empty() {
+                head.push('\n');
             }
+            head.push_str(&html::critical_css_tags(&styles.critical, &url));
+            body.push_str(&html::stylesheet_tag(&url));
+            body.push('\n');
+            files.push(EmittedFile {
+                file_name,
+                contents: styles.full.clone().into_bytes(),
+                origin: format!("stylesheet of page '{}'", name),
+                entry: Some(name.to_string()),
+            });
         }
+        body.push_str(&html::script_tag(&format!("{}/{}", public_url, script)));
         
-        // Read module source
-        let source = fs::read_to_string(&canonical_path)
-            .with_context(|| format!("Failed to read module: {}", canonical_path.display()))?;
-        
-        // Determine module type from extension
-        let module_type = Module::detect_type(&canonical_path);
-        
-        // Parse and extract dependencies
-        let dependencies = self.resolver.extract_dependencies(&source, &canonical_path, &module_type)?;
-        
-        // Create module
-        let module = Module {
-            path: canonical_path.clone(),
-            source,
-            module_type,
-            is_entry,
-            dependencies: dependencies.clone(),
-            transformed: None,
-        };
+        let source = self.copy_page_assets(page, assets, &mut files);
+        let references = html::extract_references(&source);
+        let mut contents = html::rebase(&html::rewrite(&source, &references, &head, &body), public_url);
+        if self.minifies(|minify| minify.html) {
+            contents = minify_html(&contents);
+        }
+        files.push(EmittedFile {
+            file_name: format!("{}.html", name),
+            contents: contents.into_bytes(),
+            origin: format!("page '{}' ({})", name, self.display_path(&page.path)),
+            entry: None,
+        });
+        files
+    }
+    
+    /// Copy the icons and images a page references to `assets/`, with a
+    /// content hash, and point the page at the copies
+    ///
+    /// `assets` maps files already copied for scripts and other pages to
+    /// their output names. Missing files are reported and their URLs left
+    /// alone.
+    fn copy_page_assets(&self, page: &Module, assets: &mut HashMap<PathBuf, String>, files: &mut Vec<EmittedFile>) -> String {
+        let public_url = self.config.output.public_url.trim_end_matches('/');
+        let page_dir = page.path.parent().unwrap_or(&page.path);
+        let mut replacements = Vec::new();
         
-        // Add to graph
-        let module_id = {
-            let mut graph = self.graph.write();
-            graph.add_module(module)
-        };
+        for asset in html::extract_assets(&page.source) {
+            let split = asset.specifier.find(['?', '#']).unwrap_or(asset.specifier.len());
+            let (url, suffix) = asset.specifier.split_at(split);
+            let path = match url.strip_prefix('/') {
+                Some(url) => self.canonical_root().join(url),
+                None => page_dir.join(url),
+            };
+            let path = crate::utils::normalize_path(&path);
+            
+            let referrer = format!("page {}", self.display_path(&page.path));
+            let Some(file_name) = self.copy_asset(&path, &referrer, assets, files) else {
+                self.warn(&format!(
+                    "{} references {}, which doesn't exist",
+                    self.display_path(&page.path),
+                    asset.specifier
+                ));
+                continue;
+            };
+            replacements.push((asset.range, format!("{}/{}{}", public_url, file_name, suffix)));
+        }
         
-        // Process dependencies recursively (Box::pin needed for async recursion)
-        for dep in dependencies {
-            let resolved = self.resolver.resolve(&dep, &canonical_path)?;
-            if let Some(resolved_path) = resolved {
-                let dep_id = Box::pin(self.process_module(&resolved_path, false)).await?;
-                
-                let mut graph = self.graph.write();
-                graph.add_dependency(module_id, dep_id);
+        html::replace_ranges(&page.source, &replacements)
+    }
+    
+    /// Copy the files scripts reference with `new URL('./file',
+    /// import.meta.url)` to `assets/`, with a content hash
+    ///
+    /// Missing files are reported, and their URLs left alone.
+    fn copy_script_assets(&self, graph: &ModuleGraph, assets: &mut HashMap<PathBuf, String>, files: &mut Vec<EmittedFile>) {
+        let mut ids = graph.all_module_ids();
+        ids.sort_unstable();
+        for module in ids.into_iter().filter_map(|id| graph.get_module(id)) {
+            for dependency in module.dependencies.iter().filter(|d| d.kind == ImportKind::Url) {
+                let Some((path, _)) = script_asset_path(&module.path, &dependency.specifier) else {
+                    continue;
+                };
+                if self.copy_asset(&path, &self.display_path(&module.path), assets, files).is_none() {
+                    self.warn(&format!(
+                        "{} references {}, which doesn't exist",
+                        self.display_path(&module.path),
+                        dependency.specifier
+                    ));
+                }
             }
         }
-        
-        Ok(module_id)
     }
     
-    /// Transform all modules in the graph
-    async fn transform_modules(&self) -> Result<()> {
-        let module_ids: Vec<ModuleId> = {
-            let graph = self.graph.read();
-            graph.all_module_ids()
+    /// Copy the fonts stylesheets load in `@font-face` rules to `assets/`,
+    /// with a content hash, when `fonts.subset` or `fonts.preload` is on
+    ///
+    /// With `fonts.subset`, TrueType and OpenType fonts are subset to WOFF2.
+    /// Missing files are reported, and their URLs left alone.
+    fn copy_font_assets(&self, graph: &ModuleGraph, assets: &mut HashMap<PathBuf, String>, files: &mut Vec<EmittedFile>) {
+        let config = &self.config.fonts;
+        if !config.subset && !config.preload {
+            return;
+        }
+        let ranges = fonts::parse_unicode_ranges(&config.unicode_ranges).unwrap_or_default();
+        let mut ids = graph.all_module_ids();
+        ids.sort_unstable();
+        for module in ids.into_iter().filter_map(|id| graph.get_module(id)) {
+            if module.module_type != ModuleType::Css {
+                continue;
+            }
+            for source in fonts::font_faces(&module.source).iter().flat_map(|face| &face.sources) {
+                let (path, _) = fonts::source_path(&module.path, &source.specifier);
+                let referrer = self.display_path(&module.path);
+                let copy = if config.subset && fonts::can_subset(&path) && !assets.contains_key(&path) {
+                    self.copy_subset_font(&path, &referrer, &ranges, assets, files)
+                } else {
+                    self.copy_asset(&path, &referrer, assets, files)
+                };
+                if copy.is_none() {
+                    self.warn(&format!("{} references {}, which doesn't exist", referrer, source.specifier));
+                }
+            }
+        }
+    }
+    
+    /// Copy a font to `assets/` as WOFF2 with only the glyphs of `ranges`,
+    /// and return its output name; `None` when it can't be read
+    ///
+    /// Fonts that can't be subset are reported and copied as they are.
+    fn copy_subset_font(
+        &self,
+        path: &Path,
+        referrer: &str,
+        ranges: &[RangeInclusive<u32>],
+        assets: &mut HashMap<PathBuf, String>,
+        files: &mut Vec<EmittedFile>,
+    ) -> Option<String> {
+        let font = self.fs.read(path).ok()?;
+        let contents = match fonts::subset(&font, ranges).and_then(|subset| fonts::to_woff2(&subset)) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.warn(&format!("{} isn't subset: {:#}", self.display_path(path), e));
+                return self.copy_asset(path, referrer, assets, files);
+            }
         };
+        let stem = path.file_stem().map_or("font".into(), |s| s.to_string_lossy());
+        let file_name = format!("{}/{}{}.woff2", ASSETS_DIR, stem, self.hash_suffix(&contents));
+        files.push(EmittedFile {
+            file_name: file_name.clone(),
+            contents,
+            origin: format!("subset of {} for {}", self.display_path(path), referrer),
+            entry: None,
+        });
+        assets.insert(path.to_path_buf(), file_name.clone());
+        Some(file_name)
+    }
+    
+    /// URLs of the fonts a page preloads with `fonts.preload`: the first
+    /// source of each `@font-face` rule in the CSS loaded with the page whose
+    /// family that CSS uses
+    fn font_preloads(&self, chunk: &Chunk, graph: &ModuleGraph, assets: &HashMap<PathBuf, String>) -> Vec<String> {
+        if !self.config.fonts.preload {
+            return Vec::new();
+        }
+        let public_url = self.config.output.public_url.trim_end_matches('/');
+        let stylesheets: Vec<&Module> = css_modules(&chunk.module_ids, graph)
+            .into_iter()
+            .filter_map(|id| graph.get_module(id))
+            .collect();
+        let css: String = stylesheets.iter().map(|module| module.source.as_str()).collect::<Vec<_>>().join("\n");
         
-        for id in module_ids {
-            let (source, path, module_type) = {
-                let graph = self.graph.read();
-                let module = graph.get_module(id).unwrap();
-                (module.source.clone(), module.path.clone(), module.module_type.clone())
-            };
-            
-            let transformed = self.transformer.transform(&source, &path, &module_type)?;
-            
-            {
-                let mut graph = self.graph.write();
-                if let Some(module) = graph.get_module_mut(id) {
-                    module.transformed = Some(transformed);
+        let mut urls = Vec::new();
+        for module in stylesheets {
+            for face in fonts::font_faces(&module.source) {
+                let used = face.family.as_deref().is_some_and(|family| fonts::uses_family(&css, family));
+                let copy = face.sources.first().and_then(|source| assets.get(&fonts::source_path(&module.path, &source.specifier).0));
+                if let Some(file_name) = copy.filter(|_| used) {
+                    let url = format!("{}/{}", public_url, file_name);
+                    if !urls.contains(&url) {
+                        urls.push(url);
+                    }
                 }
             }
         }
-        
-        Ok(())
+        urls
     }
     
-    /// Generate chunks from the module graph
-    fn generate_chunks(&self) -> Result<Vec<Chunk>> {
-        let graph = self.graph.read();
+    /// Copy a file to `assets/` with a content hash, unless it was already
+    /// copied, and return its output name; `None` when it can't be read
+    ///
+    /// `assets` maps files already copied to their output names.
+    fn copy_asset(&self, path: &Path, referrer: &str, assets: &mut HashMap<PathBuf, String>, files: &mut Vec<EmittedFile>) -> Option<String> {
+        if let Some(file_name) = assets.get(path) {
+            return Some(file_name.clone());
+        }
         
-        // For Milestone 1: single chunk per entrypoint
-        let mut chunks = Vec::new();
+        // Resized copies of an image are named for their width
+        if let Some((image, variant)) = images::variant(path) {
+            let contents = images::resize(&self.fs.read(&image).ok()?, &image, &variant).ok()?;
+            let stem = image.file_stem().map_or("image".into(), |s| s.to_string_lossy());
+            let extension = variant.format_of(&image).ok()?.name();
+            let file_name = format!("{}/{}-{}w{}.{}", ASSETS_DIR, stem, variant.width, self.hash_suffix(&contents), extension);
+            files.push(EmittedFile {
+                file_name: file_name.clone(),
+                contents,
+                origin: format!("{}w copy of {} for {}", variant.width, self.display_path(&image), referrer),
+                entry: None,
+            });
+            assets.insert(path.to_path_buf(), file_name.clone());
+            return Some(file_name);
+        }
         
-        for (name, path) in self.config.all_entrypoints() {
-            let canonical_path = fs::canonicalize(&path)?;
-            
-            if let Some(entry_id) = graph.get_module_id(&canonical_path) {
-                // Get all modules reachable from this entry
-                let module_ids = graph.get_reachable_modules(entry_id);
+        let mut contents = self.fs.read(path).ok()?;
+        let is_json = path.extension().is_some_and(|e| e == "json" || e == "webmanifest");
+        if is_json && self.minifies(|minify| minify.json) {
+            contents = minify_json(&contents).unwrap_or(contents);
+        }
+        let stem = path.file_stem().map_or("asset".into(), |s| s.to_string_lossy());
+        let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+        let file_name = format!("{}/{}{}{}", ASSETS_DIR, stem, self.hash_suffix(&contents), extension);
+        files.push(EmittedFile {
+            file_name: file_name.clone(),
+            contents,
+            origin: format!("asset {} of {}", self.display_path(path), referrer),
+            entry: None,
+        });
+        assets.insert(path.to_path_buf(), file_name.clone());
+        Some(file_name)
+    }
+    
+    /// Wrap each module of a chunk in a registration function
+    ///
+    /// Pages have no code, and CSS modules in `extracted_css` export nothing
+    /// since their styles are loaded by the page. Asset URLs point at the
+    /// copies in `assets`.
+    fn render_modules(
+        &self,
+        chunk: &Chunk,
+        graph: &ModuleGraph,
+        extracted_css: &HashSet<ModuleId>,
+        ids: &ModuleIdMap,
+        assets: &HashMap<PathBuf, String>,
+    ) -> Result<String> {
+        let mut code = String::new();
+        
+        for &module_id in &chunk.module_ids {
+            if let Some(module) = graph.get_module(module_id) {
+                if module.module_type == ModuleType::Html {
+                    continue;
+                }
+                let module_code = if extracted_css.contains(&module_id) {
+                    "module.exports = {};"
+                } else {
+                    module.transformed.as_ref().unwrap_or(&module.source)
+                };
+                let module_code = if module.module_type == ModuleType::Css {
+                    let public_url = self.config.output.public_url.trim_end_matches('/');
+                    self.rewrite_requires(&fonts::rewrite_sources(module_code, &module.path, assets, public_url), &module.path, ids)
+                } else if module.module_type == ModuleType::Graphql {
+                    self.rewrite_requires(module_code, &module.path, ids)
+                } else if module.dependencies.iter().any(|d| d.kind == ImportKind::Url) {
+                    rewrite_asset_urls(&self.rewrite_dynamic_imports(module_code, &module.path, ids), &module.path, assets)
+                } else {
+                    self.rewrite_dynamic_imports(module_code, &module.path, ids)
+                };
+                // Scripts can't hold `import` and `export` statements inside
+                // the wrapper, so they require each other by module id
+                let module_code = commonjs::convert(&module_code, &module.path)?.code;
+                let module_code = self.rewrite_graph_requires(&module_code, module_id, graph, ids);
                 
-                chunks.push(Chunk {
-                    name,
-                    chunk_type: ChunkType::Entry,
-                    module_ids,
-                });
+                // Wrap module in a function
+                code.push_str(&format!(
+                    "\n// Module: {}\n__component_modules__[{}] = function(module, exports, require) {{\n{}\n}};\n",
+                    self.display_path(&module.path),
+                    ids.literal(&module.path),
+                    module_code
+                ));
             }
         }
         
-        Ok(chunks)
+        Ok(code)
     }
     
-    /// Write bundles to disk
-    fn write_bundles(&self, chunks: &[Chunk]) -> Result<Vec<BundleInfo>> {
-        let output_dir = self.options.outdir.clone()
-            .unwrap_or_else(|| self.config.output_dir());
-        
-        fs::create_dir_all(&output_dir)
-            .context("Failed to create output directory")?;
-        
-        let graph = self.graph.read();
-        let mut bundles = Vec::new();
+    /// Cache key for a chunk's finished code
+    ///
+    /// Covers `parts` (the code around the modules), the minify option, and
+    /// each module's path, id, resolved imports and their ids, copied
+    /// assets, and transformed code.
+    fn chunk_cache_key(
+        &self,
+        parts: &[&[u8]],
+        chunk: &Chunk,
+        graph: &ModuleGraph,
+        extracted_css: &HashSet<ModuleId>,
+        ids: &ModuleIdMap,
+        assets: &HashMap<PathBuf, String>,
+    ) -> String {
+        let mut modules = Vec::new();
+        let mut codes = Vec::new();
+        for &module_id in &chunk.module_ids {
+            let Some(module) = graph.get_module(module_id) else {
+                continue;
+            };
+            let mut imports: Vec<String> = graph.get_dependencies(module_id)
+                .into_iter()
+                .chain(graph.get_dynamic_dependencies(module_id))
+                .filter_map(|id| graph.get_module(id).map(|m| format!("{}={}", path_to_module_id(&m.path), ids.key(&m.path))))
+                .collect();
+            imports.extend(module.dependencies.iter().filter(|d| d.kind == ImportKind::Url).map(|d| {
+                let copy = script_asset_path(&module.path, &d.specifier).and_then(|(path, _)| assets.get(&path));
+                format!("{}={}", d.specifier, copy.map_or("", String::as_str))
+            }));
+            if module.module_type == ModuleType::Css {
+                imports.extend(fonts::font_faces(&module.source).iter().flat_map(|face| &face.sources).map(|source| {
+                    let copy = assets.get(&fonts::source_path(&module.path, &source.specifier).0);
+                    format!("{}={}", source.specifier, copy.map_or("", String::as_str))
+                }));
+            }
+            imports.sort();
+            modules.push(format!(
+                "{}={}\0{:?}\0{}\0{}",
+                path_to_module_id(&module.path),
+                ids.key(&module.path),
+                module.module_type,
+                extracted_css.contains(&module_id),
+                imports.join("\0")
+            ));
+            codes.push(module.transformed.as_deref().unwrap_or(&module.source));
+        }
         
-        for chunk in chunks {
-            // Concatenate all transformed module code
-            let mut bundle_code = String::new();
-            
-            // Add runtime header
-            bundle_code.push_str(&self.generate_runtime_header());
-            
-            for &module_id in &chunk.module_ids {
-                if let Some(module) = graph.get_module(module_id) {
-                    let code = module.transformed.as_ref()
-                        .unwrap_or(&module.source);
-                    
-                    // Wrap module in a function
-                    bundle_code.push_str(&format!(
-                        "\n// Module: {}\n__component_modules__[\"{}\"] = function(module, exports, require) {{\n{}\n}};\n",
-                        module.path.display(),
-                        module.path.display(),
-                        code
-                    ));
+        let minify = [self.minifies(|minify| minify.js) as u8];
+        let inputs: Vec<&[u8]> = parts
+            .iter()
+            .copied()
+            .chain([&minify[..]])
+            .chain(modules.iter().zip(&codes).flat_map(|(module, code)| [module.as_bytes(), code.as_bytes()]))
+            .collect();
+        TransformCache::chunk_key(&inputs, &self.transform_fingerprint)
+    }
+    
+    /// A chunk's code from the cache, or rendered and cached
+    fn cached_chunk(&self, key: String, render: impl FnOnce() -> Result<String>) -> Result<String> {
+        if let Some(code) = self.cache.get_chunk(&key) {
+            return Ok(code);
+        }
+        let code = render()?;
+        self.cache.insert(key, code.clone());
+        Ok(code)
+    }
+    
+    /// Point `import('...')` calls at the runtime's chunk loader
+    fn rewrite_dynamic_imports(&self, code: &str, from: &Path, ids: &ModuleIdMap) -> String {
+        DYNAMIC_IMPORT_REGEX
+            .replace_all(code, |caps: &regex::Captures| {
+                let resolved = self.resolve_dependency(&caps[1], from)
+                    .ok()
+                    .flatten()
+                    .and_then(|path| self.canonical_module_path(&path).ok());
+                match resolved.map(|path| ids.literal(&path)) {
+                    Some(id) => format!("__component_import__({})", id),
+                    None => caps[0].to_string(),
                 }
-            }
-            
-            // Add entry point execution
-            if let ChunkType::Entry = chunk.chunk_type {
-                if let Some(&entry_id) = chunk.module_ids.first() {
-                    if let Some(entry_module) = graph.get_module(entry_id) {
-                        bundle_code.push_str(&format!(
-                            "\n// Execute entry point\n__component_require__(\"{}\");\n",
-                            entry_module.path.display()
-                        ));
-                    }
+            })
+            .into_owned()
+    }
+    
+    /// Point a module's `require('...')` calls at the ids of the modules
+    /// they resolved to in the graph
+    fn rewrite_graph_requires(&self, code: &str, module_id: ModuleId, graph: &ModuleGraph, ids: &ModuleIdMap) -> String {
+        REQUIRE_REGEX
+            .replace_all(code, |caps: &regex::Captures| {
+                match graph.get_resolved(module_id, &caps[1]).and_then(|target| graph.get_module(target)) {
+                    Some(target) => format!("require({})", ids.literal(&target.path)),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+    
+    /// Point the `require('...')` calls of generated modules and stylesheets at module ids
+    fn rewrite_requires(&self, code: &str, from: &Path, ids: &ModuleIdMap) -> String {
+        REQUIRE_REGEX
+            .replace_all(code, |caps: &regex::Captures| {
+                let resolved = self.resolve_dependency_as(&caps[1], from, ImportKind::Require)
+                    .ok()
+                    .flatten()
+                    .and_then(|path| self.canonical_module_path(&path).ok());
+                match resolved.map(|path| ids.literal(&path)) {
+                    Some(id) => format!("require({})", id),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+    
+    /// Ids of the graph's modules, by `output.ids` or readable unless minifying
+    fn module_ids(&self, graph: &ModuleGraph) -> ModuleIdMap {
+        let strategy = self.config.output.ids.unwrap_or(if self.minifies(|minify| minify.js) {
+            ModuleIds::Hashed
+        } else {
+            ModuleIds::Named
+        });
+        let paths = graph.all_module_ids().into_iter().filter_map(|id| graph.get_module(id)).map(|m| m.path.as_path());
+        ModuleIdMap::new(strategy, &self.canonical_root(), paths)
+    }
+    
+    /// Minify a bundle if enabled
+    fn finish_bundle(&self, code: String) -> Result<String> {
+        if self.minifies(|minify| minify.js) {
+            self.minify_code(&code)
+        } else {
+            Ok(code)
+        }
+    }
+    
+    /// Whether the build minifies an asset type of `[output.minify]`
+    fn minifies(&self, asset_type: impl Fn(&MinifyConfig) -> bool) -> bool {
+        self.options.minify && asset_type(&self.config.output.minify)
+    }
+    
+    /// `.<hash>` filename suffix for content, or empty when hashing is off
+    ///
+    /// Library builds are never hashed: packages point at their files by name.
+    fn hash_suffix(&self, contents: impl AsRef<[u8]>) -> String {
+        if self.config.output.hash && !self.config.library.enabled {
+            format!(".{}", &crate::utils::hash_content(contents.as_ref())[..8])
+        } else {
+            String::new()
+        }
+    }
+    
+    /// Where a script, stylesheet, or other asset lands in the output
+    /// directory: in its type's subdirectory with `output.split_by_type`
+    fn typed_file_name(&self, file_name: String) -> String {
+        if !self.config.output.split_by_type {
+            return file_name;
+        }
+        let dir = match file_name.rsplit('.').next() {
+            Some("js" | "mjs" | "cjs") => "js",
+            Some("css") => "css",
+            _ => ASSETS_DIR,
+        };
+        format!("{}/{}", dir, file_name)
+    }
+    
+    /// Render per-locale message files for an entry and its i18n runtime
+    ///
+    /// Only keys used by the given modules are included.
+    fn render_messages(&self, catalogs: &Catalogs, name: &str, module_ids: &[ModuleId], graph: &ModuleGraph) -> Result<(Vec<EmittedFile>, String)> {
+        let mut keys = std::collections::BTreeSet::new();
+        for &module_id in module_ids {
+            if let Some(module) = graph.get_module(module_id) {
+                if module.module_type.is_js_like() {
+                    extract_keys(&module.source, catalogs.functions(), &mut keys);
                 }
             }
+        }
+        
+        let default_locale = self.config.i18n.default_locale();
+        let mut files = Vec::new();
+        let mut message_files = BTreeMap::new();
+        let mut inline = None;
+        
+        for locale in catalogs.locales() {
+            let messages = catalogs.select(locale, &keys);
+            let contents = serde_json::to_string(&messages)?;
+            let file_name = self.typed_file_name(format!("{}.{}{}.json", name, locale, self.hash_suffix(&contents)));
             
-            // Minify if enabled
-            let final_code = if self.options.minify {
-                self.minify_code(&bundle_code)?
-            } else {
-                bundle_code
-            };
-            
-            // Generate hash for filename
-            let hash = if self.config.output.hash {
-                let mut hasher = Sha256::new();
-                hasher.update(final_code.as_bytes());
-                let result = hasher.finalize();
-                format!(".{}", &hex::encode(result)[..8])
-            } else {
-                String::new()
-            };
-            
-            // Write bundle
-            let filename = format!("{}{}.js", chunk.name, hash);
-            let output_path = output_dir.join(&filename);
-            
-            fs::write(&output_path, &final_code)
-                .with_context(|| format!("Failed to write bundle: {}", output_path.display()))?;
-            
-            bundles.push(BundleInfo {
-                output_path,
-                size: final_code.len(),
-                sourcemap_path: None, // TODO: Generate sourcemaps
+            message_files.insert(locale.clone(), file_name.clone());
+            if locale == default_locale && catalogs.inline_default() {
+                inline = Some(messages);
+            }
+            files.push(EmittedFile {
+                file_name,
+                contents: contents.into_bytes(),
+                origin: format!("'{}' messages of entry '{}'", locale, name),
+                entry: None,
             });
         }
         
-        Ok(bundles)
+        let runtime = catalogs.runtime(&message_files, inline.as_ref())?;
+        Ok((files, runtime))
     }
     
     /// Generate the module runtime header
@@ -330,10 +2689,126 @@ impl Bundler {
     return module.exports;
   }
   
+  // Where output files are served from; assign window.__component_public_path__
+  // before this script runs, or any time later, to change it
+  if (window.__component_public_path__ == null) {
+    window.__component_public_path__ = __PUBLIC_URL__;
+  }
+  
+  function __component_asset_url__(file) {
+    return String(window.__component_public_path__).replace(/\/?$/, '/') + file;
+  }
+  
+  // Load a chunk script; retried attempts add a cache-busting query so a
+  // deploy during the session doesn't leave the app on a stale or missing file
+  function __component_load_chunk__(src, attempt) {
+    return new Promise(function(resolve, reject) {
+      var script = document.createElement('script');
+      script.src = attempt ? src + (src.indexOf('?') < 0 ? '?' : '&') + 't=' + Date.now() : src;
+      script.onload = resolve;
+      script.onerror = function() {
+        script.parentNode.removeChild(script);
+        reject(new Error('Failed to load chunk ' + src));
+      };
+      document.head.appendChild(script);
+    })__CHUNK_RETRY__;
+  }
+  
+  // Let the app show its own UI; listeners call preventDefault() to stop
+  // the automatic reload
+  function __component_chunk_error__(moduleId, src, error) {
+    var event = new CustomEvent('component:chunk-error', {
+      cancelable: true,
+      detail: { moduleId: moduleId, src: src, error: error }
+    });
+    window.dispatchEvent(event)__CHUNK_RELOAD__;
+  }
+  
+  // Load the async chunk containing a module, then require it
+  function __component_import__(moduleId) {
+    if (__component_modules__[moduleId]) {
+      return Promise.resolve(__component_require__(moduleId));
+    }
+    var file = (window.__component_chunks__ || {})[moduleId];
+    if (file == null) {
+      return Promise.reject(new Error('No chunk contains module ' + moduleId));
+    }
+    var src = __component_asset_url__(file);
+    return __component_load_chunk__(src, 0).then(function() {
+      return __component_require__(moduleId);
+    }, function(error) {
+      __component_chunk_error__(moduleId, src, error);
+      throw error;
+    });
+  }
+  
+  // Helpers of modules rewritten to CommonJS
+  function __component_define__(exports, getters) {
+    Object.defineProperty(exports, '__esModule', { value: true });
+    Object.keys(getters).forEach(function(name) {
+      Object.defineProperty(exports, name, { enumerable: true, get: getters[name] });
+    });
+  }
+  
+  function __component_default__(exports) {
+    return exports && exports.__esModule ? exports.default : exports;
+  }
+  
+  function __component_export_star__(exports, from) {
+    Object.keys(from).forEach(function(name) {
+      if (name !== 'default' && name !== '__esModule' && !Object.prototype.hasOwnProperty.call(exports, name)) {
+        Object.defineProperty(exports, name, { enumerable: true, get: function() { return from[name]; } });
+      }
+    });
+  }
+  
+  // Exports are read through getters, so there's nothing to copy again
+  function __component_changed__(value) {
+    return value;
+  }
+  
+  // Run an entry module and publish its exports as
+  // window.__component_exports__[name], for inline scripts and other bundles
+  function __component_export__(name, moduleId) {
+    var exports = __component_require__(moduleId);
+    window.__component_exports__ = window.__component_exports__ || {};
+    window.__component_exports__[name] = exports;
+    return exports;
+  }
+  
   window.__component_modules__ = __component_modules__;
   window.__component_require__ = __component_require__;
+  window.__component_import__ = __component_import__;
+  window.__component_export__ = __component_export__;
+  window.__component_asset_url__ = __component_asset_url__;
+  window.__component_define__ = __component_define__;
+  window.__component_default__ = __component_default__;
+  window.__component_export_star__ = __component_export_star__;
+  window.__component_changed__ = __component_changed__;
 })();
-"#.to_string()
+"#
+        .replace("__PUBLIC_URL__", &serde_json::to_string(&self.config.output.public_url).unwrap_or_default())
+        .replace("__CHUNK_RETRY__", &self.chunk_retry_runtime())
+        .replace("__CHUNK_RELOAD__", if self.config.output.reload_on_chunk_error { CHUNK_RELOAD_RUNTIME } else { "" })
+    }
+    
+    /// Retry of a failed chunk load, with exponential backoff, unless
+    /// `output.chunk_retries` is 0
+    fn chunk_retry_runtime(&self) -> String {
+        let retries = self.config.output.chunk_retries;
+        if retries == 0 {
+            return String::new();
+        }
+        format!(
+            r#".catch(function(error) {{
+      if (attempt >= {}) {{
+        throw error;
+      }}
+      return new Promise(function(resolve) {{ setTimeout(resolve, {} * Math.pow(2, attempt)); }})
+        .then(function() {{ return __component_load_chunk__(src, attempt + 1); }});
+    }})"#,
+            retries, CHUNK_RETRY_DELAY_MS
+        )
     }
     
     /// Minify JavaScript code (basic implementation)
@@ -412,27 +2887,357 @@ impl Bundler {
     }
     
     /// Generate asset manifest
-    fn generate_manifest(&self, bundles: &[BundleInfo]) -> Result<HashMap<String, String>> {
-        let mut manifest = HashMap::new();
+    fn generate_manifest(&self, bundles: &[EmittedFile]) -> HashMap<String, String> {
+        let public_url = self.config.output.public_url.trim_end_matches('/');
+        bundles
+            .iter()
+            .map(|bundle| (bundle.file_name.clone(), format!("{}/{}", public_url, bundle.file_name)))
+            .collect()
+    }
+    
+    /// Render provenance.json if enabled, covering the given outputs
+    fn render_provenance<'a>(&self, outputs: impl IntoIterator<Item = &'a EmittedFile>) -> Result<Option<EmittedFile>> {
+        if !self.config.output.provenance {
+            return Ok(None);
+        }
+        
+        let root = self.canonical_root();
+        let inputs = {
+            let graph = self.graph.read();
+            graph.all_module_ids()
+                .into_iter()
+                .filter_map(|id| graph.get_module(id))
+                .map(|module| {
+                    let path = module.path.strip_prefix(&root).unwrap_or(&module.path);
+                    FileDigest::new(path_to_module_id(path), module.source.as_bytes())
+                })
+                .collect()
+        };
+        let outputs = outputs
+            .into_iter()
+            .map(|file| FileDigest::new(file.file_name.clone(), &file.contents))
+            .collect();
+        
+        let mut provenance = Provenance::new(&self.config, inputs, outputs)?;
+        if let Some(key) = &self.config.output.provenance_key {
+            provenance.sign(&load_signing_key(&self.config.root.join(key))?)?;
+        }
+        
+        Ok(Some(EmittedFile {
+            file_name: PROVENANCE_FILE.to_string(),
+            contents: serde_json::to_string_pretty(&provenance)?.into_bytes(),
+            origin: "output.provenance".to_string(),
+            entry: None,
+        }))
+    }
+    
+    /// Render stats.json if enabled, for `component diff`
+    fn render_stats(&self, chunks: &[Chunk], files: &[EmittedFile]) -> Result<Option<EmittedFile>> {
+        if !self.config.output.stats {
+            return Ok(None);
+        }
+        
+        let root = self.canonical_root();
+        let graph = self.graph.read();
+        let module_id = |id: ModuleId| {
+            graph.get_module(id).map(|m| path_to_module_id(m.path.strip_prefix(&root).unwrap_or(&m.path)))
+        };
+        let mut stats = BuildStats::default();
+        
+        for (chunk, file) in self.chunk_files(chunks, files) {
+            // A library entry's ES module build stands for both
+            stats.chunks.entry(chunk.name.clone()).or_insert_with(|| ChunkStats {
+                file: file.file_name.clone(),
+                size: file.contents.len(),
+                modules: chunk.module_ids.iter().filter_map(|&id| module_id(id)).collect(),
+            });
+        }
         
-        for bundle in bundles {
-            if let Some(filename) = bundle.output_path.file_name() {
-                let name = filename.to_string_lossy().to_string();
-                manifest.insert(name.clone(), name);
+        for id in graph.all_module_ids() {
+            let (Some(module), Some(path)) = (graph.get_module(id), module_id(id)) else {
+                continue;
+            };
+            let size = module.transformed.as_ref().map_or(module.source.len(), String::len);
+            if let Some((dir, name)) = stats::package_of(&path) {
+                if !stats.packages.contains_key(name) {
+                    let version = self.fs.read_to_string(&root.join(dir).join("package.json")).ok()
+                        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                        .and_then(|json| json["version"].as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    stats.packages.insert(name.to_string(), version);
+                }
+            }
+            if !module.exports.is_opaque() {
+                stats.exports.insert(path.clone(), graph.export_names(id));
             }
+            stats.modules.insert(path, size);
         }
         
-        // Write manifest file if enabled
-        if self.config.output.manifest {
-            let output_dir = self.options.outdir.clone()
-                .unwrap_or_else(|| self.config.output_dir());
-            let manifest_path = output_dir.join("manifest.json");
-            
-            let manifest_json = serde_json::to_string_pretty(&manifest)?;
-            fs::write(&manifest_path, manifest_json)
-                .context("Failed to write manifest.json")?;
+        Ok(Some(EmittedFile {
+            file_name: STATS_FILE.to_string(),
+            contents: serde_json::to_string_pretty(&stats)?.into_bytes(),
+            origin: "output.stats".to_string(),
+            entry: None,
+        }))
+    }
+    
+    /// Render the manifest in `output.manifest_format` if enabled
+    fn render_manifest(&self, files: &[EmittedFile], manifest: &HashMap<String, String>) -> Result<Option<EmittedFile>> {
+        if !self.config.output.manifest {
+            return Ok(None);
+        }
+        
+        let format = self.config.output.manifest_format;
+        Ok(Some(EmittedFile {
+            file_name: format.file_name().to_string(),
+            contents: format.render(files, manifest)?.into_bytes(),
+            origin: "output.manifest".to_string(),
+            entry: None,
+        }))
+    }
+}
+
+/// Stylesheets of an HTML entry with critical CSS enabled
+struct PageStyles {
+    /// CSS of the modules loaded with the page, inlined into it
+    critical: String,
+    
+    /// All CSS the page can load, including async chunks
+    full: String,
+}
+
+/// Fingerprint of what transform output depends on besides the source:
+/// features and conditions, and the lockfiles that decide how imports resolve
+fn transform_fingerprint(config: &Config, fs: &dyn FileSystem) -> Result<String> {
+    let lockfiles = lockfile_hash(&config.root, fs);
+    Ok(serde_json::to_string(&(&config.features, &config.conditions, lockfiles))?)
+}
+
+/// Specifiers only loaded with `require()`, which resolve to packages'
+/// `require` exports
+fn required_specifiers(dependencies: &[Dependency]) -> HashSet<String> {
+    let mut required: HashSet<String> = dependencies
+        .iter()
+        .filter(|dependency| dependency.kind == ImportKind::Require)
+        .map(|dependency| dependency.specifier.clone())
+        .collect();
+    for dependency in dependencies.iter().filter(|dependency| dependency.kind != ImportKind::Require) {
+        required.remove(&dependency.specifier);
+    }
+    required
+}
+
+/// Size of `contents` compressed with gzip at the default level
+fn gzip_size(contents: &[u8]) -> usize {
+    use std::io::Write;
+    
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let _ = encoder.write_all(contents);
+    encoder.finish().map_or(contents.len(), |compressed| compressed.len())
+}
+
+/// Specifiers imported with `type: 'css'`
+fn stylesheet_imports(dependencies: &[Dependency]) -> HashSet<String> {
+    dependencies
+        .iter()
+        .filter(|dependency| dependency.import_type() == Some("css"))
+        .map(|dependency| dependency.specifier.clone())
+        .collect()
+}
+
+/// File an asset URL in a script names, and the query or fragment after
+/// it; `None` for URLs that aren't relative to the script
+///
+/// A resized copy of an image, `./hero.jpg?width=480`, is named by the
+/// image's path with the query, which isn't kept.
+fn script_asset_path<'a>(script: &Path, specifier: &'a str) -> Option<(PathBuf, &'a str)> {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+    let split = specifier.find(['?', '#']).unwrap_or(specifier.len());
+    let (url, suffix) = specifier.split_at(split);
+    let dir = script.parent().unwrap_or(script);
+    let path = crate::utils::normalize_path(&dir.join(url));
+    
+    // A resized copy of an image is an asset of its own
+    match suffix.strip_prefix('?').filter(|query| images::Variant::parse(query).is_some()) {
+        Some(query) => Some((images::with_query(&path, query), "")),
+        None => Some((path, suffix)),
+    }
+}
+
+/// Point `new URL('./file', import.meta.url)` at the copy of the file in
+/// `assets`, under the runtime's public path
+fn rewrite_asset_urls(code: &str, from: &Path, assets: &HashMap<PathBuf, String>) -> String {
+    ASSET_URL_REGEX
+        .replace_all(code, |caps: &regex::Captures| {
+            let copy = script_asset_path(from, &caps[1]).and_then(|(path, suffix)| Some((assets.get(&path)?, suffix)));
+            match copy {
+                Some((file_name, suffix)) => format!(
+                    "new URL(__component_asset_url__({}), location.href)",
+                    serde_json::json!(format!("{}{}", file_name, suffix))
+                ),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// CSS modules among `module_ids`, in the order they were first imported
+fn css_modules(module_ids: &[ModuleId], graph: &ModuleGraph) -> Vec<ModuleId> {
+    let mut css: Vec<ModuleId> = module_ids
+        .iter()
+        .copied()
+        .filter(|&id| graph.get_module(id).is_some_and(|m| m.module_type == ModuleType::Css))
+        .collect();
+    css.sort_unstable();
+    css
+}
+
+/// Concatenate the sources of CSS modules, with their fonts pointed at the
+/// copies in `assets`
+fn concat_css(module_ids: &[ModuleId], graph: &ModuleGraph, assets: &HashMap<PathBuf, String>, public_url: &str) -> String {
+    module_ids
+        .iter()
+        .filter_map(|&id| graph.get_module(id))
+        .map(|module| fonts::rewrite_sources(css::strip_imports(&module.source).trim(), &module.path, assets, public_url))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render `licenses.json` and `licenses.txt` for a license report
+fn render_licenses(report: Option<&LicenseReport>) -> Result<Vec<EmittedFile>> {
+    let Some(report) = report else {
+        return Ok(Vec::new());
+    };
+    
+    Ok(vec![
+        EmittedFile {
+            file_name: "licenses.json".to_string(),
+            contents: serde_json::to_string_pretty(report)?.into_bytes(),
+            origin: "licenses.report".to_string(),
+            entry: None,
+        },
+        EmittedFile {
+            file_name: "licenses.txt".to_string(),
+            contents: report.to_text().into_bytes(),
+            origin: "licenses.report".to_string(),
+            entry: None,
+        },
+    ])
+}
+
+/// Fail when two outputs would be written to the same file
+///
+/// Names differing only in case collide too, as they do on macOS and
+/// Windows filesystems.
+fn check_collisions<'a>(files: impl IntoIterator<Item = &'a EmittedFile>) -> Result<()> {
+    let mut by_name: BTreeMap<String, Vec<&EmittedFile>> = BTreeMap::new();
+    for file in files {
+        by_name.entry(file.file_name.to_lowercase()).or_default().push(file);
+    }
+    
+    let collisions: Vec<String> = by_name
+        .values()
+        .filter(|files| files.len() > 1)
+        .map(|files| {
+            let origins: Vec<String> = files.iter().map(|f| format!("    {} from {}", f.file_name, f.origin)).collect();
+            format!("  {}:\n{}", files[0].file_name, origins.join("\n"))
+        })
+        .collect();
+    if !collisions.is_empty() {
+        anyhow::bail!(
+            "Several outputs would be written to the same file; rename the entries they come from:\n{}",
+            collisions.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// A chunk name derived from `stem` that no other chunk uses
+fn unique_chunk_name(stem: &str, taken: &mut HashSet<String>) -> String {
+    let base: String = stem.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')).collect();
+    let base = if base.is_empty() { "chunk".to_string() } else { base };
+    
+    let mut name = base.clone();
+    let mut n = 2;
+    while !taken.insert(name.clone()) {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+/// Generated module id for a path relative to the codegen directory,
+/// resolving `.` and `..` lexically
+fn codegen_id(relative: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in relative.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                parts.pop();
+            }
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            _ => {}
         }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn emitted(file_name: &str, origin: &str) -> EmittedFile {
+        EmittedFile { file_name: file_name.to_string(), contents: Vec::new(), origin: origin.to_string(), entry: None }
+    }
+    
+    #[tokio::test]
+    async fn test_affected_files_include_transitive_importers() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/project/src/main.js", "import { b } from './b.js';\nconsole.log(b);\n");
+        fs.write("/project/src/b.js", "import { c } from './c.js';\nexport const b = c;\n");
+        fs.write("/project/src/c.js", "export const c = 1;\n");
+        fs.write("/project/src/other.js", "export const other = 1;\n");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.entrypoints.insert("other".to_string(), "src/other.js".to_string());
+        let bundler = Bundler::new(config, BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        })
+        .unwrap()
+        .with_fs(fs)
+        .unwrap();
+        bundler.build_graph().await.unwrap();
+        
+        let mut affected = bundler.affected_files(&[PathBuf::from("/project/src/c.js"), PathBuf::from("/project/src/missing.js")]);
+        affected.sort();
+        assert_eq!(affected, vec![
+            PathBuf::from("/project/src/b.js"),
+            PathBuf::from("/project/src/c.js"),
+            PathBuf::from("/project/src/main.js"),
+        ]);
+    }
+    
+    #[test]
+    fn test_check_collisions() {
+        let files = [emitted("main.js", "entry 'main'"), emitted("Main.js", "entry 'Main'")];
+        let error = check_collisions(&files).unwrap_err().to_string();
+        assert!(error.contains("    main.js from entry 'main'\n    Main.js from entry 'Main'"), "{}", error);
+        
+        // Metadata files count like any other output
+        let files = [emitted("manifest.json", "asset src/manifest.json of src/main.js"), emitted("manifest.json", "output.manifest")];
+        let error = check_collisions(&files).unwrap_err().to_string();
+        assert!(error.contains("manifest.json from output.manifest"), "{}", error);
+        let files = [emitted("Stats.json", "entry 'Stats'"), emitted("stats.json", "output.stats")];
+        assert!(check_collisions(&files).is_err());
         
-        Ok(manifest)
+        let files = [emitted("main.js", "entry 'main'"), emitted("main.css", "entry 'main'"), emitted("manifest.json", "output.manifest")];
+        assert!(check_collisions(&files).is_ok());
     }
 }
@@ -0,0 +1,197 @@
+//! Build provenance
+//!
+//! `provenance.json` records what went into a build and what came out, so
+//! the produced bundles can be verified and the build reproduced. It holds
+//! no timestamps: identical inputs always produce an identical document.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+
+/// File name of the provenance document in the output directory
+pub const PROVENANCE_FILE: &str = "provenance.json";
+
+/// Current provenance document format
+const PROVENANCE_VERSION: u32 = 1;
+
+/// Inputs, configuration, and outputs of a build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Document format version
+    pub version: u32,
+    
+    /// Tool that produced the build
+    pub tool: ToolInfo,
+    
+    /// SHA-256 of the normalized configuration
+    pub config_hash: String,
+    
+    /// Source files, sorted by path
+    pub inputs: Vec<FileDigest>,
+    
+    /// Emitted files, sorted by path
+    pub outputs: Vec<FileDigest>,
+    
+    /// Signature over the document without this field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ProvenanceSignature>,
+}
+
+/// Tool name and version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub version: String,
+}
+
+/// Path and content hash of a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDigest {
+    /// Root-relative path for inputs, outdir-relative for outputs
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Detached signature of a provenance document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceSignature {
+    /// Always `ed25519`
+    pub algorithm: String,
+    
+    /// Hex-encoded public key
+    pub public_key: String,
+    
+    /// Hex-encoded signature
+    pub value: String,
+}
+
+impl FileDigest {
+    pub fn new(path: impl Into<String>, contents: &[u8]) -> Self {
+        Self {
+            path: path.into(),
+            sha256: hex::encode(Sha256::digest(contents)),
+        }
+    }
+}
+
+impl Provenance {
+    /// Create an unsigned provenance document
+    pub fn new(config: &Config, mut inputs: Vec<FileDigest>, mut outputs: Vec<FileDigest>) -> Result<Self> {
+        inputs.sort_by(|a, b| a.path.cmp(&b.path));
+        outputs.sort_by(|a, b| a.path.cmp(&b.path));
+        
+        Ok(Self {
+            version: PROVENANCE_VERSION,
+            tool: ToolInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            config_hash: config_hash(config)?,
+            inputs,
+            outputs,
+            signature: None,
+        })
+    }
+    
+    /// Sign the document, replacing any existing signature
+    pub fn sign(&mut self, key: &SigningKey) -> Result<()> {
+        self.signature = None;
+        let signature = key.sign(&self.signed_bytes()?);
+        
+        self.signature = Some(ProvenanceSignature {
+            algorithm: "ed25519".to_string(),
+            public_key: hex::encode(key.verifying_key().as_bytes()),
+            value: hex::encode(signature.to_bytes()),
+        });
+        Ok(())
+    }
+    
+    /// Check the signature against the document contents
+    ///
+    /// Returns `false` when the document is unsigned or has been modified.
+    pub fn verify(&self) -> Result<bool> {
+        let Some(signature) = &self.signature else {
+            return Ok(false);
+        };
+        anyhow::ensure!(signature.algorithm == "ed25519", "Unsupported signature algorithm: {}", signature.algorithm);
+        
+        let public_key: [u8; 32] = hex::decode(&signature.public_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid public key length"))?;
+        let value: [u8; 64] = hex::decode(&signature.value)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid signature length"))?;
+        
+        let key = VerifyingKey::from_bytes(&public_key)?;
+        let unsigned = Self { signature: None, ..self.clone() };
+        Ok(key.verify(&unsigned.signed_bytes()?, &Signature::from_bytes(&value)).is_ok())
+    }
+    
+    /// Bytes covered by the signature
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+/// Load an Ed25519 private key from a PKCS#8 PEM file
+///
+/// Generate one with `openssl genpkey -algorithm ed25519 -out key.pem`.
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let pem = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read provenance key: {}", path.display()))?;
+    SigningKey::from_pkcs8_pem(&pem)
+        .map_err(|e| anyhow::anyhow!("Invalid provenance key {}: {}", path.display(), e))
+}
+
+/// Hash of the configuration with keys in a stable order
+///
+/// The signing key location is machine-specific and does not affect the
+/// build, so it is left out.
+fn config_hash(config: &Config) -> Result<String> {
+    let mut config = config.clone();
+    config.output.provenance_key = None;
+    
+    // Round-tripping through `Value` sorts map keys
+    let normalized = serde_json::to_value(&config)?;
+    Ok(hex::encode(Sha256::digest(serde_json::to_vec(&normalized)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn provenance() -> Provenance {
+        Provenance::new(
+            &Config::default_config(),
+            vec![FileDigest::new("src/b.js", b"b"), FileDigest::new("src/a.js", b"a")],
+            vec![FileDigest::new("main.js", b"bundle")],
+        )
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_provenance_is_deterministic() {
+        let provenance = provenance();
+        assert_eq!(provenance, self::provenance());
+        assert_eq!(provenance.inputs[0].path, "src/a.js");
+    }
+    
+    #[test]
+    fn test_sign_and_verify() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut provenance = provenance();
+        assert!(!provenance.verify().unwrap());
+        
+        provenance.sign(&key).unwrap();
+        assert!(provenance.verify().unwrap());
+        
+        provenance.outputs[0].sha256 = "0".repeat(64);
+        assert!(!provenance.verify().unwrap());
+    }
+}
@@ -0,0 +1,56 @@
+//! Runtime public path
+//!
+//! Output files are loaded from `window.__component_public_path__`, which
+//! starts as `output.public_url`. Apps served under a base path only known
+//! at runtime (reverse proxies, CDNs) assign it before the bundle runs, and
+//! build asset URLs with the `component:asset-url` module.
+
+use std::path::{Path, PathBuf};
+
+/// Specifier of the asset URL helper module
+pub const ASSET_URL_MODULE: &str = "component:asset-url";
+
+/// File name of the helper module in the project root; it never exists on disk
+const ASSET_URL_FILE: &str = "__component_asset_url__.js";
+
+/// Path the helper module appears at
+pub fn asset_url_module_path(root: &Path) -> PathBuf {
+    root.join(ASSET_URL_FILE)
+}
+
+/// Source of the helper module
+pub fn render_asset_url_module(public_url: &str) -> String {
+    ASSET_URL_SOURCE.replace("__PUBLIC_URL__", &serde_json::to_string(public_url).unwrap_or_default())
+}
+
+const ASSET_URL_SOURCE: &str = r#"function base() {
+  var path = window.__component_public_path__;
+  return (path == null ? __PUBLIC_URL__ : String(path)).replace(/\/?$/, '/');
+}
+
+export function publicPath() {
+  return base();
+}
+
+export function setPublicPath(path) {
+  window.__component_public_path__ = path;
+}
+
+export function assetUrl(path) {
+  return base() + String(path).replace(/^\/+/, '');
+}
+
+export default assetUrl;
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_render_asset_url_module() {
+        let code = render_asset_url_module("/app/");
+        assert!(code.contains(r#"path == null ? "/app/" : String(path)"#));
+        assert!(code.contains("export function assetUrl(path)"));
+    }
+}
@@ -0,0 +1,207 @@
+//! Build progress reporting
+//!
+//! The bundler reports progress through a [`Reporter`], so CI systems and
+//! GUIs can consume structured events instead of parsing log output.
+
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, warn};
+
+use super::BuildResult;
+
+/// Receives build progress events; every method defaults to doing nothing
+pub trait Reporter: Send + Sync {
+    /// A build started
+    fn build_started(&self, _entrypoints: usize) {}
+    
+    /// A module went through the core transform and plugins
+    ///
+    /// `path` is relative to the project root.
+    fn module_transformed(&self, _path: &Path, _duration: Duration, _cached: bool) {}
+    
+    /// An output file was written
+    fn chunk_emitted(&self, _file_name: &str, _size: usize) {}
+    
+    /// Something worth the user's attention that doesn't fail the build
+    fn warning(&self, _message: &str) {}
+    
+    /// The build finished
+    fn build_finished(&self, _result: &BuildResult) {}
+    
+    /// The build failed
+    fn build_failed(&self, _error: &anyhow::Error) {}
+}
+
+/// Built-in reporters selectable with `output.reporter`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "native", derive(clap::ValueEnum))]
+pub enum ReporterKind {
+    /// Human-readable summary on stderr
+    #[default]
+    Console,
+    
+    /// One JSON object per event on stdout
+    Json,
+}
+
+/// Forwards warnings to the log; the bundler's default reporter
+pub struct TracingReporter;
+
+impl Reporter for TracingReporter {
+    fn module_transformed(&self, path: &Path, duration: Duration, cached: bool) {
+        debug!("Transformed {} in {:?}{}", path.display(), duration, if cached { " (cached)" } else { "" });
+    }
+    
+    fn warning(&self, message: &str) {
+        warn!("{}", message);
+    }
+}
+
+/// Prints warnings and a summary of the written files to stderr
+#[cfg(feature = "native")]
+pub struct ConsoleReporter;
+
+#[cfg(feature = "native")]
+impl Reporter for ConsoleReporter {
+    fn warning(&self, message: &str) {
+        use colored::Colorize;
+        eprintln!("{} {}", "⚠".yellow(), message);
+    }
+    
+    fn build_finished(&self, result: &BuildResult) {
+        use colored::Colorize;
+        
+        let seconds = format!("{:.2}", result.timings.total.as_secs_f64());
+        eprintln!(
+            "\n{} {}\n",
+            "✓".green().bold(),
+            crate::msg!("build.finished", count = result.bundles.len(), seconds = seconds)
+        );
+        
+        for bundle in &result.bundles {
+            let size_str = crate::msg!("build.file_size", size = format_size(bundle.size), gzip = format_size(bundle.gzip_size));
+            let unchanged = if bundle.unchanged { format!(" {}", crate::msg!("build.unchanged")) } else { String::new() };
+            eprintln!(
+                "  {} {} {}{}",
+                "•".dimmed(),
+                bundle.output_path.display().to_string().cyan(),
+                size_str.dimmed(),
+                unchanged.dimmed()
+            );
+        }
+        
+        if !result.warnings.is_empty() {
+            eprintln!("\n  {} {}", "⚠".yellow(), crate::msg!("build.warnings", count = result.warnings.len()));
+        }
+    }
+}
+
+/// Size in KB, or MB from 1 MB up
+#[cfg(feature = "native")]
+fn format_size(bytes: usize) -> String {
+    let size_kb = bytes as f64 / 1024.0;
+    if size_kb > 1024.0 {
+        format!("{:.2} MB", size_kb / 1024.0)
+    } else {
+        format!("{:.2} KB", size_kb)
+    }
+}
+
+/// Writes one JSON object per line for every event
+pub struct JsonReporter {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonReporter {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self { out: Mutex::new(out) }
+    }
+    
+    /// Report to stdout
+    pub fn stdout() -> Self {
+        Self::new(Box::new(std::io::stdout()))
+    }
+    
+    fn emit(&self, event: serde_json::Value) {
+        let mut out = self.out.lock();
+        let _ = writeln!(out, "{}", event);
+        let _ = out.flush();
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn build_started(&self, entrypoints: usize) {
+        self.emit(json!({ "event": "build_started", "entrypoints": entrypoints }));
+    }
+    
+    fn module_transformed(&self, path: &Path, duration: Duration, cached: bool) {
+        self.emit(json!({
+            "event": "module_transformed",
+            "path": crate::utils::path_to_module_id(path),
+            "duration_ms": duration.as_secs_f64() * 1000.0,
+            "cached": cached,
+        }));
+    }
+    
+    fn chunk_emitted(&self, file_name: &str, size: usize) {
+        self.emit(json!({ "event": "chunk_emitted", "file": file_name, "size": size }));
+    }
+    
+    fn warning(&self, message: &str) {
+        self.emit(json!({ "event": "warning", "message": message }));
+    }
+    
+    fn build_finished(&self, result: &BuildResult) {
+        self.emit(json!({
+            "event": "build_finished",
+            "duration_ms": result.timings.total.as_secs_f64() * 1000.0,
+            "bundles": result.bundles.len(),
+            "unchanged": result.bundles.iter().filter(|bundle| bundle.unchanged).count(),
+            "result": result,
+        }));
+    }
+    
+    fn build_failed(&self, error: &anyhow::Error) {
+        self.emit(json!({ "event": "build_failed", "error": format!("{:#}", error) }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+    
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().write(buf)
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_json_reporter_writes_lines() {
+        let buffer = Buffer::default();
+        let reporter = JsonReporter::new(Box::new(buffer.clone()));
+        reporter.module_transformed(Path::new("src/a.js"), Duration::from_millis(2), true);
+        reporter.warning("careful");
+        
+        let output = String::from_utf8(buffer.0.lock().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(events[0]["event"], "module_transformed");
+        assert_eq!(events[0]["path"], "src/a.js");
+        assert_eq!(events[0]["cached"], true);
+        assert_eq!(events[1], json!({ "event": "warning", "message": "careful" }));
+    }
+}
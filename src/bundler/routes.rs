@@ -0,0 +1,162 @@
+//! File-based routing
+//!
+//! Every page under the routes directory becomes a route with a lazy
+//! `import()`, so each page is emitted as its own async chunk. Importing
+//! `virtual:routes` yields the route table:
+//!
+//! ```js
+//! import routes from 'virtual:routes';
+//! // [{ path: '/blog/:slug', file: 'blog/[slug].jsx', component: () => import(...) }, ...]
+//! ```
+//!
+//! The `component` loaders plug directly into Vue Router, and into React
+//! Router via `React.lazy(route.component)`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::Module;
+use crate::vfs::FileSystem;
+
+/// Specifier of the generated routes module
+pub const ROUTES_MODULE: &str = "virtual:routes";
+
+/// File name of the routes module inside the routes directory
+///
+/// The module never exists on disk; placing it there lets page imports be
+/// plain relative paths.
+const ROUTES_FILE: &str = "__component_routes__.js";
+
+/// A page and the URL path it serves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// URL pattern (`/`, `/about`, `/blog/:slug`, `/*`)
+    pub path: String,
+    
+    /// Page path relative to the routes directory, with `/` separators
+    pub file: String,
+}
+
+/// Path the routes module is registered under in the module graph
+pub fn routes_module_path(dir: &Path) -> PathBuf {
+    dir.join(ROUTES_FILE)
+}
+
+/// Find all pages under `dir`
+///
+/// Files and directories starting with `_` or `.` are skipped, so layouts
+/// and helpers can live next to pages. Static routes sort before dynamic
+/// ones, and catch-all routes last, so first-match routers pick the most
+/// specific route.
+pub fn discover_routes(dir: &Path, fs: &dyn FileSystem) -> Result<Vec<Route>> {
+    let mut routes = Vec::new();
+    if fs.is_dir(dir) {
+        collect(dir, dir, fs, &mut routes)?;
+    }
+    routes.sort_by(|a, b| (rank(&a.path), &a.path).cmp(&(rank(&b.path), &b.path)));
+    Ok(routes)
+}
+
+fn collect(dir: &Path, base: &Path, fs: &dyn FileSystem, routes: &mut Vec<Route>) -> Result<()> {
+    for entry in fs.read_dir(dir)? {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('_') || name.starts_with('.') {
+            continue;
+        }
+        
+        if fs.is_dir(&entry) {
+            collect(&entry, base, fs, routes)?;
+        } else if is_page(&entry) {
+            let relative = entry.strip_prefix(base).unwrap_or(&entry);
+            routes.push(Route {
+                path: route_path(relative),
+                file: crate::utils::path_to_module_id(relative),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Whether a file is a page: any JS-like module except type declarations
+fn is_page(path: &Path) -> bool {
+    Module::detect_type(path).is_js_like() && !path.to_string_lossy().ends_with(".d.ts")
+}
+
+/// URL pattern for a page path relative to the routes directory
+fn route_path(relative: &Path) -> String {
+    let mut segments = Vec::new();
+    for component in relative.with_extension("").components() {
+        let segment = component.as_os_str().to_string_lossy();
+        let segment = if segment.starts_with("[...") && segment.ends_with(']') {
+            "*".to_string()
+        } else if let Some(param) = segment.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            format!(":{}", param)
+        } else {
+            segment.into_owned()
+        };
+        segments.push(segment);
+    }
+    
+    if segments.last().is_some_and(|s| s == "index") {
+        segments.pop();
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// Sort key: static routes, then dynamic, then catch-all
+fn rank(path: &str) -> u8 {
+    if path.contains('*') {
+        2
+    } else if path.contains(':') {
+        1
+    } else {
+        0
+    }
+}
+
+/// Source of the routes module
+pub fn render_routes_module(routes: &[Route]) -> String {
+    let mut code = String::from("export const routes = [\n");
+    for route in routes {
+        let specifier = serde_json::to_string(&format!("./{}", route.file)).unwrap_or_default();
+        code.push_str(&format!(
+            "  {{ path: {}, file: {}, component: () => import({}) }},\n",
+            serde_json::to_string(&route.path).unwrap_or_default(),
+            serde_json::to_string(&route.file).unwrap_or_default(),
+            specifier,
+        ));
+    }
+    code.push_str("];\n\nexport default routes;\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_discover_routes() {
+        let fs = MemoryFs::new();
+        for file in [
+            "index.jsx", "about.tsx", "blog/index.js", "blog/[slug].jsx",
+            "[...all].jsx", "_layout.jsx", "_components/Nav.jsx", "styles.css",
+        ] {
+            fs.write(Path::new("/p/pages").join(file), "");
+        }
+        
+        let routes = discover_routes(Path::new("/p/pages"), &fs).unwrap();
+        let paths: Vec<(&str, &str)> = routes.iter().map(|r| (r.path.as_str(), r.file.as_str())).collect();
+        assert_eq!(paths, [
+            ("/", "index.jsx"),
+            ("/about", "about.tsx"),
+            ("/blog", "blog/index.js"),
+            ("/blog/:slug", "blog/[slug].jsx"),
+            ("/*", "[...all].jsx"),
+        ]);
+        
+        let module = render_routes_module(&routes[3..4]);
+        assert!(module.contains(r#"{ path: "/blog/:slug", file: "blog/[slug].jsx", component: () => import("./blog/[slug].jsx") }"#));
+    }
+}
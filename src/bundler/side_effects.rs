@@ -0,0 +1,112 @@
+//! Side effects of modules, for tree shaking
+//!
+//! A module has side effects unless the nearest package.json says otherwise
+//! with `"sideEffects": false` or a list of the files that do, as in
+//! `["*.css", "./src/polyfills.js"]`. Packages that get it wrong are
+//! corrected in `[optimization.side_effects]`, whose globs are matched
+//! against paths relative to the project root and win over package.json;
+//! when several match, the longest glob wins.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use serde_json::Value;
+
+use crate::utils::path_to_module_id;
+use crate::vfs::FileSystem;
+
+/// Side effect overrides and package metadata lookups
+#[derive(Debug, Default)]
+pub struct SideEffects {
+    /// Overrides from `[optimization.side_effects]`, longest glob first
+    overrides: Vec<(String, GlobMatcher, bool)>,
+}
+
+impl SideEffects {
+    /// Compile the override globs
+    pub fn new<'a>(overrides: impl IntoIterator<Item = (&'a String, &'a bool)>) -> Result<Self> {
+        let mut compiled = overrides
+            .into_iter()
+            .map(|(pattern, &value)| {
+                let glob = Glob::new(pattern.trim_start_matches("./"))
+                    .with_context(|| format!("Invalid optimization.side_effects glob: {}", pattern))?
+                    .compile_matcher();
+                Ok((pattern.clone(), glob, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        compiled.sort_by_key(|(pattern, _, _)| std::cmp::Reverse(pattern.len()));
+        Ok(Self { overrides: compiled })
+    }
+    
+    /// Whether importing the module at `path` may have effects beyond its
+    /// exports; `root` is the project root overrides are relative to
+    pub fn has_side_effects(&self, path: &Path, root: &Path, fs: &dyn FileSystem) -> bool {
+        let relative = path_to_module_id(path.strip_prefix(root).unwrap_or(path));
+        if let Some((_, _, value)) = self.overrides.iter().find(|(_, glob, _)| glob.is_match(&relative)) {
+            return *value;
+        }
+        
+        let Some((package_dir, package)) = nearest_package(path, fs) else {
+            return true;
+        };
+        match package.get("sideEffects") {
+            Some(Value::Bool(value)) => *value,
+            Some(Value::Array(patterns)) => {
+                let relative = path_to_module_id(path.strip_prefix(&package_dir).unwrap_or(path));
+                patterns.iter().filter_map(Value::as_str).any(|pattern| matches_package_pattern(pattern, &relative))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// The package.json nearest to a module, with its directory
+fn nearest_package(path: &Path, fs: &dyn FileSystem) -> Option<(PathBuf, Value)> {
+    path.ancestors().skip(1).find_map(|dir| {
+        let contents = fs.read_to_string(&dir.join("package.json")).ok()?;
+        Some((dir.to_path_buf(), serde_json::from_str(&contents).ok()?))
+    })
+}
+
+/// Whether a `sideEffects` pattern matches a path relative to its package;
+/// patterns without a `/` match the file name anywhere, as bundlers do
+fn matches_package_pattern(pattern: &str, relative: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./");
+    let pattern = if pattern.contains('/') { pattern.to_string() } else { format!("**/{}", pattern) };
+    Glob::new(&pattern).is_ok_and(|glob| glob.compile_matcher().is_match(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_side_effects() {
+        let fs = MemoryFs::new();
+        fs.write("/app/package.json", r#"{ "name": "app" }"#);
+        fs.write("/app/packages/ui/package.json", r#"{ "sideEffects": ["*.css", "./src/polyfill.js"] }"#);
+        fs.write("/app/packages/utils/package.json", r#"{ "sideEffects": false }"#);
+        
+        let overrides = BTreeMap::from([
+            ("packages/utils/src/register.js".to_string(), true),
+            ("packages/ui/**".to_string(), false),
+        ]);
+        let side_effects = SideEffects::new(&overrides).unwrap();
+        let check = |path: &str| side_effects.has_side_effects(Path::new(path), Path::new("/app"), &fs);
+        
+        assert!(check("/app/src/main.js"));
+        assert!(!check("/app/packages/utils/src/format.js"));
+        assert!(check("/app/packages/utils/src/register.js"));
+        assert!(!check("/app/packages/ui/src/button.css"));
+        
+        let side_effects = SideEffects::new([]).unwrap();
+        let check = |path: &str| side_effects.has_side_effects(Path::new(path), Path::new("/app"), &fs);
+        assert!(check("/app/packages/ui/src/theme/button.css"));
+        assert!(check("/app/packages/ui/src/polyfill.js"));
+        assert!(!check("/app/packages/ui/src/button.js"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Module graph snapshots for warm dev server starts
+//!
+//! A snapshot holds every module's transformed code, the modules its imports
+//! resolved to, and content hashes of its source and plugin watch files. A
+//! restarted dev server restores it and only reads and transforms again the
+//! modules whose hashes no longer match. Snapshots taken by another version
+//! of Component, with another config, or before the lockfile changed are
+//! ignored.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Dependency, ModuleGraph, ModuleId, ModuleType};
+use crate::config::Config;
+use crate::resolver::lockfile_hash;
+use crate::utils::{hash_content, hash_parts};
+use crate::vfs::FileSystem;
+
+/// Snapshot file name inside `cache.dir`
+const SNAPSHOT_FILE: &str = "dev-graph.json";
+
+/// A fully transformed module graph
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    /// Fingerprint of the version, config, and lockfiles the graph was built with
+    pub fingerprint: String,
+    
+    /// Modules in ID order
+    pub modules: Vec<ModuleSnapshot>,
+}
+
+/// A module in a [`GraphSnapshot`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModuleSnapshot {
+    pub path: PathBuf,
+    
+    /// Hash of the source the module was transformed from
+    pub source_hash: String,
+    
+    pub module_type: ModuleType,
+    pub is_entry: bool,
+    pub dependencies: Vec<Dependency>,
+    pub transformed: String,
+    pub provenance: Vec<String>,
+    
+    /// Plugin watch files with hashes of their contents
+    pub watch_files: Vec<(PathBuf, String)>,
+    
+    /// Data plugins attached to the module
+    #[serde(default)]
+    pub meta: serde_json::Map<String, serde_json::Value>,
+    
+    /// Indices of statically imported modules
+    pub imports: Vec<usize>,
+    
+    /// Indices of dynamically imported modules
+    pub dynamic_imports: Vec<usize>,
+    
+    /// Index of the module each specifier resolved to
+    #[serde(default)]
+    pub resolved: Vec<(String, usize)>,
+}
+
+impl GraphSnapshot {
+    /// Where the dev server keeps its snapshot between runs
+    pub fn path(config: &Config) -> PathBuf {
+        config.root.join(&config.cache.dir).join(SNAPSHOT_FILE)
+    }
+    
+    /// Snapshot a graph; `None` while a module has not been transformed
+    pub fn capture(graph: &ModuleGraph, fingerprint: String, fs: &dyn FileSystem) -> Option<Self> {
+        let mut ids = graph.all_module_ids();
+        ids.sort_unstable();
+        let index: HashMap<ModuleId, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let indices = |deps: Vec<ModuleId>| -> Vec<usize> {
+            let mut deps: Vec<usize> = deps.iter().filter_map(|dep| index.get(dep).copied()).collect();
+            deps.sort_unstable();
+            deps
+        };
+        
+        let modules = ids
+            .iter()
+            .map(|&id| {
+                let module = graph.get_module(id)?;
+                Some(ModuleSnapshot {
+                    path: module.path.clone(),
+                    source_hash: hash_content(module.source.as_bytes()),
+                    module_type: module.module_type.clone(),
+                    is_entry: module.is_entry,
+                    dependencies: module.dependencies.clone(),
+                    transformed: module.transformed.clone()?,
+                    provenance: module.provenance.clone(),
+                    watch_files: module.watch_files
+                        .iter()
+                        .map(|file| (file.clone(), file_hash(fs, file).unwrap_or_default()))
+                        .collect(),
+                    meta: module.meta.clone(),
+                    imports: indices(graph.get_dependencies(id)),
+                    dynamic_imports: indices(graph.get_dynamic_dependencies(id)),
+                    resolved: module
+                        .specifiers()
+                        .into_iter()
+                        .filter_map(|specifier| {
+                            let target = index.get(&graph.get_resolved(id, &specifier)?)?;
+                            Some((specifier, *target))
+                        })
+                        .collect(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        
+        Some(Self { fingerprint, modules })
+    }
+    
+    /// Read a snapshot; `None` when it is missing or unreadable
+    pub fn load(path: &Path) -> Option<Self> {
+        let json = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+    
+    /// Write the snapshot, creating its directory
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Fingerprint of everything outside the modules that a snapshot depends on
+pub fn fingerprint(config: &Config, fs: &dyn FileSystem) -> Result<String> {
+    let settings = serde_json::to_vec(config)?;
+    let lockfiles = lockfile_hash(&config.root, fs);
+    Ok(hash_parts(&[env!("CARGO_PKG_VERSION").as_bytes(), &settings, lockfiles.as_bytes()]))
+}
+
+/// Hash of a file's contents; `None` when it can't be read
+pub fn file_hash(fs: &dyn FileSystem, path: &Path) -> Option<String> {
+    fs.read(path).ok().map(|contents| hash_content(&contents))
+}
@@ -0,0 +1,172 @@
+//! Source maps for transformed modules
+//!
+//! The built-in transforms only remove code (type annotations, interfaces)
+//! or rewrite it in place, so each generated line still starts like the
+//! source line it came from. Maps are built at line granularity by matching
+//! generated lines to source lines in order, which is enough for devtools to
+//! show the original TypeScript or JSX and to set breakpoints by line.
+
+use base64::Engine;
+use serde::Serialize;
+
+use crate::config::SourcemapConfig;
+
+/// How far ahead of the last matched line a generated line is looked for
+const MATCH_WINDOW: usize = 64;
+
+/// Base64 digits of VLQ-encoded numbers
+const BASE64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A version 3 source map
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMap {
+    pub version: u8,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_root: Option<String>,
+    
+    pub sources: Vec<String>,
+    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources_content: Option<Vec<String>>,
+    
+    pub names: Vec<String>,
+    pub mappings: String,
+}
+
+impl SourceMap {
+    /// Map a module's generated code back to its source
+    ///
+    /// `source_url` is the source's path relative to the root, prefixed by
+    /// the base path unless `sourcemap.source_root` is set.
+    pub fn for_module(source: &str, generated: &str, source_url: &str, config: &SourcemapConfig) -> Self {
+        let source_url = match &config.source_root {
+            Some(_) => source_url.trim_start_matches('/').to_string(),
+            None => source_url.to_string(),
+        };
+        
+        Self {
+            version: 3,
+            source_root: config.source_root.clone(),
+            sources: vec![source_url],
+            sources_content: config.sources_content.then(|| vec![source.to_string()]),
+            names: Vec::new(),
+            mappings: encode_mappings(&match_lines(source, generated)),
+        }
+    }
+    
+    /// `//# sourceMappingURL` comment embedding the map as a data URL
+    pub fn to_inline_comment(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        format!(
+            "//# sourceMappingURL=data:application/json;charset=utf-8;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(json)
+        )
+    }
+}
+
+/// Source line each generated line came from; `None` for blank lines
+fn match_lines(source: &str, generated: &str) -> Vec<Option<usize>> {
+    let source_lines: Vec<&str> = source.lines().map(str::trim).collect();
+    let mut next = 0;
+    
+    generated
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            if line.is_empty() || source_lines.is_empty() {
+                return None;
+            }
+            
+            let prefix = line_prefix(line);
+            let end = (next + MATCH_WINDOW).min(source_lines.len());
+            match (next..end).find(|&i| source_lines[i].starts_with(prefix)) {
+                Some(found) => {
+                    next = found + 1;
+                    Some(found)
+                }
+                None => Some(next.min(source_lines.len() - 1)),
+            }
+        })
+        .collect()
+}
+
+/// Start of a line that transforms leave alone: the leading words, or the
+/// first character when the line starts with punctuation
+fn line_prefix(line: &str) -> &str {
+    let end = line
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == ' '))
+        .unwrap_or(line.len());
+    match line[..end].trim_end() {
+        "" => &line[..line.chars().next().map_or(0, char::len_utf8)],
+        words => words,
+    }
+}
+
+/// Encode one segment per mapped line, at column 0 of line and source
+fn encode_mappings(lines: &[Option<usize>]) -> String {
+    let mut mappings = String::new();
+    let mut previous = 0i64;
+    
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            mappings.push(';');
+        }
+        if let Some(line) = *line {
+            let line = line as i64;
+            for value in [0, 0, line - previous, 0] {
+                encode_vlq(value, &mut mappings);
+            }
+            previous = line;
+        }
+    }
+    mappings
+}
+
+/// Append a base64 VLQ number
+fn encode_vlq(value: i64, out: &mut String) {
+    let mut vlq = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = (vlq & 0b11111) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64_DIGITS[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_module_source_map() {
+        let source = "interface User {\n  name: string;\n}\n\nconst name: string = 'a';\nexport function greet(user: User) {\n  return name;\n}\n";
+        let generated = "\n\nconst name = 'a';\nexport function greet(user) {\n  return name;\n}\n";
+        assert_eq!(match_lines(source, generated), vec![None, None, Some(4), Some(5), Some(6), Some(7)]);
+        
+        let map = SourceMap::for_module(source, generated, "/src/greet.ts", &SourcemapConfig::default());
+        assert_eq!(map.mappings, ";;AAIA;AACA;AACA;AACA");
+        assert_eq!(map.sources, vec!["/src/greet.ts"]);
+        assert_eq!(map.sources_content.as_deref(), Some(&[source.to_string()][..]));
+        assert!(map.to_inline_comment().starts_with("//# sourceMappingURL=data:application/json;charset=utf-8;base64,"));
+        
+        let config = SourcemapConfig {
+            sources_content: false,
+            source_root: Some("webpack://app/".to_string()),
+        };
+        let map = SourceMap::for_module(source, generated, "/src/greet.ts", &config);
+        assert_eq!(map.sources, vec!["src/greet.ts"]);
+        assert!(map.sources_content.is_none());
+        
+        let mut encoded = String::new();
+        encode_vlq(-17, &mut encoded);
+        encode_vlq(1000, &mut encoded);
+        assert_eq!(encoded, "jBw+B");
+    }
+}
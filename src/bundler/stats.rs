@@ -0,0 +1,190 @@
+//! Build stats
+//!
+//! `stats.json` lists each chunk's file, size and modules, the names each
+//! script exports, and the packages bundled from `node_modules`. `component diff` compares two of them, so a
+//! pull request or deploy can show what a change does to the bundles.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// File name of the stats document in the output directory
+pub const STATS_FILE: &str = "stats.json";
+
+/// Sizes and contents of a build's chunks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BuildStats {
+    /// Chunks by name
+    pub chunks: BTreeMap<String, ChunkStats>,
+    
+    /// Size of each module's transformed code, by path relative to the root
+    pub modules: BTreeMap<String, usize>,
+    
+    /// Versions of bundled packages by name; empty when unknown
+    pub packages: BTreeMap<String, String>,
+    
+    /// Names each script exports, by path relative to the root, for
+    /// scripts whose exports are known
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub exports: BTreeMap<String, Vec<String>>,
+}
+
+/// One chunk of a build
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkStats {
+    /// Output file, relative to the output directory
+    pub file: String,
+    
+    /// Size of the output file in bytes
+    pub size: usize,
+    
+    /// Modules bundled into the chunk
+    pub modules: Vec<String>,
+}
+
+/// Differences between two builds
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct StatsDiff {
+    /// Chunks whose size changed, or that were added or removed
+    pub chunks: Vec<ChunkDelta>,
+    
+    /// Modules only in the new build
+    pub added_modules: Vec<String>,
+    
+    /// Modules only in the old build
+    pub removed_modules: Vec<String>,
+    
+    /// Packages only in the new build, with their versions
+    pub added_packages: BTreeMap<String, String>,
+    
+    /// Packages only in the old build, with their versions
+    pub removed_packages: BTreeMap<String, String>,
+    
+    /// Packages whose version changed, as `(old, new)`
+    pub updated_packages: BTreeMap<String, (String, String)>,
+}
+
+/// Size change of one chunk; a missing size means the chunk is new or gone
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChunkDelta {
+    pub name: String,
+    pub old_size: Option<usize>,
+    pub new_size: Option<usize>,
+}
+
+impl ChunkDelta {
+    /// Change in bytes
+    pub fn delta(&self) -> i64 {
+        self.new_size.unwrap_or(0) as i64 - self.old_size.unwrap_or(0) as i64
+    }
+}
+
+impl BuildStats {
+    /// Compare `self`, the old build, with `new`
+    pub fn diff(&self, new: &BuildStats) -> StatsDiff {
+        let names: BTreeSet<&String> = self.chunks.keys().chain(new.chunks.keys()).collect();
+        let chunks = names
+            .into_iter()
+            .map(|name| ChunkDelta {
+                name: name.clone(),
+                old_size: self.chunks.get(name).map(|c| c.size),
+                new_size: new.chunks.get(name).map(|c| c.size),
+            })
+            .filter(|delta| delta.old_size != delta.new_size)
+            .collect();
+        
+        let only_in = |a: &BuildStats, b: &BuildStats| -> Vec<String> {
+            a.modules.keys().filter(|m| !b.modules.contains_key(*m)).cloned().collect()
+        };
+        let packages_only_in = |a: &BuildStats, b: &BuildStats| -> BTreeMap<String, String> {
+            a.packages.iter().filter(|(name, _)| !b.packages.contains_key(*name)).map(|(n, v)| (n.clone(), v.clone())).collect()
+        };
+        let updated_packages = self.packages
+            .iter()
+            .filter_map(|(name, old)| match new.packages.get(name) {
+                Some(version) if version != old => Some((name.clone(), (old.clone(), version.clone()))),
+                _ => None,
+            })
+            .collect();
+        
+        StatsDiff {
+            chunks,
+            added_modules: only_in(new, self),
+            removed_modules: only_in(self, new),
+            added_packages: packages_only_in(new, self),
+            removed_packages: packages_only_in(self, new),
+            updated_packages,
+        }
+    }
+}
+
+impl StatsDiff {
+    /// Whether the builds have the same chunk sizes, modules and packages
+    pub fn is_empty(&self) -> bool {
+        *self == StatsDiff::default()
+    }
+    
+    /// Total change in bytes across all chunks
+    pub fn total_delta(&self) -> i64 {
+        self.chunks.iter().map(ChunkDelta::delta).sum()
+    }
+}
+
+/// Directory and name of the package a module id is in, as in
+/// `("node_modules/@s/b", "@s/b")` for `node_modules/@s/b/index.js`
+pub(super) fn package_of(module_id: &str) -> Option<(&str, &str)> {
+    let start = module_id.rfind("node_modules/")? + "node_modules/".len();
+    let rest = &module_id[start..];
+    let mut slashes = rest.match_indices('/').map(|(i, _)| i);
+    let first = slashes.next()?;
+    let end = if rest.starts_with('@') { slashes.next()? } else { first };
+    Some((&module_id[..start + end], &rest[..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn stats(chunks: &[(&str, usize)], modules: &[&str], packages: &[(&str, &str)]) -> BuildStats {
+        BuildStats {
+            chunks: chunks
+                .iter()
+                .map(|(name, size)| (name.to_string(), ChunkStats { file: format!("{}.js", name), size: *size, modules: Vec::new() }))
+                .collect(),
+            modules: modules.iter().map(|m| (m.to_string(), 10)).collect(),
+            packages: packages.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            exports: BTreeMap::new(),
+        }
+    }
+    
+    #[test]
+    fn test_stats_diff() {
+        let old = stats(
+            &[("main", 1000), ("lazy", 200), ("admin", 50)],
+            &["src/main.js", "src/old.js", "node_modules/a/index.js"],
+            &[("a", "1.0.0"), ("b", "2.0.0")],
+        );
+        let new = stats(
+            &[("main", 1200), ("lazy", 200), ("settings", 80)],
+            &["src/main.js", "src/new.js", "node_modules/a/index.js"],
+            &[("a", "1.1.0"), ("@s/c", "0.1.0")],
+        );
+        
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.chunks.iter().map(|c| (c.name.as_str(), c.delta())).collect::<Vec<_>>(),
+            vec![("admin", -50), ("main", 200), ("settings", 80)]
+        );
+        assert_eq!(diff.total_delta(), 230);
+        assert_eq!(diff.added_modules, vec!["src/new.js"]);
+        assert_eq!(diff.removed_modules, vec!["src/old.js"]);
+        assert_eq!(diff.added_packages.get("@s/c").map(String::as_str), Some("0.1.0"));
+        assert!(diff.removed_packages.contains_key("b"));
+        assert_eq!(diff.updated_packages["a"], ("1.0.0".to_string(), "1.1.0".to_string()));
+        assert!(old.diff(&old).is_empty());
+        
+        assert_eq!(package_of("node_modules/@s/b/lib/x.js"), Some(("node_modules/@s/b", "@s/b")));
+        assert_eq!(package_of("node_modules/a/node_modules/c/index.js"), Some(("node_modules/a/node_modules/c", "c")));
+        assert_eq!(package_of("src/main.js"), None);
+    }
+}
@@ -0,0 +1,127 @@
+//! Security audit command implementation
+
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use tracing::{info, warn};
+
+use crate::audit::{audit, AuditOptions, AuditReport, PackageVersion, Severity, DEFAULT_API};
+use crate::bundler::{BuildOptions, Bundler};
+use crate::config::{Config, ProjectState};
+use crate::resolver::Lockfile;
+use crate::vfs::{FileSystem, RealFs};
+use crate::msg;
+
+/// Check bundled dependencies for known vulnerabilities
+#[derive(Args, Debug)]
+pub struct AuditCommand {
+    /// Print the report as JSON to stdout
+    #[arg(long)]
+    pub json: bool,
+    
+    /// Only use cached results, without network access
+    #[arg(long)]
+    pub offline: bool,
+    
+    /// Minimum severity that fails the audit (low, moderate, high, critical)
+    #[arg(long, default_value = "low")]
+    pub fail_on: Severity,
+    
+    /// Reuse cached results younger than this many hours
+    #[arg(long, default_value = "24")]
+    pub max_age: u64,
+    
+    /// OSV API base URL
+    #[arg(long, env = "COMPONENT_OSV_API", default_value = DEFAULT_API)]
+    pub api: String,
+}
+
+impl AuditCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let config = Config::load(config_path)?;
+        let cache_dir = ProjectState::new(&config.root).path("audit");
+        let root = RealFs.canonicalize(&config.root).unwrap_or_else(|_| config.root.clone());
+        let lockfile = Lockfile::discover(&root, &RealFs);
+        
+        let bundler = Bundler::new(config, BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "esnext".to_string(),
+            inspect_transforms: false,
+        })?;
+        bundler.build_graph().await?;
+        
+        // Audit the versions the lockfile declares, which are what a clean
+        // install would bundle
+        let packages: Vec<PackageVersion> = bundler.licenses()?
+            .packages
+            .into_iter()
+            .map(|package| {
+                let locked = lockfile.as_ref().and_then(|lockfile| lockfile.package_for(&root.join(&package.path)));
+                match locked {
+                    Some(locked) if locked.version != package.version => {
+                        warn!(
+                            "{}",
+                            msg!("audit.lock_mismatch", name = package.name, installed = package.version, locked = locked.version)
+                        );
+                        PackageVersion { name: locked.name, version: locked.version }
+                    }
+                    _ => PackageVersion { name: package.name, version: package.version },
+                }
+            })
+            .collect();
+        
+        let options = AuditOptions {
+            api: &self.api,
+            cache_dir: &cache_dir,
+            max_age: Duration::from_secs(self.max_age * 3600),
+            offline: self.offline,
+        };
+        let report = audit(&packages, &options)?;
+        
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+        
+        let failing = report.count_at_least(self.fail_on);
+        if failing > 0 {
+            anyhow::bail!("{} advisory(ies) at or above {} severity", failing, self.fail_on);
+        }
+        Ok(())
+    }
+}
+
+fn print_report(report: &AuditReport) {
+    for package in &report.vulnerable {
+        println!("{}@{}", package.name.bold(), package.version);
+        for advisory in &package.advisories {
+            let severity = match advisory.severity {
+                Some(Severity::Critical) => "critical".red().bold(),
+                Some(Severity::High) => "high".red(),
+                Some(Severity::Moderate) => "moderate".yellow(),
+                Some(Severity::Low) => "low".normal(),
+                None => "unrated".dimmed(),
+            };
+            println!("  {} [{}] {}", advisory.id, severity, advisory.summary);
+            if let Some(fixed) = &advisory.fixed {
+                println!("    {}", msg!("audit.fixed_in", version = fixed.green()));
+            }
+            println!("    {}", advisory.url.dimmed());
+        }
+    }
+    
+    for package in &report.unchecked {
+        let name = format!("{}@{}", package.name, package.version);
+        eprintln!("{} {}", "!".yellow(), msg!("audit.unchecked", package = name));
+    }
+    
+    let vulnerable = report.vulnerable.len();
+    let status = if vulnerable == 0 { "✓".green() } else { "✗".red() };
+    eprintln!("\n{} {}", status, msg!("audit.summary", count = report.checked, vulnerable = vulnerable));
+}
@@ -0,0 +1,224 @@
+//! Benchmark command implementation
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use tracing::info;
+
+use crate::bundler::{BuildOptions, BuildTimings, Bundler, CacheStats, TransformCache};
+use crate::config::Config;
+use crate::msg;
+
+/// Measure cold and warm build performance
+#[derive(Args, Debug)]
+pub struct BenchCommand {
+    /// Number of cold and warm builds to run
+    #[arg(short = 'n', long, default_value = "5")]
+    pub runs: usize,
+    
+    /// Enable minification during benchmark builds
+    #[arg(long)]
+    pub minify: bool,
+    
+    /// Print results as JSON to stdout
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Summary statistics for a series of measurements, in milliseconds
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub mean: f64,
+    pub min: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    fn from_durations(durations: &[Duration]) -> Self {
+        let mut values: Vec<f64> = durations.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        
+        let mean = if values.is_empty() {
+            0.0
+        } else {
+            values.iter().sum::<f64>() / values.len() as f64
+        };
+        
+        Self {
+            mean,
+            min: values.first().copied().unwrap_or(0.0),
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            max: values.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Per-phase statistics for a series of builds
+#[derive(Debug, Serialize)]
+pub struct SeriesReport {
+    pub runs: usize,
+    pub graph: Summary,
+    pub transform: Summary,
+    pub chunks: Summary,
+    pub write: Summary,
+    pub manifest: Summary,
+    pub total: Summary,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_hit_rate: f64,
+}
+
+impl SeriesReport {
+    fn new(timings: &[BuildTimings], cache: CacheStats) -> Self {
+        let phase = |f: fn(&BuildTimings) -> Duration| {
+            Summary::from_durations(&timings.iter().map(f).collect::<Vec<_>>())
+        };
+        
+        Self {
+            runs: timings.len(),
+            graph: phase(|t| t.graph),
+            transform: phase(|t| t.transform),
+            chunks: phase(|t| t.chunks),
+            write: phase(|t| t.write),
+            manifest: phase(|t| t.manifest),
+            total: phase(|t| t.total),
+            cache_hits: cache.hits,
+            cache_misses: cache.misses,
+            cache_hit_rate: cache.hit_rate(),
+        }
+    }
+}
+
+/// Full benchmark report
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub version: String,
+    pub cold: SeriesReport,
+    pub warm: SeriesReport,
+}
+
+impl BenchCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let config = Config::load(config_path)?;
+        
+        if self.runs == 0 {
+            anyhow::bail!("--runs must be at least 1");
+        }
+        
+        // Write benchmark output to a scratch directory, never the real dist/
+        let outdir = std::env::temp_dir().join(format!("component-bench-{}", std::process::id()));
+        let options = BuildOptions {
+            outdir: Some(outdir.clone()),
+            minify: self.minify,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        
+        eprintln!("{} {}", "→".blue(), msg!("bench.started", runs = self.runs));
+        
+        let result = self.run(&config, options).await;
+        let _ = fs::remove_dir_all(&outdir);
+        let report = result?;
+        
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_series(&msg!("bench.cold"), &report.cold);
+            print_series(&msg!("bench.warm"), &report.warm);
+            eprintln!();
+        }
+        
+        Ok(())
+    }
+    
+    async fn run(&self, config: &Config, options: BuildOptions) -> Result<BenchReport> {
+        // Cold builds: every build starts with an empty cache
+        let mut cold = Vec::with_capacity(self.runs);
+        let mut cold_cache = CacheStats::default();
+        for _ in 0..self.runs {
+            let bundler = Bundler::new(config.clone(), options.clone())?;
+            let result = bundler.build().await.context("Cold build failed")?;
+            let stats = bundler.cache().stats();
+            cold_cache.hits += stats.hits;
+            cold_cache.misses += stats.misses;
+            cold.push(result.timings);
+        }
+        
+        // Warm builds: share a cache primed by an untimed build
+        let cache = Arc::new(TransformCache::new());
+        Bundler::new(config.clone(), options.clone())?
+            .with_cache(cache.clone())
+            .build()
+            .await
+            .context("Warm-up build failed")?;
+        cache.reset_stats();
+        
+        let mut warm = Vec::with_capacity(self.runs);
+        for _ in 0..self.runs {
+            let bundler = Bundler::new(config.clone(), options.clone())?.with_cache(cache.clone());
+            let result = bundler.build().await.context("Warm build failed")?;
+            warm.push(result.timings);
+        }
+        
+        Ok(BenchReport {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            cold: SeriesReport::new(&cold, cold_cache),
+            warm: SeriesReport::new(&warm, cache.stats()),
+        })
+    }
+}
+
+/// Print a series report as a table
+fn print_series(label: &str, report: &SeriesReport) {
+    let series = msg!(
+        "bench.series",
+        runs = report.runs,
+        hits = report.cache_hits,
+        total = report.cache_hits + report.cache_misses,
+        rate = format!("{:.0}", report.cache_hit_rate * 100.0),
+    );
+    eprintln!("\n  {} ({})", label.bold(), series);
+    eprintln!(
+        "    {:<10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "phase".dimmed(),
+        "mean".dimmed(),
+        "min".dimmed(),
+        "p50".dimmed(),
+        "p95".dimmed(),
+        "max".dimmed()
+    );
+    
+    let rows: [(&str, &Summary); 6] = [
+        ("graph", &report.graph),
+        ("transform", &report.transform),
+        ("chunks", &report.chunks),
+        ("write", &report.write),
+        ("manifest", &report.manifest),
+        ("total", &report.total),
+    ];
+    for (name, s) in rows {
+        eprintln!(
+            "    {:<10} {:>8.2}ms {:>8.2}ms {:>8.2}ms {:>8.2}ms {:>8.2}ms",
+            name, s.mean, s.min, s.p50, s.p95, s.max
+        );
+    }
+}
@@ -1,15 +1,23 @@
 //! Build command implementation
 
 use std::path::PathBuf;
-use std::time::Instant;
+use std::sync::Arc;
 
 use anyhow::Result;
 use clap::Args;
 use colored::Colorize;
-use tracing::info;
+use tracing::{error, info, warn};
 
-use crate::config::Config;
-use crate::bundler::Bundler;
+use crate::cli::RunHook;
+use crate::config::{Config, LastBuild, ProjectState, PRODUCTION};
+use crate::plugins::PluginTiming;
+use crate::bundler::{
+    backend_from_location, BuildOptions, BuildResult, Bundler, ConsoleReporter, DiskBackend, JsonReporter, Reporter,
+    ReporterKind, TransformCache,
+};
+use crate::utils::{path_to_module_id, simplify_path};
+use crate::vfs::{ProjectWatcher, RealFs};
+use crate::msg;
 
 /// Build the project for production
 #[derive(Args, Debug)]
@@ -29,59 +37,409 @@ pub struct BuildCommand {
     /// Target environment (es2020, es2021, es2022, esnext)
     #[arg(long, default_value = "es2020")]
     pub target: String,
+    
+    /// Disable the persistent build cache
+    #[arg(long)]
+    pub no_cache: bool,
+    
+    /// Read cache entries from a remote cache (URL, s3://bucket/prefix, or path)
+    #[arg(long, value_name = "LOCATION")]
+    pub cache_from: Vec<String>,
+    
+    /// Write new cache entries to a remote cache (URL, s3://bucket/prefix, or path)
+    #[arg(long, value_name = "LOCATION")]
+    pub cache_to: Vec<String>,
+    
+    /// Bearer token for HTTP caches
+    #[arg(long, env = "COMPONENT_CACHE_TOKEN", hide_env_values = true)]
+    pub cache_token: Option<String>,
+    
+    /// Emit stats.json with chunk sizes, modules, and packages, for `component diff`
+    #[arg(long)]
+    pub stats: bool,
+    
+    /// Emit provenance.json with input, config, and output hashes
+    #[arg(long)]
+    pub provenance: bool,
+    
+    /// Sign provenance.json with an Ed25519 private key (PKCS#8 PEM)
+    #[arg(long, value_name = "PATH")]
+    pub provenance_key: Option<PathBuf>,
+    
+    /// Inline critical CSS into HTML entries and defer the full stylesheet
+    #[arg(long)]
+    pub critical_css: bool,
+    
+    /// Also apply `[entrypoints.<NAME>]`, after `[entrypoints.production]`
+    #[arg(long = "condition", value_name = "NAME")]
+    pub conditions: Vec<String>,
+    
+    /// Public URL or sub-path the app is served from (overrides `output.public_url`)
+    #[arg(long, value_name = "URL")]
+    pub base: Option<String>,
+    
+    /// Progress reporter (defaults to `output.reporter`)
+    #[arg(long, value_enum)]
+    pub reporter: Option<ReporterKind>,
+    
+    /// Rebuild when source files or plugin watch files change
+    #[arg(short, long)]
+    pub watch: bool,
+    
+    /// Run a shell command after each successful rebuild in watch mode, with
+    /// the changed and affected files in `COMPONENT_CHANGED_FILES` and
+    /// `COMPONENT_AFFECTED_FILES`
+    #[arg(long, value_name = "CMD", requires = "watch")]
+    pub run: Option<String>,
+    
+    /// Modules transformed and files written at once (defaults to `build.jobs`, or the CPU count)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+    
+    /// Build at low OS priority, with half the CPUs unless `--jobs` is given
+    #[arg(long)]
+    pub nice: bool,
+    
+    /// Fail if package.json's `exports`, `main`, `module` or `types` fields
+    /// don't match a library build, instead of updating them
+    #[arg(long, conflicts_with = "watch")]
+    pub check_exports: bool,
+    
+    /// Print how long each build phase and each plugin's hooks took
+    #[arg(long, conflicts_with = "watch")]
+    pub profile: bool,
+    
+    /// Fail with exit code 3 on more warnings than this (defaults to
+    /// `build.max_warnings`, or 0 with `--ci`)
+    #[arg(long, value_name = "N", conflicts_with = "watch")]
+    pub max_warnings: Option<usize>,
+}
+
+/// A build that reported more warnings than `build.max_warnings` allows
+#[derive(Debug)]
+pub struct TooManyWarnings {
+    pub count: usize,
+    pub max: usize,
+}
+
+impl std::fmt::Display for TooManyWarnings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", msg!("build.too_many_warnings", count = self.count, max = self.max))
+    }
 }
 
+impl std::error::Error for TooManyWarnings {}
+
 impl BuildCommand {
-    pub async fn execute(&self, config_path: &str) -> Result<()> {
-        let start = Instant::now();
+    /// Build, or keep building with `--watch`
+    ///
+    /// With `ci`, progress lines are left out and warnings fail the build
+    /// unless `build.max_warnings` allows them.
+    pub async fn execute(&self, config_path: &str, ci: bool) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let conditions: Vec<String> = std::iter::once(PRODUCTION.to_string())
+            .chain(self.conditions.iter().cloned())
+            .collect();
+        let mut config = Config::load_for(config_path, &conditions)?;
         
-        info!("Loading configuration from {}", config_path);
-        let config = Config::load(config_path)?;
+        if self.stats {
+            config.output.stats = true;
+        }
+        if self.provenance || self.provenance_key.is_some() {
+            config.output.provenance = true;
+        }
+        if let Some(key) = &self.provenance_key {
+            let key = std::env::current_dir()?.join(key);
+            config.output.provenance_key = Some(key.display().to_string());
+        }
+        if self.critical_css {
+            config.output.critical_css = true;
+        }
+        if let Some(base) = &self.base {
+            config.set_base(base);
+        }
+        if let Some(jobs) = self.jobs {
+            config.build.jobs = Some(jobs);
+        }
+        if self.nice {
+            config.build.nice = true;
+        }
+        if config.build.nice {
+            lower_priority();
+        }
         
-        eprintln!("{} Building project...", "→".blue());
+        let reporter_kind = self.reporter.unwrap_or(config.output.reporter);
+        let reporter: Arc<dyn Reporter> = match reporter_kind {
+            ReporterKind::Console => Arc::new(ConsoleReporter),
+            ReporterKind::Json => Arc::new(JsonReporter::stdout()),
+        };
+        let console = reporter_kind == ReporterKind::Console;
         
-        let bundler = Bundler::new(config, self.into())?;
-        let result = bundler.build().await?;
+        if console && !ci {
+            eprintln!("{} {}", "→".blue(), msg!("build.started"));
+        }
         
-        let duration = start.elapsed();
+        let cache = self.transform_cache(&config)?;
+        let root = config.root.canonicalize().map(simplify_path).unwrap_or_else(|_| config.root.clone());
+        let recorder = BuildRecorder {
+            state: ProjectState::new(&config.root),
+            mode: config.conditions.first().cloned().unwrap_or_default(),
+            root: config.root.clone(),
+            outdir: self.outdir.clone().unwrap_or_else(|| config.output_dir()),
+            package_json: (!self.check_exports).then(|| PackageJson {
+                path: config.root.join("package.json"),
+                name: config.project.name.clone(),
+                version: config.project.version.clone(),
+            }),
+            console,
+        };
+        let package_json = config.root.join("package.json");
+        let max_warnings = self.max_warnings.or(config.build.max_warnings).or(ci.then_some(0));
+        let bundler = Bundler::new(config, self.into())?
+            .with_cache(cache.clone())
+            .with_reporter(reporter);
         
-        eprintln!(
-            "\n{} Built {} bundle(s) in {:.2}s\n",
-            "✓".green().bold(),
-            result.bundles.len(),
-            duration.as_secs_f64()
-        );
+        if self.watch {
+            match bundler.build().await {
+                Ok(result) => recorder.record(&result),
+                Err(e) => error!("{}", msg!("build.failed", error = format!("{:#}", e))),
+            }
+            let run = self.run.as_ref().map(|command| RunHook::new(command, &root));
+            return watch(Arc::new(bundler), &root, console, run, recorder).await;
+        }
+        let result = bundler.build().await?;
+        recorder.record(&result);
         
-        // Print bundle summary
-        for bundle in &result.bundles {
-            let size_kb = bundle.size as f64 / 1024.0;
-            let size_str = if size_kb > 1024.0 {
-                format!("{:.2} MB", size_kb / 1024.0)
-            } else {
-                format!("{:.2} KB", size_kb)
-            };
-            
+        if let (true, Some(exports)) = (self.check_exports, &result.package_exports) {
+            let stale = exports.check(&package_json)?;
+            if !stale.is_empty() {
+                anyhow::bail!(
+                    "{} is out of date: {} (run `component build` to update it)",
+                    package_json.display(),
+                    stale.join(", ")
+                );
+            }
+        }
+        
+        let (stats, chunks) = (cache.stats(), cache.chunk_stats());
+        if console && stats.hits + stats.misses > 0 {
             eprintln!(
-                "  {} {} {}",
+                "\n  {} {}",
                 "•".dimmed(),
-                bundle.output_path.display().to_string().cyan(),
-                size_str.dimmed()
+                msg!(
+                    "build.cache",
+                    modules = stats.hits,
+                    total_modules = stats.hits + stats.misses,
+                    chunks = chunks.hits,
+                    total_chunks = chunks.hits + chunks.misses,
+                )
             );
         }
         
-        eprintln!();
+        if self.profile {
+            print_profile(&result, &bundler.plugin_timings());
+        }
+        
+        if console {
+            eprintln!();
+        }
+        
+        match max_warnings {
+            Some(max) if result.warnings.len() > max => Err(TooManyWarnings { count: result.warnings.len(), max }.into()),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Set up the transform cache with local and remote backends
+    fn transform_cache(&self, config: &Config) -> Result<Arc<TransformCache>> {
+        let mut cache = TransformCache::new();
+        let token = self.cache_token.as_deref();
+        
+        if config.cache.enabled && !self.no_cache {
+            let local = Arc::new(DiskBackend::new(config.root.join(&config.cache.dir)));
+            cache = cache.read_from(local.clone()).write_to(local);
+        }
         
-        Ok(())
+        for location in config.cache.from.iter().chain(&self.cache_from) {
+            cache = cache.read_from(backend_from_location(location, &config.root, token)?);
+        }
+        for location in config.cache.to.iter().chain(&self.cache_to) {
+            cache = cache.write_to(backend_from_location(location, &config.root, token)?);
+        }
+        
+        Ok(Arc::new(cache))
     }
 }
 
-/// Build options derived from command arguments
-#[derive(Debug, Clone)]
-pub struct BuildOptions {
-    pub outdir: Option<PathBuf>,
-    pub minify: bool,
-    pub sourcemap: bool,
-    pub target: String,
+/// Print the `--profile` report: build phases, then time per plugin hook
+fn print_profile(result: &BuildResult, plugins: &[PluginTiming]) {
+    let timings = &result.timings;
+    eprintln!("\n  {}", msg!("build.profile").bold());
+    let phases = [
+        ("graph", timings.graph),
+        ("transform", timings.transform),
+        ("chunks", timings.chunks),
+        ("write", timings.write),
+        ("manifest", timings.manifest),
+        ("total", timings.total),
+    ];
+    for (phase, elapsed) in phases {
+        eprintln!("    {:<12} {:>9.1}ms", phase, elapsed.as_secs_f64() * 1000.0);
+    }
+    
+    if plugins.is_empty() {
+        return;
+    }
+    eprintln!("\n  {}", msg!("build.plugins").bold());
+    for timing in plugins {
+        let ms = format!("{:.1}", timing.slowest.as_secs_f64() * 1000.0);
+        let slowest = match &timing.slowest_on {
+            Some(subject) => msg!("build.slowest_on", ms = ms, subject = subject),
+            None => msg!("build.slowest", ms = ms),
+        };
+        eprintln!(
+            "    {:<24} {:>9.1}ms  {:>13}  {}",
+            format!("{} {}", timing.plugin, timing.hook),
+            timing.total.as_secs_f64() * 1000.0,
+            msg!("build.calls", count = timing.calls),
+            slowest.dimmed()
+        );
+    }
+}
+
+/// Rebuild whenever a file the build depends on changes, then run the
+/// `--run` command if given
+async fn watch(
+    bundler: Arc<Bundler>,
+    root: &std::path::Path,
+    console: bool,
+    run: Option<RunHook>,
+    recorder: BuildRecorder,
+) -> Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let watch_files = bundler.clone();
+    let _watcher = ProjectWatcher::new(
+        RealFs::shared(),
+        root,
+        move |paths| {
+            let _ = tx.send(paths);
+        },
+        move || watch_files.watch_files(),
+    )?;
+    
+    if console {
+        eprintln!("{} {}\n", "→".blue(), msg!("build.watching"));
+    }
+    
+    while let Some(paths) = rx.recv().await {
+        if bundler.is_bulk_change(&paths) {
+            if console {
+                eprintln!("  {} {}", "↻".yellow(), msg!("build.bulk_change", count = paths.len()));
+            }
+            if let Err(e) = bundler.rebuild_graph().await {
+                error!("{}", msg!("build.rebuild_failed", error = format!("{:#}", e)));
+                continue;
+            }
+            match bundler.build().await {
+                Ok(result) => {
+                    recorder.record(&result);
+                    if let Some(run) = &run {
+                        run_hook(run, &bundler, &paths);
+                    }
+                }
+                Err(e) => error!("{}", msg!("build.failed", error = format!("{:#}", e))),
+            }
+            continue;
+        }
+        
+        // Output files and anything else outside the graph are ignored
+        let mut changed = Vec::new();
+        for path in paths {
+            match bundler.invalidate(&path).await {
+                Ok(Some(_)) => changed.push(path),
+                Ok(None) => {}
+                Err(e) => error!("{}", msg!("build.rebuild_failed", error = format!("{:#}", e))),
+            }
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        
+        if console {
+            for path in &changed {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                eprintln!("  {} {}", "↻".yellow(), msg!("build.file_changed", path = relative.display().to_string().dimmed()));
+            }
+        }
+        match bundler.build().await {
+            Ok(result) => {
+                recorder.record(&result);
+                if let Some(run) = &run {
+                    run_hook(run, &bundler, &changed);
+                }
+            }
+            Err(e) => error!("{}", msg!("build.failed", error = format!("{:#}", e))),
+        }
+    }
+    
+    Ok(())
+}
+
+/// Run the `--run` command for a rebuild caused by `changed`
+fn run_hook(run: &RunHook, bundler: &Bundler, changed: &[PathBuf]) {
+    let affected = bundler.affected_files(changed);
+    if let Err(e) = tokio::task::block_in_place(|| run.run(changed, &affected)) {
+        error!("{:#}", e);
+    }
+}
+
+/// Writes `.component/last-build.json` after each successful build, for
+/// `component status`, and keeps package.json up to date for library builds
+struct BuildRecorder {
+    state: ProjectState,
+    mode: String,
+    root: PathBuf,
+    outdir: PathBuf,
+    
+    /// Updated with the build's `package_exports`; `None` with `--check-exports`
+    package_json: Option<PackageJson>,
+    
+    console: bool,
+}
+
+/// The package.json a library build updates, with the project's name and
+/// version for creating it
+struct PackageJson {
+    path: PathBuf,
+    name: String,
+    version: String,
+}
+
+impl BuildRecorder {
+    /// Record a build; failures are only logged
+    fn record(&self, result: &BuildResult) {
+        if let (Some(package), Some(exports)) = (&self.package_json, &result.package_exports) {
+            match exports.write(&package.path, &package.name, &package.version) {
+                Ok(changed) if self.console && !changed.is_empty() => {
+                    eprintln!("  {} {}", "✓".green(), msg!("build.package_json_updated", fields = changed.join(", ")));
+                }
+                Ok(_) => {}
+                Err(e) => warn!("{}", msg!("build.package_json_failed", error = format!("{:#}", e))),
+            }
+        }
+        
+        let files = result.bundles.iter().map(|bundle| (bundle.file_name.clone(), bundle.size)).collect();
+        let build = LastBuild {
+            finished_at: LastBuild::now(),
+            duration_ms: result.timings.total.as_millis() as u64,
+            mode: self.mode.clone(),
+            output_dir: path_to_module_id(self.outdir.strip_prefix(&self.root).unwrap_or(&self.outdir)),
+            files,
+        };
+        if let Err(e) = self.state.write_last_build(&build) {
+            warn!("{}", msg!("build.record_failed", error = format!("{:#}", e)));
+        }
+    }
 }
 
 impl From<&BuildCommand> for BuildOptions {
@@ -91,6 +449,34 @@ impl From<&BuildCommand> for BuildOptions {
             minify: cmd.minify,
             sourcemap: cmd.sourcemap,
             target: cmd.target.clone(),
+            inspect_transforms: false,
+        }
+    }
+}
+
+/// Lower the priority of this process so builds yield to interactive work
+///
+/// On Linux priorities are per thread, so every running thread is lowered;
+/// threads started later inherit it.
+fn lower_priority() {
+    #[cfg(unix)]
+    {
+        const NICENESS: libc::c_int = 10;
+        
+        let threads: Vec<libc::id_t> = std::fs::read_dir("/proc/self/task")
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let targets = if threads.is_empty() { vec![0] } else { threads };
+        
+        for id in targets {
+            // SAFETY: setpriority only changes scheduling priority
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, id, NICENESS) } != 0 {
+                tracing::debug!("Failed to lower priority: {}", std::io::Error::last_os_error());
+            }
         }
     }
 }
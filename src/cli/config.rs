@@ -0,0 +1,104 @@
+//! Config command implementation
+
+use std::fs;
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::config::{explain, Config, KeyExplanation, ValueSource};
+use crate::msg;
+
+/// Inspect the configuration
+#[derive(Args, Debug)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Describe a config key: its type, default, and effective value
+    Explain {
+        /// Dotted key such as `output.public_url`, or a section such as `dev`
+        #[arg(default_value = "")]
+        key: String,
+        
+        /// Print the explanation as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl ConfigCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        match &self.action {
+            ConfigAction::Explain { key, json } => {
+                info!("{}", msg!("cli.loading_config", path = config_path));
+                let config = Config::load(config_path)?;
+                let file: toml::Table = toml::from_str(&fs::read_to_string(config_path)?)
+                    .with_context(|| "Failed to parse component.toml")?;
+                let explanation = explain(&config, &file, key)?;
+                
+                if *json {
+                    println!("{}", serde_json::to_string_pretty(&explanation)?);
+                } else {
+                    print_explanation(&explanation, config_path);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn print_explanation(explanation: &KeyExplanation, config_path: &str) {
+    let label = |text: &str| format!("  {:<15}", text).dimmed();
+    let key = if explanation.key.is_empty() { "component.toml" } else { &explanation.key };
+    match &explanation.ty {
+        Some(ty) => println!("{} {}", key.bold(), ty.dimmed()),
+        None => println!("{}", key.bold()),
+    }
+    
+    if let Some(field) = explanation.field.as_ref().filter(|field| **field != explanation.key) {
+        println!("  {}", msg!("explain.entry_of", field = field).dimmed());
+    }
+    for line in explanation.description.lines() {
+        println!("  {}", line);
+    }
+    
+    if !explanation.keys.is_empty() {
+        println!("\n  {}", msg!("explain.keys").bold());
+        for field in &explanation.keys {
+            let summary = field.doc.lines().next().unwrap_or_default();
+            println!("    {:<20} {}", field.name.cyan(), summary.dimmed());
+        }
+        return;
+    }
+    
+    let source = match &explanation.source {
+        ValueSource::Env(variable) => msg!("explain.from_env", variable = variable),
+        ValueSource::File => msg!("explain.from_file", path = config_path),
+        ValueSource::Default => msg!("explain.default_source"),
+    };
+    println!();
+    println!("{}{}", label(&msg!("explain.default")), format_value(explanation.default.as_ref()));
+    println!(
+        "{}{} {}",
+        label(&msg!("explain.value")),
+        format_value(explanation.value.as_ref()).cyan(),
+        format!("({})", source).dimmed()
+    );
+    if !explanation.overridden_by.is_empty() {
+        println!("{}{}", label(&msg!("explain.overridden_by")), explanation.overridden_by.join(", "));
+    }
+}
+
+/// A value in TOML-like syntax, or `unset`
+fn format_value(value: Option<&Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => msg!("explain.unset"),
+    }
+}
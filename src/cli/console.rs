@@ -0,0 +1,80 @@
+//! Terminal output
+//!
+//! Human output (the banner, progress, summaries and warnings) goes to
+//! stderr, and what scripts consume (`--json` reports, `--reporter json`
+//! events, inspected code) to stdout, so piping a command never mixes the
+//! two. Whether either is colored is decided once, here, from `--color` and
+//! the environment.
+
+use std::io::IsTerminal;
+
+use colored::Colorize;
+
+/// When to color output, set with `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when `NO_COLOR` is unset and stdout and stderr are terminals
+    #[default]
+    Auto,
+    
+    /// Always color, even when piped
+    Always,
+    
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether output is colored under this choice
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => auto_colors(
+                std::env::var("NO_COLOR").ok().as_deref(),
+                std::env::var("TERM").ok().as_deref(),
+                std::io::stdout().is_terminal() && std::io::stderr().is_terminal(),
+            ),
+        }
+    }
+}
+
+/// Whether `auto` colors, given `NO_COLOR`, `TERM`, and whether the output
+/// streams are terminals
+///
+/// As https://no-color.org asks, an empty `NO_COLOR` doesn't count.
+fn auto_colors(no_color: Option<&str>, term: Option<&str>, terminals: bool) -> bool {
+    no_color.is_none_or(str::is_empty) && term != Some("dumb") && terminals
+}
+
+/// Color everything printed from now on, or nothing; returns whether
+/// output is colored
+pub fn init(choice: ColorChoice) -> bool {
+    let enabled = choice.enabled();
+    colored::control::set_override(enabled);
+    enabled
+}
+
+/// Print the Component banner
+pub fn banner() {
+    eprintln!(
+        "\n{} {} {}\n",
+        "⚡".cyan(),
+        "Component".bold().cyan(),
+        format!("v{}", env!("CARGO_PKG_VERSION")).dimmed()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_auto_colors() {
+        assert!(auto_colors(None, Some("xterm-256color"), true));
+        assert!(!auto_colors(None, Some("xterm-256color"), false));
+        assert!(!auto_colors(Some("1"), Some("xterm-256color"), true));
+        assert!(auto_colors(Some(""), None, true));
+        assert!(!auto_colors(None, Some("dumb"), true));
+    }
+}
@@ -1,5 +1,6 @@
 //! Development server command implementation
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -7,8 +8,9 @@ use clap::Args;
 use colored::Colorize;
 use tracing::info;
 
-use crate::config::Config;
+use crate::config::{Config, DEVELOPMENT};
 use crate::server::DevServer;
+use crate::msg;
 
 /// Start development server with hot module replacement
 #[derive(Args, Debug)]
@@ -28,40 +30,69 @@ pub struct DevCommand {
     /// Disable hot module replacement
     #[arg(long)]
     pub no_hmr: bool,
+    
+    /// Record per-plugin transform output for the devtools endpoints
+    #[arg(long)]
+    pub inspect_transforms: bool,
+    
+    /// Token for the rebuild endpoints (generated into `.component/dev-token` when unset)
+    #[arg(long, env = "COMPONENT_DEV_TOKEN", hide_env_values = true, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    pub api_token: Option<String>,
+    
+    /// Also apply `[entrypoints.<NAME>]`, after `[entrypoints.development]`
+    #[arg(long = "condition", value_name = "NAME")]
+    pub conditions: Vec<String>,
+    
+    /// Serve the app under a sub-path such as `/subapp/` (defaults to `output.public_url`)
+    #[arg(long, value_name = "PATH")]
+    pub base: Option<String>,
+    
+    /// Build the module graph from scratch instead of restoring the last run's
+    #[arg(long)]
+    pub cold: bool,
+    
+    /// Run a shell command after each successful rebuild, with the changed and
+    /// affected files in `COMPONENT_CHANGED_FILES` and `COMPONENT_AFFECTED_FILES`
+    #[arg(long, value_name = "CMD")]
+    pub run: Option<String>,
+    
+    /// Config file to watch for `[[plugins]]` changes
+    pub config_path: Option<PathBuf>,
 }
 
 impl DevCommand {
     pub async fn execute(&self, config_path: &str) -> Result<()> {
-        info!("Loading configuration from {}", config_path);
-        let config = Config::load(config_path)?;
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let conditions: Vec<String> = std::iter::once(DEVELOPMENT.to_string())
+            .chain(self.conditions.iter().cloned())
+            .collect();
+        let mut config = Config::load_for(config_path, &conditions)?;
+        if let Some(base) = &self.base {
+            config.set_base(base);
+        }
+        let hmr = !self.no_hmr && config.dev.hmr.enabled;
         
         let addr = format!("{}:{}", self.host, self.port);
         
-        eprintln!(
-            "{} Starting dev server at {}\n",
-            "→".blue(),
-            format!("http://{}", addr).cyan().underline()
-        );
+        let url = format!("http://{}{}", addr, config.output.base_path());
+        eprintln!("{} {}\n", "→".blue(), msg!("dev.starting", url = url.cyan().underline()));
         
-        if !self.no_hmr {
-            eprintln!(
-                "  {} Hot Module Replacement {}",
-                "•".dimmed(),
-                "enabled".green()
-            );
+        if hmr {
+            eprintln!("  {} {}", "•".dimmed(), msg!("dev.hmr_enabled", enabled = msg!("dev.enabled").green()));
         }
         
-        eprintln!(
-            "  {} Press {} to stop\n",
-            "•".dimmed(),
-            "Ctrl+C".yellow()
-        );
+        eprintln!("  {} {}\n", "•".dimmed(), msg!("dev.stop", keys = "Ctrl+C".yellow()));
         
         let server = DevServer::new(Arc::new(config), DevServerOptions {
             host: self.host.clone(),
             port: self.port,
-            hmr: !self.no_hmr,
+            hmr,
             open: self.open,
+            inspect_transforms: self.inspect_transforms,
+            api_token: self.api_token.clone(),
+            warm_start: !self.cold,
+            config_path: Some(std::path::absolute(config_path)?),
+            run: self.run.clone(),
         })?;
         
         server.start().await
@@ -75,4 +106,17 @@ pub struct DevServerOptions {
     pub port: u16,
     pub hmr: bool,
     pub open: bool,
+    pub inspect_transforms: bool,
+    
+    /// Token for the rebuild endpoints; generated when unset
+    pub api_token: Option<String>,
+    
+    /// Restore the module graph saved by the last run
+    pub warm_start: bool,
+    
+    /// Config file to watch for `[[plugins]]` changes
+    pub config_path: Option<PathBuf>,
+    
+    /// Shell command run after each successful rebuild
+    pub run: Option<String>,
 }
@@ -0,0 +1,163 @@
+//! Build diff command implementation
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+
+use crate::bundler::{BuildStats, ChunkDelta, StatsDiff, STATS_FILE};
+use crate::utils::format_size;
+use crate::msg;
+
+/// Compare the stats of two builds
+#[derive(Args, Debug)]
+pub struct DiffCommand {
+    /// stats.json of the old build, or the output directory containing it
+    pub old: PathBuf,
+    
+    /// stats.json of the new build, or the output directory containing it
+    pub new: PathBuf,
+    
+    /// Print the differences as JSON
+    #[arg(long, conflicts_with = "markdown")]
+    pub json: bool,
+    
+    /// Print a Markdown report, for pull request comments
+    #[arg(long)]
+    pub markdown: bool,
+}
+
+impl DiffCommand {
+    pub async fn execute(&self) -> Result<()> {
+        let old = load_stats(&self.old)?;
+        let new = load_stats(&self.new)?;
+        let diff = old.diff(&new);
+        
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+        } else if self.markdown {
+            print!("{}", render_markdown(&diff));
+        } else {
+            print_diff(&diff);
+        }
+        Ok(())
+    }
+}
+
+/// Read a stats file, or the one in an output directory
+fn load_stats(path: &Path) -> Result<BuildStats> {
+    let path = if path.is_dir() { path.join(STATS_FILE) } else { path.to_path_buf() };
+    let json = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {} (build with `--stats` to write it)", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid stats file: {}", path.display()))
+}
+
+/// Signed size change, as in `+1.20 KB` or `-300 B`
+fn format_delta(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_size(delta.unsigned_abs() as usize))
+}
+
+fn format_chunk_size(size: Option<usize>) -> String {
+    size.map_or_else(|| "-".to_string(), format_size)
+}
+
+fn print_diff(diff: &StatsDiff) {
+    if diff.is_empty() {
+        println!("{} {}", "✓".green(), msg!("diff.no_differences"));
+        return;
+    }
+    
+    if !diff.chunks.is_empty() {
+        println!("{}", msg!("diff.chunks").bold());
+        for chunk in &diff.chunks {
+            let delta = format_delta(chunk.delta());
+            let delta = if chunk.delta() > 0 { delta.red() } else { delta.green() };
+            println!(
+                "  {:<24} {:>10} → {:<10} {}",
+                chunk.name,
+                format_chunk_size(chunk.old_size),
+                format_chunk_size(chunk.new_size),
+                delta
+            );
+        }
+        println!("  {:<24} {:>10}   {:<10} {}", msg!("diff.total"), "", "", format_delta(diff.total_delta()).bold());
+    }
+    
+    let sections: [(String, Vec<String>, colored::Color); 5] = [
+        (msg!("diff.added_modules"), diff.added_modules.clone(), colored::Color::Green),
+        (msg!("diff.removed_modules"), diff.removed_modules.clone(), colored::Color::Red),
+        (msg!("diff.added_dependencies"), diff.added_packages.iter().map(|(n, v)| package(n, v)).collect(), colored::Color::Green),
+        (msg!("diff.removed_dependencies"), diff.removed_packages.iter().map(|(n, v)| package(n, v)).collect(), colored::Color::Red),
+        (
+            msg!("diff.updated_dependencies"),
+            diff.updated_packages.iter().map(|(n, (old, new))| format!("{} {} → {}", n, old, new)).collect(),
+            colored::Color::Yellow,
+        ),
+    ];
+    for (title, items, color) in sections {
+        if items.is_empty() {
+            continue;
+        }
+        println!("\n{}", title.bold());
+        for item in items {
+            println!("  {}", item.color(color));
+        }
+    }
+}
+
+fn render_markdown(diff: &StatsDiff) -> String {
+    let mut out = String::from("### Bundle size\n\n");
+    if diff.is_empty() {
+        out.push_str("No differences.\n");
+        return out;
+    }
+    
+    if !diff.chunks.is_empty() {
+        out.push_str("| Chunk | Old | New | Change |\n|---|---:|---:|---:|\n");
+        for chunk in &diff.chunks {
+            out.push_str(&markdown_row(chunk));
+        }
+        out.push_str(&format!("| **Total** | | | **{}** |\n", format_delta(diff.total_delta())));
+    }
+    
+    let mut list = |title: &str, items: Vec<String>| {
+        if !items.is_empty() {
+            out.push_str(&format!("\n<details><summary>{} ({})</summary>\n\n", title, items.len()));
+            for item in items {
+                out.push_str(&format!("- `{}`\n", item));
+            }
+            out.push_str("\n</details>\n");
+        }
+    };
+    list("Added modules", diff.added_modules.clone());
+    list("Removed modules", diff.removed_modules.clone());
+    list("New dependencies", diff.added_packages.iter().map(|(n, v)| package(n, v)).collect());
+    list("Removed dependencies", diff.removed_packages.iter().map(|(n, v)| package(n, v)).collect());
+    list(
+        "Updated dependencies",
+        diff.updated_packages.iter().map(|(n, (old, new))| format!("{} {} → {}", n, old, new)).collect(),
+    );
+    out
+}
+
+fn markdown_row(chunk: &ChunkDelta) -> String {
+    format!(
+        "| `{}` | {} | {} | {} |\n",
+        chunk.name,
+        format_chunk_size(chunk.old_size),
+        format_chunk_size(chunk.new_size),
+        format_delta(chunk.delta())
+    )
+}
+
+/// `name@version`, or the name alone when the version is unknown
+fn package(name: &str, version: &str) -> String {
+    if version.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}@{}", name, version)
+    }
+}
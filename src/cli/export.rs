@@ -0,0 +1,147 @@
+//! Static export command implementation
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use tracing::info;
+
+use crate::bundler::{discover_routes, expand_entrypoints, minify_html as minify_html_page, BuildOptions, Bundler, Module, ModuleType};
+use crate::config::Config;
+use crate::export::{export_routes, find_browser, route_file, Browser, StaticServer};
+use crate::server::MimeTypes;
+use crate::vfs::RealFs;
+use crate::msg;
+
+/// Build the app and prerender its routes to static HTML
+#[derive(Args, Debug)]
+pub struct ExportCommand {
+    /// Output directory
+    #[arg(short, long)]
+    pub outdir: Option<PathBuf>,
+    
+    /// Route to prerender (repeatable; replaces `export.routes`)
+    #[arg(long = "route", value_name = "PATH")]
+    pub routes: Vec<String>,
+    
+    /// Chromium-based browser executable
+    #[arg(long, env = "COMPONENT_BROWSER")]
+    pub browser: Option<String>,
+    
+    /// Milliseconds each page may run before it is captured
+    #[arg(long)]
+    pub wait: Option<u64>,
+}
+
+impl ExportCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        let start = Instant::now();
+        
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let mut config = Config::load(config_path)?;
+        if !self.routes.is_empty() {
+            config.export.routes = self.routes.clone();
+        }
+        if let Some(browser) = &self.browser {
+            config.export.browser = Some(browser.clone());
+        }
+        if let Some(wait) = self.wait {
+            config.export.wait = wait;
+        }
+        
+        let page = export_page(&config)?;
+        let browser = Browser::new(
+            find_browser(config.export.browser.as_deref())?,
+            config.export.browser_args.clone(),
+            Duration::from_millis(config.export.wait),
+        );
+        let discovered = match &config.routes.dir {
+            Some(dir) => discover_routes(&config.root.join(dir), RealFs::shared().as_ref())?,
+            None => Vec::new(),
+        };
+        let routes = export_routes(&config.export.routes, &discovered);
+        
+        eprintln!("{} {}", "→".blue(), msg!("build.started"));
+        let out_dir = self.outdir.clone().unwrap_or_else(|| config.output_dir());
+        let base = config.output.base_path();
+        let mime = MimeTypes::new(&config.mime_types)?;
+        let minify_html = config.output.minify.html;
+        let bundler = Bundler::new(config, BuildOptions {
+            outdir: self.outdir.clone(),
+            minify: true,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        })?;
+        bundler.build().await?;
+        
+        let shell_path = out_dir.join(format!("{}.html", page));
+        let shell = fs::read_to_string(&shell_path)
+            .with_context(|| format!("Failed to read {}", shell_path.display()))?;
+        
+        eprintln!("{} {}", "→".blue(), msg!("export.prerendering", count = routes.len()));
+        let server = StaticServer::start(out_dir.clone(), &base, shell, mime).await?;
+        let mut pages = Vec::with_capacity(routes.len());
+        for route in &routes {
+            let html = browser.render(&server.url(route))
+                .await
+                .with_context(|| format!("Failed to render {}", route))?;
+            pages.push((route, html));
+        }
+        drop(server);
+        
+        // Write once everything rendered, so `/` doesn't replace the shell mid-export
+        for (route, html) in &pages {
+            let file = route_file(route);
+            let output_path = out_dir.join(&file);
+            if let Some(parent) = output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let html = if minify_html { minify_html_page(html) } else { html.clone() };
+            fs::write(&output_path, html)
+                .with_context(|| format!("Failed to write {}", output_path.display()))?;
+            eprintln!("  {} {} {}", "•".dimmed(), route, format!("→ {}", file.display()).dimmed());
+        }
+        
+        eprintln!(
+            "\n{} {}\n",
+            "✓".green().bold(),
+            msg!(
+                "export.finished",
+                count = pages.len(),
+                dir = out_dir.display(),
+                seconds = format!("{:.2}", start.elapsed().as_secs_f64()),
+            )
+        );
+        Ok(())
+    }
+}
+
+/// Name of the HTML entrypoint routes are rendered with
+fn export_page(config: &Config) -> Result<String> {
+    let entries = expand_entrypoints(config, RealFs::shared().as_ref())?;
+    let pages: Vec<&String> = entries
+        .iter()
+        .filter(|(_, path)| Module::detect_type(path) == ModuleType::Html)
+        .map(|(name, _)| name)
+        .collect();
+    
+    if let Some(page) = &config.export.page {
+        if !pages.contains(&page) {
+            anyhow::bail!("export.page '{}' is not an HTML entrypoint", page);
+        }
+        return Ok(page.clone());
+    }
+    
+    match pages.as_slice() {
+        [] => anyhow::bail!("Export needs an HTML entrypoint (e.g. index = \"index.html\")"),
+        [page] => Ok((*page).clone()),
+        _ => pages.iter()
+            .find(|page| page.as_str() == "index")
+            .map(|page| (*page).clone())
+            .context("Several HTML entrypoints; choose one with export.page"),
+    }
+}
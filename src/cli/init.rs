@@ -7,6 +7,8 @@ use anyhow::{Context, Result};
 use clap::Args;
 use colored::Colorize;
 
+use crate::msg;
+
 /// Initialize a new project
 #[derive(Args, Debug)]
 pub struct InitCommand {
@@ -27,11 +29,7 @@ impl InitCommand {
     pub async fn execute(&self) -> Result<()> {
         let project_dir = Path::new(&self.name);
         
-        eprintln!(
-            "{} Initializing new {} project...\n",
-            "→".blue(),
-            self.template.cyan()
-        );
+        eprintln!("{} {}\n", "→".blue(), msg!("init.started", template = self.template.cyan()));
         
         // Create project directory if needed
         if self.name != "." {
@@ -43,7 +41,7 @@ impl InitCommand {
         let config_content = self.generate_config();
         fs::write(project_dir.join("component.toml"), config_content)
             .context("Failed to write component.toml")?;
-        eprintln!("  {} Created {}", "✓".green(), "component.toml".cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = "component.toml".cyan()));
         
         // Generate source files based on template
         self.generate_template(project_dir)?;
@@ -52,20 +50,28 @@ impl InitCommand {
         let package_json = self.generate_package_json();
         fs::write(project_dir.join("package.json"), package_json)
             .context("Failed to write package.json")?;
-        eprintln!("  {} Created {}", "✓".green(), "package.json".cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = "package.json".cyan()));
         
         // Generate index.html
         let index_html = self.generate_index_html();
         fs::write(project_dir.join("index.html"), index_html)
             .context("Failed to write index.html")?;
-        eprintln!("  {} Created {}", "✓".green(), "index.html".cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = "index.html".cyan()));
+        
+        // Keep build output and project state out of git
+        let created = update_gitignore(project_dir)?;
+        if let Some(created) = created {
+            let message = if created {
+                msg!("init.created", path = ".gitignore".cyan())
+            } else {
+                msg!("init.updated", path = ".gitignore".cyan())
+            };
+            eprintln!("  {} {}", "✓".green(), message);
+        }
         
-        eprintln!(
-            "\n{} Project initialized successfully!\n",
-            "✓".green().bold()
-        );
+        eprintln!("\n{} {}\n", "✓".green().bold(), msg!("init.finished"));
         
-        eprintln!("  Next steps:");
+        eprintln!("  {}", msg!("init.next_steps"));
         if self.name != "." {
             eprintln!("    {} cd {}", "→".dimmed(), self.name.cyan());
         }
@@ -193,7 +199,7 @@ button:focus-visible {
 }
 "#;
         fs::write(src_dir.join("style.css"), css_content)?;
-        eprintln!("  {} Created {}", "✓".green(), "src/style.css".cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = "src/style.css".cyan()));
         
         Ok(())
     }
@@ -249,7 +255,7 @@ setupCounter();
         };
         
         fs::write(src_dir.join(format!("main.{}", ext)), content)?;
-        eprintln!("  {} Created {}", "✓".green(), format!("src/main.{}", ext).cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = format!("src/main.{}", ext).cyan()));
         
         Ok(())
     }
@@ -324,10 +330,10 @@ export default App;
         };
         
         fs::write(src_dir.join(format!("main.{}", ext)), main_content)?;
-        eprintln!("  {} Created {}", "✓".green(), format!("src/main.{}", ext).cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = format!("src/main.{}", ext).cyan()));
         
         fs::write(src_dir.join(format!("App.{}", ext)), app_content)?;
-        eprintln!("  {} Created {}", "✓".green(), format!("src/App.{}", ext).cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = format!("src/App.{}", ext).cyan()));
         
         Ok(())
     }
@@ -335,14 +341,12 @@ export default App;
     fn generate_vue_template(&self, src_dir: &Path) -> Result<()> {
         let ext = if self.typescript { "ts" } else { "js" };
         
-        let main_content = format!(
-            r#"import {{ createApp }} from 'vue';
+        let main_content = r#"import { createApp } from 'vue';
 import App from './App.vue';
 import './style.css';
 
 createApp(App).mount('#app');
-"#
-        );
+"#;
         
         let app_content = if self.typescript {
             r#"<script setup lang="ts">
@@ -373,10 +377,10 @@ const count = ref(0);
         };
         
         fs::write(src_dir.join(format!("main.{}", ext)), main_content)?;
-        eprintln!("  {} Created {}", "✓".green(), format!("src/main.{}", ext).cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = format!("src/main.{}", ext).cyan()));
         
         fs::write(src_dir.join("App.vue"), app_content)?;
-        eprintln!("  {} Created {}", "✓".green(), "src/App.vue".cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = "src/App.vue".cyan()));
         
         Ok(())
     }
@@ -423,10 +427,10 @@ export default app;
         };
         
         fs::write(src_dir.join(format!("main.{}", ext)), main_content)?;
-        eprintln!("  {} Created {}", "✓".green(), format!("src/main.{}", ext).cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = format!("src/main.{}", ext).cyan()));
         
         fs::write(src_dir.join("App.svelte"), app_content)?;
-        eprintln!("  {} Created {}", "✓".green(), "src/App.svelte".cyan());
+        eprintln!("  {} {}", "✓".green(), msg!("init.created", path = "src/App.svelte".cyan()));
         
         Ok(())
     }
@@ -500,3 +504,35 @@ export default app;
         )
     }
 }
+
+/// Entries `init` makes sure `.gitignore` has
+const GITIGNORE_ENTRIES: &[&str] = &["node_modules/", "dist/", ".component/"];
+
+/// Add the missing `GITIGNORE_ENTRIES` to the project's `.gitignore`
+///
+/// Returns whether the file was created, or `None` if nothing changed.
+fn update_gitignore(project_dir: &Path) -> Result<Option<bool>> {
+    let path = project_dir.join(".gitignore");
+    let existing = fs::read_to_string(&path).ok();
+    let content = existing.clone().unwrap_or_default();
+    let present: Vec<&str> = content.lines().map(|line| line.trim().trim_start_matches('/')).collect();
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .iter()
+        .copied()
+        .filter(|entry| !present.contains(entry) && !present.contains(&entry.trim_end_matches('/')))
+        .collect();
+    if missing.is_empty() {
+        return Ok(None);
+    }
+    
+    let mut content = content;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    fs::write(&path, content).context("Failed to write .gitignore")?;
+    Ok(Some(existing.is_none()))
+}
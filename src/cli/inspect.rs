@@ -0,0 +1,110 @@
+//! Inspect command implementation
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use tracing::info;
+
+use crate::bundler::{BuildOptions, Bundler, ModuleInspection};
+use crate::config::Config;
+use crate::msg;
+
+/// Print a module's code after resolution and every transform
+#[derive(Args, Debug)]
+pub struct InspectCommand {
+    /// File to inspect
+    pub file: PathBuf,
+    
+    /// Print the code after every transform stage, not just the final code
+    #[arg(long)]
+    pub stages: bool,
+    
+    /// Print the inspection as JSON to stdout
+    #[arg(long)]
+    pub json: bool,
+    
+    /// Write the final code to this file, and its source map to `<FILE>.map`
+    #[arg(short, long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+impl InspectCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let config = Config::load(config_path)?;
+        
+        let bundler = Bundler::new(config, BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: true,
+            target: "esnext".to_string(),
+            inspect_transforms: true,
+        })?;
+        let file = std::env::current_dir()?.join(&self.file);
+        let inspection = bundler.inspect(&file).await?;
+        
+        if let Some(out) = &self.out {
+            fs::write(out, &inspection.code)
+                .with_context(|| format!("Failed to write {}", out.display()))?;
+            if let Some(map) = &inspection.map {
+                let map_path = PathBuf::from(format!("{}.map", out.display()));
+                fs::write(&map_path, map)
+                    .with_context(|| format!("Failed to write {}", map_path.display()))?;
+            }
+        }
+        
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&inspection)?);
+            return Ok(());
+        }
+        
+        print_summary(&inspection);
+        if self.stages {
+            for stage in &inspection.stages {
+                let changed = if stage.changed { "changed" } else { "unchanged" };
+                println!("// ── {} ({}, {:.2}ms)", stage.name, changed, stage.duration_ms);
+                println!("{}", stage.code);
+            }
+        } else if self.out.is_none() {
+            println!("{}", inspection.code);
+        }
+        
+        if let Some(out) = &self.out {
+            eprintln!("{} {}", "✓".green(), msg!("inspect.wrote", path = out.display()));
+        }
+        Ok(())
+    }
+}
+
+/// Describe the module, its imports, and the stages that ran on stderr
+fn print_summary(inspection: &ModuleInspection) {
+    eprintln!(
+        "{} {} {}",
+        "→".blue(),
+        inspection.path.display(),
+        format!("({:?})", inspection.module_type).to_lowercase().dimmed()
+    );
+    
+    for dependency in &inspection.dependencies {
+        match (&dependency.resolved, &dependency.error) {
+            (Some(path), _) => eprintln!("  {} {} → {}", "•".dimmed(), dependency.specifier, path.display().to_string().dimmed()),
+            (None, Some(error)) => eprintln!("  {} {} → {}", "✗".red(), dependency.specifier, error.red()),
+            (None, None) => eprintln!("  {} {} → {}", "•".dimmed(), dependency.specifier, msg!("inspect.not_resolved").yellow()),
+        }
+    }
+    
+    let stages: Vec<String> = inspection.stages
+        .iter()
+        .map(|stage| if stage.changed { stage.name.clone() } else { msg!("inspect.unchanged", stage = stage.name) })
+        .collect();
+    eprintln!("  {} {}", "•".dimmed(), msg!("inspect.stages", stages = stages.join(", ")));
+    
+    let map = match &inspection.map {
+        Some(map) => msg!("inspect.map_size", size = map.len()),
+        None => msg!("inspect.no_map"),
+    };
+    eprintln!("  {} {}\n", "•".dimmed(), msg!("inspect.source_map", map = map));
+}
@@ -0,0 +1,73 @@
+//! License report command implementation
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use tracing::info;
+
+use crate::bundler::{BuildOptions, Bundler};
+use crate::config::Config;
+use crate::msg;
+
+/// Report the licenses of bundled node_modules packages
+#[derive(Args, Debug)]
+pub struct LicensesCommand {
+    /// Print the report as JSON to stdout
+    #[arg(long)]
+    pub json: bool,
+    
+    /// Write licenses.json and licenses.txt to this directory
+    #[arg(short, long, value_name = "DIR")]
+    pub out: Option<PathBuf>,
+    
+    /// Fail if a package uses this license (repeatable, `GPL-*` matches any suffix)
+    #[arg(long, value_name = "LICENSE")]
+    pub deny: Vec<String>,
+}
+
+impl LicensesCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let config = Config::load(config_path)?;
+        
+        let mut deny = config.licenses.deny.clone();
+        deny.extend(self.deny.iter().cloned());
+        
+        let bundler = Bundler::new(config, BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "esnext".to_string(),
+            inspect_transforms: false,
+        })?;
+        bundler.build_graph().await?;
+        let report = bundler.licenses()?;
+        
+        if let Some(out) = &self.out {
+            fs::create_dir_all(out)
+                .with_context(|| format!("Failed to create {}", out.display()))?;
+            fs::write(out.join("licenses.json"), serde_json::to_string_pretty(&report)?)?;
+            fs::write(out.join("licenses.txt"), report.to_text())?;
+            eprintln!("{} {}", "✓".green(), msg!("licenses.wrote", dir = out.display()));
+        }
+        
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else if self.out.is_none() {
+            for package in &report.packages {
+                let license = if crate::bundler::is_denied(&package.license, &deny) {
+                    package.license.red().bold()
+                } else {
+                    package.license.normal()
+                };
+                println!("{}@{}  {}", package.name, package.version.dimmed(), license);
+            }
+            eprintln!("\n{} {}", "•".dimmed(), msg!("licenses.summary", count = report.packages.len()));
+        }
+        
+        report.check(&deny)
+    }
+}
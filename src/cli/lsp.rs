@@ -0,0 +1,26 @@
+//! Editor integration command implementation
+
+use anyhow::Result;
+use clap::Args;
+use tokio::io::BufReader;
+use tracing::info;
+
+use crate::config::Config;
+use crate::lsp::LspServer;
+use crate::msg;
+
+/// Answer bundler queries from editors over stdio JSON-RPC
+#[derive(Args, Debug)]
+pub struct LspCommand {}
+
+impl LspCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let config = Config::load(config_path)?;
+        
+        let mut server = LspServer::new(config).await?;
+        server
+            .run(BufReader::new(tokio::io::stdin()), tokio::io::stdout())
+            .await
+    }
+}
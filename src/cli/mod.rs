@@ -3,19 +3,63 @@
 //! Provides the main CLI structure using clap with subcommands for:
 //! - `build`: Production build
 //! - `dev`: Development server with HMR
+//! - `preview`: Serve the production build
 //! - `init`: Project scaffolding
+//! - `bench`: Cold/warm build benchmarking
+//! - `lsp`: Editor integration over stdio
+//! - `licenses`: Third-party license report
+//! - `audit`: Known vulnerabilities in bundled dependencies
+//! - `export`: Prerender routes to static HTML
+//! - `inspect`: A module's code after every transform
+//! - `diff`: Compare the stats of two builds
+//! - `status`: Config, caches, and the last build
+//! - `config explain`: Documentation and effective value of a config key
+//! - `self-update`: Install the latest release
+//!
+//! Projects can pin the version that builds them; see [`toolchain`].
+//!
+//! Failures exit with a code scripts can rely on: [`EXIT_FAILURE`],
+//! [`EXIT_CONFIG_ERROR`] or [`EXIT_TOO_MANY_WARNINGS`].
 
+mod audit;
+mod bench;
 mod build;
+mod config;
+pub mod console;
 mod dev;
+mod diff;
+mod export;
 mod init;
+mod inspect;
+mod licenses;
+mod lsp;
+mod preview;
+mod run;
+mod self_update;
+mod status;
+pub mod toolchain;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use colored::Colorize;
 
-pub use build::{BuildCommand, BuildOptions};
+pub use audit::AuditCommand;
+pub use bench::BenchCommand;
+pub use build::{BuildCommand, TooManyWarnings};
+pub use config::{ConfigAction, ConfigCommand};
+pub use console::ColorChoice;
+pub use crate::bundler::BuildOptions;
+use crate::config::ConfigError;
 pub use dev::{DevCommand, DevServerOptions};
+pub use diff::DiffCommand;
+pub use export::ExportCommand;
 pub use init::InitCommand;
+pub use inspect::InspectCommand;
+pub use licenses::LicensesCommand;
+pub use lsp::LspCommand;
+pub use preview::PreviewCommand;
+pub use run::RunHook;
+pub use self_update::SelfUpdateCommand;
+pub use status::StatusCommand;
 
 /// Component Reborn - A modern, batteries-included frontend build tool
 #[derive(Parser, Debug)]
@@ -33,6 +77,36 @@ pub struct Cli {
     /// Path to component.toml config file
     #[arg(short, long, global = true, default_value = "component.toml")]
     pub config: String,
+    
+    /// Non-interactive mode for pipelines: no colors, banner or progress
+    /// lines, and builds fail on warnings unless `build.max_warnings` allows them
+    #[arg(long, global = true)]
+    pub ci: bool,
+    
+    /// When to color output (defaults to `never` with `--ci`, else `auto`)
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+}
+
+/// Exit code of a failed build or command
+pub const EXIT_FAILURE: u8 = 1;
+
+/// Exit code when component.toml can't be read, parsed, or validated
+pub const EXIT_CONFIG_ERROR: u8 = 2;
+
+/// Exit code when a build reports more warnings than `build.max_warnings`
+/// allows
+pub const EXIT_TOO_MANY_WARNINGS: u8 = 3;
+
+/// Exit code for the error a command failed with
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    if error.chain().any(|cause| cause.is::<ConfigError>()) {
+        EXIT_CONFIG_ERROR
+    } else if error.chain().any(|cause| cause.is::<TooManyWarnings>()) {
+        EXIT_TOO_MANY_WARNINGS
+    } else {
+        EXIT_FAILURE
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -43,29 +117,154 @@ pub enum Commands {
     /// Start development server with hot module replacement
     Dev(DevCommand),
 
+    /// Serve the production build locally
+    Preview(PreviewCommand),
+
     /// Initialize a new project
     Init(InitCommand),
+
+    /// Benchmark cold and warm builds
+    Bench(BenchCommand),
+
+    /// Serve bundler queries to editors over stdio
+    Lsp(LspCommand),
+
+    /// Report licenses of bundled dependencies
+    Licenses(LicensesCommand),
+
+    /// Check bundled dependencies for known vulnerabilities
+    Audit(AuditCommand),
+
+    /// Prerender routes to static HTML
+    Export(ExportCommand),
+
+    /// Print a module's code after resolution and every transform
+    Inspect(InspectCommand),
+
+    /// Compare the stats of two builds: chunk sizes, modules, and dependencies
+    Diff(DiffCommand),
+
+    /// Report the config summary, cache sizes, and last build
+    Status(StatusCommand),
+
+    /// Explain config keys: description, type, default, and effective value
+    Config(ConfigCommand),
+    
+    /// Update component to the latest release
+    SelfUpdate(SelfUpdateCommand),
+    
+    /// Serve transforms for a build over stdio (see `build.isolate_transforms`)
+    #[command(name = "transform-worker", hide = true)]
+    TransformWorker,
 }
 
 impl Cli {
+    /// When to color output, from `--color` and `--ci`
+    pub fn color_choice(&self) -> ColorChoice {
+        match self.color {
+            Some(choice) => choice,
+            None if self.ci => ColorChoice::Never,
+            None => ColorChoice::Auto,
+        }
+    }
+    
+    /// `cli.locale` from the config file, if it sets one
+    ///
+    /// Read leniently, before the config is loaded, so messages about
+    /// loading it are already in the right language.
+    pub fn locale(&self) -> Option<String> {
+        let table = self.config_table()?;
+        table.get("cli")?.get("locale")?.as_str().map(str::to_string)
+    }
+    
+    /// Binary to run instead of this one, when the project pins a version
+    /// this one isn't (see [`toolchain`])
+    ///
+    /// Scaffolding, updating, and transform workers run on any version.
+    pub fn pinned_toolchain(&self) -> Result<Option<std::path::PathBuf>> {
+        if matches!(self.command, Commands::Init(_) | Commands::SelfUpdate(_) | Commands::TransformWorker) {
+            return Ok(None);
+        }
+        let config = std::path::Path::new(&self.config);
+        if !config.is_file() {
+            return Ok(None);
+        }
+        let root = config.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+        let table = self.config_table();
+        let configured = table.as_ref().and_then(|table| table.get("project")?.get("required_version")?.as_str());
+        toolchain::pinned(root, configured)
+    }
+    
+    /// The config file as a TOML table, if it can be read and parsed
+    fn config_table(&self) -> Option<toml::Table> {
+        let source = std::fs::read_to_string(&self.config).ok()?;
+        toml::from_str(&source).ok()
+    }
+    
     /// Execute the CLI command
+    ///
+    /// Output is colored as [`console::init`] last decided.
     pub async fn execute(&self) -> Result<()> {
-        print_banner();
+        // Workers' stderr is the build's, which already has a banner
+        if !self.ci && !matches!(self.command, Commands::TransformWorker) {
+            console::banner();
+        }
+        if let Ok(executable) = std::env::current_exe() {
+            crate::transform::worker::set_executable(executable);
+        }
 
         match &self.command {
-            Commands::Build(cmd) => cmd.execute(&self.config).await,
+            Commands::Build(cmd) => cmd.execute(&self.config, self.ci).await,
             Commands::Dev(cmd) => cmd.execute(&self.config).await,
+            Commands::Preview(cmd) => cmd.execute(&self.config).await,
             Commands::Init(cmd) => cmd.execute().await,
+            Commands::Bench(cmd) => cmd.execute(&self.config).await,
+            Commands::Lsp(cmd) => cmd.execute(&self.config).await,
+            Commands::Licenses(cmd) => cmd.execute(&self.config).await,
+            Commands::Audit(cmd) => cmd.execute(&self.config).await,
+            Commands::Export(cmd) => cmd.execute(&self.config).await,
+            Commands::Inspect(cmd) => cmd.execute(&self.config).await,
+            Commands::Diff(cmd) => cmd.execute().await,
+            Commands::Status(cmd) => cmd.execute(&self.config).await,
+            Commands::Config(cmd) => cmd.execute(&self.config).await,
+            Commands::SelfUpdate(cmd) => cmd.execute().await,
+            Commands::TransformWorker => crate::transform::worker::serve(std::io::stdin().lock(), std::io::stdout().lock()),
         }
     }
 }
 
-/// Print the Component banner
-fn print_banner() {
-    eprintln!(
-        "\n{} {} {}\n",
-        "⚡".cyan(),
-        "Component".bold().cyan(),
-        format!("v{}", env!("CARGO_PKG_VERSION")).dimmed()
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    
+    #[test]
+    fn test_exit_codes() {
+        let config = crate::Config::load("/nonexistent/component.toml").unwrap_err();
+        assert_eq!(exit_code(&config), EXIT_CONFIG_ERROR);
+        assert!(format!("{:#}", config).starts_with("Failed to read config file: /nonexistent/component.toml: "));
+        
+        let warnings = anyhow::Error::new(TooManyWarnings { count: 2, max: 0 });
+        assert_eq!(exit_code(&warnings), EXIT_TOO_MANY_WARNINGS);
+        assert_eq!(warnings.to_string(), "Build reported 2 warning(s), more than the 0 allowed");
+        
+        let wrapped = Err::<(), _>(config).context("Failed to start").unwrap_err();
+        assert_eq!(exit_code(&wrapped), EXIT_CONFIG_ERROR);
+        assert_eq!(exit_code(&anyhow::anyhow!("Failed to resolve './missing'")), EXIT_FAILURE);
+    }
+    
+    #[test]
+    fn test_color_choice() {
+        let choice = |args: &[&str]| Cli::parse_from([&["component"], args, &["status"]].concat()).color_choice();
+        assert_eq!(choice(&[]), ColorChoice::Auto);
+        assert_eq!(choice(&["--ci"]), ColorChoice::Never);
+        assert_eq!(choice(&["--ci", "--color", "always"]), ColorChoice::Always);
+        assert_eq!(choice(&["--color=never"]), ColorChoice::Never);
+    }
+    
+    #[test]
+    fn test_empty_api_token() {
+        assert!(Cli::try_parse_from(["component", "dev", "--api-token", ""]).is_err());
+        assert!(Cli::try_parse_from(["component", "dev", "--api-token", "secret"]).is_ok());
+    }
 }
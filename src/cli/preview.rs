@@ -0,0 +1,48 @@
+//! Preview command implementation
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use clap::Args;
+use tracing::info;
+
+use crate::config::Config;
+use crate::server::PreviewServer;
+use crate::msg;
+
+/// Serve the production build locally
+#[derive(Args, Debug)]
+pub struct PreviewCommand {
+    /// Port to serve on (defaults to `preview.port`)
+    #[arg(short, long)]
+    pub port: Option<u16>,
+    
+    /// Host to bind to (defaults to `preview.host`)
+    #[arg(long)]
+    pub host: Option<String>,
+    
+    /// Directory to serve (defaults to the output directory)
+    #[arg(short, long)]
+    pub outdir: Option<PathBuf>,
+    
+    /// Sub-path the build was made for (defaults to `output.public_url`)
+    #[arg(long, value_name = "PATH")]
+    pub base: Option<String>,
+}
+
+impl PreviewCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let mut config = Config::load(config_path)?;
+        if let Some(base) = &self.base {
+            config.set_base(base);
+        }
+        
+        let host = self.host.clone().unwrap_or_else(|| config.preview.host.clone());
+        let port = self.port.unwrap_or(config.preview.port);
+        let dir = self.outdir.clone().unwrap_or_else(|| config.output_dir());
+        
+        PreviewServer::new(Arc::new(config), dir).start(&host, port).await
+    }
+}
@@ -0,0 +1,118 @@
+//! Commands run after incremental rebuilds (`--run <cmd>`)
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::msg;
+
+/// A shell command run after each successful rebuild in watch or dev mode
+///
+/// The command learns what changed from the environment and stdin, paths
+/// relative to the project root, one per line:
+///
+/// - `COMPONENT_CHANGED_FILES` - files whose change triggered the rebuild
+/// - `COMPONENT_AFFECTED_FILES` - those files and every module importing
+///   them, directly or transitively; also written to stdin
+#[derive(Debug, Clone)]
+pub struct RunHook {
+    command: String,
+    root: PathBuf,
+}
+
+impl RunHook {
+    pub fn new(command: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        Self { command: command.into(), root: root.into() }
+    }
+    
+    /// Run the command and wait for it; returns its exit status
+    pub fn run(&self, changed: &[PathBuf], affected: &[PathBuf]) -> Result<ExitStatus> {
+        let changed = self.relative_lines(changed);
+        let affected = self.relative_lines(affected);
+        eprintln!("  {} {}", "→".blue(), msg!("run.running", command = self.command.cyan()));
+        
+        let mut child = shell(&self.command)
+            .current_dir(&self.root)
+            .env("COMPONENT_CHANGED_FILES", &changed)
+            .env("COMPONENT_AFFECTED_FILES", &affected)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run `{}`", self.command))?;
+        
+        if let Some(mut stdin) = child.stdin.take() {
+            // Commands that don't read stdin close it early
+            let _ = stdin.write_all(affected.as_bytes());
+            let _ = stdin.write_all(b"\n");
+        }
+        let status = child.wait()?;
+        
+        if status.success() {
+            eprintln!("  {} {}\n", "✓".green(), msg!("run.passed", command = self.command));
+        } else {
+            eprintln!("  {} {}\n", "✗".red(), msg!("run.failed", command = self.command, status = status));
+        }
+        Ok(status)
+    }
+    
+    /// Paths relative to the root, one per line
+    fn relative_lines(&self, paths: &[PathBuf]) -> String {
+        paths
+            .iter()
+            .map(|path| relative(path, &self.root))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn relative(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).display().to_string()
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_hook_gets_changed_and_affected_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let hook = RunHook::new(
+            "printf '%s|%s|' \"$COMPONENT_CHANGED_FILES\" \"$COMPONENT_AFFECTED_FILES\" > out.txt; cat >> out.txt",
+            root,
+        );
+        
+        let changed = [root.join("src/a.js")];
+        let affected = [root.join("src/a.js"), root.join("src/main.js")];
+        let status = hook.run(&changed, &affected).unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(root.join("out.txt")).unwrap(),
+            "src/a.js|src/a.js\nsrc/main.js|src/a.js\nsrc/main.js\n"
+        );
+    }
+    
+    #[test]
+    fn test_failing_hook_reports_its_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = RunHook::new("exit 3", dir.path()).run(&[], &[]).unwrap();
+        assert!(!status.success());
+        assert_eq!(status.code(), Some(3));
+    }
+}
@@ -0,0 +1,81 @@
+//! Self-update command implementation
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use tracing::warn;
+
+use crate::msg;
+use crate::update::{self, ReleaseClient, VersionChange, DEFAULT_API, SIGNATURE_FILE, SUMS_FILE};
+
+/// Update `component` to the latest release
+#[derive(Args, Debug)]
+pub struct SelfUpdateCommand {
+    /// Only report whether a newer release exists
+    #[arg(long)]
+    pub check: bool,
+    
+    /// Install this version instead of the latest, e.g. to roll back
+    #[arg(long, value_name = "VERSION")]
+    pub to: Option<String>,
+    
+    /// GitHub API URL of the repository releases come from
+    #[arg(long, env = "COMPONENT_RELEASES_API", default_value = DEFAULT_API)]
+    pub api: String,
+}
+
+impl SelfUpdateCommand {
+    pub async fn execute(&self) -> Result<()> {
+        if !cfg!(feature = "self-update") {
+            anyhow::bail!("Self-update is disabled in this build; update component with the package manager that installed it");
+        }
+        
+        let current = semver::Version::parse(env!("CARGO_PKG_VERSION"))?;
+        let client = ReleaseClient::new(&self.api);
+        let release = client.release(self.to.as_deref())?;
+        let version = release.version()?;
+        
+        let change = VersionChange::new(&current, &version, self.to.is_some());
+        if change == VersionChange::UpToDate {
+            eprintln!("{} {}", "✓".green(), msg!("update.up_to_date", version = current));
+            return Ok(());
+        }
+        if self.check {
+            let message = match change {
+                VersionChange::Downgrade => msg!("update.would_downgrade", current = current, version = version.to_string().cyan()),
+                _ => msg!("update.available", current = current, version = version.to_string().cyan()),
+            };
+            eprintln!("{} {}", "→".blue(), message);
+            return Ok(());
+        }
+        
+        let name = update::asset_name();
+        let asset = release.asset(&name)
+            .with_context(|| format!("Release {} has no binary for this platform ({})", release.tag, name))?;
+        let sums = release.asset(SUMS_FILE)
+            .with_context(|| format!("Release {} has no {}", release.tag, SUMS_FILE))?;
+        
+        eprintln!("{} {}", "→".blue(), msg!("update.downloading", name = name, version = version));
+        let binary = client.download(asset)?;
+        let sums = String::from_utf8(client.download(sums)?).context("Invalid SHA256SUMS")?;
+        let signature = match release.asset(SIGNATURE_FILE) {
+            Some(signature) => Some(String::from_utf8(client.download(signature)?).context("Invalid SHA256SUMS.sig")?),
+            None => None,
+        };
+        
+        let key = update::release_key();
+        update::verify(&binary, &name, &sums, signature.as_deref(), key)?;
+        if key.is_none() {
+            warn!("{}", msg!("update.unsigned"));
+        }
+        
+        let exe = std::env::current_exe().context("Failed to locate the running executable")?;
+        update::replace_executable(&exe, &binary)?;
+        let message = match change {
+            VersionChange::Downgrade => msg!("update.downgraded", current = current, version = version, path = exe.display()),
+            _ => msg!("update.updated", current = current, version = version, path = exe.display()),
+        };
+        eprintln!("{} {}", "✓".green().bold(), message);
+        Ok(())
+    }
+}
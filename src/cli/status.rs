@@ -0,0 +1,170 @@
+//! Project status command implementation
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use tracing::info;
+
+use crate::config::{Config, LastBuild, ProjectState, STATE_DIR};
+use crate::utils::format_size;
+use crate::msg;
+
+/// Report the project's configuration, caches, and last build
+#[derive(Args, Debug)]
+pub struct StatusCommand {
+    /// Print the report as JSON to stdout
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Everything `component status` reports; read from disk only
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    name: String,
+    version: String,
+    entrypoints: BTreeMap<String, String>,
+    output_dir: String,
+    public_url: String,
+    plugins: usize,
+    
+    /// Transform cache, when enabled
+    cache: Option<CacheStatus>,
+    
+    /// Size in bytes of each file and directory in `.component/`
+    state: BTreeMap<String, u64>,
+    
+    last_build: Option<LastBuild>,
+}
+
+/// Transform cache directory and its size in bytes
+#[derive(Debug, Serialize)]
+struct CacheStatus {
+    dir: String,
+    size: u64,
+}
+
+impl StatusCommand {
+    pub async fn execute(&self, config_path: &str) -> Result<()> {
+        info!("{}", msg!("cli.loading_config", path = config_path));
+        let config = Config::load(config_path)?;
+        let state = ProjectState::new(&config.root);
+        
+        let report = StatusReport {
+            name: config.project.name.clone(),
+            version: config.project.version.clone(),
+            entrypoints: config.entrypoints.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            output_dir: config.output.dir.clone(),
+            public_url: config.output.public_url.clone(),
+            plugins: config.plugins.len(),
+            cache: config.cache.enabled.then(|| CacheStatus {
+                dir: config.cache.dir.clone(),
+                size: dir_size(&config.root.join(&config.cache.dir)),
+            }),
+            state: state_entries(state.dir()),
+            last_build: state.last_build(),
+        };
+        
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+        Ok(())
+    }
+}
+
+/// Size of each entry in the state directory, except its `.gitignore`
+fn state_entries(dir: &Path) -> BTreeMap<String, u64> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name() != ".gitignore")
+        .map(|entry| (entry.file_name().to_string_lossy().into_owned(), dir_size(&entry.path())))
+        .collect()
+}
+
+/// Total size of the files under a path, or of the file itself
+fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| dir_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// How long ago a Unix timestamp was, as in `5 minutes ago`
+fn format_age(timestamp: u64) -> String {
+    let seconds = LastBuild::now().saturating_sub(timestamp);
+    match seconds {
+        0..=59 => msg!("status.just_now"),
+        60..=119 => msg!("status.minute_ago"),
+        120..=3599 => msg!("status.minutes_ago", count = seconds / 60),
+        3600..=7199 => msg!("status.hour_ago"),
+        7200..=86_399 => msg!("status.hours_ago", count = seconds / 3600),
+        86_400..=172_799 => msg!("status.day_ago"),
+        _ => msg!("status.days_ago", count = seconds / 86_400),
+    }
+}
+
+fn print_report(report: &StatusReport) {
+    let label = |text: &str| format!("  {:<16} ", text).dimmed();
+    
+    println!("{} {}\n", report.name.bold(), report.version.dimmed());
+    
+    println!("{}", msg!("status.config").bold());
+    for (name, path) in &report.entrypoints {
+        println!("{}{} → {}", label(&msg!("status.entry")), name.cyan(), path);
+    }
+    println!(
+        "{}{}",
+        label(&msg!("status.output")),
+        msg!("status.served_from", dir = report.output_dir, url = report.public_url)
+    );
+    println!("{}{}", label(&msg!("status.plugins")), report.plugins);
+    match &report.cache {
+        Some(cache) => println!("{}{} ({})", label(&msg!("status.cache")), cache.dir, format_size(cache.size as usize)),
+        None => println!("{}{}", label(&msg!("status.cache")), msg!("status.disabled").yellow()),
+    }
+    
+    let total: u64 = report.state.values().sum();
+    println!("\n{} {}", format!("{}/", STATE_DIR).bold(), format!("({})", format_size(total as usize)).dimmed());
+    if report.state.is_empty() {
+        println!("  {}", msg!("status.empty").dimmed());
+    }
+    for (name, size) in &report.state {
+        println!("{}{}", label(name), format_size(*size as usize));
+    }
+    
+    println!("\n{}", msg!("status.last_build").bold());
+    match &report.last_build {
+        Some(build) => {
+            println!(
+                "{}{}",
+                label(&msg!("status.finished")),
+                msg!("status.build", age = format_age(build.finished_at), mode = build.mode, ms = build.duration_ms)
+            );
+            println!(
+                "{}{}",
+                label(&msg!("status.output")),
+                msg!(
+                    "status.files",
+                    count = build.files.len(),
+                    size = format_size(build.total_size()),
+                    dir = build.output_dir,
+                )
+            );
+        }
+        None => println!("  {}", msg!("status.no_build").dimmed()),
+    }
+}
@@ -0,0 +1,147 @@
+//! Toolchain pinning
+//!
+//! A project pins the `component` it builds with by a semver range, such as
+//! `2.1` or `=2.1.3`, in `project.required_version` or in a
+//! `.component-version` file next to component.toml. When the running
+//! binary doesn't match, the newest matching one installed under
+//! `.component/toolchains/<version>/` runs instead, with the same
+//! arguments; without one, commands fail before doing anything.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
+
+use crate::config::{ConfigError, STATE_DIR};
+
+/// File pinning the version, next to component.toml
+pub const VERSION_FILE: &str = ".component-version";
+
+/// Set for a binary another one delegated to, so it doesn't delegate again
+const DELEGATED_ENV: &str = "COMPONENT_TOOLCHAIN";
+
+/// Directory matching binaries are installed in, by version
+pub fn toolchains_dir(root: &Path) -> PathBuf {
+    root.join(STATE_DIR).join("toolchains")
+}
+
+/// The range the project pins, with where it comes from
+///
+/// `configured` is `project.required_version`, which wins over the file.
+fn required_version(root: &Path, configured: Option<&str>) -> Result<Option<(VersionReq, String)>> {
+    let (range, source) = match configured {
+        Some(range) => (range.to_string(), "project.required_version".to_string()),
+        None => match fs::read_to_string(root.join(VERSION_FILE)) {
+            Ok(range) => (range.trim().to_string(), VERSION_FILE.to_string()),
+            Err(_) => return Ok(None),
+        },
+    };
+    let req = VersionReq::parse(&range)
+        .with_context(|| format!("{} '{}' isn't a semver range", source, range))?;
+    Ok(Some((req, source)))
+}
+
+/// Binary to run instead of this one, if the project in `root` pins a
+/// version this one isn't
+///
+/// Fails with a [`ConfigError`] when no installed binary matches.
+pub fn pinned(root: &Path, configured: Option<&str>) -> Result<Option<PathBuf>> {
+    let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let delegated = std::env::var_os(DELEGATED_ENV).is_some();
+    find(root, configured, &current, delegated).map_err(|error| ConfigError(error).into())
+}
+
+fn find(root: &Path, configured: Option<&str>, current: &Version, delegated: bool) -> Result<Option<PathBuf>> {
+    let Some((req, source)) = required_version(root, configured)? else {
+        return Ok(None);
+    };
+    if req.matches(current) {
+        return Ok(None);
+    }
+    
+    let dir = toolchains_dir(root);
+    let installed = fs::read_dir(&dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let version = Version::parse(entry.ok()?.file_name().to_str()?).ok()?;
+            let binary = dir.join(version.to_string()).join(binary_name());
+            (req.matches(&version) && binary.is_file()).then_some((version, binary))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b));
+    
+    match installed {
+        Some((_, binary)) if !delegated => Ok(Some(binary)),
+        _ => anyhow::bail!(
+            "{} requires component {}, but this is {}; run `component self-update --to <version>`, or install a matching binary as {}",
+            source,
+            req,
+            current,
+            dir.join("<version>").join(binary_name()).display()
+        ),
+    }
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) { "component.exe" } else { "component" }
+}
+
+/// Run `binary` with this process's arguments, returning its exit code
+///
+/// On Unix the binary replaces this process.
+pub fn delegate(binary: &Path) -> ExitCode {
+    let mut command = Command::new(binary);
+    command.args(std::env::args_os().skip(1).collect::<Vec<OsString>>()).env(DELEGATED_ENV, binary);
+    
+    #[cfg(unix)]
+    let error = {
+        use std::os::unix::process::CommandExt;
+        command.exec()
+    };
+    #[cfg(not(unix))]
+    let error = match command.status() {
+        Ok(status) => return ExitCode::from(status.code().unwrap_or(1) as u8),
+        Err(error) => error,
+    };
+    
+    eprintln!("{} Failed to run {}: {}", crate::msg!("cli.error"), binary.display(), error);
+    ExitCode::FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_pinned_toolchains() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let current = Version::new(2, 0, 0);
+        assert_eq!(find(root, None, &current, false).unwrap(), None);
+        
+        fs::write(root.join(VERSION_FILE), "^2.0\n").unwrap();
+        assert_eq!(find(root, None, &current, false).unwrap(), None);
+        
+        // Configured ranges win over the file
+        let error = find(root, Some("=2.1.3"), &current, false).unwrap_err().to_string();
+        assert!(error.starts_with("project.required_version requires component =2.1.3, but this is 2.0.0; "), "{}", error);
+        
+        for version in ["2.1.3", "2.1.4", "3.0.0"] {
+            let toolchain = toolchains_dir(root).join(version);
+            fs::create_dir_all(&toolchain).unwrap();
+            fs::write(toolchain.join(binary_name()), "").unwrap();
+        }
+        fs::create_dir_all(toolchains_dir(root).join("2.2.0")).unwrap();
+        fs::write(root.join(VERSION_FILE), "~2.1").unwrap();
+        assert_eq!(find(root, None, &current, false).unwrap(), Some(toolchains_dir(root).join("2.1.4").join(binary_name())));
+        assert_eq!(find(root, Some("=2.1.3"), &current, false).unwrap(), Some(toolchains_dir(root).join("2.1.3").join(binary_name())));
+        assert!(find(root, Some("^2.2"), &current, false).is_err());
+        assert!(find(root, None, &current, true).is_err());
+        
+        let invalid = find(root, Some("two"), &current, false).unwrap_err();
+        assert_eq!(invalid.to_string(), "project.required_version 'two' isn't a semver range");
+    }
+}
@@ -0,0 +1,330 @@
+//! Config key reference
+//!
+//! `component config explain <key>` documents a key with the doc comment of
+//! its field, read from the source of the config structs at compile time,
+//! so the reference can't drift from the schema. Defaults come from
+//! [`Config::default_config`] and effective values from the loaded config.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Config;
+
+/// Sources of the config structs
+const SCHEMA_SOURCES: &[&str] = &[include_str!("mod.rs"), include_str!("schema.rs")];
+
+/// Documented fields of each config struct, by struct name
+static STRUCTS: Lazy<HashMap<&'static str, StructDoc>> = Lazy::new(|| {
+    SCHEMA_SOURCES.iter().flat_map(|source| parse_structs(source)).collect()
+});
+
+/// Keys command line flags override, with the flags
+const CLI_OVERRIDES: &[(&str, &str)] = &[
+    ("output.dir", "build --outdir"),
+    ("output.public_url", "build/dev/preview --base"),
+    ("output.stats", "build --stats"),
+    ("output.provenance", "build --provenance"),
+    ("output.provenance_key", "build --provenance-key"),
+    ("output.critical_css", "build --critical-css"),
+    ("output.reporter", "build --reporter"),
+    ("build.jobs", "build --jobs"),
+    ("build.nice", "build --nice"),
+    ("build.max_warnings", "build --max-warnings"),
+    ("cache.enabled", "build --no-cache"),
+    ("cache.from", "build --cache-from"),
+    ("cache.to", "build --cache-to"),
+    ("dev.hmr.enabled", "dev --no-hmr"),
+    ("preview.port", "preview --port"),
+    ("export.routes", "export --routes"),
+    ("export.browser", "export --browser"),
+    ("export.wait", "export --wait"),
+];
+
+/// Environment variables that set a key, and whether they only apply while
+/// the key is unset
+const ENV_OVERRIDES: &[(&str, &str, bool)] = &[
+    ("dev.editor", "EDITOR", true),
+    ("cli.locale", "COMPONENT_LOCALE", false),
+    ("export.browser", "COMPONENT_BROWSER", false),
+];
+
+/// Doc comment and fields of a config struct
+#[derive(Debug, Clone, Default)]
+struct StructDoc {
+    doc: String,
+    fields: Vec<FieldDoc>,
+}
+
+/// A documented field of a config struct
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldDoc {
+    pub name: String,
+    
+    /// Rust type, as written in the struct
+    #[serde(rename = "type")]
+    pub ty: String,
+    
+    pub doc: String,
+}
+
+/// Where the effective value of a key comes from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueSource {
+    /// An environment variable
+    Env(String),
+    
+    /// component.toml
+    File,
+    
+    /// The built-in default
+    Default,
+}
+
+/// Everything `component config explain` reports about a key
+#[derive(Debug, Serialize)]
+pub struct KeyExplanation {
+    pub key: String,
+    
+    /// Documented field the key belongs to; a parent for keys inside tables
+    /// such as `entrypoints.main`
+    pub field: Option<String>,
+    
+    pub description: String,
+    
+    /// TOML type, as in `array of string`
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    
+    /// Default value; `None` when unset by default
+    pub default: Option<Value>,
+    
+    /// Effective value; `None` when unset
+    pub value: Option<Value>,
+    
+    pub source: ValueSource,
+    
+    /// Command line flags that override the value
+    pub overridden_by: Vec<String>,
+    
+    /// Keys of a section
+    pub keys: Vec<FieldDoc>,
+}
+
+/// Explain `key` of `config`, loaded from the TOML table `file`
+pub fn explain(config: &Config, file: &toml::Table, key: &str) -> Result<KeyExplanation> {
+    let key = key.trim_matches('.');
+    let (field, exact) = match find_field(key) {
+        Some((path, field, exact)) => (Some((path, field)), exact),
+        None => (None, false),
+    };
+    
+    let default = lookup(&serde_json::to_value(Config::default_config())?, key);
+    let mut value = lookup(&serde_json::to_value(config)?, key);
+    if !exact && !key.is_empty() && default.is_none() && value.is_none() {
+        anyhow::bail!(
+            "Unknown config key '{}' (`component config explain <section>` lists the keys of a section)",
+            key
+        );
+    }
+    
+    let mut source = if in_file(file, key) { ValueSource::File } else { ValueSource::Default };
+    for (env_key, var, when_unset) in ENV_OVERRIDES {
+        if *env_key != key || (*when_unset && source == ValueSource::File) {
+            continue;
+        }
+        if let Some(env) = std::env::var(var).ok().filter(|v| !v.is_empty()) {
+            value = Some(Value::String(env));
+            source = ValueSource::Env(var.to_string());
+        }
+    }
+    
+    let keys = field
+        .as_ref()
+        .filter(|_| exact)
+        .and_then(|(_, field)| struct_of(&field.ty))
+        .or_else(|| STRUCTS.get("Config").filter(|_| key.is_empty()))
+        .map(|doc| doc.fields.clone())
+        .unwrap_or_default();
+    let description = match &field {
+        Some((_, field)) => field.doc.clone(),
+        None if key.is_empty() => STRUCTS.get("Config").map(|doc| doc.doc.clone()).unwrap_or_default(),
+        None => String::new(),
+    };
+    
+    Ok(KeyExplanation {
+        key: key.to_string(),
+        field: field.as_ref().map(|(path, _)| path.clone()),
+        description,
+        ty: field.as_ref().filter(|_| exact).map(|(_, field)| toml_type(&field.ty)),
+        default,
+        value,
+        source,
+        overridden_by: CLI_OVERRIDES
+            .iter()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, flag)| format!("component {}", flag))
+            .collect(),
+        keys,
+    })
+}
+
+/// The deepest documented field along `key`, with its dotted path and
+/// whether it is the key itself
+fn find_field(key: &str) -> Option<(String, &'static FieldDoc, bool)> {
+    let mut fields = &STRUCTS.get("Config")?.fields;
+    let mut found = None;
+    for (depth, segment) in key.split('.').enumerate() {
+        let Some(field) = fields.iter().find(|field| field.name == segment) else {
+            return found.map(|(path, field)| (path, field, false));
+        };
+        let path = key.split('.').take(depth + 1).collect::<Vec<_>>().join(".");
+        found = Some((path, field));
+        match struct_of(&field.ty) {
+            Some(doc) => fields = &doc.fields,
+            None => {
+                let exact = depth + 1 == key.split('.').count();
+                return found.map(|(path, field)| (path, field, exact));
+            }
+        }
+    }
+    found.map(|(path, field)| (path, field, true))
+}
+
+/// The config struct a field type holds, as `OutputConfig` in `Option<OutputConfig>`
+fn struct_of(ty: &str) -> Option<&'static StructDoc> {
+    if ty.starts_with("Vec<") || ty.contains("Map<") {
+        return None;
+    }
+    ty.split(|c: char| !c.is_alphanumeric() && c != '_').find_map(|name| STRUCTS.get(name))
+}
+
+/// Describe a Rust field type in TOML terms
+fn toml_type(ty: &str) -> String {
+    if let Some(inner) = ty.strip_prefix("Option<").and_then(|t| t.strip_suffix('>')) {
+        return format!("{} (optional)", toml_type(inner));
+    }
+    if let Some(inner) = ty.strip_prefix("Vec<").and_then(|t| t.strip_suffix('>')) {
+        return format!("array of {}", toml_type(inner));
+    }
+    match ty {
+        "bool" => "boolean".to_string(),
+        "String" | "PathBuf" => "string".to_string(),
+        "u8" | "u16" | "u32" | "u64" | "usize" | "i32" | "i64" => "integer".to_string(),
+        "f32" | "f64" => "float".to_string(),
+        _ if ty.contains("Map<") || ty.ends_with("Table") || STRUCTS.contains_key(ty) => "table".to_string(),
+        _ => ty.to_string(),
+    }
+}
+
+/// Value at a dotted key; array elements are addressed by index
+fn lookup(value: &Value, key: &str) -> Option<Value> {
+    if key.is_empty() {
+        return Some(value.clone());
+    }
+    key.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+        .filter(|value| !value.is_null())
+        .cloned()
+}
+
+/// Whether component.toml sets `key`, or a shorthand for its table (as
+/// `hmr = false` for `dev.hmr.enabled`)
+fn in_file(file: &toml::Table, key: &str) -> bool {
+    let mut table = file;
+    for segment in key.split('.') {
+        match table.get(segment) {
+            Some(toml::Value::Table(inner)) => table = inner,
+            Some(_) => return true,
+            None => return false,
+        }
+    }
+    !key.is_empty()
+}
+
+/// Fields of every top-level `pub struct` in a source file, with the doc
+/// comments of the struct and its fields
+fn parse_structs(source: &'static str) -> Vec<(&'static str, StructDoc)> {
+    let mut structs = Vec::new();
+    let mut current: Option<(&str, StructDoc)> = None;
+    let mut doc: Vec<&str> = Vec::new();
+    let mut skipped = false;
+    
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if let Some(text) = trimmed.strip_prefix("///") {
+            doc.push(text.strip_prefix(' ').unwrap_or(text));
+            continue;
+        }
+        if trimmed.starts_with("#[") {
+            skipped |= trimmed.starts_with("#[serde(skip)]");
+            continue;
+        }
+        
+        if let Some(name) = line.strip_prefix("pub struct ").and_then(|rest| rest.strip_suffix(" {")) {
+            current = Some((name, StructDoc { doc: doc.join("\n"), fields: Vec::new() }));
+        } else if line == "}" {
+            structs.extend(current.take());
+        } else if let Some((_, parsed)) = current.as_mut() {
+            let field = trimmed.strip_prefix("pub ").and_then(|f| f.strip_suffix(',')).and_then(|f| f.split_once(": "));
+            if let (Some((name, ty)), false) = (field, skipped) {
+                parsed.fields.push(FieldDoc { name: name.to_string(), ty: ty.to_string(), doc: doc.join("\n") });
+            }
+        }
+        doc.clear();
+        skipped = false;
+    }
+    structs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_explain_config_keys() {
+        let source = "[project]\nname = \"app\"\n[output]\npublic_url = \"/app\"\n[dev]\nhmr = false\n";
+        let file: toml::Table = toml::from_str(source).unwrap();
+        let config = Config::parse(source, ".").unwrap();
+        
+        let public_url = explain(&config, &file, "output.public_url").unwrap();
+        assert_eq!(public_url.description, "Public URL prefix for assets");
+        assert_eq!(public_url.ty.as_deref(), Some("string"));
+        assert_eq!(public_url.default, Some(Value::from("/")));
+        assert_eq!(public_url.value, Some(Value::from("/app/")));
+        assert_eq!(public_url.source, ValueSource::File);
+        assert_eq!(public_url.overridden_by, vec!["component build/dev/preview --base"]);
+        
+        let hmr = explain(&config, &file, "dev.hmr.enabled").unwrap();
+        assert_eq!((hmr.value, hmr.source), (Some(Value::Bool(false)), ValueSource::File));
+        
+        let jobs = explain(&config, &file, "build.jobs").unwrap();
+        assert_eq!(jobs.ty.as_deref(), Some("integer (optional)"));
+        assert_eq!((jobs.value, jobs.source), (None, ValueSource::Default));
+        
+        let cache = explain(&config, &file, "cache").unwrap();
+        assert!(cache.keys.iter().any(|field| field.name == "dir"));
+        
+        let entry = explain(&config, &file, "entrypoints.main").unwrap();
+        assert_eq!(entry.field.as_deref(), Some("entrypoints"));
+        assert_eq!(entry.default, Some(Value::from("src/main.js")));
+        
+        assert!(explain(&config, &file, "output.nope").is_err());
+        
+        // Every key of every section is documented
+        for section in &STRUCTS["Config"].fields {
+            assert!(!section.doc.is_empty(), "{} is undocumented", section.name);
+            for field in struct_of(&section.ty).map_or(&[][..], |doc| &doc.fields) {
+                assert!(!field.doc.is_empty(), "{}.{} is undocumented", section.name, field.name);
+            }
+        }
+    }
+}
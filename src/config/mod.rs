@@ -2,7 +2,9 @@
 //!
 //! Parses and manages component.toml configuration files.
 
+mod explain;
 mod schema;
+mod state;
 
 use std::collections::HashMap;
 use std::fs;
@@ -11,7 +13,28 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+pub use explain::{explain, FieldDoc, KeyExplanation, ValueSource};
 pub use schema::*;
+pub use state::{LastBuild, ProjectState, STATE_DIR};
+
+/// A component.toml that can't be read, parsed, or validated
+///
+/// Commands exit with code 2 for these rather than 1, so scripts can tell a
+/// broken config from a failed build.
+#[derive(Debug)]
+pub struct ConfigError(pub anyhow::Error);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,12 +44,20 @@ pub struct Config {
     
     /// Entry points for bundling
     #[serde(default)]
-    pub entrypoints: HashMap<String, String>,
+    pub entrypoints: Entrypoints,
     
     /// Output configuration
     #[serde(default)]
     pub output: OutputConfig,
     
+    /// Build parallelism and priority
+    #[serde(default)]
+    pub build: BuildConfig,
+    
+    /// Command line output
+    #[serde(default)]
+    pub cli: CliConfig,
+    
     /// Feature flags
     #[serde(default)]
     pub features: FeaturesConfig,
@@ -39,15 +70,83 @@ pub struct Config {
     #[serde(default)]
     pub plugins: Vec<PluginConfig>,
     
+    /// Build cache settings
+    #[serde(default)]
+    pub cache: CacheConfig,
+    
+    /// Third-party license report settings
+    #[serde(default)]
+    pub licenses: LicensesConfig,
+    
+    /// Internationalization settings
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    
+    /// File-based routing settings
+    #[serde(default)]
+    pub routes: RoutesConfig,
+    
+    /// Preview server settings
+    #[serde(default)]
+    pub preview: PreviewConfig,
+    
+    /// Static export settings
+    #[serde(default)]
+    pub export: ExportConfig,
+    
+    /// Module resolution settings
+    #[serde(default)]
+    pub resolve: ResolveConfig,
+    
+    /// Stylesheet settings
+    #[serde(default)]
+    pub css: CssConfig,
+    
+    /// Web font settings
+    #[serde(default)]
+    pub fonts: FontsConfig,
+    
+    /// Source map settings
+    #[serde(default)]
+    pub sourcemap: SourcemapConfig,
+    
+    /// Library mode settings
+    #[serde(default)]
+    pub library: LibraryConfig,
+    
+    /// Output optimization settings
+    #[serde(default)]
+    pub optimization: OptimizationConfig,
+    
+    /// Content types by file extension, added to the built-in ones
+    #[serde(default)]
+    pub mime_types: HashMap<String, String>,
+    
+    /// Conditions whose entry tables were applied, mode first
+    #[serde(skip)]
+    pub conditions: Vec<String>,
+    
     /// Root directory (computed from config file location)
     #[serde(skip)]
     pub root: PathBuf,
 }
 
 impl Config {
-    /// Load configuration from a file path
+    /// Load configuration from a file path for a production build
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref();
+        Self::load_for(path, &[PRODUCTION.to_string()])
+    }
+    
+    /// Load configuration with the entry tables of `conditions` applied
+    ///
+    /// Later conditions override entries of earlier ones.
+    ///
+    /// Errors are [`ConfigError`]s.
+    pub fn load_for<P: AsRef<Path>>(path: P, conditions: &[String]) -> Result<Self> {
+        Self::read(path.as_ref(), conditions).map_err(|error| ConfigError(error).into())
+    }
+    
+    fn read(path: &Path, conditions: &[String]) -> Result<Self> {
         let canonical_path = if path.is_absolute() {
             path.to_path_buf()
         } else {
@@ -57,14 +156,16 @@ impl Config {
         let content = fs::read_to_string(&canonical_path)
             .with_context(|| format!("Failed to read config file: {}", canonical_path.display()))?;
         
-        let mut config: Config = toml::from_str(&content)
-            .with_context(|| "Failed to parse component.toml")?;
-        
         // Set root directory to the directory containing the config file
-        config.root = canonical_path
+        let root = canonical_path
             .parent()
             .map(|p| p.to_path_buf())
             .unwrap_or_else(|| PathBuf::from("."));
+        let mut config = Self::parse(&content, root)?;
+        for condition in conditions {
+            config.entrypoints.apply(condition);
+        }
+        config.conditions = conditions.to_vec();
         
         // Validate configuration
         config.validate()?;
@@ -72,22 +173,50 @@ impl Config {
         Ok(config)
     }
     
+    /// Parse configuration from TOML without touching the filesystem
+    ///
+    /// Unlike `load`, entrypoints are not checked for existence.
+    pub fn parse(content: &str, root: impl Into<PathBuf>) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)
+            .with_context(|| "Failed to parse component.toml")?;
+        config.root = root.into();
+        config.output.public_url = normalize_public_url(&config.output.public_url);
+        Ok(config)
+    }
+    
     /// Create a default configuration
     pub fn default_config() -> Self {
         Self {
             project: ProjectConfig {
                 name: "my-app".to_string(),
                 version: "0.1.0".to_string(),
+                required_version: None,
             },
             entrypoints: {
-                let mut map = HashMap::new();
-                map.insert("main".to_string(), "src/main.js".to_string());
-                map
+                let mut entrypoints = Entrypoints::default();
+                entrypoints.insert("main".to_string(), "src/main.js".to_string());
+                entrypoints
             },
             output: OutputConfig::default(),
+            build: BuildConfig::default(),
+            cli: CliConfig::default(),
             features: FeaturesConfig::default(),
             dev: DevConfig::default(),
             plugins: Vec::new(),
+            cache: CacheConfig::default(),
+            licenses: LicensesConfig::default(),
+            i18n: I18nConfig::default(),
+            routes: RoutesConfig::default(),
+            preview: PreviewConfig::default(),
+            export: ExportConfig::default(),
+            resolve: ResolveConfig::default(),
+            css: CssConfig::default(),
+            fonts: FontsConfig::default(),
+            sourcemap: SourcemapConfig::default(),
+            library: LibraryConfig::default(),
+            optimization: OptimizationConfig::default(),
+            mime_types: HashMap::new(),
+            conditions: Vec::new(),
             root: PathBuf::from("."),
         }
     }
@@ -99,8 +228,12 @@ impl Config {
             anyhow::bail!("At least one entrypoint must be specified in component.toml");
         }
         
-        // Validate entrypoint paths exist
-        for (name, path) in &self.entrypoints {
+        // Validate entrypoint paths exist; globs only need to parse
+        for (name, path) in self.entrypoints.iter() {
+            if crate::bundler::is_entry_glob(path) {
+                crate::bundler::compile_entry_glob(path)?;
+                continue;
+            }
             let full_path = self.root.join(path);
             if !full_path.exists() {
                 anyhow::bail!(
@@ -111,9 +244,30 @@ impl Config {
             }
         }
         
+        if let Some(range) = &self.project.required_version {
+            semver::VersionReq::parse(range)
+                .with_context(|| format!("project.required_version '{}' isn't a semver range", range))?;
+        }
+        
+        crate::bundler::fonts::parse_unicode_ranges(&self.fonts.unicode_ranges)?;
+        
+        #[cfg(feature = "native")]
+        crate::server::check_hmr_path(&self.dev.hmr.path)?;
+        
+        if let Some(locale) = &self.i18n.default_locale {
+            if !self.i18n.locales.contains(locale) {
+                anyhow::bail!("i18n default_locale '{}' is not in i18n.locales", locale);
+            }
+        }
+        
         Ok(())
     }
     
+    /// Serve the app under `base`, overriding `output.public_url`
+    pub fn set_base(&mut self, base: &str) {
+        self.output.public_url = normalize_public_url(base);
+    }
+    
     /// Get the absolute output directory path
     pub fn output_dir(&self) -> PathBuf {
         self.root.join(&self.output.dir)
@@ -124,11 +278,28 @@ impl Config {
         self.entrypoints.get(name).map(|p| self.root.join(p))
     }
     
-    /// Get all entrypoint paths
+    /// Get all entrypoint paths as configured
+    ///
+    /// Glob patterns are returned as written; see
+    /// [`Bundler::entrypoints`](crate::bundler::Bundler::entrypoints) for the expanded list.
     pub fn all_entrypoints(&self) -> Vec<(String, PathBuf)> {
         self.entrypoints
             .iter()
             .map(|(name, path)| (name.clone(), self.root.join(path)))
             .collect()
     }
+    
+    /// Whether `other` differs from this config only in `[[plugins]]`
+    ///
+    /// The dev server applies such changes without restarting.
+    pub fn differs_only_in_plugins(&self, other: &Config) -> bool {
+        let without_plugins = |config: &Config| {
+            let mut value = serde_json::to_value(config).ok()?;
+            value.as_object_mut()?.remove("plugins");
+            Some(value)
+        };
+        self.plugins != other.plugins
+            && self.root == other.root
+            && without_plugins(self).is_some_and(|value| Some(value) == without_plugins(other))
+    }
 }
@@ -1,7 +1,12 @@
 //! Configuration schema definitions
 
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Deref, DerefMut};
+
 use serde::{Deserialize, Serialize};
 
+use crate::bundler::{ManifestFormat, ModuleIds, ReporterKind};
+
 /// Project metadata configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
@@ -11,12 +16,104 @@ pub struct ProjectConfig {
     /// Project version
     #[serde(default = "default_version")]
     pub version: String,
+    
+    /// Semver range of `component` versions that may build the project;
+    /// others delegate to a matching binary in `.component/toolchains/` or
+    /// fail (see also `.component-version`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_version: Option<String>,
 }
 
 fn default_version() -> String {
     "0.1.0".to_string()
 }
 
+/// Entry tables applied by `component dev`
+pub const DEVELOPMENT: &str = "development";
+
+/// Entry tables applied by `component build` and other production commands
+pub const PRODUCTION: &str = "production";
+
+/// Entrypoints by name, plus tables that only apply under a condition
+///
+/// A condition is the mode (`development` or `production`) or a name given
+/// with `--condition`:
+///
+/// ```toml
+/// [entrypoints]
+/// main = "src/main.ts"
+///
+/// [entrypoints.development]
+/// instrument = "src/instrument.ts"
+///
+/// [entrypoints.production]
+/// sw = "src/sw.ts"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, EntryRule>", into = "BTreeMap<String, EntryRule>")]
+pub struct Entrypoints {
+    entries: HashMap<String, String>,
+    
+    /// Entries by condition
+    pub conditional: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl Entrypoints {
+    /// Merge the entries of `condition` in, overriding entries of the same name
+    pub fn apply(&mut self, condition: &str) {
+        if let Some(entries) = self.conditional.get(condition) {
+            self.entries.extend(entries.clone());
+        }
+    }
+}
+
+impl Deref for Entrypoints {
+    type Target = HashMap<String, String>;
+    
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl DerefMut for Entrypoints {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.entries
+    }
+}
+
+/// An entrypoint path, or the entries of a condition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EntryRule {
+    Path(String),
+    Condition(HashMap<String, String>),
+}
+
+impl From<BTreeMap<String, EntryRule>> for Entrypoints {
+    fn from(rules: BTreeMap<String, EntryRule>) -> Self {
+        let mut entrypoints = Self::default();
+        for (key, rule) in rules {
+            match rule {
+                EntryRule::Path(path) => {
+                    entrypoints.entries.insert(key, path);
+                }
+                EntryRule::Condition(entries) => {
+                    entrypoints.conditional.insert(key, entries);
+                }
+            }
+        }
+        entrypoints
+    }
+}
+
+impl From<Entrypoints> for BTreeMap<String, EntryRule> {
+    fn from(entrypoints: Entrypoints) -> Self {
+        let paths = entrypoints.entries.into_iter().map(|(name, path)| (name, EntryRule::Path(path)));
+        let conditions = entrypoints.conditional.into_iter().map(|(name, entries)| (name, EntryRule::Condition(entries)));
+        paths.chain(conditions).collect()
+    }
+}
+
 /// Output configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputConfig {
@@ -35,6 +132,53 @@ pub struct OutputConfig {
     /// Generate asset manifest
     #[serde(default = "default_true")]
     pub manifest: bool,
+    
+    /// Manifest format: `json`, `entrypoints`, `rails`, or `laravel`
+    #[serde(default)]
+    pub manifest_format: ManifestFormat,
+    
+    /// Emit stats.json with chunk sizes, modules, and packages, for `component diff`
+    #[serde(default)]
+    pub stats: bool,
+    
+    /// Emit provenance.json with input, config, and output hashes
+    #[serde(default)]
+    pub provenance: bool,
+    
+    /// Ed25519 private key (PKCS#8 PEM) used to sign provenance.json
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance_key: Option<String>,
+    
+    /// Inline the CSS of an HTML entry's initial modules and defer the full stylesheet
+    #[serde(default)]
+    pub critical_css: bool,
+    
+    /// Build progress reporter (`console` or `json`)
+    #[serde(default)]
+    pub reporter: ReporterKind,
+    
+    /// Times a failed async chunk load is retried, with a cache-busting query
+    #[serde(default = "default_chunk_retries")]
+    pub chunk_retries: u32,
+    
+    /// Reload the page once when an async chunk still fails to load, unless a
+    /// `component:chunk-error` listener calls `preventDefault()`
+    #[serde(default)]
+    pub reload_on_chunk_error: bool,
+    
+    /// What minification (on unless `--minify false`) applies to, by asset type
+    #[serde(default)]
+    pub minify: MinifyConfig,
+    
+    /// Module ids in bundles: `named`, `hashed`, or `numeric` (defaults to
+    /// `named` without minification and `hashed` with it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ids: Option<ModuleIds>,
+    
+    /// Emit scripts to `js/`, stylesheets to `css/`, and other assets to
+    /// `assets/` instead of the top of the output directory
+    #[serde(default)]
+    pub split_by_type: bool,
 }
 
 impl Default for OutputConfig {
@@ -44,10 +188,82 @@ impl Default for OutputConfig {
             public_url: default_public_url(),
             hash: true,
             manifest: true,
+            manifest_format: ManifestFormat::Json,
+            stats: false,
+            provenance: false,
+            provenance_key: None,
+            critical_css: false,
+            reporter: ReporterKind::Console,
+            chunk_retries: default_chunk_retries(),
+            reload_on_chunk_error: false,
+            minify: MinifyConfig::default(),
+            ids: None,
+            split_by_type: false,
         }
     }
 }
 
+impl OutputConfig {
+    /// URL path the app is served under, such as `/subapp/`
+    ///
+    /// This is the path part of `public_url`; a CDN or relative public URL
+    /// serves the app from `/`.
+    pub fn base_path(&self) -> String {
+        let url = self.public_url.as_str();
+        let path = match url.split_once("//") {
+            Some((scheme, rest)) if scheme.is_empty() || scheme.ends_with(':') => rest.find('/').map_or("/", |at| &rest[at..]),
+            _ if url.starts_with('.') => "/",
+            _ => url,
+        };
+        normalize_public_url(path)
+    }
+}
+
+/// Asset types minified by builds
+///
+/// ```toml
+/// [output.minify]
+/// html = true   # Pages, and prerendered routes of `component export`
+/// json = true   # JSON files copied for pages
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinifyConfig {
+    /// Minify scripts
+    #[serde(default = "default_true")]
+    pub js: bool,
+    
+    /// Strip comments and whitespace from HTML pages and double-quote attributes
+    #[serde(default)]
+    pub html: bool,
+    
+    /// Strip whitespace from JSON assets
+    #[serde(default)]
+    pub json: bool,
+}
+
+impl Default for MinifyConfig {
+    fn default() -> Self {
+        Self { js: true, html: false, json: false }
+    }
+}
+
+/// Give a public URL or base path its leading and trailing slashes
+///
+/// Absolute (`https://cdn.example.com/app`) and relative (`./`) URLs only
+/// gain the trailing slash.
+pub fn normalize_public_url(url: &str) -> String {
+    let url = url.trim();
+    let mut normalized = if url.contains("//") || url.starts_with('.') || url.starts_with('/') {
+        url.to_string()
+    } else {
+        format!("/{}", url)
+    };
+    if !normalized.ends_with('/') {
+        normalized.push('/');
+    }
+    normalized
+}
+
 fn default_output_dir() -> String {
     "dist".to_string()
 }
@@ -56,6 +272,10 @@ fn default_public_url() -> String {
     "/".to_string()
 }
 
+fn default_chunk_retries() -> u32 {
+    2
+}
+
 fn default_true() -> bool {
     true
 }
@@ -98,6 +318,10 @@ pub struct FeaturesConfig {
     /// Enable code splitting
     #[serde(default = "default_true")]
     pub code_splitting: bool,
+    
+    /// Compile-time helpers for CSS-in-JS libraries
+    #[serde(default)]
+    pub css_in_js: CssInJsConfig,
 }
 
 impl Default for FeaturesConfig {
@@ -112,6 +336,39 @@ impl Default for FeaturesConfig {
             tailwind: false,
             tree_shaking: true,
             code_splitting: true,
+            css_in_js: CssInJsConfig::default(),
+        }
+    }
+}
+
+/// What the Babel plugins of CSS-in-JS libraries would add at compile time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CssInJsConfig {
+    /// Configure `styled` components of files importing `styled-components`
+    #[serde(default)]
+    pub styled_components: bool,
+    
+    /// Label `css` and `styled` of files importing `@emotion/*`
+    #[serde(default)]
+    pub emotion: bool,
+    
+    /// Name components and class names after the variables holding them
+    #[serde(default = "default_true")]
+    pub display_names: bool,
+    
+    /// Give components ids derived from their file, so server-rendered class
+    /// names match the client's
+    #[serde(default = "default_true")]
+    pub ssr: bool,
+}
+
+impl Default for CssInJsConfig {
+    fn default() -> Self {
+        Self {
+            styled_components: false,
+            emotion: false,
+            display_names: true,
+            ssr: true,
         }
     }
 }
@@ -143,13 +400,40 @@ pub struct DevConfig {
     #[serde(default)]
     pub open: bool,
     
-    /// Enable hot module replacement
-    #[serde(default = "default_true")]
-    pub hmr: bool,
+    /// Hot module replacement settings (`hmr = false` or a `[dev.hmr]` table)
+    #[serde(default)]
+    pub hmr: HmrConfig,
     
     /// Proxy configuration for API requests
     #[serde(default)]
     pub proxy: Vec<ProxyConfig>,
+    
+    /// Directory of mock API routes served before the proxy (e.g. `mocks/`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mocks: Option<String>,
+    
+    /// Extra response headers
+    #[serde(default)]
+    pub headers: HeadersConfig,
+    
+    /// Compress responses (gzip or brotli, per `Accept-Encoding`)
+    #[serde(default)]
+    pub compression: bool,
+    
+    /// Editor command used by the error overlay's open-in-editor links
+    /// (falls back to $EDITOR when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    
+    /// Entrypoint the generated index.html loads when there are several
+    /// (without it, a page listing every entry is served)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_entry: Option<String>,
+    
+    /// HTML template for the generated index.html; the entry script is
+    /// added before `</body>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_template: Option<String>,
 }
 
 impl Default for DevConfig {
@@ -158,8 +442,14 @@ impl Default for DevConfig {
             port: default_port(),
             host: default_host(),
             open: false,
-            hmr: true,
+            hmr: HmrConfig::default(),
             proxy: Vec::new(),
+            mocks: None,
+            headers: HeadersConfig::default(),
+            compression: false,
+            editor: None,
+            default_entry: None,
+            index_template: None,
         }
     }
 }
@@ -172,6 +462,529 @@ fn default_host() -> String {
     "localhost".to_string()
 }
 
+/// Preview server settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewConfig {
+    /// Port to serve the build output on
+    #[serde(default = "default_preview_port")]
+    pub port: u16,
+    
+    /// Host to bind to
+    #[serde(default = "default_host")]
+    pub host: String,
+    
+    /// Extra response headers
+    #[serde(default)]
+    pub headers: HeadersConfig,
+    
+    /// Compress responses (gzip or brotli, per `Accept-Encoding`)
+    #[serde(default = "default_true")]
+    pub compression: bool,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            port: default_preview_port(),
+            host: default_host(),
+            headers: HeadersConfig::default(),
+            compression: true,
+        }
+    }
+}
+
+fn default_preview_port() -> u16 {
+    4173
+}
+
+/// Extra response headers for a server
+///
+/// String values apply to every response. Tables are keyed by a path glob
+/// and apply to matching request paths, overriding global values:
+///
+/// ```toml
+/// [dev.headers]
+/// Cross-Origin-Opener-Policy = "same-origin"
+/// Cross-Origin-Embedder-Policy = "require-corp"
+///
+/// [dev.headers."/api/**"]
+/// Access-Control-Allow-Origin = "*"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HeadersConfig(pub BTreeMap<String, HeaderRule>);
+
+/// A global header value, or the headers for a path glob
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HeaderRule {
+    Value(String),
+    Path(BTreeMap<String, String>),
+}
+
+/// HMR connection settings
+///
+/// `host`, `port` and `protocol` describe where the browser should connect,
+/// which differs from the bind address behind reverse proxies or port remaps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "HmrSetting")]
+pub struct HmrConfig {
+    /// Enable hot module replacement
+    pub enabled: bool,
+    
+    /// WebSocket protocol (`ws` or `wss`); defaults to match the page
+    pub protocol: Option<String>,
+    
+    /// Host the client connects to; defaults to the page host
+    pub host: Option<String>,
+    
+    /// Port the client connects to; defaults to the page port
+    pub port: Option<u16>,
+    
+    /// WebSocket endpoint path
+    pub path: String,
+}
+
+impl Default for HmrConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            protocol: None,
+            host: None,
+            port: None,
+            path: default_hmr_path(),
+        }
+    }
+}
+
+fn default_hmr_path() -> String {
+    "/__component_hmr".to_string()
+}
+
+/// Accepts either `hmr = true|false` or a `[dev.hmr]` table
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HmrSetting {
+    Enabled(bool),
+    Table {
+        #[serde(default = "default_true")]
+        enabled: bool,
+        #[serde(default)]
+        protocol: Option<String>,
+        #[serde(default)]
+        host: Option<String>,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default = "default_hmr_path")]
+        path: String,
+    },
+}
+
+impl From<HmrSetting> for HmrConfig {
+    fn from(setting: HmrSetting) -> Self {
+        match setting {
+            HmrSetting::Enabled(enabled) => Self {
+                enabled,
+                ..Self::default()
+            },
+            HmrSetting::Table { enabled, protocol, host, port, path } => Self {
+                enabled,
+                protocol,
+                host,
+                port,
+                path,
+            },
+        }
+    }
+}
+
+/// Build cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Persist transform results between builds
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    
+    /// Local cache directory, relative to the project root
+    #[serde(default = "default_cache_dir")]
+    pub dir: String,
+    
+    /// Remote caches to read from (`https://...`, `s3://bucket/prefix`, or a path)
+    #[serde(default)]
+    pub from: Vec<String>,
+    
+    /// Remote caches to write new entries to
+    #[serde(default)]
+    pub to: Vec<String>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: default_cache_dir(),
+            from: Vec::new(),
+            to: Vec::new(),
+        }
+    }
+}
+
+fn default_cache_dir() -> String {
+    ".component/cache".to_string()
+}
+
+/// Third-party license report configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LicensesConfig {
+    /// Write licenses.json and licenses.txt to the output directory on build
+    #[serde(default)]
+    pub report: bool,
+    
+    /// SPDX license ids that fail the build (`GPL-*` matches any suffix)
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Internationalization configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct I18nConfig {
+    /// Supported locales; i18n is disabled when empty
+    #[serde(default)]
+    pub locales: Vec<String>,
+    
+    /// Locale used for missing messages (defaults to the first locale)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_locale: Option<String>,
+    
+    /// Message catalog path relative to the project root; `{locale}` is replaced
+    #[serde(default = "default_messages_path")]
+    pub messages: String,
+    
+    /// Message functions whose first argument is a message key
+    #[serde(default = "default_message_functions")]
+    pub functions: Vec<String>,
+    
+    /// Inline the default locale's messages into entry bundles
+    #[serde(default = "default_true")]
+    pub inline_default: bool,
+}
+
+impl I18nConfig {
+    /// Whether any locales are configured
+    pub fn is_enabled(&self) -> bool {
+        !self.locales.is_empty()
+    }
+    
+    /// The default locale
+    pub fn default_locale(&self) -> &str {
+        self.default_locale
+            .as_deref()
+            .or_else(|| self.locales.first().map(String::as_str))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            locales: Vec::new(),
+            default_locale: None,
+            messages: default_messages_path(),
+            functions: default_message_functions(),
+            inline_default: true,
+        }
+    }
+}
+
+fn default_messages_path() -> String {
+    "locales/{locale}.json".to_string()
+}
+
+fn default_message_functions() -> Vec<String> {
+    vec!["t".to_string()]
+}
+
+/// File-based routing configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutesConfig {
+    /// Pages directory relative to the project root (e.g. `src/pages`);
+    /// enables the `virtual:routes` module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+}
+
+/// Static export configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// URL paths to prerender, in addition to static routes from `routes.dir`
+    #[serde(default = "default_export_routes")]
+    pub routes: Vec<String>,
+    
+    /// HTML entrypoint to render routes with (defaults to `index`, or the only page)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    
+    /// Chromium-based browser executable (searched on PATH by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<String>,
+    
+    /// Extra browser arguments (e.g. `--no-sandbox` in containers)
+    #[serde(default)]
+    pub browser_args: Vec<String>,
+    
+    /// Time in milliseconds the page may run before its DOM is captured
+    #[serde(default = "default_export_wait")]
+    pub wait: u64,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            routes: default_export_routes(),
+            page: None,
+            browser: None,
+            browser_args: Vec::new(),
+            wait: default_export_wait(),
+        }
+    }
+}
+
+fn default_export_routes() -> Vec<String> {
+    vec!["/".to_string()]
+}
+
+fn default_export_wait() -> u64 {
+    5000
+}
+
+/// Module resolution configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveConfig {
+    /// Export conditions that point workspace packages at their source, in
+    /// order of preference; the `source` condition also enables the
+    /// `package.json` `source` field
+    #[serde(default = "default_source_conditions")]
+    pub source_conditions: Vec<String>,
+    
+    /// Directories packages are installed in, searched in order: names are
+    /// looked for above the importer, nearest first, and paths containing a
+    /// `/` are relative to the root
+    #[serde(default = "default_module_dirs")]
+    pub module_dirs: Vec<String>,
+    
+    /// Packages left out of the bundle and loaded at runtime instead; a
+    /// name also covers its subpaths, so `react` covers `react/jsx-runtime`
+    #[serde(default)]
+    pub external: Vec<String>,
+}
+
+impl Default for ResolveConfig {
+    fn default() -> Self {
+        Self {
+            source_conditions: default_source_conditions(),
+            module_dirs: default_module_dirs(),
+            external: Vec::new(),
+        }
+    }
+}
+
+fn default_module_dirs() -> Vec<String> {
+    vec!["node_modules".to_string()]
+}
+
+fn default_source_conditions() -> Vec<String> {
+    vec!["source".to_string(), "development".to_string()]
+}
+
+/// Source map settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourcemapConfig {
+    /// Embed the original sources, so devtools can show them without
+    /// fetching the files
+    #[serde(default = "default_true")]
+    pub sources_content: bool,
+    
+    /// URL the `sources` of every map are relative to; by default they are
+    /// absolute paths under the base path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_root: Option<String>,
+}
+
+impl Default for SourcemapConfig {
+    fn default() -> Self {
+        Self {
+            sources_content: true,
+            source_root: None,
+        }
+    }
+}
+
+/// Stylesheet settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CssConfig {
+    /// Directories, relative to the project root, searched for `@import`,
+    /// `@use` and `@forward` specifiers that aren't relative to the importer
+    #[serde(default)]
+    pub include_paths: Vec<String>,
+}
+
+/// Web font settings
+///
+/// ```toml
+/// [fonts]
+/// subset = true
+/// unicode_ranges = ["U+0000-00FF", "U+0100-017F"]   # Latin and Latin Extended-A
+/// preload = true
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontsConfig {
+    /// Copy the TrueType and OpenType fonts of `@font-face` rules with only
+    /// the glyphs of `unicode_ranges`, as WOFF2
+    #[serde(default)]
+    pub subset: bool,
+    
+    /// Characters subset fonts keep, as in CSS `unicode-range`; Latin by
+    /// default
+    #[serde(default = "default_unicode_ranges")]
+    pub unicode_ranges: Vec<String>,
+    
+    /// Preload the fonts the CSS loaded with each page uses, with
+    /// `<link rel="preload" as="font">`
+    #[serde(default)]
+    pub preload: bool,
+}
+
+impl Default for FontsConfig {
+    fn default() -> Self {
+        Self {
+            subset: false,
+            unicode_ranges: default_unicode_ranges(),
+            preload: false,
+        }
+    }
+}
+
+fn default_unicode_ranges() -> Vec<String> {
+    [
+        "U+0000-00FF", "U+0131", "U+0152-0153", "U+02BB-02BC", "U+02C6", "U+02DA", "U+02DC", "U+0304", "U+0308",
+        "U+0329", "U+2000-206F", "U+20AC", "U+2122", "U+2191", "U+2193", "U+2212", "U+2215", "U+FEFF", "U+FFFD",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+/// Output optimization settings
+///
+/// ```toml
+/// [optimization.side_effects]
+/// "node_modules/some-lib/**" = false      # Pure, despite its package.json
+/// "src/polyfills/**" = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OptimizationConfig {
+    /// Whether modules matching a glob (relative to the project root) have
+    /// side effects, overriding package.json `sideEffects`; side-effect-only
+    /// imports of modules without them are left out with `features.tree_shaking`
+    #[serde(default)]
+    pub side_effects: BTreeMap<String, bool>,
+}
+
+/// Library mode: builds published as a package instead of served
+///
+/// ```toml
+/// [library]
+/// enabled = true
+/// package_json = true
+/// dts = true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryConfig {
+    /// Build a library: output files keep their unhashed names
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Keep package.json's `exports`, `main`, `module` and `types` fields
+    /// pointing at the emitted entries after each build
+    #[serde(default)]
+    pub package_json: bool,
+    
+    /// Entry exported as the package root (defaults to the only entry, or
+    /// `index`, or `main`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main: Option<String>,
+    
+    /// Declaration file of the root entry, relative to the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub types: Option<String>,
+    
+    /// Roll the root entry's declarations, emitted by tsc, up into
+    /// `<output.dir>/<entry>.d.ts`
+    #[serde(default)]
+    pub dts: bool,
+    
+    /// Load each entry's ES module and CommonJS builds with Node after the
+    /// build, failing it when they don't export the same names
+    #[serde(default)]
+    pub check_parity: bool,
+}
+
+/// Build resource limits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildConfig {
+    /// Modules transformed and files written at once (defaults to the
+    /// number of CPUs, or half of them with `nice`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    
+    /// Run at low OS priority so builds don't compete with interactive work
+    #[serde(default)]
+    pub nice: bool,
+    
+    /// Run core transforms in worker processes, so a transform that crashes
+    /// or runs out of memory fails only its module
+    #[serde(default)]
+    pub isolate_transforms: bool,
+    
+    /// Seconds a plugin hook may run before it fails the module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_timeout: Option<u64>,
+    
+    /// Most warnings a build may report before it fails with exit code 3
+    /// (unset: no limit, or none with `--ci`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_warnings: Option<usize>,
+}
+
+impl BuildConfig {
+    /// Number of parallel jobs builds use
+    pub fn jobs(&self) -> usize {
+        if let Some(jobs) = self.jobs {
+            return jobs.max(1);
+        }
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if self.nice {
+            (cpus / 2).max(1)
+        } else {
+            cpus
+        }
+    }
+    
+    /// Longest a plugin hook may run, if limited
+    pub fn plugin_timeout(&self) -> Option<std::time::Duration> {
+        self.plugin_timeout.map(std::time::Duration::from_secs)
+    }
+}
+
+/// Command line output settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CliConfig {
+    /// Language of the messages `component` prints, such as `de` (defaults
+    /// to the system locale; `COMPONENT_LOCALE` overrides it). Messages
+    /// without a translation are printed in English
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
 /// Proxy configuration for dev server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyConfig {
@@ -187,7 +1000,7 @@ pub struct ProxyConfig {
 }
 
 /// Plugin configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PluginConfig {
     /// Plugin name/identifier
     pub name: String,
@@ -196,3 +1009,64 @@ pub struct PluginConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub options: Option<toml::Table>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_hmr_config_accepts_bool_or_table() {
+        let dev: DevConfig = toml::from_str("hmr = false").unwrap();
+        assert!(!dev.hmr.enabled);
+        assert_eq!(dev.hmr.path, "/__component_hmr");
+        
+        let dev: DevConfig = toml::from_str(
+            "[hmr]\nprotocol = \"wss\"\nport = 443\npath = \"/hmr\"",
+        )
+        .unwrap();
+        assert!(dev.hmr.enabled);
+        assert_eq!(dev.hmr.protocol.as_deref(), Some("wss"));
+        assert_eq!(dev.hmr.port, Some(443));
+        assert_eq!(dev.hmr.path, "/hmr");
+    }
+    
+    #[test]
+    fn test_dev_index_settings() {
+        let dev: DevConfig = toml::from_str("default_entry = \"admin\"\nindex_template = \"dev.html\"").unwrap();
+        assert_eq!(dev.default_entry.as_deref(), Some("admin"));
+        assert_eq!(dev.index_template.as_deref(), Some("dev.html"));
+        
+        // Unset, they're left out of the config `/__component/config` shows
+        let dev = DevConfig::default();
+        assert_eq!(dev.default_entry, None);
+        let json = serde_json::to_value(&dev).unwrap();
+        assert!(json.get("default_entry").is_none() && json.get("index_template").is_none());
+    }
+    
+    #[test]
+    fn test_chunk_error_settings() {
+        let output = OutputConfig::default();
+        assert_eq!(output.chunk_retries, 2);
+        assert!(!output.reload_on_chunk_error);
+        
+        let output: OutputConfig = toml::from_str("").unwrap();
+        assert_eq!(output.chunk_retries, 2);
+        let output: OutputConfig = toml::from_str("chunk_retries = 0\nreload_on_chunk_error = true").unwrap();
+        assert_eq!(output.chunk_retries, 0);
+        assert!(output.reload_on_chunk_error);
+        assert!(toml::from_str::<OutputConfig>("chunk_retries = -1").is_err());
+    }
+    
+    #[test]
+    fn test_compression_defaults() {
+        // Off in development, where responses come from localhost, and on
+        // for the preview of a production build
+        assert!(!DevConfig::default().compression);
+        assert!(!toml::from_str::<DevConfig>("").unwrap().compression);
+        assert!(PreviewConfig::default().compression);
+        assert!(toml::from_str::<PreviewConfig>("").unwrap().compression);
+        
+        assert!(toml::from_str::<DevConfig>("compression = true").unwrap().compression);
+        assert!(!toml::from_str::<PreviewConfig>("compression = false").unwrap().compression);
+    }
+}
@@ -0,0 +1,131 @@
+//! Project state directory
+//!
+//! Component keeps what it generates for a project, but that isn't build
+//! output, in `.component/` at the project root: the transform cache, the
+//! dev server's module graph and API token, the advisory cache of
+//! `component audit`, and a record of the last build. Everything in it can be
+//! deleted; it ignores itself for git.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Name of the state directory in the project root
+pub const STATE_DIR: &str = ".component";
+
+/// Record of the last build, relative to the state directory
+const LAST_BUILD_FILE: &str = "last-build.json";
+
+/// The `.component/` directory of a project
+#[derive(Debug, Clone)]
+pub struct ProjectState {
+    dir: PathBuf,
+}
+
+/// What the last `component build` produced
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LastBuild {
+    /// When the build finished, in seconds since the Unix epoch
+    pub finished_at: u64,
+    
+    /// How long the build took, in milliseconds
+    pub duration_ms: u64,
+    
+    /// Mode the build ran in, as in `production`
+    pub mode: String,
+    
+    /// Output directory, relative to the project root
+    pub output_dir: String,
+    
+    /// Output files with their sizes in bytes
+    pub files: Vec<(String, usize)>,
+}
+
+impl ProjectState {
+    pub fn new(root: &Path) -> Self {
+        Self { dir: root.join(STATE_DIR) }
+    }
+    
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    
+    /// Path of a file or directory inside the state directory
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+    
+    /// Create the state directory if needed, with a `.gitignore` ignoring it
+    pub fn ensure(&self) -> Result<&Path> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let gitignore = self.dir.join(".gitignore");
+        if !gitignore.exists() {
+            fs::write(&gitignore, "# Generated by Component\n*\n")
+                .with_context(|| format!("Failed to write {}", gitignore.display()))?;
+        }
+        Ok(&self.dir)
+    }
+    
+    /// Write a file inside the state directory, creating it if needed
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> Result<PathBuf> {
+        self.ensure()?;
+        let path = self.path(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+    
+    /// Record a finished build
+    pub fn write_last_build(&self, build: &LastBuild) -> Result<()> {
+        self.write(LAST_BUILD_FILE, serde_json::to_string_pretty(build)?)?;
+        Ok(())
+    }
+    
+    /// The last recorded build, if any
+    pub fn last_build(&self) -> Option<LastBuild> {
+        let json = fs::read_to_string(self.path(LAST_BUILD_FILE)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+}
+
+impl LastBuild {
+    /// Seconds since the Unix epoch
+    pub fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+    
+    /// Total size of the output files in bytes
+    pub fn total_size(&self) -> usize {
+        self.files.iter().map(|(_, size)| size).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_project_state() {
+        let root = tempfile::tempdir().unwrap();
+        let state = ProjectState::new(root.path());
+        assert_eq!(state.last_build(), None);
+        
+        let build = LastBuild {
+            finished_at: 1_700_000_000,
+            duration_ms: 120,
+            mode: "production".to_string(),
+            output_dir: "dist".to_string(),
+            files: vec![("main.js".to_string(), 300), ("main.css".to_string(), 20)],
+        };
+        state.write_last_build(&build).unwrap();
+        assert_eq!(state.last_build(), Some(build.clone()));
+        assert_eq!(build.total_size(), 320);
+        assert_eq!(fs::read_to_string(root.path().join(".component/.gitignore")).unwrap(), "# Generated by Component\n*\n");
+    }
+}
@@ -0,0 +1,200 @@
+//! Static export
+//!
+//! Prerenders routes of a built app to static HTML. The build output is
+//! served locally, with the app's page as the fallback for every path that
+//! isn't a file, and a headless Chromium loads each route and dumps the
+//! rendered DOM. Asset references in the dumped pages are the hashed bundle
+//! URLs the page was built with.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+use crate::bundler::Route;
+use crate::server::{static_router, MimeTypes};
+
+/// Browsers tried, in order, when none is configured
+const BROWSERS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "microsoft-edge",
+];
+
+/// Locate a Chromium-based browser
+///
+/// A configured name without a path separator is looked up on `PATH`.
+pub fn find_browser(configured: Option<&str>) -> Result<PathBuf> {
+    if let Some(browser) = configured {
+        if browser.contains('/') || browser.contains('\\') {
+            return Ok(PathBuf::from(browser));
+        }
+        return search_path(browser)
+            .with_context(|| format!("Browser '{}' not found on PATH", browser));
+    }
+    
+    BROWSERS
+        .iter()
+        .find_map(|name| search_path(name))
+        .or_else(|| {
+            let mac = Path::new("/Applications/Google Chrome.app/Contents/MacOS/Google Chrome");
+            mac.is_file().then(|| mac.to_path_buf())
+        })
+        .context("No Chromium-based browser found; set export.browser or COMPONENT_BROWSER")
+}
+
+fn search_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        [dir.join(name), dir.join(format!("{}.exe", name))]
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Routes to export: configured paths, then static routes from the pages directory
+///
+/// Dynamic and catch-all routes can't be rendered without concrete
+/// parameters, so only their configured instances are exported.
+pub fn export_routes(configured: &[String], discovered: &[Route]) -> Vec<String> {
+    let mut routes: Vec<String> = Vec::new();
+    let candidates = configured
+        .iter()
+        .map(String::as_str)
+        .chain(discovered.iter().map(|r| r.path.as_str()).filter(|p| !p.contains([':', '*'])));
+    
+    for route in candidates {
+        let route = format!("/{}", route.trim_matches('/'));
+        if !routes.contains(&route) {
+            routes.push(route);
+        }
+    }
+    routes
+}
+
+/// Output file for a route: `/` is `index.html`, `/about` is `about/index.html`
+pub fn route_file(route: &str) -> PathBuf {
+    let mut file = PathBuf::new();
+    for segment in route.split('/').filter(|s| !s.is_empty() && *s != "." && *s != "..") {
+        file.push(segment);
+    }
+    file.join("index.html")
+}
+
+/// Dumped DOMs lack the doctype; put it back so pages render in standards mode
+pub fn with_doctype(html: &str) -> String {
+    if html.trim_start().to_ascii_lowercase().starts_with("<!doctype") {
+        html.to_string()
+    } else {
+        format!("<!DOCTYPE html>\n{}", html)
+    }
+}
+
+/// Headless browser that renders pages
+pub struct Browser {
+    path: PathBuf,
+    args: Vec<String>,
+    wait: Duration,
+}
+
+impl Browser {
+    pub fn new(path: PathBuf, args: Vec<String>, wait: Duration) -> Self {
+        Self { path, args, wait }
+    }
+    
+    /// Load a URL and return the DOM once the page settled or `wait` elapsed
+    pub async fn render(&self, url: &str) -> Result<String> {
+        let mut command = Command::new(&self.path);
+        command
+            .arg("--headless=new")
+            .arg("--disable-gpu")
+            .arg("--hide-scrollbars")
+            .arg(format!("--virtual-time-budget={}", self.wait.as_millis()))
+            .arg("--dump-dom")
+            .args(&self.args)
+            .arg(url)
+            .kill_on_drop(true);
+        
+        let limit = self.wait + Duration::from_secs(30);
+        let output = tokio::time::timeout(limit, command.output())
+            .await
+            .with_context(|| format!("Browser did not finish within {}s", limit.as_secs()))?
+            .with_context(|| format!("Failed to run {}", self.path.display()))?;
+        
+        if !output.status.success() {
+            anyhow::bail!(
+                "Browser exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        
+        let html = String::from_utf8(output.stdout).context("Browser output is not UTF-8")?;
+        if html.trim().is_empty() {
+            anyhow::bail!("Browser returned an empty document");
+        }
+        Ok(with_doctype(&html))
+    }
+}
+
+/// Local server for a build's output directory
+pub struct StaticServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl StaticServer {
+    /// Serve `dir` on a free loopback port
+    ///
+    /// `base` is the public URL prefix assets are referenced under, and
+    /// `fallback` is served for every path that isn't a file.
+    pub async fn start(dir: PathBuf, base: &str, fallback: String, mime: MimeTypes) -> Result<Self> {
+        let app = static_router(dir, base, Some(fallback), mime);
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let handle = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        
+        Ok(Self { addr, handle })
+    }
+    
+    /// Absolute URL of a route
+    pub fn url(&self, route: &str) -> String {
+        format!("http://{}{}", self.addr, route)
+    }
+}
+
+impl Drop for StaticServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_export_routes() {
+        let discovered = [
+            Route { path: "/".to_string(), file: "index.jsx".to_string() },
+            Route { path: "/about".to_string(), file: "about.jsx".to_string() },
+            Route { path: "/blog/:slug".to_string(), file: "blog/[slug].jsx".to_string() },
+            Route { path: "/*".to_string(), file: "[...all].jsx".to_string() },
+        ];
+        let configured = ["/".to_string(), "blog/hello/".to_string()];
+        
+        assert_eq!(export_routes(&configured, &discovered), ["/", "/blog/hello", "/about"]);
+        assert_eq!(route_file("/"), PathBuf::from("index.html"));
+        assert_eq!(route_file("/blog/hello"), PathBuf::from("blog/hello/index.html"));
+        assert_eq!(route_file("/../x"), PathBuf::from("x/index.html"));
+    }
+}
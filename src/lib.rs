@@ -2,15 +2,35 @@
 //!
 //! Core functionality for the Component build tool.
 
+#[cfg(feature = "native")]
 pub mod cli;
 pub mod config;
 pub mod bundler;
 pub mod resolver;
 pub mod transform;
+#[cfg(feature = "native")]
 pub mod server;
+#[cfg(feature = "native")]
+pub mod lsp;
+#[cfg(feature = "native")]
+pub mod audit;
+#[cfg(feature = "native")]
+pub mod export;
+#[cfg(feature = "native")]
+pub mod update;
+pub mod messages;
 pub mod plugins;
 pub mod utils;
+pub mod vfs;
 
+#[cfg(all(feature = "native", any(test, feature = "test-support")))]
+pub mod testing;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "native")]
 pub use cli::Cli;
 pub use config::Config;
 pub use bundler::Bundler;
+pub use resolver::{Resolution, Resolver};
@@ -0,0 +1,403 @@
+//! Editor integration over stdio
+//!
+//! `component lsp` speaks JSON-RPC 2.0 with LSP base-protocol framing
+//! (`Content-Length` headers) and answers bundler-aware queries:
+//! - `component/resolve` `{ specifier, from }` - resolve an import
+//! - `component/chunks` `{ path }` - chunks a module lands in
+//! - `component/diagnostics` `{ path }` - import and build diagnostics
+//!
+//! Paths may be filesystem paths (relative to the project root) or `file://`
+//! URIs. Saving a file (`textDocument/didSave`) refreshes the module graph
+//! and publishes diagnostics for it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, error};
+use url::Url;
+
+use crate::bundler::{BuildOptions, Bundler, Dependency};
+use crate::config::Config;
+use crate::utils::simplify_path;
+
+/// JSON-RPC error: unknown method
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error: malformed params
+const INVALID_PARAMS: i64 = -32602;
+
+/// JSON-RPC error: request failed
+const REQUEST_FAILED: i64 = -32803;
+
+/// An incoming request or notification
+#[derive(Debug, Deserialize)]
+struct Message {
+    /// Absent for notifications
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// An LSP diagnostic
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    /// 1 = error, 2 = warning, 3 = information, 4 = hint
+    pub severity: u8,
+    pub source: &'static str,
+    pub message: String,
+}
+
+/// A zero-based range within a document
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A zero-based line and UTF-16 column
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Deserialize)]
+struct ResolveParams {
+    specifier: String,
+    from: String,
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: String,
+}
+
+/// Long-running editor integration server
+pub struct LspServer {
+    /// Project configuration
+    config: Config,
+    
+    /// Bundler holding the module graph
+    bundler: Bundler,
+    
+    /// Error from the most recent graph build or refresh
+    build_error: Option<String>,
+    
+    /// Set once `exit` is received
+    exited: bool,
+}
+
+impl LspServer {
+    /// Create a server and build the initial module graph
+    ///
+    /// Build errors are reported as diagnostics rather than failing startup.
+    pub async fn new(config: Config) -> Result<Self> {
+        let bundler = Bundler::new(config.clone(), BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "esnext".to_string(),
+            inspect_transforms: false,
+        })?;
+        
+        let build_error = bundler.build_graph().await.err().map(|e| format!("{:#}", e));
+        
+        Ok(Self {
+            config,
+            bundler,
+            build_error,
+            exited: false,
+        })
+    }
+    
+    /// Serve requests until `exit` or end of input
+    pub async fn run<R, W>(&mut self, mut reader: R, mut writer: W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(message) = read_message(&mut reader).await? {
+            for reply in self.handle(message).await {
+                write_message(&mut writer, &reply).await?;
+            }
+            if self.exited {
+                break;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Handle one message, returning the response and any notifications
+    pub async fn handle(&mut self, message: Value) -> Vec<Value> {
+        let message: Message = match serde_json::from_value(message) {
+            Ok(message) => message,
+            Err(e) => {
+                return vec![error_response(Value::Null, INVALID_PARAMS, &e.to_string())];
+            }
+        };
+        debug!("lsp: {}", message.method);
+        
+        let result = match message.method.as_str() {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": false, "save": true },
+                },
+                "serverInfo": {
+                    "name": "component",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "exit" => {
+                self.exited = true;
+                return Vec::new();
+            }
+            "textDocument/didSave" => return self.did_save(&message.params).await,
+            "component/resolve" => params(message.params).and_then(|p| self.resolve(p)),
+            "component/chunks" => params(message.params).and_then(|p| self.chunks(p)),
+            "component/diagnostics" => params(message.params).map(|p: PathParams| {
+                json!({ "diagnostics": self.diagnostics(&self.to_path(&p.path)) })
+            }),
+            method => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+        
+        // Notifications never get a response
+        let Some(id) = message.id else {
+            return Vec::new();
+        };
+        
+        match result {
+            Ok(result) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            Err((code, message)) => vec![error_response(id, code, &message)],
+        }
+    }
+    
+    fn resolve(&self, params: ResolveParams) -> Result<Value, (i64, String)> {
+        let from = self.to_path(&params.from);
+        let resolved = self
+            .bundler
+            .resolve(&params.specifier, &from)
+            .map_err(|e| (REQUEST_FAILED, format!("{:#}", e)))?;
+        
+        Ok(match resolved {
+            Some(path) => {
+                let path = path.canonicalize().map(simplify_path).unwrap_or(path);
+                json!({ "path": path, "uri": to_uri(&path) })
+            }
+            None => Value::Null,
+        })
+    }
+    
+    fn chunks(&self, params: PathParams) -> Result<Value, (i64, String)> {
+        let path = self.to_path(&params.path);
+        let chunks = self.bundler.chunks().map_err(|e| (REQUEST_FAILED, format!("{:#}", e)))?;
+        
+        let graph = self.bundler.graph();
+        let graph = graph.read();
+        let mut names: Vec<String> = match graph.get_module_id(&path) {
+            Some(id) => chunks
+                .into_iter()
+                .filter(|chunk| chunk.module_ids.contains(&id))
+                .map(|chunk| chunk.name)
+                .collect(),
+            None => Vec::new(),
+        };
+        names.sort();
+        
+        Ok(json!({ "chunks": names }))
+    }
+    
+    /// Diagnostics for a file in the project
+    pub fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        
+        if let Some(error) = &self.build_error {
+            diagnostics.push(Diagnostic {
+                range: Range::default(),
+                severity: 1,
+                source: "component",
+                message: format!("Build failed: {}", error),
+            });
+        }
+        
+        let graph = self.bundler.graph();
+        let graph = graph.read();
+        let Some(module) = graph.get_module_id(path).and_then(|id| graph.get_module(id)) else {
+            diagnostics.push(Diagnostic {
+                range: Range::default(),
+                severity: 3,
+                source: "component",
+                message: "File is not reachable from any entrypoint".to_string(),
+            });
+            return diagnostics;
+        };
+        
+        for dependency in &module.dependencies {
+            let specifier = &dependency.specifier;
+            let relative = specifier.starts_with('.') || specifier.starts_with('/');
+            if relative && matches!(self.bundler.resolve(specifier, &module.path), Ok(None)) {
+                diagnostics.push(Diagnostic {
+                    range: span_range(&module.source, dependency),
+                    severity: 1,
+                    source: "component",
+                    message: format!("Cannot resolve import '{}'", specifier),
+                });
+            }
+        }
+        
+        diagnostics
+    }
+    
+    /// Refresh a saved file and publish its diagnostics
+    async fn did_save(&mut self, params: &Value) -> Vec<Value> {
+        let Some(uri) = params.pointer("/textDocument/uri").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        let path = self.to_path(uri);
+        
+        self.build_error = match self.bundler.invalidate(&path).await {
+            Ok(_) => None,
+            Err(e) => {
+                error!("Rebuild failed: {:#}", e);
+                Some(format!("{:#}", e))
+            }
+        };
+        
+        vec![json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": self.diagnostics(&path) },
+        })]
+    }
+    
+    /// Convert a URI or (root-relative) path to a canonical path
+    fn to_path(&self, value: &str) -> PathBuf {
+        let path = Url::parse(value)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+            .unwrap_or_else(|| self.config.root.join(value));
+        path.canonicalize().map(simplify_path).unwrap_or(path)
+    }
+}
+
+impl Default for Range {
+    fn default() -> Self {
+        let start = Position { line: 0, character: 0 };
+        Self { start, end: start }
+    }
+}
+
+/// Read one framed message; `None` at end of input
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>().context("Invalid Content-Length")?);
+            }
+        }
+    }
+    
+    let length = content_length.context("Missing Content-Length header")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    
+    Ok(Some(serde_json::from_slice(&body).context("Invalid JSON-RPC message")?))
+}
+
+/// Write one framed message
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn to_uri(path: &Path) -> Option<String> {
+    Url::from_file_path(path).ok().map(String::from)
+}
+
+/// Editor range of an import's specifier
+fn span_range(source: &str, dependency: &Dependency) -> Range {
+    let (line, character) = dependency.position(source);
+    let length = source.get(dependency.span.clone()).map_or(0, |text| text.encode_utf16().count());
+    Range {
+        start: Position { line: line as u32, character: character as u32 },
+        end: Position { line: line as u32, character: (character + length) as u32 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_message_framing_roundtrip() {
+        let mut buffer = Vec::new();
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" });
+        write_message(&mut buffer, &message).await.unwrap();
+        
+        let mut reader = tokio::io::BufReader::new(buffer.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_resolve_chunks_and_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.js"), "import './a';\nimport './missing';\n").unwrap();
+        fs::write(dir.path().join("src/a.js"), "export const a = 1;\n").unwrap();
+        
+        let mut config = Config::default_config();
+        config.root = dir.path().to_path_buf();
+        let mut server = LspServer::new(config).await.unwrap();
+        
+        let reply = server
+            .handle(json!({ "id": 1, "method": "component/resolve", "params": { "specifier": "./a", "from": "src/main.js" } }))
+            .await;
+        assert!(reply[0]["result"]["path"].as_str().unwrap().ends_with("a.js"));
+        
+        let reply = server
+            .handle(json!({ "id": 2, "method": "component/chunks", "params": { "path": "src/a.js" } }))
+            .await;
+        assert_eq!(reply[0]["result"]["chunks"], json!(["main"]));
+        
+        let diagnostics = server.diagnostics(&server.to_path("src/main.js"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Cannot resolve import './missing'");
+        assert_eq!(diagnostics[0].range.start, Position { line: 1, character: 8 });
+        
+        let reply = server.handle(json!({ "id": 3, "method": "nope" })).await;
+        assert_eq!(reply[0]["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
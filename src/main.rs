@@ -10,25 +10,15 @@
 //! - Dev server with WebSocket-based hot module replacement
 //! - Plugin system for extensibility
 
-use anyhow::Result;
+use std::process::ExitCode;
+
 use clap::Parser;
+use component_lib::cli::{console, exit_code, toolchain, Cli};
+use component_lib::{messages, msg};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-mod cli;
-mod config;
-mod bundler;
-mod resolver;
-mod transform;
-mod server;
-mod plugins;
-mod utils;
-
-pub use cli::Cli;
-pub use config::Config;
-pub use bundler::Bundler;
-
 /// Initialize the logging/tracing system
-fn init_tracing(verbose: bool) {
+fn init_tracing(verbose: bool, colors: bool) {
     let filter = if verbose {
         EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| EnvFilter::new("component=debug,tower_http=debug"))
@@ -39,15 +29,28 @@ fn init_tracing(verbose: bool) {
 
     tracing_subscriber::registry()
         .with(filter)
-        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(tracing_subscriber::fmt::layer().with_target(false).with_ansi(colors).with_writer(std::io::stderr))
         .init();
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
     
-    init_tracing(cli.verbose);
+    messages::init(cli.locale().as_deref());
+    let colors = console::init(cli.color_choice());
+    init_tracing(cli.verbose, colors);
     
-    cli.execute().await
+    let result = match cli.pinned_toolchain() {
+        Ok(Some(binary)) => return toolchain::delegate(&binary),
+        Ok(None) => cli.execute().await,
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{} {:?}", msg!("cli.error"), error);
+            ExitCode::from(exit_code(&error))
+        }
+    }
 }
@@ -0,0 +1,164 @@
+//! Messages the CLI and dev server print
+//!
+//! User-facing text comes from a catalog of templates by key, such as
+//! `build.finished = "Built {count} bundle(s) in {seconds}s"`, printed with
+//! [`msg!`](crate::msg). A distribution ships `component` in another
+//! language by adding a catalog to [`CATALOGS`]; keys a translation leaves
+//! out are printed in English. The locale comes from `COMPONENT_LOCALE`,
+//! then `cli.locale` in component.toml, then `LC_ALL`, `LC_MESSAGES` or
+//! `LANG`.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use once_cell::sync::{Lazy, OnceCell};
+
+/// Built-in catalogs by language tag, English first
+///
+/// A translation is a TOML file with the keys of `en.toml`, tagged with a
+/// language (`de`) or a language and region (`pt-br`).
+pub const CATALOGS: &[(&str, &str)] = &[("en", include_str!("en.toml"))];
+
+type Catalog = HashMap<String, String>;
+
+static ENGLISH: Lazy<Catalog> = Lazy::new(|| parse(CATALOGS[0].1));
+
+/// The catalog [`init`] selected, unless English
+static SELECTED: OnceCell<Catalog> = OnceCell::new();
+
+/// A message from the catalog with its `{name}` placeholders filled in, as
+/// in `msg!("build.finished", count = bundles, seconds = "0.42")`
+#[macro_export]
+macro_rules! msg {
+    ($key:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::messages::format($key, &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*])
+    };
+}
+
+/// Select the catalog for the user's locale, with `configured` from
+/// `cli.locale`
+///
+/// Only the first call has an effect; until then messages are English.
+pub fn init(configured: Option<&str>) {
+    let from_env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let locale = from_env("COMPONENT_LOCALE")
+        .or_else(|| configured.map(str::to_string))
+        .or_else(|| ["LC_ALL", "LC_MESSAGES", "LANG"].into_iter().find_map(from_env));
+    if let Some(source) = locale.as_deref().and_then(catalog_source) {
+        if source != CATALOGS[0].1 {
+            let _ = SELECTED.set(parse(source));
+        }
+    }
+}
+
+/// Source of the catalog for a locale such as `pt_BR.UTF-8`, matching its
+/// language and region first, then its language
+fn catalog_source(locale: &str) -> Option<&'static str> {
+    let tag = locale.split(['.', '@']).next().unwrap_or_default().replace('_', "-").to_ascii_lowercase();
+    let language = tag.split('-').next().unwrap_or_default();
+    let catalog = [tag.as_str(), language]
+        .into_iter()
+        .find_map(|wanted| CATALOGS.iter().find(|(tag, _)| *tag == wanted));
+    catalog.map(|(_, source)| *source)
+}
+
+/// A message with its placeholders filled in; see [`msg!`](crate::msg)
+pub fn format(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let template = SELECTED
+        .get()
+        .and_then(|catalog| catalog.get(key))
+        .or_else(|| ENGLISH.get(key))
+        .map_or(key, String::as_str);
+    fill(template, args)
+}
+
+/// Replace each `{name}` in a template with the argument of that name,
+/// leaving unknown placeholders as written
+fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let placeholder = rest[start + 1..]
+            .find('}')
+            .map(|end| &rest[start + 1..start + 1 + end])
+            .and_then(|name| args.iter().find(|(arg, _)| *arg == name).map(|(_, value)| (name, value)));
+        match placeholder {
+            Some((name, value)) => {
+                output.push_str(&value.to_string());
+                rest = &rest[start + name.len() + 2..];
+            }
+            None => {
+                output.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Templates of a catalog by dotted key, from nested TOML tables
+fn parse(source: &str) -> Catalog {
+    fn flatten(prefix: &str, table: &toml::Table, catalog: &mut Catalog) {
+        for (key, value) in table {
+            let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+            match value {
+                toml::Value::String(template) => {
+                    catalog.insert(key, template.clone());
+                }
+                toml::Value::Table(table) => flatten(&key, table, catalog),
+                _ => {}
+            }
+        }
+    }
+    
+    let mut catalog = Catalog::new();
+    match toml::from_str::<toml::Table>(source) {
+        Ok(table) => flatten("", &table, &mut catalog),
+        Err(e) => tracing::warn!("Invalid message catalog: {}", e),
+    }
+    catalog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_format_messages() {
+        assert_eq!(
+            fill("Built {count} bundle(s) in {seconds}s", &[("count", &3), ("seconds", &"0.42")]),
+            "Built 3 bundle(s) in 0.42s"
+        );
+        assert_eq!(fill("{unknown} and {", &[("count", &1)]), "{unknown} and {");
+        assert_eq!(crate::msg!("build.started"), "Building project...");
+        assert_eq!(format("no.such.key", &[]), "no.such.key");
+        
+        assert_eq!(catalog_source("en_US.UTF-8"), Some(CATALOGS[0].1));
+        assert_eq!(catalog_source("EN"), Some(CATALOGS[0].1));
+        assert_eq!(catalog_source("xx-YY"), None);
+        assert_eq!(catalog_source("C"), None);
+    }
+    
+    #[test]
+    fn test_catalogs_cover_used_keys() {
+        let key = regex::Regex::new(r#"msg!\(\s*"([^"]+)""#).unwrap();
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        for entry in walkdir::WalkDir::new(src).into_iter().filter_map(Result::ok) {
+            if entry.path().extension().and_then(|ext| ext.to_str()) != Some("rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(entry.path()).unwrap();
+            for captures in key.captures_iter(&source) {
+                assert!(ENGLISH.contains_key(&captures[1]), "{} isn't in en.toml ({})", &captures[1], entry.path().display());
+            }
+        }
+        
+        for (tag, source) in CATALOGS {
+            for key in parse(source).keys() {
+                assert!(ENGLISH.contains_key(key), "{} catalog has unknown key {}", tag, key);
+            }
+        }
+    }
+}
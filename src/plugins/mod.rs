@@ -1,20 +1,390 @@
 //! Plugin system for Component
 //!
 //! Provides a Vite/Rollup-style plugin API for extending the bundler.
+//! Plugins listed under `[[plugins]]` are created by name from a
+//! [`PluginRegistry`].
+//!
+//! Hooks are called guarded: a panic becomes an error naming the plugin,
+//! the hook, and the module, as do errors the hook returns and, with
+//! `build.plugin_timeout`, hooks that run too long. Each plugin's time per
+//! hook is recorded for `build --profile`, and slow calls are logged.
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::{debug, error, info, warn};
+use web_time::Instant;
+
+use crate::bundler::{CacheBackend, DiskBackend, ModuleGraph, ModuleId, ModuleType, TransformStage};
+use crate::config::{Config, PluginConfig};
+use crate::resolver::Resolver;
+use crate::utils::{hash_content, path_to_module_id};
+
+/// Hook calls at least this slow are logged
+const SLOW_HOOK: Duration = Duration::from_secs(1);
+
+/// The future an `async_trait` hook returns
+type HookFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
 
 /// Plugin hook context
 pub struct PluginContext {
     /// Project root directory
-    pub root: std::path::PathBuf,
+    pub root: PathBuf,
+    
+    /// Resolved project configuration
+    config: Arc<Config>,
+    
+    /// Module graph of the build the hooks run for
+    graph: Arc<RwLock<ModuleGraph>>,
+    
+    /// What [`resolve`](Self::resolve) delegates to
+    chain: Arc<ResolveChain>,
+    
+    /// Data hooks attached to modules, by module id
+    meta: Arc<RwLock<HashMap<String, Map<String, Value>>>>,
+    
+    /// Files read by the transform in progress
+    watch_files: Mutex<Vec<PathBuf>>,
+    
+    /// Custom events exchanged with the dev server's browser clients
+    hmr: Arc<HmrEvents>,
+}
+
+impl PluginContext {
+    /// A context outside any build, with the default config
+    pub fn new(root: PathBuf) -> Self {
+        let mut config = Config::default_config();
+        config.root = root;
+        Self::for_build(Arc::new(config), Arc::default())
+    }
+    
+    /// A context for the build of `graph` with `config`
+    pub fn for_build(config: Arc<Config>, graph: Arc<RwLock<ModuleGraph>>) -> Self {
+        Self {
+            root: config.root.clone(),
+            config,
+            graph,
+            chain: Arc::default(),
+            meta: Arc::default(),
+            watch_files: Mutex::new(Vec::new()),
+            hmr: Arc::default(),
+        }
+    }
+    
+    /// A context for one module's hooks, recording its own watch files
+    fn for_module(&self) -> Self {
+        Self {
+            chain: self.chain.clone(),
+            meta: self.meta.clone(),
+            hmr: self.hmr.clone(),
+            ..Self::for_build(self.config.clone(), self.graph.clone())
+        }
+    }
+    
+    /// Resolve with `resolver` after the plugins in [`resolve`](Self::resolve)
+    pub fn set_resolver(&self, resolver: Arc<Resolver>) {
+        *self.chain.resolver.write() = Some(resolver);
+    }
+    
+    /// Resolve a specifier as the bundler would: through the plugins'
+    /// `resolve_id` hooks, then the core resolver
+    ///
+    /// Like Rollup's `this.resolve`, a plugin passes its own name as
+    /// `options.skip_plugin` to keep from resolving through itself.
+    /// Returns [`ResolveResult::Skip`] when nothing resolves it.
+    pub async fn resolve(&self, specifier: &str, importer: Option<&Path>, options: ResolveOptions<'_>) -> Result<ResolveResult> {
+        let plugins = self.chain.plugins.read().clone();
+        for plugin in plugins.iter().filter(|plugin| Some(plugin.name()) != options.skip_plugin) {
+            match plugin.resolve_id(specifier, importer, self).await? {
+                ResolveResult::Skip => continue,
+                result => return Ok(result),
+            }
+        }
+        
+        let resolver = self.chain.resolver.read().clone();
+        let Some(resolver) = resolver else {
+            return Ok(ResolveResult::Skip);
+        };
+        // Without an importer, resolve as from a file in the project root
+        let from = importer.map_or_else(|| self.root.join("package.json"), Path::to_path_buf);
+        let resolution = resolver.resolve_detailed(specifier, &from)?;
+        Ok(match resolution.path {
+            Some(path) => ResolveResult::Resolved(path.display().to_string()),
+            None if resolution.external && !options.skip_external => ResolveResult::External,
+            None => ResolveResult::Skip,
+        })
+    }
+    
+    /// Resolved project configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+    
+    /// Logger whose messages are prefixed with the plugin's name
+    pub fn logger(&self, plugin: &str) -> PluginLogger {
+        PluginLogger { plugin: plugin.to_string() }
+    }
+    
+    /// Key-value store under the build cache directory, kept between builds
+    ///
+    /// Each `namespace`, usually the plugin's name, has its own keys. With
+    /// `cache.enabled = false` nothing is stored.
+    pub fn cache(&self, namespace: &str) -> PluginCache {
+        let backend = self.config.cache.enabled.then(|| {
+            let dir = self.root.join(&self.config.cache.dir).join("plugins").join(hash_content(namespace.as_bytes()));
+            DiskBackend::new(dir)
+        });
+        PluginCache { backend }
+    }
+    
+    /// What the module graph knows about the module at `id`, if it has
+    /// been added to the graph yet
+    pub fn get_module_info(&self, id: &str) -> Option<ModuleInfo> {
+        let graph = self.graph.read();
+        let module_id = graph.get_module_id(Path::new(id))?;
+        let module = graph.get_module(module_id)?;
+        let paths = |ids: Vec<ModuleId>| -> Vec<String> {
+            ids.into_iter()
+                .filter_map(|id| graph.get_module(id))
+                .map(|module| module.path.display().to_string())
+                .collect()
+        };
+        Some(ModuleInfo {
+            id: module.path.display().to_string(),
+            module_type: module.module_type.clone(),
+            is_entry: module.is_entry,
+            specifiers: module.specifiers(),
+            imports: paths(graph.get_dependencies(module_id)),
+            dynamic_imports: paths(graph.get_dynamic_dependencies(module_id)),
+            importers: paths(graph.get_importers(module_id)),
+            transformed: module.transformed.is_some(),
+            meta: self.get_meta(id),
+        })
+    }
+    
+    /// Attach data to a module for later hooks and other plugins
+    ///
+    /// Any hook may set it, including `resolve_id` for a module not loaded
+    /// yet; it ends up in the module's `meta` and in
+    /// [`get_module_info`](Self::get_module_info).
+    pub fn set_meta(&self, id: &str, key: &str, value: Value) {
+        self.meta.write().entry(id.to_string()).or_default().insert(key.to_string(), value);
+    }
+    
+    /// Data attached to a module with [`set_meta`](Self::set_meta)
+    pub fn get_meta(&self, id: &str) -> Map<String, Value> {
+        self.meta.read().get(id).cloned().unwrap_or_default()
+    }
+    
+    /// Restore a module's data, e.g. from a graph snapshot
+    pub(crate) fn restore_meta(&self, id: &str, meta: Map<String, Value>) {
+        if !meta.is_empty() {
+            self.meta.write().insert(id.to_string(), meta);
+        }
+    }
+    
+    /// Ids of the modules in the graph
+    pub fn get_module_ids(&self) -> Vec<String> {
+        let graph = self.graph.read();
+        let mut ids: Vec<String> = graph
+            .all_module_ids()
+            .into_iter()
+            .filter_map(|id| graph.get_module(id))
+            .map(|module| module.path.display().to_string())
+            .collect();
+        ids.sort();
+        ids
+    }
+    
+    /// Record a file outside the module graph that the module being
+    /// transformed depends on, such as a config file or an included partial
+    ///
+    /// The module is transformed again when the file changes, both in the
+    /// dev server and in `build --watch`. Relative paths are resolved against
+    /// the project root.
+    pub fn add_watch_file(&self, path: impl AsRef<Path>) {
+        self.watch_files.lock().push(self.root.join(path));
+    }
+    
+    /// Files recorded with [`add_watch_file`](Self::add_watch_file) so far
+    pub fn get_watch_files(&self) -> Vec<PathBuf> {
+        self.watch_files.lock().clone()
+    }
+    
+    /// Send a custom event to the dev server's browser clients, which get
+    /// `data` in their `import.meta.hot.on(event, cb)` callbacks
+    ///
+    /// Outside the dev server there are no clients and nothing is sent.
+    pub fn hmr_send(&self, event: &str, data: Value) {
+        let sink = self.hmr.sink.read().clone();
+        if let Some(sink) = sink {
+            sink(event, data);
+        }
+    }
+    
+    /// Call `listener` with the data of every `event` browser clients send
+    /// with `import.meta.hot.send(event, data)`
+    pub fn hmr_on(&self, event: &str, listener: impl Fn(&Value) + Send + Sync + 'static) {
+        self.hmr.listeners.write().entry(event.to_string()).or_default().push(Arc::new(listener));
+    }
+    
+    /// Deliver [`hmr_send`](Self::hmr_send) events with `sink`
+    #[cfg(feature = "native")]
+    pub(crate) fn connect_hmr(&self, sink: impl Fn(&str, Value) + Send + Sync + 'static) {
+        *self.hmr.sink.write() = Some(Arc::new(sink));
+    }
+    
+    /// Hand an event a browser client sent to the [`hmr_on`](Self::hmr_on)
+    /// listeners for it
+    #[cfg(feature = "native")]
+    pub(crate) fn receive_hmr_event(&self, event: &str, data: &Value) {
+        let listeners = self.hmr.listeners.read().get(event).cloned().unwrap_or_default();
+        for listener in listeners {
+            listener(data);
+        }
+    }
+}
+
+/// Custom HMR events between plugins and browser clients, shared by a
+/// build's contexts
+#[derive(Default)]
+struct HmrEvents {
+    /// Sends events to the clients, once a dev server connects
+    sink: RwLock<Option<HmrSink>>,
+    
+    /// Listeners for the events clients send, by event name
+    listeners: RwLock<HashMap<String, Vec<HmrListener>>>,
+}
+
+type HmrSink = Arc<dyn Fn(&str, Value) + Send + Sync>;
+type HmrListener = Arc<dyn Fn(&Value) + Send + Sync>;
+
+/// Options for [`PluginContext::resolve`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResolveOptions<'a> {
+    /// Plugin whose `resolve_id` hook is not called, usually the caller
+    pub skip_plugin: Option<&'a str>,
+    
+    /// Report packages the core resolver leaves external, those in
+    /// `resolve.external`, as unresolved
+    pub skip_external: bool,
+}
+
+/// The plugins and resolver behind [`PluginContext::resolve`], shared by a
+/// build's contexts
+#[derive(Default)]
+struct ResolveChain {
+    plugins: RwLock<Vec<Arc<dyn Plugin>>>,
+    resolver: RwLock<Option<Arc<Resolver>>>,
+}
+
+/// A module as seen by [`PluginContext::get_module_info`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModuleInfo {
+    /// Absolute path of the module
+    pub id: String,
+    pub module_type: ModuleType,
+    pub is_entry: bool,
+    
+    /// Import specifiers as written in the source
+    pub specifiers: Vec<String>,
+    
+    /// Ids of the modules it imports statically
+    pub imports: Vec<String>,
+    
+    /// Ids of the modules it imports with `import()`
+    pub dynamic_imports: Vec<String>,
+    
+    /// Ids of the modules importing it
+    pub importers: Vec<String>,
+    
+    /// Whether its transformed code is available
+    pub transformed: bool,
+    
+    /// Data plugins attached with [`PluginContext::set_meta`]
+    pub meta: Map<String, Value>,
+}
+
+/// Logs messages prefixed with a plugin's name
+pub struct PluginLogger {
+    plugin: String,
+}
+
+impl PluginLogger {
+    pub fn debug(&self, message: impl std::fmt::Display) {
+        debug!(plugin = %self.plugin, "[{}] {}", self.plugin, message);
+    }
+    
+    pub fn info(&self, message: impl std::fmt::Display) {
+        info!(plugin = %self.plugin, "[{}] {}", self.plugin, message);
+    }
+    
+    pub fn warn(&self, message: impl std::fmt::Display) {
+        warn!(plugin = %self.plugin, "[{}] {}", self.plugin, message);
+    }
+    
+    pub fn error(&self, message: impl std::fmt::Display) {
+        error!(plugin = %self.plugin, "[{}] {}", self.plugin, message);
+    }
+}
+
+/// A plugin's persistent key-value store, from [`PluginContext::cache`]
+pub struct PluginCache {
+    backend: Option<DiskBackend>,
+}
+
+impl PluginCache {
+    /// The value stored under `key`
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        match &self.backend {
+            Some(backend) => backend.get(&hash_content(key.as_bytes())),
+            None => Ok(None),
+        }
+    }
+    
+    /// Store `value` under `key`, replacing any previous value
+    pub fn set(&self, key: &str, value: &str) -> Result<()> {
+        match &self.backend {
+            Some(backend) => backend.put(&hash_content(key.as_bytes()), value),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Prefix of the specifiers generated modules are imported with
+pub const CODEGEN_PREFIX: &str = "codegen:";
+
+/// A module produced by a codegen hook
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedModule {
+    /// Path-like id with an extension, e.g. `proto/user.ts`; imported as
+    /// `codegen:proto/user`
+    pub id: String,
+    
+    /// Module source
+    pub code: String,
+}
+
+/// Modules from all codegen hooks and the files they were generated from
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOutput {
+    pub modules: Vec<GeneratedModule>,
+    pub watch_files: Vec<PathBuf>,
 }
 
 /// Result of a resolve hook
+#[derive(Debug, Clone, PartialEq)]
 pub enum ResolveResult {
     /// Continue to next plugin
     Skip,
@@ -36,6 +406,18 @@ pub enum LoadResult {
     },
 }
 
+/// Output of the transform hooks for one module
+pub struct PluginTransform {
+    pub code: String,
+    pub map: Option<String>,
+    
+    /// Names of the plugins that returned transformed code
+    pub plugins: Vec<String>,
+    
+    /// Files the plugins registered with [`PluginContext::add_watch_file`]
+    pub watch_files: Vec<PathBuf>,
+}
+
 /// Result of a transform hook
 pub enum TransformResult {
     /// Continue to next plugin (no transformation)
@@ -48,12 +430,29 @@ pub enum TransformResult {
     },
 }
 
+/// Where a plugin runs among the others, like Vite's `enforce`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PluginOrder {
+    /// Before the `[[plugins]]` entries
+    Pre,
+    /// In the order plugins were listed or registered, after `[[plugins]]`
+    #[default]
+    Normal,
+    /// After every other plugin
+    Post,
+}
+
 /// Plugin trait - implement this to create a Component plugin
 #[async_trait]
 pub trait Plugin: Send + Sync {
     /// Plugin name for logging and debugging
     fn name(&self) -> &str;
     
+    /// Where this plugin's hooks run among the other plugins'
+    fn order(&self) -> PluginOrder {
+        PluginOrder::Normal
+    }
+    
     /// Called when the build starts
     async fn build_start(&self, _ctx: &PluginContext) -> Result<()> {
         Ok(())
@@ -64,6 +463,15 @@ pub trait Plugin: Send + Sync {
         Ok(())
     }
     
+    /// Generate modules before the module graph is built, e.g. TypeScript
+    /// from `.proto` or JSON schema files
+    ///
+    /// Register the input files with `ctx.add_watch_file` so editing them
+    /// generates the modules again and updates their importers.
+    async fn codegen(&self, _ctx: &PluginContext) -> Result<Vec<GeneratedModule>> {
+        Ok(Vec::new())
+    }
+    
     /// Resolve an import specifier to a path
     /// Return ResolveResult::Skip to let other plugins handle it
     async fn resolve_id(
@@ -97,30 +505,313 @@ pub trait Plugin: Send + Sync {
     }
 }
 
+/// Creates a plugin from its `[[plugins]]` options
+pub type PluginFactory = Box<dyn Fn(&toml::Table) -> Result<Arc<dyn Plugin>> + Send + Sync>;
+
+/// Plugins that can be created by name from `[[plugins]]`
+pub struct PluginRegistry {
+    factories: HashMap<String, PluginFactory>,
+}
+
+impl PluginRegistry {
+    /// A registry without any plugins
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+    
+    /// Make a plugin available under `name`
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&toml::Table) -> Result<Arc<dyn Plugin>> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+    
+    /// Create the plugin a config entry names; `None` when it is unknown
+    pub fn create(&self, config: &PluginConfig) -> Result<Option<Arc<dyn Plugin>>> {
+        let Some(factory) = self.factories.get(&config.name) else {
+            return Ok(None);
+        };
+        let options = config.options.clone().unwrap_or_default();
+        factory(&options)
+            .with_context(|| format!("Invalid options for plugin '{}'", config.name))
+            .map(Some)
+    }
+}
+
+impl Default for PluginRegistry {
+    /// The built-in `json` and `virtual` plugins
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("json", |_| Ok(Arc::new(JsonPlugin)));
+        registry.register("virtual", |options| Ok(Arc::new(VirtualPlugin::from_options(options)?)));
+        registry
+    }
+}
+
+/// Time a plugin spent in one of its hooks
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PluginTiming {
+    pub plugin: String,
+    pub hook: String,
+    pub calls: usize,
+    pub total: Duration,
+    
+    /// Longest call, with the module or specifier it was for, if any
+    pub slowest: Duration,
+    pub slowest_on: Option<String>,
+}
+
 /// Plugin manager
 pub struct PluginManager {
     plugins: Vec<Arc<dyn Plugin>>,
-    context: PluginContext,
+    
+    /// Config entry each plugin was created from; `None` for registered ones
+    sources: Vec<Option<PluginConfig>>,
+    
+    /// Context passed to the build, resolve and load hooks, shared with
+    /// embedders and kept across reconfiguration
+    context: Arc<PluginContext>,
+    
+    /// Longest a hook may run, from `build.plugin_timeout`
+    timeout: Option<Duration>,
+    
+    /// Time spent per plugin and hook
+    timings: Mutex<HashMap<(String, &'static str), PluginTiming>>,
 }
 
 impl PluginManager {
     /// Create a new plugin manager
-    pub fn new(root: std::path::PathBuf) -> Self {
+    pub fn new(root: PathBuf) -> Self {
         Self {
             plugins: Vec::new(),
-            context: PluginContext { root },
+            sources: Vec::new(),
+            context: Arc::new(PluginContext::new(root)),
+            timeout: None,
+            timings: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Fail hooks that run longer than `timeout`
+    ///
+    /// Only hooks that yield can be stopped, and only on native targets.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Time spent in each plugin's hooks, longest first
+    pub fn timings(&self) -> Vec<PluginTiming> {
+        let mut timings: Vec<PluginTiming> = self.timings.lock().values().cloned().collect();
+        timings.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| (&a.plugin, &a.hook).cmp(&(&b.plugin, &b.hook))));
+        timings
+    }
+    
+    /// Forget the recorded timings, e.g. before a new build
+    pub fn reset_timings(&self) {
+        self.timings.lock().clear();
+    }
+    
+    /// Run a hook's future, attributing failures and slowness to the plugin
+    ///
+    /// `subject` is the module id or specifier the hook was called for.
+    async fn call<T>(&self, plugin: &dyn Plugin, hook: &'static str, subject: Option<&str>, future: HookFuture<'_, T>) -> Result<T> {
+        let on = subject.map(|subject| format!(" on {}", self.display_subject(subject))).unwrap_or_default();
+        let started = Instant::now();
+        let guarded = CatchUnwind(future);
+        
+        #[cfg(feature = "native")]
+        let outcome = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, guarded).await.map_err(|_| {
+                anyhow::anyhow!("Plugin '{}' {} timed out after {:.1}s{}", plugin.name(), hook, timeout.as_secs_f64(), on)
+            })?,
+            None => guarded.await,
+        };
+        #[cfg(not(feature = "native"))]
+        let outcome = guarded.await;
+        
+        let elapsed = started.elapsed();
+        self.record(plugin.name(), hook, subject, elapsed);
+        if elapsed >= SLOW_HOOK {
+            warn!("Plugin '{}' {} took {:.1}s{}", plugin.name(), hook, elapsed.as_secs_f64(), on);
+        }
+        
+        match outcome {
+            Ok(result) => result.with_context(|| format!("Plugin '{}' {} failed{}", plugin.name(), hook, on)),
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                anyhow::bail!("Plugin '{}' panicked in {}{}: {}", plugin.name(), hook, on, message)
+            }
+        }
+    }
+    
+    fn record(&self, plugin: &str, hook: &'static str, subject: Option<&str>, elapsed: Duration) {
+        let mut timings = self.timings.lock();
+        let timing = timings.entry((plugin.to_string(), hook)).or_insert_with(|| PluginTiming {
+            plugin: plugin.to_string(),
+            hook: hook.to_string(),
+            ..Default::default()
+        });
+        timing.calls += 1;
+        timing.total += elapsed;
+        if elapsed > timing.slowest || timing.calls == 1 {
+            timing.slowest = elapsed;
+            timing.slowest_on = subject.map(|subject| self.display_subject(subject));
+        }
+    }
+    
+    /// A module id relative to the project root, for messages
+    fn display_subject(&self, subject: &str) -> String {
+        match Path::new(subject).strip_prefix(&self.context.root) {
+            Ok(relative) => path_to_module_id(relative),
+            Err(_) => subject.to_string(),
+        }
+    }
+    
+    /// Create the plugins listed in `[[plugins]]`
+    ///
+    /// Unknown plugin names are skipped with a warning.
+    pub fn from_config(root: PathBuf, configs: &[PluginConfig], registry: &PluginRegistry) -> Result<Self> {
+        let mut manager = Self::new(root);
+        for config in configs {
+            if let Some(plugin) = create_plugin(config, registry)? {
+                manager.plugins.push(plugin);
+                manager.sources.push(Some(config.clone()));
+            }
         }
+        manager.sort();
+        Ok(manager)
     }
     
     /// Register a plugin
+    ///
+    /// It runs after the plugins before it of the same [`PluginOrder`].
     pub fn register(&mut self, plugin: Arc<dyn Plugin>) {
         self.plugins.push(plugin);
+        self.sources.push(None);
+        self.sort();
+    }
+    
+    /// A manager with these plugins registered in addition
+    pub fn extended(&self, plugins: impl IntoIterator<Item = Arc<dyn Plugin>>) -> Self {
+        let mut next = self.successor();
+        next.plugins = self.plugins.clone();
+        next.sources = self.sources.clone();
+        for plugin in plugins {
+            next.register(plugin);
+        }
+        next
+    }
+    
+    /// A manager creating the `[[plugins]]` entries anew from `registry`,
+    /// keeping the registered plugins
+    pub fn rebuild(&self, configs: &[PluginConfig], registry: &PluginRegistry) -> Result<Self> {
+        let mut next = self.successor();
+        for config in configs {
+            if let Some(plugin) = create_plugin(config, registry)? {
+                next.plugins.push(plugin);
+                next.sources.push(Some(config.clone()));
+            }
+        }
+        next.sort();
+        Ok(next.extended(self.registered()))
+    }
+    
+    /// Plugins registered in code rather than created from `[[plugins]]`
+    pub fn registered(&self) -> Vec<Arc<dyn Plugin>> {
+        self.plugins
+            .iter()
+            .zip(&self.sources)
+            .filter(|(_, source)| source.is_none())
+            .map(|(plugin, _)| plugin.clone())
+            .collect()
+    }
+    
+    /// The context shared by the build, resolve and load hooks
+    pub fn context(&self) -> Arc<PluginContext> {
+        self.context.clone()
+    }
+    
+    /// Give hooks a context for the build of `graph` with `config`
+    pub fn with_context(mut self, context: PluginContext) -> Self {
+        self.context = Arc::new(context);
+        self.sort();
+        self
+    }
+    
+    /// An empty manager with this one's root, context and timeout
+    fn successor(&self) -> Self {
+        let mut next = Self::new(self.context.root.clone()).with_timeout(self.timeout);
+        next.context = self.context.clone();
+        next
+    }
+    
+    /// Order plugins by [`Plugin::order`], keeping their order otherwise
+    fn sort(&mut self) {
+        let mut entries: Vec<_> = self.plugins.drain(..).zip(self.sources.drain(..)).collect();
+        entries.sort_by_key(|(plugin, _)| plugin.order());
+        (self.plugins, self.sources) = entries.into_iter().unzip();
+        *self.context.chain.plugins.write() = self.plugins.clone();
+    }
+    
+    /// Whether a plugin with this name is active
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.plugins.iter().any(|p| p.name() == name)
+    }
+    
+    /// A manager for a new `[[plugins]]` list, and the names of the plugins that changed
+    ///
+    /// Entries whose name and options are unchanged keep their plugin
+    /// instance; the rest are created anew. Registered plugins are kept as-is
+    /// after the configured ones.
+    pub fn reconfigure(&self, configs: &[PluginConfig], registry: &PluginRegistry) -> Result<(Self, Vec<String>)> {
+        let mut next = self.successor();
+        let mut unused: Vec<usize> = (0..self.plugins.len()).filter(|&i| self.sources[i].is_some()).collect();
+        let mut changed = Vec::new();
+        
+        for config in configs {
+            let reused = unused.iter().position(|&i| self.sources[i].as_ref() == Some(config));
+            let plugin = match reused {
+                Some(position) => Some(self.plugins[unused.remove(position)].clone()),
+                None => {
+                    let plugin = create_plugin(config, registry)?;
+                    if plugin.is_some() {
+                        changed.push(config.name.clone());
+                    }
+                    plugin
+                }
+            };
+            if let Some(plugin) = plugin {
+                next.plugins.push(plugin);
+                next.sources.push(Some(config.clone()));
+            }
+        }
+        changed.extend(unused.into_iter().map(|i| self.plugins[i].name().to_string()));
+        
+        for (plugin, source) in self.plugins.iter().zip(&self.sources) {
+            if source.is_none() {
+                next.plugins.push(plugin.clone());
+                next.sources.push(None);
+            }
+        }
+        
+        next.sort();
+        changed.sort();
+        changed.dedup();
+        Ok((next, changed))
     }
     
     /// Run build_start hooks
     pub async fn run_build_start(&self) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.build_start(&self.context).await?;
+            self.call(plugin.as_ref(), "build_start", None, plugin.build_start(&self.context)).await?;
         }
         Ok(())
     }
@@ -128,11 +819,29 @@ impl PluginManager {
     /// Run build_end hooks
     pub async fn run_build_end(&self) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.build_end(&self.context).await?;
+            self.call(plugin.as_ref(), "build_end", None, plugin.build_end(&self.context)).await?;
         }
         Ok(())
     }
     
+    /// Run codegen hooks; ids must be unique across plugins
+    pub async fn run_codegen(&self) -> Result<CodegenOutput> {
+        let mut output = CodegenOutput::default();
+        for plugin in &self.plugins {
+            let context = self.context.for_module();
+            let modules = self.call(plugin.as_ref(), "codegen", None, plugin.codegen(&context)).await?;
+            
+            for module in modules {
+                if output.modules.iter().any(|m| m.id == module.id) {
+                    anyhow::bail!("Generated module '{}' is generated more than once", module.id);
+                }
+                output.modules.push(module);
+            }
+            output.watch_files.extend(context.watch_files.into_inner());
+        }
+        Ok(output)
+    }
+    
     /// Run resolve_id hooks
     pub async fn resolve_id(
         &self,
@@ -140,7 +849,8 @@ impl PluginManager {
         importer: Option<&Path>,
     ) -> Result<Option<String>> {
         for plugin in &self.plugins {
-            match plugin.resolve_id(specifier, importer, &self.context).await? {
+            let future = plugin.resolve_id(specifier, importer, &self.context);
+            match self.call(plugin.as_ref(), "resolve_id", Some(specifier), future).await? {
                 ResolveResult::Skip => continue,
                 ResolveResult::Resolved(path) => return Ok(Some(path)),
                 ResolveResult::External => return Ok(None),
@@ -152,7 +862,7 @@ impl PluginManager {
     /// Run load hooks
     pub async fn load(&self, id: &str) -> Result<Option<(String, Option<String>)>> {
         for plugin in &self.plugins {
-            match plugin.load(id, &self.context).await? {
+            match self.call(plugin.as_ref(), "load", Some(id), plugin.load(id, &self.context)).await? {
                 LoadResult::Skip => continue,
                 LoadResult::Loaded { content, loader } => {
                     return Ok(Some((content, loader)));
@@ -164,25 +874,87 @@ impl PluginManager {
     
     /// Run transform hooks
     pub async fn transform(&self, code: &str, id: &str) -> Result<(String, Option<String>)> {
+        let output = self.transform_traced(code, id, None).await?;
+        Ok((output.code, output.map))
+    }
+    
+    /// Run transform hooks, recording each plugin's output into `stages`
+    pub async fn transform_traced(
+        &self,
+        code: &str,
+        id: &str,
+        mut stages: Option<&mut Vec<TransformStage>>,
+    ) -> Result<PluginTransform> {
         let mut current_code = code.to_string();
         let mut current_map = None;
+        let mut transformed_by = Vec::new();
+        
+        // A context per module so watch files are attributed to it
+        let context = self.context.for_module();
         
         for plugin in &self.plugins {
-            match plugin.transform(&current_code, id, &self.context).await? {
-                TransformResult::Skip => continue,
+            let started = Instant::now();
+            let future = plugin.transform(&current_code, id, &context);
+            let result = self.call(plugin.as_ref(), "transform", Some(id), future).await?;
+            let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+            
+            let changed = match result {
+                TransformResult::Skip => false,
                 TransformResult::Transformed { code, map } => {
+                    transformed_by.push(plugin.name().to_string());
+                    let changed = code != current_code;
                     current_code = code;
                     if map.is_some() {
                         current_map = map;
                     }
+                    changed
                 }
+            };
+            
+            if let Some(stages) = stages.as_deref_mut() {
+                stages.push(TransformStage {
+                    name: format!("plugin:{}", plugin.name()),
+                    code: current_code.clone(),
+                    changed,
+                    duration_ms,
+                });
             }
         }
         
-        Ok((current_code, current_map))
+        Ok(PluginTransform {
+            code: current_code,
+            map: current_map,
+            plugins: transformed_by,
+            watch_files: context.watch_files.into_inner(),
+        })
+    }
+}
+
+/// Polls a hook's future, catching a panic in the plugin instead of
+/// unwinding through the build
+struct CatchUnwind<'a, T>(HookFuture<'a, T>);
+
+impl<T> Future for CatchUnwind<'_, T> {
+    type Output = std::thread::Result<Result<T>>;
+    
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let future = &mut self.0;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(result)) => Poll::Ready(Ok(result)),
+            Err(panic) => Poll::Ready(Err(panic)),
+        }
     }
 }
 
+fn create_plugin(config: &PluginConfig, registry: &PluginRegistry) -> Result<Option<Arc<dyn Plugin>>> {
+    let plugin = registry.create(config)?;
+    if plugin.is_none() {
+        warn!("Unknown plugin '{}' in [[plugins]]; skipping it", config.name);
+    }
+    Ok(plugin)
+}
+
 // Example built-in plugins
 
 /// JSON plugin - transforms JSON files to ES modules
@@ -226,6 +998,19 @@ impl VirtualPlugin {
         }
     }
     
+    /// Modules from the `modules` option, a table of ids to source code
+    pub fn from_options(options: &toml::Table) -> Result<Self> {
+        let mut plugin = Self::new();
+        if let Some(modules) = options.get("modules") {
+            let modules = modules.as_table().context("`modules` must be a table")?;
+            for (id, content) in modules {
+                let content = content.as_str().with_context(|| format!("Source of virtual module '{}' must be a string", id))?;
+                plugin.add_module(id, content);
+            }
+        }
+        Ok(plugin)
+    }
+    
     pub fn add_module(&mut self, id: &str, content: &str) {
         self.modules.insert(id.to_string(), content.to_string());
     }
@@ -272,3 +1057,356 @@ impl Plugin for VirtualPlugin {
         Ok(LoadResult::Skip)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn plugin(name: &str, options: Option<&str>) -> PluginConfig {
+        PluginConfig {
+            name: name.to_string(),
+            options: options.map(|o| toml::from_str(o).unwrap()),
+        }
+    }
+    
+    struct IncludePlugin;
+    
+    #[async_trait]
+    impl Plugin for IncludePlugin {
+        fn name(&self) -> &str {
+            "include"
+        }
+        
+        async fn transform(&self, code: &str, _id: &str, ctx: &PluginContext) -> Result<TransformResult> {
+            ctx.add_watch_file("partials/header.html");
+            Ok(TransformResult::Transformed { code: code.to_string(), map: None })
+        }
+    }
+    
+    /// Generates a TypeScript interface per message in `schema/user.proto`
+    struct ProtoPlugin(Arc<crate::vfs::MemoryFs>);
+    
+    #[async_trait]
+    impl Plugin for ProtoPlugin {
+        fn name(&self) -> &str {
+            "proto"
+        }
+        
+        async fn codegen(&self, ctx: &PluginContext) -> Result<Vec<GeneratedModule>> {
+            use crate::vfs::FileSystem;
+            
+            ctx.add_watch_file("schema/user.proto");
+            let schema = self.0.read_to_string(&ctx.root.join("schema/user.proto"))?;
+            let code = schema
+                .lines()
+                .filter_map(|line| line.strip_prefix("message "))
+                .map(|name| format!("export const {0} = '{0}';\n", name.trim_end_matches(" {}")))
+                .collect();
+            Ok(vec![GeneratedModule { id: "proto/user.ts".to_string(), code }])
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_codegen_modules_are_importable_and_regenerated() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/project/src/main.js", "import { User } from 'codegen:proto/user';\nconsole.log(User);\n");
+        fs.write("/project/schema/user.proto", "message User {}\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        config.plugins.push(plugin("proto", None));
+        let mut registry = PluginRegistry::empty();
+        let proto_fs = fs.clone();
+        registry.register("proto", move |_| Ok(Arc::new(ProtoPlugin(proto_fs.clone()))));
+        
+        let bundler = crate::bundler::Bundler::new(config, crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        })
+        .unwrap()
+        .with_fs(fs.clone())
+        .unwrap()
+        .with_plugin_registry(registry)
+        .unwrap();
+        
+        let files = bundler.build_in_memory().await.unwrap();
+        assert!(String::from_utf8_lossy(&files["main.js"]).contains("const User = 'User';"));
+        assert_eq!(bundler.watch_files(), [PathBuf::from("/project/schema/user.proto")]);
+        
+        fs.write("/project/schema/user.proto", "message User {}\nmessage Team {}\n");
+        let schema = Path::new("/project/schema/user.proto");
+        assert!(bundler.is_watch_file(schema));
+        assert!(bundler.invalidate(schema).await.unwrap().is_some());
+        let files = bundler.build_in_memory().await.unwrap();
+        assert!(String::from_utf8_lossy(&files["main.js"]).contains("const Team = 'Team';"));
+    }
+    
+    #[test]
+    fn test_reconfigure_recreates_changed_plugins() {
+        let registry = PluginRegistry::default();
+        let configs = [plugin("json", None), plugin("virtual", Some("modules = { a = 'export default 1' }"))];
+        let manager = PluginManager::from_config("/p".into(), &configs, &registry).unwrap();
+        
+        let configs = [plugin("json", None), plugin("virtual", Some("modules = { a = 'export default 2' }"))];
+        let (next, changed) = manager.reconfigure(&configs, &registry).unwrap();
+        assert_eq!(changed, ["virtual"]);
+        assert!(Arc::ptr_eq(&manager.plugins[0], &next.plugins[0]));
+        assert!(!Arc::ptr_eq(&manager.plugins[1], &next.plugins[1]));
+        
+        let (next, changed) = next.reconfigure(&configs[1..], &registry).unwrap();
+        assert_eq!(changed, ["json"]);
+        assert!(!next.has_plugin("json"));
+    }
+    
+    struct PanickingPlugin;
+    
+    #[async_trait]
+    impl Plugin for PanickingPlugin {
+        fn name(&self) -> &str {
+            "broken"
+        }
+        
+        async fn transform(&self, _code: &str, id: &str, _ctx: &PluginContext) -> Result<TransformResult> {
+            if id.ends_with(".ts") {
+                panic!("unexpected token");
+            }
+            Ok(TransformResult::Skip)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_plugin_panics_are_attributed_and_timed() {
+        let mut manager = PluginManager::new("/p".into());
+        manager.register(Arc::new(PanickingPlugin));
+        
+        assert!(manager.transform_traced("x", "/p/src/main.js", None).await.is_ok());
+        let error = manager.transform_traced("x", "/p/src/x.ts", None).await.err().unwrap();
+        assert_eq!(error.to_string(), "Plugin 'broken' panicked in transform on src/x.ts: unexpected token");
+        
+        let timings = manager.timings();
+        assert_eq!(timings.len(), 1);
+        assert_eq!((timings[0].plugin.as_str(), timings[0].hook.as_str(), timings[0].calls), ("broken", "transform", 2));
+        manager.reset_timings();
+        assert!(manager.timings().is_empty());
+    }
+    
+    struct OrderedPlugin(&'static str, PluginOrder);
+    
+    #[async_trait]
+    impl Plugin for OrderedPlugin {
+        fn name(&self) -> &str {
+            self.0
+        }
+        
+        fn order(&self) -> PluginOrder {
+            self.1
+        }
+    }
+    
+    #[test]
+    fn test_registered_plugins_are_ordered_and_kept() {
+        let manager = PluginManager::from_config("/p".into(), &[plugin("json", None)], &PluginRegistry::default()).unwrap();
+        let manager = manager.extended([
+            Arc::new(OrderedPlugin("last", PluginOrder::Post)) as Arc<dyn Plugin>,
+            Arc::new(OrderedPlugin("after", PluginOrder::Normal)),
+            Arc::new(OrderedPlugin("first", PluginOrder::Pre)),
+        ]);
+        let names = |manager: &PluginManager| manager.plugins.iter().map(|p| p.name().to_string()).collect::<Vec<_>>();
+        assert_eq!(names(&manager), ["first", "json", "after", "last"]);
+        
+        let (next, _) = manager.reconfigure(&[plugin("virtual", None), plugin("json", None)], &PluginRegistry::default()).unwrap();
+        assert_eq!(names(&next), ["first", "virtual", "json", "after", "last"]);
+        let rebuilt = next.rebuild(&[], &PluginRegistry::empty()).unwrap();
+        assert_eq!(names(&rebuilt), ["first", "after", "last"]);
+        assert!(Arc::ptr_eq(&manager.context(), &rebuilt.context()));
+    }
+    
+    /// Records what the context says about each module it transforms
+    struct InspectPlugin(Mutex<Vec<ModuleInfo>>);
+    
+    #[async_trait]
+    impl Plugin for InspectPlugin {
+        fn name(&self) -> &str {
+            "inspect"
+        }
+        
+        async fn transform(&self, _code: &str, id: &str, ctx: &PluginContext) -> Result<TransformResult> {
+            assert!(!ctx.config().output.hash);
+            ctx.set_meta(id, "inspected", Value::Bool(true));
+            self.0.lock().extend(ctx.get_module_info(id));
+            Ok(TransformResult::Skip)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_context_exposes_config_graph_and_cache() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a.js';\nconsole.log(a);\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        config.cache.dir = dir.path().display().to_string();
+        let inspect = Arc::new(InspectPlugin(Mutex::new(Vec::new())));
+        let bundler = crate::bundler::Bundler::new(config, crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        })
+        .unwrap()
+        .with_fs(fs)
+        .unwrap()
+        .with_plugins(vec![inspect.clone()]);
+        bundler.build_in_memory().await.unwrap();
+        
+        let infos = inspect.0.lock();
+        let main = infos.iter().find(|info| info.id == "/project/src/main.js").unwrap();
+        assert!(main.is_entry);
+        assert_eq!(main.specifiers, ["./a.js"]);
+        assert_eq!(main.imports, ["/project/src/a.js"]);
+        let a = infos.iter().find(|info| info.id == "/project/src/a.js").unwrap();
+        assert_eq!(a.importers, ["/project/src/main.js"]);
+        assert_eq!(a.meta.get("inspected"), Some(&Value::Bool(true)));
+        
+        let graph = bundler.graph();
+        let graph = graph.read();
+        let module = graph.get_module(graph.get_module_id(Path::new("/project/src/a.js")).unwrap()).unwrap();
+        assert_eq!(module.meta.get("inspected"), Some(&Value::Bool(true)));
+        
+        let context = bundler.plugin_context();
+        assert_eq!(context.get_module_ids(), ["/project/src/a.js", "/project/src/main.js"]);
+        let cache = context.cache("inspect");
+        assert_eq!(cache.get("key").unwrap(), None);
+        cache.set("key", "value").unwrap();
+        assert_eq!(context.cache("inspect").get("key").unwrap().as_deref(), Some("value"));
+        assert_eq!(context.cache("other").get("key").unwrap(), None);
+    }
+    
+    /// Attaches the length of each module's code to it
+    struct SizePlugin;
+    
+    #[async_trait]
+    impl Plugin for SizePlugin {
+        fn name(&self) -> &str {
+            "size"
+        }
+        
+        async fn transform(&self, code: &str, id: &str, ctx: &PluginContext) -> Result<TransformResult> {
+            ctx.set_meta(id, "size", Value::from(code.len()));
+            Ok(TransformResult::Skip)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_meta_is_kept_in_graph_snapshots() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/project/src/main.js", "console.log(1);\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let bundler = || {
+            crate::bundler::Bundler::new(config.clone(), crate::bundler::BuildOptions {
+                outdir: None,
+                minify: false,
+                sourcemap: false,
+                target: "es2020".to_string(),
+                inspect_transforms: false,
+            })
+            .unwrap()
+            .with_fs(fs.clone())
+            .unwrap()
+        };
+        
+        let cold = bundler().with_plugins(vec![Arc::new(SizePlugin)]);
+        cold.build_graph().await.unwrap();
+        assert_eq!(cold.plugin_context().get_meta("/project/src/main.js").get("size"), Some(&Value::from(16)));
+        let snapshot = cold.snapshot().unwrap().unwrap();
+        assert_eq!(snapshot.modules[0].meta.get("size"), Some(&Value::from(16)));
+        
+        // Restored unchanged modules aren't transformed again, so their data
+        // comes from the snapshot
+        let warm = bundler();
+        assert_eq!(warm.restore_graph(snapshot).await.unwrap(), Some(0));
+        let info = warm.plugin_context().get_module_info("/project/src/main.js").unwrap();
+        assert_eq!(info.meta.get("size"), Some(&Value::from(16)));
+        let graph = warm.graph();
+        let graph = graph.read();
+        let module = graph.get_module(graph.get_module_id(Path::new("/project/src/main.js")).unwrap()).unwrap();
+        assert_eq!(module.meta.get("size"), Some(&Value::from(16)));
+    }
+    
+    /// Redirects `./theme` to the dark variant next to it, if there is one
+    struct ThemePlugin;
+    
+    #[async_trait]
+    impl Plugin for ThemePlugin {
+        fn name(&self) -> &str {
+            "theme"
+        }
+        
+        async fn resolve_id(&self, specifier: &str, importer: Option<&Path>, ctx: &PluginContext) -> Result<ResolveResult> {
+            if specifier != "./theme" {
+                return Ok(ResolveResult::Skip);
+            }
+            let options = ResolveOptions { skip_plugin: Some(self.name()), ..Default::default() };
+            match ctx.resolve("./theme.dark", importer, options).await? {
+                ResolveResult::Skip => ctx.resolve(specifier, importer, options).await,
+                resolved => Ok(resolved),
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_context_resolves_through_other_plugins_and_resolver() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/p/src/main.js", "");
+        fs.write("/p/src/theme.js", "");
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/p");
+        config.resolve.external = vec!["react".to_string()];
+        let config = Arc::new(config);
+        
+        let mut manager = PluginManager::new("/p".into()).with_context(PluginContext::for_build(config.clone(), Arc::default()));
+        manager.register(Arc::new(ThemePlugin));
+        let mut virtual_modules = VirtualPlugin::new();
+        virtual_modules.add_module("virtual:env", "export default {}");
+        manager.register(Arc::new(virtual_modules));
+        let context = manager.context();
+        context.set_resolver(Arc::new(Resolver::with_fs(config, fs.clone()).unwrap()));
+        
+        let main = Path::new("/p/src/main.js");
+        assert_eq!(manager.resolve_id("./theme", Some(main)).await.unwrap().as_deref(), Some("/p/src/theme.js"));
+        fs.write("/p/src/theme.dark.js", "");
+        assert_eq!(manager.resolve_id("./theme", Some(main)).await.unwrap().as_deref(), Some("/p/src/theme.dark.js"));
+        
+        let resolve = |specifier: &'static str, options| {
+            let context = context.clone();
+            async move { context.resolve(specifier, Some(main), options).await.unwrap() }
+        };
+        assert_eq!(resolve("virtual:env", ResolveOptions::default()).await, ResolveResult::Resolved("\0virtual:virtual:env".to_string()));
+        assert_eq!(resolve("react", ResolveOptions::default()).await, ResolveResult::External);
+        assert_eq!(resolve("react", ResolveOptions { skip_external: true, ..Default::default() }).await, ResolveResult::Skip);
+    }
+    
+    #[tokio::test]
+    async fn test_transform_records_plugins_and_watch_files() {
+        let mut manager = PluginManager::from_config("/p".into(), &[plugin("json", None)], &PluginRegistry::default()).unwrap();
+        manager.register(Arc::new(IncludePlugin));
+        
+        let output = manager.transform_traced("{}", "/p/data.json", None).await.unwrap();
+        assert_eq!(output.code, "export default {};");
+        assert_eq!(output.plugins, ["json", "include"]);
+        assert_eq!(output.watch_files, [PathBuf::from("/p/partials/header.html")]);
+        
+        let output = manager.transform_traced("x", "/p/main.js", None).await.unwrap();
+        assert_eq!(output.plugins, ["include"]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Package `exports` maps
+//!
+//! A package with an `exports` field in its package.json can only be
+//! imported at the subpaths it lists, exactly or through `*` patterns, and
+//! each subpath may point at different files under conditions such as
+//! `import`, `require`, `browser` and `default`. These are Node's rules, as
+//! bundlers apply them.
+
+use serde_json::Value;
+
+/// The `exports` entry for a subpath, with the text a `*` pattern matched
+///
+/// `subpath` is `.` for the package itself or `./name` for a subpath. An
+/// exact key wins over patterns, and of several matching patterns the one
+/// with the longest text before its `*` wins.
+pub fn entry<'a, 'b>(exports: &'a Value, subpath: &'b str) -> Option<(&'a Value, &'b str)> {
+    // A string, array, or object without subpath keys is the package itself
+    let map = match exports {
+        Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => map,
+        _ => return (subpath == ".").then_some((exports, "")),
+    };
+    
+    if let Some(entry) = map.get(subpath).filter(|_| !subpath.contains('*')) {
+        return Some((entry, ""));
+    }
+    map.iter()
+        .filter_map(|(key, entry)| {
+            let (prefix, suffix) = key.split_once('*')?;
+            let matched = subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            Some((prefix.len(), entry, matched))
+        })
+        .max_by_key(|&(prefix, _, _)| prefix)
+        .map(|(_, entry, matched)| (entry, matched))
+}
+
+/// Target of an entry under `conditions`, with each `*` replaced by the
+/// text the entry's pattern matched
+///
+/// An object's conditions are tried in the order the package lists them,
+/// `default` matching always, and arrays are fallbacks tried in order.
+/// Returns the target relative to the package directory, with the
+/// conditions followed to it, outermost first; `None` when no condition
+/// matches or the entry is `null`, which hides a subpath.
+pub fn target(entry: &Value, matched: &str, conditions: &[&str]) -> Option<(String, Vec<String>)> {
+    match entry {
+        Value::String(target) if target.starts_with("./") => Some((target.replace('*', matched), Vec::new())),
+        Value::Array(targets) => targets.iter().find_map(|target| self::target(target, matched, conditions)),
+        Value::Object(map) => map.iter().find_map(|(condition, nested)| {
+            if condition != "default" && !conditions.contains(&condition.as_str()) {
+                return None;
+            }
+            let (target, mut used) = self::target(nested, matched, conditions)?;
+            used.insert(0, condition.clone());
+            Some((target, used))
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_resolve_exports() {
+        let exports = json!({
+            ".": { "types": "./index.d.ts", "browser": "./browser.js", "import": "./index.mjs", "require": "./index.cjs" },
+            "./jsx-runtime": { "import": "./jsx/runtime.mjs", "default": "./jsx/runtime.js" },
+            "./utils/*": "./dist/utils/*.js",
+            "./utils/internal/*": null,
+            "./locales/*.json": ["./locales/*.json"],
+            "./package.json": "./package.json"
+        });
+        let resolve = |subpath: &str, conditions: &[&str]| {
+            let (entry, matched) = entry(&exports, subpath)?;
+            target(entry, matched, conditions)
+        };
+        
+        assert_eq!(resolve(".", &["import"]), Some(("./index.mjs".to_string(), vec!["import".to_string()])));
+        assert_eq!(resolve(".", &["browser", "import"]).unwrap().0, "./browser.js");
+        assert_eq!(resolve(".", &["require"]).unwrap().0, "./index.cjs");
+        assert_eq!(resolve(".", &["node"]), None);
+        assert_eq!(resolve("./jsx-runtime", &["require"]), Some(("./jsx/runtime.js".to_string(), vec!["default".to_string()])));
+        assert_eq!(resolve("./utils/format", &["import"]).unwrap().0, "./dist/utils/format.js");
+        assert_eq!(resolve("./utils/internal/secret", &["import"]), None);
+        assert_eq!(resolve("./locales/en.json", &[]).unwrap().0, "./locales/en.json");
+        assert!(entry(&exports, "./missing").is_none());
+        
+        // Exports without subpath keys are the package's own
+        let exports = json!({ "import": "./index.mjs", "default": "./index.js" });
+        assert_eq!(entry(&exports, "."), Some((&exports, "")));
+        assert!(entry(&exports, "./sub").is_none());
+        assert_eq!(target(&json!("./index.js"), "", &[]).unwrap().0, "./index.js");
+        assert_eq!(target(&json!("../outside.js"), "", &[]), None);
+    }
+}
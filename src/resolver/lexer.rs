@@ -0,0 +1,760 @@
+//! Import scanning for scripts
+//!
+//! A small lexer in the spirit of es-module-lexer. It skips comments,
+//! strings, template literals and regular expressions, so only real
+//! `import`, `export ... from`, `import()` and `require()` calls, and asset
+//! URLs written as `new URL('./file', import.meta.url)`, count, and
+//! statements spread over several lines are found like any other. Spans are
+//! exact byte ranges of the specifiers.
+//!
+//! The same tokens give a module's exports, for checking named imports
+//! against them.
+
+use std::ops::Range;
+
+use crate::bundler::{ImportKind, ModuleExports, ReExport};
+
+/// An import found in a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedImport {
+    pub kind: ImportKind,
+    
+    /// Byte range of the specifier, without its quotes
+    pub span: Range<usize>,
+    
+    /// Whether it is `import 'x'`, importing no bindings
+    pub bare: bool,
+    
+    /// Bindings a static import takes by name, `default` for a default
+    /// import; empty for namespace and type-only imports
+    pub names: Vec<String>,
+    
+    /// Import attributes, as in `with { type: 'json' }`
+    pub attributes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Punct(u8),
+    /// A string literal, by the byte range of its contents
+    Str(usize, usize),
+    /// A piece of a template literal between its delimiters and
+    /// substitutions, by its byte range including them, as in `` `a${ ``
+    Template(usize, usize),
+    /// Regular expressions and unterminated strings
+    Other,
+}
+
+/// Keywords after which `/` starts a regular expression rather than a
+/// division
+const REGEX_KEYWORDS: &[&str] = &[
+    "return", "typeof", "instanceof", "in", "of", "new", "delete", "void", "throw", "case", "do", "else", "yield", "await",
+];
+
+/// Imports in a script, in source order
+///
+/// Imports and re-exports of types only, which TypeScript erases, are left
+/// out so the modules they name aren't bundled.
+pub fn scan(source: &str) -> Vec<ScannedImport> {
+    let tokens = tokenize(source);
+    let string_at = |index: usize| match tokens.get(index) {
+        Some(&Token::Str(start, end)) => Some(start..end),
+        _ => None,
+    };
+    
+    let mut imports = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        // Properties such as `loader.import(...)` aren't imports
+        if index > 0 && tokens[index - 1] == Token::Punct(b'.') {
+            continue;
+        }
+        let found = match token {
+            Token::Word("import") => match tokens.get(index + 1) {
+                Some(Token::Punct(b'(')) => string_at(index + 2)
+                    .filter(|_| matches!(tokens.get(index + 3), Some(Token::Punct(b')' | b','))))
+                    .map(|span| (ImportKind::Dynamic, span, false, Vec::new(), import_options(source, &tokens, index + 4))),
+                Some(Token::Str(..)) => string_at(index + 1)
+                    .map(|span| (ImportKind::Static, span, true, Vec::new(), attribute_clause(source, &tokens, index + 2))),
+                _ => from_clause(&tokens, index + 1).filter(|&(_, from)| !is_type_only(&tokens[index + 1..from])).map(|(span, from)| {
+                    let names = imported_names(source, &tokens[index + 1..from]);
+                    (ImportKind::Static, span, false, names, attribute_clause(source, &tokens, from + 2))
+                }),
+            },
+            Token::Word("export") => match tokens.get(index + 1) {
+                Some(Token::Punct(b'*' | b'{') | Token::Word("type")) => from_clause(&tokens, index + 1).filter(|&(_, from)| !is_type_only(&tokens[index + 1..from])).map(|(span, from)| {
+                    (ImportKind::Static, span, false, Vec::new(), attribute_clause(source, &tokens, from + 2))
+                }),
+                _ => None,
+            },
+            Token::Word("new") => asset_url(&tokens[index + 1..])
+                .map(|span| (ImportKind::Url, span, false, Vec::new(), Vec::new())),
+            Token::Word("require") if tokens.get(index + 1) == Some(&Token::Punct(b'(')) => string_at(index + 2)
+                .filter(|_| tokens.get(index + 3) == Some(&Token::Punct(b')')))
+                .map(|span| (ImportKind::Require, span, false, Vec::new(), Vec::new())),
+            _ => None,
+        };
+        if let Some((kind, span, bare, names, attributes)) = found {
+            imports.push(ScannedImport { kind, span, bare, names, attributes });
+        }
+    }
+    imports
+}
+
+/// Byte range of the path in `new URL('./file', import.meta.url)`, given
+/// the tokens after `new`
+fn asset_url(tokens: &[Token]) -> Option<Range<usize>> {
+    let path = *tokens.get(2)?;
+    let Token::Str(start, end) = path else {
+        return None;
+    };
+    let expected = [
+        Token::Word("URL"), Token::Punct(b'('), path, Token::Punct(b','),
+        Token::Word("import"), Token::Punct(b'.'), Token::Word("meta"), Token::Punct(b'.'), Token::Word("url"), Token::Punct(b')'),
+    ];
+    tokens.starts_with(&expected).then_some(start..end)
+}
+
+/// Attributes of a `with { ... }` clause starting at `index`, or of the
+/// older `assert { ... }`
+fn attribute_clause(source: &str, tokens: &[Token], index: usize) -> Vec<(String, String)> {
+    match tokens.get(index) {
+        Some(Token::Word("with" | "assert")) => attribute_list(source, tokens, index + 1),
+        _ => Vec::new(),
+    }
+}
+
+/// Attributes in the options of an `import()` starting at `index`, as in
+/// `{ with: { type: 'json' } }`
+fn import_options(source: &str, tokens: &[Token], index: usize) -> Vec<(String, String)> {
+    if tokens.get(index) != Some(&Token::Punct(b'{')) || tokens.get(index + 2) != Some(&Token::Punct(b':')) {
+        return Vec::new();
+    }
+    match tokens[index + 1] {
+        Token::Word(key) if key == "with" || key == "assert" => attribute_list(source, tokens, index + 3),
+        Token::Str(start, end) if matches!(&source[start..end], "with" | "assert") => attribute_list(source, tokens, index + 3),
+        _ => Vec::new(),
+    }
+}
+
+/// Keys and string values of the object literal starting at `index`
+fn attribute_list(source: &str, tokens: &[Token], mut index: usize) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    if tokens.get(index) != Some(&Token::Punct(b'{')) {
+        return attributes;
+    }
+    index += 1;
+    loop {
+        let key = match tokens.get(index) {
+            Some(Token::Word(word)) => word.to_string(),
+            Some(&Token::Str(start, end)) => source[start..end].to_string(),
+            _ => return attributes,
+        };
+        let (Some(Token::Punct(b':')), Some(&Token::Str(start, end))) = (tokens.get(index + 1), tokens.get(index + 2)) else {
+            return attributes;
+        };
+        attributes.push((key, source[start..end].to_string()));
+        index += 3;
+        match tokens.get(index) {
+            Some(Token::Punct(b',')) => index += 1,
+            _ => return attributes,
+        }
+    }
+}
+
+/// An `import()` whose specifier isn't a string literal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedImport {
+    /// Byte range of the call, or just of `import` when the specifier isn't
+    /// a template literal
+    pub span: Range<usize>,
+    
+    /// Byte range of a template literal specifier, backticks included
+    pub template: Option<Range<usize>>,
+    
+    /// Text around the template's substitutions, as in `["./pages/", ".js"]`
+    pub parts: Vec<String>,
+}
+
+/// Calls of `import()` with computed specifiers, in source order
+pub fn scan_computed_imports(source: &str) -> Vec<ComputedImport> {
+    let tokens = tokenize(source);
+    let mut imports = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let Token::Word(word @ "import") = *token else {
+            continue;
+        };
+        if tokens.get(index + 1) != Some(&Token::Punct(b'(')) || (index > 0 && tokens[index - 1] == Token::Punct(b'.')) {
+            continue;
+        }
+        // Words are slices of the source
+        let start = word.as_ptr() as usize - source.as_ptr() as usize;
+        match tokens.get(index + 2) {
+            Some(Token::Str(..)) => {}
+            // A template without substitutions names a single file
+            Some(&Token::Template(open, close)) if close - open >= 2 && source[open..close].ends_with('`') => {}
+            Some(&Token::Template(open, _)) => {
+                let Some((close, parts)) = template_parts(source, &tokens[index + 2..]) else {
+                    continue;
+                };
+                let rest = source[close..].trim_start();
+                let span = match rest.strip_prefix(')') {
+                    Some(after) => start..source.len() - after.len(),
+                    None => start..start + "import".len(),
+                };
+                imports.push(ComputedImport { span, template: Some(open..close), parts });
+            }
+            _ => imports.push(ComputedImport { span: start..start + "import".len(), template: None, parts: Vec::new() }),
+        }
+    }
+    imports
+}
+
+/// End of the template literal starting the tokens, and the text around
+/// its substitutions
+fn template_parts(source: &str, tokens: &[Token]) -> Option<(usize, Vec<String>)> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    for token in tokens {
+        let Token::Template(start, end) = *token else {
+            continue;
+        };
+        let piece = &source[start..end];
+        let text = |suffix: usize| piece[1..piece.len() - suffix].to_string();
+        match (piece.starts_with('`'), piece.ends_with("${")) {
+            (true, true) => {
+                depth += 1;
+                if depth == 1 {
+                    parts.push(text(2));
+                }
+            }
+            (false, true) if depth == 1 => parts.push(text(2)),
+            (false, false) => {
+                if depth == 1 {
+                    parts.push(text(1));
+                    return Some((end, parts));
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether the bindings of an import or export statement only name types, as
+/// in `type { a }`, `type * as ns` or `{ type a, type b }`
+fn is_type_only(bindings: &[Token]) -> bool {
+    match bindings {
+        // `import type from './a'` and `import type, { b }` bind a value named `type`
+        [Token::Word("type"), next, ..] => !matches!(next, Token::Word("from") | Token::Punct(b',')),
+        [Token::Punct(b'{'), elements @ .., Token::Punct(b'}')] => {
+            let mut elements = elements.split(|t| *t == Token::Punct(b',')).filter(|element| !element.is_empty()).peekable();
+            elements.peek().is_some()
+                && elements.all(|element| matches!(element, [Token::Word("type"), rest @ ..] if !rest.is_empty() && rest[0] != Token::Word("as")))
+        }
+        _ => false,
+    }
+}
+
+/// Names imported by the bindings of an import statement, as in
+/// `a, { b as c, type d }`
+fn imported_names(source: &str, bindings: &[Token]) -> Vec<String> {
+    if let [Token::Word("type"), next, ..] = bindings {
+        if !matches!(next, Token::Word("from") | Token::Punct(b',')) {
+            return Vec::new();
+        }
+    }
+    
+    let mut names = Vec::new();
+    let mut index = 0;
+    while let Some(token) = bindings.get(index) {
+        match token {
+            // `* as ns`
+            Token::Punct(b'*') => index += 2,
+            Token::Punct(b'{') => {
+                let end = bindings[index..].iter().position(|t| *t == Token::Punct(b'}')).map_or(bindings.len(), |end| index + end);
+                for element in bindings[index + 1..end].split(|t| *t == Token::Punct(b',')) {
+                    if let Some(name) = element_names(source, element).map(|(name, _)| name) {
+                        names.push(name);
+                    }
+                }
+                index = end;
+            }
+            Token::Word(_) => names.push("default".to_string()),
+            _ => {}
+        }
+        index += 1;
+    }
+    names
+}
+
+/// The name a module exports and the local name of an element of an import
+/// or export list, as in `type a as b`
+fn element_names(source: &str, element: &[Token]) -> Option<(String, String)> {
+    let element = match element {
+        [Token::Word("type"), rest @ ..] if !rest.is_empty() && rest[0] != Token::Word("as") => rest,
+        element => element,
+    };
+    let name = |token: &Token| match *token {
+        Token::Word(word) => Some(word.to_string()),
+        Token::Str(start, end) => Some(source[start..end].to_string()),
+        _ => None,
+    };
+    let first = name(element.first()?)?;
+    match element {
+        [_, Token::Word("as"), alias] => Some((first, name(alias)?)),
+        _ => Some((first.clone(), first)),
+    }
+}
+
+/// The exports of a script
+pub fn scan_exports(source: &str) -> ModuleExports {
+    let tokens = tokenize(source);
+    let mut exports = ModuleExports::default();
+    for (index, token) in tokens.iter().enumerate() {
+        let after_dot = index > 0 && tokens[index - 1] == Token::Punct(b'.');
+        match token {
+            Token::Word("exports") if !after_dot && tokens.get(index + 1) != Some(&Token::Punct(b':')) => exports.commonjs = true,
+            Token::Word("module") if tokens.get(index + 1) == Some(&Token::Punct(b'.')) && tokens.get(index + 2) == Some(&Token::Word("exports")) => {
+                exports.commonjs = true;
+            }
+            Token::Word("export") if !after_dot => export_statement(source, &tokens, index + 1, &mut exports),
+            _ => {}
+        }
+    }
+    exports
+}
+
+/// Record what the export statement whose body starts at `index` exports
+fn export_statement(source: &str, tokens: &[Token], mut index: usize, exports: &mut ModuleExports) {
+    let specifier = |index: usize| from_clause(tokens, index).map(|(span, _)| source[span].to_string());
+    let word = |index: usize| match tokens.get(index) {
+        Some(Token::Word(word)) => Some(word.to_string()),
+        _ => None,
+    };
+    
+    match tokens.get(index) {
+        Some(Token::Punct(b'*')) => {
+            let Some(from) = specifier(index) else {
+                return;
+            };
+            match tokens.get(index + 1..index + 3) {
+                Some([Token::Word("as"), alias]) => {
+                    if let Some((name, _)) = element_names(source, &[*alias]) {
+                        exports.reexports.push(ReExport { name, imported: "*".to_string(), specifier: from });
+                    }
+                }
+                _ => exports.star.push(from),
+            }
+            return;
+        }
+        Some(Token::Word("type")) if tokens.get(index + 1) == Some(&Token::Punct(b'{')) => index += 1,
+        _ => {}
+    }
+    
+    match tokens.get(index) {
+        Some(Token::Punct(b'{')) => {
+            let end = tokens[index..].iter().position(|t| *t == Token::Punct(b'}')).map_or(tokens.len(), |end| index + end);
+            let from = specifier(index);
+            for element in tokens[index + 1..end].split(|t| *t == Token::Punct(b',')) {
+                let Some((local, name)) = element_names(source, element) else {
+                    continue;
+                };
+                match &from {
+                    Some(from) => exports.reexports.push(ReExport { name, imported: local, specifier: from.clone() }),
+                    None => exports.names.push(name),
+                }
+            }
+        }
+        Some(Token::Word("default")) => exports.names.push("default".to_string()),
+        // TypeScript's `export = value`
+        Some(Token::Punct(b'=')) => exports.commonjs = true,
+        Some(Token::Word(_)) => {
+            while matches!(tokens.get(index), Some(Token::Word("declare" | "abstract" | "async"))) {
+                index += 1;
+            }
+            match tokens.get(index) {
+                Some(Token::Word("function")) => {
+                    let name = if tokens.get(index + 1) == Some(&Token::Punct(b'*')) { index + 2 } else { index + 1 };
+                    exports.names.extend(word(name));
+                }
+                Some(Token::Word("const")) if tokens.get(index + 1) == Some(&Token::Word("enum")) => {
+                    exports.names.extend(word(index + 2));
+                }
+                Some(Token::Word("const" | "let" | "var" | "using")) => declared_names(tokens, index + 1, &mut exports.names),
+                Some(Token::Word("class" | "interface" | "enum" | "namespace" | "module" | "type" | "import")) => {
+                    exports.names.extend(word(index + 1));
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Names declared by the declarators of a `const`, `let` or `var` starting
+/// at `index`, including those in destructuring patterns
+fn declared_names(tokens: &[Token], mut index: usize, names: &mut Vec<String>) {
+    loop {
+        match tokens.get(index) {
+            Some(Token::Word(name)) => names.push(name.to_string()),
+            Some(Token::Punct(open @ (b'{' | b'['))) => {
+                let close = if *open == b'{' { b'}' } else { b']' };
+                let mut depth = 0;
+                while let Some(token) = tokens.get(index) {
+                    match token {
+                        Token::Punct(b'{' | b'[') => depth += 1,
+                        Token::Punct(b'}' | b']') => depth -= 1,
+                        Token::Word(name) => {
+                            let previous = tokens[index - 1];
+                            let binds = matches!(tokens.get(index + 1), Some(Token::Punct(b',' | b'}' | b']' | b'=')))
+                                && (matches!(previous, Token::Punct(b'{' | b'[' | b',' | b':'))
+                                    || (index >= 3 && tokens[index - 3..index].iter().all(|t| *t == Token::Punct(b'.'))));
+                            if binds {
+                                names.push(name.to_string());
+                            }
+                        }
+                        _ => {}
+                    }
+                    if depth == 0 && *token == Token::Punct(close) {
+                        break;
+                    }
+                    index += 1;
+                }
+            }
+            _ => return,
+        }
+        
+        // Skip the type and initializer to the next declarator
+        let mut depth = 0i32;
+        loop {
+            index += 1;
+            match tokens.get(index) {
+                None | Some(Token::Punct(b';')) => return,
+                Some(Token::Punct(b'(' | b'[' | b'{')) => depth += 1,
+                Some(Token::Punct(b')' | b']' | b'}')) => depth -= 1,
+                Some(Token::Punct(b',')) if depth == 0 => break,
+                Some(Token::Word("export" | "import" | "const" | "let" | "var" | "function" | "class")) if depth == 0 => return,
+                _ => {}
+            }
+            if depth < 0 {
+                return;
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Specifier of the `from` clause of an import or export statement whose
+/// bindings start at `index`, and the index of `from`
+fn from_clause(tokens: &[Token], mut index: usize) -> Option<(Range<usize>, usize)> {
+    let mut in_braces = false;
+    let mut closed = false;
+    while let Some(token) = tokens.get(index) {
+        match token {
+            Token::Word("from") if !in_braces => {
+                return match tokens.get(index + 1) {
+                    Some(&Token::Str(start, end)) => Some((start..end, index)),
+                    _ => None,
+                };
+            }
+            // Only `from` may follow the braces
+            _ if closed => return None,
+            Token::Punct(b'{') if !in_braces => in_braces = true,
+            Token::Punct(b'}') if in_braces => {
+                in_braces = false;
+                closed = true;
+            }
+            Token::Word("import" | "export") => return None,
+            Token::Word(_) | Token::Punct(b',' | b'*') => {}
+            // Arbitrary names, as in `export { "a-b" as ab }`
+            Token::Str(..) if in_braces => {}
+            _ => return None,
+        }
+        index += 1;
+    }
+    None
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    // Brace depth inside each open template substitution
+    let mut templates: Vec<usize> = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = source[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            }
+            b'/' if starts_regex(tokens.last()) => {
+                i = regex_end(bytes, i);
+                tokens.push(Token::Other);
+            }
+            quote @ (b'\'' | b'"') => {
+                let start = i + 1;
+                i = start;
+                let mut terminated = false;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'\\' => i += 2,
+                        b'\n' => break,
+                        b if b == quote => {
+                            terminated = true;
+                            break;
+                        }
+                        _ => i += 1,
+                    }
+                }
+                let end = i.min(bytes.len());
+                tokens.push(if terminated { Token::Str(start, end) } else { Token::Other });
+                i = end + usize::from(terminated);
+            }
+            b'`' => {
+                let (next, opened) = template_text(bytes, i + 1);
+                if opened {
+                    depth += 1;
+                    templates.push(depth);
+                }
+                tokens.push(Token::Template(i, next));
+                i = next;
+            }
+            b'{' => {
+                depth += 1;
+                tokens.push(Token::Punct(b'{'));
+                i += 1;
+            }
+            b'}' if templates.last() == Some(&depth) => {
+                templates.pop();
+                depth -= 1;
+                let (next, opened) = template_text(bytes, i + 1);
+                if opened {
+                    depth += 1;
+                    templates.push(depth);
+                }
+                tokens.push(Token::Template(i, next));
+                i = next;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                tokens.push(Token::Punct(b'}'));
+                i += 1;
+            }
+            b if is_word_byte(b) => {
+                let start = i;
+                while i < bytes.len() && is_word_byte(bytes[i]) {
+                    i += 1;
+                }
+                tokens.push(Token::Word(&source[start..i]));
+            }
+            b => {
+                tokens.push(Token::Punct(b));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Identifier and number characters; any non-ASCII byte counts, so
+/// multi-byte characters are never split
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte >= 0x80
+}
+
+/// Whether a `/` after `previous` starts a regular expression
+///
+/// After `}` it is taken as one: misreading a division as a regular
+/// expression skips at most the rest of the line, as they can't span lines.
+fn starts_regex(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(Token::Word(word)) => REGEX_KEYWORDS.contains(word),
+        Some(Token::Punct(punct)) => !matches!(punct, b')' | b']'),
+        Some(Token::Str(..) | Token::Template(..) | Token::Other) => false,
+    }
+}
+
+/// End of the regular expression starting at `start`, after its flags
+fn regex_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut in_class = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'\n' => return i,
+            b'[' => in_class = true,
+            b']' => in_class = false,
+            b'/' if !in_class => {
+                i += 1;
+                while i < bytes.len() && is_word_byte(bytes[i]) {
+                    i += 1;
+                }
+                return i;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Skip template literal text from `i`, returning where code resumes and
+/// whether that is inside a `${` substitution
+fn template_text(bytes: &[u8], mut i: usize) -> (usize, bool) {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => return (i + 1, false),
+            b'$' if bytes.get(i + 1) == Some(&b'{') => return (i + 2, true),
+            _ => i += 1,
+        }
+    }
+    (bytes.len(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn specifiers(source: &str) -> Vec<(ImportKind, &str)> {
+        scan(source).into_iter().map(|import| (import.kind, &source[import.span])).collect()
+    }
+    
+    #[test]
+    fn test_skips_comments_strings_and_regexes() {
+        let source = r#"
+            // import a from './commented';
+            /* export * from './block'; */
+            const text = "import b from './string'";
+            const pattern = /import c from '.\/regex'/g;
+            const template = `import('./template') ${ require('./inside') } done`;
+            loader.import('./method');
+            import {
+                d,
+                e as f,
+            } from './multiline';
+            export * as ns from "./star";
+            export { default } from './default';
+            export type { T } from './types';
+            import './polyfill';
+            const g = await import('./dynamic', { with: { type: 'json' } });
+            const ratio = (a) / 2; const h = require('./after-division');
+        "#;
+        assert_eq!(specifiers(source), [
+            (ImportKind::Require, "./inside"),
+            (ImportKind::Static, "./multiline"),
+            (ImportKind::Static, "./star"),
+            (ImportKind::Static, "./default"),
+            (ImportKind::Static, "./polyfill"),
+            (ImportKind::Dynamic, "./dynamic"),
+            (ImportKind::Require, "./after-division"),
+        ]);
+        let bare: Vec<&str> = scan(source).into_iter().filter(|import| import.bare).map(|import| &source[import.span]).collect();
+        assert_eq!(bare, ["./polyfill"]);
+    }
+    
+    #[test]
+    fn test_ignores_non_import_uses_of_keywords() {
+        let source = "const config = { import: './a', export: true };\nexport const b = 1;\nexport default function require(x) {}\nimport.meta.url;\n";
+        assert!(specifiers(source).is_empty());
+        
+        let source = "export { b }\nimport c from './c';\n";
+        assert_eq!(specifiers(source), [(ImportKind::Static, "./c")]);
+    }
+    
+    #[test]
+    fn test_imported_names() {
+        let source = "import a, { b, c as d, type e, 'f-g' as h } from './a';\nimport * as ns from './ns';\nimport type, * as t from './type';\n";
+        let names: Vec<Vec<String>> = scan(source).into_iter().map(|import| import.names).collect();
+        assert_eq!(names, [vec!["default", "b", "c", "e", "f-g"], vec![], vec!["default"]]);
+    }
+    
+    #[test]
+    fn test_skips_type_only_imports() {
+        let source = "import type { A } from './a';\nimport type B from './b';\nimport type * as C from './c';\nimport { type D, type E as F, } from './d';\nexport type { G } from './g';\nexport type * from './h';\nexport { type I } from './i';\nimport { type J, K } from './kept';\nimport type from './type';\nimport {} from './empty';\nexport { type as L } from './l';\n";
+        assert_eq!(specifiers(source), [
+            (ImportKind::Static, "./kept"),
+            (ImportKind::Static, "./type"),
+            (ImportKind::Static, "./empty"),
+            (ImportKind::Static, "./l"),
+        ]);
+    }
+    
+    #[test]
+    fn test_import_attributes() {
+        let source = "import data from './data.json' with { type: 'json' };\nimport sheet from './a.css' assert { \"type\": \"css\" };\nexport { b } from './b.js' with { type: 'json', mode: 'x' };\nimport './c.css' with { type: 'css' };\nconst d = await import('./d.json', { with: { type: 'json' } });\nimport e from './e.js';\n";
+        let attributes: Vec<Vec<(String, String)>> = scan(source).into_iter().map(|import| import.attributes).collect();
+        let pair = |key: &str, value: &str| (key.to_string(), value.to_string());
+        assert_eq!(attributes, [
+            vec![pair("type", "json")],
+            vec![pair("type", "css")],
+            vec![pair("type", "json"), pair("mode", "x")],
+            vec![pair("type", "css")],
+            vec![pair("type", "json")],
+            vec![],
+        ]);
+    }
+    
+    #[test]
+    fn test_asset_urls() {
+        let source = "const logo = new URL('./logo.png', import.meta.url);\nnew URL(\n  \"../fonts/a.woff2\",\n  import.meta.url\n).href;\nnew URL('./a.png', location.href);\nnew URL(base, import.meta.url);\nnew window.URL('./b.png', import.meta.url);\n";
+        assert_eq!(specifiers(source), [(ImportKind::Url, "./logo.png"), (ImportKind::Url, "../fonts/a.woff2")]);
+    }
+    
+    #[test]
+    fn test_scan_computed_imports() {
+        let source = "import(`./pages/${name}.js`);\nimport(`./${a}/${`x${b}`}.md`).then(show);\nimport(url);\nimport(`./static.js`);\nimport('./string.js');\n";
+        let imports = scan_computed_imports(source);
+        let found: Vec<(&str, Option<&str>, Vec<String>)> = imports
+            .iter()
+            .map(|import| (&source[import.span.clone()], import.template.clone().map(|t| &source[t]), import.parts.clone()))
+            .collect();
+        assert_eq!(found, [
+            ("import(`./pages/${name}.js`)", Some("`./pages/${name}.js`"), vec!["./pages/".to_string(), ".js".to_string()]),
+            ("import(`./${a}/${`x${b}`}.md`)", Some("`./${a}/${`x${b}`}.md`"), vec!["./".to_string(), "/".to_string(), ".md".to_string()]),
+            ("import", None, vec![]),
+        ]);
+    }
+    
+    #[test]
+    fn test_scan_exports() {
+        let source = r#"
+            export const a = 1, { b, c: [d, ...e] } = obj, f = g(1, 2);
+            export async function h() {}
+            export function* i() {}
+            export default class {}
+            export { j, k as l, m as "n-o" };
+            export { p as q } from './p';
+            export * as r from './r';
+            export * from './s';
+            export type { T } from './t';
+            export interface U {}
+            export declare abstract class V {}
+            export const enum W {}
+            obj.export = 1;
+        "#;
+        let exports = scan_exports(source);
+        assert_eq!(exports.names, ["a", "b", "d", "e", "f", "h", "i", "default", "j", "l", "n-o", "U", "V", "W"]);
+        let reexports: Vec<(&str, &str, &str)> = exports
+            .reexports
+            .iter()
+            .map(|r| (r.name.as_str(), r.imported.as_str(), r.specifier.as_str()))
+            .collect();
+        assert_eq!(reexports, [("q", "p", "./p"), ("r", "*", "./r"), ("T", "T", "./t")]);
+        assert_eq!(exports.star, ["./s"]);
+        assert!(!exports.commonjs);
+        
+        assert!(scan_exports("module.exports = { a };").commonjs);
+        assert!(scan_exports("exports.a = 1;").commonjs);
+        assert!(scan_exports("export = a;").commonjs);
+        assert!(scan_exports("const x = { exports: 1 };").is_opaque());
+    }
+}
@@ -0,0 +1,265 @@
+//! Package manager lockfiles
+//!
+//! A lockfile change can change what every bare import resolves to, so the
+//! lockfile is part of the keys of caches that depend on resolution. The text
+//! formats (`package-lock.json`, `pnpm-lock.yaml` and `yarn.lock`) are also
+//! parsed, so a file inside `node_modules` can be traced back to the package
+//! version the lockfile declares. `bun.lockb` is binary and only hashed.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::utils::hash_parts;
+use crate::vfs::FileSystem;
+
+/// Lockfiles of the supported package managers
+pub const LOCKFILES: &[&str] = &["package-lock.json", "pnpm-lock.yaml", "yarn.lock", "bun.lockb", "bun.lock"];
+
+/// Hash of the lockfiles of the project containing `dir`, found in the
+/// nearest ancestor that has any, so workspace packages share the root's
+pub fn lockfile_hash(dir: &Path, fs: &dyn FileSystem) -> String {
+    let contents: Vec<Vec<u8>> = match lockfile_dir(dir, fs) {
+        Some(root) => LOCKFILES.iter().map(|lockfile| fs.read(&root.join(lockfile)).unwrap_or_default()).collect(),
+        None => Vec::new(),
+    };
+    hash_parts(&contents.iter().map(Vec::as_slice).collect::<Vec<_>>())
+}
+
+/// Nearest ancestor of `dir` with a lockfile
+fn lockfile_dir(dir: &Path, fs: &dyn FileSystem) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|root| LOCKFILES.iter().any(|lockfile| fs.is_file(&root.join(lockfile))))
+        .map(Path::to_path_buf)
+}
+
+/// A package version declared by a lockfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+}
+
+/// The packages a lockfile declares
+#[derive(Debug, Default)]
+pub struct Lockfile {
+    /// Directory holding the lockfile
+    root: PathBuf,
+    
+    /// Packages by install directory relative to the root, as in
+    /// `node_modules/a/node_modules/b`; only `package-lock.json` has these
+    by_path: HashMap<String, LockedPackage>,
+    
+    /// Versions declared for each package name
+    by_name: HashMap<String, BTreeSet<String>>,
+}
+
+impl Lockfile {
+    /// Load the lockfile of the project containing `dir`, if it has one in
+    /// a text format
+    pub fn discover(dir: &Path, fs: &dyn FileSystem) -> Option<Self> {
+        let root = lockfile_dir(dir, fs)?;
+        let read = |name: &str| fs.read_to_string(&root.join(name)).ok();
+        let mut lockfile = if let Some(contents) = read("package-lock.json") {
+            Self::parse_package_lock(&serde_json::from_str(&contents).ok()?)
+        } else if let Some(contents) = read("pnpm-lock.yaml") {
+            Self::parse_pnpm_lock(&contents)
+        } else if let Some(contents) = read("yarn.lock") {
+            Self::parse_yarn_lock(&contents)
+        } else {
+            return None;
+        };
+        debug!("Found {} locked package(s) in {}", lockfile.by_name.len(), root.display());
+        lockfile.root = root;
+        Some(lockfile)
+    }
+    
+    fn insert(&mut self, path: Option<String>, name: String, version: String) {
+        self.by_name.entry(name.clone()).or_default().insert(version.clone());
+        if let Some(path) = path {
+            self.by_path.insert(path, LockedPackage { name, version });
+        }
+    }
+    
+    /// `package-lock.json`, with install paths (v2 and v3) or nested
+    /// dependencies (v1)
+    fn parse_package_lock(data: &Value) -> Self {
+        let mut lockfile = Self::default();
+        if let Some(packages) = data.get("packages").and_then(Value::as_object) {
+            for (path, package) in packages {
+                let Some(version) = package.get("version").and_then(Value::as_str) else {
+                    continue;
+                };
+                let Some((_, installed_name)) = path.rsplit_once("node_modules/") else {
+                    continue;
+                };
+                let name = package.get("name").and_then(Value::as_str).unwrap_or(installed_name);
+                lockfile.insert(Some(path.clone()), name.to_string(), version.to_string());
+            }
+        } else if let Some(dependencies) = data.get("dependencies") {
+            lockfile.insert_nested(dependencies, "");
+        }
+        lockfile
+    }
+    
+    fn insert_nested(&mut self, dependencies: &Value, parent: &str) {
+        for (name, package) in dependencies.as_object().into_iter().flatten() {
+            let path = format!("{}node_modules/{}", parent, name);
+            if let Some(version) = package.get("version").and_then(Value::as_str) {
+                self.insert(Some(path.clone()), name.clone(), version.to_string());
+            }
+            if let Some(nested) = package.get("dependencies") {
+                self.insert_nested(nested, &format!("{}/", path));
+            }
+        }
+    }
+    
+    /// `pnpm-lock.yaml`, whose `packages` are keyed `/name@version` (v6),
+    /// `name@version` (v9) or `/name/version` (v5), with peers appended
+    fn parse_pnpm_lock(contents: &str) -> Self {
+        let mut lockfile = Self::default();
+        let mut in_packages = false;
+        for line in contents.lines() {
+            if !line.starts_with(' ') && !line.trim().is_empty() {
+                in_packages = line.trim_end() == "packages:";
+                continue;
+            }
+            let Some(key) = line.strip_prefix("  ").filter(|key| in_packages && !key.starts_with(' ')) else {
+                continue;
+            };
+            let key = key.trim_end().trim_end_matches(':').trim_matches(['\'', '"']).trim_start_matches('/');
+            let key = key.split('(').next().unwrap_or(key);
+            let scope_len = if key.starts_with('@') { key.find('/').map_or(0, |index| index + 1) } else { 0 };
+            if let Some(index) = key[scope_len..].find(['@', '/']).map(|index| scope_len + index) {
+                let version = key[index + 1..].split('_').next().unwrap_or_default();
+                lockfile.insert(None, key[..index].to_string(), version.to_string());
+            }
+        }
+        lockfile
+    }
+    
+    /// `yarn.lock`, in the classic or the Berry format
+    fn parse_yarn_lock(contents: &str) -> Self {
+        let mut lockfile = Self::default();
+        let mut name = None;
+        for line in contents.lines() {
+            if !line.starts_with(' ') && line.ends_with(':') {
+                let descriptor = line.split(", ").next().unwrap_or(line).trim_end_matches(':').trim_matches('"');
+                name = descriptor.get(1..).and_then(|rest| rest.find('@')).map(|index| descriptor[..index + 1].to_string());
+                continue;
+            }
+            let Some(version) = line.trim().strip_prefix("version") else {
+                continue;
+            };
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            if let Some(name) = name.take() {
+                lockfile.insert(None, name, version.to_string());
+            }
+        }
+        lockfile
+    }
+    
+    /// The package version declared for the installed package a file
+    /// belongs to
+    ///
+    /// Packages are matched by install path where the lockfile has them,
+    /// then by the version in a pnpm store directory, then by name when the
+    /// lockfile declares a single version.
+    pub fn package_for(&self, path: &Path) -> Option<LockedPackage> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let index = parts.iter().rposition(|part| part == "node_modules")?;
+        let name_len = if parts.get(index + 1)?.starts_with('@') { 2 } else { 1 };
+        let name = parts.get(index + 1..index + 1 + name_len)?.join("/");
+        
+        if let Some(package) = self.by_path.get(&parts[..index + 1 + name_len].join("/")) {
+            return Some(package.clone());
+        }
+        
+        let versions = self.by_name.get(&name)?;
+        let store_dir = (index >= 2 && parts[index - 2] == ".pnpm").then(|| &parts[index - 1]);
+        let stored_version = store_dir
+            .and_then(|dir| dir.strip_prefix(&format!("{}@", name.replace('/', "+"))))
+            .map(|version| version.split(['(', '_']).next().unwrap_or(version));
+        let version = match stored_version {
+            Some(version) => versions.get(version)?,
+            None if versions.len() == 1 => versions.first()?,
+            None => return None,
+        };
+        Some(LockedPackage { name, version: version.clone() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    fn locked(name: &str, version: &str) -> Option<LockedPackage> {
+        Some(LockedPackage { name: name.to_string(), version: version.to_string() })
+    }
+    
+    #[test]
+    fn test_package_lock() {
+        let fs = MemoryFs::new();
+        fs.write("/app/package-lock.json", r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/react": { "version": "18.2.0" },
+                "node_modules/@scope/ui": { "version": "1.0.0" },
+                "node_modules/@scope/ui/node_modules/react": { "version": "17.0.2" }
+            }
+        }"#);
+        
+        let lockfile = Lockfile::discover(Path::new("/app/packages/site"), &fs).unwrap();
+        assert_eq!(lockfile.package_for(Path::new("/app/node_modules/react/index.js")), locked("react", "18.2.0"));
+        assert_eq!(
+            lockfile.package_for(Path::new("/app/node_modules/@scope/ui/node_modules/react/cjs/react.js")),
+            locked("react", "17.0.2")
+        );
+        assert_eq!(lockfile.package_for(Path::new("/app/node_modules/@scope/ui")), locked("@scope/ui", "1.0.0"));
+        assert_eq!(lockfile.package_for(Path::new("/app/src/main.js")), None);
+        
+        let hash = lockfile_hash(Path::new("/app/src"), &fs);
+        fs.write("/app/package-lock.json", "{}");
+        assert_ne!(lockfile_hash(Path::new("/app/src"), &fs), hash);
+    }
+    
+    #[test]
+    fn test_pnpm_and_yarn_locks() {
+        let pnpm = Lockfile {
+            root: PathBuf::from("/app"),
+            ..Lockfile::parse_pnpm_lock(
+                "lockfileVersion: '9.0'\n\nimporters:\n  .:\n    dependencies:\n      react:\n        specifier: ^18.2.0\n        version: 18.2.0\n\npackages:\n  react@17.0.2:\n    resolution: {integrity: sha512-a}\n  react@18.2.0:\n    resolution: {integrity: sha512-b}\n  '@scope/ui@1.0.0(react@18.2.0)':\n    resolution: {integrity: sha512-c}\n",
+            )
+        };
+        assert_eq!(
+            pnpm.package_for(Path::new("/app/node_modules/.pnpm/react@17.0.2/node_modules/react/index.js")),
+            locked("react", "17.0.2")
+        );
+        assert_eq!(
+            pnpm.package_for(Path::new("/app/node_modules/.pnpm/@scope+ui@1.0.0_react@18.2.0/node_modules/@scope/ui/index.js")),
+            locked("@scope/ui", "1.0.0")
+        );
+        assert_eq!(pnpm.package_for(Path::new("/app/node_modules/react/index.js")), None);
+        assert_eq!(Lockfile::parse_pnpm_lock("packages:\n  /@scope/ui/1.0.0_react@18.2.0:\n").by_name["@scope/ui"].first().unwrap(), "1.0.0");
+        
+        let classic = Lockfile::parse_yarn_lock("# yarn lockfile v1\n\n\"@babel/core@^7.0.0\", \"@babel/core@^7.1.0\":\n  version \"7.23.0\"\n  resolved \"https://registry.yarnpkg.com/x\"\n\nreact@^18.2.0:\n  version \"18.2.0\"\n");
+        assert_eq!(classic.by_name["@babel/core"].first().unwrap(), "7.23.0");
+        assert_eq!(classic.by_name["react"].first().unwrap(), "18.2.0");
+        
+        let berry = Lockfile::parse_yarn_lock("__metadata:\n  version: 8\n\n\"react@npm:^18.2.0\":\n  version: 18.2.0\n  resolution: \"react@npm:18.2.0\"\n");
+        assert_eq!(berry.by_name.len(), 1);
+        assert_eq!(berry.by_name["react"].first().unwrap(), "18.2.0");
+    }
+}
@@ -1,73 +1,183 @@
 //! Module resolution
 //!
 //! Handles resolving import specifiers to actual file paths.
+//!
+//! [`Resolver`] is usable on its own, by tools that need to know where an
+//! import leads without building: [`Resolver::resolve`] returns the file,
+//! while [`Resolver::resolve_detailed`] and [`Resolver::resolve_many`] return
+//! a [`Resolution`] that also tells whether the import is external, which
+//! package it named, the `exports` conditions followed, and every candidate
+//! file tried, for diagnostics.
+
+mod exports;
+mod lexer;
+mod lockfile;
+mod pnp;
+mod workspace;
 
-use std::fs;
+use std::cell::RefCell;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use regex::Regex;
+use serde::Serialize;
 use tracing::debug;
 
-use crate::bundler::ModuleType;
+use crate::bundler::{distinct_specifiers, html, Dependency, ImportKind, Module, ModuleExports, ModuleType};
 use crate::config::Config;
+use crate::transform::{css, env, graphql};
+use crate::utils::normalize_path;
+use crate::vfs::{FileSystem, RealFs};
+use pnp::PnpManifest;
+use workspace::Workspace;
+
+pub use lockfile::{lockfile_hash, LockedPackage, Lockfile, LOCKFILES};
+pub(crate) use lexer::scan_computed_imports;
+
+/// Static imports, `require()` calls, `import()` calls and asset URLs in a
+/// script, in source order
+fn script_imports(source: &str) -> Vec<Dependency> {
+    lexer::scan(source)
+        .into_iter()
+        .map(|import| Dependency {
+            specifier: source[import.span.clone()].to_string(),
+            kind: import.kind,
+            span: import.span,
+            names: import.names,
+            attributes: import.attributes,
+        })
+        .collect()
+}
+
+/// Patterns for rewriting imports in bundled output
+pub(crate) static DYNAMIC_IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"import\s*\(\s*["']([^"']+)["']\s*(?:,\s*\{[^()]*?\}\s*)?\)"#).unwrap()
+});
 
-/// Regex patterns for extracting imports
-static IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"(?:import|export)\s+(?:(?:\{[^}]*\}|\*\s+as\s+\w+|\w+)\s+from\s+)?["']([^"']+)["']|require\s*\(\s*["']([^"']+)["']\s*\)"#).unwrap()
+pub(crate) static ASSET_URL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"new\s+URL\s*\(\s*["']([^"']+)["']\s*,\s*import\.meta\.url\s*\)"#).unwrap()
 });
 
-static DYNAMIC_IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"import\s*\(\s*["']([^"']+)["']\s*\)"#).unwrap()
+pub(crate) static REQUIRE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"require\s*\(\s*["']([^"']+)["']\s*\)"#).unwrap()
 });
 
+thread_local! {
+    /// The resolution `resolve_detailed` is recording on this thread
+    static TRACE: RefCell<Option<Resolution>> = const { RefCell::new(None) };
+}
+
+/// Where an import resolved, and how
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Resolution {
+    /// File the import resolved to; `None` when it is external or wasn't found
+    pub path: Option<PathBuf>,
+    
+    /// Whether the import names a package left out of the bundle
+    pub external: bool,
+    
+    /// package.json of the package the import named, when it has one
+    pub package_json: Option<PathBuf>,
+    
+    /// `exports` conditions followed to the file, outermost first
+    pub conditions_used: Vec<String>,
+    
+    /// Files tried, in order
+    pub candidates: Vec<PathBuf>,
+}
+
 /// Module resolver
 pub struct Resolver {
     /// Project configuration
-    #[allow(dead_code)]
     config: Arc<Config>,
+    
+    /// Filesystem used to probe candidate paths
+    fs: Arc<dyn FileSystem>,
+    
+    /// Packages of the surrounding monorepo, discovered on first use
+    workspace: OnceCell<Workspace>,
+    
+    /// Yarn Plug'n'Play package locations, loaded on first use
+    pnp: OnceCell<Option<PnpManifest>>,
+    
+    /// Directories from `NODE_PATH`, searched after `resolve.module_dirs`
+    node_path: Vec<PathBuf>,
 }
 
 impl Resolver {
     /// Create a new resolver
     pub fn new(config: Arc<Config>) -> Result<Self> {
+        Self::with_fs(config, RealFs::shared())
+    }
+    
+    /// Create a resolver over the given filesystem
+    pub fn with_fs(config: Arc<Config>, fs: Arc<dyn FileSystem>) -> Result<Self> {
         Ok(Self {
             config,
+            fs,
+            workspace: OnceCell::new(),
+            pnp: OnceCell::new(),
+            node_path: std::env::var_os("NODE_PATH")
+                .map(|paths| std::env::split_paths(&paths).filter(|path| !path.as_os_str().is_empty()).collect())
+                .unwrap_or_default(),
         })
     }
     
-    /// Extract import/require dependencies from source code
+    /// Extract the imports in source code, in source order
+    ///
+    /// Every import is a record, so a specifier imported twice appears
+    /// twice; [`distinct_specifiers`](crate::bundler::distinct_specifiers)
+    /// merges them.
     pub fn extract_dependencies(
         &self,
         source: &str,
         _file_path: &Path,
         module_type: &ModuleType,
-    ) -> Result<Vec<String>> {
+    ) -> Result<Vec<Dependency>> {
+        let records = |kind: ImportKind, imports: Vec<(String, std::ops::Range<usize>)>| -> Vec<Dependency> {
+            imports.into_iter().map(|(specifier, span)| Dependency { specifier, kind, span, names: Vec::new(), attributes: Vec::new() }).collect()
+        };
+        
+        // Pages depend on the scripts and stylesheets they reference
+        if *module_type == ModuleType::Html {
+            let references = html::extract_references(source);
+            return Ok(records(ImportKind::Html, references.into_iter().map(|r| (r.specifier, r.range)).collect()));
+        }
+        
+        // Documents depend on the documents they `#import`
+        if *module_type == ModuleType::Graphql {
+            return Ok(records(ImportKind::Graphql, graphql::import_ranges(source)));
+        }
+        
+        // Stylesheets depend on the stylesheets they `@import`, `@use` or `@forward`
+        if *module_type == ModuleType::Css {
+            return Ok(records(ImportKind::Css, css::import_ranges(source)));
+        }
+        
         // Skip non-JS modules for now
         if !module_type.is_js_like() {
             return Ok(Vec::new());
         }
         
-        let mut dependencies = Vec::new();
+        // Imports for another mode are left out
+        let applied = env::apply(source, &self.config.conditions);
+        let mut dependencies = script_imports(&applied);
         
-        // Find static imports/exports
-        for cap in IMPORT_REGEX.captures_iter(source) {
-            if let Some(specifier) = cap.get(1).or_else(|| cap.get(2)) {
-                let spec = specifier.as_str().to_string();
-                if !dependencies.contains(&spec) {
-                    dependencies.push(spec);
-                }
-            }
-        }
-        
-        // Find dynamic imports
-        for cap in DYNAMIC_IMPORT_REGEX.captures_iter(source) {
-            if let Some(specifier) = cap.get(1) {
-                let spec = specifier.as_str().to_string();
-                if !dependencies.contains(&spec) {
-                    dependencies.push(spec);
+        // The rest may have moved or lost a `?dev` suffix; point them back
+        // at the source as written
+        if let std::borrow::Cow::Owned(_) = applied {
+            let mut candidates = script_imports(source).into_iter();
+            for dependency in &mut dependencies {
+                let mut rest = candidates.clone();
+                let found = rest.find(|candidate| {
+                    candidate.kind == dependency.kind
+                        && candidate.specifier.strip_prefix(dependency.specifier.as_str()).is_some_and(|suffix| suffix.is_empty() || suffix.starts_with('?'))
+                });
+                if let Some(found) = found {
+                    dependency.span = found.span;
+                    candidates = rest;
                 }
             }
         }
@@ -77,14 +187,162 @@ impl Resolver {
         Ok(dependencies)
     }
     
+    /// What a script exports
+    pub fn extract_exports(&self, source: &str, module_type: &ModuleType) -> ModuleExports {
+        if !module_type.is_js_like() {
+            return ModuleExports::default();
+        }
+        lexer::scan_exports(&env::apply(source, &self.config.conditions))
+    }
+    
+    /// Specifiers that a module only imports dynamically via `import()`
+    ///
+    /// These start async chunks instead of being bundled with the importer.
+    pub fn extract_dynamic_imports(&self, source: &str, module_type: &ModuleType) -> Vec<String> {
+        if !module_type.is_js_like() {
+            return Vec::new();
+        }
+        
+        let source = env::apply(source, &self.config.conditions);
+        distinct_specifiers(&script_imports(&source))
+            .into_iter()
+            .filter_map(|(specifier, dynamic)| dynamic.then_some(specifier))
+            .collect()
+    }
+    
+    /// Specifiers that a module imports only for their side effects, with no
+    /// bindings, as in `import './polyfill'`
+    pub fn extract_side_effect_imports(&self, source: &str, module_type: &ModuleType) -> Vec<String> {
+        if !module_type.is_js_like() {
+            return Vec::new();
+        }
+        
+        let source = env::apply(source, &self.config.conditions);
+        let scanned = lexer::scan(&source);
+        let specifier = |import: &lexer::ScannedImport| &source[import.span.clone()];
+        let mut imports: Vec<String> = Vec::new();
+        for import in scanned.iter().filter(|import| import.bare) {
+            let spec = specifier(import);
+            let only_bare = scanned
+                .iter()
+                .filter(|other| other.kind != ImportKind::Dynamic && specifier(other) == spec)
+                .all(|other| other.bare);
+            if only_bare && !imports.iter().any(|i| i == spec) {
+                imports.push(spec.to_string());
+            }
+        }
+        imports
+    }
+    
+    /// Resolve an import specifier, recording how
+    pub fn resolve_detailed(&self, specifier: &str, from: &Path) -> Result<Resolution> {
+        let outer = TRACE.with(|trace| trace.replace(Some(Resolution::default())));
+        let path = self.resolve(specifier, from);
+        let mut resolution = TRACE.with(|trace| trace.replace(outer)).unwrap_or_default();
+        resolution.path = path?;
+        Ok(resolution)
+    }
+    
+    /// Resolve each of an importer's specifiers, in order
+    pub fn resolve_many<'a>(&self, specifiers: impl IntoIterator<Item = &'a str>, importer: &Path) -> Vec<Result<Resolution>> {
+        specifiers
+            .into_iter()
+            .map(|specifier| self.resolve_detailed(specifier, importer))
+            .collect()
+    }
+    
+    /// Update the resolution being recorded, if any
+    fn trace(&self, f: impl FnOnce(&mut Resolution)) {
+        TRACE.with(|trace| {
+            if let Some(resolution) = trace.borrow_mut().as_mut() {
+                f(resolution);
+            }
+        });
+    }
+    
+    /// Whether a candidate file exists, recording that it was tried
+    fn probe(&self, path: &Path) -> bool {
+        self.trace(|resolution| resolution.candidates.push(path.to_path_buf()));
+        self.fs.is_file(path)
+    }
+    
+    /// package.json of an installed package, in the first of its package
+    /// directories that has it
+    fn installed_package_json(&self, specifier: &str, from: &Path) -> Option<PathBuf> {
+        let (package_name, _) = split_package_specifier(specifier)?;
+        self.package_dirs(&package_name, from)
+            .into_iter()
+            .map(|dir| dir.join("package.json"))
+            .find(|package_json| self.fs.is_file(package_json))
+    }
+    
+    /// Directories the package `name` imported from `from` may be installed
+    /// in, in the order they're searched: its Yarn PnP location, then the
+    /// module directories
+    fn package_dirs(&self, name: &str, from: &Path) -> Vec<PathBuf> {
+        let pnp = self.pnp.get_or_init(|| {
+            let root = self.fs.canonicalize(&self.config.root).unwrap_or_else(|_| self.config.root.clone());
+            PnpManifest::discover(&root, self.fs.as_ref())
+        });
+        let mut dirs: Vec<PathBuf> = pnp.iter().filter_map(|pnp| pnp.package_dir(name, from)).collect();
+        dirs.extend(self.module_dirs(from).into_iter().map(|dir| dir.join(name)));
+        dirs
+    }
+    
+    /// Directories packages imported from `from` may be installed in, in the
+    /// order they're searched: each of `resolve.module_dirs`, then `NODE_PATH`
+    fn module_dirs(&self, from: &Path) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        for dir in &self.config.resolve.module_dirs {
+            if dir.contains(['/', '\\']) {
+                dirs.push(normalize_path(&self.config.root.join(dir)));
+            } else {
+                dirs.extend(from.ancestors().map(|ancestor| ancestor.join(dir)));
+            }
+        }
+        dirs.extend(self.node_path.iter().cloned());
+        dirs
+    }
+    
     /// Resolve an import specifier to an absolute file path
     pub fn resolve(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
+        self.resolve_as(specifier, from, ImportKind::Static)
+    }
+    
+    /// Resolve a specifier imported in a given way; packages' `exports`
+    /// maps pick `require` targets for `require()` calls and `import`
+    /// targets for anything else
+    pub fn resolve_as(&self, specifier: &str, from: &Path, kind: ImportKind) -> Result<Option<PathBuf>> {
         debug!("Resolving '{}' from '{}'", specifier, from.display());
         
-        // Skip external packages for now (bare specifiers)
+        if Module::detect_type(from) == ModuleType::Css {
+            return self.resolve_stylesheet(specifier, from);
+        }
+        
+        // Bare specifiers name packages: those listed in `resolve.external`
+        // are left out, workspace packages resolve to their source, and
+        // others to their installed files
         if !specifier.starts_with('.') && !specifier.starts_with('/') {
-            debug!("Skipping bare specifier: {}", specifier);
-            return Ok(None);
+            if self.is_external(specifier) {
+                debug!("Leaving external package: {}", specifier);
+                self.trace(|resolution| {
+                    resolution.external = true;
+                    resolution.package_json = self.installed_package_json(specifier, from);
+                });
+                return Ok(None);
+            }
+            if let Some(resolved) = self.resolve_workspace(specifier)? {
+                debug!("Resolved workspace package to: {}", resolved.display());
+                return Ok(Some(resolved));
+            }
+            let resolved = self.resolve_package(specifier, from, kind)?;
+            debug!("Resolved package to: {:?}", resolved);
+            return Ok(resolved);
+        }
+        
+        // Root-relative URLs in pages point into the project
+        if specifier.starts_with('/') && Module::detect_type(from) == ModuleType::Html {
+            return self.resolve_relative(specifier.trim_start_matches('/'), &self.config.root);
         }
         
         let base_dir = from.parent().unwrap_or(Path::new("."));
@@ -97,29 +355,45 @@ impl Resolver {
         Ok(resolved)
     }
     
+    /// Whether a bare specifier names a package in `resolve.external`
+    fn is_external(&self, specifier: &str) -> bool {
+        let Some((package_name, _)) = split_package_specifier(specifier) else {
+            return false;
+        };
+        self.config.resolve.external.iter().any(|name| *name == package_name || name == specifier)
+    }
+    
     /// Resolve a relative import
     fn resolve_relative(&self, specifier: &str, base_dir: &Path) -> Result<Option<PathBuf>> {
-        let target = base_dir.join(specifier);
+        let target = normalize_path(&base_dir.join(specifier));
         
         // Try exact path first
-        if target.is_file() {
+        if self.probe(&target) {
             return Ok(Some(target));
         }
         
+        // TypeScript under `NodeNext` imports `./foo.js` for `foo.ts`
+        let extension = target.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        for source_ext in typescript_sources(extension) {
+            let source = target.with_extension(source_ext);
+            if self.probe(&source) {
+                return Ok(Some(source));
+            }
+        }
+        
         // Try adding extensions
-        let extensions = ["js", "ts", "jsx", "tsx", "mjs", "cjs", "json"];
-        for ext in &extensions {
-            let with_ext = target.with_extension(ext);
-            if with_ext.is_file() {
+        for ext in EXTENSIONS {
+            let with_ext = append_extension(&target, ext);
+            if self.probe(&with_ext) {
                 return Ok(Some(with_ext));
             }
         }
         
         // Try as directory with index file
-        if target.is_dir() {
-            for ext in &extensions {
+        if self.fs.is_dir(&target) {
+            for ext in EXTENSIONS {
                 let index = target.join(format!("index.{}", ext));
-                if index.is_file() {
+                if self.probe(&index) {
                     return Ok(Some(index));
                 }
             }
@@ -129,91 +403,303 @@ impl Resolver {
         Ok(None)
     }
     
-    /// Resolve a bare import (from node_modules)
-    #[allow(dead_code)]
-    fn resolve_bare(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
-        let mut current = from.to_path_buf();
+    /// Resolve a stylesheet import the way Sass and Less do
+    ///
+    /// Specifiers are tried relative to the importer, then in each of
+    /// `css.include_paths`, then as packages in `node_modules`. `~pkg/file`
+    /// always names a package. Extensions may be left out, and `colors`
+    /// also finds the partial `_colors.scss`.
+    fn resolve_stylesheet(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
+        let base_dir = from.parent().unwrap_or(Path::new("."));
+        let extension = from.extension().and_then(|ext| ext.to_str()).unwrap_or("css");
         
-        // Walk up directory tree looking for node_modules
-        loop {
-            let node_modules = current.join("node_modules");
-            
-            if node_modules.is_dir() {
-                // Try to resolve in this node_modules
-                if let Some(resolved) = self.resolve_in_node_modules(&node_modules, specifier)? {
-                    return Ok(Some(resolved));
-                }
-            }
-            
-            // Move to parent directory
-            if !current.pop() {
-                break;
+        if let Some(package) = specifier.strip_prefix('~') {
+            return self.resolve_stylesheet_package(package, base_dir, extension);
+        }
+        
+        if let Some(found) = self.resolve_stylesheet_file(specifier, base_dir, extension) {
+            return Ok(Some(found));
+        }
+        if specifier.starts_with('.') || specifier.starts_with('/') {
+            return Ok(None);
+        }
+        
+        for dir in &self.config.css.include_paths {
+            let dir = self.config.root.join(dir);
+            if let Some(found) = self.resolve_stylesheet_file(specifier, &dir, extension) {
+                return Ok(Some(found));
             }
         }
         
-        Ok(None)
+        self.resolve_stylesheet_package(specifier, base_dir, extension)
     }
     
-    /// Resolve a module within a node_modules directory
-    fn resolve_in_node_modules(&self, node_modules: &Path, specifier: &str) -> Result<Option<PathBuf>> {
-        // Split specifier into package name and subpath
-        let (package_name, subpath) = if specifier.starts_with('@') {
-            // Scoped package: @scope/name or @scope/name/subpath
-            let parts: Vec<&str> = specifier.splitn(3, '/').collect();
-            if parts.len() < 2 {
-                return Ok(None);
+    /// Find a stylesheet in `dir`, trying extensions, partials and index files
+    fn resolve_stylesheet_file(&self, specifier: &str, dir: &Path, extension: &str) -> Option<PathBuf> {
+        let target = normalize_path(&dir.join(specifier));
+        if self.probe(&target) {
+            return Some(target);
+        }
+        
+        let extensions = stylesheet_extensions(extension);
+        let name = target.file_name()?.to_string_lossy().into_owned();
+        let partial = target.with_file_name(format!("_{}", name));
+        let mut candidates = extensions
+            .iter()
+            .flat_map(|ext| [append_extension(&target, ext), append_extension(&partial, ext)])
+            .chain(extensions.iter().flat_map(|ext| {
+                [target.join(format!("_index.{}", ext)), target.join(format!("index.{}", ext))]
+            }));
+        candidates.find(|candidate| self.probe(candidate))
+    }
+    
+    /// Find a stylesheet of a package in the first module directory that has it
+    ///
+    /// A package imported without a subpath resolves to its `package.json`
+    /// `sass` or `style` field, or to an index stylesheet.
+    fn resolve_stylesheet_package(&self, specifier: &str, from_dir: &Path, extension: &str) -> Result<Option<PathBuf>> {
+        let Some((package_name, subpath)) = split_package_specifier(specifier) else {
+            return Ok(None);
+        };
+        
+        for package_dir in self.package_dirs(&package_name, from_dir) {
+            if self.fs.is_dir(&package_dir) {
+                if let Some(sub) = &subpath {
+                    return Ok(self.resolve_stylesheet_file(sub, &package_dir, extension));
+                }
+                
+                let package_json = package_dir.join("package.json");
+                if self.fs.is_file(&package_json) {
+                    self.trace(|resolution| resolution.package_json = Some(package_json.clone()));
+                    let content = self.fs.read_to_string(&package_json)
+                        .with_context(|| format!("Failed to read {}", package_json.display()))?;
+                    let pkg: serde_json::Value = serde_json::from_str(&content)
+                        .with_context(|| format!("Failed to parse {}", package_json.display()))?;
+                    for field in ["sass", "style"] {
+                        if let Some(target) = pkg.get(field).and_then(|v| v.as_str()) {
+                            let target = normalize_path(&package_dir.join(target));
+                            if self.probe(&target) {
+                                return Ok(Some(target));
+                            }
+                        }
+                    }
+                }
+                return Ok(self.resolve_stylesheet_file("index", &package_dir, extension));
             }
-            let name = format!("{}/{}", parts[0], parts[1]);
-            let sub = if parts.len() > 2 {
-                Some(parts[2..].join("/"))
-            } else {
-                None
-            };
-            (name, sub)
-        } else {
-            // Regular package: name or name/subpath
-            let parts: Vec<&str> = specifier.splitn(2, '/').collect();
-            let name = parts[0].to_string();
-            let sub = parts.get(1).map(|s| s.to_string());
-            (name, sub)
+        }
+        Ok(None)
+    }
+    
+    /// Resolve an import of a workspace package to its source
+    ///
+    /// Only packages whose `exports` have one of `resolve.source_conditions`
+    /// for the imported path, or with a `source` field, resolve; others keep
+    /// being treated as external.
+    fn resolve_workspace(&self, specifier: &str) -> Result<Option<PathBuf>> {
+        let conditions = &self.config.resolve.source_conditions;
+        if conditions.is_empty() {
+            return Ok(None);
+        }
+        let Some((package_name, subpath)) = split_package_specifier(specifier) else {
+            return Ok(None);
+        };
+        
+        let workspace = self.workspace.get_or_init(|| {
+            let root = self.fs.canonicalize(&self.config.root).unwrap_or_else(|_| self.config.root.clone());
+            Workspace::discover(&root, self.fs.as_ref())
+        });
+        let Some(package_dir) = workspace.package_dir(&package_name) else {
+            return Ok(None);
         };
         
-        let package_dir = node_modules.join(&package_name);
+        let package_json = package_dir.join("package.json");
+        let content = self.fs.read_to_string(&package_json)
+            .with_context(|| format!("Failed to read {}", package_json.display()))?;
+        let pkg: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", package_json.display()))?;
         
-        if !package_dir.is_dir() {
+        let subpath = subpath.map_or_else(|| ".".to_string(), |sub| format!("./{}", sub));
+        let Some((target, used)) = workspace::source_target(&pkg, &subpath, conditions) else {
             return Ok(None);
+        };
+        self.trace(|resolution| {
+            resolution.package_json = Some(package_json.clone());
+            resolution.conditions_used = used;
+        });
+        
+        let resolved = self.resolve_relative(target.trim_start_matches("./"), package_dir)?;
+        if resolved.is_none() {
+            anyhow::bail!(
+                "Package '{}' maps '{}' to source '{}', which does not exist",
+                package_name,
+                subpath,
+                target
+            );
+        }
+        Ok(resolved)
+    }
+    
+    /// Resolve a bare import to a file in the first package directory for
+    /// `from` that has the package
+    pub fn resolve_bare(&self, specifier: &str, from: &Path) -> Result<Option<PathBuf>> {
+        self.resolve_package(specifier, from, ImportKind::Static)
+    }
+    
+    fn resolve_package(&self, specifier: &str, from: &Path, kind: ImportKind) -> Result<Option<PathBuf>> {
+        let Some((package_name, subpath)) = split_package_specifier(specifier) else {
+            return Ok(None);
+        };
+        for package_dir in self.package_dirs(&package_name, from) {
+            if self.fs.is_dir(&package_dir) {
+                if let Some(resolved) = self.resolve_in_package(&package_dir, &package_name, subpath.as_deref(), kind)? {
+                    return Ok(Some(resolved));
+                }
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Resolve a subpath of an installed package, or its entry
+    ///
+    /// A package with an `exports` map can only be imported at the subpaths
+    /// it lists; otherwise subpaths are files in the package, and the entry
+    /// is its `module` or `main` field, or `index.js`.
+    fn resolve_in_package(&self, package_dir: &Path, package_name: &str, subpath: Option<&str>, kind: ImportKind) -> Result<Option<PathBuf>> {
+        let package_json = package_dir.join("package.json");
+        let pkg: Option<serde_json::Value> = if self.fs.is_file(&package_json) {
+            self.trace(|resolution| resolution.package_json = Some(package_json.clone()));
+            let content = self.fs.read_to_string(&package_json)
+                .with_context(|| format!("Failed to read {}", package_json.display()))?;
+            Some(serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", package_json.display()))?)
+        } else {
+            None
+        };
+        
+        if let Some(map) = pkg.as_ref().and_then(|pkg| pkg.get("exports")) {
+            return self.resolve_export(map, package_dir, package_name, subpath, kind).map(Some);
         }
         
         // If there's a subpath, resolve it directly
         if let Some(sub) = subpath {
-            return self.resolve_relative(&sub, &package_dir);
+            return self.resolve_relative(sub, package_dir);
         }
         
         // Otherwise, look at package.json for main/module entry
-        let package_json = package_dir.join("package.json");
-        
-        if package_json.is_file() {
-            let content = fs::read_to_string(&package_json)
-                .context("Failed to read package.json")?;
-            let pkg: serde_json::Value = serde_json::from_str(&content)
-                .context("Failed to parse package.json")?;
-            
+        if let Some(pkg) = pkg {
             // Try module field first (ESM)
             if let Some(module) = pkg.get("module").and_then(|v| v.as_str()) {
-                let module_path = package_dir.join(module);
-                if module_path.is_file() {
+                let module_path = normalize_path(&package_dir.join(module));
+                if self.probe(&module_path) {
                     return Ok(Some(module_path));
                 }
             }
             
             // Then try main field
             if let Some(main) = pkg.get("main").and_then(|v| v.as_str()) {
-                return self.resolve_relative(main, &package_dir);
+                return self.resolve_relative(main, package_dir);
             }
         }
         
         // Default to index.js
-        self.resolve_relative("index.js", &package_dir)
+        self.resolve_relative("index.js", package_dir)
+    }
+    
+    /// Resolve a subpath through a package's `exports` map
+    ///
+    /// Subpaths the map doesn't list, or lists under no condition that
+    /// applies, fail to resolve, as they do in Node.
+    fn resolve_export(
+        &self,
+        map: &serde_json::Value,
+        package_dir: &Path,
+        package_name: &str,
+        subpath: Option<&str>,
+        kind: ImportKind,
+    ) -> Result<PathBuf> {
+        let subpath = subpath.map_or_else(|| ".".to_string(), |sub| format!("./{}", sub));
+        let Some((entry, matched)) = exports::entry(map, &subpath) else {
+            anyhow::bail!("Package '{}' doesn't export '{}'", package_name, subpath);
+        };
+        
+        let conditions = self.export_conditions(kind);
+        let Some((target, used)) = exports::target(entry, matched, &conditions) else {
+            anyhow::bail!(
+                "Package '{}' doesn't export '{}' under any of the conditions {}",
+                package_name,
+                subpath,
+                conditions.join(", ")
+            );
+        };
+        self.trace(|resolution| resolution.conditions_used = used);
+        
+        let resolved = normalize_path(&package_dir.join(&target));
+        if !self.probe(&resolved) {
+            anyhow::bail!("Package '{}' exports '{}' as '{}', which does not exist", package_name, subpath, target);
+        }
+        Ok(resolved)
+    }
+    
+    /// Conditions that apply to `exports` maps, besides `default`: `browser`,
+    /// `import` or `require` by how the package is loaded, `module`, then the
+    /// build's mode and conditions
+    fn export_conditions(&self, kind: ImportKind) -> Vec<&str> {
+        let loader = if kind == ImportKind::Require { "require" } else { "import" };
+        let mut conditions = vec!["browser", loader, "module"];
+        conditions.extend(self.config.conditions.iter().map(String::as_str));
+        conditions
+    }
+}
+
+/// Extensions tried, in order, for imports that leave them out
+const EXTENSIONS: &[&str] = &["js", "ts", "jsx", "tsx", "mjs", "cjs", "json"];
+
+/// Extensions tried for stylesheet imports, the importer's own first
+fn stylesheet_extensions(importer: &str) -> Vec<&str> {
+    let mut extensions = vec![importer];
+    for ext in ["scss", "sass", "less", "css"] {
+        if ext != importer {
+            extensions.push(ext);
+        }
+    }
+    extensions
+}
+
+/// Extensions of the TypeScript sources an import of compiled output may mean
+fn typescript_sources(extension: &str) -> &'static [&'static str] {
+    match extension {
+        "js" => &["ts", "tsx"],
+        "jsx" => &["tsx"],
+        "mjs" => &["mts"],
+        "cjs" => &["cts"],
+        _ => &[],
+    }
+}
+
+/// `path` with `.ext` added, keeping any dots already in the file name
+fn append_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+/// Split a bare specifier into package name and subpath
+fn split_package_specifier(specifier: &str) -> Option<(String, Option<String>)> {
+    if specifier.starts_with('@') {
+        // Scoped package: @scope/name or @scope/name/subpath
+        let parts: Vec<&str> = specifier.splitn(3, '/').collect();
+        if parts.len() < 2 {
+            return None;
+        }
+        let name = format!("{}/{}", parts[0], parts[1]);
+        let sub = parts.get(2).map(|s| s.to_string());
+        Some((name, sub))
+    } else {
+        // Regular package: name or name/subpath
+        let parts: Vec<&str> = specifier.splitn(2, '/').collect();
+        let name = parts[0].to_string();
+        let sub = parts.get(1).map(|s| s.to_string());
+        Some((name, sub))
     }
 }
 
@@ -235,11 +721,28 @@ mod tests {
         let resolver = Resolver::new(Arc::new(config)).unwrap();
         let deps = resolver.extract_dependencies(source, Path::new("/test.js"), &ModuleType::JavaScript).unwrap();
         
-        assert!(deps.contains(&"./foo".to_string()));
-        assert!(deps.contains(&"./bar.js".to_string()));
-        assert!(deps.contains(&"../baz".to_string()));
-        assert!(deps.contains(&"./qux".to_string()));
-        assert!(deps.contains(&"./x".to_string()));
+        let specifiers: Vec<&str> = deps.iter().map(|dep| dep.specifier.as_str()).collect();
+        assert_eq!(specifiers, ["./foo", "./bar.js", "../baz", "./qux", "./x"]);
+        assert_eq!(deps[4].kind, ImportKind::Require);
+        assert_eq!(&source[deps[1].span.clone()], "./bar.js");
+        assert_eq!(deps[1].position(source), (2, 33));
+    }
+    
+    #[test]
+    fn test_dependency_records_keep_order_kind_and_position() {
+        let source = "import a from './a?dev';\nconst b = import('./b');\nimport { c } from './b';\nimport.meta.env.DEV && import('./c');\n";
+        let mut config = Config::default_config();
+        config.conditions = vec!["production".to_string()];
+        let resolver = Resolver::new(Arc::new(config)).unwrap();
+        let deps = resolver.extract_dependencies(source, Path::new("/test.js"), &ModuleType::JavaScript).unwrap();
+        
+        let records: Vec<(&str, ImportKind, &str)> = deps.iter().map(|dep| (dep.specifier.as_str(), dep.kind, &source[dep.span.clone()])).collect();
+        assert_eq!(records, [
+            ("./b", ImportKind::Dynamic, "./b"),
+            ("./b", ImportKind::Static, "./b"),
+            ("./c", ImportKind::Dynamic, "./c"),
+        ]);
+        assert_eq!(crate::bundler::distinct_specifiers(&deps), [("./b".to_string(), false), ("./c".to_string(), true)]);
     }
     
     #[test]
@@ -253,7 +756,279 @@ mod tests {
         let resolver = Resolver::new(Arc::new(config)).unwrap();
         let deps = resolver.extract_dependencies(source, Path::new("/test.js"), &ModuleType::JavaScript).unwrap();
         
-        assert!(deps.contains(&"./dynamic".to_string()));
-        assert!(deps.contains(&"./other".to_string()));
+        let specifiers: Vec<&str> = deps.iter().map(|dep| dep.specifier.as_str()).collect();
+        assert_eq!(specifiers, ["./dynamic", "./other"]);
+        assert!(deps.iter().all(|dep| dep.kind == ImportKind::Dynamic));
+    }
+    
+    #[test]
+    fn test_extract_side_effect_imports() {
+        let source = r#"
+            import './polyfill';
+            import "./styles.css";
+            import './both.js';
+            import { x } from './both.js';
+            import y from './y';
+        "#;
+        
+        let config = Config::default_config();
+        let resolver = Resolver::new(Arc::new(config)).unwrap();
+        let imports = resolver.extract_side_effect_imports(source, &ModuleType::JavaScript);
+        
+        assert_eq!(imports, vec!["./polyfill".to_string(), "./styles.css".to_string()]);
+    }
+    
+    #[test]
+    fn test_resolve_workspace_package_to_source() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/repo/pnpm-workspace.yaml", "packages:\n  - 'packages/*'\n  - 'apps/*'\n");
+        fs.write("/repo/packages/ui/package.json", r#"{
+            "name": "@acme/ui",
+            "exports": { ".": { "source": "./src/index.ts", "default": "./dist/index.js" } }
+        }"#);
+        fs.write("/repo/packages/ui/src/index.ts", "export const ui = 1;");
+        fs.write("/repo/packages/built/package.json", r#"{ "name": "built", "main": "dist/index.js" }"#);
+        fs.write("/repo/apps/web/src/main.js", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/repo/apps/web");
+        let resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        let from = Path::new("/repo/apps/web/src/main.js");
+        
+        assert_eq!(resolver.resolve("@acme/ui", from).unwrap(), Some(PathBuf::from("/repo/packages/ui/src/index.ts")));
+        assert_eq!(resolver.resolve("built", from).unwrap(), None);
+        assert_eq!(resolver.resolve("react", from).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_resolve_many_records_resolutions() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/repo/pnpm-workspace.yaml", "packages:\n  - 'packages/*'\n");
+        fs.write("/repo/packages/ui/package.json", r#"{
+            "name": "@acme/ui",
+            "exports": { ".": { "import": { "source": "./src/index.ts" }, "default": "./dist/index.js" } }
+        }"#);
+        fs.write("/repo/packages/ui/src/index.ts", "");
+        fs.write("/repo/node_modules/react/package.json", r#"{ "name": "react" }"#);
+        fs.write("/repo/src/util.ts", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/repo");
+        config.resolve.external = vec!["react".to_string()];
+        let resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        let resolutions: Vec<Resolution> = resolver
+            .resolve_many(["./util", "@acme/ui", "react", "./missing"], Path::new("/repo/src/main.ts"))
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+        
+        assert_eq!(resolutions[0].path, Some(PathBuf::from("/repo/src/util.ts")));
+        assert_eq!(resolutions[0].candidates, vec![PathBuf::from("/repo/src/util"), PathBuf::from("/repo/src/util.js"), PathBuf::from("/repo/src/util.ts")]);
+        
+        assert_eq!(resolutions[1].path, Some(PathBuf::from("/repo/packages/ui/src/index.ts")));
+        assert_eq!(resolutions[1].package_json, Some(PathBuf::from("/repo/packages/ui/package.json")));
+        assert_eq!(resolutions[1].conditions_used, vec!["import", "source"]);
+        
+        assert!(resolutions[2].external);
+        assert_eq!(resolutions[2].path, None);
+        assert_eq!(resolutions[2].package_json, Some(PathBuf::from("/repo/node_modules/react/package.json")));
+        
+        assert!(!resolutions[3].external);
+        assert_eq!(resolutions[3].path, None);
+        assert!(resolutions[3].candidates.len() > 1);
+    }
+    
+    #[test]
+    fn test_resolve_packages_unless_external() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/repo/node_modules/react/package.json", r#"{ "name": "react", "main": "index.js" }"#);
+        fs.write("/repo/node_modules/react/index.js", "");
+        fs.write("/repo/node_modules/react/jsx-runtime.js", "");
+        fs.write("/repo/node_modules/@acme/icons/package.json", r#"{ "name": "@acme/icons", "module": "esm/index.js" }"#);
+        fs.write("/repo/node_modules/@acme/icons/esm/index.js", "");
+        fs.write("/repo/node_modules/lodash/index.js", "");
+        fs.write("/repo/node_modules/lodash/fp.js", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/repo");
+        let resolver = Resolver::with_fs(Arc::new(config.clone()), fs.clone()).unwrap();
+        let from = Path::new("/repo/src/main.js");
+        assert_eq!(resolver.resolve("react", from).unwrap(), Some(PathBuf::from("/repo/node_modules/react/index.js")));
+        assert_eq!(resolver.resolve("react/jsx-runtime", from).unwrap(), Some(PathBuf::from("/repo/node_modules/react/jsx-runtime.js")));
+        assert_eq!(resolver.resolve("@acme/icons", from).unwrap(), Some(PathBuf::from("/repo/node_modules/@acme/icons/esm/index.js")));
+        assert_eq!(resolver.resolve("vue", from).unwrap(), None);
+        
+        config.resolve.external = vec!["react".to_string(), "lodash/fp".to_string()];
+        let resolver = Resolver::with_fs(Arc::new(config), fs).unwrap();
+        assert!(resolver.resolve_detailed("react/jsx-runtime", from).unwrap().external);
+        assert_eq!(resolver.resolve("react", from).unwrap(), None);
+        assert_eq!(resolver.resolve("lodash/fp", from).unwrap(), None);
+        assert_eq!(resolver.resolve("lodash", from).unwrap(), Some(PathBuf::from("/repo/node_modules/lodash/index.js")));
+        assert!(!resolver.resolve_detailed("vue", from).unwrap().external);
+    }
+    
+    #[test]
+    fn test_resolve_package_exports() {
+        let fs = Arc::new(crate::vfs::MemoryFs::new());
+        fs.write("/repo/node_modules/preact/package.json", r#"{
+            "name": "preact",
+            "main": "dist/preact.js",
+            "exports": {
+                ".": { "types": "./src/index.d.ts", "browser": "./dist/preact.module.js", "require": "./dist/preact.js" },
+                "./hooks": { "import": "./hooks/dist/hooks.mjs", "require": "./hooks/dist/hooks.js" },
+                "./compat/*": "./compat/*.js",
+                "./missing": "./missing.js"
+            }
+        }"#);
+        fs.write("/repo/node_modules/preact/dist/preact.module.js", "");
+        fs.write("/repo/node_modules/preact/dist/preact.js", "");
+        fs.write("/repo/node_modules/preact/hooks/dist/hooks.mjs", "");
+        fs.write("/repo/node_modules/preact/hooks/dist/hooks.js", "");
+        fs.write("/repo/node_modules/preact/compat/server.js", "");
+        fs.write("/repo/node_modules/nanoid/package.json", r#"{ "name": "nanoid", "exports": { "import": "./index.js", "require": "./index.cjs" } }"#);
+        fs.write("/repo/node_modules/nanoid/index.js", "");
+        fs.write("/repo/node_modules/nanoid/index.cjs", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/repo");
+        let resolver = Resolver::with_fs(Arc::new(config), fs).unwrap();
+        let from = Path::new("/repo/src/main.js");
+        let resolve = |specifier: &str, kind: ImportKind| resolver.resolve_as(specifier, from, kind).unwrap().unwrap();
+        
+        assert_eq!(resolve("preact", ImportKind::Static), PathBuf::from("/repo/node_modules/preact/dist/preact.module.js"));
+        assert_eq!(resolve("preact/hooks", ImportKind::Static), PathBuf::from("/repo/node_modules/preact/hooks/dist/hooks.mjs"));
+        assert_eq!(resolve("preact/hooks", ImportKind::Require), PathBuf::from("/repo/node_modules/preact/hooks/dist/hooks.js"));
+        assert_eq!(resolve("preact/compat/server", ImportKind::Static), PathBuf::from("/repo/node_modules/preact/compat/server.js"));
+        assert_eq!(resolve("nanoid", ImportKind::Dynamic), PathBuf::from("/repo/node_modules/nanoid/index.js"));
+        assert_eq!(resolve("nanoid", ImportKind::Require), PathBuf::from("/repo/node_modules/nanoid/index.cjs"));
+        assert_eq!(resolver.resolve_detailed("preact/hooks", from).unwrap().conditions_used, vec!["import"]);
+        
+        let error = |specifier: &str| resolver.resolve(specifier, from).unwrap_err().to_string();
+        assert_eq!(error("preact/dist/preact.js"), "Package 'preact' doesn't export './dist/preact.js'");
+        assert_eq!(error("preact/missing"), "Package 'preact' exports './missing' as './missing.js', which does not exist");
+        assert_eq!(error("nanoid/index.cjs"), "Package 'nanoid' doesn't export './index.cjs'");
+    }
+    
+    #[test]
+    fn test_resolve_bare_in_module_dirs() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/repo/web_modules/a/index.js", "");
+        fs.write("/repo/node_modules/a/index.js", "");
+        fs.write("/repo/node_modules/b/index.js", "");
+        fs.write("/repo/vendor/modules/c/index.js", "");
+        fs.write("/shared/d/index.js", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/repo");
+        config.resolve.module_dirs = vec!["web_modules".to_string(), "node_modules".to_string(), "./vendor/modules".to_string()];
+        let mut resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        resolver.node_path = vec![PathBuf::from("/shared")];
+        let resolve = |specifier: &str| resolver.resolve_bare(specifier, Path::new("/repo/src/main.js")).unwrap();
+        
+        assert_eq!(resolve("a"), Some(PathBuf::from("/repo/web_modules/a/index.js")));
+        assert_eq!(resolve("b"), Some(PathBuf::from("/repo/node_modules/b/index.js")));
+        assert_eq!(resolve("c"), Some(PathBuf::from("/repo/vendor/modules/c/index.js")));
+        assert_eq!(resolve("d"), Some(PathBuf::from("/shared/d/index.js")));
+        assert_eq!(resolve("e"), None);
+    }
+    
+    #[test]
+    fn test_resolve_bare_with_pnp() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/repo/.pnp.data.json", r#"{
+            "packageRegistryData": [
+                [null, [[null, { "packageLocation": "./", "packageDependencies": [["react", "npm:18.2.0"]] }]]],
+                ["react", [["npm:18.2.0", {
+                    "packageLocation": "./.yarn/cache/react-npm-18.2.0-1eae08fee2.zip/node_modules/react/",
+                    "packageDependencies": []
+                }]]]
+            ]
+        }"#);
+        let react = PathBuf::from("/repo/.yarn/cache/react-npm-18.2.0-1eae08fee2.zip/node_modules/react");
+        fs.write(react.join("package.json"), r#"{ "name": "react", "main": "index.js" }"#);
+        fs.write(react.join("index.js"), "");
+        fs.write(react.join("jsx-runtime.js"), "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/repo");
+        let resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        let from = Path::new("/repo/src/main.js");
+        
+        assert_eq!(resolver.resolve_bare("react", from).unwrap(), Some(react.join("index.js")));
+        assert_eq!(resolver.resolve_bare("react/jsx-runtime", from).unwrap(), Some(react.join("jsx-runtime.js")));
+        assert_eq!(resolver.resolve_detailed("react", from).unwrap().package_json, Some(react.join("package.json")));
+        assert_eq!(resolver.resolve_bare("vue", from).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_resolve_normalizes_paths() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/app/src/lib/a.ts", "");
+        fs.write("/app/src/components/index.tsx", "");
+        fs.write("/app/src/main.ts", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/app");
+        let resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        let from = Path::new("/app/src/main.ts");
+        
+        // Dot segments never reach module IDs, so one file gets one module
+        assert_eq!(resolver.resolve("./lib/../lib/./a", from).unwrap(), Some(PathBuf::from("/app/src/lib/a.ts")));
+        assert_eq!(resolver.resolve("../src/components", from).unwrap(), Some(PathBuf::from("/app/src/components/index.tsx")));
+        assert_eq!(resolver.resolve("./lib/a.ts", Path::new("/app/src/./main.ts")).unwrap(), Some(PathBuf::from("/app/src/lib/a.ts")));
+    }
+    
+    #[test]
+    fn test_resolve_stylesheets() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/app/src/styles/_colors.scss", "");
+        fs.write("/app/src/styles/theme/_index.scss", "");
+        fs.write("/app/design/_spacing.scss", "");
+        fs.write("/app/node_modules/@acme/tokens/package.json", r#"{ "sass": "scss/tokens.scss" }"#);
+        fs.write("/app/node_modules/@acme/tokens/scss/tokens.scss", "");
+        fs.write("/app/node_modules/@acme/tokens/scss/_colors.scss", "");
+        fs.write("/app/node_modules/normalize.css/normalize.css", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/app");
+        config.css.include_paths = vec!["design".to_string()];
+        let resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        let from = Path::new("/app/src/main.scss");
+        let resolve = |specifier| resolver.resolve(specifier, from).unwrap();
+        
+        assert_eq!(resolve("./styles/colors"), Some(PathBuf::from("/app/src/styles/_colors.scss")));
+        assert_eq!(resolve("styles/theme"), Some(PathBuf::from("/app/src/styles/theme/_index.scss")));
+        assert_eq!(resolve("spacing"), Some(PathBuf::from("/app/design/_spacing.scss")));
+        assert_eq!(resolve("@acme/tokens"), Some(PathBuf::from("/app/node_modules/@acme/tokens/scss/tokens.scss")));
+        assert_eq!(resolve("~@acme/tokens/scss/colors"), Some(PathBuf::from("/app/node_modules/@acme/tokens/scss/_colors.scss")));
+        assert_eq!(resolve("~normalize.css/normalize.css"), Some(PathBuf::from("/app/node_modules/normalize.css/normalize.css")));
+        assert_eq!(resolve("./missing"), None);
+    }
+    
+    #[test]
+    fn test_resolve_typescript_sources_of_js_imports() {
+        let fs = crate::vfs::MemoryFs::new();
+        fs.write("/app/src/util.ts", "");
+        fs.write("/app/src/view.tsx", "");
+        fs.write("/app/src/esm.mts", "");
+        fs.write("/app/src/legacy.cts", "");
+        fs.write("/app/src/both.js", "");
+        fs.write("/app/src/both.ts", "");
+        fs.write("/app/src/app.config.ts", "");
+        
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/app");
+        let resolver = Resolver::with_fs(Arc::new(config), Arc::new(fs)).unwrap();
+        let from = Path::new("/app/src/main.ts");
+        let resolve = |specifier| resolver.resolve(specifier, from).unwrap();
+        
+        assert_eq!(resolve("./util.js"), Some(PathBuf::from("/app/src/util.ts")));
+        assert_eq!(resolve("./view.js"), Some(PathBuf::from("/app/src/view.tsx")));
+        assert_eq!(resolve("./view.jsx"), Some(PathBuf::from("/app/src/view.tsx")));
+        assert_eq!(resolve("./esm.mjs"), Some(PathBuf::from("/app/src/esm.mts")));
+        assert_eq!(resolve("./legacy.cjs"), Some(PathBuf::from("/app/src/legacy.cts")));
+        assert_eq!(resolve("./both.js"), Some(PathBuf::from("/app/src/both.js")));
+        assert_eq!(resolve("./app.config"), Some(PathBuf::from("/app/src/app.config.ts")));
+        assert_eq!(resolve("./util.mjs"), None);
     }
 }
@@ -0,0 +1,176 @@
+//! Yarn Plug'n'Play
+//!
+//! Projects installed with Yarn PnP have no `node_modules`. Instead,
+//! `.pnp.data.json`, written when `pnpEnableInlining` is `false`, lists
+//! where each package is, usually a directory inside a zip archive in
+//! `.yarn/cache` or an unplugged copy in `.yarn/unplugged`, and which
+//! packages each may import. Packages inside archives are read by the real
+//! filesystem directly.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use tracing::debug;
+
+use crate::utils::normalize_path;
+use crate::vfs::FileSystem;
+
+/// Name of the manifest file
+const MANIFEST_FILE: &str = ".pnp.data.json";
+
+/// A package by name and reference; the project's top level has neither
+type Locator = (Option<String>, Option<String>);
+
+/// Package locations and dependencies from `.pnp.data.json`
+#[derive(Debug, Default)]
+pub struct PnpManifest {
+    /// Packages by locator
+    packages: HashMap<Locator, PnpPackage>,
+    
+    /// Package locations, deepest first, for finding a file's package; the
+    /// top level comes after the workspace sharing its location
+    locations: Vec<(PathBuf, Locator)>,
+}
+
+#[derive(Debug)]
+struct PnpPackage {
+    location: PathBuf,
+    
+    /// Locators of the packages it may import, by the name it imports them
+    /// under; `None` for peer dependencies that weren't provided
+    dependencies: HashMap<String, Option<Locator>>,
+}
+
+impl PnpManifest {
+    /// Load the manifest of the project containing `dir`, if it uses PnP
+    pub fn discover(dir: &Path, fs: &dyn FileSystem) -> Option<Self> {
+        let root = dir.ancestors().find(|root| fs.is_file(&root.join(MANIFEST_FILE)))?;
+        let path = root.join(MANIFEST_FILE);
+        let data: Value = match fs.read_to_string(&path).map(|contents| serde_json::from_str(&contents)) {
+            Ok(Ok(data)) => data,
+            _ => {
+                debug!("Ignoring unreadable {}", path.display());
+                return None;
+            }
+        };
+        let manifest = Self::parse(root, &data);
+        debug!("Found {} PnP package(s) in {}", manifest.packages.len(), path.display());
+        Some(manifest)
+    }
+    
+    /// Read the `packageRegistryData` of a manifest in `root`
+    fn parse(root: &Path, data: &Value) -> Self {
+        let mut packages = HashMap::new();
+        let registry = data.get("packageRegistryData").and_then(Value::as_array).into_iter().flatten();
+        for entry in registry {
+            let name = entry.get(0).and_then(Value::as_str).map(str::to_string);
+            let references = entry.get(1).and_then(Value::as_array).into_iter().flatten();
+            for reference in references {
+                let (Some(version), Some(info)) = (reference.get(0), reference.get(1)) else {
+                    continue;
+                };
+                let Some(location) = info.get("packageLocation").and_then(Value::as_str) else {
+                    continue;
+                };
+                let dependencies = info
+                    .get("packageDependencies")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|dependency| {
+                        let dependency_name = dependency.get(0)?.as_str()?.to_string();
+                        let target = match dependency.get(1)? {
+                            Value::String(reference) => Some((Some(dependency_name.clone()), Some(reference.clone()))),
+                            // Aliases name the package and reference they stand for
+                            Value::Array(alias) => Some((
+                                alias.first()?.as_str().map(str::to_string),
+                                alias.get(1)?.as_str().map(str::to_string),
+                            )),
+                            _ => None,
+                        };
+                        Some((dependency_name, target))
+                    })
+                    .collect();
+                let locator = (name.clone(), version.as_str().map(str::to_string));
+                let location = normalize_path(&root.join(location));
+                packages.insert(locator, PnpPackage { location, dependencies });
+            }
+        }
+        
+        let mut locations: Vec<(PathBuf, Locator)> = packages
+            .iter()
+            .map(|(locator, package)| (package.location.clone(), locator.clone()))
+            .collect();
+        locations.sort_by_key(|(location, locator)| (std::cmp::Reverse(location.components().count()), locator.0.is_none()));
+        Self { packages, locations }
+    }
+    
+    /// Directory of the package `name` as imported from `from`
+    ///
+    /// Packages that don't list it fall back to the dependencies of the
+    /// project's top level, as Yarn does by default.
+    pub fn package_dir(&self, name: &str, from: &Path) -> Option<PathBuf> {
+        let top_level: Locator = (None, None);
+        let importer = self
+            .locations
+            .iter()
+            .find(|(location, _)| from.starts_with(location))
+            .map_or(&top_level, |(_, locator)| locator);
+        
+        let target = [importer, &top_level]
+            .into_iter()
+            .filter_map(|locator| self.packages.get(locator))
+            .find_map(|package| package.dependencies.get(name))?
+            .as_ref()?;
+        self.packages.get(target).map(|package| package.location.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_package_dirs() {
+        let data = serde_json::json!({
+            "packageRegistryData": [
+                [null, [[null, {
+                    "packageLocation": "./",
+                    "packageDependencies": [["app", "workspace:."], ["react", "npm:18.2.0"]]
+                }]]],
+                ["app", [["workspace:.", {
+                    "packageLocation": "./",
+                    "packageDependencies": [["react", "npm:18.2.0"], ["preact-compat", ["preact", "npm:10.0.0"]], ["vue", null]]
+                }]]],
+                ["react", [["npm:18.2.0", {
+                    "packageLocation": "./.yarn/cache/react-npm-18.2.0-1eae08fee2.zip/node_modules/react/",
+                    "packageDependencies": [["loose-envify", "npm:1.4.0"]]
+                }]]],
+                ["loose-envify", [["npm:1.4.0", {
+                    "packageLocation": "./.yarn/unplugged/loose-envify-npm-1.4.0/node_modules/loose-envify/",
+                    "packageDependencies": []
+                }]]],
+                ["preact", [["npm:10.0.0", {
+                    "packageLocation": "./.yarn/cache/preact-npm-10.0.0-5ab1c2.zip/node_modules/preact/",
+                    "packageDependencies": []
+                }]]]
+            ]
+        });
+        let manifest = PnpManifest::parse(Path::new("/app"), &data);
+        let react = PathBuf::from("/app/.yarn/cache/react-npm-18.2.0-1eae08fee2.zip/node_modules/react");
+        
+        assert_eq!(manifest.package_dir("react", Path::new("/app/src/main.js")), Some(react.clone()));
+        assert_eq!(
+            manifest.package_dir("loose-envify", &react.join("index.js")),
+            Some(PathBuf::from("/app/.yarn/unplugged/loose-envify-npm-1.4.0/node_modules/loose-envify"))
+        );
+        assert_eq!(
+            manifest.package_dir("preact-compat", Path::new("/app/src/main.js")),
+            Some(PathBuf::from("/app/.yarn/cache/preact-npm-10.0.0-5ab1c2.zip/node_modules/preact"))
+        );
+        assert_eq!(manifest.package_dir("loose-envify", Path::new("/app/src/main.js")), None);
+        assert_eq!(manifest.package_dir("vue", Path::new("/app/src/main.js")), None);
+        assert_eq!(manifest.package_dir("react", &react.join("index.js")), Some(react));
+    }
+}
@@ -0,0 +1,244 @@
+//! Monorepo workspace packages
+//!
+//! Imports of packages that live in the same workspace resolve to their
+//! source through the `source` field or a source export condition, so edits
+//! to a shared package show up without building it first.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSetBuilder};
+use serde_json::Value;
+use tracing::debug;
+
+use super::exports;
+use crate::vfs::FileSystem;
+
+/// Directories never searched for workspace packages
+const SKIPPED_DIRS: &[&str] = &["node_modules", "dist", "build", "target"];
+
+/// How deep below the workspace root packages are searched for
+const MAX_DEPTH: usize = 4;
+
+/// Packages of the workspace a project belongs to
+#[derive(Debug, Default)]
+pub struct Workspace {
+    /// Package directories by package name
+    packages: HashMap<String, PathBuf>,
+}
+
+impl Workspace {
+    /// Find the workspace containing `dir`
+    ///
+    /// The workspace root is the nearest ancestor whose `package.json` has a
+    /// `workspaces` field or that has a `pnpm-workspace.yaml`.
+    pub fn discover(dir: &Path, fs: &dyn FileSystem) -> Self {
+        for root in dir.ancestors() {
+            if let Some(patterns) = workspace_patterns(root, fs) {
+                let workspace = Self::load(root, &patterns, fs);
+                debug!("Found {} workspace package(s) in {}", workspace.packages.len(), root.display());
+                return workspace;
+            }
+        }
+        Self::default()
+    }
+    
+    fn load(root: &Path, patterns: &[String], fs: &dyn FileSystem) -> Self {
+        let mut globs = GlobSetBuilder::new();
+        for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+            let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+            if let Ok(glob) = GlobBuilder::new(pattern).literal_separator(true).build() {
+                globs.add(glob);
+            }
+        }
+        let Ok(globs) = globs.build() else {
+            return Self::default();
+        };
+        
+        let mut dirs = Vec::new();
+        collect_package_dirs(root, root, 0, fs, &mut dirs);
+        
+        let mut packages = HashMap::new();
+        for dir in dirs {
+            let relative = dir.strip_prefix(root).unwrap_or(&dir);
+            if !globs.is_match(relative) {
+                continue;
+            }
+            if let Some(name) = read_json(&dir.join("package.json"), fs).and_then(|pkg| pkg["name"].as_str().map(str::to_string)) {
+                packages.insert(name, dir);
+            }
+        }
+        Self { packages }
+    }
+    
+    /// Directory of a workspace package
+    pub fn package_dir(&self, name: &str) -> Option<&Path> {
+        self.packages.get(name).map(PathBuf::as_path)
+    }
+}
+
+/// Package globs from `package.json` `workspaces` or `pnpm-workspace.yaml`
+fn workspace_patterns(root: &Path, fs: &dyn FileSystem) -> Option<Vec<String>> {
+    if let Some(pkg) = read_json(&root.join("package.json"), fs) {
+        let workspaces = match &pkg["workspaces"] {
+            Value::Array(patterns) => Some(patterns),
+            Value::Object(config) => config.get("packages").and_then(Value::as_array),
+            _ => None,
+        };
+        if let Some(patterns) = workspaces {
+            return Some(patterns.iter().filter_map(|p| p.as_str().map(str::to_string)).collect());
+        }
+    }
+    
+    let pnpm = fs.read_to_string(&root.join("pnpm-workspace.yaml")).ok()?;
+    Some(pnpm_patterns(&pnpm))
+}
+
+/// The `packages` list of a `pnpm-workspace.yaml`
+fn pnpm_patterns(yaml: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if let (true, Some(item)) = (in_packages, trimmed.strip_prefix('-')) {
+            patterns.push(item.trim().trim_matches(['\'', '"']).to_string());
+        }
+    }
+    patterns
+}
+
+fn collect_package_dirs(root: &Path, dir: &Path, depth: usize, fs: &dyn FileSystem, dirs: &mut Vec<PathBuf>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    if dir != root && fs.is_file(&dir.join("package.json")) {
+        dirs.push(dir.to_path_buf());
+    }
+    for entry in fs.read_dir(dir).unwrap_or_default() {
+        let name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name) && fs.is_dir(&entry) {
+            collect_package_dirs(root, &entry, depth + 1, fs, dirs);
+        }
+    }
+}
+
+fn read_json(path: &Path, fs: &dyn FileSystem) -> Option<Value> {
+    serde_json::from_str(&fs.read_to_string(path).ok()?).ok()
+}
+
+/// Source file a package entry maps to under one of `conditions`
+///
+/// `subpath` is `.` for the package itself or `./name` for a subpath. The
+/// `source` field counts for the package itself. Returns the target as
+/// written, relative to the package directory, with the conditions followed
+/// to it, or `None` when the package doesn't point at its source.
+pub fn source_target(package_json: &Value, subpath: &str, conditions: &[String]) -> Option<(String, Vec<String>)> {
+    if let Some(map) = package_json.get("exports") {
+        if let Some(found) = exports::entry(map, subpath).and_then(|(entry, wildcard)| {
+            conditional_target(entry, conditions).map(|(target, used)| (target.replace('*', wildcard), used))
+        }) {
+            return Some(found);
+        }
+    }
+    
+    if subpath == "." && conditions.iter().any(|c| c == "source") {
+        let target = package_json.get("source").and_then(Value::as_str)?;
+        return Some((target.to_string(), vec!["source".to_string()]));
+    }
+    None
+}
+
+/// Follow conditions to a target, requiring one of `conditions` on the way
+///
+/// Other conditions a bundler honors (`import`, `module`, `browser`,
+/// `default`) are searched for a nested source condition. Returns the
+/// target with the conditions followed to it, outermost first.
+fn conditional_target<'a>(entry: &'a Value, conditions: &[String]) -> Option<(&'a str, Vec<String>)> {
+    let Value::Object(map) = entry else {
+        return None;
+    };
+    
+    for condition in conditions {
+        let found = match map.get(condition) {
+            Some(Value::String(target)) => Some((target.as_str(), Vec::new())),
+            Some(nested) => conditional_target(nested, conditions).or_else(|| plain_target(nested).map(|target| (target, Vec::new()))),
+            None => None,
+        };
+        if let Some((target, mut used)) = found {
+            used.insert(0, condition.clone());
+            return Some((target, used));
+        }
+    }
+    
+    ["import", "module", "browser", "default"].iter().find_map(|condition| {
+        let (target, mut used) = conditional_target(map.get(*condition)?, conditions)?;
+        used.insert(0, condition.to_string());
+        Some((target, used))
+    })
+}
+
+/// First string target of an entry, whatever its conditions
+fn plain_target(entry: &Value) -> Option<&str> {
+    match entry {
+        Value::String(target) => Some(target),
+        Value::Object(map) => map.values().find_map(plain_target),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    use serde_json::json;
+    
+    #[test]
+    fn test_discover_workspace() {
+        let fs = MemoryFs::new();
+        fs.write("/repo/package.json", r#"{ "workspaces": ["packages/*", "apps/*"] }"#);
+        fs.write("/repo/packages/ui/package.json", r#"{ "name": "@acme/ui" }"#);
+        fs.write("/repo/packages/ui/node_modules/dep/package.json", r#"{ "name": "dep" }"#);
+        fs.write("/repo/packages/nested/deep/package.json", r#"{ "name": "deep" }"#);
+        fs.write("/repo/apps/web/package.json", r#"{ "name": "web" }"#);
+        
+        let workspace = Workspace::discover(Path::new("/repo/apps/web"), &fs);
+        assert_eq!(workspace.package_dir("@acme/ui"), Some(Path::new("/repo/packages/ui")));
+        assert_eq!(workspace.package_dir("web"), Some(Path::new("/repo/apps/web")));
+        assert_eq!(workspace.package_dir("dep"), None);
+        assert_eq!(workspace.package_dir("deep"), None);
+        
+        assert_eq!(pnpm_patterns("packages:\n  - 'packages/*'\n  - \"!**/test/**\"\nother: 1\n"), ["packages/*", "!**/test/**"]);
+    }
+    
+    #[test]
+    fn test_source_target() {
+        let conditions = vec!["source".to_string(), "development".to_string()];
+        let pkg = json!({
+            "source": "src/main.ts",
+            "exports": {
+                ".": { "source": "./src/index.ts", "import": "./dist/index.js" },
+                "./button": { "import": { "development": "./src/button.tsx", "default": "./dist/button.js" } },
+                "./icons/*": { "source": "./src/icons/*.tsx", "default": "./dist/icons/*.js" },
+                "./dist-only": "./dist/only.js"
+            }
+        });
+        
+        let target = |pkg: &Value, subpath: &str, conditions: &[String]| source_target(pkg, subpath, conditions).map(|(target, _)| target);
+        assert_eq!(target(&pkg, ".", &conditions).as_deref(), Some("./src/index.ts"));
+        assert_eq!(target(&pkg, "./button", &conditions).as_deref(), Some("./src/button.tsx"));
+        assert_eq!(target(&pkg, "./icons/star", &conditions).as_deref(), Some("./src/icons/star.tsx"));
+        assert_eq!(target(&pkg, "./dist-only", &conditions), None);
+        assert_eq!(source_target(&pkg, "./button", &conditions).unwrap().1, vec!["import", "development"]);
+        
+        let pkg = json!({ "source": "src/main.ts", "main": "dist/main.js" });
+        assert_eq!(target(&pkg, ".", &conditions).as_deref(), Some("src/main.ts"));
+        assert_eq!(target(&pkg, ".", &["development".to_string()]), None);
+    }
+}
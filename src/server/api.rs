@@ -0,0 +1,489 @@
+//! Dev server introspection API
+//!
+//! JSON endpoints under `/__component/` exposing the live module graph and
+//! build state for devtools and editor integrations, plus token-protected
+//! endpoints that let external tools trigger rebuilds.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Handle;
+
+use super::{handle_file_changes, ServerState};
+use crate::bundler::{Dependency, Module, ModuleExports, ModuleGraph, ModuleId, ModuleType};
+use crate::config::Config;
+use crate::utils::{line_diff, path_to_module_id};
+
+/// Build state tracked by the dev server
+#[derive(Debug)]
+pub struct DevStats {
+    /// When the server started
+    pub started_at: Instant,
+    
+    /// Time taken by the initial graph build
+    pub initial_build: Option<Duration>,
+    
+    /// Number of incremental rebuilds triggered by file changes
+    pub rebuilds: u64,
+    
+    /// Duration of the most recent rebuild
+    pub last_rebuild: Option<Duration>,
+    
+    /// Most recent rebuilds, newest first
+    pub recent_rebuilds: VecDeque<RebuildRecord>,
+    
+    /// Bumped by every rebuild; rebuild endpoints use it as their ETag
+    pub generation: u64,
+}
+
+/// Number of rebuilds kept for the devtools page
+const RECENT_REBUILDS: usize = 50;
+
+impl DevStats {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            initial_build: None,
+            rebuilds: 0,
+            last_rebuild: None,
+            recent_rebuilds: VecDeque::new(),
+            generation: 0,
+        }
+    }
+    
+    /// Record a completed rebuild
+    pub fn record_rebuild(&mut self, path: &Path, duration: Duration) {
+        self.record(path_to_module_id(path), duration);
+    }
+    
+    /// Record a rebuild of the whole graph
+    pub fn record_full_rebuild(&mut self, duration: Duration) {
+        self.record("*".to_string(), duration);
+    }
+    
+    fn record(&mut self, path: String, duration: Duration) {
+        self.rebuilds += 1;
+        self.generation += 1;
+        self.last_rebuild = Some(duration);
+        self.recent_rebuilds.push_front(RebuildRecord {
+            path,
+            at_ms: as_ms(self.started_at.elapsed()),
+            duration_ms: as_ms(duration),
+        });
+        self.recent_rebuilds.truncate(RECENT_REBUILDS);
+    }
+}
+
+impl Default for DevStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A single incremental rebuild
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildRecord {
+    /// Changed file
+    pub path: String,
+    
+    /// Time since server start
+    pub at_ms: f64,
+    
+    /// Rebuild duration
+    pub duration_ms: f64,
+}
+
+/// Summary of a module in the graph
+#[derive(Debug, Serialize)]
+pub struct ModuleSummary {
+    pub id: ModuleId,
+    pub path: String,
+    pub module_type: ModuleType,
+    pub is_entry: bool,
+    pub dependencies: Vec<ModuleId>,
+    pub importers: Vec<ModuleId>,
+    pub size: usize,
+    pub transformed_size: Option<usize>,
+    pub transform_time_ms: Option<f64>,
+    pub invalidations: u32,
+}
+
+/// Full details of a single module
+#[derive(Debug, Serialize)]
+pub struct ModuleDetails {
+    #[serde(flatten)]
+    pub summary: ModuleSummary,
+    pub specifiers: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    pub exports: ModuleExports,
+    
+    /// Every name the module exports, following `export *`
+    pub export_names: Vec<String>,
+    
+    pub source: String,
+    pub transformed: Option<String>,
+    pub provenance: Vec<String>,
+    pub watch_files: Vec<String>,
+    pub meta: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Aggregate build statistics
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    pub uptime_ms: f64,
+    pub initial_build_ms: Option<f64>,
+    pub rebuilds: u64,
+    pub last_rebuild_ms: Option<f64>,
+    pub modules: usize,
+    pub total_transform_ms: f64,
+    pub invalidations: u64,
+    pub hmr_clients: usize,
+    pub generation: u64,
+    pub recent_rebuilds: Vec<RebuildRecord>,
+}
+
+/// `GET /__component/graph`
+pub async fn graph(State(state): State<Arc<ServerState>>) -> Json<Vec<ModuleSummary>> {
+    let graph = state.bundler.graph();
+    let graph = graph.read();
+    
+    let mut ids = graph.all_module_ids();
+    ids.sort_unstable();
+    
+    let modules = ids
+        .into_iter()
+        .filter_map(|id| {
+            graph
+                .get_module(id)
+                .map(|module| summarize(&graph, id, module, &state.config.root))
+        })
+        .collect();
+    
+    Json(modules)
+}
+
+/// `GET /__component/modules/:id`
+pub async fn module(
+    State(state): State<Arc<ServerState>>,
+    UrlPath(id): UrlPath<ModuleId>,
+) -> Response {
+    let graph = state.bundler.graph();
+    let graph = graph.read();
+    
+    match graph.get_module(id) {
+        Some(module) => Json(ModuleDetails {
+            summary: summarize(&graph, id, module, &state.config.root),
+            specifiers: module.specifiers(),
+            dependencies: module.dependencies.clone(),
+            exports: module.exports.clone(),
+            export_names: graph.export_names(id),
+            source: module.source.clone(),
+            transformed: module.transformed.clone(),
+            provenance: module.provenance.clone(),
+            watch_files: module.watch_files
+                .iter()
+                .map(|file| path_to_module_id(file.strip_prefix(&state.config.root).unwrap_or(file)))
+                .collect(),
+            meta: module.meta.clone(),
+        })
+        .into_response(),
+        None => (StatusCode::NOT_FOUND, format!("Module not found: {}", id)).into_response(),
+    }
+}
+
+/// A transform stage with a diff against the previous stage
+#[derive(Debug, Serialize)]
+pub struct StageDetails {
+    pub name: String,
+    pub changed: bool,
+    pub duration_ms: f64,
+    pub diff: String,
+    pub code: String,
+}
+
+/// `GET /__component/modules/:id/transforms`
+///
+/// Only populated when the server runs with `--inspect-transforms`.
+pub async fn module_transforms(
+    State(state): State<Arc<ServerState>>,
+    UrlPath(id): UrlPath<ModuleId>,
+) -> Response {
+    let graph = state.bundler.graph();
+    let graph = graph.read();
+    
+    let Some(module) = graph.get_module(id) else {
+        return (StatusCode::NOT_FOUND, format!("Module not found: {}", id)).into_response();
+    };
+    
+    let mut previous = module.source.as_str();
+    let stages: Vec<StageDetails> = module
+        .transform_stages
+        .iter()
+        .map(|stage| {
+            let diff = line_diff(previous, &stage.code);
+            previous = &stage.code;
+            StageDetails {
+                name: stage.name.clone(),
+                changed: stage.changed,
+                duration_ms: stage.duration_ms,
+                diff,
+                code: stage.code.clone(),
+            }
+        })
+        .collect();
+    
+    Json(stages).into_response()
+}
+
+/// `GET /__component/stats`
+pub async fn stats(State(state): State<Arc<ServerState>>) -> Json<StatsResponse> {
+    let graph = state.bundler.graph();
+    let graph = graph.read();
+    let stats = state.stats.read();
+    
+    let mut total_transform = Duration::ZERO;
+    let mut invalidations = 0u64;
+    for id in graph.all_module_ids() {
+        if let Some(module) = graph.get_module(id) {
+            total_transform += module.transform_time.unwrap_or_default();
+            invalidations += u64::from(module.invalidations);
+        }
+    }
+    
+    Json(StatsResponse {
+        uptime_ms: as_ms(stats.started_at.elapsed()),
+        initial_build_ms: stats.initial_build.map(as_ms),
+        rebuilds: stats.rebuilds,
+        last_rebuild_ms: stats.last_rebuild.map(as_ms),
+        modules: graph.len(),
+        total_transform_ms: as_ms(total_transform),
+        invalidations,
+        hmr_clients: state.hmr.client_count(),
+        generation: stats.generation,
+        recent_rebuilds: stats.recent_rebuilds.iter().cloned().collect(),
+    })
+}
+
+/// `GET /__component/config`
+pub async fn config(State(state): State<Arc<ServerState>>) -> Json<Config> {
+    Json((*state.config).clone())
+}
+
+/// Body of `POST /__component/invalidate`
+#[derive(Debug, Deserialize)]
+pub struct InvalidateRequest {
+    /// Changed files, relative to the project root or absolute
+    pub paths: Vec<String>,
+}
+
+/// Result of a rebuild requested through the API
+#[derive(Debug, Serialize)]
+pub struct RebuildResponse {
+    pub generation: u64,
+    pub duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `POST /__component/invalidate` - rebuild after changes to `paths`
+///
+/// Responds once the rebuild has finished, like a file watcher event that
+/// can be waited on.
+pub async fn invalidate(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(request): Json<InvalidateRequest>,
+) -> Response {
+    if let Some(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+    
+    let paths: Vec<PathBuf> = request.paths
+        .iter()
+        .map(|path| {
+            let path = state.config.root.join(path);
+            state.fs.canonicalize(&path).unwrap_or(path)
+        })
+        .collect();
+    rebuild_with(state, move |state, runtime| handle_file_changes(paths, state, runtime)).await
+}
+
+/// `POST /__component/rebuild` - rebuild the whole graph and reload clients
+pub async fn rebuild(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Some(rejection) = authorize(&state, &headers) {
+        return rejection;
+    }
+    
+    rebuild_with(state, |state, runtime| {
+        let started = Instant::now();
+        runtime.block_on(state.bundler.rebuild_graph())?;
+        state.stats.write().record_full_rebuild(started.elapsed());
+        state.hmr.send(super::HmrMessage::FullReload {
+            reason: "Rebuild requested".to_string(),
+        });
+        Ok(())
+    })
+    .await
+}
+
+/// Run a rebuild off the async runtime and report the new generation
+async fn rebuild_with<F>(state: Arc<ServerState>, rebuild: F) -> Response
+where
+    F: FnOnce(&ServerState, &Handle) -> anyhow::Result<()> + Send + 'static,
+{
+    let runtime = Handle::current();
+    let started = Instant::now();
+    let result = {
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || rebuild(&state, &runtime)).await
+    };
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(format!("{:#}", e)),
+        Err(e) => Some(format!("Rebuild panicked: {}", e)),
+    };
+    
+    let generation = state.stats.read().generation;
+    let status = if error.is_some() { StatusCode::INTERNAL_SERVER_ERROR } else { StatusCode::OK };
+    let body = RebuildResponse {
+        generation,
+        duration_ms: as_ms(started.elapsed()),
+        error,
+    };
+    (status, [(header::ETAG, etag(generation))], Json(body)).into_response()
+}
+
+/// Check the bearer token and an optional `If-Match` build generation,
+/// returning the response that rejects the request if either is wrong
+///
+/// A stale `If-Match` fails with 412, so a tool can skip rebuilding when
+/// something else already rebuilt since it last looked.
+fn authorize(state: &ServerState, headers: &HeaderMap) -> Option<Response> {
+    if !has_token(headers, &state.api_token) {
+        return Some((StatusCode::UNAUTHORIZED, "Missing or invalid API token").into_response());
+    }
+    
+    let current = etag(state.stats.read().generation);
+    if let Some(expected) = headers.get(header::IF_MATCH) {
+        let matches = expected
+            .to_str()
+            .unwrap_or("")
+            .split(',')
+            .any(|tag| tag.trim() == "*" || tag.trim() == current);
+        if !matches {
+            return Some((StatusCode::PRECONDITION_FAILED, [(header::ETAG, current)], "Build generation changed").into_response());
+        }
+    }
+    None
+}
+
+fn etag(generation: u64) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", generation)).expect("ETag is ASCII")
+}
+
+/// Whether a request carries `Bearer <token>`; nothing matches an empty token
+pub(super) fn has_token(headers: &HeaderMap, token: &str) -> bool {
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    !token.is_empty() && given.is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Random token for the rebuild endpoints when none is configured: 128 bits
+/// from the OS's secure random number generator, as hex
+pub fn generate_token() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow::anyhow!("Failed to generate an API token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// `GET /__component/` - devtools dashboard
+pub async fn devtools() -> Html<&'static str> {
+    Html(include_str!("devtools.html"))
+}
+
+/// Build the summary for a module
+fn summarize(graph: &ModuleGraph, id: ModuleId, module: &Module, root: &Path) -> ModuleSummary {
+    let mut dependencies = graph.get_dependencies(id);
+    dependencies.extend(graph.get_dynamic_dependencies(id));
+    dependencies.sort_unstable();
+    dependencies.dedup();
+    
+    let path = module.path.strip_prefix(root).unwrap_or(&module.path);
+    
+    ModuleSummary {
+        id,
+        path: path_to_module_id(path),
+        module_type: module.module_type.clone(),
+        is_entry: module.is_entry,
+        dependencies,
+        importers: graph.get_importers(id),
+        size: module.source.len(),
+        transformed_size: module.transformed.as_ref().map(|t| t.len()),
+        transform_time_ms: module.transform_time.map(as_ms),
+        invalidations: module.invalidations,
+    }
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn bearer(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+    
+    #[test]
+    fn test_has_token() {
+        assert!(has_token(&bearer("Bearer secret"), "secret"));
+        assert!(!has_token(&HeaderMap::new(), "secret"));
+        assert!(!has_token(&bearer("Bearer wrong"), "secret"));
+        assert!(!has_token(&bearer("secret"), "secret"));
+        
+        // An empty token would otherwise let `Bearer ` through
+        assert!(!has_token(&bearer("Bearer "), ""));
+        assert!(!has_token(&bearer("Bearer "), "secret"));
+    }
+    
+    #[test]
+    fn test_record_rebuilds() {
+        let mut stats = DevStats::new();
+        for i in 0..RECENT_REBUILDS + 5 {
+            stats.record_rebuild(Path::new(&format!("src/{}.js", i)), Duration::from_millis(i as u64));
+        }
+        assert_eq!(stats.rebuilds, RECENT_REBUILDS as u64 + 5);
+        assert_eq!(stats.last_rebuild, Some(Duration::from_millis(RECENT_REBUILDS as u64 + 4)));
+        
+        // Newest first, keeping only the most recent ones
+        assert_eq!(stats.recent_rebuilds.len(), RECENT_REBUILDS);
+        assert_eq!(stats.recent_rebuilds[0].path, format!("src/{}.js", RECENT_REBUILDS + 4));
+        assert_eq!(stats.recent_rebuilds[RECENT_REBUILDS - 1].path, "src/5.js");
+    }
+    
+    #[test]
+    fn test_generate_token() {
+        let token = generate_token().unwrap();
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token().unwrap());
+    }
+}
@@ -0,0 +1,194 @@
+// Component HMR Client
+const PROTOCOL_VERSION = __HMR_PROTOCOL_VERSION__;
+const config = __HMR_CONFIG__;
+
+// Connect through whatever host/port/protocol served the page unless
+// configured otherwise (reverse proxies, TLS termination, port remaps)
+const protocol = config.protocol || (location.protocol === 'https:' ? 'wss' : 'ws');
+const host = config.host || location.hostname;
+const port = config.port || location.port;
+const ws = new WebSocket(`${protocol}://${host}${port ? ':' + port : ''}${config.path}`);
+const listeners = new Map();
+
+// Custom event channel shared with plugins and framework integrations
+const hot = {
+  on(event, cb) {
+    if (!listeners.has(event)) listeners.set(event, []);
+    listeners.get(event).push(cb);
+  },
+  off(event, cb) {
+    const cbs = listeners.get(event);
+    if (cbs) listeners.set(event, cbs.filter(fn => fn !== cb));
+  },
+  send(event, data) {
+    const payload = JSON.stringify({ type: 'custom', event, data });
+    if (ws.readyState === WebSocket.OPEN) {
+      ws.send(payload);
+    } else {
+      ws.addEventListener('open', () => ws.send(payload), { once: true });
+    }
+  }
+};
+window.__component_hot__ = hot;
+
+// Modules with an `import.meta.hot`, by URL: the `accept()` callbacks
+// for updates of themselves or of modules they import, and `dispose()`
+// callbacks run before they're replaced
+const hotModules = new Map();
+// `import.meta.hot.data` of each module, kept across its versions
+const hotData = new Map();
+
+// The `import.meta.hot` of the module served from `url`
+export function createHotContext(url) {
+  const module = { accepts: [], disposers: [] };
+  hotModules.set(url, module);
+  if (!hotData.has(url)) hotData.set(url, {});
+  return {
+    get data() {
+      return hotData.get(url);
+    },
+    accept(deps, cb) {
+      if (typeof deps === 'function' || deps === undefined) {
+        module.accepts.push({ deps: [url], cb: deps ? ([updated]) => deps(updated) : null });
+      } else if (typeof deps === 'string') {
+        module.accepts.push({ deps: [deps], cb: cb ? ([updated]) => cb(updated) : null });
+      } else {
+        module.accepts.push({ deps, cb: cb || null });
+      }
+    },
+    dispose(cb) {
+      module.disposers.push(cb);
+    },
+    on: hot.on,
+    off: hot.off,
+    send: hot.send
+  };
+}
+
+// Import a module again as of an update, and hand it to the callbacks of
+// the module accepting it; modules the page hasn't loaded are skipped
+async function applyUpdate(update) {
+  const boundary = hotModules.get(update.path);
+  if (!boundary) return;
+  const replaced = hotModules.get(update.accepted_path);
+  if (replaced) {
+    const data = hotData.get(update.accepted_path);
+    replaced.disposers.forEach(cb => cb(data));
+  }
+  
+  const separator = update.accepted_path.includes('?') ? '&' : '?';
+  const updated = await import(update.accepted_path + separator + 't=' + update.timestamp);
+  boundary.accepts
+    .filter(accept => accept.deps.includes(update.accepted_path))
+    .forEach(accept => {
+      if (accept.cb) accept.cb(accept.deps.map(dep => dep === update.accepted_path ? updated : undefined));
+    });
+  console.log('[Component] Hot updated:', update.accepted_path);
+}
+
+// Updates are applied one at a time, in the order they arrive
+let updates = Promise.resolve();
+
+function dispatch(event, data) {
+  (listeners.get(event) || []).forEach(cb => cb(data));
+}
+
+ws.onopen = function() {
+  ws.send(JSON.stringify({ type: 'hello', protocol: PROTOCOL_VERSION }));
+};
+
+function clearOverlay() {
+  const existing = document.getElementById('__component_overlay');
+  if (existing) existing.remove();
+}
+
+function showOverlay(message) {
+  clearOverlay();
+  const overlay = document.createElement('div');
+  overlay.id = '__component_overlay';
+  overlay.style.cssText = 'position:fixed;inset:0;z-index:99999;background:rgba(0,0,0,0.85);' +
+    'color:#e8e8e8;font:14px/1.5 ui-monospace,Menlo,Consolas,monospace;padding:32px;overflow:auto';
+  
+  const title = document.createElement('pre');
+  title.style.cssText = 'color:#ff5555;white-space:pre-wrap;margin:0 0 16px';
+  title.textContent = message.message;
+  overlay.appendChild(title);
+  
+  if (message.file) {
+    const location = message.file + (message.line ? ':' + message.line : '') +
+      (message.column ? ':' + message.column : '');
+    const link = document.createElement('a');
+    link.href = '#';
+    link.textContent = location;
+    link.title = 'Open in editor';
+    link.style.cssText = 'color:#8be9fd;text-decoration:underline;cursor:pointer';
+    link.onclick = function(e) {
+      e.preventDefault();
+      const params = new URLSearchParams({ file: message.file });
+      if (message.line) params.set('line', message.line);
+      if (message.column) params.set('column', message.column);
+      fetch(config.base + '__open-in-editor?' + params.toString());
+    };
+    overlay.appendChild(link);
+  }
+  
+  overlay.addEventListener('click', function(e) {
+    if (e.target === overlay) clearOverlay();
+  });
+  document.body.appendChild(overlay);
+}
+
+ws.onmessage = function(event) {
+  const message = JSON.parse(event.data);
+  
+  if (message.type !== 'error' && message.type !== 'custom') {
+    clearOverlay();
+  }
+  
+  switch (message.type) {
+    case 'full-reload':
+      console.log('[Component] Full reload:', message.reason);
+      location.reload();
+      break;
+      
+    case 'css-update':
+      console.log('[Component] CSS update:', message.path);
+      // Find and reload CSS
+      const links = document.querySelectorAll('link[rel="stylesheet"]');
+      links.forEach(link => {
+        const url = new URL(link.href);
+        url.searchParams.set('t', Date.now());
+        link.href = url.toString();
+      });
+      break;
+      
+    case 'js-update':
+      updates = updates.then(() => applyUpdate(message)).catch(e => {
+        console.error('[Component] Hot update of ' + message.accepted_path + ' failed:', e);
+      });
+      break;
+      
+    case 'error':
+      console.error('[Component] Build error:', message.message);
+      showOverlay(message);
+      break;
+    
+    case 'custom':
+      dispatch(message.event, message.data);
+      break;
+    
+    case 'connected':
+      if (message.protocol !== PROTOCOL_VERSION) {
+        console.warn('[Component] HMR protocol mismatch, reloading');
+        location.reload();
+        return;
+      }
+      console.log('[Component] HMR connected');
+      break;
+  }
+};
+
+ws.onclose = function() {
+  console.log('[Component] HMR disconnected, attempting to reconnect...');
+  setTimeout(() => location.reload(), 1000);
+};
@@ -0,0 +1,141 @@
+//! Packages from `node_modules` under `/@deps/`
+//!
+//! Modules are served unbundled in dev, so the browser sees their bare
+//! imports. Those that resolve to an installed package are rewritten to
+//! `/@deps/<specifier>.js`, as in `/@deps/react.js` or
+//! `/@deps/react-dom/client.js`; that URL redirects to the file the package
+//! exports, served by its path inside the package, so relative imports
+//! between a package's files keep working and no filesystem path is exposed.
+//!
+//! Packages are served as installed rather than pre-bundled, so they must
+//! ship ES modules.
+
+use std::path::{Component, Path};
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use tracing::error;
+
+use super::ServerState;
+use crate::utils::path_to_module_id;
+
+/// URL namespace packages are served under, below the base path
+pub const DEPS_PATH: &str = "/@deps/";
+
+/// Static and dynamic imports and re-exports, with the specifier captured
+static IMPORT_SPECIFIER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"((?:^|[^\w$.])(?:import|export)\s*(?:\(\s*|[\w$*{}\s,]*?\s*from\s*)?)(["'])([^"'\n]+)["']"#).unwrap()
+});
+
+/// Point the bare imports of a served module at `/@deps/`
+///
+/// `is_package` tells which specifiers name installed packages; others, such
+/// as workspace packages and virtual modules, are left alone.
+pub fn rewrite_bare_imports(code: &str, base: &str, is_package: impl Fn(&str) -> bool) -> String {
+    rewrite_imports(code, |specifier| {
+        (is_bare(specifier) && is_package(specifier)).then(|| deps_url(base, specifier))
+    })
+}
+
+/// Replace the specifiers of a module's imports and re-exports with the
+/// URLs `rewrite` gives, leaving those it returns `None` for as written
+pub fn rewrite_imports(code: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    IMPORT_SPECIFIER_REGEX
+        .replace_all(code, |caps: &Captures| match rewrite(&caps[3]) {
+            Some(url) => format!("{}{}{}{}", &caps[1], &caps[2], url, &caps[2]),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Whether a specifier names a package rather than a file or URL
+pub fn is_bare(specifier: &str) -> bool {
+    !specifier.starts_with(['.', '/', '#']) && !specifier.contains(':')
+}
+
+/// URL a package specifier is served from
+pub fn deps_url(base: &str, specifier: &str) -> String {
+    let extension = Path::new(specifier).extension().and_then(|e| e.to_str());
+    let suffix = if matches!(extension, Some("js" | "mjs" | "cjs")) { "" } else { ".js" };
+    format!("{}{}{}{}", base.trim_end_matches('/'), DEPS_PATH, specifier, suffix)
+}
+
+/// A package file's path below the module directory it was found in, one of
+/// `node_modules` or the names of `resolve.module_dirs`
+fn package_path(file: &Path, module_dirs: &[String]) -> Option<String> {
+    let is_module_dir = |component: &Component| {
+        component.as_os_str() == "node_modules"
+            || module_dirs.iter().any(|dir| Path::new(dir).file_name() == Some(component.as_os_str()))
+    };
+    let components: Vec<Component> = file.components().collect();
+    let index = components.iter().rposition(is_module_dir)?;
+    let inside: std::path::PathBuf = components[index + 1..].iter().collect();
+    Some(path_to_module_id(&inside))
+}
+
+/// Handle `/@deps/<specifier>`: redirect a package entry to its file, and
+/// serve files by their path inside the package
+pub async fn serve_dep(
+    State(state): State<Arc<ServerState>>,
+    axum::extract::Path(path): axum::extract::Path<String>,
+) -> Response {
+    let not_found = || (StatusCode::NOT_FOUND, format!("Package file not found: {}", path)).into_response();
+    if path.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return not_found();
+    }
+    
+    // `react-dom/client.js` may be a file of the package or its `client` export
+    let root = &state.config.root;
+    let candidates = [Some(path.as_str()), path.strip_suffix(".js")];
+    let file = candidates
+        .into_iter()
+        .flatten()
+        .find_map(|specifier| state.bundler.resolve_package(specifier, root).ok().flatten());
+    let Some((file, inside)) = file.and_then(|file| package_path(&file, &state.config.resolve.module_dirs).map(|inside| (file, inside))) else {
+        return not_found();
+    };
+    
+    let base = state.config.output.base_path();
+    if inside != path {
+        return Redirect::temporary(&format!("{}{}{}", base.trim_end_matches('/'), DEPS_PATH, inside)).into_response();
+    }
+    
+    match state.fs.read_to_string(&file) {
+        Ok(code) => {
+            let code = rewrite_bare_imports(&code, &base, |specifier| {
+                state.bundler.resolve_package(specifier, root).ok().flatten().is_some()
+            });
+            ([(header::CONTENT_TYPE, state.mime.for_path(&file))], code).into_response()
+        }
+        Err(e) => {
+            error!("Failed to read {}: {}", file.display(), e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rewrite_bare_imports() {
+        let code = "import React from 'react';\nimport { createRoot } from \"react-dom/client\";\nimport './app.js';\nexport * from 'lodash-es/debounce.js';\nimport('ui');\nimport routes from 'virtual:routes';\n";
+        let rewritten = rewrite_bare_imports(code, "/app/", |specifier| specifier != "ui");
+        assert_eq!(
+            rewritten,
+            "import React from '/app/@deps/react.js';\nimport { createRoot } from \"/app/@deps/react-dom/client.js\";\nimport './app.js';\nexport * from '/app/@deps/lodash-es/debounce.js';\nimport('ui');\nimport routes from 'virtual:routes';\n"
+        );
+        
+        let module_dirs = ["./vendor/modules".to_string()];
+        assert_eq!(package_path(Path::new("/app/node_modules/@scope/pkg/dist/index.js"), &[]).unwrap(), "@scope/pkg/dist/index.js");
+        assert_eq!(package_path(Path::new("/app/node_modules/.pnpm/a@1.0.0/node_modules/a/index.js"), &[]).unwrap(), "a/index.js");
+        assert_eq!(package_path(Path::new("/app/vendor/modules/b/index.js"), &module_dirs).unwrap(), "b/index.js");
+    }
+}
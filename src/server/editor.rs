@@ -0,0 +1,200 @@
+//! Open-in-editor support for the error overlay
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tracing::{debug, error};
+
+use super::{api, ServerState};
+use crate::utils::is_subpath;
+
+/// Query parameters for `/__open-in-editor`
+#[derive(Debug, Deserialize)]
+pub struct OpenInEditorQuery {
+    /// File to open (absolute or relative to the project root)
+    pub file: String,
+    
+    /// 1-based line number
+    #[serde(default)]
+    pub line: Option<u32>,
+    
+    /// 1-based column number
+    #[serde(default)]
+    pub column: Option<u32>,
+}
+
+/// Handle `/__open-in-editor?file=...&line=...`
+pub async fn open_in_editor(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Query(query): Query<OpenInEditorQuery>,
+) -> Response {
+    if !is_allowed(&headers, &state.api_token) {
+        return (StatusCode::FORBIDDEN, "Only pages served by the dev server can open the editor").into_response();
+    }
+    
+    let root = &state.config.root;
+    let file = resolve_file(root, &query.file);
+    
+    // Only files inside the project may be opened
+    if !file.is_file() || !is_subpath(&file, root) {
+        return (StatusCode::NOT_FOUND, format!("File not found: {}", query.file)).into_response();
+    }
+    
+    let editor = match state.config.dev.editor.clone().or_else(|| std::env::var("EDITOR").ok()) {
+        Some(editor) if !editor.trim().is_empty() => editor,
+        _ => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "No editor configured; set `dev.editor` in component.toml or $EDITOR",
+            )
+                .into_response();
+        }
+    };
+    
+    match launch_editor(&editor, &file, query.line, query.column) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => {
+            error!("Failed to launch editor '{}': {}", editor, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to launch editor: {}", e)).into_response()
+        }
+    }
+}
+
+/// Whether a request comes from a page the dev server served, or carries the
+/// API token, so other sites the developer visits can't launch their editor
+///
+/// Browsers send `Sec-Fetch-Site`; older ones only an `Origin` to compare
+/// with `Host`. Requests with neither need the token.
+fn is_allowed(headers: &HeaderMap, token: &str) -> bool {
+    if api::has_token(headers, token) {
+        return true;
+    }
+    let value = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    match value(header::HeaderName::from_static("sec-fetch-site")) {
+        Some(site) => site == "same-origin" || site == "none",
+        None => match (value(header::ORIGIN), value(header::HOST)) {
+            (Some(origin), Some(host)) => origin.split_once("://").is_some_and(|(_, origin)| origin.eq_ignore_ascii_case(host)),
+            _ => false,
+        },
+    }
+}
+
+/// Resolve a requested file against the project root
+fn resolve_file(root: &Path, file: &str) -> PathBuf {
+    let path = Path::new(file);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        root.join(file.trim_start_matches('/'))
+    }
+}
+
+/// Spawn the editor at the given location
+fn launch_editor(editor: &str, file: &Path, line: Option<u32>, column: Option<u32>) -> Result<()> {
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("Empty editor command")?;
+    
+    let mut args: Vec<String> = parts.map(|s| s.to_string()).collect();
+    args.extend(editor_args(program, file, line, column));
+    
+    debug!("Launching editor: {} {:?}", program, args);
+    
+    Command::new(program)
+        .args(&args)
+        .spawn()
+        .with_context(|| format!("Failed to spawn {}", program))?;
+    
+    Ok(())
+}
+
+/// Build the location arguments understood by well-known editors
+fn editor_args(program: &str, file: &Path, line: Option<u32>, column: Option<u32>) -> Vec<String> {
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(program)
+        .to_lowercase();
+    let file = file.display().to_string();
+    let line = line.unwrap_or(1);
+    let column = column.unwrap_or(1);
+    
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" | "windsurf" => {
+            vec!["-g".to_string(), format!("{}:{}:{}", file, line, column)]
+        }
+        "subl" | "sublime_text" | "atom" | "zed" => {
+            vec![format!("{}:{}:{}", file, line, column)]
+        }
+        "vim" | "nvim" | "vi" | "emacs" | "emacsclient" | "nano" | "micro" | "hx" | "helix" => {
+            vec![format!("+{}", line), file]
+        }
+        "idea" | "webstorm" | "phpstorm" | "pycharm" | "rustrover" | "clion" => {
+            vec!["--line".to_string(), line.to_string(), file]
+        }
+        _ => vec![file],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+    
+    use super::*;
+    use crate::server::tests::{dev_router, get};
+    use crate::vfs::MemoryFs;
+    
+    fn headers(list: &[(&'static str, &str)]) -> HeaderMap {
+        list.iter().map(|(name, value)| (header::HeaderName::from_static(name), HeaderValue::from_str(value).unwrap())).collect()
+    }
+    
+    #[test]
+    fn test_is_allowed() {
+        assert!(is_allowed(&headers(&[("sec-fetch-site", "same-origin")]), "secret"));
+        assert!(is_allowed(&headers(&[("origin", "http://localhost:3000"), ("host", "localhost:3000")]), "secret"));
+        assert!(is_allowed(&headers(&[("authorization", "Bearer secret")]), "secret"));
+        
+        assert!(!is_allowed(&headers(&[("sec-fetch-site", "cross-site")]), "secret"));
+        assert!(!is_allowed(&headers(&[("sec-fetch-site", "same-site"), ("origin", "http://localhost:3000"), ("host", "localhost:3000")]), "secret"));
+        assert!(!is_allowed(&headers(&[("origin", "https://evil.example"), ("host", "localhost:3000")]), "secret"));
+        assert!(!is_allowed(&HeaderMap::new(), "secret"));
+    }
+    
+    #[tokio::test]
+    async fn test_cross_origin_requests_are_refused() {
+        let fs = Arc::new(MemoryFs::new());
+        let path = "/__open-in-editor?file=missing.js";
+        
+        let (head, _) = get(dev_router(fs.clone(), |_| {}), path, &[("Sec-Fetch-Site", "cross-site")]).await;
+        assert!(head.starts_with("http/1.1 403"), "{}", head);
+        let (head, _) = get(dev_router(fs.clone(), |_| {}), path, &[("Origin", "https://evil.example")]).await;
+        assert!(head.starts_with("http/1.1 403"), "{}", head);
+        
+        // The overlay's own requests get past the check
+        let (head, _) = get(dev_router(fs, |_| {}), path, &[("Sec-Fetch-Site", "same-origin")]).await;
+        assert!(head.starts_with("http/1.1 404"), "{}", head);
+    }
+    
+    #[test]
+    fn test_editor_args() {
+        let file = Path::new("/proj/src/main.ts");
+        
+        assert_eq!(
+            editor_args("code", file, Some(12), Some(4)),
+            vec!["-g", "/proj/src/main.ts:12:4"]
+        );
+        assert_eq!(
+            editor_args("/usr/bin/nvim", file, Some(3), None),
+            vec!["+3", "/proj/src/main.ts"]
+        );
+        assert_eq!(editor_args("unknown-editor", file, None, None), vec!["/proj/src/main.ts"]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Custom response headers from `[dev.headers]` and `[preview.headers]`
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use globset::{Glob, GlobMatcher};
+
+use crate::config::{HeaderRule, HeadersConfig};
+
+type Headers = Vec<(HeaderName, HeaderValue)>;
+
+/// Compiled header rules
+#[derive(Debug, Default)]
+pub struct HeaderRules {
+    global: Headers,
+    paths: Vec<(GlobMatcher, Headers)>,
+}
+
+impl HeaderRules {
+    /// Validate header names and values and compile path globs
+    pub fn new(config: &HeadersConfig) -> Result<Self> {
+        let mut rules = Self::default();
+        for (key, rule) in &config.0 {
+            match rule {
+                HeaderRule::Value(value) => rules.global.push(header(key, value)?),
+                HeaderRule::Path(headers) => {
+                    let glob = Glob::new(key)
+                        .with_context(|| format!("Invalid header path glob: {}", key))?
+                        .compile_matcher();
+                    let headers = headers
+                        .iter()
+                        .map(|(name, value)| header(name, value))
+                        .collect::<Result<_>>()?;
+                    rules.paths.push((glob, headers));
+                }
+            }
+        }
+        Ok(rules)
+    }
+    
+    /// Whether no headers are configured
+    pub fn is_empty(&self) -> bool {
+        self.global.is_empty() && self.paths.is_empty()
+    }
+    
+    /// Headers for a request path; path rules override global ones
+    pub fn for_path(&self, path: &str) -> HeaderMap {
+        let matching = self.paths
+            .iter()
+            .filter(|(glob, _)| glob.is_match(path))
+            .flat_map(|(_, headers)| headers);
+        
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.global.iter().chain(matching) {
+            headers.insert(name.clone(), value.clone());
+        }
+        headers
+    }
+}
+
+fn header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    Ok((
+        HeaderName::try_from(name).with_context(|| format!("Invalid header name: {}", name))?,
+        HeaderValue::try_from(value).with_context(|| format!("Invalid value for header {}", name))?,
+    ))
+}
+
+/// Middleware adding configured headers to every response
+pub async fn apply(State(rules): State<Arc<HeaderRules>>, request: Request, next: Next) -> Response {
+    let headers = rules.for_path(request.uri().path());
+    let mut response = next.run(request).await;
+    response.headers_mut().extend(headers);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_header_rules() {
+        let config: HeadersConfig = toml::from_str(r#"
+            Cross-Origin-Opener-Policy = "same-origin"
+            X-Frame-Options = "DENY"
+            
+            ["/embed/**"]
+            X-Frame-Options = "SAMEORIGIN"
+        "#).unwrap();
+        let rules = HeaderRules::new(&config).unwrap();
+        
+        assert_eq!(rules.for_path("/app.js")["x-frame-options"], "DENY");
+        let embed = rules.for_path("/embed/widget.html");
+        assert_eq!(embed["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(embed["cross-origin-opener-policy"], "same-origin");
+    }
+}
@@ -1,6 +1,9 @@
 //! Hot Module Replacement (HMR) support
 
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use axum::{
     extract::{
@@ -11,16 +14,27 @@ use axum::{
 };
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
 
-use super::ServerState;
+use super::{modules, ServerState};
+use crate::bundler::hot_update;
+use crate::plugins::PluginContext;
 
-/// HMR message types
+/// Version of the HMR wire protocol
+///
+/// Bumped whenever message shapes change so stale clients (e.g. a tab left
+/// open across a Component upgrade) can detect the mismatch and reload.
+pub const HMR_PROTOCOL_VERSION: u32 = 2;
+
+/// HMR message types (server -> client)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum HmrMessage {
     /// Connection established
-    Connected,
+    Connected {
+        protocol: u32,
+    },
     
     /// Full page reload required
     FullReload {
@@ -32,11 +46,13 @@ pub enum HmrMessage {
         path: String,
     },
     
-    /// JavaScript module updated
+    /// A module accepting an update: the client imports `accepted_path`
+    /// again, as of `timestamp`, and hands it to the `accept()` callbacks
+    /// of the module at `path`
     JsUpdate {
         path: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        accepted: Option<bool>,
+        accepted_path: String,
+        timestamp: u64,
     },
     
     /// Error during compilation
@@ -49,6 +65,136 @@ pub enum HmrMessage {
         #[serde(skip_serializing_if = "Option::is_none")]
         column: Option<u32>,
     },
+    
+    /// Custom event for plugins and framework integrations
+    Custom {
+        event: String,
+        #[serde(default)]
+        data: serde_json::Value,
+    },
+}
+
+/// Messages sent from the browser client to the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ClientMessage {
+    /// Handshake announcing the client's protocol version
+    Hello {
+        protocol: u32,
+    },
+    
+    /// Custom event sent via `import.meta.hot.send(event, data)`
+    Custom {
+        event: String,
+        #[serde(default)]
+        data: serde_json::Value,
+    },
+}
+
+/// A custom event received from a connected client
+#[derive(Debug, Clone)]
+pub struct CustomEvent {
+    pub event: String,
+    pub data: serde_json::Value,
+}
+
+/// Handle for exchanging messages with connected HMR clients
+#[derive(Clone)]
+pub struct HmrChannel {
+    /// Server -> client messages
+    tx: broadcast::Sender<HmrMessage>,
+    
+    /// Client -> server custom events
+    client_tx: broadcast::Sender<CustomEvent>,
+    
+    /// Number of currently connected clients
+    clients: Arc<AtomicUsize>,
+}
+
+impl HmrChannel {
+    /// Create a new channel
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(100);
+        let (client_tx, _) = broadcast::channel(100);
+        Self {
+            tx,
+            client_tx,
+            clients: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+    
+    /// Number of currently connected clients
+    pub fn client_count(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+    
+    /// Broadcast a message to all connected clients
+    pub fn send(&self, message: HmrMessage) {
+        let _ = self.tx.send(message);
+    }
+    
+    /// Broadcast a custom event to all connected clients
+    pub fn send_custom(&self, event: &str, data: serde_json::Value) {
+        self.send(HmrMessage::Custom {
+            event: event.to_string(),
+            data,
+        });
+    }
+    
+    /// Subscribe to server -> client messages
+    pub fn subscribe(&self) -> broadcast::Receiver<HmrMessage> {
+        self.tx.subscribe()
+    }
+    
+    /// Subscribe to custom events sent by clients
+    pub fn subscribe_custom(&self) -> broadcast::Receiver<CustomEvent> {
+        self.client_tx.subscribe()
+    }
+    
+    /// Broadcast the custom events plugins send with
+    /// [`PluginContext::hmr_send`]
+    pub(crate) fn connect_plugins(&self, plugins: &PluginContext) {
+        let hmr = self.clone();
+        plugins.connect_hmr(move |event, data| hmr.send_custom(event, data));
+    }
+}
+
+impl Default for HmrChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Messages updating the modules that accept a change of `path` in place,
+/// or `None` when the page must reload
+///
+/// Every module the update passes through is imported afresh from then on,
+/// with the time of the update in its URL.
+pub fn hot_update_messages(state: &ServerState, path: &Path) -> Option<Vec<HmrMessage>> {
+    let path = state.fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let graph = state.bundler.graph();
+    let graph = graph.read();
+    let update = hot_update(&graph, graph.get_module_id(&path)?)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as u64);
+    
+    let url = |id| modules::module_url(state, &graph.get_module(id)?.path);
+    let messages = update
+        .boundaries
+        .iter()
+        .map(|&(boundary, accepted)| {
+            Some(HmrMessage::JsUpdate {
+                path: url(boundary)?,
+                accepted_path: url(accepted)?,
+                timestamp,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    
+    let mut timestamps = state.hot_timestamps.write();
+    for module in update.stale.iter().filter_map(|&id| graph.get_module(id)) {
+        timestamps.insert(module.path.clone(), timestamp);
+    }
+    Some(messages)
 }
 
 /// Handle WebSocket upgrade for HMR
@@ -64,14 +210,17 @@ async fn handle_hmr_socket(socket: WebSocket, state: Arc<ServerState>) {
     let (mut sender, mut receiver) = socket.split();
     
     // Subscribe to HMR messages
-    let mut hmr_rx = state.hmr_tx.subscribe();
+    let mut hmr_rx = state.hmr.subscribe();
     
     // Send connected message
-    let connected = HmrMessage::Connected;
+    let connected = HmrMessage::Connected {
+        protocol: HMR_PROTOCOL_VERSION,
+    };
     if let Ok(json) = serde_json::to_string(&connected) {
         let _ = sender.send(Message::Text(json)).await;
     }
     
+    state.hmr.clients.fetch_add(1, Ordering::Relaxed);
     debug!("HMR client connected");
     
     // Spawn task to forward HMR messages to client
@@ -85,13 +234,15 @@ async fn handle_hmr_socket(socket: WebSocket, state: Arc<ServerState>) {
         }
     });
     
-    // Handle incoming messages from client (for future use)
+    // Handle incoming messages from client
+    let hmr = state.hmr.clone();
+    let plugins = state.bundler.plugin_context();
     let recv_task = tokio::spawn(async move {
         while let Some(Ok(message)) = receiver.next().await {
             match message {
                 Message::Text(text) => {
                     debug!("Received HMR message: {}", text);
-                    // Handle client messages if needed
+                    handle_client_message(&text, &hmr, &plugins);
                 }
                 Message::Close(_) => {
                     debug!("HMR client disconnected");
@@ -108,5 +259,90 @@ async fn handle_hmr_socket(socket: WebSocket, state: Arc<ServerState>) {
         _ = recv_task => {},
     }
     
+    state.hmr.clients.fetch_sub(1, Ordering::Relaxed);
     debug!("HMR connection closed");
 }
+
+/// Handle a message sent by the browser client, handing custom events to
+/// subscribers and to plugins' [`PluginContext::hmr_on`] listeners
+fn handle_client_message(text: &str, hmr: &HmrChannel, plugins: &PluginContext) {
+    match serde_json::from_str::<ClientMessage>(text) {
+        Ok(ClientMessage::Hello { protocol }) => {
+            if protocol != HMR_PROTOCOL_VERSION {
+                warn!(
+                    "HMR client speaks protocol v{} but server expects v{}; the page will reload",
+                    protocol, HMR_PROTOCOL_VERSION
+                );
+            }
+        }
+        Ok(ClientMessage::Custom { event, data }) => {
+            plugins.receive_hmr_event(&event, &data);
+            let _ = hmr.client_tx.send(CustomEvent { event, data });
+        }
+        Err(e) => {
+            debug!("Ignoring malformed HMR client message: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_custom_message_roundtrip() {
+        let message = HmrMessage::Custom {
+            event: "my-event".to_string(),
+            data: serde_json::json!({ "count": 1 }),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json, r#"{"type":"custom","event":"my-event","data":{"count":1}}"#);
+        
+        let message = HmrMessage::JsUpdate {
+            path: "/src/App.tsx".to_string(),
+            accepted_path: "/src/Button.tsx".to_string(),
+            timestamp: 1700000000000,
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json, r#"{"type":"js-update","path":"/src/App.tsx","accepted_path":"/src/Button.tsx","timestamp":1700000000000}"#);
+        
+        let client: ClientMessage = serde_json::from_str(r#"{"type":"hello","protocol":1}"#).unwrap();
+        assert!(matches!(client, ClientMessage::Hello { protocol: 1 }));
+    }
+    
+    #[tokio::test]
+    async fn test_client_custom_event_is_forwarded() {
+        let hmr = HmrChannel::new();
+        let mut rx = hmr.subscribe_custom();
+        
+        handle_client_message(r#"{"type":"custom","event":"ping","data":[1,2]}"#, &hmr, &PluginContext::new("/app".into()));
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event, "ping");
+        assert_eq!(event.data, serde_json::json!([1, 2]));
+    }
+    
+    #[tokio::test]
+    async fn test_plugins_exchange_custom_events() {
+        let hmr = HmrChannel::new();
+        let plugins = PluginContext::new("/app".into());
+        hmr.connect_plugins(&plugins);
+        let mut rx = hmr.subscribe();
+        
+        plugins.hmr_send("routes-changed", serde_json::json!({ "count": 2 }));
+        match rx.recv().await.unwrap() {
+            HmrMessage::Custom { event, data } => {
+                assert_eq!(event, "routes-changed");
+                assert_eq!(data, serde_json::json!({ "count": 2 }));
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+        
+        let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = received.clone();
+        plugins.hmr_on("ping", move |data| sink.lock().push(data.clone()));
+        handle_client_message(r#"{"type":"custom","event":"ping","data":1}"#, &hmr, &plugins);
+        handle_client_message(r#"{"type":"custom","event":"other","data":2}"#, &hmr, &plugins);
+        assert_eq!(*received.lock(), vec![serde_json::json!(1)]);
+    }
+}
@@ -0,0 +1,129 @@
+//! Content types from `[mime_types]` and the built-in table
+//!
+//! Text types are always served with `charset=utf-8`, and unknown
+//! extensions as `application/octet-stream` so browsers never sniff them.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use axum::http::HeaderValue;
+
+const FALLBACK: &str = "application/octet-stream";
+
+/// Content types by lowercase file extension
+#[derive(Debug, Default)]
+pub struct MimeTypes {
+    custom: HashMap<String, HeaderValue>,
+}
+
+impl MimeTypes {
+    /// Validate configured types, keyed by extension with or without the dot
+    pub fn new(config: &HashMap<String, String>) -> Result<Self> {
+        let mut custom = HashMap::new();
+        for (extension, mime) in config {
+            let extension = extension.trim_start_matches('.');
+            let invalid = || anyhow::anyhow!("Invalid MIME type for .{}: {}", extension, mime);
+            
+            let essence = mime.split(';').next().unwrap_or("").trim();
+            let valid = essence.split_once('/').is_some_and(|(kind, subtype)| {
+                !kind.is_empty() && !subtype.is_empty() && !subtype.contains(['/', ' '])
+            });
+            if !valid {
+                return Err(invalid());
+            }
+            let value = HeaderValue::from_str(&with_charset(mime.trim())).map_err(|_| invalid())?;
+            custom.insert(extension.to_ascii_lowercase(), value);
+        }
+        Ok(Self { custom })
+    }
+    
+    /// Content type for a file
+    pub fn for_path(&self, path: &Path) -> HeaderValue {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        
+        match self.custom.get(&extension) {
+            Some(value) => value.clone(),
+            None => HeaderValue::from_static(builtin(&extension).unwrap_or(FALLBACK)),
+        }
+    }
+}
+
+/// Built-in content type of an extension
+fn builtin(extension: &str) -> Option<&'static str> {
+    Some(match extension {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" | "cjs" => "text/javascript; charset=utf-8",
+        "ts" | "tsx" | "jsx" | "mts" | "cts" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json; charset=utf-8",
+        "webmanifest" => "application/manifest+json; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml; charset=utf-8",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml; charset=utf-8",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        _ => return None,
+    })
+}
+
+/// Add `charset=utf-8` to textual types that don't name a charset
+fn with_charset(mime: &str) -> String {
+    let essence = mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    let is_text = essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(essence.as_str(), "application/json" | "application/javascript" | "application/xml");
+    
+    if is_text && !mime.to_ascii_lowercase().contains("charset=") {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_mime_types() {
+        let config = HashMap::from([
+            (".glb".to_string(), "model/gltf-binary".to_string()),
+            ("HBS".to_string(), "text/x-handlebars-template".to_string()),
+            ("svg".to_string(), "image/svg+xml".to_string()),
+        ]);
+        let mime = MimeTypes::new(&config).unwrap();
+        
+        assert_eq!(mime.for_path(Path::new("app.wasm")), "application/wasm");
+        assert_eq!(mime.for_path(Path::new("main.JS")), "text/javascript; charset=utf-8");
+        assert_eq!(mime.for_path(Path::new("site.webmanifest")), "application/manifest+json; charset=utf-8");
+        assert_eq!(mime.for_path(Path::new("main.js.map")), "application/json; charset=utf-8");
+        assert_eq!(mime.for_path(Path::new("scene.glb")), "model/gltf-binary");
+        assert_eq!(mime.for_path(Path::new("view.hbs")), "text/x-handlebars-template; charset=utf-8");
+        assert_eq!(mime.for_path(Path::new("icon.svg")), "image/svg+xml; charset=utf-8");
+        assert_eq!(mime.for_path(Path::new("LICENSE")), "application/octet-stream");
+        
+        let invalid = HashMap::from([("x".to_string(), "not a type".to_string())]);
+        assert!(MimeTypes::new(&invalid).is_err());
+    }
+}
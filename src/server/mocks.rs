@@ -0,0 +1,237 @@
+//! Mock API routes
+//!
+//! Files in `dev.mocks` define canned responses the dev server returns
+//! before anything else, so the frontend can be developed without a backend.
+//! A file holds one route or a list of routes:
+//!
+//! ```json
+//! [
+//!   { "method": "GET", "path": "/api/users/:id", "body": { "name": "Ada" }, "delay": 200, "jitter": 100 },
+//!   { "method": "POST", "path": "/api/login", "status": 401, "body": { "error": "bad password" } }
+//! ]
+//! ```
+//!
+//! JS files (`.js`, `.mjs`, `.cjs`) are evaluated once with Node and must
+//! export the same data as their default export.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use axum::{
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::vfs::FileSystem;
+
+/// A mocked route and its response
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockRoute {
+    /// HTTP method; any method matches when unset
+    #[serde(default)]
+    pub method: Option<String>,
+    
+    /// Path pattern with `:param` segments and a trailing `*` wildcard
+    pub path: String,
+    
+    /// Response status
+    #[serde(default = "default_status")]
+    pub status: u16,
+    
+    /// Response headers
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    
+    /// Response body; strings are sent as text, anything else as JSON
+    #[serde(default)]
+    pub body: Value,
+    
+    /// Latency in milliseconds before responding
+    #[serde(default)]
+    pub delay: u64,
+    
+    /// Random extra latency of up to this many milliseconds
+    #[serde(default)]
+    pub jitter: u64,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MockFile {
+    Many(Vec<MockRoute>),
+    One(MockRoute),
+}
+
+/// All mocked routes, in file name order
+#[derive(Debug, Default)]
+pub struct Mocks {
+    routes: Vec<MockRoute>,
+}
+
+impl Mocks {
+    /// Load every mock file under `dir`
+    pub fn load(dir: &Path, fs: &dyn FileSystem) -> Result<Self> {
+        let mut files = Vec::new();
+        if fs.is_dir(dir) {
+            collect_files(dir, fs, &mut files)?;
+        }
+        
+        let mut routes = Vec::new();
+        for file in files {
+            let data = match file.extension().and_then(|e| e.to_str()) {
+                Some("json") => fs.read_to_string(&file)?,
+                Some("js" | "mjs" | "cjs") => evaluate_js(&file)?,
+                _ => continue,
+            };
+            let parsed: MockFile = serde_json::from_str(&data)
+                .with_context(|| format!("Invalid mock file: {}", file.display()))?;
+            match parsed {
+                MockFile::Many(many) => routes.extend(many),
+                MockFile::One(one) => routes.push(one),
+            }
+        }
+        
+        Ok(Self { routes })
+    }
+    
+    /// Number of mocked routes
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+    
+    /// Whether no routes are mocked
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+    
+    /// First route matching a request
+    pub fn find(&self, method: &str, path: &str) -> Option<&MockRoute> {
+        self.routes.iter().find(|route| {
+            route.method.as_deref().is_none_or(|m| m.eq_ignore_ascii_case(method))
+                && path_matches(&route.path, path)
+        })
+    }
+}
+
+fn collect_files(dir: &Path, fs: &dyn FileSystem, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs.read_dir(dir)? {
+        if fs.is_dir(&entry) {
+            collect_files(&entry, fs, files)?;
+        } else {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Print a JS module's default export as JSON using Node
+fn evaluate_js(file: &Path) -> Result<String> {
+    let url = url::Url::from_file_path(file)
+        .map_err(|_| anyhow::anyhow!("Invalid mock path: {}", file.display()))?;
+    let output = Command::new("node")
+        .args(["--input-type=module", "-e"])
+        .arg("const m = await import(process.argv[1]); process.stdout.write(JSON.stringify(m.default ?? m));")
+        .arg(url.as_str())
+        .output()
+        .context("Failed to run node; JS mock files need Node.js on PATH")?;
+    
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to evaluate {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Match a request path against a route pattern
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern_segments = pattern.trim_matches('/').split('/');
+    let mut path_segments = path.trim_matches('/').split('/');
+    
+    loop {
+        match (pattern_segments.next(), path_segments.next()) {
+            (Some("*"), _) => return true,
+            (Some(expected), Some(actual)) => {
+                if !expected.starts_with(':') && expected != actual {
+                    return false;
+                }
+            }
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+impl MockRoute {
+    /// Latency for one response: `delay` plus up to `jitter`
+    pub fn latency(&self) -> Duration {
+        let jitter = if self.jitter > 0 {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+            u64::from(nanos) % (self.jitter + 1)
+        } else {
+            0
+        };
+        Duration::from_millis(self.delay + jitter)
+    }
+    
+    /// Build the HTTP response
+    pub fn response(&self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        let (content_type, body) = match &self.body {
+            Value::Null => (None, String::new()),
+            Value::String(text) => (Some("text/plain; charset=utf-8"), text.clone()),
+            json => (Some("application/json"), json.to_string()),
+        };
+        
+        let mut response = (status, body).into_response();
+        let headers = response.headers_mut();
+        if let Some(content_type) = content_type {
+            headers.insert(axum::http::header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::try_from(value.as_str())) {
+                headers.insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_load_and_match() {
+        let fs = MemoryFs::new();
+        fs.write("/p/mocks/users.json", r#"[
+            { "method": "GET", "path": "/api/users/:id", "body": { "name": "Ada" } },
+            { "path": "/api/files/*", "status": 204 }
+        ]"#);
+        fs.write("/p/mocks/login.json", r#"{ "method": "post", "path": "/api/login", "status": 401, "delay": 10 }"#);
+        
+        let mocks = Mocks::load(Path::new("/p/mocks"), &fs).unwrap();
+        assert_eq!(mocks.len(), 3);
+        
+        assert_eq!(mocks.find("GET", "/api/users/7").unwrap().body["name"], "Ada");
+        assert!(mocks.find("DELETE", "/api/users/7").is_none());
+        assert!(mocks.find("GET", "/api/users/7/posts").is_none());
+        assert_eq!(mocks.find("PUT", "/api/files/a/b.txt").unwrap().status, 204);
+        
+        let login = mocks.find("POST", "/api/login/").unwrap();
+        assert_eq!(login.latency(), Duration::from_millis(10));
+        assert_eq!(login.response().status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -2,45 +2,124 @@
 //!
 //! Provides a local development server with:
 //! - Static file serving
+//! - Installed packages under `/@deps/`
 //! - WebSocket-based HMR
 //! - File watching and auto-rebuild
+//! - Error overlay with open-in-editor links
+//! - Introspection API and devtools page for the live module graph
+//! - Mock API routes from `dev.mocks`
+//! - Custom response headers from `[dev.headers]`
+//! - Content types extended by `[mime_types]`
+//! - `[[plugins]]` changes in `component.toml` applied without a restart
+//! - Warm starts from the module graph saved by the last run
+//!
+//! Also provides the preview server for finished builds.
 
+mod api;
+mod deps;
+mod editor;
+mod headers;
 mod hmr;
+mod mime;
+mod mocks;
+mod modules;
+mod preview;
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
 use axum::{
-    extract::State,
+    extract::{Query, RawQuery, Request, State},
     http::{header, StatusCode},
-    response::{Html, IntoResponse, Response},
-    routing::get,
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
     Router,
 };
 use colored::Colorize;
-use notify::RecursiveMode;
-use notify_debouncer_mini::new_debouncer;
-use tokio::sync::broadcast;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use tokio::runtime::Handle;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::cli::DevServerOptions;
-use crate::config::Config;
+use crate::bundler::{html, images, BuildOptions, Bundler, GraphSnapshot, Module, ModuleType};
+use crate::cli::{DevServerOptions, RunHook};
+use crate::config::{Config, ProjectState};
+use crate::plugins::{Plugin, PluginContext};
+use crate::utils::path_to_module_id;
+use crate::vfs::{FileSystem, ProjectWatcher, RealFs};
+use crate::msg;
 
-pub use hmr::HmrMessage;
+pub use api::DevStats;
+pub use hmr::{ClientMessage, CustomEvent, HmrChannel, HmrMessage, HMR_PROTOCOL_VERSION};
+pub use headers::HeaderRules;
+pub use mime::MimeTypes;
+pub use mocks::{MockRoute, Mocks};
+pub use preview::{static_router, PreviewServer};
 
 /// Shared server state
 struct ServerState {
     /// Project configuration
     config: Arc<Config>,
     
-    /// HMR broadcast channel
-    hmr_tx: broadcast::Sender<HmrMessage>,
+    /// HMR channel
+    hmr: HmrChannel,
     
     /// Whether HMR is enabled
     hmr_enabled: bool,
+    
+    /// Bundler holding the live module graph
+    bundler: Arc<Bundler>,
+    
+    /// Time of the last in-place update of each module updated since the
+    /// server started, which URLs importing it carry
+    hot_timestamps: RwLock<HashMap<PathBuf, u64>>,
+    
+    /// Build statistics
+    stats: RwLock<DevStats>,
+    
+    /// Filesystem project files are served from
+    fs: Arc<dyn FileSystem>,
+    
+    /// Content types of served files
+    mime: MimeTypes,
+    
+    /// Bearer token required by the rebuild endpoints
+    api_token: String,
+    
+    /// Mock routes directory, when `dev.mocks` is set
+    mocks_dir: Option<PathBuf>,
+    
+    /// Mock routes answered before any other handler
+    mocks: RwLock<Arc<Mocks>>,
+    
+    /// Config file watched for `[[plugins]]` changes
+    config_path: Option<PathBuf>,
+    
+    /// Command run after each successful rebuild (`--run`)
+    run_hook: Option<RunHook>,
+}
+
+impl ServerState {
+    /// Reload mock routes, keeping the previous ones if loading fails
+    fn reload_mocks(&self) {
+        let Some(dir) = &self.mocks_dir else {
+            return;
+        };
+        match Mocks::load(dir, self.fs.as_ref()) {
+            Ok(mocks) => {
+                info!("{}", msg!("dev.mocks_loaded", count = mocks.len(), dir = dir.display()));
+                *self.mocks.write() = Arc::new(mocks);
+            }
+            Err(e) => error!("{}", msg!("dev.mocks_failed", error = format!("{:#}", e))),
+        }
+    }
 }
 
 /// Development server
@@ -50,12 +129,60 @@ pub struct DevServer {
     
     /// Server options
     options: DevServerOptions,
+    
+    /// HMR channel shared with connected clients
+    hmr: HmrChannel,
+    
+    /// Bundler holding the live module graph
+    bundler: Arc<Bundler>,
+    
+    /// Filesystem project files are read and watched through
+    fs: Arc<dyn FileSystem>,
 }
 
 impl DevServer {
     /// Create a new development server
     pub fn new(config: Arc<Config>, options: DevServerOptions) -> Result<Self> {
-        Ok(Self { config, options })
+        Self::with_fs(config, options, RealFs::shared())
+    }
+    
+    /// Create a development server over the given filesystem
+    pub fn with_fs(config: Arc<Config>, options: DevServerOptions, fs: Arc<dyn FileSystem>) -> Result<Self> {
+        let bundler = Bundler::new((*config).clone(), BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: true,
+            target: "esnext".to_string(),
+            inspect_transforms: options.inspect_transforms,
+        })?
+        .with_fs(fs.clone())?;
+        let hmr = HmrChannel::new();
+        hmr.connect_plugins(&bundler.plugin_context());
+        
+        Ok(Self {
+            config,
+            options,
+            hmr,
+            bundler: Arc::new(bundler),
+            fs,
+        })
+    }
+    
+    /// Run these plugins in addition to the `[[plugins]]` entries, as
+    /// [`Bundler::with_plugins`] does
+    pub fn with_plugins(self, plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        self.bundler.add_plugins(plugins);
+        self
+    }
+    
+    /// The context plugins' build, resolve and load hooks receive
+    pub fn plugin_context(&self) -> Arc<PluginContext> {
+        self.bundler.plugin_context()
+    }
+    
+    /// Get a handle to the HMR channel for exchanging custom events with clients
+    pub fn hmr(&self) -> HmrChannel {
+        self.hmr.clone()
     }
     
     /// Start the development server
@@ -63,91 +190,243 @@ impl DevServer {
         let addr: SocketAddr = format!("{}:{}", self.options.host, self.options.port)
             .parse()?;
         
-        // Create HMR broadcast channel
-        let (hmr_tx, _) = broadcast::channel::<HmrMessage>(100);
-        
-        // Create shared state
-        let state = Arc::new(ServerState {
-            config: self.config.clone(),
-            hmr_tx: hmr_tx.clone(),
-            hmr_enabled: self.options.hmr,
-        });
+        let state = self.state()?;
         
-        // Set up file watcher
-        if self.options.hmr {
-            self.setup_file_watcher(hmr_tx.clone())?;
+        // Build the initial module graph; errors are reported but not fatal
+        let started = Instant::now();
+        match self.initial_build().await {
+            Ok(()) => state.stats.write().initial_build = Some(started.elapsed()),
+            Err(e) => error!("{}", msg!("dev.initial_build_failed", error = format!("{:#}", e))),
         }
         
-        // Build router
-        let app = Router::new()
-            .route("/", get(serve_index))
-            .route("/*path", get(serve_file))
-            .route("/__component_hmr", get(hmr::hmr_websocket))
-            .layer(CorsLayer::permissive())
-            .with_state(state);
+        // Set up file watcher; it stops when dropped
+        let _watcher = if self.options.hmr || self.options.run.is_some() {
+            Some(self.setup_file_watcher(state.clone())?)
+        } else {
+            None
+        };
+        
+        let app = self.router(state)?;
+        let base = self.config.output.base_path();
         
         // Open browser if requested
         if self.options.open {
-            let url = format!("http://{}", addr);
+            let url = format!("http://{}{}", addr, base);
             if let Err(e) = webbrowser_open(&url) {
                 debug!("Failed to open browser: {}", e);
             }
         }
         
         // Start server
-        info!("Server listening on http://{}", addr);
+        info!("{}", msg!("dev.listening", address = addr));
         
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        tokio::select! {
+            result = async { axum::serve(listener, app).await } => result?,
+            _ = tokio::signal::ctrl_c() => {}
+        }
         
+        // Keep the graph for a warm start next time
+        self.save_graph();
         Ok(())
     }
     
-    /// Set up file watching for HMR
-    fn setup_file_watcher(&self, hmr_tx: broadcast::Sender<HmrMessage>) -> Result<()> {
-        let root = self.config.root.clone();
+    /// State shared by the request handlers, with mock routes loaded
+    fn state(&self) -> Result<Arc<ServerState>> {
+        let state = Arc::new(ServerState {
+            config: self.config.clone(),
+            hmr: self.hmr.clone(),
+            hmr_enabled: self.options.hmr,
+            bundler: self.bundler.clone(),
+            hot_timestamps: RwLock::new(HashMap::new()),
+            stats: RwLock::new(DevStats::new()),
+            fs: self.fs.clone(),
+            mime: MimeTypes::new(&self.config.mime_types)?,
+            api_token: self.api_token()?,
+            mocks_dir: self.config.dev.mocks.as_ref().map(|dir| {
+                let dir = self.config.root.join(dir);
+                self.fs.canonicalize(&dir).unwrap_or(dir)
+            }),
+            mocks: RwLock::new(Arc::default()),
+            config_path: self.options.config_path.as_deref().map(|path| {
+                self.fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+            }),
+            run_hook: self.options.run.as_ref().map(|command| {
+                RunHook::new(command, self.fs.canonicalize(&self.config.root).unwrap_or_else(|_| self.config.root.clone()))
+            }),
+        });
+        state.reload_mocks();
+        Ok(state)
+    }
+    
+    /// Routes of the dev server, under the base path
+    fn router(&self, state: Arc<ServerState>) -> Result<Router> {
+        let header_rules = Arc::new(HeaderRules::new(&self.config.dev.headers)?);
+        let routes = Router::new()
+            .route("/", get(serve_index))
+            .route("/*path", get(serve_file))
+            .route(&self.config.dev.hmr.path, get(hmr::hmr_websocket))
+            .route(HMR_CLIENT_PATH, get(serve_hmr_client))
+            .route(&format!("{}*path", deps::DEPS_PATH), get(deps::serve_dep))
+            .route("/__open-in-editor", get(editor::open_in_editor))
+            .route("/__component", get(api::devtools))
+            .route("/__component/", get(api::devtools))
+            .route("/__component/config", get(api::config))
+            .route("/__component/graph", get(api::graph))
+            .route("/__component/modules/:id", get(api::module))
+            .route("/__component/modules/:id/transforms", get(api::module_transforms))
+            .route("/__component/stats", get(api::stats))
+            .route("/__component/invalidate", post(api::invalidate))
+            .route("/__component/rebuild", post(api::rebuild));
         
-        // Use a debouncer to avoid too many events
-        let (tx, rx) = std::sync::mpsc::channel();
+        // Under a base path, everything but mocks lives below it
+        let base = self.config.output.base_path();
+        let app = if base == "/" {
+            routes
+        } else {
+            let target = base.clone();
+            Router::new()
+                .nest(base.trim_end_matches('/'), routes)
+                .route(&base, get(serve_index))
+                .route("/", get(move || async move { Redirect::temporary(&target) }))
+        };
+        Ok(app
+            .with_state(state.clone())
+            .layer(middleware::from_fn_with_state(state, serve_mocks))
+            .layer(CorsLayer::permissive())
+            .layer(middleware::from_fn_with_state(header_rules, headers::apply))
+            .layer(compression_layer(self.config.dev.compression)))
+    }
+    
+    /// Restore the module graph saved by the last run, or build it from scratch
+    ///
+    /// A graph built from scratch is saved right away, so a server that
+    /// doesn't shut down cleanly still leaves one behind.
+    async fn initial_build(&self) -> Result<()> {
+        let snapshot = self.options.warm_start
+            .then(|| GraphSnapshot::load(&GraphSnapshot::path(&self.config)))
+            .flatten();
+        if let Some(snapshot) = snapshot {
+            match self.bundler.restore_graph(snapshot).await {
+                Ok(Some(changed)) => {
+                    info!("{}", msg!("dev.graph_restored", count = changed));
+                    return Ok(());
+                }
+                Ok(None) => debug!("Saved module graph is out of date, building from scratch"),
+                Err(e) => warn!("{}", msg!("dev.graph_restore_failed", error = format!("{:#}", e))),
+            }
+        }
         
-        let mut debouncer = new_debouncer(
-            std::time::Duration::from_millis(100),
-            tx,
-        )?;
+        self.bundler.rebuild_graph().await?;
+        self.save_graph();
+        Ok(())
+    }
+    
+    /// Save the module graph to `cache.dir`; failures are only logged
+    fn save_graph(&self) {
+        let path = GraphSnapshot::path(&self.config);
+        match self.bundler.snapshot() {
+            Ok(Some(snapshot)) => {
+                if let Err(e) = snapshot.save(&path) {
+                    warn!("{}", msg!("dev.graph_save_failed", error = format!("{:#}", e)));
+                }
+            }
+            Ok(None) => debug!("Not saving the module graph while a module failed to transform"),
+            Err(e) => warn!("{}", msg!("dev.graph_save_failed", error = format!("{:#}", e))),
+        }
+    }
+    
+    /// Token for the rebuild endpoints
+    ///
+    /// Without `--api-token`, a random one is written to `.component/dev-token`
+    /// for local tools to read.
+    fn api_token(&self) -> Result<String> {
+        if let Some(token) = &self.options.api_token {
+            if token.is_empty() {
+                anyhow::bail!("The API token can't be empty");
+            }
+            return Ok(token.clone());
+        }
         
-        // Watch the source directory
-        debouncer.watcher().watch(&root, RecursiveMode::Recursive)?;
+        let token = api::generate_token()?;
+        if let Err(e) = ProjectState::new(&self.config.root).write(DEV_TOKEN_FILE, &token) {
+            warn!("{:#}", e);
+        }
+        Ok(token)
+    }
+    
+    /// Set up file watching for HMR, including plugin watch files outside the root
+    fn setup_file_watcher(&self, state: Arc<ServerState>) -> Result<ProjectWatcher> {
+        let runtime = Handle::current();
+        let bundler = self.bundler.clone();
         
-        // Spawn a thread to handle file change events
-        // The debouncer is moved into the thread to keep it alive
-        std::thread::spawn(move || {
-            // Keep debouncer alive for the duration of the watcher
-            let _debouncer = debouncer;
-            
-            loop {
-                match rx.recv() {
-                    Ok(Ok(events)) => {
-                        for event in events {
-                            handle_file_change(&event.path, &hmr_tx);
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        error!("Watch error: {:?}", e);
-                    }
-                    Err(_) => {
-                        // Channel closed, exit
-                        break;
-                    }
-                }
+        ProjectWatcher::new(
+            self.fs.clone(),
+            &self.config.root,
+            move |paths| {
+                let _ = handle_file_changes(paths, &state, &runtime);
+            },
+            move || bundler.watch_files(),
+        )
+    }
+}
+
+/// Handle a batch of file changes
+///
+/// Large batches, such as a branch switch or `npm install`, rebuild the
+/// graph once and reload the page instead of updating file by file. Errors
+/// are reported to clients and returned for the rebuild endpoints.
+fn handle_file_changes(paths: Vec<PathBuf>, state: &ServerState, runtime: &Handle) -> Result<()> {
+    let is_settings = |path: &PathBuf| {
+        state.mocks_dir.as_ref().is_some_and(|dir| path.starts_with(dir)) || state.config_path.as_ref() == Some(path)
+    };
+    let (settings, sources): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(is_settings);
+    
+    let mut result = Ok(());
+    for path in &settings {
+        handle_file_change(path, state, runtime)?;
+    }
+    if !state.bundler.is_bulk_change(&sources) {
+        for path in &sources {
+            if let Err(e) = handle_file_change(path, state, runtime) {
+                result = result.and(Err(e));
             }
+        }
+        return result;
+    }
+    
+    let reason = format!("Reloading due to {} changed files", sources.len());
+    eprintln!("  {} {}", "↻".yellow(), reason);
+    let started = Instant::now();
+    if let Err(e) = runtime.block_on(state.bundler.rebuild_graph()) {
+        error!("{}", msg!("build.rebuild_failed", error = format!("{:#}", e)));
+        state.hmr.send(HmrMessage::Error {
+            message: format!("{:#}", e),
+            file: None,
+            line: None,
+            column: None,
         });
-        
-        Ok(())
+        return Err(e);
     }
+    state.stats.write().record_full_rebuild(started.elapsed());
+    state.hmr.send(HmrMessage::FullReload { reason });
+    run_hook(state, &sources);
+    Ok(())
 }
 
 /// Handle a file change event
-fn handle_file_change(path: &PathBuf, hmr_tx: &broadcast::Sender<HmrMessage>) {
+fn handle_file_change(path: &Path, state: &ServerState, runtime: &Handle) -> Result<()> {
+    if state.mocks_dir.as_ref().is_some_and(|dir| path.starts_with(dir)) {
+        eprintln!("  {} {}", "↻".yellow(), msg!("dev.mocks_changed", path = path.display().to_string().dimmed()));
+        state.reload_mocks();
+        return Ok(());
+    }
+    
+    if state.config_path.as_deref() == Some(path) {
+        reload_config(path, state, runtime);
+        return Ok(());
+    }
+    
     let extension = path.extension()
         .and_then(|e| e.to_str())
         .unwrap_or("");
@@ -155,42 +434,140 @@ fn handle_file_change(path: &PathBuf, hmr_tx: &broadcast::Sender<HmrMessage>) {
     // Only handle relevant file types
     let is_relevant = matches!(
         extension,
-        "js" | "ts" | "jsx" | "tsx" | "css" | "scss" | "html" | "vue" | "svelte"
+        "js" | "ts" | "jsx" | "tsx" | "css" | "scss" | "html" | "vue" | "svelte" | "graphql" | "gql"
     );
     
-    if !is_relevant {
-        return;
+    if !is_relevant && !state.bundler.is_watch_file(path) {
+        return Ok(());
     }
     
-    eprintln!(
-        "  {} File changed: {}",
-        "↻".yellow(),
-        path.display().to_string().dimmed()
-    );
+    eprintln!("  {} {}", "↻".yellow(), msg!("build.file_changed", path = path.display().to_string().dimmed()));
     
-    let message = if extension == "css" || extension == "scss" {
-        HmrMessage::CssUpdate {
-            path: path.display().to_string(),
+    // Refresh the module in the live graph
+    let started = Instant::now();
+    let rebuilt = match runtime.block_on(state.bundler.invalidate(path)) {
+        Ok(Some(_)) => {
+            let relative = path.strip_prefix(&state.config.root).unwrap_or(path);
+            state.stats.write().record_rebuild(relative, started.elapsed());
+            true
         }
-    } else {
-        HmrMessage::FullReload {
+        Ok(None) => false,
+        Err(e) => {
+            error!("{}", msg!("build.rebuild_failed", error = format!("{:#}", e)));
+            state.hmr.send(HmrMessage::Error {
+                message: format!("{:#}", e),
+                file: Some(path.display().to_string()),
+                line: None,
+                column: None,
+            });
+            return Err(e);
+        }
+    };
+    
+    // Stylesheets a page links are reloaded, and the modules accepting an
+    // update are sent it, falling back to reloading the page
+    let is_style = extension == "css" || extension == "scss";
+    if is_style {
+        state.hmr.send(HmrMessage::CssUpdate {
+            path: path_to_module_id(path),
+        });
+    }
+    match rebuilt.then(|| hmr::hot_update_messages(state, path)).flatten() {
+        Some(messages) => messages.into_iter().for_each(|message| state.hmr.send(message)),
+        None if is_style => {}
+        None => state.hmr.send(HmrMessage::FullReload {
             reason: format!("File changed: {}", path.display()),
+        }),
+    }
+    if rebuilt {
+        run_hook(state, &[path.to_path_buf()]);
+    }
+    Ok(())
+}
+
+/// Run the `--run` command, if any, for a rebuild caused by `changed`
+fn run_hook(state: &ServerState, changed: &[PathBuf]) {
+    let Some(run) = &state.run_hook else {
+        return;
+    };
+    let affected = state.bundler.affected_files(changed);
+    if let Err(e) = run.run(changed, &affected) {
+        error!("{:#}", e);
+    }
+}
+
+/// Apply `[[plugins]]` changes to the live graph
+///
+/// Any other config change needs a restart, since it can affect resolution,
+/// the server itself, or every transform.
+fn reload_config(path: &Path, state: &ServerState, runtime: &Handle) {
+    let config = match Config::load_for(path, &state.config.conditions) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", msg!("dev.config_reload_failed", path = path.display(), error = format!("{:#}", e)));
+            return;
         }
     };
     
-    let _ = hmr_tx.send(message);
+    if !config.differs_only_in_plugins(&state.config) {
+        eprintln!("  {} {}", "!".yellow(), msg!("dev.restart_to_apply", path = path.display().to_string().dimmed()));
+        return;
+    }
+    
+    match runtime.block_on(state.bundler.reload_plugins(&config.plugins)) {
+        Ok(reloaded) if reloaded.is_empty() => {}
+        Ok(reloaded) => {
+            eprintln!("  {} {}", "↻".yellow(), msg!("dev.plugins_reloaded", plugins = reloaded.join(", ")));
+            state.hmr.send(HmrMessage::FullReload {
+                reason: format!("Plugins reloaded: {}", reloaded.join(", ")),
+            });
+        }
+        Err(e) => {
+            error!("{}", msg!("dev.plugin_reload_failed", error = format!("{:#}", e)));
+            state.hmr.send(HmrMessage::Error {
+                message: format!("{:#}", e),
+                file: Some(path.display().to_string()),
+                line: None,
+                column: None,
+            });
+        }
+    }
+}
+
+/// Response compression, honoring `Accept-Encoding`, when enabled
+///
+/// Small bodies, images, and WebSocket upgrades are never compressed.
+fn compression_layer(enabled: bool) -> CompressionLayer {
+    CompressionLayer::new()
+        .gzip(enabled)
+        .br(enabled)
+}
+
+/// Answer requests that match a mock route before any other handler
+async fn serve_mocks(State(state): State<Arc<ServerState>>, request: Request, next: Next) -> Response {
+    let route = state.mocks.read().find(request.method().as_str(), request.uri().path()).cloned();
+    match route {
+        Some(route) => {
+            tokio::time::sleep(route.latency()).await;
+            route.response()
+        }
+        None => next.run(request).await,
+    }
 }
 
 /// Serve the index.html file
-async fn serve_index(State(state): State<Arc<ServerState>>) -> Response {
+async fn serve_index(State(state): State<Arc<ServerState>>, Query(query): Query<IndexQuery>) -> Response {
     let index_path = state.config.root.join("index.html");
     
-    if index_path.exists() {
-        match std::fs::read_to_string(&index_path) {
-            Ok(mut content) => {
+    if state.fs.exists(&index_path) {
+        match state.fs.read_to_string(&index_path) {
+            Ok(content) => {
+                let base = state.config.output.base_path();
+                let mut content = html::rebase(&content, &base);
+                
                 // Inject HMR client if enabled
                 if state.hmr_enabled {
-                    content = inject_hmr_client(&content);
+                    content = inject_hmr_client(&content, &base);
                 }
                 Html(content).into_response()
             }
@@ -200,33 +577,89 @@ async fn serve_index(State(state): State<Arc<ServerState>>) -> Response {
             }
         }
     } else {
-        // Generate a default index.html
-        let default_html = generate_default_index(&state.config, state.hmr_enabled);
-        Html(default_html).into_response()
+        generated_index(&state, query.entry.as_deref())
     }
 }
 
+/// Query parameters for the generated index page
+#[derive(Debug, Deserialize)]
+struct IndexQuery {
+    /// Entrypoint to load instead of the default one
+    entry: Option<String>,
+}
+
+/// Serve a generated index.html for one entry, or a page listing them
+///
+/// The entry is the one named by `?entry=`, then `dev.default_entry`, then
+/// the only script entry. With several entries and no choice made, every
+/// entry is listed instead.
+fn generated_index(state: &ServerState, requested: Option<&str>) -> Response {
+    let entries: Vec<(String, String)> = state.bundler
+        .entrypoints()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(name, path)| {
+            let relative = path.strip_prefix(&state.config.root).ok().map(path_to_module_id)?;
+            Some((name, relative))
+        })
+        .collect();
+    let entrypoint = match index_entry(&entries, requested.or(state.config.dev.default_entry.as_deref())) {
+        IndexEntry::Script(path) => path,
+        IndexEntry::List => return Html(generate_entry_list(&state.config, &entries)).into_response(),
+        IndexEntry::Unknown(name) => {
+            let message = format!("No script entrypoint named '{}'", name);
+            return (StatusCode::NOT_FOUND, message).into_response();
+        }
+    };
+    
+    let template = match &state.config.dev.index_template {
+        Some(template) => match state.fs.read_to_string(&state.config.root.join(template)) {
+            Ok(template) => Some(template),
+            Err(e) => {
+                error!("Failed to read dev.index_template {}: {}", template, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read dev.index_template").into_response();
+            }
+        },
+        None => None,
+    };
+    Html(generate_default_index(&state.config, template.as_deref(), &entrypoint, state.hmr_enabled)).into_response()
+}
+
 /// Serve static files
 async fn serve_file(
     State(state): State<Arc<ServerState>>,
     axum::extract::Path(path): axum::extract::Path<String>,
+    RawQuery(query): RawQuery,
 ) -> Response {
     let file_path = state.config.root.join(&path);
     
-    if !file_path.exists() {
+    if !state.fs.exists(&file_path) {
         return (StatusCode::NOT_FOUND, format!("File not found: {}", path)).into_response();
     }
     
-    // Determine content type
-    let content_type = get_content_type(&file_path);
+    // Scripts are served transformed, with an inline source map and their
+    // imports pointed at URLs, the files they import as JS modules, and
+    // images resized for `?srcset=` imports
+    let query = query.unwrap_or_default();
+    if modules::is_import(&query) {
+        let url = format!("{}{}", state.config.output.base_path(), path);
+        return modules::serve_import(&state, &file_path, &url).await;
+    }
+    if let Some(variant) = images::Variant::parse(&query) {
+        return modules::serve_image(&state, &file_path, &variant);
+    }
+    let module_path = match images::Srcset::parse(&query) {
+        Some(_) => images::with_query(&file_path, &query),
+        None => file_path.clone(),
+    };
+    if let Some(response) = modules::serve_script(&state, &module_path).await {
+        return response;
+    }
     
-    match std::fs::read(&file_path) {
+    match state.fs.read(&file_path) {
         Ok(content) => {
             let mut response = content.into_response();
-            response.headers_mut().insert(
-                header::CONTENT_TYPE,
-                content_type.parse().unwrap(),
-            );
+            response.headers_mut().insert(header::CONTENT_TYPE, state.mime.for_path(&file_path));
             response
         }
         Err(e) => {
@@ -236,94 +669,120 @@ async fn serve_file(
     }
 }
 
-/// Get content type for a file
-fn get_content_type(path: &PathBuf) -> &'static str {
-    let extension = path.extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
+/// Where the generated rebuild endpoint token is written, in `.component/`
+const DEV_TOKEN_FILE: &str = "dev-token";
+
+/// URL the HMR client module is served from
+const HMR_CLIENT_PATH: &str = "/@component/client";
+
+/// Check that `dev.hmr.path` is a literal path none of the dev server's own
+/// routes already take
+pub(crate) fn check_hmr_path(path: &str) -> Result<()> {
+    if !path.starts_with('/') {
+        anyhow::bail!("dev.hmr.path '{}' must start with '/'", path);
+    }
+    if path.split('/').any(|segment| segment.starts_with(':') || segment.starts_with('*')) {
+        anyhow::bail!("dev.hmr.path '{}' can't contain route parameters", path);
+    }
+    let under = |prefix: &str| path == prefix || path.starts_with(&format!("{}/", prefix.trim_end_matches('/')));
+    let reserved = ["/__component", "/__open-in-editor", HMR_CLIENT_PATH, deps::DEPS_PATH];
+    if path == "/" || reserved.into_iter().any(under) {
+        anyhow::bail!("dev.hmr.path '{}' is taken by a dev server route", path);
+    }
+    Ok(())
+}
+
+/// HMR client source
+const HMR_CLIENT_SCRIPT: &str = include_str!("client.js");
+
+/// Serve the HMR client as a standalone module
+async fn serve_hmr_client(State(state): State<Arc<ServerState>>) -> Response {
+    let hmr = &state.config.dev.hmr;
+    let base = state.config.output.base_path();
+    let client_config = serde_json::json!({
+        "protocol": hmr.protocol,
+        "host": hmr.host,
+        "port": hmr.port,
+        "path": format!("{}{}", base.trim_end_matches('/'), hmr.path),
+        "base": base,
+    });
+    
+    let script = HMR_CLIENT_SCRIPT
+        .replace("__HMR_PROTOCOL_VERSION__", &HMR_PROTOCOL_VERSION.to_string())
+        .replace("__HMR_CONFIG__", &client_config.to_string());
+    
+    (
+        [(header::CONTENT_TYPE, "application/javascript; charset=utf-8")],
+        script,
+    )
+        .into_response()
+}
+
+/// What the generated index page shows
+#[derive(Debug, PartialEq)]
+enum IndexEntry {
+    /// Load the script entrypoint at this path
+    Script(String),
     
-    match extension {
-        "html" | "htm" => "text/html; charset=utf-8",
-        "js" | "mjs" => "application/javascript; charset=utf-8",
-        "ts" | "tsx" | "jsx" => "application/javascript; charset=utf-8",
-        "css" => "text/css; charset=utf-8",
-        "json" => "application/json; charset=utf-8",
-        "png" => "image/png",
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "svg" => "image/svg+xml",
-        "woff" => "font/woff",
-        "woff2" => "font/woff2",
-        "ttf" => "font/ttf",
-        "eot" => "application/vnd.ms-fontobject",
-        _ => "application/octet-stream",
+    /// Link to every entrypoint
+    List,
+    
+    /// The chosen entrypoint isn't a script entrypoint
+    Unknown(String),
+}
+
+/// Pick what the generated index shows for `entries` (name and path
+/// relative to the root), given the `?entry=` or `dev.default_entry` choice
+fn index_entry(entries: &[(String, String)], chosen: Option<&str>) -> IndexEntry {
+    let scripts: Vec<&(String, String)> = entries
+        .iter()
+        .filter(|(_, path)| Module::detect_type(Path::new(path)) != ModuleType::Html)
+        .collect();
+    
+    match (chosen, scripts.as_slice()) {
+        (Some(name), _) => match scripts.iter().find(|(entry, _)| entry == name) {
+            Some((_, path)) => IndexEntry::Script(path.clone()),
+            None => IndexEntry::Unknown(name.to_string()),
+        },
+        (None, [(_, path)]) if entries.len() == 1 => IndexEntry::Script(path.clone()),
+        (None, []) => IndexEntry::Script("src/main.js".to_string()),
+        (None, _) => IndexEntry::List,
     }
 }
 
-/// Inject HMR client script into HTML
-fn inject_hmr_client(html: &str) -> String {
-    let hmr_script = r#"
-<script type="module">
-// Component HMR Client
-(function() {
-  const ws = new WebSocket(`ws://${location.host}/__component_hmr`);
-  
-  ws.onmessage = function(event) {
-    const message = JSON.parse(event.data);
-    
-    switch (message.type) {
-      case 'full-reload':
-        console.log('[Component] Full reload:', message.reason);
-        location.reload();
-        break;
-        
-      case 'css-update':
-        console.log('[Component] CSS update:', message.path);
-        // Find and reload CSS
-        const links = document.querySelectorAll('link[rel="stylesheet"]');
-        links.forEach(link => {
-          const url = new URL(link.href);
-          url.searchParams.set('t', Date.now());
-          link.href = url.toString();
-        });
-        break;
-        
-      case 'connected':
-        console.log('[Component] HMR connected');
-        break;
-    }
-  };
-  
-  ws.onclose = function() {
-    console.log('[Component] HMR disconnected, attempting to reconnect...');
-    setTimeout(() => location.reload(), 1000);
-  };
-})();
-</script>
-"#;
+/// Inject HMR client script into HTML served under `base`
+fn inject_hmr_client(html: &str, base: &str) -> String {
+    let hmr_script = format!(
+        "\n<script type=\"module\" src=\"{}{}\"></script>\n",
+        base.trim_end_matches('/'),
+        HMR_CLIENT_PATH
+    );
     
     // Insert before </body> or at the end
     if let Some(pos) = html.rfind("</body>") {
         let mut result = html.to_string();
-        result.insert_str(pos, hmr_script);
+        result.insert_str(pos, &hmr_script);
         result
     } else {
         format!("{}{}", html, hmr_script)
     }
 }
 
-/// Generate a default index.html
-fn generate_default_index(config: &Config, hmr_enabled: bool) -> String {
-    let entrypoint = config.entrypoints.values().next()
-        .map(|p| p.as_str())
-        .unwrap_or("src/main.js");
-    
+
+/// Generate a default index.html, from `template` when given
+fn generate_default_index(config: &Config, template: Option<&str>, entrypoint: &str, hmr_enabled: bool) -> String {
+    let base = config.output.base_path();
     let hmr_script = if hmr_enabled {
-        inject_hmr_client("")
+        inject_hmr_client("", &base)
     } else {
         String::new()
     };
     
+    if let Some(template) = template {
+        let script = format!("<script type=\"module\" src=\"{}{}\"></script>{}", base, html::escape(entrypoint), hmr_script);
+        return html::rebase(&html::rewrite(template, &[], "", &script), &base);
+    }
+    
     format!(
         r#"<!DOCTYPE html>
 <html lang="en">
@@ -334,17 +793,55 @@ fn generate_default_index(config: &Config, hmr_enabled: bool) -> String {
   </head>
   <body>
     <div id="app"></div>
-    <script type="module" src="/{}"></script>
+    <script type="module" src="{}{}"></script>
     {}
   </body>
 </html>
 "#,
-        config.project.name,
-        entrypoint,
+        html::escape(&config.project.name),
+        base,
+        html::escape(entrypoint),
         hmr_script
     )
 }
 
+/// Generate a page linking to every entrypoint
+fn generate_entry_list(config: &Config, entries: &[(String, String)]) -> String {
+    let base = config.output.base_path();
+    let items: String = entries
+        .iter()
+        .map(|(name, path)| {
+            let href = if Module::detect_type(Path::new(path)) == ModuleType::Html {
+                format!("{}{}", base, path)
+            } else {
+                format!("{}?entry={}", base, url::form_urlencoded::byte_serialize(name.as_bytes()).collect::<String>())
+            };
+            format!("      <li><a href=\"{}\">{}</a> <code>{}</code></li>\n", html::escape(&href), html::escape(name), html::escape(path))
+        })
+        .collect();
+    
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="UTF-8" />
+    <title>{}</title>
+  </head>
+  <body>
+    <h1>{}</h1>
+    <p>Pick an entrypoint, or set <code>dev.default_entry</code> to load one at <code>{}</code>.</p>
+    <ul>
+{}    </ul>
+  </body>
+</html>
+"#,
+        html::escape(&config.project.name),
+        html::escape(&config.project.name),
+        base,
+        items
+    )
+}
+
 /// Open URL in browser (simple implementation)
 fn webbrowser_open(url: &str) -> Result<()> {
     #[cfg(target_os = "macos")]
@@ -366,3 +863,133 @@ fn webbrowser_open(url: &str) -> Result<()> {
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    /// Serve `app` on a free port and GET `path` with the given request
+    /// headers; returns the lowercased response head and the body
+    pub(super) async fn get(app: Router, path: &str, headers: &[(&str, &str)]) -> (String, Vec<u8>) {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        
+        let mut request = format!("GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n", path, addr);
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        
+        let end = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        (String::from_utf8_lossy(&response[..end]).to_lowercase(), response[end + 4..].to_vec())
+    }
+    
+    /// Router of a dev server over an in-memory project at `/project`
+    pub(super) fn dev_router(fs: Arc<MemoryFs>, configure: impl FnOnce(&mut Config)) -> Router {
+        let mut config = Config::default_config();
+        config.root = PathBuf::from("/project");
+        configure(&mut config);
+        let options = DevServerOptions {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            hmr: false,
+            open: false,
+            inspect_transforms: false,
+            api_token: Some("secret".to_string()),
+            warm_start: false,
+            config_path: None,
+            run: None,
+        };
+        let server = DevServer::with_fs(Arc::new(config), options, fs).unwrap();
+        server.router(server.state().unwrap()).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_dev_compression() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/notes.txt", "compress me\n".repeat(100));
+        
+        let (head, _) = get(dev_router(fs.clone(), |config| config.dev.compression = true), "/notes.txt", &[("Accept-Encoding", "gzip")]).await;
+        assert!(head.contains("content-encoding: gzip"), "{}", head);
+        let (head, _) = get(dev_router(fs.clone(), |config| config.dev.compression = true), "/notes.txt", &[("Accept-Encoding", "br")]).await;
+        assert!(head.contains("content-encoding: br"), "{}", head);
+        
+        let (head, body) = get(dev_router(fs, |_| {}), "/notes.txt", &[("Accept-Encoding", "gzip, br")]).await;
+        assert!(head.starts_with("http/1.1 200"), "{}", head);
+        assert!(!head.contains("content-encoding"), "{}", head);
+        assert!(String::from_utf8_lossy(&body).contains(&"compress me\n".repeat(100)));
+    }
+    
+    fn entries(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter().map(|(name, path)| (name.to_string(), path.to_string())).collect()
+    }
+    
+    #[test]
+    fn test_index_entry() {
+        let several = entries(&[("admin", "src/admin.js"), ("docs", "docs.html"), ("main", "src/main.js")]);
+        assert_eq!(index_entry(&several, Some("admin")), IndexEntry::Script("src/admin.js".to_string()));
+        assert_eq!(index_entry(&several, None), IndexEntry::List);
+        assert_eq!(index_entry(&several, Some("docs")), IndexEntry::Unknown("docs".to_string()));
+        assert_eq!(index_entry(&several, Some("missing")), IndexEntry::Unknown("missing".to_string()));
+        
+        // A lone script entry needs no choice; a lone page still gets the list
+        assert_eq!(index_entry(&entries(&[("app", "src/app.ts")]), None), IndexEntry::Script("src/app.ts".to_string()));
+        assert_eq!(index_entry(&entries(&[("app", "src/app.ts"), ("docs", "docs.html")]), None), IndexEntry::List);
+        assert_eq!(index_entry(&[], None), IndexEntry::Script("src/main.js".to_string()));
+    }
+    
+    #[test]
+    fn test_generate_default_index() {
+        let config = Config::default_config();
+        let page = generate_default_index(&config, None, "src/app.js", false);
+        assert!(page.contains("<div id=\"app\"></div>\n    <script type=\"module\" src=\"/src/app.js\"></script>"), "{}", page);
+        
+        let template = "<html><body><main id=\"root\"></main></body></html>";
+        let page = generate_default_index(&config, Some(template), "src/app.js", false);
+        assert!(page.contains("<main id=\"root\"></main>"), "{}", page);
+        assert!(page.contains("<script type=\"module\" src=\"/src/app.js\"></script>"), "{}", page);
+        assert!(!page.contains("id=\"app\""));
+    }
+    
+    #[test]
+    fn test_generate_entry_list_escapes_names_and_paths() {
+        let config = Config::default_config();
+        let list = generate_entry_list(&config, &entries(&[("<b>", "src/<b>.js"), ("docs", "docs.html")]));
+        assert!(list.contains("<li><a href=\"/?entry=%3Cb%3E\">&lt;b&gt;</a> <code>src/&lt;b&gt;.js</code></li>"), "{}", list);
+        assert!(list.contains("<li><a href=\"/docs.html\">docs</a> <code>docs.html</code></li>"), "{}", list);
+        assert!(!list.contains("<b>"));
+    }
+    
+    #[test]
+    fn test_hmr_path_is_checked_when_the_config_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.js"), "").unwrap();
+        let load = |path: &str| {
+            let toml = format!(
+                "[project]\nname = \"app\"\nversion = \"0.1.0\"\n\n[entrypoints]\nmain = \"src/main.js\"\n\n[dev.hmr]\npath = \"{}\"\n",
+                path,
+            );
+            std::fs::write(dir.path().join("component.toml"), toml).unwrap();
+            Config::load(dir.path().join("component.toml")).map_err(|e| e.to_string())
+        };
+        
+        assert_eq!(load("hmr").unwrap_err(), "dev.hmr.path 'hmr' must start with '/'");
+        assert_eq!(load("/hmr/:id").unwrap_err(), "dev.hmr.path '/hmr/:id' can't contain route parameters");
+        for taken in ["/", "/__component", "/__component/stats", "/@component/client", "/@deps/react.js", "/__open-in-editor"] {
+            assert_eq!(load(taken).unwrap_err(), format!("dev.hmr.path '{}' is taken by a dev server route", taken));
+        }
+        
+        // Any other path routes alongside the built-in ones
+        let config = load("/__component_ws").unwrap();
+        let _ = dev_router(Arc::new(MemoryFs::new()), |c| c.dev.hmr = config.dev.hmr.clone());
+    }
+}
@@ -0,0 +1,234 @@
+//! Modules transformed as the browser requests them
+//!
+//! The dev server doesn't bundle: each module is its own request, compiled
+//! when it's asked for. TypeScript and JSX are served as JavaScript whether
+//! or not an entry reaches them yet, and every import's specifier becomes a
+//! URL the browser can load. Installed packages go to `/@deps/`, and
+//! relative and aliased imports to the file they resolve to, so `./App`
+//! becomes `/src/App.tsx`.
+//!
+//! Browsers only import JavaScript, so imports of other files get
+//! `?import`, which serves the file as a module: a stylesheet injects a
+//! `<style>`, JSON and GraphQL are their default export, and any other file
+//! exports its URL, as in a bundle. Images imported with `?srcset=` are
+//! resized as each copy is requested.
+
+use std::path::Path;
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::json;
+use tracing::error;
+
+use super::{deps, ServerState, HMR_CLIENT_PATH};
+use crate::bundler::images::{self, Variant};
+use crate::bundler::{rewrite_accepted, Module, ModuleType};
+use crate::utils::path_to_module_id;
+
+/// Query asking for a file as a JS module
+const IMPORT_QUERY: &str = "?import";
+
+/// Content type of every module served
+const JAVASCRIPT: &str = "text/javascript; charset=utf-8";
+
+/// Code a stylesheet's module ends with under HMR, accepting its own
+/// updates and putting each new `<style>` where the last one was
+const HOT_STYLE: &str = r#"var __component_style__ = document.head.lastElementChild;
+if (import.meta.hot.data.style) import.meta.hot.data.style.replaceWith(__component_style__);
+import.meta.hot.data.style = __component_style__;
+import.meta.hot.accept();
+"#;
+
+/// `require()` calls of a string specifier, as transforms write them
+static REQUIRE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\brequire\(("(?:[^"\\\n]|\\.)*")\)"#).unwrap()
+});
+
+/// Serve a script transformed, with its imports pointed at URLs, or `None`
+/// when the file isn't a script
+///
+/// With HMR, a script using `import.meta.hot` gets a hot context from the
+/// client, and the imports its `accept()` calls name become URLs too.
+pub async fn serve_script(state: &ServerState, file: &Path) -> Option<Response> {
+    let code = match state.bundler.served_module(file).await {
+        Ok(code) => code?,
+        Err(e) => return Some(transform_failed(file, e)),
+    };
+    let mut code = deps::rewrite_imports(&code, |specifier| import_url(state, specifier, file));
+    if state.hmr_enabled && code.contains("import.meta.hot") {
+        code = rewrite_accepted(&code, |specifier| resolved_url(state, specifier, file));
+        if let Some(url) = module_url(state, file) {
+            code.insert_str(0, &hot_context(state, &url));
+        }
+    }
+    Some(([(header::CONTENT_TYPE, JAVASCRIPT)], code).into_response())
+}
+
+/// Whether a request's query asks for the file as a JS module
+pub fn is_import(query: &str) -> bool {
+    url::form_urlencoded::parse(query.as_bytes()).any(|(key, _)| key == "import")
+}
+
+/// Serve a file a script imports as a JS module, for `?import`
+///
+/// `url` is the file's own URL, which files that aren't compiled export.
+pub async fn serve_import(state: &ServerState, file: &Path, url: &str) -> Response {
+    let code = match state.bundler.served_import(file).await {
+        Ok(Some(code)) => {
+            let mut code = commonjs_module(&code, |specifier| import_url(state, specifier, file));
+            if state.hmr_enabled && Module::detect_type(file) == ModuleType::Css {
+                code.insert_str(0, &hot_context(state, &format!("{}{}", url, IMPORT_QUERY)));
+                code.push_str(HOT_STYLE);
+            }
+            code
+        }
+        Ok(None) => format!("export default {};\n", json!(url)),
+        Err(e) => return transform_failed(file, e),
+    };
+    ([(header::CONTENT_TYPE, JAVASCRIPT)], code).into_response()
+}
+
+/// Serve a resized copy of an image, for `?width=`
+pub fn serve_image(state: &ServerState, file: &Path, variant: &Variant) -> Response {
+    let resized = variant.format_of(file).and_then(|format| {
+        let contents = state.fs.read(file)?;
+        anyhow::Ok((format, images::resize(&contents, file, variant)?))
+    });
+    match resized {
+        Ok((format, contents)) => ([(header::CONTENT_TYPE, format.mime())], contents).into_response(),
+        Err(e) => transform_failed(file, e),
+    }
+}
+
+/// URL an import of `specifier` from `importer` loads, or `None` to leave
+/// the import as written
+///
+/// Modules updated in place since the page loaded carry the time of the
+/// update, so the browser fetches them again.
+fn import_url(state: &ServerState, specifier: &str, importer: &Path) -> Option<String> {
+    let url = resolved_url(state, specifier, importer)?;
+    let resolved = state.bundler.resolve(specifier, importer).ok().flatten();
+    let key = resolved.map(|path| state.fs.canonicalize(&path).unwrap_or(path));
+    match key.and_then(|key| state.hot_timestamps.read().get(&key).copied()) {
+        Some(timestamp) => Some(with_timestamp(&url, timestamp)),
+        None => Some(url),
+    }
+}
+
+/// URL the module `specifier` names is served from, as a hot context
+/// knows it, or `None` when it isn't served
+fn resolved_url(state: &ServerState, specifier: &str, importer: &Path) -> Option<String> {
+    if deps::is_bare(specifier) && state.bundler.resolve_package(specifier, importer).ok().flatten().is_some() {
+        return Some(deps::deps_url(&state.config.output.base_path(), specifier));
+    }
+    module_url(state, &state.bundler.resolve(specifier, importer).ok().flatten()?)
+}
+
+/// URL a project module is served from; `None` for files outside the root,
+/// and virtual modules other than `?srcset=` imports
+pub fn module_url(state: &ServerState, path: &Path) -> Option<String> {
+    let (file, srcset) = match images::srcset_module(path) {
+        Some((image, query)) => (image, Some(query)),
+        None => (path.to_path_buf(), None),
+    };
+    let root = state.fs.canonicalize(&state.config.root).unwrap_or_else(|_| state.config.root.clone());
+    let relative = file.strip_prefix(&root).or_else(|_| file.strip_prefix(&state.config.root)).ok()?;
+    state.fs.is_file(&file).then(|| file_url(&state.config.output.base_path(), relative, srcset))
+}
+
+/// URL of a project file by its path below the root: with `?import` unless
+/// it's a script, or with the query of a `?srcset=` import
+fn file_url(base: &str, relative: &Path, srcset: Option<&str>) -> String {
+    let query = match srcset {
+        Some(query) => format!("?{}", query),
+        None if Module::detect_type(relative).is_js_like() => String::new(),
+        None => IMPORT_QUERY.to_string(),
+    };
+    format!("{}{}{}", base, path_to_module_id(relative), query)
+}
+
+/// A URL with the time of an update added to its query
+pub fn with_timestamp(url: &str, timestamp: u64) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}t={}", url, separator, timestamp)
+}
+
+/// Code giving a module served from `url` its `import.meta.hot`
+///
+/// It's kept to one line, ahead of the module's first, so the module's
+/// source map still lines up.
+fn hot_context(state: &ServerState, url: &str) -> String {
+    let client = format!("{}{}", state.config.output.base_path().trim_end_matches('/'), HMR_CLIENT_PATH);
+    format!(
+        "import {{ createHotContext as __component_hot_context__ }} from {}; import.meta.hot = __component_hot_context__({}); ",
+        json!(client),
+        json!(url)
+    )
+}
+
+/// A CommonJS module as an ES module exporting `module.exports`, each
+/// `require()` answered by an import of the URL `url` gives its specifier
+///
+/// Specifiers without a URL are left out, and `require()` returns
+/// `undefined` for them.
+fn commonjs_module(code: &str, url: impl Fn(&str) -> Option<String>) -> String {
+    let mut imports = String::new();
+    let mut table = Vec::new();
+    let specifiers = REQUIRE_REGEX
+        .captures_iter(code)
+        .filter_map(|caps| serde_json::from_str::<String>(&caps[1]).ok());
+    for (index, specifier) in specifiers.enumerate() {
+        let Some(url) = url(&specifier) else {
+            continue;
+        };
+        imports.push_str(&format!("import __component_import_{} from {};\n", index, json!(url)));
+        table.push(format!("{}: __component_import_{}", json!(specifier), index));
+    }
+    
+    let table = if table.is_empty() { "{}".to_string() } else { format!("{{ {} }}", table.join(", ")) };
+    format!(
+        "{}var module = {{ exports: {{}} }}, exports = module.exports;\nfunction require(specifier) {{ return {}[specifier]; }}\n{}\nexport default module.exports;\n",
+        imports,
+        table,
+        code.trim_end()
+    )
+}
+
+/// Report a module that failed to transform, to the terminal and in the
+/// response the browser shows
+fn transform_failed(file: &Path, e: anyhow::Error) -> Response {
+    let message = format!("Failed to transform {}: {:#}", file.display(), e);
+    error!("{}", message);
+    (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_import_urls() {
+        assert_eq!(file_url("/", Path::new("src/App.tsx"), None), "/src/App.tsx");
+        assert_eq!(file_url("/app/", Path::new("src/styles/main.css"), None), "/app/src/styles/main.css?import");
+        assert_eq!(file_url("/", Path::new("src/logo.svg"), None), "/src/logo.svg?import");
+        assert_eq!(file_url("/", Path::new("src/hero.jpg"), Some("srcset=480")), "/src/hero.jpg?srcset=480");
+        assert_eq!(with_timestamp("/src/App.tsx", 1), "/src/App.tsx?t=1");
+        assert_eq!(with_timestamp("/src/main.css?import", 1), "/src/main.css?import&t=1");
+        assert!(is_import("import"));
+        assert!(is_import("import&t=1700000000"));
+        assert!(!is_import("srcset=480"));
+        
+        let code = "require(\"./base.css\");\nrequire(\"https://example.com/font.css\");\n(function() {})();\nmodule.exports = {};\n";
+        let wrapped = commonjs_module(code, |specifier| {
+            specifier.starts_with("./").then(|| format!("/src/{}?import", &specifier[2..]))
+        });
+        assert_eq!(
+            wrapped,
+            "import __component_import_0 from \"/src/base.css?import\";\nvar module = { exports: {} }, exports = module.exports;\nfunction require(specifier) { return { \"./base.css\": __component_import_0 }[specifier]; }\nrequire(\"./base.css\");\nrequire(\"https://example.com/font.css\");\n(function() {})();\nmodule.exports = {};\nexport default module.exports;\n"
+        );
+    }
+}
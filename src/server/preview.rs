@@ -0,0 +1,130 @@
+//! Preview server for the build output
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::State,
+    http::{header, StatusCode, Uri},
+    middleware,
+    response::{Html, IntoResponse, Response},
+    Router,
+};
+use colored::Colorize;
+use tracing::info;
+
+use super::headers::{self, HeaderRules};
+use super::{compression_layer, MimeTypes};
+use crate::config::Config;
+use crate::msg;
+
+struct StaticState {
+    dir: PathBuf,
+    base: String,
+    fallback: Option<String>,
+    mime: MimeTypes,
+}
+
+/// Router serving files from `dir`
+///
+/// Assets are also found under `base`, the public URL prefix they are
+/// referenced with. Paths that aren't files get `fallback`, so client-side
+/// routes load the app, or a 404 without one.
+pub fn static_router(dir: PathBuf, base: &str, fallback: Option<String>, mime: MimeTypes) -> Router {
+    let state = Arc::new(StaticState {
+        dir,
+        base: base.trim_end_matches('/').to_string(),
+        fallback,
+        mime,
+    });
+    Router::new().fallback(serve).with_state(state)
+}
+
+async fn serve(State(state): State<Arc<StaticState>>, uri: Uri) -> Response {
+    let path = uri.path();
+    let candidates = [Some(path), path.strip_prefix(state.base.as_str()).filter(|_| !state.base.is_empty())];
+    
+    for candidate in candidates.into_iter().flatten() {
+        let relative = Path::new(candidate.trim_start_matches('/'));
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        let file = state.dir.join(relative);
+        if file.is_file() {
+            if let Ok(contents) = tokio::fs::read(&file).await {
+                return ([(header::CONTENT_TYPE, state.mime.for_path(&file))], contents).into_response();
+            }
+        }
+    }
+    
+    match &state.fallback {
+        Some(page) => Html(page.clone()).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("File not found: {}", path)).into_response(),
+    }
+}
+
+/// Serves a finished build the way static hosting would
+pub struct PreviewServer {
+    config: Arc<Config>,
+    dir: PathBuf,
+}
+
+impl PreviewServer {
+    pub fn new(config: Arc<Config>, dir: PathBuf) -> Self {
+        Self { config, dir }
+    }
+    
+    /// Serve until the process is stopped
+    pub async fn start(&self, host: &str, port: u16) -> Result<()> {
+        if !self.dir.is_dir() {
+            anyhow::bail!("{} does not exist; run `component build` first", self.dir.display());
+        }
+        
+        let fallback = std::fs::read_to_string(self.dir.join("index.html")).ok();
+        let rules = Arc::new(HeaderRules::new(&self.config.preview.headers)?);
+        let mime = MimeTypes::new(&self.config.mime_types)?;
+        let app = static_router(self.dir.clone(), &self.config.output.base_path(), fallback, mime)
+            .layer(middleware::from_fn_with_state(rules, headers::apply))
+            .layer(compression_layer(self.config.preview.compression));
+        
+        let listener = tokio::net::TcpListener::bind((host, port))
+            .await
+            .with_context(|| format!("Failed to bind {}:{}", host, port))?;
+        let addr = listener.local_addr()?;
+        
+        let url = format!("http://{}{}", addr, self.config.output.base_path());
+        eprintln!("{} {}\n", "→".blue(), msg!("preview.serving", dir = self.dir.display(), url = url.cyan().underline()));
+        info!("{}", msg!("preview.listening", address = addr));
+        
+        axum::serve(listener, app).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    
+    use super::*;
+    use crate::server::tests::get;
+    
+    #[tokio::test]
+    async fn test_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.js"), "console.log('compress me');\n".repeat(100)).unwrap();
+        let app = |enabled| {
+            static_router(dir.path().to_path_buf(), "/", None, MimeTypes::new(&HashMap::new()).unwrap())
+                .layer(compression_layer(enabled))
+        };
+        
+        let (head, _) = get(app(true), "/main.js", &[("Accept-Encoding", "gzip")]).await;
+        assert!(head.contains("content-encoding: gzip"), "{}", head);
+        let (head, _) = get(app(true), "/main.js", &[("Accept-Encoding", "br")]).await;
+        assert!(head.contains("content-encoding: br"), "{}", head);
+        
+        let (head, _) = get(app(false), "/main.js", &[("Accept-Encoding", "gzip, br")]).await;
+        assert!(head.starts_with("http/1.1 200"), "{}", head);
+        assert!(!head.contains("content-encoding"), "{}", head);
+    }
+}
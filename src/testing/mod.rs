@@ -0,0 +1,566 @@
+//! Test support for snapshotting build output
+//!
+//! Enabled with the `test-support` feature. `BuildHarness` builds a fixture
+//! project in memory and returns the emitted files, so plugin authors and
+//! integration tests can assert on output without writing to `dist/`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::bundler::{BuildOptions, Bundler};
+use crate::config::Config;
+
+/// Placeholder substituted for the fixture directory in emitted files
+pub const ROOT_PLACEHOLDER: &str = "<root>";
+
+/// Runs in-memory builds of a fixture project
+pub struct BuildHarness {
+    /// Canonical fixture directory
+    root: PathBuf,
+    
+    /// Loaded fixture configuration
+    config: Config,
+    
+    /// Build options
+    options: BuildOptions,
+}
+
+/// Files emitted by a harness build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildOutput {
+    /// File contents keyed by path relative to the output directory; binary
+    /// files such as images are decoded lossily
+    pub files: BTreeMap<String, String>,
+}
+
+impl BuildHarness {
+    /// Load the fixture's `component.toml`
+    ///
+    /// Content hashes are disabled by default so output file names are
+    /// stable; re-enable them with [`BuildHarness::hash`].
+    pub fn new<P: AsRef<Path>>(fixture_dir: P) -> Result<Self> {
+        let root = fs::canonicalize(fixture_dir.as_ref()).with_context(|| {
+            format!("Fixture directory not found: {}", fixture_dir.as_ref().display())
+        })?;
+        
+        let mut config = Config::load(root.join("component.toml"))?;
+        config.output.hash = false;
+        
+        Ok(Self {
+            root,
+            config,
+            options: BuildOptions {
+                outdir: None,
+                minify: false,
+                sourcemap: false,
+                target: "es2020".to_string(),
+                inspect_transforms: false,
+            },
+        })
+    }
+    
+    /// Enable or disable minification (default: disabled)
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.options.minify = minify;
+        self
+    }
+    
+    /// Enable or disable content hashes in file names (default: disabled)
+    pub fn hash(mut self, hash: bool) -> Self {
+        self.config.output.hash = hash;
+        self
+    }
+    
+    /// Adjust the fixture configuration before building
+    pub fn configure(mut self, f: impl FnOnce(&mut Config)) -> Self {
+        f(&mut self.config);
+        self
+    }
+    
+    /// Run the build and collect emitted files
+    ///
+    /// Absolute fixture paths in the output are replaced with
+    /// [`ROOT_PLACEHOLDER`] so snapshots are portable between machines.
+    pub async fn build(&self) -> Result<BuildOutput> {
+        let bundler = Bundler::new(self.config.clone(), self.options.clone())?;
+        let files = bundler.build_in_memory().await?;
+        
+        let root = self.root.display().to_string();
+        let files = files
+            .into_iter()
+            .map(|(name, contents)| (name, String::from_utf8_lossy(&contents).replace(&root, ROOT_PLACEHOLDER)))
+            .collect();
+        
+        Ok(BuildOutput { files })
+    }
+}
+
+impl BuildOutput {
+    /// Contents of an emitted file
+    pub fn get(&self, file_name: &str) -> Option<&str> {
+        self.files.get(file_name).map(String::as_str)
+    }
+    
+    /// Names of all emitted files, sorted
+    pub fn file_names(&self) -> Vec<&str> {
+        self.files.keys().map(String::as_str).collect()
+    }
+    
+    /// Render all files as a single string suitable for snapshot comparison
+    pub fn snapshot(&self) -> String {
+        let mut out = String::new();
+        for (name, contents) in &self.files {
+            out.push_str(&format!("--- {} ---\n{}\n", name, contents.trim_end()));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn write_fixture(dir: &Path) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("component.toml"),
+            "[project]\nname = \"fixture\"\n\n[entrypoints]\nmain = \"src/main.js\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/main.js"), "import { a } from './a.js';\nconsole.log(a);\n").unwrap();
+        fs::write(dir.join("src/a.js"), "export const a = 1;\n").unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_build_harness_emits_files_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        
+        let output = BuildHarness::new(dir.path()).unwrap().build().await.unwrap();
+        
+        assert_eq!(output.file_names(), vec!["main.js", "manifest.json"]);
+        let bundle = output.get("main.js").unwrap();
+        assert!(bundle.contains("__component_modules__[\"src/a.js\"]"));
+        assert!(bundle.ends_with("__component_export__(\"main\", \"src/main.js\");\n"));
+        assert!(!bundle.contains(&dir.path().canonicalize().unwrap().display().to_string()));
+        assert!(!dir.path().join("dist").exists());
+    }
+    
+    #[tokio::test]
+    async fn test_entry_chunks_run_their_roots_and_publish_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("src/main.js"), "import { a } from './a.js';\nexport const b = a + 1;\nexport default 'main';\n").unwrap();
+        fs::write(dir.path().join("index.html"), "<html><body>\n<script type=\"module\" src=\"/src/first.js\"></script>\n<script type=\"module\" src=\"/src/second.js\"></script>\n</body></html>\n").unwrap();
+        fs::write(dir.path().join("src/first.js"), "console.log('first');\n").unwrap();
+        fs::write(dir.path().join("src/second.js"), "console.log('second');\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.insert("index".to_string(), "index.html".to_string());
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        // A page runs each of its scripts, in document order
+        let page = output.get("index.js").unwrap();
+        assert!(page.ends_with("__component_require__(\"src/first.js\");\n__component_require__(\"src/second.js\");\n"), "{}", page);
+        
+        let main = output.get("main.js").unwrap();
+        assert!(main.ends_with("__component_export__(\"main\", \"src/main.js\");\n"), "{}", main);
+        if std::process::Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        fs::write(dir.path().join("main.js"), main).unwrap();
+        let run = std::process::Command::new("node")
+            .arg("-e")
+            .arg("globalThis.window = globalThis; require(process.argv[1]); const main = window.__component_exports__.main; console.log(main.b, main.default);")
+            .arg(dir.path().join("main.js"))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "2 main\n", "{}", String::from_utf8_lossy(&run.stderr));
+    }
+    
+    #[tokio::test]
+    async fn test_outputs_differing_only_in_case_collide() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        
+        let harness = BuildHarness::new(dir.path()).unwrap().configure(|config| {
+            config.entrypoints.insert("Main".to_string(), "src/a.js".to_string());
+        });
+        let error = harness.build().await.unwrap_err().to_string();
+        assert!(error.contains("Several outputs would be written to the same file"), "{}", error);
+        assert!(error.contains("Main.js from entry 'Main' (src/a.js)") && error.contains("main.js from entry 'main' (src/main.js)"), "{}", error);
+        
+        // Distinct names build as before
+        let output = harness
+            .configure(|config| {
+                config.entrypoints.remove("Main");
+                config.entrypoints.insert("other".to_string(), "src/a.js".to_string());
+            })
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(output.file_names(), vec!["main.js", "manifest.json", "other.js"]);
+    }
+    
+    #[tokio::test]
+    async fn test_chunk_loader_retries_and_reloads_as_configured() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("src/main.js"), "import('./a.js');\n").unwrap();
+        
+        let harness = BuildHarness::new(dir.path()).unwrap().configure(|config| {
+            config.output.chunk_retries = 3;
+            config.output.reload_on_chunk_error = true;
+        });
+        let main = harness.build().await.unwrap().get("main.js").unwrap().to_string();
+        assert!(main.contains("if (attempt >= 3) {"), "{}", main);
+        assert!(main.contains("setTimeout(resolve, 500 * Math.pow(2, attempt))"));
+        assert!(main.contains("window.dispatchEvent(event) && (function() {"));
+        assert!(main.contains("location.reload();"));
+        if std::process::Command::new("node").arg("--version").output().is_ok() {
+            fs::write(dir.path().join("main.js"), &main).unwrap();
+            let check = std::process::Command::new("node").arg("--check").arg(dir.path().join("main.js")).output().unwrap();
+            assert!(check.status.success(), "{}", String::from_utf8_lossy(&check.stderr));
+        }
+        
+        let harness = harness.configure(|config| {
+            config.output.chunk_retries = 0;
+            config.output.reload_on_chunk_error = false;
+        });
+        let main = harness.build().await.unwrap().get("main.js").unwrap().to_string();
+        assert!(!main.contains("attempt >=") && !main.contains("setTimeout"), "{}", main);
+        assert!(!main.contains("location.reload()"));
+        assert!(main.contains("window.dispatchEvent(event);"));
+    }
+    
+    #[tokio::test]
+    async fn test_isolated_transforms_run_in_process_without_an_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        
+        // Tests aren't the component CLI, so there's no binary to run workers
+        let isolated = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| config.build.isolate_transforms = true)
+            .build()
+            .await
+            .unwrap();
+        let output = BuildHarness::new(dir.path()).unwrap().build().await.unwrap();
+        assert_eq!(isolated.snapshot(), output.snapshot());
+    }
+    
+    #[tokio::test]
+    async fn test_routes_become_async_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::create_dir_all(dir.path().join("src/pages/blog")).unwrap();
+        fs::write(dir.path().join("src/pages/index.js"), "import { a } from '../a.js';\nexport default a;\n").unwrap();
+        fs::write(dir.path().join("src/pages/blog/[slug].js"), "export default 'post';\n").unwrap();
+        fs::write(dir.path().join("src/main.js"), "import routes from 'virtual:routes';\nimport { a } from './a.js';\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| config.routes.dir = Some("src/pages".to_string()))
+            .build()
+            .await
+            .unwrap();
+        
+        assert_eq!(output.file_names(), vec!["index.js", "main.js", "manifest.json", "slug.js"]);
+        let main = output.get("main.js").unwrap();
+        assert!(main.contains(r#"{ path: "/blog/:slug", file: "blog/[slug].js", component: () => __component_import__("src/pages/blog/[slug].js") }"#));
+        assert!(main.contains(r#""src/pages/index.js":"index.js""#));
+        
+        // Modules already in the entry chunk are not duplicated
+        let index = output.get("index.js").unwrap();
+        assert!(index.contains("\"src/pages/index.js\""));
+        assert!(!index.contains(r#"__component_modules__["src/a.js"]"#));
+    }
+    
+    #[tokio::test]
+    async fn test_async_chunks_hold_what_each_entry_lacks() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("src/main.js"), "import('./other.js');\nimport('./lazy.js');\n").unwrap();
+        fs::write(dir.path().join("src/other.js"), "export default 'other';\n").unwrap();
+        fs::write(dir.path().join("src/admin.js"), "import { a } from './a.js';\nimport('./lazy.js');\n").unwrap();
+        fs::write(dir.path().join("src/lazy.js"), "import { a } from './a.js';\nexport default a;\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.insert("other".to_string(), "src/other.js".to_string());
+                config.entrypoints.insert("admin".to_string(), "src/admin.js".to_string());
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        assert_eq!(output.file_names(), vec!["admin.js", "lazy-2.js", "lazy.js", "main.js", "manifest.json", "other-2.js", "other.js"]);
+        
+        // Importing another entry still loads it from an async chunk
+        let main = output.get("main.js").unwrap();
+        assert!(main.contains(r#"{"src/lazy.js":"lazy-2.js","src/other.js":"other-2.js"}"#), "{}", main);
+        assert!(output.get("other-2.js").unwrap().contains("exports.default = 'other';"));
+        
+        // The lazy chunk main loads brings what admin bundles itself
+        assert!(output.get("lazy-2.js").unwrap().contains(r#"__component_modules__["src/a.js"]"#));
+        assert!(output.get("admin.js").unwrap().contains(r#"{"src/lazy.js":"lazy.js"}"#));
+        assert!(!output.get("lazy.js").unwrap().contains(r#"__component_modules__["src/a.js"]"#));
+    }
+    
+    #[tokio::test]
+    async fn test_library_emits_esm_and_commonjs() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("src/main.js"), "import { a } from './a.js';\nexport { a };\nexport * from './more.js';\nexport default function greet(name) { return 'hi ' + name; }\nexport const lazy = () => import('./lazy.js');\n").unwrap();
+        fs::write(dir.path().join("src/more.js"), "export const more = 2;\n").unwrap();
+        fs::write(dir.path().join("src/lazy.js"), "export default 'lazy';\n").unwrap();
+        fs::write(dir.path().join("src/legacy.js"), "module.exports = { c: 3 };\n").unwrap();
+        
+        let harness = BuildHarness::new(dir.path()).unwrap().configure(|config| {
+            config.library.enabled = true;
+            config.library.check_parity = std::process::Command::new("node").arg("--version").output().is_ok();
+        });
+        let output = harness.build().await.unwrap();
+        assert_eq!(output.file_names(), vec!["main.cjs", "main.mjs", "manifest.json"]);
+        let esm = output.get("main.mjs").unwrap();
+        assert!(esm.contains("export { __component_export_0__ as a, __component_export_1__ as default, __component_export_2__ as lazy, __component_export_3__ as more };"), "{}", esm);
+        let cjs = output.get("main.cjs").unwrap();
+        assert!(cjs.starts_with("\"use strict\";\n"));
+        assert!(cjs.contains("\"a\": function() { return __component_import_0[\"a\"]; }"), "{}", cjs);
+        assert!(cjs.contains("var __component_import_0 = require(\"src/a.js\");\n"), "{}", cjs);
+        assert!(cjs.contains("__component_import__(\"src/lazy.js\")"));
+        assert!(cjs.ends_with("module.exports = __component_require__(\"src/main.js\");\n"));
+        
+        if !harness.config.library.check_parity {
+            return;
+        }
+        fs::write(dir.path().join("main.cjs"), cjs).unwrap();
+        let run = std::process::Command::new("node")
+            .arg("-e")
+            .arg("const m = require(process.argv[1]); m.lazy().then((l) => console.log(m.default('x'), m.a, m.more, l.default));")
+            .arg(dir.path().join("main.cjs"))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "hi x 1 2 lazy\n");
+        
+        // Names only known at runtime are missing from the ES module build
+        fs::write(dir.path().join("src/main.js"), "export * from './legacy.js';\nexport const b = 1;\n").unwrap();
+        let error = harness.build().await.unwrap_err().to_string();
+        assert!(error.contains("main: 'c' is exported by require() but not by the ES module build"), "{}", error);
+    }
+    
+    #[tokio::test]
+    async fn test_library_exports_stay_live() {
+        if std::process::Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("src/main.js"), "export let counter = 0;\nexport function inc() { counter++; }\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| config.library.enabled = true)
+            .build()
+            .await
+            .unwrap();
+        fs::write(dir.path().join("main.mjs"), output.get("main.mjs").unwrap()).unwrap();
+        fs::write(dir.path().join("main.cjs"), output.get("main.cjs").unwrap()).unwrap();
+        
+        let esm = std::process::Command::new("node")
+            .args(["--input-type=module", "-e", "const m = await import(process.argv[1]); m.inc(); console.log(m.counter);"])
+            .arg(format!("file://{}", dir.path().join("main.mjs").display()))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&esm.stdout), "1\n", "{}", String::from_utf8_lossy(&esm.stderr));
+        
+        let cjs = std::process::Command::new("node")
+            .args(["-e", "const m = require(process.argv[1]); m.inc(); console.log(m.counter);"])
+            .arg(dir.path().join("main.cjs"))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&cjs.stdout), "1\n", "{}", String::from_utf8_lossy(&cjs.stderr));
+    }
+    
+    #[tokio::test]
+    async fn test_html_entry_inlines_critical_css() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("index.html"), "<html><head>\n<link rel=\"stylesheet\" href=\"/src/base.css\">\n</head><body>\n<script type=\"module\" src=\"/src/main.js\"></script>\n</body></html>\n").unwrap();
+        fs::write(dir.path().join("src/base.css"), "body { margin: 0; }\n").unwrap();
+        fs::write(dir.path().join("src/lazy.css"), ".lazy { color: red; }\n").unwrap();
+        fs::write(dir.path().join("src/lazy.js"), "import './lazy.css';\nexport default 1;\n").unwrap();
+        fs::write(dir.path().join("src/main.js"), "import { a } from './a.js';\nimport('./lazy.js');\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.clear();
+                config.entrypoints.insert("index".to_string(), "index.html".to_string());
+                config.output.critical_css = true;
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        assert_eq!(output.file_names(), vec!["csp.json", "index.css", "index.html", "index.js", "lazy.js", "manifest.json"]);
+        let page = output.get("index.html").unwrap();
+        assert!(page.contains("<style>body { margin: 0; }</style>"));
+        assert!(output.get("csp.json").unwrap().contains("\"style-src\": [\n      \"'sha256-"));
+        assert!(page.contains(r#"<link rel="preload" href="/index.css" as="style">"#));
+        assert!(page.contains("<link rel=\"stylesheet\" href=\"/index.css\">\n<script defer src=\"/index.js\"></script>\n</body>"), "{}", page);
+        assert!(!page.contains("onload"));
+        assert!(!page.contains("/src/"));
+        assert_eq!(output.get("index.css").unwrap(), "body { margin: 0; }\n.lazy { color: red; }");
+        
+        // Extracted CSS no longer injects itself
+        assert!(!output.get("lazy.js").unwrap().contains("createElement('style')"));
+        assert!(output.get("index.js").unwrap().contains("__component_require__(\"src/base.css\");\n__component_require__(\"src/main.js\");"));
+    }
+    
+    #[tokio::test]
+    async fn test_csp_covers_every_inline_handler_of_a_page() {
+        use base64::Engine;
+        use sha2::{Digest, Sha256};
+        
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("index.html"), "<html><head>\n<link rel=\"stylesheet\" href=\"/src/base.css\">\n<script type=\"application/ld+json\">{\"@type\": \"WebSite\"}</script>\n</head><body>\n<button onclick=\"go(&quot;home&quot;)\">Home</button>\n<img src=\"https://example.com/a.png\" onerror='this.remove()'>\n<script type=\"module\" src=\"/src/main.js\"></script>\n</body></html>\n").unwrap();
+        fs::write(dir.path().join("src/base.css"), "body { margin: 0; }\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.clear();
+                config.entrypoints.insert("index".to_string(), "index.html".to_string());
+                config.output.critical_css = true;
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        let csp: serde_json::Value = serde_json::from_str(output.get("csp.json").unwrap()).unwrap();
+        let script_src: Vec<&str> = csp["index.html"]["script-src"].as_array().unwrap().iter().map(|v| v.as_str().unwrap()).collect();
+        let hash = |source: &str| format!("'sha256-{}'", base64::engine::general_purpose::STANDARD.encode(Sha256::digest(source.as_bytes())));
+        assert_eq!(script_src.len(), 3, "{:?}", script_src);
+        assert_eq!(script_src[0], "'unsafe-hashes'");
+        
+        // Each handler in the page has its hash, and the data block doesn't
+        let page = output.get("index.html").unwrap();
+        let handlers = regex::Regex::new(r#"\son\w+=(?:"([^"]*)"|'([^']*)')"#).unwrap();
+        let values: Vec<String> = handlers
+            .captures_iter(page)
+            .map(|cap| cap.get(1).or_else(|| cap.get(2)).unwrap().as_str().replace("&quot;", "\""))
+            .collect();
+        assert_eq!(values, vec!["go(\"home\")", "this.remove()"]);
+        for value in &values {
+            assert!(script_src.contains(&hash(value).as_str()), "{} isn't allowed by {:?}", value, script_src);
+        }
+        assert!(!script_src.contains(&hash("{\"@type\": \"WebSite\"}").as_str()));
+        assert_eq!(csp["index.html"]["style-src"].as_array().unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_html_entry_subsets_and_preloads_fonts() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("index.html"), "<html><head>\n<link rel=\"stylesheet\" href=\"/src/base.css\">\n</head><body>\n<script type=\"module\" src=\"/src/main.js\"></script>\n</body></html>\n").unwrap();
+        fs::write(dir.path().join("src/base.css"), "@font-face { font-family: Body; src: url(./fonts/body.ttf) format(\"truetype\"); }\n@font-face { font-family: Icons; src: url(./fonts/icons.ttf); }\nbody { font-family: Body, sans-serif; }\n").unwrap();
+        fs::create_dir_all(dir.path().join("src/fonts")).unwrap();
+        fs::write(dir.path().join("src/fonts/body.ttf"), crate::bundler::fonts::test_font()).unwrap();
+        fs::write(dir.path().join("src/fonts/icons.ttf"), crate::bundler::fonts::test_font()).unwrap();
+        fs::write(dir.path().join("src/main.js"), "console.log(1);\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.clear();
+                config.entrypoints.insert("index".to_string(), "index.html".to_string());
+                config.fonts.subset = true;
+                config.fonts.unicode_ranges = vec!["U+0041".to_string()];
+                config.fonts.preload = true;
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        assert_eq!(output.file_names(), vec!["assets/body.woff2", "assets/icons.woff2", "index.html", "index.js", "manifest.json"]);
+        assert!(output.get("assets/body.woff2").unwrap().starts_with("wOF2"));
+        
+        // Only fonts the page's CSS uses are preloaded
+        let page = output.get("index.html").unwrap();
+        assert!(page.contains(r#"<link rel="preload" href="/assets/body.woff2" as="font" type="font/woff2" crossorigin>"#), "{}", page);
+        assert!(!page.contains("icons.woff2"));
+        let index = output.get("index.js").unwrap();
+        assert!(index.contains(r#"src: url("/assets/body.woff2") format("woff2");"#), "{}", index);
+        assert!(index.contains(r#"src: url("/assets/icons.woff2");"#));
+    }
+    
+    #[tokio::test]
+    async fn test_split_by_type_emits_typed_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("index.html"), "<html><head>\n<link rel=\"stylesheet\" href=\"/src/base.css\">\n</head><body>\n<script type=\"module\" src=\"/src/main.js\"></script>\n</body></html>\n").unwrap();
+        fs::write(dir.path().join("src/base.css"), "body { margin: 0; }\n").unwrap();
+        fs::write(dir.path().join("src/lazy.js"), "export default 1;\n").unwrap();
+        fs::write(dir.path().join("src/main.js"), "import('./lazy.js');\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.clear();
+                config.entrypoints.insert("index".to_string(), "index.html".to_string());
+                config.output.critical_css = true;
+                config.output.split_by_type = true;
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        assert_eq!(output.file_names(), vec!["csp.json", "css/index.css", "index.html", "js/index.js", "js/lazy.js", "manifest.json"]);
+        let page = output.get("index.html").unwrap();
+        assert!(page.contains(r#"<link rel="preload" href="/css/index.css" as="style""#));
+        assert!(page.contains(r#"<script defer src="/js/index.js"></script>"#));
+        assert!(output.get("js/index.js").unwrap().contains(r#""src/lazy.js":"js/lazy.js""#));
+        assert!(output.get("manifest.json").unwrap().contains(r#""js/index.js": "/js/index.js""#));
+    }
+    
+    #[tokio::test]
+    async fn test_html_entry_bundles_inline_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(dir.path());
+        fs::write(dir.path().join("index.html"), "<html><head>\n<style>h1 { color: red; }</style>\n<script type=\"application/ld+json\">{\"@type\": \"WebSite\"}</script>\n</head><body>\n<script type=\"module\">\nimport { a } from './src/a.js';\nconsole.log(a);\n</script>\n<script data-component-ignore>window.kept = 1;</script>\n</body></html>\n").unwrap();
+        
+        let output = BuildHarness::new(dir.path())
+            .unwrap()
+            .configure(|config| {
+                config.entrypoints.clear();
+                config.entrypoints.insert("index".to_string(), "index.html".to_string());
+            })
+            .build()
+            .await
+            .unwrap();
+        
+        let page = output.get("index.html").unwrap();
+        assert!(!page.contains("h1 {") && !page.contains("console.log(a)"));
+        assert!(page.contains("application/ld+json") && page.contains("window.kept = 1;"));
+        
+        let script = output.get("index.js").unwrap();
+        assert!(script.contains("// Module: index.html?inline-0.css") && script.contains("h1 { color: red; }"));
+        assert!(script.contains("console.log(__component_import_0[\"a\"])") && script.contains("\"src/a.js\""));
+        assert!(script.contains("__component_require__(\"index.html?inline-0.css\");\n__component_require__(\"index.html?inline-1.js\");"));
+        
+        // The JSON-LD block is data CSP doesn't check
+        let csp = output.get("csp.json").unwrap();
+        assert_eq!(csp.matches("'sha256-").count(), 1);
+    }
+}
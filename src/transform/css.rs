@@ -0,0 +1,82 @@
+//! Stylesheet imports
+//!
+//! `@import`, `@use` and `@forward` rules in CSS, Sass and Less files pull in
+//! other stylesheets. Local ones become dependencies of the importing module
+//! and are removed from its source, so the bundle injects each stylesheet
+//! once, imports first. URLs and Sass built-in modules (`sass:math`) are left
+//! in place for the browser or the preprocessor.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^[ \t]*@(?:import|use|forward)\s+(?:\([^)]*\)\s*)?(?:url\(\s*)?["']([^"']+)["']\s*\)?[^;\n]*;?[ \t]*\n?"#).unwrap()
+});
+
+/// Specifiers of the local stylesheets a stylesheet imports
+pub fn extract_imports(source: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    for (specifier, _) in import_ranges(source) {
+        if !imports.contains(&specifier) {
+            imports.push(specifier);
+        }
+    }
+    imports
+}
+
+/// Every local import with the byte range of its specifier, in order
+pub fn import_ranges(source: &str) -> Vec<(String, Range<usize>)> {
+    IMPORT_REGEX
+        .captures_iter(source)
+        .map(|cap| cap.get(1).unwrap())
+        .filter(|specifier| is_local(specifier.as_str()))
+        .map(|specifier| (specifier.as_str().to_string(), specifier.range()))
+        .collect()
+}
+
+/// The stylesheet without the rules importing local stylesheets
+pub fn strip_imports(source: &str) -> Cow<'_, str> {
+    IMPORT_REGEX.replace_all(source, |caps: &regex::Captures| {
+        if is_local(&caps[1]) {
+            String::new()
+        } else {
+            caps[0].to_string()
+        }
+    })
+}
+
+/// Whether a specifier names a stylesheet to bundle rather than a URL
+fn is_local(specifier: &str) -> bool {
+    !specifier.contains("://")
+        && !specifier.starts_with("//")
+        && !specifier.starts_with("data:")
+        && !specifier.starts_with("sass:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_stylesheet_imports() {
+        let source = r#"@use "sass:math";
+@use '~@acme/tokens/colors' as colors;
+@import url("./reset.css");
+@import (reference) "mixins.less";
+@import url(https://fonts.googleapis.com/css?family=Inter);
+@forward "theme";
+body { color: red; }
+"#;
+        assert_eq!(
+            extract_imports(source),
+            vec!["~@acme/tokens/colors", "./reset.css", "mixins.less", "theme"]
+        );
+        assert_eq!(
+            strip_imports(source),
+            "@use \"sass:math\";\n@import url(https://fonts.googleapis.com/css?family=Inter);\nbody { color: red; }\n"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! CSS-in-JS compile-time helpers
+//!
+//! Stand-ins for the Babel plugins of styled-components and Emotion, which
+//! can't run here. In files importing the library, components and class
+//! names assigned to a variable are named after it, and components get ids
+//! derived from the file, so server and client render the same class names:
+//!
+//! ```js
+//! const Button = styled.button`...`;
+//! // styled-components
+//! const Button = styled.button.withConfig({ displayName: "Button", componentId: "sc-1f2e3d4c-0" })`...`;
+//! // Emotion
+//! const Button = styled("button", { target: "e1f2e3d4c0", label: "Button" })`...`;
+//! const title = css`color: red;label:title;`;
+//! ```
+
+use std::borrow::Cow;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::json;
+
+use crate::config::CssInJsConfig;
+use crate::utils::hash_content;
+
+static STYLED_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b((?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*=\s*)styled(?:\.([A-Za-z]\w*)|\(([^(),]*)\))(\s*(?:`|<|\.attrs\b))").unwrap()
+});
+
+static CSS_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:const|let|var)\s+([A-Za-z_$][\w$]*)\s*=\s*css\s*`").unwrap()
+});
+
+static STYLED_COMPONENTS_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"from\s*["']styled-components["']"#).unwrap()
+});
+
+static EMOTION_STYLED_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"from\s*["']@emotion/styled["']"#).unwrap()
+});
+
+static EMOTION_CSS_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"from\s*["']@emotion/(?:react|css)["']"#).unwrap()
+});
+
+/// Apply the enabled helpers to a script module
+///
+/// `file_id` is the module's path relative to the root, which component
+/// ids are derived from.
+pub fn apply<'a>(source: &'a str, file_id: &str, config: &CssInJsConfig) -> Cow<'a, str> {
+    if !config.display_names && !config.ssr {
+        return Cow::Borrowed(source);
+    }
+    let hash = &hash_content(file_id.as_bytes())[..8];
+    let mut source = Cow::Borrowed(source);
+    
+    if config.styled_components && STYLED_COMPONENTS_IMPORT.is_match(&source) {
+        source = Cow::Owned(configure_styled_components(&source, hash, config));
+    }
+    if config.emotion && EMOTION_STYLED_IMPORT.is_match(&source) {
+        source = Cow::Owned(configure_emotion_styled(&source, hash, config));
+    }
+    if config.emotion && config.display_names && EMOTION_CSS_IMPORT.is_match(&source) {
+        source = Cow::Owned(label_emotion_css(&source));
+    }
+    source
+}
+
+/// Add `.withConfig({ displayName, componentId })` to `styled` components
+fn configure_styled_components(source: &str, hash: &str, config: &CssInJsConfig) -> String {
+    let mut index = 0;
+    STYLED_REGEX
+        .replace_all(source, |caps: &regex::Captures| {
+            let target = match caps.get(3) {
+                Some(tag) => format!(".{}", tag.as_str()),
+                None => format!("({})", &caps[4]),
+            };
+            let mut options = Vec::new();
+            if config.display_names {
+                options.push(format!("displayName: {}", json!(&caps[2])));
+            }
+            if config.ssr {
+                options.push(format!("componentId: {}", json!(format!("sc-{}-{}", hash, index))));
+            }
+            index += 1;
+            format!("{}styled{}.withConfig({{ {} }}){}", &caps[1], target, options.join(", "), &caps[5])
+        })
+        .into_owned()
+}
+
+/// Pass `{ target, label }` options to Emotion's `styled`
+fn configure_emotion_styled(source: &str, hash: &str, config: &CssInJsConfig) -> String {
+    let mut index = 0;
+    STYLED_REGEX
+        .replace_all(source, |caps: &regex::Captures| {
+            let target = match caps.get(3) {
+                Some(tag) => json!(tag.as_str()).to_string(),
+                None => caps[4].to_string(),
+            };
+            let mut options = Vec::new();
+            if config.ssr {
+                options.push(format!("target: {}", json!(format!("e{}{}", hash, index))));
+            }
+            if config.display_names {
+                options.push(format!("label: {}", json!(&caps[2])));
+            }
+            index += 1;
+            format!("{}styled({}, {{ {} }}){}", &caps[1], target, options.join(", "), &caps[5])
+        })
+        .into_owned()
+}
+
+/// Append `label:<variable>;` to Emotion `css` templates
+fn label_emotion_css(source: &str) -> String {
+    let mut labeled = String::with_capacity(source.len());
+    let mut copied = 0;
+    
+    for caps in CSS_REGEX.captures_iter(source) {
+        let body = caps.get(0).map_or(0, |m| m.end());
+        if body < copied {
+            continue;
+        }
+        let Some(end) = template_end(source, body) else {
+            continue;
+        };
+        let styles = source[body..end].trim_end();
+        let separator = if styles.is_empty() || styles.ends_with(';') { "" } else { ";" };
+        labeled.push_str(&source[copied..end]);
+        labeled.push_str(&format!("{}label:{};", separator, &caps[1]));
+        copied = end;
+    }
+    
+    labeled.push_str(&source[copied..]);
+    labeled
+}
+
+/// Index of the backtick closing a template literal whose body starts at `start`
+fn template_end(source: &str, start: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'$' if depth == 0 && bytes.get(i + 1) == Some(&b'{') => {
+                depth = 1;
+                i += 1;
+            }
+            b'{' if depth > 0 => depth += 1,
+            b'}' if depth > 0 => depth -= 1,
+            b'`' if depth == 0 => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_css_in_js_helpers() {
+        let config = CssInJsConfig { styled_components: true, emotion: true, ..CssInJsConfig::default() };
+        let hash = &hash_content(b"src/Button.tsx")[..8];
+        
+        let source = "import styled from 'styled-components';\nconst Button = styled.button`color: red;`;\nconst Link = styled(Anchor)<Props>`color: ${p => p.color};`;\n";
+        let expected = format!(
+            "import styled from 'styled-components';\nconst Button = styled.button.withConfig({{ displayName: \"Button\", componentId: \"sc-{0}-0\" }})`color: red;`;\nconst Link = styled(Anchor).withConfig({{ displayName: \"Link\", componentId: \"sc-{0}-1\" }})<Props>`color: ${{p => p.color}};`;\n",
+            hash
+        );
+        assert_eq!(apply(source, "src/Button.tsx", &config), expected);
+        
+        let source = "import styled from '@emotion/styled';\nimport { css } from '@emotion/react';\nconst Button = styled.button`color: red;`;\nconst title = css`\n  color: ${theme.color}\n`;\n";
+        let expected = format!(
+            "import styled from '@emotion/styled';\nimport {{ css }} from '@emotion/react';\nconst Button = styled(\"button\", {{ target: \"e{}0\", label: \"Button\" }})`color: red;`;\nconst title = css`\n  color: ${{theme.color}}\n;label:title;`;\n",
+            hash
+        );
+        assert_eq!(apply(source, "src/Button.tsx", &config), expected);
+        
+        let untouched = "const Button = styled.button`color: red;`;";
+        assert_eq!(apply(untouched, "src/Button.tsx", &config), untouched);
+    }
+}
@@ -0,0 +1,98 @@
+//! Mode-specific code
+//!
+//! Imports can be limited to one mode, either with an import attribute or a
+//! specifier suffix:
+//!
+//! ```js
+//! import { devtools } from 'lib' with { env: 'development' };
+//! import 'why-did-you-render?dev';
+//! ```
+//!
+//! Imports for another mode are dropped before dependencies are collected,
+//! so their modules never reach the bundle; the others lose the condition.
+//! `import.meta.env.DEV`, `PROD` and `MODE` are replaced by constants.
+
+use std::borrow::Cow;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::{DEVELOPMENT, PRODUCTION};
+
+static CONDITIONAL_IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^([ \t]*(?:import|export)\s+(?:[^;'"]*?\s+from\s+)?)(["'])([^"']+)["'](?:\s*with\s*\{\s*env\s*:\s*["']([\w-]+)["']\s*\})?([ \t]*;)?"#).unwrap()
+});
+
+static ENV_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\bimport\.meta\.env\.(DEV|PROD|MODE)\b").unwrap()
+});
+
+/// Apply `conditions`, the mode first, to a script module's source
+pub fn apply<'a>(source: &'a str, conditions: &[String]) -> Cow<'a, str> {
+    let mode = conditions.first().map_or(PRODUCTION, String::as_str);
+    
+    let source = CONDITIONAL_IMPORT_REGEX.replace_all(source, |caps: &regex::Captures| {
+        let (prefix, quote, specifier) = (&caps[1], &caps[2], &caps[3]);
+        let (specifier, condition) = match (caps.get(4), specifier.rsplit_once('?')) {
+            (Some(env), _) => (specifier, env.as_str()),
+            (None, Some((bare, "dev"))) => (bare, DEVELOPMENT),
+            (None, Some((bare, "prod"))) => (bare, PRODUCTION),
+            _ => return caps[0].to_string(),
+        };
+        
+        if conditions.iter().any(|active| active == condition) {
+            let semicolon = caps.get(5).map_or("", |m| m.as_str());
+            format!("{}{}{}{}{}", prefix, quote, specifier, quote, semicolon)
+        } else {
+            // Keep the line, so later lines stay where they were
+            String::new()
+        }
+    });
+    
+    if !ENV_REGEX.is_match(&source) {
+        return source;
+    }
+    let replaced = ENV_REGEX.replace_all(&source, |caps: &regex::Captures| match &caps[1] {
+        "DEV" => (mode == DEVELOPMENT).to_string(),
+        "PROD" => (mode != DEVELOPMENT).to_string(),
+        _ => serde_json::json!(mode).to_string(),
+    });
+    Cow::Owned(replaced.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_mode_specific_code() {
+        let source = r#"import { devtools } from 'lib' with { env: 'development' };
+import 'why-did-you-render?dev';
+import { report } from "./report.js?prod";
+import { a } from './a.js';
+if (import.meta.env.DEV) devtools(import.meta.env.MODE);
+"#;
+        let development = vec![DEVELOPMENT.to_string()];
+        assert_eq!(
+            apply(source, &development),
+            r#"import { devtools } from 'lib';
+import 'why-did-you-render';
+
+import { a } from './a.js';
+if (true) devtools("development");
+"#
+        );
+        
+        let production = vec![PRODUCTION.to_string(), "staging".to_string()];
+        assert_eq!(
+            apply(source, &production),
+            r#"
+
+import { report } from "./report.js";
+import { a } from './a.js';
+if (false) devtools("production");
+"#
+        );
+        assert_eq!(apply("import 'lib' with { env: 'staging' };", &production), "import 'lib';");
+    }
+}
@@ -0,0 +1,633 @@
+//! GraphQL documents
+//!
+//! `.graphql` and `.gql` files become modules exporting the parsed document
+//! in the AST format of graphql-js, so they can be passed straight to Apollo
+//! or urql. `#import "./fragment.graphql"` lines pull in the definitions of
+//! other documents, and every named operation is also exported on its own.
+
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+static IMPORT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^[ \t]*#import\s+["']([^"']+)["']"#).unwrap()
+});
+
+/// Properties of the exported document that named operations can't shadow
+const RESERVED_EXPORTS: &[&str] = &["kind", "definitions", "loc", "default", "__esModule"];
+
+/// Specifiers of the `#import` lines in a document
+pub fn extract_imports(source: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    for (specifier, _) in import_ranges(source) {
+        if !imports.contains(&specifier) {
+            imports.push(specifier);
+        }
+    }
+    imports
+}
+
+/// Every `#import` with the byte range of its specifier, in order
+pub fn import_ranges(source: &str) -> Vec<(String, Range<usize>)> {
+    IMPORT_REGEX
+        .captures_iter(source)
+        .map(|cap| cap.get(1).unwrap())
+        .map(|specifier| (specifier.as_str().to_string(), specifier.range()))
+        .collect()
+}
+
+/// Compile a document to a module
+///
+/// Imports are emitted as `require()` calls with the specifiers as written;
+/// the bundler points them at the resolved modules.
+pub fn to_module(source: &str) -> Result<String, String> {
+    let document = parse(source)?;
+    let imports = extract_imports(source);
+    let body = IMPORT_REGEX.replace_all(source, "");
+    let operations: Vec<&str> = document["definitions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|d| d["kind"] == "OperationDefinition")
+        .filter_map(|d| d["name"]["value"].as_str())
+        .filter(|name| !RESERVED_EXPORTS.contains(name))
+        .collect();
+    
+    let mut code = format!(
+        "var doc = {};\ndoc.loc = {{ start: 0, end: {}, source: {{ body: {}, name: \"GraphQL request\" }} }};\n",
+        document,
+        body.len(),
+        json!(body.trim()),
+    );
+    
+    if !imports.is_empty() {
+        let requires: Vec<String> = imports.iter().map(|i| format!("require({})", json!(i))).collect();
+        code.push_str(&format!(
+            r#"[{}].forEach(function (imported) {{
+  var added = false;
+  imported.definitions.forEach(function (definition) {{
+    var exists = doc.definitions.some(function (d) {{
+      return d.kind === definition.kind && d.name && definition.name && d.name.value === definition.name.value;
+    }});
+    if (!exists) {{
+      doc.definitions.push(definition);
+      added = true;
+    }}
+  }});
+  if (added) {{
+    doc.loc.source.body += "\n\n" + imported.loc.source.body;
+  }}
+}});
+"#,
+            requires.join(", ")
+        ));
+    }
+    
+    code.push_str("module.exports = doc;\nmodule.exports.default = doc;\n");
+    if !operations.is_empty() {
+        code.push_str(
+            r#"function operation(name) {
+  return {
+    kind: "Document",
+    definitions: doc.definitions.filter(function (d) {
+      return d.kind !== "OperationDefinition" || (d.name && d.name.value === name);
+    }),
+    loc: doc.loc
+  };
+}
+"#,
+        );
+        for name in operations {
+            code.push_str(&format!("module.exports.{0} = operation(\"{0}\");\n", name));
+        }
+    }
+    
+    Ok(code)
+}
+
+/// Parse an executable document into a graphql-js AST
+pub fn parse(source: &str) -> Result<Value, String> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { source, tokens, pos: 0 };
+    
+    let mut definitions = Vec::new();
+    while parser.peek() != &Token::Eof {
+        definitions.push(parser.definition()?);
+    }
+    if definitions.is_empty() {
+        return Err("GraphQL document has no definitions".to_string());
+    }
+    
+    Ok(json!({ "kind": "Document", "definitions": definitions }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(char),
+    Spread,
+    Name(String),
+    Int(String),
+    Float(String),
+    Str { value: String, block: bool },
+    Eof,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' | b',' => i += 1,
+            0xEF if source[i..].starts_with('\u{feff}') => i += 3,
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'.' if source[i..].starts_with("...") => {
+                tokens.push((Token::Spread, start));
+                i += 3;
+            }
+            b'!' | b'$' | b'&' | b'(' | b')' | b':' | b'=' | b'@' | b'[' | b']' | b'{' | b'|' | b'}' => {
+                tokens.push((Token::Punct(bytes[i] as char), start));
+                i += 1;
+            }
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' => {
+                while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                tokens.push((Token::Name(source[start..i].to_string()), start));
+            }
+            b'-' | b'0'..=b'9' => {
+                let (token, end) = number(source, start)?;
+                tokens.push((token, start));
+                i = end;
+            }
+            b'"' if source[i..].starts_with("\"\"\"") => {
+                let end = source[i + 3..]
+                    .find("\"\"\"")
+                    .map(|offset| i + 3 + offset)
+                    .ok_or_else(|| error_at(source, start, "Unterminated block string"))?;
+                let raw = source[i + 3..end].replace("\\\"\"\"", "\"\"\"");
+                tokens.push((Token::Str { value: block_string_value(&raw), block: true }, start));
+                i = end + 3;
+            }
+            b'"' => {
+                let (value, end) = string(source, start)?;
+                tokens.push((Token::Str { value, block: false }, start));
+                i = end;
+            }
+            _ => {
+                let c = source[i..].chars().next().unwrap_or_default();
+                return Err(error_at(source, start, &format!("Unexpected character '{}'", c)));
+            }
+        }
+    }
+    
+    tokens.push((Token::Eof, source.len()));
+    Ok(tokens)
+}
+
+fn number(source: &str, start: usize) -> Result<(Token, usize), String> {
+    let bytes = source.as_bytes();
+    let mut i = start;
+    let digits = |i: &mut usize| {
+        let from = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i > from
+    };
+    
+    if bytes[i] == b'-' {
+        i += 1;
+    }
+    if !digits(&mut i) {
+        return Err(error_at(source, start, "Invalid number"));
+    }
+    
+    let mut float = false;
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        float = true;
+        if !digits(&mut i) {
+            return Err(error_at(source, start, "Invalid number"));
+        }
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        i += 1;
+        float = true;
+        if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+            i += 1;
+        }
+        if !digits(&mut i) {
+            return Err(error_at(source, start, "Invalid number"));
+        }
+    }
+    
+    let text = source[start..i].to_string();
+    Ok((if float { Token::Float(text) } else { Token::Int(text) }, i))
+}
+
+fn string(source: &str, start: usize) -> Result<(String, usize), String> {
+    let mut value = String::new();
+    let mut chars = source[start + 1..].char_indices();
+    
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, start + 1 + offset + 1)),
+            '\n' | '\r' => break,
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('"') => value.push('"'),
+                Some('\\') => value.push('\\'),
+                Some('/') => value.push('/'),
+                Some('b') => value.push('\u{8}'),
+                Some('f') => value.push('\u{c}'),
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| error_at(source, start + 1 + offset, "Invalid unicode escape"))?;
+                    value.push(c);
+                }
+                _ => return Err(error_at(source, start + 1 + offset, "Invalid escape sequence")),
+            },
+            c => value.push(c),
+        }
+    }
+    
+    Err(error_at(source, start, "Unterminated string"))
+}
+
+/// Block string value: common indentation and blank first/last lines removed
+fn block_string_value(raw: &str) -> String {
+    let lines: Vec<&str> = raw.lines().collect();
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    
+    let mut lines: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| if i == 0 { line } else { line.get(indent..).unwrap_or("") })
+        .collect();
+    while lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+fn error_at(source: &str, offset: usize, message: &str) -> String {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    format!("{} at {}:{}", message, line, column)
+}
+
+fn name_node(value: &str) -> Value {
+    json!({ "kind": "Name", "value": value })
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+    
+    fn error(&self, expected: &str) -> String {
+        let (token, offset) = &self.tokens[self.pos];
+        let found = match token {
+            Token::Punct(c) => format!("'{}'", c),
+            Token::Spread => "'...'".to_string(),
+            Token::Name(name) => format!("'{}'", name),
+            Token::Int(n) | Token::Float(n) => n.clone(),
+            Token::Str { .. } => "a string".to_string(),
+            Token::Eof => "end of document".to_string(),
+        };
+        error_at(self.source, *offset, &format!("Expected {}, found {}", expected, found))
+    }
+    
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == &Token::Punct(c)
+    }
+    
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = self.is_punct(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    
+    fn expect_punct(&mut self, c: char) -> Result<(), String> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", c)))
+        }
+    }
+    
+    fn name(&mut self) -> Result<Value, String> {
+        match self.peek().clone() {
+            Token::Name(name) => {
+                self.pos += 1;
+                Ok(name_node(&name))
+            }
+            _ => Err(self.error("a name")),
+        }
+    }
+    
+    fn keyword(&self) -> Option<&str> {
+        match self.peek() {
+            Token::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+    
+    fn definition(&mut self) -> Result<Value, String> {
+        if self.is_punct('{') {
+            return Ok(json!({
+                "kind": "OperationDefinition",
+                "operation": "query",
+                "variableDefinitions": [],
+                "directives": [],
+                "selectionSet": self.selection_set()?,
+            }));
+        }
+        
+        match self.keyword() {
+            Some(operation @ ("query" | "mutation" | "subscription")) => {
+                let mut node = Map::new();
+                node.insert("kind".into(), json!("OperationDefinition"));
+                node.insert("operation".into(), json!(operation));
+                self.pos += 1;
+                if matches!(self.peek(), Token::Name(_)) {
+                    node.insert("name".into(), self.name()?);
+                }
+                node.insert("variableDefinitions".into(), self.variable_definitions()?);
+                node.insert("directives".into(), self.directives(false)?);
+                node.insert("selectionSet".into(), self.selection_set()?);
+                Ok(Value::Object(node))
+            }
+            Some("fragment") => {
+                self.pos += 1;
+                if self.keyword() == Some("on") {
+                    return Err(self.error("a fragment name"));
+                }
+                let name = self.name()?;
+                let type_condition = self.type_condition()?;
+                Ok(json!({
+                    "kind": "FragmentDefinition",
+                    "name": name,
+                    "typeCondition": type_condition,
+                    "directives": self.directives(false)?,
+                    "selectionSet": self.selection_set()?,
+                }))
+            }
+            _ => Err(self.error("an operation or fragment")),
+        }
+    }
+    
+    fn type_condition(&mut self) -> Result<Value, String> {
+        if self.keyword() != Some("on") {
+            return Err(self.error("'on'"));
+        }
+        self.pos += 1;
+        Ok(json!({ "kind": "NamedType", "name": self.name()? }))
+    }
+    
+    fn variable_definitions(&mut self) -> Result<Value, String> {
+        let mut definitions = Vec::new();
+        if self.eat_punct('(') {
+            while !self.eat_punct(')') {
+                let variable = self.variable()?;
+                self.expect_punct(':')?;
+                let mut node = Map::new();
+                node.insert("kind".into(), json!("VariableDefinition"));
+                node.insert("variable".into(), variable);
+                node.insert("type".into(), self.type_reference()?);
+                if self.eat_punct('=') {
+                    node.insert("defaultValue".into(), self.value(true)?);
+                }
+                node.insert("directives".into(), self.directives(true)?);
+                definitions.push(Value::Object(node));
+            }
+        }
+        Ok(Value::Array(definitions))
+    }
+    
+    fn variable(&mut self) -> Result<Value, String> {
+        self.expect_punct('$')?;
+        Ok(json!({ "kind": "Variable", "name": self.name()? }))
+    }
+    
+    fn type_reference(&mut self) -> Result<Value, String> {
+        let inner = if self.eat_punct('[') {
+            let item = self.type_reference()?;
+            self.expect_punct(']')?;
+            json!({ "kind": "ListType", "type": item })
+        } else {
+            json!({ "kind": "NamedType", "name": self.name()? })
+        };
+        
+        if self.eat_punct('!') {
+            Ok(json!({ "kind": "NonNullType", "type": inner }))
+        } else {
+            Ok(inner)
+        }
+    }
+    
+    fn directives(&mut self, is_const: bool) -> Result<Value, String> {
+        let mut directives = Vec::new();
+        while self.eat_punct('@') {
+            let name = self.name()?;
+            directives.push(json!({
+                "kind": "Directive",
+                "name": name,
+                "arguments": self.arguments(is_const)?,
+            }));
+        }
+        Ok(Value::Array(directives))
+    }
+    
+    fn arguments(&mut self, is_const: bool) -> Result<Value, String> {
+        let mut arguments = Vec::new();
+        if self.eat_punct('(') {
+            while !self.eat_punct(')') {
+                let name = self.name()?;
+                self.expect_punct(':')?;
+                arguments.push(json!({ "kind": "Argument", "name": name, "value": self.value(is_const)? }));
+            }
+        }
+        Ok(Value::Array(arguments))
+    }
+    
+    fn selection_set(&mut self) -> Result<Value, String> {
+        self.expect_punct('{')?;
+        let mut selections = Vec::new();
+        while !self.eat_punct('}') {
+            selections.push(self.selection()?);
+        }
+        if selections.is_empty() {
+            return Err(self.error("a selection"));
+        }
+        Ok(json!({ "kind": "SelectionSet", "selections": selections }))
+    }
+    
+    fn selection(&mut self) -> Result<Value, String> {
+        if self.peek() == &Token::Spread {
+            self.pos += 1;
+            return match self.keyword() {
+                Some(name) if name != "on" => {
+                    let name = self.name()?;
+                    Ok(json!({ "kind": "FragmentSpread", "name": name, "directives": self.directives(false)? }))
+                }
+                _ => {
+                    let mut node = Map::new();
+                    node.insert("kind".into(), json!("InlineFragment"));
+                    if self.keyword() == Some("on") {
+                        node.insert("typeCondition".into(), self.type_condition()?);
+                    }
+                    node.insert("directives".into(), self.directives(false)?);
+                    node.insert("selectionSet".into(), self.selection_set()?);
+                    Ok(Value::Object(node))
+                }
+            };
+        }
+        
+        let mut node = Map::new();
+        node.insert("kind".into(), json!("Field"));
+        let name = self.name()?;
+        if self.eat_punct(':') {
+            node.insert("alias".into(), name);
+            node.insert("name".into(), self.name()?);
+        } else {
+            node.insert("name".into(), name);
+        }
+        node.insert("arguments".into(), self.arguments(false)?);
+        node.insert("directives".into(), self.directives(false)?);
+        if self.is_punct('{') {
+            node.insert("selectionSet".into(), self.selection_set()?);
+        }
+        Ok(Value::Object(node))
+    }
+    
+    fn value(&mut self, is_const: bool) -> Result<Value, String> {
+        match self.peek().clone() {
+            Token::Punct('$') if !is_const => self.variable(),
+            Token::Punct('[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                while !self.eat_punct(']') {
+                    values.push(self.value(is_const)?);
+                }
+                Ok(json!({ "kind": "ListValue", "values": values }))
+            }
+            Token::Punct('{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                while !self.eat_punct('}') {
+                    let name = self.name()?;
+                    self.expect_punct(':')?;
+                    fields.push(json!({ "kind": "ObjectField", "name": name, "value": self.value(is_const)? }));
+                }
+                Ok(json!({ "kind": "ObjectValue", "fields": fields }))
+            }
+            Token::Int(value) => {
+                self.pos += 1;
+                Ok(json!({ "kind": "IntValue", "value": value }))
+            }
+            Token::Float(value) => {
+                self.pos += 1;
+                Ok(json!({ "kind": "FloatValue", "value": value }))
+            }
+            Token::Str { value, block } => {
+                self.pos += 1;
+                Ok(json!({ "kind": "StringValue", "value": value, "block": block }))
+            }
+            Token::Name(name) => {
+                self.pos += 1;
+                Ok(match name.as_str() {
+                    "true" | "false" => json!({ "kind": "BooleanValue", "value": name == "true" }),
+                    "null" => json!({ "kind": "NullValue" }),
+                    _ => json!({ "kind": "EnumValue", "value": name }),
+                })
+            }
+            _ => Err(self.error("a value")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_document() {
+        let source = r#"
+            #import "./user.graphql"
+            query GetUser($id: ID!, $first: Int = 10) @cached {
+              user(id: $id) {
+                ...UserFields
+                avatar: picture(size: [64, 128], filter: { kind: CROP, ratio: 1.5 })
+                ... on Admin @include(if: true) { role }
+              }
+            }
+            fragment Extra on User { bio(format: """
+                markdown
+            """) }
+        "#;
+        
+        assert_eq!(extract_imports(source), ["./user.graphql"]);
+        
+        let document = parse(source).unwrap();
+        let query = &document["definitions"][0];
+        assert_eq!(query["operation"], "query");
+        assert_eq!(query["name"]["value"], "GetUser");
+        assert_eq!(query["variableDefinitions"][0]["type"]["kind"], "NonNullType");
+        assert_eq!(query["variableDefinitions"][1]["defaultValue"], json!({ "kind": "IntValue", "value": "10" }));
+        assert_eq!(query["directives"][0]["name"]["value"], "cached");
+        
+        let user = &query["selectionSet"]["selections"][0];
+        assert_eq!(user["arguments"][0]["value"]["kind"], "Variable");
+        let selections = &user["selectionSet"]["selections"];
+        assert_eq!(selections[0], json!({ "kind": "FragmentSpread", "name": name_node("UserFields"), "directives": [] }));
+        assert_eq!(selections[1]["alias"]["value"], "avatar");
+        assert_eq!(selections[1]["arguments"][1]["value"]["fields"][1]["value"]["kind"], "FloatValue");
+        assert_eq!(selections[2]["typeCondition"]["name"]["value"], "Admin");
+        
+        let fragment = &document["definitions"][1];
+        assert_eq!(fragment["kind"], "FragmentDefinition");
+        let bio = &fragment["selectionSet"]["selections"][0];
+        assert_eq!(bio["arguments"][0]["value"], json!({ "kind": "StringValue", "value": "markdown", "block": true }));
+        
+        let module = to_module(source).unwrap();
+        assert!(module.contains(r#"[require("./user.graphql")]"#));
+        assert!(module.contains(r#"module.exports.GetUser = operation("GetUser");"#));
+        
+        assert_eq!(parse("query { user(id: $id }").unwrap_err(), "Expected a name, found '}' at 1:22");
+        assert!(parse("fragment on User { id }").is_err());
+        assert!(parse("query Q($id: ID = $other) { a }").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Per-file JSX settings
+//!
+//! `[features]` sets the JSX runtime for the whole project. A file can
+//! override it with pragmas in a block comment, as Babel, TypeScript and esbuild
+//! allow, so Solid islands can live inside a React app:
+//!
+//! ```js
+//! /** @jsxImportSource solid-js */
+//! /** @jsxRuntime classic @jsx h @jsxFrag Fragment */
+//! ```
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::FeaturesConfig;
+
+static BLOCK_COMMENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)/\*.*?\*/").unwrap()
+});
+
+static PRAGMA_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"@(jsxRuntime|jsxImportSource|jsxFrag|jsx)\s+([^\s*]+)").unwrap()
+});
+
+/// How a file's JSX compiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsxRuntime {
+    /// Calls to a factory in scope, `React.createElement` by default
+    Classic { factory: String, fragment: String },
+    
+    /// Calls to functions imported from `<import_source>/jsx-runtime`
+    Automatic { import_source: String },
+}
+
+impl JsxRuntime {
+    /// The runtime for a file: the config's, overridden by the file's pragmas
+    ///
+    /// `@jsxImportSource` implies the automatic runtime, and `@jsx` or
+    /// `@jsxFrag` the classic one, unless `@jsxRuntime` says otherwise.
+    pub fn for_file(source: &str, features: &FeaturesConfig) -> Self {
+        let mut runtime = None;
+        let mut import_source = None;
+        let mut factory = None;
+        let mut fragment = None;
+        
+        for comment in BLOCK_COMMENT_REGEX.find_iter(source) {
+            for cap in PRAGMA_REGEX.captures_iter(comment.as_str()) {
+                let value = Some(cap[2].to_string());
+                match &cap[1] {
+                    "jsxRuntime" => runtime = value,
+                    "jsxImportSource" => import_source = value,
+                    "jsx" => factory = value,
+                    _ => fragment = value,
+                }
+            }
+        }
+        
+        let runtime = runtime.unwrap_or_else(|| {
+            if import_source.is_some() {
+                "automatic".to_string()
+            } else if factory.is_some() || fragment.is_some() {
+                "classic".to_string()
+            } else {
+                features.jsx_runtime.clone()
+            }
+        });
+        
+        if runtime == "classic" {
+            JsxRuntime::Classic {
+                factory: factory.unwrap_or_else(|| "React.createElement".to_string()),
+                fragment: fragment.unwrap_or_else(|| "React.Fragment".to_string()),
+            }
+        } else {
+            JsxRuntime::Automatic {
+                import_source: import_source.unwrap_or_else(|| features.jsx_import_source.clone()),
+            }
+        }
+    }
+    
+    /// Code for an empty fragment
+    pub fn empty_fragment(&self) -> String {
+        match self {
+            JsxRuntime::Classic { factory, fragment } => format!("{}({}, null)", factory, fragment),
+            JsxRuntime::Automatic { .. } => "_jsx(_Fragment, {})".to_string(),
+        }
+    }
+    
+    /// Names in scope the generated code refers to
+    pub fn bindings(&self) -> Vec<&str> {
+        match self {
+            JsxRuntime::Classic { factory, fragment } => [factory, fragment]
+                .iter()
+                .map(|name| name.split('.').next().unwrap_or(name))
+                .collect(),
+            JsxRuntime::Automatic { .. } => Vec::new(),
+        }
+    }
+    
+    /// Imports the generated code needs, if any
+    pub fn imports(&self) -> Option<String> {
+        match self {
+            JsxRuntime::Classic { .. } => None,
+            JsxRuntime::Automatic { import_source } => Some(format!(
+                "import {{ jsx as _jsx, Fragment as _Fragment }} from {};",
+                serde_json::json!(format!("{}/jsx-runtime", import_source))
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_jsx_pragmas() {
+        let features = FeaturesConfig::default();
+        let automatic = |source| JsxRuntime::Automatic { import_source: source };
+        
+        assert_eq!(JsxRuntime::for_file("const a = <></>;", &features), automatic("react".to_string()));
+        assert_eq!(
+            JsxRuntime::for_file("/** @jsxImportSource solid-js */\nconst a = <></>;", &features),
+            automatic("solid-js".to_string())
+        );
+        assert_eq!(
+            JsxRuntime::for_file("/** @jsxRuntime classic */", &features),
+            JsxRuntime::Classic { factory: "React.createElement".to_string(), fragment: "React.Fragment".to_string() }
+        );
+        assert_eq!(
+            JsxRuntime::for_file("/* @jsx h */\n/* @jsxFrag Fragment */", &features),
+            JsxRuntime::Classic { factory: "h".to_string(), fragment: "Fragment".to_string() }
+        );
+        assert_eq!(JsxRuntime::for_file("// @jsxRuntime classic", &features), automatic("react".to_string()));
+        
+        let classic = FeaturesConfig { jsx_runtime: "classic".to_string(), ..FeaturesConfig::default() };
+        let preact = JsxRuntime::for_file("/** @jsxImportSource preact */", &classic);
+        assert_eq!(preact.empty_fragment(), "_jsx(_Fragment, {})");
+        assert_eq!(preact.imports().unwrap(), r#"import { jsx as _jsx, Fragment as _Fragment } from "preact/jsx-runtime";"#);
+    }
+}
@@ -1,7 +1,17 @@
 //! Code transformation
 //!
-//! Handles TypeScript, JSX, and other transformations using SWC.
+//! Handles TypeScript, JSX, and other transformations.
 
+pub(crate) mod css;
+pub(crate) mod css_in_js;
+pub(crate) mod env;
+pub(crate) mod graphql;
+pub(crate) mod jsx;
+pub(crate) mod typescript;
+#[cfg(feature = "native")]
+pub mod worker;
+
+use std::borrow::Cow;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -10,8 +20,10 @@ use tracing::debug;
 
 use crate::bundler::ModuleType;
 use crate::config::Config;
+use crate::utils::path_to_module_id;
+use jsx::JsxRuntime;
 
-/// Code transformer using SWC
+/// Code transformer
 pub struct Transformer {
     /// Project configuration
     config: Arc<Config>,
@@ -30,12 +42,28 @@ impl Transformer {
         path: &Path,
         module_type: &ModuleType,
     ) -> Result<String> {
+        // Drop imports for another mode, fill in `import.meta.env`, and
+        // name CSS-in-JS components
+        let source = if module_type.is_js_like() {
+            let source = env::apply(source, &self.config.conditions);
+            let file_id = path_to_module_id(path.strip_prefix(&self.config.root).unwrap_or(path));
+            match css_in_js::apply(&source, &file_id, &self.config.features.css_in_js) {
+                Cow::Owned(configured) => Cow::Owned(configured),
+                Cow::Borrowed(_) => source,
+            }
+        } else {
+            Cow::Borrowed(source)
+        };
+        let source = source.as_ref();
+        
         match module_type {
             ModuleType::TypeScript => self.transform_typescript(source, path),
             ModuleType::Tsx => self.transform_tsx(source, path),
             ModuleType::Jsx => self.transform_jsx(source, path),
             ModuleType::Css => self.transform_css(source, path),
+            ModuleType::StyleSheet => self.transform_stylesheet(source, path),
             ModuleType::Json => self.transform_json(source, path),
+            ModuleType::Graphql => self.transform_graphql(source, path),
             _ => Ok(source.to_string()),
         }
     }
@@ -44,20 +72,17 @@ impl Transformer {
     fn transform_typescript(&self, source: &str, path: &Path) -> Result<String> {
         debug!("Transforming TypeScript: {}", path.display());
         
-        // For now, we'll do a simple transformation that removes type annotations
-        // In a full implementation, we'd use swc_ecma_parser and swc_ecma_transforms_typescript
-        
-        let result = self.strip_typescript_types(source)?;
-        
-        Ok(result)
+        typescript::compile(source, path, false, &[])
     }
     
     /// Transform TSX to JavaScript
     fn transform_tsx(&self, source: &str, path: &Path) -> Result<String> {
         debug!("Transforming TSX: {}", path.display());
         
-        // First strip TypeScript types, then transform JSX
-        let without_types = self.strip_typescript_types(source)?;
+        // First compile TypeScript, keeping the imports JSX will refer to,
+        // then transform JSX
+        let runtime = JsxRuntime::for_file(source, &self.config.features);
+        let without_types = typescript::compile(source, path, true, &runtime.bindings())?;
         self.transform_jsx(&without_types, path)
     }
     
@@ -71,35 +96,58 @@ impl Transformer {
         
         // Simple JSX transformation
         // In a full implementation, we'd use swc_ecma_transforms_react
-        let result = self.transform_jsx_simple(source)?;
+        let runtime = JsxRuntime::for_file(source, &self.config.features);
+        let (result, converted) = self.transform_jsx_simple(source, &runtime)?;
         
-        Ok(result)
+        // Imports go on the first line, so later lines stay where they were
+        match runtime.imports() {
+            Some(imports) if converted => Ok(format!("{} {}", imports, result)),
+            _ => Ok(result),
+        }
     }
     
     /// Transform CSS (wrap as JS module)
     fn transform_css(&self, source: &str, path: &Path) -> Result<String> {
         debug!("Transforming CSS: {}", path.display());
         
+        // Imported stylesheets are required first so their rules come earlier
+        let requires: String = css::extract_imports(source)
+            .iter()
+            .map(|specifier| format!("require({});\n", serde_json::json!(specifier)))
+            .collect();
+        
         // Wrap CSS as a JS module that injects styles
-        let escaped = source
+        let escaped = css::strip_imports(source)
             .replace('\\', "\\\\")
             .replace('`', "\\`")
             .replace("${", "\\${");
         
         let js_module = format!(
-            r#"(function() {{
+            r#"{}(function() {{
   var style = document.createElement('style');
   style.textContent = `{}`;
   document.head.appendChild(style);
 }})();
 module.exports = {{}};
 "#,
+            requires,
             escaped
         );
         
         Ok(js_module)
     }
     
+    /// Transform a stylesheet imported with `type: 'css'` to a JS module
+    /// exporting a constructable stylesheet
+    ///
+    /// `replaceSync` ignores `@import` rules, so they're left out.
+    fn transform_stylesheet(&self, source: &str, path: &Path) -> Result<String> {
+        debug!("Transforming stylesheet: {}", path.display());
+        
+        let css = serde_json::to_string(css::strip_imports(source).trim())?;
+        Ok(format!("var sheet = new CSSStyleSheet();\nsheet.replaceSync({});\nmodule.exports = sheet;\n", css))
+    }
+    
     /// Transform JSON to JS module
     fn transform_json(&self, source: &str, path: &Path) -> Result<String> {
         debug!("Transforming JSON: {}", path.display());
@@ -111,169 +159,19 @@ module.exports = {{}};
         Ok(format!("module.exports = {};", source))
     }
     
-    /// Simple TypeScript type stripping
-    /// This is a basic implementation - a full solution would use SWC's TypeScript transforms
-    fn strip_typescript_types(&self, source: &str) -> Result<String> {
-        let mut result = String::with_capacity(source.len());
-        let mut chars = source.chars().peekable();
-        let mut in_string = false;
-        let mut string_char = '"';
-        let mut in_template = false;
-        let mut template_depth: usize = 0;
-        
-        while let Some(c) = chars.next() {
-            // Handle string literals
-            if !in_template && (c == '"' || c == '\'') {
-                if !in_string {
-                    in_string = true;
-                    string_char = c;
-                } else if string_char == c {
-                    in_string = false;
-                }
-                result.push(c);
-                continue;
-            }
-            
-            // Handle template literals
-            if c == '`' {
-                if !in_template {
-                    in_template = true;
-                    template_depth = 0;
-                } else if template_depth == 0 {
-                    in_template = false;
-                }
-                result.push(c);
-                continue;
-            }
-            
-            // Inside strings/templates, just copy
-            if in_string || in_template {
-                if in_template && c == '{' {
-                    template_depth += 1;
-                } else if in_template && c == '}' {
-                    template_depth = template_depth.saturating_sub(1);
-                }
-                result.push(c);
-                continue;
-            }
-            
-            // Skip type annotations after colons (simplified)
-            if c == ':' {
-                // Check if this might be a type annotation
-                // Look ahead for common patterns
-                let rest: String = chars.clone().take(50).collect();
-                
-                // Skip if followed by type-like patterns
-                if rest.starts_with(' ') {
-                    let trimmed = rest.trim_start();
-                    // Check for common type patterns
-                    if trimmed.starts_with("string")
-                        || trimmed.starts_with("number")
-                        || trimmed.starts_with("boolean")
-                        || trimmed.starts_with("any")
-                        || trimmed.starts_with("void")
-                        || trimmed.starts_with("never")
-                        || trimmed.starts_with("unknown")
-                        || trimmed.starts_with("null")
-                        || trimmed.starts_with("undefined")
-                        || trimmed.starts_with("Array<")
-                        || trimmed.starts_with("Promise<")
-                        || trimmed.starts_with("Record<")
-                        || trimmed.starts_with("Map<")
-                        || trimmed.starts_with("Set<")
-                        || trimmed.chars().next().map(|c| c.is_uppercase()).unwrap_or(false)
-                    {
-                        // Skip until we hit =, ), ,, {, or newline
-                        let mut depth = 0;
-                        while let Some(&next) = chars.peek() {
-                            if next == '<' || next == '(' || next == '[' {
-                                depth += 1;
-                                chars.next();
-                            } else if next == '>' || next == ')' || next == ']' {
-                                depth -= 1;
-                                chars.next();
-                            } else if depth == 0 && (next == '=' || next == ')' || next == ',' || next == '{' || next == ';' || next == '\n') {
-                                break;
-                            } else {
-                                chars.next();
-                            }
-                        }
-                        continue;
-                    }
-                }
-            }
-            
-            // Skip interface/type declarations
-            if c == 'i' {
-                let rest: String = std::iter::once(c).chain(chars.clone().take(10)).collect();
-                if rest.starts_with("interface ") {
-                    // Skip until opening brace, then skip the whole block
-                    while let Some(nc) = chars.next() {
-                        if nc == '{' {
-                            let mut depth = 1;
-                            while depth > 0 {
-                                if let Some(bc) = chars.next() {
-                                    if bc == '{' { depth += 1; }
-                                    else if bc == '}' { depth -= 1; }
-                                }
-                            }
-                            break;
-                        }
-                    }
-                    continue;
-                }
-            }
-            
-            if c == 't' {
-                let rest: String = std::iter::once(c).chain(chars.clone().take(5)).collect();
-                if rest.starts_with("type ") {
-                    // Skip until semicolon or newline
-                    while let Some(nc) = chars.next() {
-                        if nc == ';' || nc == '\n' {
-                            break;
-                        }
-                    }
-                    continue;
-                }
-            }
-            
-            // Remove 'as Type' casts (simplified)
-            if c == ' ' {
-                let rest: String = chars.clone().take(3).collect();
-                if rest == "as " {
-                    // Skip "as Type"
-                    for _ in 0..3 {
-                        chars.next();
-                    }
-                    // Skip the type name
-                    let mut depth = 0;
-                    while let Some(&next) = chars.peek() {
-                        if next == '<' || next == '(' {
-                            depth += 1;
-                            chars.next();
-                        } else if next == '>' || next == ')' {
-                            depth -= 1;
-                            chars.next();
-                        } else if depth == 0 && (next.is_whitespace() || next == ',' || next == ')' || next == ';' || next == '}') {
-                            break;
-                        } else {
-                            chars.next();
-                        }
-                    }
-                    result.push(' ');
-                    continue;
-                }
-            }
-            
-            result.push(c);
-        }
+    /// Transform a GraphQL document to a JS module exporting its AST
+    fn transform_graphql(&self, source: &str, path: &Path) -> Result<String> {
+        debug!("Transforming GraphQL: {}", path.display());
         
-        Ok(result)
+        graphql::to_module(source)
+            .map_err(|e| anyhow::anyhow!("Invalid GraphQL in {}: {}", path.display(), e))
     }
     
     /// Simple JSX transformation
-    /// Transforms JSX syntax to React.createElement calls
-    fn transform_jsx_simple(&self, source: &str) -> Result<String> {
+    /// Transforms JSX syntax to calls of the file's JSX runtime, and reports
+    /// whether any element was converted
+    fn transform_jsx_simple(&self, source: &str, runtime: &JsxRuntime) -> Result<(String, bool)> {
+        let mut converted = false;
         let mut result = String::with_capacity(source.len());
         let mut chars = source.chars().peekable();
         let mut in_string = false;
@@ -303,7 +201,8 @@ module.exports = {{}};
                 if let Some(&next) = chars.peek() {
                     if next.is_alphabetic() || next == '>' || next == '/' {
                         // Try to parse JSX element
-                        let jsx_result = self.parse_jsx_element(&mut chars, c)?;
+                        let jsx_result = self.parse_jsx_element(&mut chars, c, runtime)?;
+                        converted |= !jsx_result.starts_with('<');
                         result.push_str(&jsx_result);
                         continue;
                     }
@@ -313,11 +212,11 @@ module.exports = {{}};
             result.push(c);
         }
         
-        Ok(result)
+        Ok((result, converted))
     }
     
     /// Parse a single JSX element
-    fn parse_jsx_element(&self, chars: &mut std::iter::Peekable<std::str::Chars>, start: char) -> Result<String> {
+    fn parse_jsx_element(&self, chars: &mut std::iter::Peekable<std::str::Chars>, start: char, runtime: &JsxRuntime) -> Result<String> {
         let mut element = String::from(start);
         let mut depth = 1;
         
@@ -331,9 +230,9 @@ module.exports = {{}};
                     }
                 } else if c == '>' {
                     // Check if previous char was /
-                    if element.len() >= 2 && element.chars().nth(element.len() - 2) == Some('/') {
-                        depth -= 1;
-                    } else if element.contains("</") {
+                    let self_closing = element.len() >= 2
+                        && element.chars().nth(element.len() - 2) == Some('/');
+                    if self_closing || element.contains("</") {
                         depth -= 1;
                     }
                 } else if c == '/' && chars.peek() == Some(&'>') {
@@ -348,11 +247,9 @@ module.exports = {{}};
         
         // For now, just wrap in a comment with the original
         // A full implementation would properly transform to createElement calls
-        let _import_source = &self.config.features.jsx_import_source;
-        
         // Very basic transformation for simple cases
         if element.starts_with("<>") {
-            return Ok(format!("React.createElement(React.Fragment, null)"));
+            return Ok(runtime.empty_fragment());
         }
         
         // Return original for complex cases (proper parsing needed)
@@ -387,4 +284,30 @@ mod tests {
         assert!(result.contains("document.createElement('style')"));
         assert!(result.contains("body { color: red; }"));
     }
+    
+    #[test]
+    fn test_transform_jsx_pragmas() {
+        let mut config = Config::default_config();
+        config.features.jsx = true;
+        let transformer = Transformer::new(Arc::new(config)).unwrap();
+        
+        let result = transformer.transform_jsx("const a = <></>;", Path::new("a.jsx")).unwrap();
+        assert_eq!(result, r#"import { jsx as _jsx, Fragment as _Fragment } from "react/jsx-runtime"; const a = _jsx(_Fragment, {});"#);
+        
+        let source = "/** @jsx h @jsxFrag Fragment */\nconst a = <></>;";
+        let result = transformer.transform_jsx(source, Path::new("b.jsx")).unwrap();
+        assert_eq!(result, "/** @jsx h @jsxFrag Fragment */\nconst a = h(Fragment, null);");
+    }
+    
+    #[test]
+    fn test_transform_css_requires_imports() {
+        let config = Config::default_config();
+        let transformer = Transformer::new(Arc::new(config)).unwrap();
+        
+        let css = "@import './reset.css';\nbody { color: red; }";
+        let result = transformer.transform_css(css, Path::new("test.css")).unwrap();
+        
+        assert!(result.starts_with("require(\"./reset.css\");\n"));
+        assert!(!result.contains("@import"));
+    }
 }
@@ -0,0 +1,932 @@
+//! TypeScript compilation
+//!
+//! Files are parsed with oxc's TypeScript parser and compiled one at a time,
+//! as `tsc` does with `isolatedModules`. Syntax that only exists for the type
+//! checker is cut out by span: annotations, type parameters and arguments,
+//! `as`, `satisfies` and non-null assertions, interfaces, type aliases,
+//! `declare`d and abstract members, overload signatures, and type-only
+//! imports and exports. Enums, namespaces, parameter properties,
+//! `import x = require()` and `export =` are rewritten to the JavaScript
+//! `tsc` emits for them. Everything else is copied as written, and removed
+//! code keeps its line breaks, so lines stay where they were.
+//!
+//! Imports none of whose bindings are used as values are dropped, as `tsc`
+//! drops them, so importing a type without `import type` doesn't load its
+//! module at runtime. `const enum`s compile like other enums.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser;
+use oxc_span::{GetSpan, SourceType, Span};
+use oxc_syntax::scope::ScopeFlags;
+use regex::Regex;
+
+/// Modifiers of class members and parameter properties
+static MODIFIER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:public|private|protected|readonly|override|declare|abstract)\s+").unwrap()
+});
+
+/// Compile TypeScript, or TSX when `jsx` is set, to JavaScript
+///
+/// Imports of the names in `keep` are kept even when unused, for bindings
+/// that code generated later refers to, such as the classic JSX factory.
+pub fn compile(source: &str, path: &Path, jsx: bool, keep: &[&str]) -> Result<String> {
+    let allocator = Allocator::default();
+    let source_type = if jsx { SourceType::tsx() } else { SourceType::ts() };
+    let parsed = Parser::new(&allocator, source, source_type).parse();
+    if let Some(error) = parsed.errors.first() {
+        let offset = error.labels.as_ref().and_then(|labels| labels.first()).map_or(0, |label| label.offset());
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        anyhow::bail!("Failed to parse {}:{}:{}: {}", path.display(), line, column, error.message);
+    }
+    
+    let mut compiler = Compiler::new(source);
+    compiler.find_types(&parsed.program.body);
+    compiler.visit_program(&parsed.program);
+    compiler.elide_imports(keep);
+    Ok(compiler.render(0..source.len()))
+}
+
+/// Replacement text for a range of the source
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+/// An import that is dropped, or loses some bindings, if they're unused
+struct Import {
+    span: Range<usize>,
+    
+    /// Default and namespace bindings, as written
+    default: Option<String>,
+    namespace: Option<String>,
+    
+    /// Named bindings: the specifier as written, its local name, and whether
+    /// it's marked `type`
+    named: Vec<(String, String, bool)>,
+    
+    /// Source onwards: `'./a' with { type: 'json' };`
+    tail: String,
+}
+
+/// Collects the edits that compile a file
+struct Compiler<'s> {
+    source: &'s str,
+    edits: Vec<Edit>,
+    
+    /// Names referred to as values
+    references: HashSet<String>,
+    
+    /// Imports to check once every reference is known
+    imports: Vec<Import>,
+    
+    /// Top-level names declared only as types
+    types: HashSet<String>,
+    
+    /// Namespace whose body is being compiled, which its exports are
+    /// assigned to
+    namespace: Option<String>,
+    
+    /// Enum whose initializer is being compiled, and its members, which the
+    /// initializer can refer to unqualified
+    enum_scope: Option<(String, Vec<String>)>,
+    
+    /// Starts of arrow function return types, which are removed with their
+    /// line breaks since `=>` must stay on the line of the parameters
+    arrow_returns: HashSet<u32>,
+}
+
+impl<'s> Compiler<'s> {
+    fn new(source: &'s str) -> Self {
+        Self {
+            source,
+            edits: Vec::new(),
+            references: HashSet::new(),
+            imports: Vec::new(),
+            types: HashSet::new(),
+            namespace: None,
+            enum_scope: None,
+            arrow_returns: HashSet::new(),
+        }
+    }
+    
+    fn text(&self, span: Span) -> &'s str {
+        &self.source[span.start as usize..span.end as usize]
+    }
+    
+    /// Source with the edits inside `range` applied
+    fn render(&self, range: Range<usize>) -> String {
+        let mut edits: Vec<&Edit> = self
+            .edits
+            .iter()
+            .filter(|edit| edit.range.start >= range.start && edit.range.end <= range.end)
+            .collect();
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        
+        let mut output = String::with_capacity(range.len());
+        let mut cursor = range.start;
+        for edit in edits {
+            // Edits inside a replaced range were already applied by it
+            if edit.range.start < cursor {
+                continue;
+            }
+            output.push_str(&self.source[cursor..edit.range.start]);
+            output.push_str(&edit.text);
+            cursor = edit.range.end;
+        }
+        output.push_str(&self.source[cursor..range.end]);
+        output
+    }
+    
+    fn replace(&mut self, range: Range<usize>, text: String) {
+        self.edits.push(Edit { range, text });
+    }
+    
+    fn insert(&mut self, at: usize, text: String) {
+        self.edits.push(Edit { range: at..at, text });
+    }
+    
+    /// Remove a range, keeping its line breaks
+    fn remove(&mut self, range: Range<usize>) {
+        let text = self.line_breaks(range.clone());
+        self.replace(range, text);
+    }
+    
+    fn remove_span(&mut self, span: Span) {
+        self.remove(span.start as usize..span.end as usize);
+    }
+    
+    /// Replace a range with code, padded with the line breaks it's missing
+    fn rewrite(&mut self, range: Range<usize>, mut code: String) {
+        let missing = self.source[range.clone()].matches('\n').count().saturating_sub(code.matches('\n').count());
+        code.push_str(&"\n".repeat(missing));
+        self.replace(range, code);
+    }
+    
+    fn line_breaks(&self, range: Range<usize>) -> String {
+        "\n".repeat(self.source[range].matches('\n').count())
+    }
+    
+    /// Remove the modifier keywords between two offsets
+    fn strip_modifiers(&mut self, start: u32, end: u32) {
+        let (start, end) = (start as usize, end as usize);
+        let modifiers: Vec<Range<usize>> = MODIFIER_REGEX
+            .find_iter(&self.source[start..end])
+            .map(|found| start + found.start()..start + found.end())
+            .collect();
+        for range in modifiers {
+            self.remove(range);
+        }
+    }
+    
+    /// Remove the `?` or `!` following a name ending at `end`
+    fn strip_marker(&mut self, end: u32, computed: bool) {
+        let mut at = end as usize;
+        let skip_spaces = |at: usize| at + self.source[at..].len() - self.source[at..].trim_start().len();
+        if computed {
+            at = skip_spaces(at);
+            if self.source[at..].starts_with(']') {
+                at += 1;
+            }
+        }
+        at = skip_spaces(at);
+        if self.source[at..].starts_with(['?', '!']) {
+            self.remove(at..at + 1);
+        }
+    }
+    
+    /// Where code can be added after a statement, and the separator it needs
+    fn after_statement(&self, span: Span) -> (usize, &'static str) {
+        let separator = if self.text(span).ends_with([';', '}']) { "" } else { ";" };
+        (span.end as usize, separator)
+    }
+    
+    /// Collect the top-level names declared only as types, whose exports
+    /// are dropped
+    fn find_types(&mut self, body: &[Statement]) {
+        let mut values = HashSet::new();
+        for statement in body {
+            let declaration = match statement {
+                Statement::ExportNamedDeclaration(export) => export.declaration.as_ref(),
+                _ => statement.as_declaration(),
+            };
+            match declaration {
+                Some(Declaration::TSTypeAliasDeclaration(alias)) => {
+                    self.types.insert(alias.id.name.to_string());
+                }
+                Some(Declaration::TSInterfaceDeclaration(interface)) => {
+                    self.types.insert(interface.id.name.to_string());
+                }
+                Some(Declaration::VariableDeclaration(variables)) => {
+                    for declarator in &variables.declarations {
+                        values.extend(declarator.id.get_binding_identifiers().iter().map(|id| id.name.to_string()));
+                    }
+                }
+                Some(declaration) => {
+                    if let Some(id) = declaration.id() {
+                        values.insert(id.name.to_string());
+                    }
+                }
+                None => {}
+            }
+        }
+        self.types.retain(|name| !values.contains(name));
+    }
+    
+    /// Drop the imports, and the bindings of imports, that aren't used as
+    /// values
+    fn elide_imports(&mut self, keep: &[&str]) {
+        for import in std::mem::take(&mut self.imports) {
+            let used = |local: &str| self.references.contains(local) || keep.contains(&local);
+            let default = import.default.as_deref().filter(|local| used(local));
+            let namespace = import.namespace.as_deref().filter(|local| used(local));
+            let named: Vec<&str> = import
+                .named
+                .iter()
+                .filter(|(_, local, is_type)| !is_type && used(local))
+                .map(|(written, _, _)| written.as_str())
+                .collect();
+            
+            let before = import.default.is_some() as usize + import.namespace.is_some() as usize + import.named.len();
+            let after = default.is_some() as usize + namespace.is_some() as usize + named.len();
+            if after == before {
+                continue;
+            }
+            if after == 0 {
+                self.remove(import.span);
+                continue;
+            }
+            
+            let mut bindings: Vec<String> = default.into_iter().map(str::to_string).collect();
+            if let Some(namespace) = namespace {
+                bindings.push(format!("* as {}", namespace));
+            } else if !named.is_empty() {
+                bindings.push(format!("{{ {} }}", named.join(", ")));
+            }
+            let code = format!("import {} from {}", bindings.join(", "), import.tail);
+            self.rewrite(import.span, code);
+        }
+    }
+    
+    /// Compile a declaration, which `statement` spans with any `export`
+    fn declaration(&mut self, declaration: &Declaration, statement: Span, export: bool) {
+        let range = statement.start as usize..statement.end as usize;
+        let prefix = if export && self.namespace.is_none() { "export " } else { "" };
+        match declaration {
+            Declaration::TSEnumDeclaration(declaration) if !declaration.declare => {
+                let code = format!("{}{}", prefix, self.enum_code(declaration, export));
+                self.replace(range, code);
+            }
+            Declaration::TSModuleDeclaration(declaration) if !declaration.declare => {
+                self.namespace_code(declaration, statement, export);
+            }
+            Declaration::TSImportEqualsDeclaration(declaration) if declaration.import_kind.is_value() => {
+                let name = declaration.id.name.as_str();
+                let (keyword, value) = match &declaration.module_reference {
+                    TSModuleReference::ExternalModuleReference(reference) => {
+                        ("const", format!("require({})", self.text(reference.expression.span)))
+                    }
+                    reference => {
+                        let value = self.text(reference.span());
+                        self.references.insert(value.split('.').next().unwrap_or(value).trim().to_string());
+                        ("var", value.to_string())
+                    }
+                };
+                let value = match (&self.namespace, export) {
+                    (Some(namespace), true) => format!("{}.{} = {}", namespace, name, value),
+                    _ => value,
+                };
+                self.rewrite(range, format!("{}{} {} = {};", prefix, keyword, name, value));
+            }
+            Declaration::VariableDeclaration(declaration) if declaration.declare => self.remove(range),
+            Declaration::ClassDeclaration(class) if class.declare => self.remove(range),
+            Declaration::ClassDeclaration(_) => self.value_declaration(declaration, statement, export),
+            _ if declaration.is_typescript_syntax() => self.remove(range),
+            _ => self.value_declaration(declaration, statement, export),
+        }
+    }
+    
+    /// Compile a variable, function or class declaration, assigning it to
+    /// the namespace it's exported from
+    fn value_declaration(&mut self, declaration: &Declaration, statement: Span, export: bool) {
+        let Some(namespace) = self.namespace.clone().filter(|_| export) else {
+            walk::walk_declaration(self, declaration);
+            return;
+        };
+        
+        self.remove(statement.start as usize..declaration.span().start as usize);
+        walk::walk_declaration(self, declaration);
+        let names: Vec<String> = match declaration {
+            Declaration::VariableDeclaration(variables) => variables
+                .declarations
+                .iter()
+                .flat_map(|declarator| declarator.id.get_binding_identifiers())
+                .map(|id| id.name.to_string())
+                .collect(),
+            _ => declaration.id().map(|id| id.name.to_string()).into_iter().collect(),
+        };
+        let (at, separator) = self.after_statement(declaration.span());
+        let assignments: String = names.iter().map(|name| format!(" {0}.{1} = {1};", namespace, name)).collect();
+        self.insert(at, format!("{}{}", separator, assignments));
+    }
+    
+    /// What an enum or namespace is stored in: a new object, or its
+    /// namespace's property when exported from one
+    fn holder(&self, name: &str, export: bool) -> String {
+        match (&self.namespace, export) {
+            (Some(namespace), true) => format!("{0} = {1}.{0} || ({1}.{0} = {{}})", name, namespace),
+            _ => format!("{0} || ({0} = {{}})", name),
+        }
+    }
+    
+    /// An enum as a function filling an object, with each member on its
+    /// original line
+    fn enum_code(&mut self, declaration: &TSEnumDeclaration, export: bool) -> String {
+        let name = declaration.id.name.to_string();
+        let members: Vec<String> = declaration
+            .body
+            .members
+            .iter()
+            .map(|member| match &member.id {
+                TSEnumMemberName::Identifier(id) => id.name.to_string(),
+                TSEnumMemberName::String(literal) | TSEnumMemberName::ComputedString(literal) => literal.value.to_string(),
+                TSEnumMemberName::ComputedTemplateString(template) => {
+                    template.quasis.first().map(|quasi| quasi.value.raw.to_string()).unwrap_or_default()
+                }
+            })
+            .collect();
+        
+        let mut code = format!("var {0}; (function ({0}) {{", name);
+        let mut cursor = declaration.body.span.start as usize;
+        // The previous member's value, to count up from
+        let mut previous: Option<Result<f64, String>> = None;
+        for (member, key) in declaration.body.members.iter().zip(&members) {
+            code.push_str(&self.line_breaks(cursor..member.span.start as usize));
+            cursor = member.span.end as usize;
+            let key_json = serde_json::to_string(key).unwrap_or_default();
+            
+            let value = match &member.initializer {
+                Some(initializer) => match constant(initializer) {
+                    Some(Constant::Number(value)) => {
+                        previous = Some(Ok(value));
+                        number(value)
+                    }
+                    Some(Constant::String) => {
+                        previous = None;
+                        code.push_str(&format!(" {}[{}] = {};", name, key_json, self.text(initializer.span())));
+                        continue;
+                    }
+                    None => {
+                        previous = Some(Err(key_json.clone()));
+                        let mut compiler = Compiler::new(self.source);
+                        compiler.enum_scope = Some((name.clone(), members.clone()));
+                        compiler.visit_expression(initializer);
+                        self.references.extend(compiler.references.drain());
+                        compiler.render(initializer.span().start as usize..initializer.span().end as usize)
+                    }
+                },
+                None => match &previous {
+                    None => "0".to_string(),
+                    Some(Ok(value)) => number(value + 1.0),
+                    Some(Err(previous_key)) => format!("{}[{}] + 1", name, previous_key),
+                },
+            };
+            if let Some(Ok(_)) | None = previous {
+                previous = Some(Ok(value.parse().unwrap_or(0.0)));
+            }
+            code.push_str(&format!(" {0}[{0}[{1}] = {2}] = {1};", name, key_json, value));
+        }
+        code.push_str(&self.line_breaks(cursor..declaration.span.end as usize));
+        code.push_str(&format!(" }})({});", self.holder(&name, export)));
+        code
+    }
+    
+    /// A namespace as functions filling an object, one per dotted name part
+    fn namespace_code(&mut self, declaration: &TSModuleDeclaration, statement: Span, export: bool) {
+        let range = statement.start as usize..statement.end as usize;
+        let mut names = Vec::new();
+        let mut current = declaration;
+        let block = loop {
+            let TSModuleDeclarationName::Identifier(id) = &current.id else {
+                return self.remove(range);
+            };
+            names.push(id.name.to_string());
+            match &current.body {
+                Some(TSModuleDeclarationBody::TSModuleDeclaration(inner)) => current = inner,
+                Some(TSModuleDeclarationBody::TSModuleBlock(block)) => break block,
+                None => return self.remove(range),
+            }
+        };
+        if !block.body.iter().any(is_instantiated) {
+            return self.remove(range);
+        }
+        
+        let mut header = String::new();
+        let mut footer = String::new();
+        for (index, name) in names.iter().enumerate() {
+            let (keyword, holder) = match index {
+                0 if export && self.namespace.is_none() => ("export var", self.holder(name, export)),
+                0 => ("var", self.holder(name, export)),
+                _ => ("let", format!("{0} = {1}.{0} || ({1}.{0} = {{}})", name, names[index - 1])),
+            };
+            if index > 0 {
+                header.push(' ');
+            }
+            header.push_str(&format!("{} {1}; (function ({1}) {{", keyword, name));
+            footer = format!("}})({});{}", holder, if footer.is_empty() { String::new() } else { format!(" {}", footer) });
+        }
+        let body_start = block.span.start as usize + 1;
+        let body_end = block.span.end as usize - 1;
+        self.rewrite(range.start..body_start, header);
+        self.rewrite(body_end..range.end, footer);
+        
+        let outer = self.namespace.replace(names.pop().unwrap_or_default());
+        for statement in &block.body {
+            self.visit_statement(statement);
+        }
+        self.namespace = outer;
+    }
+    
+    fn export_named(&mut self, export: &ExportNamedDeclaration) {
+        if export.export_kind.is_type() {
+            return self.remove_span(export.span);
+        }
+        if let Some(declaration) = &export.declaration {
+            return self.declaration(declaration, export.span, true);
+        }
+        
+        let local = export.source.is_none();
+        let kept: Vec<&ExportSpecifier> = export
+            .specifiers
+            .iter()
+            .filter(|specifier| specifier.export_kind.is_value())
+            .filter(|specifier| !(local && self.types.contains(specifier.local.name().as_str())))
+            .collect();
+        if local {
+            self.references.extend(kept.iter().map(|specifier| specifier.local.name().to_string()));
+        }
+        if kept.len() == export.specifiers.len() {
+            return;
+        }
+        if kept.is_empty() {
+            return self.remove_span(export.span);
+        }
+        
+        let specifiers: Vec<String> = kept
+            .iter()
+            .map(|specifier| {
+                let text = self.text(specifier.span);
+                text.strip_prefix("type ").unwrap_or(text).to_string()
+            })
+            .collect();
+        let tail = match &export.source {
+            Some(source) => format!(" from {}", &self.source[source.span.start as usize..export.span.end as usize]),
+            None if self.text(export.span).ends_with(';') => ";".to_string(),
+            None => String::new(),
+        };
+        let code = format!("export {{ {} }}{}", specifiers.join(", "), tail);
+        self.rewrite(export.span.start as usize..export.span.end as usize, code);
+    }
+    
+    /// Assign parameter properties to `this` at the start of a constructor,
+    /// after the `super()` call if it has one
+    fn parameter_properties(&mut self, constructor: &Function) {
+        let mut assignments = String::new();
+        for param in &constructor.params.items {
+            if param.accessibility.is_none() && !param.readonly && !param.r#override {
+                continue;
+            }
+            let start = param.decorators.last().map_or(param.span.start, |decorator| decorator.span.end);
+            self.strip_modifiers(start, param.pattern.span().start);
+            if let BindingPattern::BindingIdentifier(id) = &param.pattern {
+                assignments.push_str(&format!(" this.{0} = {0};", id.name));
+            }
+        }
+        let Some(body) = constructor.body.as_ref().filter(|_| !assignments.is_empty()) else {
+            return;
+        };
+        
+        let super_call = body.statements.iter().find(|statement| {
+            matches!(statement, Statement::ExpressionStatement(expression)
+                if matches!(&expression.expression, Expression::CallExpression(call) if call.callee.is_super()))
+        });
+        match super_call {
+            Some(statement) => {
+                let (at, separator) = self.after_statement(statement.span());
+                self.insert(at, format!("{}{}", separator, assignments));
+            }
+            None => self.insert(body.span.start as usize + 1, assignments),
+        }
+    }
+}
+
+impl<'a> Visit<'a> for Compiler<'_> {
+    fn visit_statement(&mut self, it: &Statement<'a>) {
+        match it {
+            Statement::ImportDeclaration(import) => self.visit_import_declaration(import),
+            Statement::ExportNamedDeclaration(export) => self.export_named(export),
+            Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                ExportDefaultDeclarationKind::TSInterfaceDeclaration(_) => self.remove_span(export.span),
+                ExportDefaultDeclarationKind::FunctionDeclaration(function) if function.is_typescript_syntax() => {
+                    self.remove_span(export.span)
+                }
+                _ => walk::walk_statement(self, it),
+            },
+            Statement::ExportAllDeclaration(export) if export.export_kind.is_type() => self.remove_span(export.span),
+            Statement::TSExportAssignment(export) => {
+                self.replace(export.span.start as usize..export.expression.span().start as usize, "module.exports = ".to_string());
+                self.visit_expression(&export.expression);
+            }
+            Statement::TSNamespaceExportDeclaration(declaration) => self.remove_span(declaration.span),
+            _ => match it.as_declaration() {
+                Some(declaration) => self.declaration(declaration, it.span(), false),
+                None => walk::walk_statement(self, it),
+            },
+        }
+    }
+    
+    fn visit_import_declaration(&mut self, it: &ImportDeclaration<'a>) {
+        if it.import_kind.is_type() {
+            return self.remove_span(it.span);
+        }
+        let Some(specifiers) = &it.specifiers else {
+            return;
+        };
+        
+        let mut import = Import {
+            span: it.span.start as usize..it.span.end as usize,
+            default: None,
+            namespace: None,
+            named: Vec::new(),
+            tail: self.source[it.source.span.start as usize..it.span.end as usize].to_string(),
+        };
+        for specifier in specifiers {
+            match specifier {
+                ImportDeclarationSpecifier::ImportDefaultSpecifier(default) => {
+                    import.default = Some(default.local.name.to_string());
+                }
+                ImportDeclarationSpecifier::ImportNamespaceSpecifier(namespace) => {
+                    import.namespace = Some(namespace.local.name.to_string());
+                }
+                ImportDeclarationSpecifier::ImportSpecifier(named) => {
+                    import.named.push((self.text(named.span).to_string(), named.local.name.to_string(), named.import_kind.is_type()));
+                }
+            }
+        }
+        self.imports.push(import);
+    }
+    
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        self.references.insert(it.name.to_string());
+        if let Some((name, members)) = &self.enum_scope {
+            if members.iter().any(|member| member == it.name.as_str()) {
+                let qualified = format!("{}.{}", name, it.name);
+                self.replace(it.span.start as usize..it.span.end as usize, qualified);
+            }
+        }
+    }
+    
+    fn visit_variable_declarator(&mut self, it: &VariableDeclarator<'a>) {
+        if it.definite {
+            self.strip_marker(it.id.span().end, false);
+        }
+        walk::walk_variable_declarator(self, it);
+    }
+    
+    fn visit_function(&mut self, it: &Function<'a>, flags: ScopeFlags) {
+        if let Some(this) = &it.this_param {
+            let after = &self.source[this.span.end as usize..];
+            let rest = after.trim_start().strip_prefix(',').unwrap_or(after).trim_start();
+            self.remove(this.span.start as usize..this.span.end as usize + after.len() - rest.len());
+        }
+        walk::walk_function(self, it, flags);
+    }
+    
+    fn visit_formal_parameter(&mut self, it: &FormalParameter<'a>) {
+        if it.optional {
+            self.strip_marker(it.pattern.span().end, false);
+        }
+        walk::walk_formal_parameter(self, it);
+    }
+    
+    fn visit_arrow_function_expression(&mut self, it: &ArrowFunctionExpression<'a>) {
+        if let Some(return_type) = &it.return_type {
+            self.arrow_returns.insert(return_type.span.start);
+        }
+        walk::walk_arrow_function_expression(self, it);
+    }
+    
+    fn visit_class(&mut self, it: &Class<'a>) {
+        if it.r#abstract {
+            let before = &self.source[..it.span.start as usize];
+            let within = &self.source[it.span.start as usize..it.body.span.start as usize];
+            let keyword = within
+                .find("abstract")
+                .map(|index| it.span.start as usize + index)
+                .or_else(|| before.trim_end().strip_suffix("abstract").map(str::len));
+            if let Some(start) = keyword {
+                let rest = &self.source[start + "abstract".len()..];
+                self.remove(start..start + "abstract".len() + rest.len() - rest.trim_start().len());
+            }
+        }
+        if let (Some(first), Some(last)) = (it.implements.first(), it.implements.last()) {
+            let start = self.source[..first.span.start as usize].rfind("implements").unwrap_or(first.span.start as usize);
+            self.remove(start..last.span.end as usize);
+        }
+        walk::walk_class(self, it);
+    }
+    
+    fn visit_class_element(&mut self, it: &ClassElement<'a>) {
+        match it {
+            ClassElement::TSIndexSignature(signature) => self.remove_span(signature.span),
+            ClassElement::MethodDefinition(method)
+                if method.r#type == MethodDefinitionType::TSAbstractMethodDefinition || method.value.body.is_none() =>
+            {
+                self.remove_span(method.span)
+            }
+            ClassElement::PropertyDefinition(property)
+                if property.declare || property.r#type == PropertyDefinitionType::TSAbstractPropertyDefinition =>
+            {
+                self.remove_span(property.span)
+            }
+            ClassElement::AccessorProperty(accessor) if accessor.r#type == AccessorPropertyType::TSAbstractAccessorProperty => {
+                self.remove_span(accessor.span)
+            }
+            _ => walk::walk_class_element(self, it),
+        }
+    }
+    
+    fn visit_method_definition(&mut self, it: &MethodDefinition<'a>) {
+        let start = it.decorators.last().map_or(it.span.start, |decorator| decorator.span.end);
+        self.strip_modifiers(start, it.key.span().start);
+        if it.optional {
+            self.strip_marker(it.key.span().end, it.computed);
+        }
+        if it.kind == MethodDefinitionKind::Constructor {
+            self.parameter_properties(&it.value);
+        }
+        walk::walk_method_definition(self, it);
+    }
+    
+    fn visit_property_definition(&mut self, it: &PropertyDefinition<'a>) {
+        let start = it.decorators.last().map_or(it.span.start, |decorator| decorator.span.end);
+        self.strip_modifiers(start, it.key.span().start);
+        if it.optional || it.definite {
+            self.strip_marker(it.key.span().end, it.computed);
+        }
+        walk::walk_property_definition(self, it);
+    }
+    
+    fn visit_accessor_property(&mut self, it: &AccessorProperty<'a>) {
+        let start = it.decorators.last().map_or(it.span.start, |decorator| decorator.span.end);
+        self.strip_modifiers(start, it.key.span().start);
+        if it.definite {
+            self.strip_marker(it.key.span().end, it.computed);
+        }
+        walk::walk_accessor_property(self, it);
+    }
+    
+    fn visit_ts_type_annotation(&mut self, it: &TSTypeAnnotation<'a>) {
+        if self.arrow_returns.remove(&it.span.start) {
+            self.replace(it.span.start as usize..it.span.end as usize, String::new());
+        } else {
+            self.remove_span(it.span);
+        }
+    }
+    
+    fn visit_ts_type_parameter_declaration(&mut self, it: &TSTypeParameterDeclaration<'a>) {
+        self.remove_span(it.span);
+    }
+    
+    fn visit_ts_type_parameter_instantiation(&mut self, it: &TSTypeParameterInstantiation<'a>) {
+        self.remove_span(it.span);
+    }
+    
+    fn visit_ts_as_expression(&mut self, it: &TSAsExpression<'a>) {
+        self.visit_expression(&it.expression);
+        self.remove(it.expression.span().end as usize..it.span.end as usize);
+    }
+    
+    fn visit_ts_satisfies_expression(&mut self, it: &TSSatisfiesExpression<'a>) {
+        self.visit_expression(&it.expression);
+        self.remove(it.expression.span().end as usize..it.span.end as usize);
+    }
+    
+    fn visit_ts_non_null_expression(&mut self, it: &TSNonNullExpression<'a>) {
+        self.visit_expression(&it.expression);
+        self.remove(it.expression.span().end as usize..it.span.end as usize);
+    }
+    
+    fn visit_ts_type_assertion(&mut self, it: &TSTypeAssertion<'a>) {
+        self.remove(it.span.start as usize..it.expression.span().start as usize);
+        self.visit_expression(&it.expression);
+    }
+    
+    fn visit_ts_type(&mut self, _it: &TSType<'a>) {}
+    
+    fn visit_ts_this_parameter(&mut self, _it: &TSThisParameter<'a>) {}
+    
+    fn visit_ts_class_implements(&mut self, _it: &TSClassImplements<'a>) {}
+}
+
+/// An enum initializer whose value is known without running it
+enum Constant {
+    Number(f64),
+    String,
+}
+
+fn constant(expression: &Expression) -> Option<Constant> {
+    match expression {
+        Expression::NumericLiteral(literal) => Some(Constant::Number(literal.value)),
+        Expression::UnaryExpression(unary) if unary.operator == UnaryOperator::UnaryNegation => match constant(&unary.argument)? {
+            Constant::Number(value) => Some(Constant::Number(-value)),
+            Constant::String => None,
+        },
+        Expression::StringLiteral(_) => Some(Constant::String),
+        Expression::TemplateLiteral(template) if template.expressions.is_empty() => Some(Constant::String),
+        _ => None,
+    }
+}
+
+fn number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Whether a namespace statement creates values at runtime
+fn is_instantiated(statement: &Statement) -> bool {
+    match statement {
+        Statement::ExportNamedDeclaration(export) => match &export.declaration {
+            Some(declaration) => is_instantiated_declaration(declaration),
+            None => true,
+        },
+        _ => match statement.as_declaration() {
+            Some(declaration) => is_instantiated_declaration(declaration),
+            None => true,
+        },
+    }
+}
+
+fn is_instantiated_declaration(declaration: &Declaration) -> bool {
+    match declaration {
+        Declaration::TSModuleDeclaration(module) => {
+            !module.declare
+                && match &module.body {
+                    Some(TSModuleDeclarationBody::TSModuleBlock(block)) => block.body.iter().any(is_instantiated),
+                    Some(TSModuleDeclarationBody::TSModuleDeclaration(_)) => true,
+                    None => false,
+                }
+        }
+        Declaration::TSEnumDeclaration(declaration) => !declaration.declare,
+        Declaration::ClassDeclaration(class) => !class.declare,
+        Declaration::TSImportEqualsDeclaration(declaration) => declaration.import_kind.is_value(),
+        _ => !declaration.is_typescript_syntax(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn ts(source: &str) -> String {
+        compile(source, Path::new("/app/src/a.ts"), false, &[]).unwrap()
+    }
+    
+    #[test]
+    fn test_strip_types() {
+        let source = "\
+const pick = <T,>(items: T[], ok?: boolean): T | undefined => ok ? items[0] : undefined;
+const point = { x: 1 as number, y: (2 satisfies number) };
+let el!: HTMLElement;
+function first<T>(this: Window, list: Array<T>): T { return list[0]!; }
+type Id = string;
+interface Shape {
+  area(): number;
+}
+const size = <number>point.x;";
+        let expected = [
+            "const pick = (items, ok) => ok ? items[0] : undefined;",
+            "const point = { x: 1, y: (2) };",
+            "let el;",
+            "function first(list) { return list[0]; }",
+            "",
+            "",
+            "",
+            "",
+            "const size = point.x;",
+        ];
+        assert_eq!(ts(source), expected.join("\n"));
+    }
+    
+    #[test]
+    fn test_classes() {
+        let source = "\
+abstract class Base<T> implements Shape {
+  private readonly id: string;
+  declare tag: string;
+  abstract area(): number;
+  [key: string]: unknown;
+  size?: number;
+  constructor(public name: string, protected count = 0) { super(); }
+  describe(): string;
+  describe(): string { return this.name; }
+}";
+        let expected = [
+            "class Base  {",
+            "  id;",
+            "  ",
+            "  ",
+            "  ",
+            "  size;",
+            "  constructor(name, count = 0) { super(); this.name = name; this.count = count; }",
+            "  ",
+            "  describe() { return this.name; }",
+            "}",
+        ];
+        assert_eq!(ts(source), expected.join("\n"));
+    }
+    
+    #[test]
+    fn test_enums_and_namespaces() {
+        let source = "\
+export enum Color {
+  Red,
+  Green = 'green',
+  Blue = 4,
+  Cyan,
+  Mask = Blue | Cyan,
+}
+namespace Geometry.Shapes {
+  export const sides = 4;
+  export function area(size: number) { return size * size; }
+  type Unit = string;
+}
+declare namespace Ambient { const x: number; }
+namespace Types { export type A = string; }";
+        let expected = [
+            "export var Color; (function (Color) {",
+            r#" Color[Color["Red"] = 0] = "Red";"#,
+            r#" Color["Green"] = 'green';"#,
+            r#" Color[Color["Blue"] = 4] = "Blue";"#,
+            r#" Color[Color["Cyan"] = 5] = "Cyan";"#,
+            r#" Color[Color["Mask"] = Color.Blue | Color.Cyan] = "Mask";"#,
+            " })(Color || (Color = {}));",
+            "var Geometry; (function (Geometry) { let Shapes; (function (Shapes) {",
+            "  const sides = 4; Shapes.sides = sides;",
+            "  function area(size) { return size * size; } Shapes.area = area;",
+            "  ",
+            "})(Shapes = Geometry.Shapes || (Geometry.Shapes = {})); })(Geometry || (Geometry = {}));",
+            "",
+            "",
+        ];
+        assert_eq!(ts(source), expected.join("\n"));
+    }
+    
+    #[test]
+    fn test_type_only_imports() {
+        let source = "\
+import type { Props } from './types';
+import { type Theme, useTheme, Config } from './theme';
+import Widget, { WidgetProps } from './widget';
+import * as types from './all-types';
+import './styles.css';
+export type { Props };
+export { Config, useTheme };
+export type Mode = 'light';
+const theme: Theme = useTheme(Widget);";
+        let expected = [
+            "",
+            "import { useTheme, Config } from './theme';",
+            "import Widget from './widget';",
+            "",
+            "import './styles.css';",
+            "",
+            "export { Config, useTheme };",
+            "",
+            "const theme = useTheme(Widget);",
+        ];
+        assert_eq!(ts(source), expected.join("\n"));
+        
+        let source = "import React from 'react';\nimport type { FC } from 'react';\nconst App: FC = () => <div />;";
+        let result = compile(source, Path::new("a.tsx"), true, &["React"]).unwrap();
+        assert_eq!(result, "import React from 'react';\n\nconst App = () => <div />;");
+    }
+    
+    #[test]
+    fn test_parse_errors() {
+        let error = compile("const a = 1;\nconst b: = 2;", Path::new("/app/src/a.ts"), false, &[]).unwrap_err();
+        assert!(error.to_string().starts_with("Failed to parse /app/src/a.ts:2:"), "{}", error);
+    }
+}
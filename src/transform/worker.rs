@@ -0,0 +1,226 @@
+//! Transform worker processes
+//!
+//! With `build.isolate_transforms`, core transforms run in child processes
+//! of the `component` binary instead of on build threads. The CLI registers
+//! itself with [`set_executable`]; programs embedding the library register a
+//! `component` binary the same way, or transform in-process without one. A transform that
+//! panics or runs out of memory takes down only its worker: the module fails
+//! with a diagnostic, the rest of the build or the dev server carries on, and
+//! the next transform starts a fresh worker.
+//!
+//! Workers speak JSON lines over stdin and stdout: a first line with the
+//! config, then one request and one response per module. Their stderr is
+//! the parent's, so panic messages show up where the build's logs do.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::Transformer;
+use crate::bundler::ModuleType;
+use crate::config::Config;
+
+/// Hidden subcommand that runs a worker
+pub const WORKER_COMMAND: &str = "transform-worker";
+
+/// The `component` binary workers run, once registered
+static EXECUTABLE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Run workers as `<executable> transform-worker`
+///
+/// Only the first call takes effect.
+pub fn set_executable(executable: impl Into<PathBuf>) {
+    let _ = EXECUTABLE.set(executable.into());
+}
+
+/// First line sent to a worker: the config, with the fields it doesn't
+/// serialize
+#[derive(Serialize, Deserialize)]
+struct Setup {
+    config: Config,
+    root: PathBuf,
+    conditions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    source: String,
+    path: PathBuf,
+    module_type: ModuleType,
+}
+
+/// Transformed code, or the transform's error message
+type Response = std::result::Result<String, String>;
+
+/// Worker processes, started as transforms need them
+///
+/// Each transform takes an idle worker or starts one, so there are at most
+/// as many workers as transforms run at once.
+pub struct WorkerPool {
+    command: Vec<String>,
+    setup: String,
+    idle: Mutex<Vec<Worker>>,
+}
+
+impl WorkerPool {
+    /// A pool of workers running the executable set with [`set_executable`],
+    /// or `None` when there is none
+    pub fn new(config: &Config) -> Result<Option<Self>> {
+        let Some(program) = EXECUTABLE.get() else {
+            return Ok(None);
+        };
+        Self::with_command(config, vec![program.display().to_string(), WORKER_COMMAND.to_string()]).map(Some)
+    }
+    
+    fn with_command(config: &Config, command: Vec<String>) -> Result<Self> {
+        let setup = serde_json::to_string(&Setup {
+            config: config.clone(),
+            root: config.root.clone(),
+            conditions: config.conditions.clone(),
+        })?;
+        Ok(Self { command, setup, idle: Mutex::new(Vec::new()) })
+    }
+    
+    /// Transform a module in a worker
+    pub fn transform(&self, source: &str, path: &Path, module_type: &ModuleType) -> Result<String> {
+        let idle = self.idle.lock().pop();
+        let mut worker = match idle {
+            Some(worker) => worker,
+            None => self.spawn()?,
+        };
+        
+        let request = Request { source: source.to_string(), path: path.to_path_buf(), module_type: module_type.clone() };
+        match worker.send(&request) {
+            Ok(response) => {
+                self.idle.lock().push(worker);
+                response.map_err(anyhow::Error::msg)
+            }
+            Err(e) => {
+                let status = worker.child.wait().map_or_else(|_| e.to_string(), |status| status.to_string());
+                anyhow::bail!("Transform worker crashed while transforming {} ({})", path.display(), status)
+            }
+        }
+    }
+    
+    fn spawn(&self) -> Result<Worker> {
+        let (program, args) = self.command.split_first().context("Empty worker command")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start transform worker: {}", program))?;
+        debug!("Started transform worker {}", child.id());
+        
+        let mut stdin = child.stdin.take().context("Worker has no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().context("Worker has no stdout")?);
+        writeln!(stdin, "{}", self.setup).context("Failed to configure transform worker")?;
+        Ok(Worker { child, stdin, stdout })
+    }
+}
+
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Worker {
+    /// Send a request and wait for its response; errors mean the worker died
+    fn send(&mut self, request: &Request) -> io::Result<Response> {
+        writeln!(self.stdin, "{}", serde_json::to_string(request)?)?;
+        self.stdin.flush()?;
+        
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Serve transform requests from `input` until it closes
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> Result<()> {
+    let mut line = String::new();
+    input.read_line(&mut line).context("Failed to read worker setup")?;
+    let setup: Setup = serde_json::from_str(&line).context("Invalid worker setup")?;
+    let mut config = setup.config;
+    config.root = setup.root;
+    config.conditions = setup.conditions;
+    let transformer = Transformer::new(Arc::new(config))?;
+    
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let request: Request = serde_json::from_str(&line).context("Invalid transform request")?;
+        let response: Response = transformer
+            .transform(&request.source, &request.path, &request.module_type)
+            .map_err(|e| format!("{:#}", e));
+        writeln!(output, "{}", serde_json::to_string(&response)?)?;
+        output.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_serve_transform_requests() {
+        let config = Config::default_config();
+        let pool = WorkerPool::with_command(&config, Vec::new()).unwrap();
+        let request = |source: &str, module_type: ModuleType| {
+            serde_json::to_string(&Request { source: source.to_string(), path: PathBuf::from("/app/src/a.ts"), module_type }).unwrap()
+        };
+        let input = format!(
+            "{}\n{}\n{}\n",
+            pool.setup,
+            request("export const a: number = 1;", ModuleType::TypeScript),
+            request("{", ModuleType::Json),
+        );
+        
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Response> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let expected = Transformer::new(Arc::new(config))
+            .unwrap()
+            .transform("export const a: number = 1;", Path::new("/app/src/a.ts"), &ModuleType::TypeScript)
+            .unwrap();
+        assert_eq!(responses[0].as_ref().unwrap(), &expected);
+        assert!(responses[1].is_err());
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_crashed_worker_fails_only_its_module() {
+        let config = Config::default_config();
+        let command = ["sh", "-c", "read setup; read request; exit 3"].map(str::to_string).to_vec();
+        let pool = WorkerPool::with_command(&config, command).unwrap();
+        
+        let error = pool.transform("const a = 1;", Path::new("/app/src/a.ts"), &ModuleType::TypeScript).unwrap_err();
+        assert_eq!(error.to_string(), "Transform worker crashed while transforming /app/src/a.ts (exit status: 3)");
+        assert!(pool.idle.lock().is_empty());
+        assert!(pool.transform("", Path::new("/app/src/b.ts"), &ModuleType::TypeScript).is_err());
+    }
+}
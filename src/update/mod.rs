@@ -0,0 +1,289 @@
+//! Self-update from GitHub releases
+//!
+//! Each release carries a binary per platform, named
+//! `component-<os>-<arch>` (with `.exe` on Windows), a `SHA256SUMS` file
+//! listing their hashes as `sha256sum` prints them, and `SHA256SUMS.sig`,
+//! the hex Ed25519 signature of that file. Release builds embed the public
+//! key from `COMPONENT_RELEASE_KEY` at compile time and reject binaries
+//! whose checksums it didn't sign; other builds only check the hash.
+//! Copies installed by a package manager are built without the
+//! `self-update` feature and leave updates to it.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// GitHub API URL of the repository releases come from
+pub const DEFAULT_API: &str = "https://api.github.com/repos/componentjs/component";
+
+/// Release asset listing the SHA-256 of every binary
+pub const SUMS_FILE: &str = "SHA256SUMS";
+
+/// Release asset with the signature of [`SUMS_FILE`]
+pub const SIGNATURE_FILE: &str = "SHA256SUMS.sig";
+
+/// Request timeout, long enough to download a binary
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Public key, in hex, that this build accepts release checksums signed with
+pub fn release_key() -> Option<&'static str> {
+    option_env!("COMPONENT_RELEASE_KEY").filter(|key| !key.is_empty())
+}
+
+/// A GitHub release
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    /// Tag, such as `v2.1.0`
+    #[serde(rename = "tag_name")]
+    pub tag: String,
+    
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// A file attached to a release
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+}
+
+impl Release {
+    /// Version the release's tag names
+    pub fn version(&self) -> Result<semver::Version> {
+        semver::Version::parse(self.tag.trim_start_matches('v'))
+            .with_context(|| format!("Release tag '{}' isn't a version", self.tag))
+    }
+    
+    /// The attached file named `name`
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// What installing a release would do to the running version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionChange {
+    UpToDate,
+    Upgrade,
+    Downgrade,
+}
+
+impl VersionChange {
+    /// Compare a release's `version` with `current`; unless the release was
+    /// asked for by version, an older one only means there's nothing newer
+    pub fn new(current: &semver::Version, version: &semver::Version, pinned: bool) -> Self {
+        match version.cmp(current) {
+            std::cmp::Ordering::Greater => Self::Upgrade,
+            std::cmp::Ordering::Less if pinned => Self::Downgrade,
+            _ => Self::UpToDate,
+        }
+    }
+}
+
+/// Name of the release asset with the binary for this platform
+pub fn asset_name() -> String {
+    platform_asset(std::env::consts::OS, std::env::consts::ARCH)
+}
+
+fn platform_asset(os: &str, arch: &str) -> String {
+    format!("component-{}-{}{}", os, arch, if os == "windows" { ".exe" } else { "" })
+}
+
+/// Client for the GitHub releases API
+pub struct ReleaseClient {
+    api: String,
+    agent: ureq::Agent,
+}
+
+impl ReleaseClient {
+    pub fn new(api: &str) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(TIMEOUT)
+                .user_agent(concat!("component/", env!("CARGO_PKG_VERSION")))
+                .build(),
+        }
+    }
+    
+    /// The latest release, or the one tagged with `version`
+    pub fn release(&self, version: Option<&str>) -> Result<Release> {
+        let url = match version {
+            Some(version) => format!("{}/releases/tags/v{}", self.api, version.trim_start_matches('v')),
+            None => format!("{}/releases/latest", self.api),
+        };
+        let body = self.agent
+            .get(&url)
+            .set("Accept", "application/vnd.github+json")
+            .call()
+            .with_context(|| format!("Failed to fetch {}", url))?
+            .into_string()?;
+        serde_json::from_str(&body).with_context(|| format!("Invalid release from {}", url))
+    }
+    
+    /// Contents of a release asset
+    pub fn download(&self, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.agent
+            .get(&asset.url)
+            .call()
+            .with_context(|| format!("Failed to download {}", asset.name))?
+            .into_reader()
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("Failed to download {}", asset.name))?;
+        Ok(bytes)
+    }
+}
+
+/// Check a downloaded binary against the release's checksums, and the
+/// checksums against their signature when a public key is given
+pub fn verify(binary: &[u8], name: &str, sums: &str, signature: Option<&str>, key: Option<&str>) -> Result<()> {
+    if let Some(key) = key {
+        let signature = signature.with_context(|| format!("Release has no {} to check {} against", SIGNATURE_FILE, SUMS_FILE))?;
+        let key: [u8; 32] = hex::decode(key.trim())?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid release key length"))?;
+        let signature: [u8; 64] = hex::decode(signature.trim())?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid signature length in {}", SIGNATURE_FILE))?;
+        VerifyingKey::from_bytes(&key)?
+            .verify(sums.as_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| anyhow::anyhow!("{} isn't signed with this build's release key", SUMS_FILE))?;
+    }
+    
+    let expected = checksum(sums, name).with_context(|| format!("{} isn't listed in {}", name, SUMS_FILE))?;
+    let actual = hex::encode(Sha256::digest(binary));
+    anyhow::ensure!(
+        expected.eq_ignore_ascii_case(&actual),
+        "Checksum mismatch for {}: expected {}, downloaded {}",
+        name,
+        expected,
+        actual
+    );
+    Ok(())
+}
+
+/// The hash `sums` lists for `name`, from lines of `<hash>  <name>`
+fn checksum<'a>(sums: &'a str, name: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        // `*` marks files hashed in binary mode
+        (file.trim_start().trim_start_matches('*') == name).then_some(hash)
+    })
+}
+
+/// Replace the executable at `exe` with `binary`
+///
+/// The new binary is written next to it and renamed into place, so an
+/// interrupted update leaves the old one working. Windows can't replace a
+/// running executable, so there the old one is first moved aside to
+/// `<name>.old`.
+pub fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    let file_name = exe.file_name().and_then(|name| name.to_str()).context("Invalid executable path")?;
+    let staged = exe.with_file_name(format!(".{}.new", file_name));
+    fs::write(&staged, binary)
+        .with_context(|| format!("Failed to write {} (is its directory writable?)", staged.display()))?;
+    
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old).with_context(|| format!("Failed to move {} aside", exe.display()))?;
+    }
+    
+    fs::rename(&staged, exe).map_err(|e| {
+        let _ = fs::remove_file(&staged);
+        // Put the running binary back so a failed update never leaves the user without one
+        #[cfg(windows)]
+        let _ = fs::rename(exe.with_extension("old"), exe);
+        anyhow::anyhow!("Failed to replace {}: {}", exe.display(), e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    
+    #[test]
+    fn test_release_assets() {
+        let release: Release = serde_json::from_str(r#"{
+            "tag_name": "v2.1.0",
+            "assets": [{ "name": "component-linux-x86_64", "browser_download_url": "https://example.com/component-linux-x86_64" }]
+        }"#).unwrap();
+        assert_eq!(release.version().unwrap(), semver::Version::new(2, 1, 0));
+        assert_eq!(release.asset("component-linux-x86_64").unwrap().url, "https://example.com/component-linux-x86_64");
+        assert!(release.asset(SUMS_FILE).is_none());
+        
+        assert_eq!(platform_asset("linux", "x86_64"), "component-linux-x86_64");
+        assert_eq!(platform_asset("windows", "aarch64"), "component-windows-aarch64.exe");
+    }
+    
+    #[test]
+    fn test_version_change() {
+        let current = semver::Version::new(2, 0, 0);
+        let older = semver::Version::new(1, 9, 0);
+        let newer = semver::Version::new(2, 1, 0);
+        assert_eq!(VersionChange::new(&current, &newer, false), VersionChange::Upgrade);
+        assert_eq!(VersionChange::new(&current, &newer, true), VersionChange::Upgrade);
+        assert_eq!(VersionChange::new(&current, &current, true), VersionChange::UpToDate);
+        
+        // `--to <older>` rolls back, while an older latest release is no update
+        assert_eq!(VersionChange::new(&current, &older, true), VersionChange::Downgrade);
+        assert_eq!(VersionChange::new(&current, &older, false), VersionChange::UpToDate);
+    }
+    
+    #[test]
+    fn test_verify_release_binary() {
+        let binary = b"new component";
+        let sums = format!("{}  component-linux-x86_64\n{} *component-macos-aarch64\n", hex::encode(Sha256::digest(binary)), "00".repeat(32));
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+        let signature = hex::encode(key.sign(sums.as_bytes()).to_bytes());
+        
+        verify(binary, "component-linux-x86_64", &sums, None, None).unwrap();
+        verify(binary, "component-linux-x86_64", &sums, Some(&signature), Some(&public_key)).unwrap();
+        assert_eq!(checksum(&sums, "component-macos-aarch64"), Some("00".repeat(32).as_str()));
+        
+        let tampered = verify(b"tampered", "component-linux-x86_64", &sums, None, None).unwrap_err();
+        assert!(tampered.to_string().starts_with("Checksum mismatch for component-linux-x86_64"));
+        let missing = verify(binary, "component-windows-x86_64.exe", &sums, None, None).unwrap_err();
+        assert_eq!(missing.to_string(), "component-windows-x86_64.exe isn't listed in SHA256SUMS");
+        
+        let forged = sums.replace("component-linux", "component-linux-old");
+        let error = verify(binary, "component-linux-old-x86_64", &forged, Some(&signature), Some(&public_key)).unwrap_err();
+        assert_eq!(error.to_string(), "SHA256SUMS isn't signed with this build's release key");
+        let unsigned = verify(binary, "component-linux-x86_64", &sums, None, Some(&public_key)).unwrap_err();
+        assert_eq!(unsigned.to_string(), "Release has no SHA256SUMS.sig to check SHA256SUMS against");
+    }
+    
+    #[test]
+    fn test_replace_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("component");
+        fs::write(&exe, "old").unwrap();
+        
+        replace_executable(&exe, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new");
+        assert!(!dir.path().join(".component.new").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&exe).unwrap().permissions().mode() & 0o777, 0o755);
+        }
+    }
+}
@@ -1,15 +1,33 @@
 //! Utility functions and helpers
 
+mod path;
+
 use std::path::Path;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use xxhash_rust::xxh3::{xxh3_64, Xxh3};
 
-use sha2::{Digest, Sha256};
+pub use path::{normalize_path, path_key, path_to_module_id, simplify as simplify_path, strip_verbatim_prefix};
 
 /// Generate a hash of the given content
+///
+/// Uses xxh3, which is fast but not cryptographic; use SHA-256 where a
+/// digest must resist tampering, as in provenance files.
 pub fn hash_content(content: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(content);
-    let result = hasher.finalize();
-    hex::encode(&result[..8])
+    format!("{:016x}", xxh3_64(content))
+}
+
+/// Generate a 128-bit hash of several parts, e.g. for cache keys
+///
+/// Parts are length-prefixed, so `["ab", "c"]` and `["a", "bc"]` differ.
+pub fn hash_parts(parts: &[&[u8]]) -> String {
+    let mut hasher = Xxh3::new();
+    for part in parts {
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    format!("{:032x}", hasher.digest128())
 }
 
 /// Generate a hash-based filename
@@ -57,13 +75,6 @@ pub fn clean_path(path: &str) -> String {
     }
 }
 
-/// Convert a file path to a module ID
-pub fn path_to_module_id(path: &Path) -> String {
-    path.display()
-        .to_string()
-        .replace('\\', "/")
-}
-
 /// Format bytes as human-readable size
 pub fn format_size(bytes: usize) -> String {
     const KB: usize = 1024;
@@ -96,6 +107,76 @@ pub fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
+/// Produce a line-based diff between two texts
+///
+/// Only changed lines are emitted, prefixed with `-` or `+`. Lines are
+/// matched with Myers' algorithm; when that takes longer than a second the
+/// diff is still correct but may not be the smallest.
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let diff = similar::TextDiff::configure()
+        .algorithm(similar::Algorithm::Myers)
+        .timeout(Duration::from_secs(1))
+        .diff_slices(&old_lines, &new_lines);
+    
+    let mut result = String::new();
+    for change in diff.iter_all_changes() {
+        let sign = match change.tag() {
+            similar::ChangeTag::Equal => continue,
+            similar::ChangeTag::Delete => '-',
+            similar::ChangeTag::Insert => '+',
+        };
+        result.push(sign);
+        result.push_str(change.value());
+        result.push('\n');
+    }
+    result
+}
+
+/// Write a file through a temporary file in the same directory, renamed
+/// over `path` once complete, so readers see the old contents or the new but
+/// never part of them
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.tmp{}", name, std::process::id()));
+    std::fs::write(&tmp, contents)
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+}
+
+/// Apply `f` to every item on up to `jobs` threads, keeping their order
+///
+/// Runs on the calling thread with one job, and always on wasm32, which has
+/// no threads.
+pub fn parallel_map<T: Send, R: Send>(items: Vec<T>, jobs: usize, f: impl Fn(T) -> R + Sync) -> Vec<R> {
+    let jobs = jobs.min(items.len());
+    if jobs <= 1 || cfg!(target_arch = "wasm32") {
+        return items.into_iter().map(f).collect();
+    }
+    
+    let len = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(len));
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let Some((index, item)) = queue.lock().next() else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().push((index, result));
+            });
+        }
+    });
+    
+    let mut results = results.into_inner();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,6 +185,11 @@ mod tests {
     fn test_hash_content() {
         let hash = hash_content(b"hello world");
         assert_eq!(hash.len(), 16);
+        assert_eq!(hash, hash_content(b"hello world"));
+        assert_ne!(hash, hash_content(b"hello world!"));
+        
+        assert_eq!(hash_parts(&[b"ab", b"c"]).len(), 32);
+        assert_ne!(hash_parts(&[b"ab", b"c"]), hash_parts(&[b"a", b"bc"]));
     }
     
     #[test]
@@ -129,4 +215,36 @@ mod tests {
         assert_eq!(format_duration(Duration::from_secs_f64(1.5)), "1.50s");
         assert_eq!(format_duration(Duration::from_secs(65)), "1m 5.00s");
     }
+    
+    #[test]
+    fn test_line_diff() {
+        let old = "a\nb\nc";
+        let new = "a\nB\nc\nd";
+        assert_eq!(line_diff(old, new), "-b\n+B\n+d\n");
+        assert_eq!(line_diff(old, old), "");
+        
+        // Large inputs are diffed too, without a table of every line pair
+        let old: String = (0..50_000).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 25000\n", "line 25000 changed\n");
+        assert_eq!(line_diff(&old, &new), "-line 25000\n+line 25000 changed\n");
+    }
+    
+    #[test]
+    fn test_parallel_map_keeps_order() {
+        let items: Vec<u32> = (0..100).collect();
+        let doubled = parallel_map(items, 4, |n| n * 2);
+        assert_eq!(doubled, (0..100).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(parallel_map(Vec::<u32>::new(), 4, |n| n).is_empty());
+    }
+    
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.js");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert!(write_atomic(&dir.path().join("missing/main.js"), b"").is_err());
+    }
 }
@@ -0,0 +1,129 @@
+//! Path normalization
+//!
+//! Module IDs, URLs and graph keys must not depend on how a path was
+//! spelled. On Windows, `canonicalize` returns verbatim paths
+//! (`\\?\C:\app\src\main.ts`) whose prefix would leak into IDs and URLs,
+//! separators can be `\` or `/`, and `Main.ts` and `main.ts` name the same
+//! file. These helpers work on strings, so Windows paths behave the same
+//! when tested on any platform.
+
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+
+/// Whether the host filesystem treats names differing in case as one file
+pub const CASE_INSENSITIVE: bool = cfg!(windows);
+
+/// Longest path Windows APIs accept without a verbatim prefix
+const MAX_PATH: usize = 260;
+
+/// Remove a Windows verbatim prefix that isn't needed to express the path
+///
+/// `\\?\C:\app` becomes `C:\app` and `\\?\UNC\server\share` becomes
+/// `\\server\share`. Paths too long for the plain form keep the prefix.
+pub fn strip_verbatim_prefix(path: &str) -> Cow<'_, str> {
+    let stripped = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        Cow::Owned(format!(r"\\{}", rest))
+    } else if let Some(rest) = path.strip_prefix(r"\\?\").filter(|rest| is_drive_path(rest)) {
+        Cow::Borrowed(rest)
+    } else {
+        return Cow::Borrowed(path);
+    };
+    
+    if stripped.len() < MAX_PATH {
+        stripped
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// A `canonicalize` result in the form users and tools expect
+pub fn simplify(path: PathBuf) -> PathBuf {
+    if !cfg!(windows) {
+        return path;
+    }
+    match strip_verbatim_prefix(&path.to_string_lossy()) {
+        Cow::Owned(simplified) => PathBuf::from(simplified),
+        Cow::Borrowed(simplified) if simplified.len() != path.as_os_str().len() => PathBuf::from(simplified),
+        Cow::Borrowed(_) => path,
+    }
+}
+
+/// Convert a file path to a module ID: `/` separators, no verbatim prefix
+pub fn path_to_module_id(path: &Path) -> String {
+    strip_verbatim_prefix(&path.to_string_lossy()).replace('\\', "/")
+}
+
+/// Key under which a file is looked up, folding case where the filesystem does
+pub fn path_key(path: &Path) -> PathBuf {
+    key(path, CASE_INSENSITIVE)
+}
+
+fn key(path: &Path, case_insensitive: bool) -> PathBuf {
+    if case_insensitive {
+        PathBuf::from(path_to_module_id(path).to_lowercase())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+///
+/// Separators are unified the way the platform parses them, so on Windows
+/// `C:\app\src\./lib/../a.ts` becomes `C:\app\src\a.ts`.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Whether a path starts with a drive letter, as in `C:\`
+fn is_drive_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_windows_paths() {
+        assert_eq!(strip_verbatim_prefix(r"\\?\C:\app\src\main.ts"), r"C:\app\src\main.ts");
+        assert_eq!(strip_verbatim_prefix(r"\\?\UNC\server\share\app"), r"\\server\share\app");
+        assert_eq!(strip_verbatim_prefix(r"\\?\Volume{1234}\app"), r"\\?\Volume{1234}\app");
+        assert_eq!(strip_verbatim_prefix("/home/app/main.ts"), "/home/app/main.ts");
+        
+        let long = format!(r"\\?\C:\{}", "a".repeat(MAX_PATH));
+        assert_eq!(strip_verbatim_prefix(&long), long);
+        
+        assert_eq!(path_to_module_id(Path::new(r"\\?\C:\app\src\main.ts")), "C:/app/src/main.ts");
+        assert_eq!(path_to_module_id(Path::new(r"\\?\UNC\server\share\a.ts")), "//server/share/a.ts");
+        assert_eq!(path_to_module_id(Path::new("src/pages/about.ts")), "src/pages/about.ts");
+        
+        assert_eq!(
+            key(Path::new(r"C:\App\Src\Main.ts"), true),
+            key(Path::new(r"\\?\c:\app\src\main.ts"), true)
+        );
+        assert_ne!(key(Path::new("/app/Main.ts"), false), key(Path::new("/app/main.ts"), false));
+    }
+    
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(Path::new("/app/src/./lib/../a.ts")), Path::new("/app/src/a.ts"));
+        assert_eq!(normalize_path(Path::new("/../a.ts")), Path::new("/a.ts"));
+        assert_eq!(normalize_path(Path::new("../lib/./a.ts")), Path::new("../lib/a.ts"));
+        assert_eq!(normalize_path(Path::new("src/../../a.ts")), Path::new("../a.ts"));
+    }
+}
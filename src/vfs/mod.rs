@@ -0,0 +1,927 @@
+//! Filesystem abstraction
+//!
+//! The resolver, bundler, and dev server access source files through the
+//! `FileSystem` trait so builds can run against the real disk, an in-memory
+//! tree (tests, WASM), or a virtual overlay provided by a plugin.
+
+#[cfg(feature = "native")]
+mod native;
+mod watch;
+mod zip;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use parking_lot::{Mutex, RwLock};
+
+pub use watch::ProjectWatcher;
+
+/// Callback invoked with the paths changed under a watched directory
+pub type WatchHandler = Box<dyn Fn(Vec<PathBuf>) + Send + 'static>;
+
+/// File metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Whether the path is a directory
+    pub is_dir: bool,
+    
+    /// File size in bytes (0 for directories)
+    pub len: u64,
+    
+    /// Last modification time, if known
+    pub modified: Option<SystemTime>,
+}
+
+impl Metadata {
+    /// Whether the path is a regular file
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+    
+    /// Stamp for telling a file unchanged without reading it
+    ///
+    /// `None` for directories, unknown modification times, and files
+    /// modified within [`STAMP_SLACK`], which a second edit could leave with
+    /// the same coarse modification time and size.
+    pub fn stamp(&self) -> Option<FileStamp> {
+        let modified = self.modified.filter(|_| !self.is_dir)?;
+        let age = now().duration_since(modified).ok()?;
+        (age >= STAMP_SLACK).then_some(FileStamp { len: self.len, modified })
+    }
+}
+
+/// How old a modification time must be before a [`FileStamp`] trusts it
+pub const STAMP_SLACK: Duration = Duration::from_secs(2);
+
+/// Size and modification time of a file when it was last read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub len: u64,
+    pub modified: SystemTime,
+}
+
+/// Keeps a watch active; dropping it stops watching
+pub struct WatchGuard {
+    _inner: Box<dyn Send>,
+}
+
+impl WatchGuard {
+    fn new<T: Send + 'static>(inner: T) -> Self {
+        Self { _inner: Box::new(inner) }
+    }
+}
+
+/// Filesystem operations used by the build pipeline
+pub trait FileSystem: Send + Sync {
+    /// Read a file's contents
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    
+    /// Query metadata for a file or directory
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    
+    /// Return the absolute, normalized form of an existing path
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    
+    /// List the entries of a directory, sorted by path
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    
+    /// Watch a directory recursively, or a single file, calling `handler` with changed paths
+    fn watch(&self, root: &Path, handler: WatchHandler) -> Result<WatchGuard>;
+    
+    /// Read a file as UTF-8
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    
+    /// Check whether a regular file exists at `path`
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| m.is_file())
+    }
+    
+    /// Check whether a directory exists at `path`
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| m.is_dir)
+    }
+    
+    /// Check whether anything exists at `path`
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+}
+
+/// The real filesystem
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealFs;
+
+impl RealFs {
+    /// Shared handle to the real filesystem
+    pub fn shared() -> Arc<dyn FileSystem> {
+        Arc::new(RealFs)
+    }
+}
+
+impl FileSystem for RealFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if let Some((archive, name)) = zip::split_archive_path(path) {
+            return zip::open(&archive)?.read(&name);
+        }
+        std::fs::read(path)
+    }
+    
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        if zip::split_archive_path(path).is_some() {
+            return String::from_utf8(self.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        std::fs::read_to_string(path)
+    }
+    
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if let Some((archive, name)) = zip::split_archive_path(path) {
+            return zip::metadata(&archive, &name);
+        }
+        let metadata = std::fs::metadata(path)?;
+        Ok(Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+    
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if let Some((archive, name)) = zip::split_archive_path(path) {
+            zip::metadata(&archive, &name)?;
+            return self.canonicalize(&archive).map(|archive| archive.join(name));
+        }
+        std::fs::canonicalize(path).map(crate::utils::simplify_path)
+    }
+    
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if let Some((archive, name)) = zip::split_archive_path(path) {
+            return zip::read_dir(&archive, &name);
+        }
+        let mut entries = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+    
+    #[cfg(feature = "native")]
+    fn watch(&self, root: &Path, handler: WatchHandler) -> Result<WatchGuard> {
+        native::watch(root, handler)
+    }
+    
+    #[cfg(not(feature = "native"))]
+    fn watch(&self, _root: &Path, _handler: WatchHandler) -> Result<WatchGuard> {
+        anyhow::bail!("File watching requires the `native` feature")
+    }
+}
+
+/// An in-memory filesystem
+///
+/// Directories are implied by the files they contain. Writes are reported
+/// to active watchers, which run on a background thread and so are not
+/// available on single-threaded targets such as wasm32.
+#[derive(Default)]
+pub struct MemoryFs {
+    /// File contents by normalized absolute path
+    files: RwLock<BTreeMap<PathBuf, MemoryFile>>,
+    
+    /// Active watchers
+    watchers: Arc<Mutex<Vec<MemoryWatcher>>>,
+}
+
+struct MemoryFile {
+    contents: Vec<u8>,
+    modified: SystemTime,
+}
+
+struct MemoryWatcher {
+    id: u64,
+    root: PathBuf,
+    tx: Sender<Vec<PathBuf>>,
+}
+
+/// Unregisters a memory watcher when dropped
+struct MemoryWatchGuard {
+    id: u64,
+    watchers: Arc<Mutex<Vec<MemoryWatcher>>>,
+}
+
+impl Drop for MemoryWatchGuard {
+    fn drop(&mut self) {
+        self.watchers.lock().retain(|w| w.id != self.id);
+    }
+}
+
+impl MemoryFs {
+    /// Create an empty in-memory filesystem
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Create or replace a file, notifying watchers
+    pub fn write(&self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        let path = normalize(path.as_ref());
+        self.files.write().insert(path.clone(), MemoryFile {
+            contents: contents.into(),
+            modified: now(),
+        });
+        self.notify(path);
+    }
+    
+    /// Remove a file, notifying watchers; returns whether it existed
+    pub fn remove(&self, path: impl AsRef<Path>) -> bool {
+        let path = normalize(path.as_ref());
+        let existed = self.files.write().remove(&path).is_some();
+        if existed {
+            self.notify(path);
+        }
+        existed
+    }
+    
+    fn notify(&self, path: PathBuf) {
+        for watcher in self.watchers.lock().iter() {
+            if path.starts_with(&watcher.root) {
+                let _ = watcher.tx.send(vec![path.clone()]);
+            }
+        }
+    }
+    
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(io::ErrorKind::NotFound, format!("No such file: {}", path.display()))
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files
+            .read()
+            .get(&normalize(path))
+            .map(|file| file.contents.clone())
+            .ok_or_else(|| Self::not_found(path))
+    }
+    
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let path = normalize(path);
+        let files = self.files.read();
+        
+        if let Some(file) = files.get(&path) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: file.contents.len() as u64,
+                modified: Some(file.modified),
+            });
+        }
+        
+        // A directory exists if any file lives beneath it
+        if files.range(path.clone()..).next().is_some_and(|(p, _)| p.starts_with(&path)) {
+            return Ok(Metadata { is_dir: true, len: 0, modified: None });
+        }
+        
+        Err(Self::not_found(&path))
+    }
+    
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalize(path);
+        self.metadata(&path)?;
+        Ok(path)
+    }
+    
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let path = normalize(path);
+        if !self.metadata(&path)?.is_dir {
+            return Err(io::Error::new(io::ErrorKind::NotADirectory, format!("Not a directory: {}", path.display())));
+        }
+        
+        // Immediate children are the first component below `path`
+        let mut entries: Vec<PathBuf> = self.files
+            .read()
+            .range(path.clone()..)
+            .take_while(|(p, _)| p.starts_with(&path))
+            .filter_map(|(p, _)| p.strip_prefix(&path).ok()?.components().next())
+            .map(|child| path.join(child))
+            .collect();
+        entries.dedup();
+        Ok(entries)
+    }
+    
+    fn watch(&self, root: &Path, handler: WatchHandler) -> Result<WatchGuard> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        
+        // Deliver events on a separate thread, as the real watcher does, so
+        // handlers may block on async work
+        let (tx, rx) = channel::<Vec<PathBuf>>();
+        std::thread::spawn(move || {
+            while let Ok(paths) = rx.recv() {
+                handler(paths);
+            }
+        });
+        
+        self.watchers.lock().push(MemoryWatcher { id, root: normalize(root), tx });
+        
+        Ok(WatchGuard::new(MemoryWatchGuard {
+            id,
+            watchers: self.watchers.clone(),
+        }))
+    }
+}
+
+/// Current time; `SystemTime::now()` panics on wasm32-unknown-unknown
+fn now() -> SystemTime {
+    let since_epoch = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .unwrap_or_default();
+    UNIX_EPOCH + since_epoch
+}
+
+/// Lexically normalize a path, resolving `.` and `..` against `/`
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => normalized = PathBuf::from(prefix.as_os_str()),
+            Component::RootDir => normalized.push(Component::RootDir.as_os_str()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(part) => normalized.push(part),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    
+    use super::*;
+    
+    #[test]
+    fn test_memory_fs_read_and_metadata() {
+        let fs = MemoryFs::new();
+        fs.write("/project/src/main.js", "console.log(1);");
+        
+        assert_eq!(fs.read_to_string(Path::new("/project/src/main.js")).unwrap(), "console.log(1);");
+        assert!(fs.is_file(Path::new("/project/src/main.js")));
+        assert!(fs.is_dir(Path::new("/project/src")));
+        assert!(fs.is_dir(Path::new("/project")));
+        assert!(!fs.exists(Path::new("/project/src/other.js")));
+        assert!(!fs.exists(Path::new("/proj")));
+        assert_eq!(
+            fs.canonicalize(Path::new("/project/src/../src/./main.js")).unwrap(),
+            PathBuf::from("/project/src/main.js")
+        );
+        assert!(fs.canonicalize(Path::new("/project/missing.js")).is_err());
+        
+        fs.write("/project/src/pages/about.js", "");
+        fs.write("/project/src2/other.js", "");
+        assert_eq!(
+            fs.read_dir(Path::new("/project/src")).unwrap(),
+            vec![PathBuf::from("/project/src/main.js"), PathBuf::from("/project/src/pages")]
+        );
+        assert!(fs.read_dir(Path::new("/project/src/main.js")).is_err());
+    }
+    
+    #[test]
+    fn test_metadata_stamp() {
+        let old = now() - Duration::from_secs(60);
+        let file = Metadata { is_dir: false, len: 3, modified: Some(old) };
+        assert_eq!(file.stamp(), Some(FileStamp { len: 3, modified: old }));
+        
+        // Too recent to trust: another edit this second keeps the same time
+        let recent = Metadata { modified: Some(now()), ..file.clone() };
+        assert_eq!(recent.stamp(), None);
+        
+        let dir = Metadata { is_dir: true, ..file };
+        assert_eq!(dir.stamp(), None);
+    }
+    
+    #[test]
+    fn test_memory_fs_watch() {
+        let fs = MemoryFs::new();
+        let (tx, rx) = channel();
+        let guard = fs
+            .watch(Path::new("/project"), Box::new(move |paths| tx.send(paths).unwrap()))
+            .unwrap();
+        
+        fs.write("/elsewhere/a.js", "");
+        fs.write("/project/a.js", "");
+        let changed = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(changed, vec![PathBuf::from("/project/a.js")]);
+        
+        drop(guard);
+        fs.write("/project/a.js", "changed");
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_bundler_builds_from_memory_fs() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a';\nconsole.log(a);\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        
+        let bundler = crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap().with_fs(fs.clone()).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        
+        assert!(String::from_utf8_lossy(&files["main.js"]).contains("__component_modules__[\"src/a.js\"]"));
+        
+        // Entries differing in case would overwrite each other on macOS
+        config.entrypoints.insert("Main".to_string(), "src/a.js".to_string());
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let error = bundler.build_in_memory().await.unwrap_err().to_string();
+        assert!(error.contains("Main.js from entry 'Main' (src/a.js)"), "{}", error);
+        assert!(error.contains("main.js from entry 'main' (src/main.js)"), "{}", error);
+    }
+    
+    #[tokio::test]
+    async fn test_build_from_parsed_config() {
+        // What the wasm bindings do: a config that was never on disk, and
+        // files only in memory
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/app.js", "import './b.js';\n");
+        fs.write("/project/src/b.js", "console.log('b');\n");
+        
+        let config = crate::config::Config::parse("[project]\nname = \"demo\"\n\n[entrypoints]\napp = \"src/app.js\"\n\n[output]\nhash = false\n", "/project").unwrap();
+        assert_eq!(config.root, PathBuf::from("/project"));
+        assert!(crate::config::Config::parse("[project", "/project").is_err());
+        
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "esnext".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        assert!(String::from_utf8_lossy(&files["app.js"]).contains("console.log('b')"));
+        assert!(!files.contains_key("main.js"));
+    }
+    
+    #[tokio::test]
+    async fn test_missing_exports_fail_the_build() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { Button, Link } from './ui';\nimport data from './data.json';\nconsole.log(Button, Link, data);\n");
+        fs.write("/project/src/ui.js", "export * from './button';\nexport { Link as Anchor } from './link';\n");
+        fs.write("/project/src/button.js", "export function Buton() {}\n");
+        fs.write("/project/src/link.js", "export const Link = 'a';\n");
+        fs.write("/project/src/data.json", "{}");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.stats = true;
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap().with_fs(fs.clone()).unwrap();
+        let error = bundler.build_in_memory().await.unwrap_err().to_string();
+        assert!(error.contains("src/main.js:1:31: 'Button' is not exported by src/ui.js"), "{}", error);
+        assert!(error.contains("src/main.js:1:31: 'Link' is not exported by src/ui.js"), "{}", error);
+        
+        fs.write("/project/src/button.js", "export function Button() {}\n");
+        fs.write("/project/src/ui.js", "export * from './button';\nexport { Link } from './link';\n");
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        let stats: crate::bundler::BuildStats = serde_json::from_slice(&files["stats.json"]).unwrap();
+        assert_eq!(stats.exports["src/ui.js"], ["Button", "Link"]);
+        assert!(!stats.exports.contains_key("src/data.json"));
+    }
+    
+    #[tokio::test]
+    async fn test_missing_files_fail_the_build() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.ts", "import type { Options } from './types';\nimport x from './nope';\nimport './pure';\nconst lazy = () => import('../lazy');\nconsole.log(x);\n");
+        fs.write("/project/src/pure.js", "export const unused = 1;\n");
+        fs.write("/project/package.json", r#"{ "sideEffects": false }"#);
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.entrypoints.clear();
+        config.entrypoints.insert("main".to_string(), "src/main.ts".to_string());
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap().with_fs(fs.clone()).unwrap();
+        let error = bundler.build_in_memory().await.unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Some imports point at files that don't exist:\n  src/main.ts:2:16: './nope' does not exist\n  src/main.ts:4:28: '../lazy' does not exist"
+        );
+        
+        // Type-only imports and imports dropped for having no side effects are fine
+        fs.write("/project/src/nope.ts", "export default 1;\n");
+        fs.write("/project/lazy.js", "export default 2;\n");
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        bundler.build_in_memory().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_type_only_imports_are_not_bundled() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.ts", "import type { Options } from './types';\nimport { type Mode, run } from './run';\nexport type { Shape } from './shapes';\nrun({} as Options);\n");
+        fs.write("/project/src/types.ts", "export interface Options {}\n");
+        fs.write("/project/src/shapes.ts", "export type Shape = 'circle';\n");
+        fs.write("/project/src/run.ts", "export type Mode = 'a';\nexport function run(options: object) {}\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        config.entrypoints.clear();
+        config.entrypoints.insert("main".to_string(), "src/main.ts".to_string());
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        let main = String::from_utf8_lossy(&files["main.js"]);
+        assert!(main.contains("// Module: src/run.ts"), "{}", main);
+        assert!(!main.contains("src/types.ts"), "{}", main);
+        assert!(!main.contains("src/shapes.ts"));
+    }
+    
+    #[tokio::test]
+    async fn test_import_attributes() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import sheet from './theme.css' with { type: 'css' };\nimport './page.css';\nimport data from './data.json' with { type: 'json' };\ndocument.adoptedStyleSheets = [sheet];\nimport('./lazy.json', { with: { type: 'json' } });\n");
+        fs.write("/project/src/lazy.json", "[]");
+        fs.write("/project/src/theme.css", "@import './reset.css';\n:host { color: red; }\n");
+        fs.write("/project/src/page.css", "body { margin: 0; }\n");
+        fs.write("/project/src/data.json", "{}");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap().with_fs(fs.clone()).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        let main = String::from_utf8_lossy(&files["main.js"]);
+        assert!(main.contains("var sheet = new CSSStyleSheet();\nsheet.replaceSync(\":host { color: red; }\");\nmodule.exports = sheet;"), "{}", main);
+        assert!(!main.contains("reset.css"));
+        assert!(main.contains("document.createElement('style')"));
+        assert!(main.contains("__component_import__(\"src/lazy.json\");"), "{}", main);
+        
+        fs.write("/project/src/main.js", "import sheet from './page.css' with { type: 'css' };\nimport './page.css';\nimport data from './data.json' with { type: 'javascript' };\nimport other from './data.json' with { type: 'css' };\n");
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let error = bundler.build_in_memory().await.unwrap_err().to_string();
+        assert!(error.contains("src/main.js:2:9: './page.css' is imported with type 'css' elsewhere, so it isn't added to the page"), "{}", error);
+        assert!(error.contains("src/main.js:3:19: './data.json' is imported with unsupported type 'javascript'"), "{}", error);
+        assert!(error.contains("src/main.js:4:20: './data.json' is imported with type 'css' but isn't a stylesheet"), "{}", error);
+    }
+    
+    #[tokio::test]
+    async fn test_asset_urls() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import './icons/menu.js';\nconst logo = new URL('./logo.png', import.meta.url);\nconst missing = new URL('./missing.png', import.meta.url);\n");
+        fs.write("/project/src/icons/menu.js", "export const menu = new URL(\"../logo.png#icon\", import.meta.url).href;\n");
+        fs.write("/project/src/logo.png", "PNG");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        let logo = files.keys().find(|name| name.starts_with("assets/logo.")).unwrap();
+        assert_eq!(files[logo], b"PNG");
+        assert_eq!(files.keys().filter(|name| name.starts_with("assets/")).count(), 1);
+        
+        let main = files.iter().find(|(name, _)| name.starts_with("main.")).map(|(_, code)| String::from_utf8_lossy(code)).unwrap();
+        assert!(main.contains(&format!("const logo = new URL(__component_asset_url__(\"{}\"), location.href);", logo)), "{}", main);
+        assert!(main.contains(&format!("new URL(__component_asset_url__(\"{}#icon\"), location.href).href", logo)), "{}", main);
+        assert!(main.contains("new URL('./missing.png', import.meta.url)"));
+    }
+    
+    #[tokio::test]
+    async fn test_srcset_imports() {
+        let mut hero = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::new_rgb8(600, 300).write_to(&mut hero, image::ImageFormat::Png).unwrap();
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import hero from './images/hero.png?srcset=200,400&format=webp';\nconsole.log(hero);\n");
+        fs.write("/project/src/images/hero.png", hero.into_inner());
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let files = bundler.build_in_memory().await.unwrap();
+        let mut assets: Vec<&String> = files.keys().filter(|name| name.starts_with("assets/")).collect();
+        assets.sort();
+        assert_eq!(assets, ["assets/hero-200w.png", "assets/hero-200w.webp", "assets/hero-400w.png", "assets/hero-400w.webp"]);
+        assert_eq!(image::load_from_memory(&files["assets/hero-400w.webp"]).unwrap().height(), 200);
+        
+        let main = String::from_utf8_lossy(&files["main.js"]);
+        assert!(main.contains("new URL(__component_asset_url__(\"assets/hero-200w.png\"), location.href).href"), "{}", main);
+        assert!(main.contains("{ type: \"image/webp\", srcset: srcset(["), "{}", main);
+    }
+    
+    #[tokio::test]
+    async fn test_unchanged_chunks_come_from_cache() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a';\nconsole.log(a);\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: true,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let cache = Arc::new(crate::bundler::TransformCache::new());
+        let build = || async {
+            let bundler = crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap()
+                .with_fs(fs.clone()).unwrap()
+                .with_cache(cache.clone());
+            bundler.build_in_memory().await.unwrap()
+        };
+        
+        let cold = build().await;
+        let warm = build().await;
+        assert_eq!(cold, warm);
+        assert_eq!(cache.chunk_stats(), crate::bundler::CacheStats { hits: 1, misses: 1 });
+        
+        fs.write("/project/src/a.js", "export const a = 2;\n");
+        let changed = build().await;
+        assert_ne!(changed, warm);
+        assert_eq!(cache.chunk_stats(), crate::bundler::CacheStats { hits: 1, misses: 2 });
+    }
+    
+    #[tokio::test]
+    async fn test_rebuild_leaves_unchanged_outputs_alone() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import('./lazy.js');\n");
+        fs.write("/project/src/lazy.js", "export const a = 1;\n");
+        
+        let dist = tempfile::tempdir().unwrap();
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        let options = crate::bundler::BuildOptions {
+            outdir: Some(dist.path().to_path_buf()),
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let build = || async {
+            let bundler = crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap().with_fs(fs.clone()).unwrap();
+            let result = bundler.build().await.unwrap();
+            let mut unchanged: Vec<String> = result.bundles
+                .iter()
+                .filter(|bundle| bundle.unchanged)
+                .map(|bundle| bundle.output_path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            unchanged.sort();
+            unchanged
+        };
+        
+        assert!(build().await.is_empty());
+        assert_eq!(build().await, vec!["lazy.js", "main.js"]);
+        
+        fs.write("/project/src/lazy.js", "export const a = 2;\n");
+        assert_eq!(build().await, vec!["main.js"]);
+        assert!(std::fs::read_to_string(dist.path().join("lazy.js")).unwrap().contains("a = 2"));
+    }
+    
+    #[tokio::test]
+    async fn test_build_result_reports_files_and_warnings() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import('./lazy.js');\nconst logo = new URL('./logo.png', import.meta.url);\nconst icon = new URL('./icon.png', import.meta.url);\n");
+        fs.write("/project/src/lazy.js", "export const a = 1;\n");
+        fs.write("/project/src/logo.png", "PNG");
+        
+        let dist = tempfile::tempdir().unwrap();
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        config.output.hash = false;
+        let options = crate::bundler::BuildOptions {
+            outdir: Some(dist.path().to_path_buf()),
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let result = bundler.build().await.unwrap();
+        
+        let files: Vec<(&str, crate::bundler::OutputKind, &[String])> = result.bundles
+            .iter()
+            .map(|bundle| (bundle.file_name.as_str(), bundle.kind, bundle.modules.as_slice()))
+            .collect();
+        assert_eq!(files, [
+            ("assets/logo.png", crate::bundler::OutputKind::Asset, &[][..]),
+            ("lazy.js", crate::bundler::OutputKind::Chunk, &["src/lazy.js".to_string()][..]),
+            ("main.js", crate::bundler::OutputKind::Chunk, &["src/main.js".to_string()][..]),
+        ]);
+        assert_eq!(result.assets().count(), 1);
+        assert!(result.bundles.iter().all(|bundle| bundle.gzip_size > 0));
+        assert_eq!(result.warnings, ["src/main.js references ./icon.png, which doesn't exist"]);
+        
+        let report = serde_json::to_value(&result).unwrap();
+        assert_eq!(report["bundles"][2]["kind"], "chunk");
+        assert!(report["timings"]["total_ms"].is_f64());
+        assert_eq!(report["warnings"].as_array().unwrap().len(), 1);
+        
+        // Warnings are per build
+        assert_eq!(bundler.build().await.unwrap().warnings.len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_change_rebuilds_graph() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a';\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs.clone()).unwrap();
+        bundler.build_graph().await.unwrap();
+        
+        // A branch switch rewrites the entry and removes what it imported
+        fs.write("/project/src/main.js", "import { b } from './b';\n");
+        fs.write("/project/src/b.js", "export const b = 2;\n");
+        fs.remove("/project/src/a.js");
+        let changed: Vec<PathBuf> = (0..crate::bundler::BULK_CHANGE_THRESHOLD)
+            .map(|i| PathBuf::from(format!("/project/src/gen{}.js", i)))
+            .collect();
+        let outputs: Vec<PathBuf> = changed.iter().map(|p| Path::new("/project/dist").join(p.file_name().unwrap())).collect();
+        let cache: Vec<PathBuf> = changed.iter().map(|p| Path::new("/project/.component/cache").join(p.file_name().unwrap())).collect();
+        assert!(bundler.is_bulk_change(&changed));
+        assert!(!bundler.is_bulk_change(&changed[1..]));
+        assert!(!bundler.is_bulk_change(&outputs));
+        assert!(!bundler.is_bulk_change(&cache));
+        
+        bundler.rebuild_graph().await.unwrap();
+        let graph = bundler.graph();
+        let graph = graph.read();
+        assert!(graph.get_module_id(&PathBuf::from("/project/src/b.js")).is_some());
+        assert!(graph.get_module_id(&PathBuf::from("/project/src/a.js")).is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_inspect_module() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a';\nimport x from './missing';\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        let inspection = bundler.inspect(Path::new("/project/src/main.js")).await.unwrap();
+        
+        assert_eq!(inspection.dependencies[0].resolved.as_deref(), Some(Path::new("/project/src/a.js")));
+        assert_eq!(inspection.dependencies[1].resolved, None);
+        assert_eq!(inspection.stages[0].name, "core:javascript");
+        assert!(inspection.code.contains("import { a }"));
+        assert!(bundler.graph().read().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_served_modules() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a';\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        fs.write("/project/src/lazy.ts", "export const b: number = 2;\n");
+        fs.write("/project/src/styles.css", "@import './base.css';\nbody { margin: 0; }\n");
+        fs.write("/project/src/logo.svg", "<svg></svg>");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = crate::bundler::Bundler::new(config, options).unwrap().with_fs(fs).unwrap();
+        bundler.build_graph().await.unwrap();
+        
+        let a = bundler.served_module(Path::new("/project/src/a.js")).await.unwrap().unwrap();
+        assert_eq!(a, "export const a = 1;\n");
+        
+        // Files the graph doesn't reach are transformed when requested
+        let lazy = bundler.served_module(Path::new("/project/src/lazy.ts")).await.unwrap().unwrap();
+        assert!(lazy.contains("export const b = 2;"), "{}", lazy);
+        assert!(bundler.served_module(Path::new("/project/src/styles.css")).await.unwrap().is_none());
+        
+        let styles = bundler.served_import(Path::new("/project/src/styles.css")).await.unwrap().unwrap();
+        assert!(styles.starts_with("require(\"./base.css\");\n"), "{}", styles);
+        assert!(styles.contains("body { margin: 0; }"));
+        assert!(bundler.served_import(Path::new("/project/src/logo.svg")).await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_restore_graph_snapshot() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "import { a } from './a';\nimport('./lazy');\n");
+        fs.write("/project/src/a.js", "export const a = 1;\n");
+        fs.write("/project/src/lazy.js", "export default 'lazy';\n");
+        
+        let mut config = crate::config::Config::default_config();
+        config.root = PathBuf::from("/project");
+        let options = crate::bundler::BuildOptions {
+            outdir: None,
+            minify: false,
+            sourcemap: false,
+            target: "es2020".to_string(),
+            inspect_transforms: false,
+        };
+        let bundler = |fs: Arc<MemoryFs>| {
+            crate::bundler::Bundler::new(config.clone(), options.clone()).unwrap().with_fs(fs).unwrap()
+        };
+        
+        let cold = bundler(fs.clone());
+        cold.build_graph().await.unwrap();
+        let snapshot = cold.snapshot().unwrap().unwrap();
+        assert_eq!(snapshot.modules.len(), 3);
+        
+        fs.write("/project/src/a.js", "export const a = 2;\n");
+        let warm = bundler(fs.clone());
+        let changed = warm.restore_graph(snapshot).await.unwrap();
+        assert_eq!(changed, Some(1));
+        
+        {
+            let graph = warm.graph();
+            let graph = graph.read();
+            let main = graph.get_module_id(&PathBuf::from("/project/src/main.js")).unwrap();
+            let a = graph.get_module_id(&PathBuf::from("/project/src/a.js")).unwrap();
+            assert_eq!(graph.get_dependencies(main), [a]);
+            assert_eq!(graph.get_dynamic_dependencies(main).len(), 1);
+            assert!(graph.get_module(a).unwrap().transformed.as_deref().unwrap().contains("a = 2"));
+        }
+        
+        // A deleted module makes the snapshot unusable
+        let snapshot = warm.snapshot().unwrap().unwrap();
+        fs.remove("/project/src/lazy.js");
+        assert_eq!(bundler(fs).restore_graph(snapshot).await.unwrap(), None);
+    }
+}
+
@@ -0,0 +1,374 @@
+//! Watching the real filesystem
+//!
+//! All watches share one OS watcher, so watching many files outside the
+//! project doesn't cost a file descriptor each. Events are debounced per
+//! watch until things settle, so bursts like switching branches arrive as
+//! one batch. When the OS runs out of watches, or drops events because its
+//! queue overflowed, a watch rescans and falls back to polling.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use notify::{Config as NotifyConfig, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use tracing::{debug, error, warn};
+
+use super::{WatchGuard, WatchHandler};
+
+/// Quiet time after the last event before a batch is delivered
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Longest a batch is held back while events keep arriving
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// How often polling watches look for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Slack for coarse modification times when rescanning
+const MTIME_SLACK: Duration = Duration::from_secs(1);
+
+static HUB: Lazy<Hub> = Lazy::new(Hub::default);
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static WARNED_LIMIT: AtomicBool = AtomicBool::new(false);
+static WARNED_OVERFLOW: AtomicBool = AtomicBool::new(false);
+
+/// What a watch is told by the OS watcher
+enum Signal {
+    Paths(Vec<PathBuf>),
+    
+    /// Events were dropped; changed files have to be found by rescanning
+    Overflow,
+    
+    /// The OS ran out of watches for new directories
+    Limit,
+}
+
+/// Why a watch stopped using the OS watcher
+#[derive(Clone, Copy)]
+enum Fallback {
+    Limit,
+    Overflow,
+}
+
+/// The shared OS watcher and the watches it feeds
+#[derive(Default)]
+struct Hub {
+    /// Held while adding or removing OS watches; never locked by the event
+    /// thread, which `watch` calls can wait on
+    native: Mutex<NativeWatcher>,
+    
+    subscribers: RwLock<Vec<Subscriber>>,
+}
+
+#[derive(Default)]
+struct NativeWatcher {
+    watcher: Option<RecommendedWatcher>,
+    
+    /// Watches per watched path
+    roots: HashMap<PathBuf, usize>,
+}
+
+struct Subscriber {
+    id: u64,
+    root: PathBuf,
+    tx: Sender<Signal>,
+}
+
+impl Hub {
+    fn subscribe(&self, id: u64, root: &Path, tx: Sender<Signal>) -> notify::Result<()> {
+        {
+            let mut native = self.native.lock();
+            let NativeWatcher { watcher, roots } = &mut *native;
+            let watcher = match watcher {
+                Some(watcher) => watcher,
+                None => watcher.insert(RecommendedWatcher::new(|event| HUB.dispatch(event), NotifyConfig::default())?),
+            };
+            if !roots.contains_key(root) {
+                watcher.watch(root, RecursiveMode::Recursive)?;
+            }
+            *roots.entry(root.to_path_buf()).or_default() += 1;
+        }
+        
+        self.subscribers.write().push(Subscriber { id, root: root.to_path_buf(), tx });
+        Ok(())
+    }
+    
+    fn unsubscribe(&self, id: u64, root: &Path) {
+        self.subscribers.write().retain(|s| s.id != id);
+        
+        let mut native = self.native.lock();
+        let NativeWatcher { watcher, roots } = &mut *native;
+        if let Some(count) = roots.get_mut(root) {
+            *count -= 1;
+            if *count == 0 {
+                roots.remove(root);
+                if let Some(watcher) = watcher {
+                    let _ = watcher.unwatch(root);
+                }
+            }
+        }
+    }
+    
+    /// Route an OS event to the watches it concerns
+    fn dispatch(&self, event: notify::Result<Event>) {
+        let subscribers = self.subscribers.read();
+        match event {
+            Ok(event) if event.need_rescan() => {
+                for subscriber in subscribers.iter() {
+                    let _ = subscriber.tx.send(Signal::Overflow);
+                }
+            }
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for subscriber in subscribers.iter() {
+                    let paths: Vec<PathBuf> = event.paths.iter()
+                        .filter(|path| path.starts_with(&subscriber.root))
+                        .cloned()
+                        .collect();
+                    if !paths.is_empty() {
+                        let _ = subscriber.tx.send(Signal::Paths(paths));
+                    }
+                }
+            }
+            Err(e) if is_limit_error(&e) => {
+                let affected = |root: &Path| e.paths.is_empty() || e.paths.iter().any(|path| path.starts_with(root));
+                for subscriber in subscribers.iter().filter(|s| affected(&s.root)) {
+                    let _ = subscriber.tx.send(Signal::Limit);
+                }
+            }
+            Err(e) => error!("Watch error: {}", e),
+        }
+    }
+}
+
+/// One `watch` call; dropping its guard stops it
+struct Watch {
+    id: u64,
+    root: PathBuf,
+    tx: Sender<Signal>,
+    
+    /// Set once the watch polls instead of using the shared OS watcher
+    poller: Option<PollWatcher>,
+}
+
+impl Watch {
+    fn fall_back_to_polling(&mut self) -> notify::Result<()> {
+        if self.poller.is_some() {
+            return Ok(());
+        }
+        self.poller = Some(poll(&self.root, self.tx.clone())?);
+        HUB.unsubscribe(self.id, &self.root);
+        Ok(())
+    }
+}
+
+struct WatchHandle(Arc<Mutex<Watch>>);
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        let watch = self.0.lock();
+        if watch.poller.is_none() {
+            HUB.unsubscribe(watch.id, &watch.root);
+        }
+    }
+}
+
+/// Watch `root` recursively, calling `handler` with batches of changed paths
+pub(super) fn watch(root: &Path, handler: WatchHandler) -> Result<WatchGuard> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = channel();
+    
+    let poller = match HUB.subscribe(id, root, tx.clone()) {
+        Ok(()) => None,
+        Err(e) if is_limit_error(&e) => {
+            warn_fallback(Fallback::Limit, root);
+            Some(poll(root, tx.clone())?)
+        }
+        Err(e) => return Err(e.into()),
+    };
+    
+    let watch = Arc::new(Mutex::new(Watch { id, root: root.to_path_buf(), tx, poller }));
+    let weak = Arc::downgrade(&watch);
+    std::thread::spawn(move || deliver(weak, rx, handler));
+    
+    Ok(WatchGuard::new(WatchHandle(watch)))
+}
+
+/// Poll `root` for changes
+fn poll(root: &Path, tx: Sender<Signal>) -> notify::Result<PollWatcher> {
+    let config = NotifyConfig::default().with_poll_interval(POLL_INTERVAL);
+    let mut watcher = PollWatcher::new(
+        move |event: notify::Result<Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = tx.send(Signal::Paths(event.paths));
+            }
+            Ok(_) => {}
+            Err(e) => debug!("Poll error: {}", e),
+        },
+        config,
+    )?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// Paths and conditions collected until events settle
+#[derive(Default)]
+struct Batch {
+    paths: BTreeSet<PathBuf>,
+    fallback: Option<Fallback>,
+}
+
+impl Batch {
+    fn add(&mut self, signal: Signal) {
+        match signal {
+            Signal::Paths(paths) => self.paths.extend(paths),
+            Signal::Overflow => self.fallback = Some(Fallback::Overflow),
+            Signal::Limit => {
+                if self.fallback.is_none() {
+                    self.fallback = Some(Fallback::Limit);
+                }
+            }
+        }
+    }
+}
+
+/// Debounce signals into batches and hand them to `handler`
+///
+/// Exits once the watch is dropped, which disconnects every sender.
+fn deliver(watch: Weak<Mutex<Watch>>, rx: Receiver<Signal>, handler: WatchHandler) {
+    let mut last_batch = SystemTime::now();
+    
+    while let Ok(signal) = rx.recv() {
+        let received = SystemTime::now();
+        let started = Instant::now();
+        let mut batch = Batch::default();
+        batch.add(signal);
+        
+        while started.elapsed() < MAX_BATCH_DELAY {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(signal) => batch.add(signal),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        
+        if let Some(fallback) = batch.fallback {
+            let Some(watch) = watch.upgrade() else {
+                return;
+            };
+            let mut watch = watch.lock();
+            if let Fallback::Overflow = fallback {
+                batch.paths.extend(modified_since(&watch.root, last_batch - MTIME_SLACK));
+            }
+            if watch.poller.is_none() {
+                warn_fallback(fallback, &watch.root);
+                if let Err(e) = watch.fall_back_to_polling() {
+                    error!("Failed to poll {}: {}", watch.root.display(), e);
+                }
+            }
+        }
+        
+        last_batch = received;
+        if !batch.paths.is_empty() {
+            handler(batch.paths.into_iter().collect());
+        }
+    }
+}
+
+/// Files under `root` modified at or after `since`
+///
+/// Dependency and hidden directories are skipped, as they rarely hold
+/// sources and can be huge.
+fn modified_since(root: &Path, since: SystemTime) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || {
+                let name = entry.file_name().to_string_lossy();
+                !name.starts_with('.') && name != "node_modules"
+            }
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.metadata().ok().and_then(|m| m.modified().ok()).is_some_and(|modified| modified >= since))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn is_limit_error(error: &notify::Error) -> bool {
+    match &error.kind {
+        notify::ErrorKind::MaxFilesWatch => true,
+        #[cfg(unix)]
+        notify::ErrorKind::Io(e) => matches!(e.raw_os_error(), Some(libc::ENOSPC | libc::EMFILE)),
+        _ => false,
+    }
+}
+
+/// Tell the user once per kind why watching got slower and how to fix it
+fn warn_fallback(fallback: Fallback, root: &Path) {
+    match fallback {
+        Fallback::Limit => {
+            if !WARNED_LIMIT.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "Ran out of OS file watches; watching {} by polling, which is slower. On Linux, raise the \
+                    limit with `sudo sysctl fs.inotify.max_user_watches=524288 fs.inotify.max_user_instances=512` \
+                    and add those settings to /etc/sysctl.conf to keep them",
+                    root.display()
+                );
+            }
+        }
+        Fallback::Overflow => {
+            if !WARNED_OVERFLOW.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "The OS dropped file change events; rescanned {} and switched to polling it. On Linux, \
+                    `sudo sysctl fs.inotify.max_queued_events=65536` avoids this",
+                    root.display()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bursts_are_delivered_as_one_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let (tx, rx) = channel();
+        let _guard = watch(&root, Box::new(move |paths| tx.send(paths).unwrap())).unwrap();
+        
+        for i in 0..10 {
+            std::fs::write(root.join(format!("{}.js", i)), "").unwrap();
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        
+        let batch = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(batch.len(), 10);
+        assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+    }
+    
+    #[test]
+    fn test_modified_since_skips_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let before = SystemTime::now() - MTIME_SLACK;
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/dep")).unwrap();
+        std::fs::write(root.join("src/main.js"), "").unwrap();
+        std::fs::write(root.join("node_modules/dep/index.js"), "").unwrap();
+        
+        assert_eq!(modified_since(root, before), [root.join("src/main.js")]);
+        assert!(modified_since(root, SystemTime::now() + Duration::from_secs(60)).is_empty());
+    }
+}
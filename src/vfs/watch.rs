@@ -0,0 +1,140 @@
+//! Watching a project along with files outside it
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use tracing::debug;
+
+use super::{FileSystem, WatchGuard, WatchHandler};
+
+/// Watches a project root and the watch files plugins registered outside it
+///
+/// The set of watch files is queried again after every batch of changes, so
+/// files plugins start reading are picked up and ones they no longer read are
+/// dropped.
+pub struct ProjectWatcher {
+    inner: Arc<Inner>,
+    _root: WatchGuard,
+}
+
+struct Inner {
+    fs: Arc<dyn FileSystem>,
+    root: PathBuf,
+    handler: Box<dyn Fn(Vec<PathBuf>) + Send + Sync>,
+    watch_files: Box<dyn Fn() -> Vec<PathBuf> + Send + Sync>,
+    extra: Mutex<HashMap<PathBuf, WatchGuard>>,
+}
+
+impl ProjectWatcher {
+    /// Watch `root`, calling `handler` with changed paths
+    ///
+    /// `watch_files` lists the files builds read, typically
+    /// [`Bundler::watch_files`](crate::bundler::Bundler::watch_files).
+    pub fn new(
+        fs: Arc<dyn FileSystem>,
+        root: &Path,
+        handler: impl Fn(Vec<PathBuf>) + Send + Sync + 'static,
+        watch_files: impl Fn() -> Vec<PathBuf> + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let root = fs.canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let inner = Arc::new(Inner {
+            fs,
+            root,
+            handler: Box::new(handler),
+            watch_files: Box::new(watch_files),
+            extra: Mutex::new(HashMap::new()),
+        });
+        
+        let guard = inner.fs.watch(&inner.root, Inner::watch_handler(&inner))?;
+        inner.sync();
+        
+        Ok(Self { inner, _root: guard })
+    }
+    
+    /// Files outside the root currently being watched, sorted
+    pub fn extra_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.inner.extra.lock().keys().cloned().collect();
+        files.sort();
+        files
+    }
+}
+
+impl Inner {
+    /// Forward changes, then refresh the watched files; holds only a weak
+    /// reference so dropping the watcher stops everything
+    fn watch_handler(inner: &Arc<Self>) -> WatchHandler {
+        let inner = Arc::downgrade(inner);
+        Box::new(move |paths| {
+            if let Some(inner) = inner.upgrade() {
+                (inner.handler)(paths);
+                inner.sync();
+            }
+        })
+    }
+    
+    fn sync(self: &Arc<Self>) {
+        let files: BTreeSet<PathBuf> = (self.watch_files)()
+            .into_iter()
+            .filter(|file| !file.starts_with(&self.root))
+            .collect();
+        
+        let mut extra = self.extra.lock();
+        extra.retain(|file, _| files.contains(file));
+        for file in files {
+            if extra.contains_key(&file) {
+                continue;
+            }
+            match self.fs.watch(&file, Self::watch_handler(self)) {
+                Ok(guard) => {
+                    extra.insert(file, guard);
+                }
+                Err(e) => debug!("Failed to watch {}: {:#}", file.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+    
+    use super::*;
+    use crate::vfs::MemoryFs;
+    
+    #[test]
+    fn test_project_watcher_follows_watch_files() {
+        let fs = Arc::new(MemoryFs::new());
+        fs.write("/project/src/main.js", "");
+        fs.write("/shared/tailwind.config.js", "");
+        
+        let watched = Arc::new(Mutex::new(vec![
+            PathBuf::from("/project/src/main.css"),
+            PathBuf::from("/shared/tailwind.config.js"),
+        ]));
+        let (tx, rx) = channel();
+        let files = watched.clone();
+        let watcher = ProjectWatcher::new(
+            fs.clone(),
+            Path::new("/project"),
+            move |paths| tx.send(paths).unwrap(),
+            move || files.lock().clone(),
+        )
+        .unwrap();
+        assert_eq!(watcher.extra_files(), [PathBuf::from("/shared/tailwind.config.js")]);
+        
+        fs.write("/shared/tailwind.config.js", "module.exports = {};");
+        let changed = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(changed, [PathBuf::from("/shared/tailwind.config.js")]);
+        
+        // Files no longer read stop being watched after the next change
+        watched.lock().clear();
+        fs.write("/project/src/main.js", "changed");
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(watcher.extra_files().is_empty());
+    }
+}
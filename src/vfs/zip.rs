@@ -0,0 +1,315 @@
+//! Files inside zip archives
+//!
+//! Yarn Plug'n'Play installs packages as zip archives and resolves imports
+//! to paths through them, as in
+//! `.yarn/cache/react-npm-18.2.0-3c2e1d.zip/node_modules/react/index.js`.
+//! The real filesystem reads such paths out of the archive. Archives are
+//! read whole and cached until they change on disk. Only stored and
+//! deflated entries are supported, which is all Yarn writes.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Read};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use flate2::read::DeflateDecoder;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use super::Metadata;
+
+/// An archive with the size and modification time it had when read
+type CachedArchive = (u64, Option<SystemTime>, Arc<ZipArchive>);
+
+/// Archives read so far
+static ARCHIVES: Lazy<Mutex<HashMap<PathBuf, CachedArchive>>> = Lazy::new(Default::default);
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+
+/// Compression methods
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// The entries of a zip archive, held in memory
+pub(super) struct ZipArchive {
+    data: Vec<u8>,
+    
+    /// Files by path inside the archive
+    files: BTreeMap<String, ZipEntry>,
+    
+    /// Directories, listed or implied by the files in them
+    dirs: BTreeSet<String>,
+}
+
+struct ZipEntry {
+    method: u16,
+    
+    /// Compressed contents within the archive
+    range: Range<usize>,
+    
+    /// Uncompressed size
+    len: u64,
+}
+
+impl ZipArchive {
+    /// Read the central directory of an archive
+    pub(super) fn parse(data: Vec<u8>) -> io::Result<Self> {
+        let search_start = data.len().saturating_sub(22 + u16::MAX as usize);
+        let end = (search_start..data.len().saturating_sub(21))
+            .rev()
+            .find(|&offset| read_u32(&data, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or_else(|| invalid("missing end of central directory"))?;
+        let count = read_u16(&data, end + 10)?;
+        let mut offset = read_u32(&data, end + 16)? as usize;
+        
+        let mut files = BTreeMap::new();
+        let mut dirs = BTreeSet::new();
+        for _ in 0..count {
+            if read_u32(&data, offset)? != CENTRAL_DIRECTORY_HEADER {
+                return Err(invalid("bad central directory header"));
+            }
+            let method = read_u16(&data, offset + 10)?;
+            let compressed_len = read_u32(&data, offset + 20)?;
+            let len = read_u32(&data, offset + 24)?;
+            let name_len = read_u16(&data, offset + 28)? as usize;
+            let extra_len = read_u16(&data, offset + 30)? as usize;
+            let comment_len = read_u16(&data, offset + 32)? as usize;
+            let local = read_u32(&data, offset + 42)? as usize;
+            if compressed_len == u32::MAX || len == u32::MAX || local == u32::MAX as usize {
+                return Err(invalid("zip64 archives are not supported"));
+            }
+            let name = data
+                .get(offset + 46..offset + 46 + name_len)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .ok_or_else(|| invalid("truncated central directory"))?;
+            offset += 46 + name_len + extra_len + comment_len;
+            
+            let mut parent = name.trim_end_matches('/');
+            while let Some((dir, _)) = parent.rsplit_once('/') {
+                dirs.insert(dir.to_string());
+                parent = dir;
+            }
+            if name.ends_with('/') {
+                dirs.insert(name.trim_end_matches('/').to_string());
+                continue;
+            }
+            
+            if read_u32(&data, local)? != LOCAL_FILE_HEADER {
+                return Err(invalid("bad local file header"));
+            }
+            let start = local + 30 + read_u16(&data, local + 26)? as usize + read_u16(&data, local + 28)? as usize;
+            let range = start..start + compressed_len as usize;
+            if range.end > data.len() {
+                return Err(invalid("truncated file contents"));
+            }
+            files.insert(name, ZipEntry { method, range, len: len as u64 });
+        }
+        Ok(Self { data, files, dirs })
+    }
+    
+    /// Read a file
+    pub(super) fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self.files.get(name).ok_or_else(|| not_found(name))?;
+        let compressed = &self.data[entry.range.clone()];
+        match entry.method {
+            STORED => Ok(compressed.to_vec()),
+            DEFLATED => {
+                let mut contents = Vec::new();
+                DeflateDecoder::new(compressed).read_to_end(&mut contents)?;
+                Ok(contents)
+            }
+            method => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} uses unsupported compression method {}", name, method),
+            )),
+        }
+    }
+    
+    /// Whether `name` is a directory, and the size of files
+    fn entry(&self, name: &str) -> Option<(bool, u64)> {
+        if self.dirs.contains(name) {
+            return Some((true, 0));
+        }
+        self.files.get(name).map(|entry| (false, entry.len))
+    }
+    
+    /// Names of the entries directly inside a directory
+    fn children(&self, dir: &str) -> Vec<String> {
+        let prefix = format!("{}/", dir);
+        let direct = |name: &&String| name.strip_prefix(&prefix).is_some_and(|rest| !rest.contains('/'));
+        let mut children: Vec<String> = self
+            .files
+            .keys()
+            .chain(self.dirs.iter())
+            .filter(direct)
+            .map(|name| name[prefix.len()..].to_string())
+            .collect();
+        children.sort();
+        children
+    }
+}
+
+/// Split a path through an archive into the archive and the path inside it
+///
+/// `None` for paths that don't pass through an existing `.zip` file,
+/// including the archive itself.
+pub(super) fn split_archive_path(path: &Path) -> Option<(PathBuf, String)> {
+    let mut archive = PathBuf::new();
+    let mut components = path.components();
+    while let Some(component) = components.next() {
+        archive.push(component);
+        let is_zip = matches!(component, Component::Normal(name) if name.to_string_lossy().ends_with(".zip"));
+        if is_zip && std::fs::metadata(&archive).is_ok_and(|m| m.is_file()) {
+            let inner: Vec<String> = components.map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+            return (!inner.is_empty()).then(|| (archive, inner.join("/")));
+        }
+    }
+    None
+}
+
+/// Open an archive, from the cache when it hasn't changed since it was read
+pub(super) fn open(path: &Path) -> io::Result<Arc<ZipArchive>> {
+    let metadata = std::fs::metadata(path)?;
+    let (len, modified) = (metadata.len(), metadata.modified().ok());
+    if let Some((cached_len, cached_modified, archive)) = ARCHIVES.lock().get(path) {
+        if *cached_len == len && *cached_modified == modified {
+            return Ok(archive.clone());
+        }
+    }
+    
+    let archive = Arc::new(
+        ZipArchive::parse(std::fs::read(path)?)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?,
+    );
+    ARCHIVES.lock().insert(path.to_path_buf(), (len, modified, archive.clone()));
+    Ok(archive)
+}
+
+/// Metadata of a path inside an archive, which shares the archive's
+/// modification time
+pub(super) fn metadata(archive_path: &Path, name: &str) -> io::Result<Metadata> {
+    let archive = open(archive_path)?;
+    let (is_dir, len) = archive.entry(name).ok_or_else(|| not_found(name))?;
+    let modified = std::fs::metadata(archive_path)?.modified().ok();
+    Ok(Metadata { is_dir, len, modified })
+}
+
+/// Entries of a directory inside an archive, as full paths
+pub(super) fn read_dir(archive_path: &Path, name: &str) -> io::Result<Vec<PathBuf>> {
+    let archive = open(archive_path)?;
+    if !archive.dirs.contains(name) {
+        return Err(not_found(name));
+    }
+    let dir = archive_path.join(name);
+    Ok(archive.children(name).into_iter().map(|child| dir.join(child)).collect())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> io::Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid("unexpected end of archive"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid("unexpected end of archive"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid zip archive: {}", message))
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found in archive", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    
+    use super::*;
+    use crate::vfs::{FileSystem, RealFs};
+    
+    /// Build an archive, deflating files whose name ends in `.js`
+    fn build_archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut central = Vec::new();
+        for (name, contents) in files {
+            let (method, stored) = if name.ends_with(".js") {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents.as_bytes()).unwrap();
+                (DEFLATED, encoder.finish().unwrap())
+            } else {
+                (STORED, contents.as_bytes().to_vec())
+            };
+            let header = |signature: u32, extra: &[u8]| {
+                let mut header = signature.to_le_bytes().to_vec();
+                header.extend_from_slice(extra);
+                header.extend_from_slice(&[20, 0, 0, 0]);
+                header.extend_from_slice(&method.to_le_bytes());
+                header.extend_from_slice(&[0; 8]);
+                header.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+                header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+                header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                header.extend_from_slice(&[0, 0]);
+                header
+            };
+            
+            central.extend(header(CENTRAL_DIRECTORY_HEADER, &[20, 0]));
+            central.extend_from_slice(&[0; 10]);
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+            
+            data.extend(header(LOCAL_FILE_HEADER, &[]));
+            data.extend_from_slice(name.as_bytes());
+            data.extend(stored);
+        }
+        
+        let central_offset = data.len() as u32;
+        let count = (files.len() as u16).to_le_bytes();
+        data.extend_from_slice(&central);
+        data.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&count);
+        data.extend_from_slice(&count);
+        data.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        data.extend_from_slice(&central_offset.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+    
+    #[test]
+    fn test_read_files_inside_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("react-npm-18.2.0.zip");
+        std::fs::write(&archive, build_archive(&[
+            ("node_modules/react/package.json", r#"{ "name": "react" }"#),
+            ("node_modules/react/index.js", "export default {};\n"),
+            ("node_modules/react/cjs/react.js", "module.exports = {};\n"),
+        ])).unwrap();
+        
+        let package = archive.join("node_modules/react");
+        let fs = RealFs;
+        assert_eq!(fs.read_to_string(&package.join("index.js")).unwrap(), "export default {};\n");
+        assert_eq!(fs.read_to_string(&package.join("package.json")).unwrap(), r#"{ "name": "react" }"#);
+        assert!(fs.is_dir(&package));
+        assert!(fs.is_file(&package.join("cjs/react.js")));
+        assert!(!fs.exists(&package.join("missing.js")));
+        assert_eq!(fs.metadata(&package.join("index.js")).unwrap().len, 19);
+        assert_eq!(
+            fs.read_dir(&package).unwrap(),
+            vec![package.join("cjs"), package.join("index.js"), package.join("package.json")]
+        );
+        assert!(fs.is_file(&archive));
+        
+        assert!(ZipArchive::parse(b"not a zip".to_vec()).is_err());
+    }
+}
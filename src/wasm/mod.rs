@@ -0,0 +1,120 @@
+//! JavaScript bindings for in-browser builds
+//!
+//! Enabled with the `wasm` feature. Build with
+//! `wasm-pack build --target web -- --no-default-features --features wasm`
+//! and drive the bundler from a web playground:
+//!
+//! ```js
+//! const project = new Project(`[project]\nname = "demo"\n\n[entrypoints]\nmain = "src/main.js"`);
+//! project.writeFile("src/main.js", "import './a.js';");
+//! project.writeFile("src/a.js", "console.log('hi');");
+//! const files = await project.build(); // { "main.js": "...", "manifest.json": "..." }
+//! ```
+//!
+//! Text files are returned as strings and binary files, such as images, as
+//! `Uint8Array`s.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use js_sys::{Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::bundler::{BuildOptions, Bundler, Module};
+use crate::config::Config;
+use crate::transform::Transformer;
+use crate::vfs::MemoryFs;
+
+/// Root of the virtual project filesystem
+const PROJECT_ROOT: &str = "/project";
+
+/// A project held in memory, built entirely in the browser
+#[wasm_bindgen]
+pub struct Project {
+    /// Parsed `component.toml`
+    config: Config,
+    
+    /// Project files
+    fs: Arc<MemoryFs>,
+}
+
+#[wasm_bindgen]
+impl Project {
+    /// Create a project from the contents of a `component.toml`
+    #[wasm_bindgen(constructor)]
+    pub fn new(config_toml: &str) -> Result<Project, JsError> {
+        let config = Config::parse(config_toml, PROJECT_ROOT).map_err(to_js_error)?;
+        
+        Ok(Self {
+            config,
+            fs: Arc::new(MemoryFs::new()),
+        })
+    }
+    
+    /// Create or replace a file; `path` is relative to the project root
+    #[wasm_bindgen(js_name = writeFile)]
+    pub fn write_file(&self, path: &str, contents: &str) {
+        self.fs.write(project_path(path), contents);
+    }
+    
+    /// Remove a file; returns whether it existed
+    #[wasm_bindgen(js_name = removeFile)]
+    pub fn remove_file(&self, path: &str) -> bool {
+        self.fs.remove(project_path(path))
+    }
+    
+    /// Build the project, resolving to an object of file name to contents
+    pub fn build(&self, minify: Option<bool>) -> js_sys::Promise {
+        let config = self.config.clone();
+        let fs = self.fs.clone();
+        let options = BuildOptions {
+            outdir: None,
+            minify: minify.unwrap_or(false),
+            sourcemap: false,
+            target: "esnext".to_string(),
+            inspect_transforms: false,
+        };
+        
+        wasm_bindgen_futures::future_to_promise(async move {
+            let bundler = Bundler::new(config, options)
+                .and_then(|bundler| bundler.with_fs(fs))
+                .map_err(to_js_value)?;
+            let files = bundler.build_in_memory().await.map_err(to_js_value)?;
+            
+            let output = Object::new();
+            for (name, contents) in files {
+                let contents: JsValue = match String::from_utf8(contents) {
+                    Ok(text) => text.into(),
+                    Err(binary) => Uint8Array::from(binary.as_bytes()).into(),
+                };
+                Reflect::set(&output, &name.into(), &contents)?;
+            }
+            Ok(output.into())
+        })
+    }
+}
+
+/// Transform a single module; the module type is inferred from `filename`
+#[wasm_bindgen]
+pub fn transform(source: &str, filename: &str) -> Result<String, JsError> {
+    let mut config = Config::default_config();
+    config.root = PathBuf::from(PROJECT_ROOT);
+    
+    let path = project_path(filename);
+    let transformer = Transformer::new(Arc::new(config)).map_err(to_js_error)?;
+    transformer
+        .transform(source, &path, &Module::detect_type(&path))
+        .map_err(to_js_error)
+}
+
+fn project_path(path: &str) -> PathBuf {
+    Path::new(PROJECT_ROOT).join(path.trim_start_matches('/'))
+}
+
+fn to_js_error(e: anyhow::Error) -> JsError {
+    JsError::new(&format!("{:#}", e))
+}
+
+fn to_js_value(e: anyhow::Error) -> JsValue {
+    JsValue::from(to_js_error(e))
+}